@@ -0,0 +1,108 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares `DType` against its previous layout, which stored arrays &
+//! objects inline, on arrays of a million values.
+//!
+//! ```sh
+//! cargo bench --bench dtype
+//! ```
+
+use std::{collections::BTreeMap, hint::black_box, mem, time::Instant};
+
+use sage::{dtype::Number, DType, DateTime};
+
+const LEN: usize = 1_000_000;
+
+/// `DType` as it was laid out before arrays & objects were boxed.
+#[allow(dead_code)]
+enum Unboxed {
+  Array(Vec<Unboxed>),
+  Boolean(bool),
+  DateTime(DateTime),
+  Null,
+  Number(Number),
+  Object(BTreeMap<String, Unboxed>),
+  String(String),
+}
+
+/// Runs `f` a few times, returning the fastest run in milliseconds.
+fn time<T>(mut f: impl FnMut() -> T) -> f64 {
+  (0..5)
+    .map(|_| {
+      let start = Instant::now();
+      black_box(f());
+      start.elapsed().as_secs_f64() * 1e3
+    })
+    .fold(f64::INFINITY, f64::min)
+}
+
+fn report(case: &str, size: usize, old_size: usize, ms: f64, old_ms: f64) {
+  println!(
+    "{:<10} {:>6.1} MB {:>6.1} MB {:>8.2} ms {:>8.2} ms",
+    case,
+    (size * LEN) as f64 / 1e6,
+    (old_size * LEN) as f64 / 1e6,
+    ms,
+    old_ms,
+  );
+}
+
+fn main() {
+  let size = mem::size_of::<DType>();
+  let old_size = mem::size_of::<Unboxed>();
+  println!("size_of: DType {} bytes, unboxed {} bytes", size, old_size);
+  println!();
+  println!(
+    "{:<10} {:>9} {:>9} {:>11} {:>11}",
+    "array of", "DType", "unboxed", "DType", "unboxed"
+  );
+
+  let ms = time(|| (0..LEN as u64).map(DType::from).collect::<Vec<_>>());
+  let old_ms = time(|| {
+    (0..LEN as u64)
+      .map(|n| Unboxed::Number(n.into()))
+      .collect::<Vec<_>>()
+  });
+  report("numbers", size, old_size, ms, old_ms);
+
+  let ms = time(|| {
+    (0..LEN)
+      .map(|n| DType::from(n % 2 == 0))
+      .collect::<Vec<_>>()
+  });
+  let old_ms = time(|| {
+    (0..LEN)
+      .map(|n| Unboxed::Boolean(n % 2 == 0))
+      .collect::<Vec<_>>()
+  });
+  report("booleans", size, old_size, ms, old_ms);
+
+  let ms = time(|| (0..LEN).map(|_| DType::Null).collect::<Vec<_>>());
+  let old_ms = time(|| (0..LEN).map(|_| Unboxed::Null).collect::<Vec<_>>());
+  report("nulls", size, old_size, ms, old_ms);
+
+  // Strings own a heap buffer either way, only the inline part shrinks.
+  let ms = time(|| {
+    (0..LEN)
+      .map(|n| DType::from(format!("v{}", n % 100)))
+      .collect::<Vec<_>>()
+  });
+  let old_ms = time(|| {
+    (0..LEN)
+      .map(|n| Unboxed::String(format!("v{}", n % 100)))
+      .collect::<Vec<_>>()
+  });
+  report("strings", size, old_size, ms, old_ms);
+}
@@ -12,6 +12,282 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-fn main() {
-  println!("SAGE: Command Line Interface!");
+use std::{
+  error::Error,
+  fs::File,
+  io::{self, BufReader, BufWriter, Read, Write},
+  path::{Path, PathBuf},
+  process::ExitCode,
+};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use sage::{
+  formats::{JsonLd, NTriples, Turtle},
+  graph::{KnowledgeGraph, MergePolicy},
+  json,
+  query::{ResultFormat, Select},
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// SAGE: Command Line Interface.
+#[derive(Parser)]
+#[command(name = "sage-cli", version, about)]
+struct Cli {
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Converts a graph between JSON-LD, Turtle & N-Triples.
+  Convert {
+    /// File to read, `-` for standard input.
+    input: PathBuf,
+    /// Format of the input, guessed from its extension by default.
+    #[arg(short, long)]
+    from: Option<Format>,
+    /// Format of the output, guessed from its extension by default.
+    #[arg(short, long)]
+    to: Option<Format>,
+    /// File to write, standard output by default.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+  },
+  /// Runs a SPARQL `SELECT` query against the graph of every file.
+  Query {
+    /// The SPARQL query.
+    query: String,
+    /// Files to query, `-` for standard input.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Format of the files, guessed from their extension by default.
+    #[arg(short, long)]
+    from: Option<Format>,
+    /// Format of the results.
+    #[arg(short, long, value_enum, default_value_t = Results::Tsv)]
+    results: Results,
+  },
+  /// Checks that every file parses, reporting where it doesn't.
+  Validate {
+    /// Files to check, `-` for standard input.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Format of the files, guessed from their extension by default.
+    #[arg(short, long)]
+    from: Option<Format>,
+  },
+  /// Prints statistics about the graph of every file as JSON.
+  Stats {
+    /// Files to read, `-` for standard input.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Format of the files, guessed from their extension by default.
+    #[arg(short, long)]
+    from: Option<Format>,
+  },
+  /// Starts an interactive shell (see `sage::repl`).
+  #[cfg(feature = "repl")]
+  Repl,
+}
+
+/// Graph serializations.
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+  /// JSON-LD (`.jsonld`, `.json`).
+  #[value(name = "jsonld")]
+  JsonLd,
+  /// Turtle (`.ttl`).
+  Turtle,
+  /// N-Triples (`.nt`).
+  #[value(name = "ntriples")]
+  NTriples,
+}
+
+impl Format {
+  /// Picks `format` if given, else guesses it from the extension of `path`
+  /// (ignoring a compression extension).
+  fn of(path: &Path, format: Option<Format>) -> Result<Format> {
+    if let Some(format) = format {
+      return Ok(format);
+    }
+    #[cfg(feature = "compression")]
+    let name = match sage::compression::Compression::from_path(path) {
+      Some(_) => Path::new(path.file_stem().unwrap_or_default()),
+      None => path,
+    };
+    #[cfg(not(feature = "compression"))]
+    let name = path;
+    let extension = name.extension().and_then(|e| e.to_str());
+    match extension.map(str::to_ascii_lowercase).as_deref() {
+      Some("jsonld" | "json") => Ok(Format::JsonLd),
+      Some("ttl") => Ok(Format::Turtle),
+      Some("nt") => Ok(Format::NTriples),
+      _ => Err(
+        format!(
+          "can't guess the format of {}, pass it explicitly",
+          path.display()
+        )
+        .into(),
+      ),
+    }
+  }
+}
+
+/// Query result serializations.
+#[derive(Clone, Copy, ValueEnum)]
+enum Results {
+  Json,
+  Csv,
+  Tsv,
+  Xml,
+}
+
+impl From<Results> for ResultFormat {
+  fn from(results: Results) -> ResultFormat {
+    match results {
+      Results::Json => ResultFormat::Json,
+      Results::Csv => ResultFormat::Csv,
+      Results::Tsv => ResultFormat::Tsv,
+      Results::Xml => ResultFormat::Xml,
+    }
+  }
+}
+
+fn main() -> ExitCode {
+  match run(Cli::parse().command) {
+    Ok(code) => code,
+    // Output piped into e.g. `head` was cut short on purpose.
+    Err(err)
+      if err
+        .downcast_ref::<io::Error>()
+        .is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe) =>
+    {
+      ExitCode::SUCCESS
+    }
+    Err(err) => {
+      eprintln!("sage-cli: {}", err);
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn run(command: Command) -> Result<ExitCode> {
+  let mut stdout = io::stdout().lock();
+  match command {
+    Command::Convert {
+      input,
+      from,
+      to,
+      output,
+    } => {
+      let graph = read(&input, from)?;
+      let to = match (to, &output) {
+        (Some(to), _) => to,
+        (None, Some(output)) => Format::of(output, None)?,
+        (None, None) => Format::NTriples,
+      };
+      match &output {
+        #[cfg(feature = "compression")]
+        Some(path) => {
+          let mut writer = sage::compression::create(path)?;
+          write(&graph, to, &mut writer)?;
+          writer.finish()?;
+        }
+        #[cfg(not(feature = "compression"))]
+        Some(path) => write(&graph, to, BufWriter::new(File::create(path)?))?,
+        None => write(&graph, to, BufWriter::new(stdout))?,
+      }
+    }
+    Command::Query {
+      query,
+      files,
+      from,
+      results,
+    } => {
+      let select = Select::parse(&query)?;
+      let graph = read_all(&files, from)?;
+      stdout.write_all(&select.results(&graph, results.into()))?;
+    }
+    Command::Validate { files, from } => {
+      let mut valid = true;
+      for path in &files {
+        match read(path, from) {
+          Ok(graph) => writeln!(
+            stdout,
+            "{}: ok, {} statements",
+            path.display(),
+            graph.len()
+          )?,
+          Err(err) => {
+            writeln!(stdout, "{}: {}", path.display(), err)?;
+            valid = false;
+          }
+        }
+      }
+      if !valid {
+        return Ok(ExitCode::FAILURE);
+      }
+    }
+    Command::Stats { files, from } => {
+      let graph = read_all(&files, from)?;
+      writeln!(stdout, "{}", json::to_string_pretty(&graph.stats())?)?;
+    }
+    #[cfg(feature = "repl")]
+    Command::Repl => {
+      let mut repl = sage::repl::Repl::new();
+      if let Some(home) = std::env::var_os("HOME") {
+        repl = repl.history(Path::new(&home).join(".sage_history"));
+      }
+      repl.run()?;
+    }
+  }
+  Ok(ExitCode::SUCCESS)
+}
+
+/// Reads the graph stored at `path`, or standard input for `-`.
+fn read(path: &Path, format: Option<Format>) -> Result<KnowledgeGraph> {
+  let stdin = path == Path::new("-");
+  let format = match (format, stdin) {
+    (None, true) => return Err("pass the format of standard input".into()),
+    (format, _) => Format::of(path, format)?,
+  };
+  let reader: Box<dyn Read> = if stdin {
+    Box::new(io::stdin().lock())
+  } else {
+    Box::new(File::open(path)?)
+  };
+  let reader = BufReader::new(reader);
+  let graph = match format {
+    Format::JsonLd => JsonLd::from_reader(reader)?,
+    Format::Turtle => Turtle::from_reader(reader)?,
+    Format::NTriples => NTriples::from_reader(reader)?,
+  };
+  Ok(graph)
+}
+
+/// Reads every file into a single graph, keeping their blank nodes apart.
+fn read_all(
+  paths: &[PathBuf],
+  format: Option<Format>,
+) -> Result<KnowledgeGraph> {
+  let mut graph = KnowledgeGraph::new();
+  for path in paths {
+    graph.merge(&read(path, format)?, MergePolicy::new());
+  }
+  Ok(graph)
+}
+
+fn write<W: Write>(
+  graph: &KnowledgeGraph,
+  format: Format,
+  mut writer: W,
+) -> Result<()> {
+  match format {
+    Format::JsonLd => JsonLd::new(graph).to_writer(&mut writer)?,
+    Format::Turtle => Turtle::new(graph).to_writer(&mut writer)?,
+    Format::NTriples => NTriples::new(graph).to_writer(&mut writer)?,
+  }
+  writer.flush()?;
+  Ok(())
 }
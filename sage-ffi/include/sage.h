@@ -0,0 +1,69 @@
+/*
+ * Copyright 2021 Victor I. Afolabi
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+#ifndef SAGE_H
+#define SAGE_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque handle to a `sage::DType`. */
+typedef struct SageValue SageValue;
+
+/* Opaque handle to a `sage::graph::KnowledgeGraph`. */
+typedef struct SageGraph SageGraph;
+
+typedef enum SageKind {
+  SageNull = 0,
+  SageBoolean = 1,
+  SageNumber = 2,
+  SageString = 3,
+  SageDateTime = 4,
+  SageArray = 5,
+  SageObject = 6,
+  SageBytes = 7,
+} SageKind;
+
+/* Errors & strings. */
+const char *sage_last_error(void);
+void sage_string_free(char *s);
+
+/* Values. */
+SageValue *sage_parse(const char *text);
+void sage_value_free(SageValue *value);
+SageKind sage_value_kind(const SageValue *value);
+SageValue *sage_value_pointer(const SageValue *value, const char *pointer);
+char *sage_value_to_json(const SageValue *value);
+
+/* Graphs. `NULL` query terms match anything. */
+SageGraph *sage_graph_new(void);
+void sage_graph_free(SageGraph *graph);
+size_t sage_graph_len(const SageGraph *graph);
+int sage_graph_insert(SageGraph *graph, const char *subject,
+                      const char *predicate, const char *object);
+int sage_graph_insert_literal(SageGraph *graph, const char *subject,
+                              const char *predicate, const SageValue *value);
+SageValue *sage_graph_query(const SageGraph *graph, const char *subject,
+                            const char *predicate, const char *object);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SAGE_H */
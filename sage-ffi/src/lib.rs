@@ -0,0 +1,403 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! C compatible interface for embedding `sage`.
+//!
+//! Values & graphs are handed out as opaque, heap allocated handles which
+//! must be released with their matching `*_free` function. Strings returned
+//! to C are owned by the caller and released with `sage_string_free`.
+//!
+//! Functions returning a pointer return `NULL` on failure, functions
+//! returning an `int` return a non-zero value on failure. In both cases
+//! `sage_last_error` describes the failure.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::ffi::{CStr, CString};
+//! use sage_ffi::*;
+//!
+//! unsafe {
+//!   let text = CString::new(r#"{"name": {"first": "John"}}"#).unwrap();
+//!   let doc = sage_parse(text.as_ptr());
+//!
+//!   let path = CString::new("/name/first").unwrap();
+//!   let first = sage_value_pointer(doc, path.as_ptr());
+//!   let json = sage_value_to_json(first);
+//!   assert_eq!(CStr::from_ptr(json).to_str().unwrap(), r#""John""#);
+//!
+//!   sage_string_free(json);
+//!   sage_value_free(first);
+//!   sage_value_free(doc);
+//! }
+//! ```
+
+use std::{
+  cell::RefCell,
+  ffi::{CStr, CString},
+  os::raw::{c_char, c_int},
+  ptr,
+};
+
+use sage::{
+  graph::{KnowledgeGraph, Node, Predicate},
+  json, DType, Map,
+};
+
+/// Opaque handle to a `sage::DType`.
+pub struct SageValue(DType);
+
+/// Opaque handle to a `sage::graph::KnowledgeGraph`.
+pub struct SageGraph(KnowledgeGraph);
+
+/// Kind of value held by a `SageValue`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SageKind {
+  SageNull = 0,
+  SageBoolean = 1,
+  SageNumber = 2,
+  SageString = 3,
+  SageDateTime = 4,
+  SageArray = 5,
+  SageObject = 6,
+  SageBytes = 7,
+}
+
+thread_local! {
+  static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: &str) {
+  let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+  LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Reads a NUL terminated UTF-8 string, recording an error if it's invalid.
+unsafe fn to_str<'a>(s: *const c_char, what: &str) -> Option<&'a str> {
+  if s.is_null() {
+    set_last_error(&format!("`{}` is NULL", what));
+    return None;
+  }
+  match CStr::from_ptr(s).to_str() {
+    Ok(s) => Some(s),
+    Err(_) => {
+      set_last_error(&format!("`{}` isn't valid UTF-8", what));
+      None
+    }
+  }
+}
+
+/// Reads an optional pattern term where `NULL` matches anything.
+unsafe fn to_term<'a>(
+  s: *const c_char,
+  what: &str,
+) -> Result<Option<&'a str>, ()> {
+  if s.is_null() {
+    Ok(None)
+  } else {
+    to_str(s, what).map(Some).ok_or(())
+  }
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+  match CString::new(s) {
+    Ok(s) => s.into_raw(),
+    Err(_) => {
+      set_last_error("string contains an interior NUL byte");
+      ptr::null_mut()
+    }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Errors & strings.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Returns the message of the last error raised on this thread, or `NULL`.
+///
+/// The returned string is owned by the library and valid until the next
+/// call into the library on the same thread.
+#[no_mangle]
+pub extern "C" fn sage_last_error() -> *const c_char {
+  LAST_ERROR.with(|e| match &*e.borrow() {
+    Some(message) => message.as_ptr(),
+    None => ptr::null(),
+  })
+}
+
+/// Releases a string returned by the library.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a string returned by this library which hasn't
+/// been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn sage_string_free(s: *mut c_char) {
+  if !s.is_null() {
+    drop(CString::from_raw(s));
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Values.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Parses NUL terminated JSON text into a new value.
+///
+/// # Safety
+///
+/// `text` must be `NULL` or a valid NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn sage_parse(text: *const c_char) -> *mut SageValue {
+  let text = match to_str(text, "text") {
+    Some(text) => text,
+    None => return ptr::null_mut(),
+  };
+  match json::from_str::<DType>(text) {
+    Ok(value) => Box::into_raw(Box::new(SageValue(value))),
+    Err(err) => {
+      set_last_error(&err.to_string());
+      ptr::null_mut()
+    }
+  }
+}
+
+/// Releases a value.
+///
+/// # Safety
+///
+/// `value` must be `NULL` or a value returned by this library which hasn't
+/// been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn sage_value_free(value: *mut SageValue) {
+  if !value.is_null() {
+    drop(Box::from_raw(value));
+  }
+}
+
+/// Returns the kind of `value`.
+///
+/// # Safety
+///
+/// `value` must be a valid, non-`NULL` value handle.
+#[no_mangle]
+pub unsafe extern "C" fn sage_value_kind(value: *const SageValue) -> SageKind {
+  match &(*value).0 {
+    DType::Null => SageKind::SageNull,
+    DType::Boolean(_) => SageKind::SageBoolean,
+    DType::Number(_) => SageKind::SageNumber,
+    DType::String(_) => SageKind::SageString,
+    DType::DateTime(_) => SageKind::SageDateTime,
+    DType::Array(_) => SageKind::SageArray,
+    DType::Object(_) => SageKind::SageObject,
+    DType::Bytes(_) => SageKind::SageBytes,
+  }
+}
+
+/// Looks up a copy of the value at the [RFC 6901] JSON pointer `pointer`,
+/// e.g. `"/name/first"`. Returns `NULL` if nothing is found.
+///
+/// # Safety
+///
+/// `value` must be a valid, non-`NULL` value handle and `pointer` a valid
+/// NUL terminated string.
+///
+/// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+#[no_mangle]
+pub unsafe extern "C" fn sage_value_pointer(
+  value: *const SageValue,
+  pointer: *const c_char,
+) -> *mut SageValue {
+  let pointer = match to_str(pointer, "pointer") {
+    Some(pointer) => pointer,
+    None => return ptr::null_mut(),
+  };
+  match (*value).0.pointer(pointer) {
+    Some(found) => Box::into_raw(Box::new(SageValue(found.clone()))),
+    None => {
+      set_last_error(&format!("nothing found at `{}`", pointer));
+      ptr::null_mut()
+    }
+  }
+}
+
+/// Serializes `value` into JSON text. Release with `sage_string_free`.
+///
+/// # Safety
+///
+/// `value` must be a valid, non-`NULL` value handle.
+#[no_mangle]
+pub unsafe extern "C" fn sage_value_to_json(
+  value: *const SageValue,
+) -> *mut c_char {
+  match json::to_string(&(*value).0) {
+    Ok(text) => into_c_string(text),
+    Err(err) => {
+      set_last_error(&err.to_string());
+      ptr::null_mut()
+    }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Graphs.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Creates an empty graph.
+#[no_mangle]
+pub extern "C" fn sage_graph_new() -> *mut SageGraph {
+  Box::into_raw(Box::new(SageGraph(KnowledgeGraph::new())))
+}
+
+/// Releases a graph.
+///
+/// # Safety
+///
+/// `graph` must be `NULL` or a graph returned by `sage_graph_new` which
+/// hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn sage_graph_free(graph: *mut SageGraph) {
+  if !graph.is_null() {
+    drop(Box::from_raw(graph));
+  }
+}
+
+/// Returns the number of triples in `graph`.
+///
+/// # Safety
+///
+/// `graph` must be a valid, non-`NULL` graph handle.
+#[no_mangle]
+pub unsafe extern "C" fn sage_graph_len(graph: *const SageGraph) -> usize {
+  (*graph).0.len()
+}
+
+/// Links the resources `subject` & `object` (both IRIs) with `predicate`.
+///
+/// # Safety
+///
+/// `graph` must be a valid, non-`NULL` graph handle and the remaining
+/// arguments valid NUL terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn sage_graph_insert(
+  graph: *mut SageGraph,
+  subject: *const c_char,
+  predicate: *const c_char,
+  object: *const c_char,
+) -> c_int {
+  let terms = (
+    to_str(subject, "subject"),
+    to_str(predicate, "predicate"),
+    to_str(object, "object"),
+  );
+  match terms {
+    (Some(s), Some(p), Some(o)) => {
+      (*graph).0.insert(
+        Node::Http(s.to_string()),
+        Predicate::Literal(p.to_string()),
+        Node::Http(o.to_string()),
+      );
+      0
+    }
+    _ => -1,
+  }
+}
+
+/// Attaches a copy of `value` to the resource `subject`.
+///
+/// # Safety
+///
+/// `graph` & `value` must be valid, non-`NULL` handles and the remaining
+/// arguments valid NUL terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn sage_graph_insert_literal(
+  graph: *mut SageGraph,
+  subject: *const c_char,
+  predicate: *const c_char,
+  value: *const SageValue,
+) -> c_int {
+  match (to_str(subject, "subject"), to_str(predicate, "predicate")) {
+    (Some(s), Some(p)) => {
+      (*graph).0.insert(
+        Node::Http(s.to_string()),
+        Predicate::Literal(p.to_string()),
+        Node::Literal((*value).0.clone()),
+      );
+      0
+    }
+    _ => -1,
+  }
+}
+
+/// Returns every triple matching the pattern as an array value of
+/// `{"subject", "predicate", "object"}` objects. A `NULL` term matches
+/// anything; `subject` & `object` are matched as IRIs.
+///
+/// # Safety
+///
+/// `graph` must be a valid, non-`NULL` graph handle and the remaining
+/// arguments `NULL` or valid NUL terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn sage_graph_query(
+  graph: *const SageGraph,
+  subject: *const c_char,
+  predicate: *const c_char,
+  object: *const c_char,
+) -> *mut SageValue {
+  let terms = (
+    to_term(subject, "subject"),
+    to_term(predicate, "predicate"),
+    to_term(object, "object"),
+  );
+  let (subject, predicate, object) = match terms {
+    (Ok(s), Ok(p), Ok(o)) => (s, p, o),
+    _ => return ptr::null_mut(),
+  };
+  let subject = subject.map(|s| Node::Http(s.to_string()));
+  let object = object.map(|o| Node::Http(o.to_string()));
+
+  let rows: Vec<DType> = (*graph)
+    .0
+    .matches(subject.as_ref(), predicate, object.as_ref())
+    .map(|triple| {
+      let mut row = Map::new();
+      row.insert("subject".to_string(), triple.source().to_string().into());
+      row.insert(
+        "predicate".to_string(),
+        triple.predicate().to_string().into(),
+      );
+      let object = match triple.destination() {
+        Node::Literal(value) => value.clone(),
+        node => node.to_string().into(),
+      };
+      row.insert("object".to_string(), object);
+      DType::Object(row)
+    })
+    .collect();
+
+  Box::into_raw(Box::new(SageValue(DType::from(rows))))
+}
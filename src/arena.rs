@@ -0,0 +1,429 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::arena` parses JSON into a `Document` which keeps the whole tree
+//! in a handful of flat buffers: the elements of an array (or members of an
+//! object) are a contiguous slice of one buffer, and every string lives in
+//! a single text buffer.
+//!
+//! Parsing allocates a few growing buffers rather than one allocation per
+//! value, and dropping a `Document` frees the entire tree at once. It suits
+//! workloads that parse, inspect & drop many documents per second; use
+//! `DType` to build or modify documents.
+//!
+//! # Example
+//!
+//! ```rust
+//! use sage::arena::Document;
+//!
+//! let doc = Document::parse(
+//!   r#"{"@id": "https://example.com/Ada", "knows": [{"name": "Alan"}]}"#,
+//! )
+//! .unwrap();
+//!
+//! let root = doc.root();
+//! assert_eq!(root.get("@id").unwrap().as_str(), Some("https://example.com/Ada"));
+//! let friend = root.get("knows").unwrap().at(0).unwrap();
+//! assert_eq!(friend.pointer("/name").unwrap().as_str(), Some("Alan"));
+//! assert_eq!(root.members().count(), 2);
+//! ```
+
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+
+use crate::{
+  datastore::json::Deserializer,
+  dtype::{DType, Map, Number},
+  Result,
+};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Document
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// A range of one of the buffers of a `Document`.
+#[derive(Clone, Copy, Debug)]
+struct Span {
+  start: u32,
+  len: u32,
+}
+
+impl Span {
+  fn range(self) -> std::ops::Range<usize> {
+    self.start as usize..(self.start + self.len) as usize
+  }
+}
+
+/// A value of a `Document`, whose strings & children are spans of its
+/// buffers.
+#[derive(Clone, Debug)]
+enum Value {
+  Null,
+  Boolean(bool),
+  Number(Number),
+  String(Span),
+  /// Span of `Document::elements`.
+  Array(Span),
+  /// Span of `Document::members`.
+  Object(Span),
+}
+
+/// A key (span of `Document::text`) and its value.
+#[derive(Clone, Debug)]
+struct Member {
+  key: Span,
+  value: Value,
+}
+
+/// `Document` is a parsed JSON document stored in an arena. See the module
+/// documentation.
+#[derive(Clone, Debug)]
+pub struct Document {
+  root: Value,
+  text: String,
+  elements: Vec<Value>,
+  members: Vec<Member>,
+  /// Values of the arrays & objects being parsed.
+  stack: Vec<Value>,
+  member_stack: Vec<Member>,
+}
+
+impl Document {
+  /// Parses `input` into a new `Document`.
+  pub fn parse(input: &str) -> Result<Document> {
+    let mut doc = Document {
+      root: Value::Null,
+      text: String::with_capacity(input.len() / 2),
+      elements: Vec::new(),
+      members: Vec::new(),
+      stack: Vec::new(),
+      member_stack: Vec::new(),
+    };
+    let mut de = Deserializer::from_str(input);
+    doc.root = Seed { doc: &mut doc }.deserialize(&mut de)?;
+    de.end()?;
+
+    doc.stack = Vec::new();
+    doc.member_stack = Vec::new();
+    Ok(doc)
+  }
+
+  /// Returns the top-level value.
+  pub fn root(&self) -> Ref<'_> {
+    Ref {
+      doc: self,
+      value: &self.root,
+    }
+  }
+
+  fn str(&self, span: Span) -> &str {
+    &self.text[span.range()]
+  }
+
+  /// Appends `s` to the text buffer.
+  fn push_str(&mut self, s: &str) -> Span {
+    let start = self.text.len() as u32;
+    self.text.push_str(s);
+    Span {
+      start,
+      len: s.len() as u32,
+    }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Ref
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Ref` is a value of a `Document`.
+#[derive(Clone, Copy)]
+pub struct Ref<'a> {
+  doc: &'a Document,
+  value: &'a Value,
+}
+
+impl<'a> Ref<'a> {
+  /// Returns `true` if the value is `null`.
+  pub fn is_null(&self) -> bool {
+    matches!(self.value, Value::Null)
+  }
+
+  /// Returns `true` if the value is an array.
+  pub fn is_array(&self) -> bool {
+    matches!(self.value, Value::Array(_))
+  }
+
+  /// Returns `true` if the value is an object.
+  pub fn is_object(&self) -> bool {
+    matches!(self.value, Value::Object(_))
+  }
+
+  /// Returns the boolean, if the value is one.
+  pub fn as_bool(&self) -> Option<bool> {
+    match *self.value {
+      Value::Boolean(b) => Some(b),
+      _ => None,
+    }
+  }
+
+  /// Returns the number, if the value is one.
+  pub fn as_number(&self) -> Option<&'a Number> {
+    match self.value {
+      Value::Number(n) => Some(n),
+      _ => None,
+    }
+  }
+
+  /// Returns the number as an `i64`, if it's an integer which fits.
+  pub fn as_i64(&self) -> Option<i64> {
+    self.as_number()?.as_i64()
+  }
+
+  /// Returns the number as an `f64`, if the value is a number.
+  pub fn as_f64(&self) -> Option<f64> {
+    self.as_number()?.as_f64()
+  }
+
+  /// Returns the string, borrowed from the document, if the value is one.
+  pub fn as_str(&self) -> Option<&'a str> {
+    match *self.value {
+      Value::String(span) => Some(self.doc.str(span)),
+      _ => None,
+    }
+  }
+
+  /// Returns the number of elements or members of an array or object, `0`
+  /// for any other value.
+  pub fn len(&self) -> usize {
+    match *self.value {
+      Value::Array(span) | Value::Object(span) => span.len as usize,
+      _ => 0,
+    }
+  }
+
+  /// Returns `true` if the value has no elements or members.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns the `idx`th element, if the value is an array long enough.
+  pub fn at(&self, idx: usize) -> Option<Ref<'a>> {
+    self.elements().nth(idx)
+  }
+
+  /// Returns the value of `key`, if the value is an object which has it.
+  pub fn get(&self, key: &str) -> Option<Ref<'a>> {
+    self.members().find(|(k, _)| *k == key).map(|(_, v)| v)
+  }
+
+  /// Looks up a value by [JSON Pointer], like `DType::pointer`.
+  ///
+  /// [JSON Pointer]: https://tools.ietf.org/html/rfc6901
+  pub fn pointer(&self, pointer: &str) -> Option<Ref<'a>> {
+    if pointer.is_empty() {
+      return Some(*self);
+    }
+    let tokens = pointer.strip_prefix('/')?.split('/');
+    tokens
+      .map(|t| t.replace("~1", "/").replace("~0", "~"))
+      .try_fold(*self, |target, token| match target.value {
+        Value::Object(_) => target.get(&token),
+        Value::Array(_) => target.at(token.parse().ok()?),
+        _ => None,
+      })
+  }
+
+  /// Returns the elements of an array, nothing for any other value.
+  pub fn elements(&self) -> impl Iterator<Item = Ref<'a>> + 'a {
+    let doc = self.doc;
+    let elements: &'a [Value] = match *self.value {
+      Value::Array(span) => &doc.elements[span.range()],
+      _ => &[],
+    };
+    elements.iter().map(move |value| Ref { doc, value })
+  }
+
+  /// Returns the keys & values of an object, in document order, nothing for
+  /// any other value.
+  pub fn members(&self) -> impl Iterator<Item = (&'a str, Ref<'a>)> + 'a {
+    let doc = self.doc;
+    let members: &'a [Member] = match *self.value {
+      Value::Object(span) => &doc.members[span.range()],
+      _ => &[],
+    };
+    members.iter().map(move |member| {
+      let value = Ref {
+        doc,
+        value: &member.value,
+      };
+      (doc.str(member.key), value)
+    })
+  }
+
+  /// Copies the value into a `DType`.
+  pub fn to_dtype(&self) -> DType {
+    match *self.value {
+      Value::Null => DType::Null,
+      Value::Boolean(b) => DType::Boolean(b),
+      Value::Number(ref n) => DType::Number(n.clone()),
+      Value::String(span) => DType::String(self.doc.str(span).to_string()),
+      Value::Array(_) => self.elements().map(|e| e.to_dtype()).collect(),
+      Value::Object(_) => DType::Object(
+        self
+          .members()
+          .map(|(k, v)| (k.to_string(), v.to_dtype()))
+          .collect::<Map<String, DType>>(),
+      ),
+    }
+  }
+}
+
+impl fmt::Debug for Ref<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Debug::fmt(&self.to_dtype(), f)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Parsing
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Parses a value into a `Document`, returning it once its children are in
+/// place.
+struct Seed<'d> {
+  doc: &'d mut Document,
+}
+
+impl<'de> DeserializeSeed<'de> for Seed<'_> {
+  type Value = Value;
+
+  fn deserialize<D>(self, deserializer: D) -> Result<Value, D::Error>
+  where
+    D: de::Deserializer<'de>,
+  {
+    deserializer.deserialize_any(self)
+  }
+}
+
+impl<'de> Visitor<'de> for Seed<'_> {
+  type Value = Value;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("any valid JSON value")
+  }
+
+  fn visit_bool<E>(self, b: bool) -> Result<Value, E> {
+    Ok(Value::Boolean(b))
+  }
+
+  fn visit_i64<E>(self, n: i64) -> Result<Value, E> {
+    Ok(Value::Number(n.into()))
+  }
+
+  fn visit_u64<E>(self, n: u64) -> Result<Value, E> {
+    Ok(Value::Number(n.into()))
+  }
+
+  fn visit_f64<E>(self, f: f64) -> Result<Value, E> {
+    Ok(Number::from_f64(f).map_or(Value::Null, Value::Number))
+  }
+
+  fn visit_str<E>(self, s: &str) -> Result<Value, E> {
+    Ok(Value::String(self.doc.push_str(s)))
+  }
+
+  fn visit_unit<E>(self) -> Result<Value, E> {
+    Ok(Value::Null)
+  }
+
+  fn visit_seq<A>(self, mut seq: A) -> Result<Value, A::Error>
+  where
+    A: SeqAccess<'de>,
+  {
+    // Nested containers are moved out of the stack before the next element
+    // is pushed, so this one's elements end up contiguous.
+    let mark = self.doc.stack.len();
+    while let Some(value) = seq.next_element_seed(Seed { doc: self.doc })? {
+      self.doc.stack.push(value);
+    }
+
+    let start = self.doc.elements.len() as u32;
+    let doc = &mut *self.doc;
+    doc.elements.extend(doc.stack.drain(mark..));
+    Ok(Value::Array(Span {
+      start,
+      len: doc.elements.len() as u32 - start,
+    }))
+  }
+
+  fn visit_map<A>(self, mut map: A) -> Result<Value, A::Error>
+  where
+    A: MapAccess<'de>,
+  {
+    let mark = self.doc.member_stack.len();
+    while let Some(key) = map.next_key_seed(KeySeed { doc: self.doc })? {
+      let value = map.next_value_seed(Seed { doc: self.doc })?;
+      self.doc.member_stack.push(Member { key, value });
+    }
+
+    let start = self.doc.members.len() as u32;
+    let doc = &mut *self.doc;
+    doc.members.extend(doc.member_stack.drain(mark..));
+    Ok(Value::Object(Span {
+      start,
+      len: doc.members.len() as u32 - start,
+    }))
+  }
+}
+
+/// Parses an object key into the text buffer of a `Document`.
+struct KeySeed<'d> {
+  doc: &'d mut Document,
+}
+
+impl<'de> DeserializeSeed<'de> for KeySeed<'_> {
+  type Value = Span;
+
+  fn deserialize<D>(self, deserializer: D) -> Result<Span, D::Error>
+  where
+    D: de::Deserializer<'de>,
+  {
+    deserializer.deserialize_str(self)
+  }
+}
+
+impl<'de> Visitor<'de> for KeySeed<'_> {
+  type Value = Span;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("a string key")
+  }
+
+  fn visit_str<E>(self, s: &str) -> Result<Span, E> {
+    Ok(self.doc.push_str(s))
+  }
+}
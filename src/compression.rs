@@ -0,0 +1,420 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transparent gzip & Zstandard (de)compression of dumps.
+//!
+//! Public RDF dumps are almost always compressed. With the `compression`
+//! feature, `json::from_reader`, `NTriples::from_reader`,
+//! `Turtle::from_reader`, `JsonLd::from_reader` and
+//! `sage::load::bulk_load` sniff their input and decompress gzip (`.gz`) &
+//! Zstandard (`.zst`) streams on the fly; plain input is read as before.
+//!
+//! `Decoder` wraps any reader the same way, and `Encoder` compresses what's
+//! written into it, e.g. the output of `to_writer`. `open` & `create` pick
+//! the codec of a file from its content & extension respectively.
+//!
+//! The codecs are those of the [flate2] & [zstd] crates. Decoders read
+//! concatenated members & frames, as written by the reference tools, and
+//! stop with an error once they've produced [`MAX_OUTPUT`] bytes, so a
+//! small malicious stream can't exhaust memory or disk.
+//!
+//! [flate2]: https://docs.rs/flate2
+//! [zstd]: https://docs.rs/zstd
+
+use std::{
+  fs::File,
+  io::{self, BufRead, BufReader, BufWriter, Read, Write},
+  path::Path,
+};
+
+use flate2::{bufread::MultiGzDecoder, write::GzEncoder};
+
+/// The number of bytes a `Decoder` produces from a compressed stream before
+/// failing, unless changed with `Decoder::max_output`.
+pub const MAX_OUTPUT: u64 = 16 << 30;
+
+/// The magic number starting gzip members.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The magic number starting Zstandard frames.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// `Compression` is a supported compression format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Compression {
+  /// [gzip](https://www.rfc-editor.org/rfc/rfc1952) (`.gz`).
+  Gzip,
+  /// [Zstandard](https://www.rfc-editor.org/rfc/rfc8878) (`.zst`).
+  Zstd,
+}
+
+impl Compression {
+  /// Returns the compression of `path` implied by its extension.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::compression::Compression;
+  ///
+  /// assert_eq!(
+  ///   Compression::from_path("latest-all.nt.gz"),
+  ///   Some(Compression::Gzip)
+  /// );
+  /// assert_eq!(
+  ///   Compression::from_path("dump.ttl.zst"),
+  ///   Some(Compression::Zstd)
+  /// );
+  /// assert_eq!(Compression::from_path("dump.ttl"), None);
+  /// ```
+  pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Compression> {
+    let extension = path.as_ref().extension()?.to_str()?;
+    match extension.to_ascii_lowercase().as_str() {
+      "gz" | "gzip" => Some(Compression::Gzip),
+      "zst" | "zstd" => Some(Compression::Zstd),
+      _ => None,
+    }
+  }
+
+  /// Returns the compression of a stream starting with `bytes`, from its
+  /// magic number.
+  pub fn detect(bytes: &[u8]) -> Option<Compression> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+      Some(Compression::Gzip)
+    } else if bytes.starts_with(&ZSTD_MAGIC) {
+      Some(Compression::Zstd)
+    } else {
+      None
+    }
+  }
+
+  /// Returns the extension of files in this format, without the dot.
+  pub fn extension(self) -> &'static str {
+    match self {
+      Compression::Gzip => "gz",
+      Compression::Zstd => "zst",
+    }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Decoder
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Limit` fails reads once `inner` has produced more than `max` bytes.
+struct Limit<R> {
+  inner: R,
+  max: u64,
+  remaining: u64,
+}
+
+impl<R: Read> Limit<R> {
+  fn new(inner: R) -> Limit<R> {
+    Limit {
+      inner,
+      max: MAX_OUTPUT,
+      remaining: MAX_OUTPUT,
+    }
+  }
+
+  fn set_max(&mut self, max: u64) {
+    self.max = max;
+    self.remaining = max;
+  }
+}
+
+impl<R: Read> Read for Limit<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    if buf.is_empty() {
+      return Ok(0);
+    }
+    if self.remaining == 0 {
+      // Only streams going on past the limit are refused.
+      return match self.inner.read(&mut [0])? {
+        0 => Ok(0),
+        _ => Err(io::Error::new(
+          io::ErrorKind::InvalidData,
+          format!("decompressed stream exceeds {} bytes", self.max),
+        )),
+      };
+    }
+    let len = buf
+      .len()
+      .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+    let read = self.inner.read(&mut buf[..len])?;
+    self.remaining -= read as u64;
+    Ok(read)
+  }
+}
+
+enum Decoding<R> {
+  Plain(R),
+  Gzip(BufReader<Limit<MultiGzDecoder<R>>>),
+  Zstd(BufReader<Limit<zstd::Decoder<'static, R>>>),
+}
+
+/// `Decoder` reads a stream, decompressing it if it's gzip or Zstandard
+/// compressed.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::{Read, Write};
+///
+/// use sage::compression::{Compression, Decoder, Encoder};
+///
+/// let mut encoder = Encoder::new(Vec::new(), Compression::Zstd).unwrap();
+/// encoder.write_all(b"<a> <b> <c> .\n").unwrap();
+/// let compressed = encoder.finish().unwrap();
+///
+/// let mut decoder = Decoder::new(&compressed[..]).unwrap();
+/// assert_eq!(decoder.compression(), Some(Compression::Zstd));
+/// let mut text = String::new();
+/// decoder.read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "<a> <b> <c> .\n");
+///
+/// // Output beyond `max_output` is refused.
+/// let mut decoder = Decoder::new(&compressed[..]).unwrap().max_output(4);
+/// assert!(decoder.read_to_end(&mut Vec::new()).is_err());
+///
+/// // Uncompressed input is passed through.
+/// let mut decoder = Decoder::new(&b"<a> <b> <c> ."[..]).unwrap();
+/// assert_eq!(decoder.compression(), None);
+/// ```
+pub struct Decoder<R> {
+  inner: Decoding<R>,
+}
+
+impl<R: BufRead> Decoder<R> {
+  /// Creates a decoder of `reader`, detecting its compression from the
+  /// start of its buffer.
+  pub fn new(mut reader: R) -> io::Result<Decoder<R>> {
+    let inner = match Compression::detect(reader.fill_buf()?) {
+      None => Decoding::Plain(reader),
+      Some(Compression::Gzip) => {
+        Decoding::Gzip(BufReader::new(Limit::new(MultiGzDecoder::new(reader))))
+      }
+      Some(Compression::Zstd) => Decoding::Zstd(BufReader::new(Limit::new(
+        zstd::Decoder::with_buffer(reader)?,
+      ))),
+    };
+    Ok(Decoder { inner })
+  }
+
+  /// Sets the number of bytes decompressed before reads fail,
+  /// [`MAX_OUTPUT`] by default. Plain input isn't limited.
+  ///
+  /// Should be set before reading starts.
+  pub fn max_output(mut self, max: u64) -> Self {
+    match &mut self.inner {
+      Decoding::Plain(_) => {}
+      Decoding::Gzip(reader) => reader.get_mut().set_max(max),
+      Decoding::Zstd(reader) => reader.get_mut().set_max(max),
+    }
+    self
+  }
+
+  /// Returns the compression detected, `None` for plain input.
+  pub fn compression(&self) -> Option<Compression> {
+    match self.inner {
+      Decoding::Plain(_) => None,
+      Decoding::Gzip(_) => Some(Compression::Gzip),
+      Decoding::Zstd(_) => Some(Compression::Zstd),
+    }
+  }
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    match &mut self.inner {
+      Decoding::Plain(reader) => reader.read(buf),
+      Decoding::Gzip(reader) => reader.read(buf),
+      Decoding::Zstd(reader) => reader.read(buf),
+    }
+  }
+}
+
+impl<R: BufRead> BufRead for Decoder<R> {
+  fn fill_buf(&mut self) -> io::Result<&[u8]> {
+    match &mut self.inner {
+      Decoding::Plain(reader) => reader.fill_buf(),
+      Decoding::Gzip(reader) => reader.fill_buf(),
+      Decoding::Zstd(reader) => reader.fill_buf(),
+    }
+  }
+
+  fn consume(&mut self, amt: usize) {
+    match &mut self.inner {
+      Decoding::Plain(reader) => reader.consume(amt),
+      Decoding::Gzip(reader) => reader.consume(amt),
+      Decoding::Zstd(reader) => reader.consume(amt),
+    }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Encoder
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+enum Encoding<W: Write> {
+  Plain(W),
+  Gzip(GzEncoder<W>),
+  Zstd(zstd::Encoder<'static, W>),
+}
+
+/// `Encoder` compresses what's written into it, at the default level of
+/// its codec.
+///
+/// `finish` writes the end of the stream and returns the inner writer.
+/// Dropping an encoder finishes it too, but ignores errors.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::{
+///   compression::{Compression, Encoder},
+///   formats::NTriples,
+///   graph::{KnowledgeGraph, Node, Predicate},
+/// };
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   Node::Http("https://example.com/Ada".to_string()),
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal("Ada".into()),
+/// );
+///
+/// let mut encoder = Encoder::new(Vec::new(), Compression::Gzip).unwrap();
+/// NTriples::new(&graph).to_writer(&mut encoder).unwrap();
+/// let compressed = encoder.finish().unwrap();
+///
+/// // Readers decompress transparently.
+/// assert_eq!(NTriples::from_reader(&compressed[..]).unwrap().len(), 1);
+/// ```
+pub struct Encoder<W: Write> {
+  inner: Option<Encoding<W>>,
+}
+
+impl<W: Write> Encoder<W> {
+  /// Creates an encoder compressing into `writer`.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the Zstandard context can't be created.
+  pub fn new(writer: W, compression: Compression) -> io::Result<Encoder<W>> {
+    let inner = match compression {
+      Compression::Gzip => {
+        Encoding::Gzip(GzEncoder::new(writer, flate2::Compression::default()))
+      }
+      Compression::Zstd => {
+        let mut encoder = zstd::Encoder::new(writer, 0)?;
+        encoder.include_checksum(true)?;
+        Encoding::Zstd(encoder)
+      }
+    };
+    Ok(Encoder { inner: Some(inner) })
+  }
+
+  /// Writes the end of the stream, returning the inner writer.
+  pub fn finish(mut self) -> io::Result<W> {
+    match self.inner.take().expect("encoder finished once") {
+      Encoding::Plain(mut writer) => writer.flush().map(|_| writer),
+      Encoding::Gzip(encoder) => encoder.finish(),
+      Encoding::Zstd(encoder) => encoder.finish(),
+    }
+  }
+
+  fn inner(&mut self) -> &mut dyn Write {
+    match self.inner.as_mut().expect("encoder finished once") {
+      Encoding::Plain(writer) => writer,
+      Encoding::Gzip(encoder) => encoder,
+      Encoding::Zstd(encoder) => encoder,
+    }
+  }
+}
+
+impl<W: Write> Write for Encoder<W> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.inner().write(buf)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.inner().flush()
+  }
+}
+
+impl<W: Write> Drop for Encoder<W> {
+  fn drop(&mut self) {
+    if self.inner.is_some() {
+      let _ = Encoder {
+        inner: self.inner.take(),
+      }
+      .finish();
+    }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Files
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Opens the file at `path` for reading, decompressing it if it's gzip or
+/// Zstandard compressed.
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Decoder<BufReader<File>>> {
+  Decoder::new(BufReader::new(File::open(path)?))
+}
+
+/// Creates the file at `path` for writing, compressing it if its extension
+/// is `.gz` or `.zst`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::io::{Read, Write};
+///
+/// use sage::compression::{self, Compression};
+///
+/// let path = std::env::temp_dir().join("sage-compression-example.nt.zst");
+/// let mut file = compression::create(&path).unwrap();
+/// file.write_all(b"<a> <b> <c> .\n").unwrap();
+/// file.finish().unwrap();
+///
+/// let mut file = compression::open(&path).unwrap();
+/// assert_eq!(file.compression(), Some(Compression::Zstd));
+/// let mut text = String::new();
+/// file.read_to_string(&mut text).unwrap();
+/// assert_eq!(text, "<a> <b> <c> .\n");
+///
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Encoder<BufWriter<File>>> {
+  let writer = BufWriter::new(File::create(&path)?);
+  match Compression::from_path(&path) {
+    Some(compression) => Encoder::new(writer, compression),
+    None => Ok(Encoder {
+      inner: Some(Encoding::Plain(writer)),
+    }),
+  }
+}
@@ -0,0 +1,347 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Content-addressable sidecar store for large values.
+//!
+//! Large documents, images & other payloads linked from nodes are written to
+//! files named after the SHA-256 of their content. The graph only keeps a
+//! small `sage:blob:<hash>` reference, which the `BlobStore` resolves back
+//! to the content on access.
+
+use std::{
+  fmt,
+  fs::{self, File, OpenOptions},
+  io::{self, Read, Write},
+  mem,
+  path::{Path, PathBuf},
+  str::FromStr,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+  dtype::DType,
+  error::{Error, ErrorCode},
+  random, Result,
+};
+
+/// Prefix of a blob reference.
+pub const BLOB_PREFIX: &str = "sage:blob:";
+
+/// Names tried for a temporary file before giving up.
+const TEMP_ATTEMPTS: usize = 16;
+
+/// Creates a new temporary file in `root`. The file is created exclusively,
+/// so a name that's taken (or guessed & planted by someone else) is never
+/// reused: another name is tried instead.
+fn create_temp(root: &Path) -> io::Result<(PathBuf, File)> {
+  for _ in 0..TEMP_ATTEMPTS {
+    let path = root.join(format!(".tmp-{:016x}", random::u64()));
+    match OpenOptions::new().write(true).create_new(true).open(&path) {
+      Ok(file) => return Ok((path, file)),
+      Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+      Err(err) => return Err(err),
+    }
+  }
+  Err(io::Error::new(
+    io::ErrorKind::AlreadyExists,
+    "couldn't find an unused temporary file name",
+  ))
+}
+
+/// `BlobRef` is a `sage:blob:<hash>` reference to content in a `BlobStore`.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::blob::BlobRef;
+///
+/// let hash = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+/// let blob: BlobRef = format!("sage:blob:{}", hash).parse().unwrap();
+/// assert_eq!(blob.hash(), hash);
+///
+/// assert!("sage:blob:not-a-hash".parse::<BlobRef>().is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BlobRef {
+  hash: String,
+}
+
+impl BlobRef {
+  /// Returns the blob reference held by `value`, if it's a
+  /// `sage:blob:<hash>` string.
+  pub fn from_dtype(value: &DType) -> Option<BlobRef> {
+    value.as_str().and_then(|s| s.parse().ok())
+  }
+
+  /// Returns the lower case, hex encoded SHA-256 of the content.
+  pub fn hash(&self) -> &str {
+    &self.hash
+  }
+
+  /// Returns the reference as a `DType::String`.
+  pub fn to_dtype(&self) -> DType {
+    DType::String(self.to_string())
+  }
+}
+
+impl FromStr for BlobRef {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.strip_prefix(BLOB_PREFIX) {
+      Some(hash) if is_sha256_hex(hash) => Ok(BlobRef {
+        hash: hash.to_ascii_lowercase(),
+      }),
+      _ => Err(Error::syntax(ErrorCode::InvalidBlobRef, 0, 0)),
+    }
+  }
+}
+
+impl fmt::Display for BlobRef {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}{}", BLOB_PREFIX, self.hash)
+  }
+}
+
+impl From<BlobRef> for DType {
+  fn from(blob: BlobRef) -> DType {
+    blob.to_dtype()
+  }
+}
+
+/// `BlobStore` is a directory of hash-addressed files.
+///
+/// Blobs are sharded into sub-directories by the first two hex digits of
+/// their hash, i.e. `<root>/ab/abcdef...`. Writing the same content twice
+/// stores it once.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::blob::BlobStore;
+/// use sage::DType;
+///
+/// let dir = std::env::temp_dir().join("sage-blob-doctest");
+/// let store = BlobStore::open(&dir).unwrap();
+///
+/// let blob = store.put(b"a very large document").unwrap();
+/// assert!(blob.to_string().starts_with("sage:blob:"));
+/// assert_eq!(store.get(&blob).unwrap(), b"a very large document");
+///
+/// // Any `DType` holding the reference is fetched transparently.
+/// let value = blob.to_dtype();
+/// assert_eq!(
+///   store.fetch(&value).unwrap().as_deref(),
+///   Some(&b"a very large document"[..])
+/// );
+/// assert_eq!(store.fetch(&DType::from("plain")).unwrap(), None);
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct BlobStore {
+  root: PathBuf,
+}
+
+impl BlobStore {
+  /// Opens the store rooted at `root`, creating the directory if needed.
+  pub fn open<P: AsRef<Path>>(root: P) -> Result<BlobStore> {
+    let root = root.as_ref().to_path_buf();
+    fs::create_dir_all(&root).map_err(Error::io)?;
+    Ok(BlobStore { root })
+  }
+
+  /// Returns the root directory of the store.
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+
+  /// Stores `bytes` and returns its reference.
+  pub fn put(&self, bytes: &[u8]) -> Result<BlobRef> {
+    self.put_reader(bytes)
+  }
+
+  /// Streams `reader` into the store and returns its reference.
+  ///
+  /// The content is first written to a temporary file which is renamed into
+  /// place once the hash is known, so readers never observe partial blobs.
+  pub fn put_reader<R: Read>(&self, mut reader: R) -> Result<BlobRef> {
+    let (tmp, mut file) = create_temp(&self.root).map_err(Error::io)?;
+    let result = (|| {
+      let mut hasher = Sha256::new();
+      let mut buf = [0; 8192];
+      loop {
+        let n = match reader.read(&mut buf) {
+          Ok(0) => break,
+          Ok(n) => n,
+          Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+          Err(err) => return Err(err),
+        };
+        hasher.update(&buf[..n]);
+        file.write_all(&buf[..n])?;
+      }
+      file.sync_all()?;
+      Ok(to_hex(&hasher.finalize()))
+    })();
+
+    let hash = match result {
+      Ok(hash) => hash,
+      Err(err) => {
+        let _ = fs::remove_file(&tmp);
+        return Err(Error::io(err));
+      }
+    };
+
+    let blob = BlobRef { hash };
+    let path = self.path(&blob);
+    if path.exists() {
+      fs::remove_file(&tmp).map_err(Error::io)?;
+    } else {
+      fs::create_dir_all(path.parent().unwrap_or(&self.root))
+        .and_then(|_| fs::rename(&tmp, &path))
+        .map_err(Error::io)?;
+    }
+    Ok(blob)
+  }
+
+  /// Reads the whole content of `blob`, verifying it against its hash.
+  pub fn get(&self, blob: &BlobRef) -> Result<Vec<u8>> {
+    let bytes = fs::read(self.path(blob)).map_err(Error::io)?;
+    if to_hex(&Sha256::digest(&bytes)) != blob.hash {
+      return Err(Error::io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("blob {} is corrupt", blob),
+      )));
+    }
+    Ok(bytes)
+  }
+
+  /// Opens `blob` for streaming reads. Content isn't verified.
+  pub fn reader(&self, blob: &BlobRef) -> Result<File> {
+    File::open(self.path(blob)).map_err(Error::io)
+  }
+
+  /// Returns `true` if the store holds `blob`.
+  pub fn contains(&self, blob: &BlobRef) -> bool {
+    self.path(blob).is_file()
+  }
+
+  /// Deletes `blob` from the store. Missing blobs are ignored.
+  pub fn remove(&self, blob: &BlobRef) -> Result<()> {
+    match fs::remove_file(self.path(blob)) {
+      Err(err) if err.kind() != io::ErrorKind::NotFound => Err(Error::io(err)),
+      _ => Ok(()),
+    }
+  }
+
+  /// Returns the content referenced by `value` or `None` if `value` isn't a
+  /// blob reference.
+  pub fn fetch(&self, value: &DType) -> Result<Option<Vec<u8>>> {
+    match BlobRef::from_dtype(value) {
+      Some(blob) => self.get(&blob).map(Some),
+      None => Ok(None),
+    }
+  }
+
+  /// Replaces every string in `value` longer than `threshold` bytes with a
+  /// blob reference, recursing into arrays & objects.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::json;
+  /// use sage::blob::BlobStore;
+  ///
+  /// let dir = std::env::temp_dir().join("sage-blob-externalize-doctest");
+  /// let store = BlobStore::open(&dir).unwrap();
+  ///
+  /// let doc = json!({ "name": "John", "bio": "x".repeat(1024) });
+  /// let slim = store.externalize(doc.clone(), 64).unwrap();
+  ///
+  /// assert_eq!(slim["name"], "John");
+  /// assert!(slim["bio"].as_str().unwrap().starts_with("sage:blob:"));
+  /// assert_eq!(store.resolve(slim).unwrap(), doc);
+  /// # std::fs::remove_dir_all(&dir).unwrap();
+  /// ```
+  pub fn externalize(
+    &self,
+    mut value: DType,
+    threshold: usize,
+  ) -> Result<DType> {
+    Ok(match value {
+      DType::String(ref s) if s.len() > threshold => {
+        self.put(s.as_bytes())?.to_dtype()
+      }
+      DType::Array(ref mut values) => DType::Array(Box::new(
+        mem::take(&mut **values)
+          .into_iter()
+          .map(|v| self.externalize(v, threshold))
+          .collect::<Result<_>>()?,
+      )),
+      DType::Object(ref mut map) => DType::Object(
+        mem::take(map)
+          .into_iter()
+          .map(|(k, v)| Ok((k, self.externalize(v, threshold)?)))
+          .collect::<Result<_>>()?,
+      ),
+      value => value,
+    })
+  }
+
+  /// Replaces every blob reference in `value` whose content is valid UTF-8
+  /// with a `DType::String` of that content, recursing into arrays &
+  /// objects. Binary blobs are left as references.
+  pub fn resolve(&self, mut value: DType) -> Result<DType> {
+    Ok(match value {
+      DType::String(ref s) => match s.parse::<BlobRef>() {
+        Ok(blob) => match String::from_utf8(self.get(&blob)?) {
+          Ok(content) => DType::String(content),
+          Err(_) => value,
+        },
+        Err(_) => value,
+      },
+      DType::Array(ref mut values) => DType::Array(Box::new(
+        mem::take(&mut **values)
+          .into_iter()
+          .map(|v| self.resolve(v))
+          .collect::<Result<_>>()?,
+      )),
+      DType::Object(ref mut map) => DType::Object(
+        mem::take(map)
+          .into_iter()
+          .map(|(k, v)| Ok((k, self.resolve(v)?)))
+          .collect::<Result<_>>()?,
+      ),
+      value => value,
+    })
+  }
+
+  fn path(&self, blob: &BlobRef) -> PathBuf {
+    self.root.join(&blob.hash[..2]).join(&blob.hash)
+  }
+}
+
+fn is_sha256_hex(s: &str) -> bool {
+  s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+  const HEX: &[u8; 16] = b"0123456789abcdef";
+  let mut out = String::with_capacity(bytes.len() * 2);
+  for b in bytes {
+    out.push(HEX[(b >> 4) as usize] as char);
+    out.push(HEX[(b & 0xf) as usize] as char);
+  }
+  out
+}
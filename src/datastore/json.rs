@@ -12,8 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod config;
 mod de;
 mod iter;
+mod lazy;
+mod lenient;
+mod parallel;
+mod path;
+mod pretty;
 mod raw;
 mod read;
 mod ser;
@@ -22,16 +28,32 @@ mod ser;
 
 // Deserializer
 pub use de::{
-  from_reader, from_slice, from_str, Deserializer, StreamDeserializer,
+  from_reader, from_reader_with, from_slice, from_slice_with, from_str,
+  from_str_with, DeserializeOptions, Deserializer, StreamDeserializer,
+  RECURSION_LIMIT,
 };
+pub use lazy::LazyDType;
+pub use lenient::from_str_lenient;
+pub use parallel::{to_vec_parallel, to_writer_parallel};
+pub use path::get_path;
 
 // Serializer.
+#[cfg(feature = "compression")]
+pub use ser::to_writer_compressed;
 pub use ser::{
   to_string, to_string_pretty, to_vec, to_vec_pretty, to_writer,
   to_writer_pretty, CharEscape, CompactFormatter, Compound, Formatter,
   PrettyFormatter, Serializer, State,
 };
 
+// Configurable representations.
+pub use config::{
+  to_string_with, to_vec_with, to_writer_with, DateTimeFormat, SerializeConfig,
+};
+
+// Configurable pretty printing.
+pub use pretty::{to_string_pretty_with, to_writer_pretty_with, PrettyConfig};
+
 // Raw dtype.
 pub use raw::{
   to_raw_dtype, BorrowedRawDeserializer, OwnedRawDeserializer, RawDType, TOKEN,
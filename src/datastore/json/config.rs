@@ -0,0 +1,156 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+
+use serde::ser::Serialize;
+
+use crate::{datastore::json::ser::Serializer, Result};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `DateTimeFormat`.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// `DateTimeFormat` is how a `DType::DateTime` is written as JSON.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum DateTimeFormat {
+  /// An RFC 3339 string in UTC, e.g. `"2021-03-14T15:09:26Z"`.
+  #[default]
+  Rfc3339,
+  /// Milliseconds since the Unix epoch, e.g. `1615734566000`.
+  EpochMillis,
+  /// A string in the given `strftime` format, e.g. `"%Y-%m-%d"`, see
+  /// [`chrono::format::strftime`]. Invalid formats fail serialization.
+  Custom(String),
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `SerializeConfig`.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// `SerializeConfig` chooses the representation of values JSON has no
+/// native type for, per `Serializer`.
+///
+/// The default configuration produces the same output as `to_string`.
+///
+/// # Example
+///
+/// ```rust
+/// use chrono::{TimeZone, Utc};
+/// use sage::json::{self, DateTimeFormat, SerializeConfig};
+/// use sage::DType;
+///
+/// let when = Utc.with_ymd_and_hms(2021, 3, 14, 15, 9, 26).unwrap();
+/// let value = DType::from(vec![DType::from(when)]);
+/// assert_eq!(json::to_string(&value).unwrap(), r#"["2021-03-14T15:09:26Z"]"#);
+///
+/// let config = SerializeConfig::new().datetime(DateTimeFormat::EpochMillis);
+/// let text = json::to_string_with(&value, &config).unwrap();
+/// assert_eq!(text, "[1615734566000]");
+///
+/// let custom = DateTimeFormat::Custom("%d/%m/%Y".to_string());
+/// let config = SerializeConfig::new().datetime(custom);
+/// let text = json::to_string_with(&value, &config).unwrap();
+/// assert_eq!(text, r#"["14/03/2021"]"#);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SerializeConfig {
+  datetime: DateTimeFormat,
+}
+
+impl SerializeConfig {
+  /// Creates the configuration of `to_string`: RFC 3339 datetimes.
+  pub fn new() -> SerializeConfig {
+    SerializeConfig::default()
+  }
+
+  /// Sets how datetimes are written.
+  pub fn datetime(mut self, format: DateTimeFormat) -> Self {
+    self.datetime = format;
+    self
+  }
+
+  /// Returns how datetimes are written.
+  pub fn datetime_format(&self) -> &DateTimeFormat {
+    &self.datetime
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `to_string_with` & friends.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// Serialize the given data structure as JSON into the IO stream, as
+/// `config` dictates.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// fail, if `T` contains a map with non-string keys, or if a custom datetime
+/// format is invalid.
+pub fn to_writer_with<W, T>(
+  writer: W,
+  value: &T,
+  config: &SerializeConfig,
+) -> Result<()>
+where
+  W: io::Write,
+  T: ?Sized + Serialize,
+{
+  let mut ser = Serializer::new(writer);
+  ser.set_config(config.clone());
+  value.serialize(&mut ser)
+}
+
+/// Serialize the given data structure as a JSON byte vector, as `config`
+/// dictates.
+///
+/// # Errors
+///
+/// See `to_writer_with`.
+pub fn to_vec_with<T>(value: &T, config: &SerializeConfig) -> Result<Vec<u8>>
+where
+  T: ?Sized + Serialize,
+{
+  let mut writer = Vec::with_capacity(128);
+  to_writer_with(&mut writer, value, config)?;
+  Ok(writer)
+}
+
+/// Serialize the given data structure as a String of JSON, as `config`
+/// dictates.
+///
+/// # Errors
+///
+/// See `to_writer_with`.
+pub fn to_string_with<T>(value: &T, config: &SerializeConfig) -> Result<String>
+where
+  T: ?Sized + Serialize,
+{
+  let writer = to_vec_with(value, config)?;
+  // Only valid UTF-8 is ever written.
+  Ok(String::from_utf8(writer).unwrap_or_default())
+}
@@ -41,11 +41,105 @@ use serde::{
  * +----------------------------------------------------------------------+
 */
 
+/// The default number of nested arrays & objects a `Deserializer` accepts.
+pub const RECURSION_LIMIT: usize = 128;
+
+/// Limits on the size & complexity of JSON input, for parsing payloads from
+/// untrusted clients without letting them exhaust memory or the stack.
+///
+/// Every limit is enforced while parsing, so oversized input fails as soon
+/// as a limit is crossed. Only `max_depth` is bounded by default.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::{json::{self, DeserializeOptions}, DType};
+///
+/// let options = DeserializeOptions::default()
+///   .max_bytes(1 << 20)
+///   .max_depth(16)
+///   .max_array_len(3)
+///   .max_string_len(8);
+///
+/// let ok: DType = json::from_str_with(r#"["a", "b"]"#, &options).unwrap();
+/// assert_eq!(ok, sage::json!(["a", "b"]));
+///
+/// let err = json::from_str_with::<DType>("[1, 2, 3, 4]", &options);
+/// assert!(err.unwrap_err().to_string().starts_with("array exceeds"));
+///
+/// let err = json::from_str_with::<DType>(r#""too long a string""#, &options);
+/// assert!(err.unwrap_err().to_string().starts_with("string exceeds"));
+///
+/// // Readers stop buffering a string once it's too long.
+/// let endless = std::io::repeat(b'a');
+/// let rdr = std::io::Read::chain(&b"\""[..], endless);
+/// let err = json::from_reader_with::<_, DType>(rdr, &options);
+/// assert!(err.unwrap_err().to_string().starts_with("string exceeds"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeserializeOptions {
+  /// Maximum length of the whole input in bytes.
+  pub max_bytes: usize,
+  /// Maximum number of nested arrays & objects.
+  pub max_depth: usize,
+  /// Maximum number of elements in an array.
+  pub max_array_len: usize,
+  /// Maximum number of entries in an object.
+  pub max_object_entries: usize,
+  /// Maximum length of a string or object key in bytes, after unescaping.
+  pub max_string_len: usize,
+}
+
+impl Default for DeserializeOptions {
+  fn default() -> Self {
+    DeserializeOptions {
+      max_bytes: usize::MAX,
+      max_depth: RECURSION_LIMIT,
+      max_array_len: usize::MAX,
+      max_object_entries: usize::MAX,
+      max_string_len: usize::MAX,
+    }
+  }
+}
+
+impl DeserializeOptions {
+  /// Sets the maximum length of the whole input in bytes.
+  pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+    self.max_bytes = max_bytes;
+    self
+  }
+
+  /// Sets the maximum number of nested arrays & objects.
+  pub fn max_depth(mut self, max_depth: usize) -> Self {
+    self.max_depth = max_depth;
+    self
+  }
+
+  /// Sets the maximum number of elements in an array.
+  pub fn max_array_len(mut self, max_array_len: usize) -> Self {
+    self.max_array_len = max_array_len;
+    self
+  }
+
+  /// Sets the maximum number of entries in an object.
+  pub fn max_object_entries(mut self, max_object_entries: usize) -> Self {
+    self.max_object_entries = max_object_entries;
+    self
+  }
+
+  /// Sets the maximum length of a string or object key in bytes.
+  pub fn max_string_len(mut self, max_string_len: usize) -> Self {
+    self.max_string_len = max_string_len;
+    self
+  }
+}
+
 /// A structure that deserializes JSON into Rust values.
 pub struct Deserializer<R> {
   read: R,
   scratch: Vec<u8>,
-  remaining_depth: u8,
+  remaining_depth: usize,
+  options: DeserializeOptions,
   #[cfg(feature = "float_roundtrip")]
   single_precision: bool,
   #[cfg(feature = "unbounded_depth")]
@@ -68,7 +162,8 @@ where
     Deserializer {
       read,
       scratch: Vec::new(),
-      remaining_depth: 128,
+      remaining_depth: RECURSION_LIMIT,
+      options: DeserializeOptions::default(),
       #[cfg(feature = "float_roundtrip")]
       single_precision: false,
       #[cfg(feature = "unbounded_depth")]
@@ -231,6 +326,42 @@ impl<'de, R: Read<'de>> Deserializer<R> {
     }
   }
 
+  /// Sets the number of nested arrays & objects accepted, [`RECURSION_LIMIT`]
+  /// by default. Deeper input fails with a `RecursionLimitExceeded` error at
+  /// the position of the offending bracket, before the stack can overflow.
+  ///
+  /// Should be set before parsing starts.
+  ///
+  /// # Examples
+  ///
+  /// ```rust
+  /// use serde::Deserialize;
+  /// use sage::DType;
+  ///
+  /// let json = format!("{}{}", "[".repeat(16), "]".repeat(16));
+  ///
+  /// let mut deserializer = sage::json::Deserializer::from_str(&json);
+  /// deserializer.set_recursion_limit(8);
+  /// let err = DType::deserialize(&mut deserializer).unwrap_err();
+  /// assert!(err.to_string().starts_with("recursion limit exceeded"));
+  ///
+  /// let mut deserializer = sage::json::Deserializer::from_str(&json);
+  /// deserializer.set_recursion_limit(16);
+  /// assert!(DType::deserialize(&mut deserializer).is_ok());
+  /// ```
+  pub fn set_recursion_limit(&mut self, limit: usize) {
+    self.remaining_depth = limit;
+  }
+
+  /// Enforces the limits of `options` on the input, replacing the recursion
+  /// limit with `options.max_depth`.
+  ///
+  /// Should be set before parsing starts.
+  pub fn set_options(&mut self, options: DeserializeOptions) {
+    self.remaining_depth = options.max_depth;
+    self.options = options;
+  }
+
   /// Parse arbitrarily deep JSON structures without any consideration for
   /// overflowing the stack.
   ///
@@ -262,16 +393,8 @@ impl<'de, R: Read<'de>> Deserializer<R> {
   ///     let deserializer = serde_stacker::Deserializer::new(&mut deserializer);
   ///     let value = DType::deserialize(deserializer).unwrap();
   ///
-  ///     carefully_drop_nested_arrays(value);
-  /// }
-  ///
-  /// fn carefully_drop_nested_arrays(value: DType) {
-  ///     let mut stack = vec![value];
-  ///     while let Some(value) = stack.pop() {
-  ///         if let DType::Array(array) = value {
-  ///             stack.extend(array);
-  ///         }
-  ///     }
+  ///     // `DType` is dropped iteratively, so this doesn't recurse either.
+  ///     drop(value);
   /// }
   /// ```
   #[cfg(feature = "unbounded_depth")]
@@ -323,6 +446,9 @@ impl<'de, R: Read<'de>> Deserializer<R> {
           self.eat_char();
         }
         other => {
+          if self.read.byte_offset() > self.options.max_bytes {
+            return Err(self.peek_error(ErrorCode::InputTooLarge));
+          }
           return Ok(other);
         }
       }
@@ -367,7 +493,10 @@ impl<'de, R: Read<'de>> Deserializer<R> {
       b'"' => {
         self.eat_char();
         self.scratch.clear();
-        match self.read.parse_str(&mut self.scratch) {
+        match self
+          .read
+          .parse_str(&mut self.scratch, self.options.max_string_len)
+        {
           Ok(s) => de::Error::invalid_type(Unexpected::Str(&s), exp),
           Err(err) => return err,
         }
@@ -1393,10 +1522,10 @@ macro_rules! if_checking_recursion_limit {
 macro_rules! check_recursion {
     ($this:ident $($body:tt)*) => {
         if_checking_recursion_limit! {
-            $this.remaining_depth -= 1;
-            if $this.remaining_depth == 0 {
-                return Err($this.peek_error(ErrorCode::RecursionLimitExceeded));
-            }
+            $this.remaining_depth = match $this.remaining_depth.checked_sub(1) {
+                Some(depth) => depth,
+                None => return Err($this.peek_error(ErrorCode::RecursionLimitExceeded)),
+            };
         }
 
         $this $($body)*
@@ -1446,7 +1575,10 @@ impl<'de, 'a, R: Read<'de>> de::Deserializer<'de> for &'a mut Deserializer<R> {
       b'"' => {
         self.eat_char();
         self.scratch.clear();
-        match tri!(self.read.parse_str(&mut self.scratch)) {
+        let string = tri!(self
+          .read
+          .parse_str(&mut self.scratch, self.options.max_string_len));
+        match string {
           Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
           Reference::Copied(s) => visitor.visit_str(s),
         }
@@ -1627,7 +1759,10 @@ impl<'de, 'a, R: Read<'de>> de::Deserializer<'de> for &'a mut Deserializer<R> {
       b'"' => {
         self.eat_char();
         self.scratch.clear();
-        match tri!(self.read.parse_str(&mut self.scratch)) {
+        let string = tri!(self
+          .read
+          .parse_str(&mut self.scratch, self.options.max_string_len));
+        match string {
           Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
           Reference::Copied(s) => visitor.visit_str(s),
         }
@@ -1734,7 +1869,10 @@ impl<'de, 'a, R: Read<'de>> de::Deserializer<'de> for &'a mut Deserializer<R> {
       b'"' => {
         self.eat_char();
         self.scratch.clear();
-        match tri!(self.read.parse_str_raw(&mut self.scratch)) {
+        let bytes = tri!(self
+          .read
+          .parse_str_raw(&mut self.scratch, self.options.max_string_len));
+        match bytes {
           Reference::Borrowed(b) => visitor.visit_borrowed_bytes(b),
           Reference::Copied(b) => visitor.visit_bytes(b),
         }
@@ -2015,11 +2153,26 @@ impl<'de, 'a, R: Read<'de>> de::Deserializer<'de> for &'a mut Deserializer<R> {
 struct SeqAccess<'a, R: 'a> {
   de: &'a mut Deserializer<R>,
   first: bool,
+  len: usize,
 }
 
 impl<'a, R: 'a> SeqAccess<'a, R> {
   fn new(de: &'a mut Deserializer<R>) -> Self {
-    SeqAccess { de, first: true }
+    SeqAccess {
+      de,
+      first: true,
+      len: 0,
+    }
+  }
+}
+
+impl<'de, 'a, R: Read<'de> + 'a> SeqAccess<'a, R> {
+  fn count(&mut self) -> Result<()> {
+    self.len += 1;
+    if self.len > self.de.options.max_array_len {
+      return Err(self.de.peek_error(ErrorCode::ArrayTooLong));
+    }
+    Ok(())
   }
 }
 
@@ -2053,7 +2206,10 @@ impl<'de, 'a, R: Read<'de> + 'a> de::SeqAccess<'de> for SeqAccess<'a, R> {
 
     match peek {
       Some(b']') => Err(self.de.peek_error(ErrorCode::TrailingComma)),
-      Some(_) => Ok(Some(tri!(seed.deserialize(&mut *self.de)))),
+      Some(_) => {
+        tri!(self.count());
+        Ok(Some(tri!(seed.deserialize(&mut *self.de))))
+      }
       None => Err(self.de.peek_error(ErrorCode::EofWhileParsingValue)),
     }
   }
@@ -2062,11 +2218,26 @@ impl<'de, 'a, R: Read<'de> + 'a> de::SeqAccess<'de> for SeqAccess<'a, R> {
 struct MapAccess<'a, R: 'a> {
   de: &'a mut Deserializer<R>,
   first: bool,
+  len: usize,
 }
 
 impl<'a, R: 'a> MapAccess<'a, R> {
   fn new(de: &'a mut Deserializer<R>) -> Self {
-    MapAccess { de, first: true }
+    MapAccess {
+      de,
+      first: true,
+      len: 0,
+    }
+  }
+}
+
+impl<'de, 'a, R: Read<'de> + 'a> MapAccess<'a, R> {
+  fn count(&mut self) -> Result<()> {
+    self.len += 1;
+    if self.len > self.de.options.max_object_entries {
+      return Err(self.de.peek_error(ErrorCode::ObjectTooLarge));
+    }
+    Ok(())
   }
 }
 
@@ -2099,7 +2270,10 @@ impl<'de, 'a, R: Read<'de> + 'a> de::MapAccess<'de> for MapAccess<'a, R> {
     };
 
     match peek {
-      Some(b'"') => seed.deserialize(MapKey { de: &mut *self.de }).map(Some),
+      Some(b'"') => {
+        tri!(self.count());
+        seed.deserialize(MapKey { de: &mut *self.de }).map(Some)
+      }
       Some(b'}') => Err(self.de.peek_error(ErrorCode::TrailingComma)),
       Some(_) => Err(self.de.peek_error(ErrorCode::KeyMustBeAString)),
       None => Err(self.de.peek_error(ErrorCode::EofWhileParsingValue)),
@@ -2258,7 +2432,8 @@ macro_rules! deserialize_integer_key {
     {
       self.de.eat_char();
       self.de.scratch.clear();
-      let string = tri!(self.de.read.parse_str(&mut self.de.scratch));
+      let max_len = self.de.options.max_string_len;
+      let string = tri!(self.de.read.parse_str(&mut self.de.scratch, max_len));
       match (string.parse(), string) {
         (Ok(integer), _) => visitor.$visit(integer),
         (Err(_), Reference::Borrowed(s)) => visitor.visit_borrowed_str(s),
@@ -2281,7 +2456,9 @@ where
   {
     self.de.eat_char();
     self.de.scratch.clear();
-    match tri!(self.de.read.parse_str(&mut self.de.scratch)) {
+    let max_len = self.de.options.max_string_len;
+    let string = tri!(self.de.read.parse_str(&mut self.de.scratch, max_len));
+    match string {
       Reference::Borrowed(s) => visitor.visit_borrowed_str(s),
       Reference::Copied(s) => visitor.visit_str(s),
     }
@@ -2545,6 +2722,22 @@ where
   Ok(value)
 }
 
+/// Deserialize an instance of `T`, enforcing the limits of `options`.
+fn from_trait_with<'de, R, T>(
+  read: R,
+  options: &DeserializeOptions,
+) -> Result<T>
+where
+  R: Read<'de>,
+  T: de::Deserialize<'de>,
+{
+  let mut de = Deserializer::new(read);
+  de.set_options(*options);
+  let value = tri!(de::Deserialize::deserialize(&mut de));
+  tri!(de.end());
+  Ok(value)
+}
+
 /// Deserialize an instance of type `T` from an IO stream of JSON.
 ///
 /// The content of the IO stream is deserialized directly from the stream
@@ -2569,6 +2762,10 @@ where
 /// [`from_slice`]: ./fn.from_slice.html
 /// [issue #160]: https://github.com/serde-rs/json/issues/160
 ///
+/// With the `compression` feature, the input is buffered and gzip &
+/// Zstandard compressed streams are decompressed on the fly, see
+/// `sage::compression::Decoder`.
+///
 /// # Example
 ///
 /// Reading the contents of a file.
@@ -2653,9 +2850,19 @@ where
   R: io::Read,
   T: de::DeserializeOwned,
 {
+  #[cfg(feature = "compression")]
+  let rdr = tri!(decompress(rdr));
   from_trait(read::IoRead::new(rdr))
 }
 
+/// Wraps `rdr` in a `compression::Decoder`, which detects its compression.
+#[cfg(feature = "compression")]
+fn decompress<R: io::Read>(
+  rdr: R,
+) -> Result<crate::compression::Decoder<io::BufReader<R>>> {
+  crate::compression::Decoder::new(io::BufReader::new(rdr)).map_err(Error::io)
+}
+
 /// Deserialize an instance of type `T` from bytes of JSON text.
 ///
 /// # Example
@@ -2682,6 +2889,28 @@ where
 /// }
 /// ```
 ///
+/// # Borrowing
+///
+/// Strings without escape sequences are handed to `T` as slices of `v`, so
+/// fields of type `&'a str` or `&'a [u8]` borrow from the input instead of
+/// allocating.
+///
+/// ```rust
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Blob<'a> {
+///     name: &'a str,
+///     data: &'a [u8],
+/// }
+///
+/// let j = br#"{"name": "logo", "data": "iVBORw"}"#;
+///
+/// let blob: Blob = sage::json::from_slice(j).unwrap();
+/// assert_eq!(blob.name, "logo");
+/// assert_eq!(blob.data, b"iVBORw");
+/// ```
+///
 /// # Errors
 ///
 /// This conversion can fail if the structure of the input does not match the
@@ -2724,6 +2953,30 @@ where
 /// }
 /// ```
 ///
+/// # Borrowing
+///
+/// Strings without escape sequences borrow from `s`. Use `Cow<'a, str>`
+/// with `#[serde(borrow)]` for fields that may contain escapes, which are
+/// only allocated when they do.
+///
+/// ```rust
+/// use std::borrow::Cow;
+/// use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct User<'a> {
+///     fingerprint: &'a str,
+///     #[serde(borrow)]
+///     location: Cow<'a, str>,
+/// }
+///
+/// let j = r#"{"fingerprint": "0xF9BA", "location": "Menlo\tPark"}"#;
+///
+/// let u: User = sage::json::from_str(j).unwrap();
+/// assert_eq!(u.fingerprint, "0xF9BA");
+/// assert!(matches!(u.location, Cow::Owned(_)));
+/// ```
+///
 /// # Errors
 ///
 /// This conversion can fail if the structure of the input does not match the
@@ -2739,3 +2992,75 @@ where
 {
   from_trait(read::StrRead::new(s))
 }
+
+/// Deserialize an instance of type `T` from an IO stream of JSON, enforcing
+/// the limits of `options`.
+///
+/// No more than `options.max_bytes` (plus one) bytes are read from `rdr`,
+/// after decompression with the `compression` feature.
+///
+/// # Errors
+///
+/// Fails like [`from_reader`], or if the input crosses one of the limits.
+pub fn from_reader_with<R, T>(rdr: R, options: &DeserializeOptions) -> Result<T>
+where
+  R: io::Read,
+  T: de::DeserializeOwned,
+{
+  #[cfg(feature = "compression")]
+  let rdr = tri!(decompress(rdr));
+  let limit = options.max_bytes.saturating_add(1) as u64;
+  let mut de = Deserializer::new(read::IoRead::new(io::Read::take(rdr, limit)));
+  de.set_options(*options);
+  let value = de::Deserialize::deserialize(&mut de).and_then(|value| {
+    tri!(de.end());
+    Ok(value)
+  });
+  // Input cut short by `take` reports EOF, report the limit instead.
+  match value {
+    Err(_) if de.read.byte_offset() > options.max_bytes => {
+      Err(de.error(ErrorCode::InputTooLarge))
+    }
+    value => value,
+  }
+}
+
+/// Deserialize an instance of type `T` from bytes of JSON text, enforcing the
+/// limits of `options`.
+///
+/// # Errors
+///
+/// Fails like [`from_slice`], or if the input crosses one of the limits.
+pub fn from_slice_with<'a, T>(
+  v: &'a [u8],
+  options: &DeserializeOptions,
+) -> Result<T>
+where
+  T: de::Deserialize<'a>,
+{
+  if v.len() > options.max_bytes {
+    return Err(Error::syntax(ErrorCode::InputTooLarge, 0, 0));
+  }
+  from_trait_with(read::SliceRead::new(v), options)
+}
+
+/// Deserialize an instance of type `T` from a string of JSON text, enforcing
+/// the limits of `options`.
+///
+/// See [`DeserializeOptions`] for an example.
+///
+/// # Errors
+///
+/// Fails like [`from_str`], or if the input crosses one of the limits.
+pub fn from_str_with<'a, T>(
+  s: &'a str,
+  options: &DeserializeOptions,
+) -> Result<T>
+where
+  T: de::Deserialize<'a>,
+{
+  if s.len() > options.max_bytes {
+    return Err(Error::syntax(ErrorCode::InputTooLarge, 0, 0));
+  }
+  from_trait_with(read::StrRead::new(s), options)
+}
@@ -0,0 +1,361 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A lazily parsed JSON document.
+
+use std::{borrow::Cow, cell::OnceCell, fmt};
+
+use serde::de::Deserialize;
+
+use crate::{datastore::json, DType, Result};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `LazyDType`.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// `LazyDType` is a JSON value which is only parsed as far as it's read.
+///
+/// Creating one costs nothing. The first lookup into an array or object
+/// indexes that value alone: the raw text of each element, or the key &
+/// raw text of each entry, is located by skipping over nested values
+/// without parsing them. Those children are `LazyDType`s themselves, so
+/// reading a few fields of a large document only ever indexes the arrays
+/// & objects on the way to them. Values are parsed into Rust types with
+/// `LazyDType::deserialize` or `LazyDType::to_dtype`.
+///
+/// Input isn't validated up front: lookups into malformed arrays & objects
+/// find nothing, and parsing a malformed value fails.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::json;
+/// use sage::json::LazyDType;
+///
+/// let text = r#"{
+///   "meta": { "count": 2, "next": null },
+///   "results": [
+///     { "id": 1, "name": "Ada", "bio": "..." },
+///     { "id": 2, "name": "Alan", "bio": "..." }
+///   ]
+/// }"#;
+/// let doc = LazyDType::new(text);
+///
+/// let results = doc.get("results").unwrap();
+/// assert!(results.is_array());
+/// assert_eq!(results.len(), Some(2));
+///
+/// let name = results.pointer("/1/name").unwrap();
+/// assert_eq!(name.deserialize::<&str>().unwrap(), "Alan");
+///
+/// let meta = doc.get("meta").unwrap();
+/// assert_eq!(meta.raw(), r#"{ "count": 2, "next": null }"#);
+/// assert_eq!(meta.to_dtype().unwrap(), json!({ "count": 2, "next": null }));
+/// ```
+pub struct LazyDType<'a> {
+  raw: &'a str,
+  children: OnceCell<Children<'a>>,
+}
+
+/// The located children of an array or object.
+enum Children<'a> {
+  Array(Vec<LazyDType<'a>>),
+  Object(Vec<(Cow<'a, str>, LazyDType<'a>)>),
+  /// Scalars & malformed values.
+  None,
+}
+
+impl<'a> LazyDType<'a> {
+  /// Wraps the JSON text `raw`, without parsing it.
+  pub fn new(raw: &'a str) -> LazyDType<'a> {
+    LazyDType {
+      raw: raw.trim_matches(is_whitespace),
+      children: OnceCell::new(),
+    }
+  }
+
+  /// Returns the raw JSON text of the value.
+  pub fn raw(&self) -> &'a str {
+    self.raw
+  }
+
+  /// Returns true if the value is an array.
+  pub fn is_array(&self) -> bool {
+    self.raw.starts_with('[')
+  }
+
+  /// Returns true if the value is an object.
+  pub fn is_object(&self) -> bool {
+    self.raw.starts_with('{')
+  }
+
+  /// Returns the number of elements of an array or entries of an object,
+  /// `None` for other values.
+  pub fn len(&self) -> Option<usize> {
+    match self.children() {
+      Children::Array(values) => Some(values.len()),
+      Children::Object(entries) => Some(entries.len()),
+      Children::None => None,
+    }
+  }
+
+  /// Returns true if an array or object has no children, `None` for other
+  /// values.
+  pub fn is_empty(&self) -> Option<bool> {
+    self.len().map(|len| len == 0)
+  }
+
+  /// Returns the `key` entry of an object. Like `DType::deserialize`, the
+  /// last of duplicate keys wins.
+  pub fn get(&self, key: &str) -> Option<&LazyDType<'a>> {
+    match self.children() {
+      Children::Object(entries) => entries
+        .iter()
+        .rev()
+        .find(|(k, _)| k == key)
+        .map(|(_, value)| value),
+      _ => None,
+    }
+  }
+
+  /// Returns the element at `index` of an array.
+  pub fn get_index(&self, index: usize) -> Option<&LazyDType<'a>> {
+    match self.children() {
+      Children::Array(values) => values.get(index),
+      _ => None,
+    }
+  }
+
+  /// Returns the keys of an object, in input order.
+  pub fn keys(&self) -> Vec<&str> {
+    match self.children() {
+      Children::Object(entries) => {
+        entries.iter().map(|(key, _)| key.as_ref()).collect()
+      }
+      _ => Vec::new(),
+    }
+  }
+
+  /// Looks up a value by a JSON Pointer, like `DType::pointer`.
+  pub fn pointer(&self, pointer: &str) -> Option<&LazyDType<'a>> {
+    if pointer.is_empty() {
+      return Some(self);
+    }
+    if !pointer.starts_with('/') {
+      return None;
+    }
+    pointer
+      .split('/')
+      .skip(1)
+      .map(|x| x.replace("~1", "/").replace("~0", "~"))
+      .try_fold(self, |target, token| match target.children() {
+        Children::Object(_) => target.get(&token),
+        Children::Array(_) => {
+          DType::parse_index(&token).and_then(|i| target.get_index(i))
+        }
+        Children::None => None,
+      })
+  }
+
+  /// Parses the value into `T`, borrowing from the input where `T` can.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the value is malformed or doesn't match `T`.
+  pub fn deserialize<T>(&self) -> Result<T>
+  where
+    T: Deserialize<'a>,
+  {
+    json::from_str(self.raw)
+  }
+
+  /// Parses the whole value into a `DType`.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the value is malformed.
+  pub fn to_dtype(&self) -> Result<DType> {
+    self.deserialize()
+  }
+
+  fn children(&self) -> &Children<'a> {
+    self.children.get_or_init(|| {
+      let bytes = self.raw.as_bytes();
+      let children = match bytes.first() {
+        Some(b'[') => index_array(self.raw).map(Children::Array),
+        Some(b'{') => index_object(self.raw).map(Children::Object),
+        _ => None,
+      };
+      children.unwrap_or(Children::None)
+    })
+  }
+}
+
+impl<'a> Clone for LazyDType<'a> {
+  /// Clones the raw text only, the clone indexes itself again.
+  fn clone(&self) -> Self {
+    LazyDType::new(self.raw)
+  }
+}
+
+impl<'a> fmt::Debug for LazyDType<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_tuple("LazyDType").field(&self.raw).finish()
+  }
+}
+
+impl<'a> fmt::Display for LazyDType<'a> {
+  /// Writes the raw JSON text.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(self.raw)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Indexing.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+fn is_whitespace(c: char) -> bool {
+  matches!(c, ' ' | '\n' | '\t' | '\r')
+}
+
+fn skip_whitespace(bytes: &[u8], mut at: usize) -> usize {
+  while matches!(bytes.get(at), Some(b' ' | b'\n' | b'\t' | b'\r')) {
+    at += 1;
+  }
+  at
+}
+
+/// Returns the end of the string starting at `at`.
+fn skip_string(bytes: &[u8], mut at: usize) -> Option<usize> {
+  at += 1;
+  loop {
+    match *bytes.get(at)? {
+      b'"' => return Some(at + 1),
+      b'\\' => at += 2,
+      _ => at += 1,
+    }
+  }
+}
+
+/// Returns the end of the value starting at `at`, matching brackets but
+/// otherwise not checking its syntax.
+fn skip_value(bytes: &[u8], mut at: usize) -> Option<usize> {
+  match *bytes.get(at)? {
+    b'"' => skip_string(bytes, at),
+    b'[' | b'{' => {
+      let mut depth = 0_usize;
+      loop {
+        match *bytes.get(at)? {
+          b'"' => {
+            at = skip_string(bytes, at)?;
+            continue;
+          }
+          b'[' | b'{' => depth += 1,
+          b']' | b'}' => {
+            depth -= 1;
+            if depth == 0 {
+              return Some(at + 1);
+            }
+          }
+          _ => {}
+        }
+        at += 1;
+      }
+    }
+    _ => {
+      let start = at;
+      while !matches!(
+        bytes.get(at),
+        None | Some(b',' | b']' | b'}' | b' ' | b'\n' | b'\t' | b'\r')
+      ) {
+        at += 1;
+      }
+      (at > start).then_some(at)
+    }
+  }
+}
+
+/// Locates the next value, from `at`, followed by `,` or the closing
+/// bracket `close`. Returns its span & whether more values follow.
+fn next_value(
+  bytes: &[u8],
+  at: usize,
+  close: u8,
+) -> Option<((usize, usize), bool)> {
+  let start = skip_whitespace(bytes, at);
+  let end = skip_value(bytes, start)?;
+  let after = skip_whitespace(bytes, end);
+  match *bytes.get(after)? {
+    b',' => Some(((start, end), true)),
+    b if b == close && after + 1 == bytes.len() => Some(((start, end), false)),
+    _ => None,
+  }
+}
+
+fn index_array(raw: &str) -> Option<Vec<LazyDType<'_>>> {
+  let bytes = raw.as_bytes();
+  let mut values = Vec::new();
+  let mut at = skip_whitespace(bytes, 1);
+  if bytes.get(at) == Some(&b']') {
+    return (at + 1 == bytes.len()).then_some(values);
+  }
+  loop {
+    let ((start, end), more) = next_value(bytes, at, b']')?;
+    values.push(LazyDType::new(&raw[start..end]));
+    if !more {
+      return Some(values);
+    }
+    at = skip_whitespace(bytes, end) + 1;
+  }
+}
+
+fn index_object(raw: &str) -> Option<Vec<(Cow<'_, str>, LazyDType<'_>)>> {
+  let bytes = raw.as_bytes();
+  let mut entries = Vec::new();
+  let mut at = skip_whitespace(bytes, 1);
+  if bytes.get(at) == Some(&b'}') {
+    return (at + 1 == bytes.len()).then_some(entries);
+  }
+  loop {
+    if bytes.get(at) != Some(&b'"') {
+      return None;
+    }
+    let key_end = skip_string(bytes, at)?;
+    let key = &raw[at..key_end];
+    let key = if key.contains('\\') {
+      Cow::Owned(json::from_str::<String>(key).ok()?)
+    } else {
+      Cow::Borrowed(&key[1..key.len() - 1])
+    };
+    at = skip_whitespace(bytes, key_end);
+    if bytes.get(at) != Some(&b':') {
+      return None;
+    }
+    let ((start, end), more) = next_value(bytes, at + 1, b'}')?;
+    entries.push((key, LazyDType::new(&raw[start..end])));
+    if !more {
+      return Some(entries);
+    }
+    at = skip_whitespace(bytes, skip_whitespace(bytes, end) + 1);
+  }
+}
@@ -0,0 +1,192 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+  datastore::json::de,
+  error::{Error, ErrorCode},
+  Result,
+};
+
+/// Deserialize an instance of type `T` from a string of JSON which may use
+/// the JSON5/JSONC extensions common in hand-authored files:
+///
+/// - `// line` and `/* block */` comments,
+/// - trailing commas in arrays & objects,
+/// - unquoted object keys (identifiers such as `name` or `$id`),
+/// - single quoted strings.
+///
+/// The input is rewritten into strict JSON (keeping its line breaks, so
+/// error lines still match) and parsed with `from_str`.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::{json, DType};
+///
+/// let context: DType = json::from_str_lenient(
+///   r#"{
+///     // Shorthands for schema.org terms.
+///     '@vocab': 'https://schema.org/',
+///     name: "name", /* block comments work too */
+///     knows: {'@type': '@id',},
+///   }"#,
+/// )
+/// .unwrap();
+///
+/// assert_eq!(context["@vocab"], "https://schema.org/");
+/// assert_eq!(context["knows"]["@type"], "@id");
+/// assert!(json::from_str_lenient::<DType>("{ /* unterminated }").is_err());
+/// ```
+///
+/// # Errors
+///
+/// Fails like `from_str`, or if a comment or string is never terminated.
+pub fn from_str_lenient<T>(s: &str) -> Result<T>
+where
+  T: DeserializeOwned,
+{
+  de::from_str(&to_strict(s)?)
+}
+
+/// Rewrites lenient JSON into strict JSON.
+fn to_strict(input: &str) -> Result<String> {
+  let chars: Vec<char> = input.chars().collect();
+  let mut out = String::with_capacity(input.len());
+  let mut pos = 0;
+  let mut line = 1;
+  let mut column = 0;
+  // A comma which is only written if a value follows it.
+  let mut comma = false;
+
+  macro_rules! next {
+    () => {{
+      let c = chars[pos];
+      pos += 1;
+      if c == '\n' {
+        line += 1;
+        column = 0;
+      } else {
+        column += 1;
+      }
+      c
+    }};
+  }
+
+  while pos < chars.len() {
+    let c = chars[pos];
+    match c {
+      '/' if chars.get(pos + 1) == Some(&'/') => {
+        while pos < chars.len() && chars[pos] != '\n' {
+          next!();
+        }
+        continue;
+      }
+      '/' if chars.get(pos + 1) == Some(&'*') => {
+        next!();
+        next!();
+        loop {
+          if pos >= chars.len() {
+            return Err(Error::syntax(
+              ErrorCode::EofWhileParsingValue,
+              line,
+              column,
+            ));
+          }
+          if chars[pos] == '*' && chars.get(pos + 1) == Some(&'/') {
+            next!();
+            next!();
+            break;
+          }
+          if next!() == '\n' {
+            out.push('\n');
+          }
+        }
+        continue;
+      }
+      c if c.is_whitespace() => {
+        out.push(next!());
+        continue;
+      }
+      _ => {}
+    }
+
+    // `c` starts a token: write the pending comma unless it closes a
+    // container.
+    if comma && c != ']' && c != '}' {
+      out.push(',');
+    }
+    comma = false;
+
+    match c {
+      ',' => {
+        next!();
+        comma = true;
+      }
+      '"' | '\'' => {
+        let quote = next!();
+        out.push('"');
+        loop {
+          if pos >= chars.len() {
+            return Err(Error::syntax(
+              ErrorCode::EofWhileParsingString,
+              line,
+              column,
+            ));
+          }
+          match next!() {
+            c if c == quote => break,
+            '\\' if pos < chars.len() => match next!() {
+              '\'' => out.push('\''),
+              escaped => {
+                out.push('\\');
+                out.push(escaped);
+              }
+            },
+            '"' => out.push_str("\\\""),
+            c => out.push(c),
+          }
+        }
+        out.push('"');
+      }
+      c if c.is_alphabetic() || c == '_' || c == '$' => {
+        let start = pos;
+        while pos < chars.len()
+          && (chars[pos].is_alphanumeric() || matches!(chars[pos], '_' | '$'))
+        {
+          next!();
+        }
+        let word: String = chars[start..pos].iter().collect();
+        let mut ahead = pos;
+        while ahead < chars.len() && chars[ahead].is_whitespace() {
+          ahead += 1;
+        }
+        if chars.get(ahead) == Some(&':') {
+          out.push('"');
+          out.push_str(&word);
+          out.push('"');
+        } else {
+          out.push_str(&word);
+        }
+      }
+      _ => out.push(next!()),
+    }
+  }
+
+  if comma {
+    out.push(',');
+  }
+  Ok(out)
+}
@@ -0,0 +1,112 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serialization of large top-level arrays over several threads.
+
+use std::{io, thread};
+
+use crate::{datastore::json, dtype::DType, error::Error, Result};
+
+/// Number of elements each thread serializes per round.
+const CHUNK_LEN: usize = 4096;
+
+/// Serialize `value` as JSON into the IO stream, serializing the elements
+/// of a top-level array on `threads` threads.
+///
+/// The elements are split into rounds of `threads` chunks. The chunks of a
+/// round are serialized concurrently into buffers, which are then written
+/// in order, so at most a round of output is held in memory. The output is
+/// the same as `to_writer`'s. Other values, or a single thread, are
+/// serialized by `to_writer` directly.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::{json, DType};
+///
+/// let rows: Vec<DType> = (0..10_000)
+///   .map(|i| json!({ "id": i, "name": format!("node-{}", i) }))
+///   .collect();
+/// let value = DType::from(rows);
+///
+/// let bytes = json::to_vec_parallel(&value, 4).unwrap();
+/// assert_eq!(bytes, json::to_vec(&value).unwrap());
+/// ```
+///
+/// # Errors
+///
+/// Fails if an element can't be serialized, or writing fails.
+pub fn to_writer_parallel<W>(
+  mut writer: W,
+  value: &DType,
+  threads: usize,
+) -> Result<()>
+where
+  W: io::Write,
+{
+  let values = match value {
+    DType::Array(values) if threads > 1 => values,
+    value => return json::to_writer(writer, value),
+  };
+
+  writer.write_all(b"[").map_err(Error::io)?;
+  let mut first = true;
+  for round in values.chunks(CHUNK_LEN * threads) {
+    let chunk = round.len().div_ceil(threads);
+    let buffers: Vec<Result<Vec<u8>>> = thread::scope(|scope| {
+      let handles: Vec<_> = round
+        .chunks(chunk)
+        .map(|values| scope.spawn(move || serialize_chunk(values)))
+        .collect();
+      handles
+        .into_iter()
+        .map(|handle| handle.join().expect("serialization thread panicked"))
+        .collect()
+    });
+
+    for buffer in buffers {
+      let buffer = tri!(buffer);
+      if !first {
+        writer.write_all(b",").map_err(Error::io)?;
+      }
+      first = false;
+      writer.write_all(&buffer).map_err(Error::io)?;
+    }
+  }
+  writer.write_all(b"]").map_err(Error::io)
+}
+
+/// Serialize `value` as a JSON byte vector, serializing the elements of a
+/// top-level array on `threads` threads.
+///
+/// # Errors
+///
+/// See `to_writer_parallel`.
+pub fn to_vec_parallel(value: &DType, threads: usize) -> Result<Vec<u8>> {
+  let mut writer = Vec::with_capacity(128);
+  to_writer_parallel(&mut writer, value, threads)?;
+  Ok(writer)
+}
+
+/// Serializes `values` separated by commas, without brackets.
+fn serialize_chunk(values: &[DType]) -> Result<Vec<u8>> {
+  let mut buffer = Vec::with_capacity(128 * values.len());
+  for (i, value) in values.iter().enumerate() {
+    if i > 0 {
+      buffer.push(b',');
+    }
+    tri!(json::to_writer(&mut buffer, value));
+  }
+  Ok(buffer)
+}
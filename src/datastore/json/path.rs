@@ -0,0 +1,233 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Partial deserialization of the value a JSON Pointer addresses.
+
+use std::{fmt, io, marker::PhantomData};
+
+use serde::de::{
+  self, Deserialize, DeserializeOwned, DeserializeSeed, Deserializer,
+  IgnoredAny, MapAccess, SeqAccess, Visitor,
+};
+
+use crate::{datastore::json, DType, Result};
+
+/// Deserializes the value `pointer` addresses in the JSON stream `reader`,
+/// or `None` if there's no such value.
+///
+/// Everything outside the addressed subtree is scanned & checked for syntax
+/// errors without being deserialized, so only the subtree is ever held in
+/// memory. `pointer` is a JSON Pointer like the ones `DType::pointer`
+/// takes, e.g. `/results/0/entity`; the empty pointer addresses the whole
+/// document. Malformed pointers address nothing.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::{json, DType};
+///
+/// let response = r#"{
+///   "count": 2,
+///   "results": [
+///     { "entity": { "name": "Ada" }, "score": 0.9 },
+///     { "entity": { "name": "Alan" }, "score": 0.7 }
+///   ]
+/// }"#;
+///
+/// let entity: Option<DType> =
+///   json::get_path(response.as_bytes(), "/results/1/entity").unwrap();
+/// assert_eq!(entity, Some(json!({ "name": "Alan" })));
+///
+/// let count = json::get_path::<_, u32>(response.as_bytes(), "/count");
+/// assert_eq!(count.unwrap(), Some(2));
+///
+/// let missing: Option<DType> =
+///   json::get_path(response.as_bytes(), "/results/7").unwrap();
+/// assert_eq!(missing, None);
+/// ```
+///
+/// # Errors
+///
+/// Fails if the stream isn't valid JSON, or if the addressed value can't be
+/// deserialized into `T`.
+pub fn get_path<R, T>(reader: R, pointer: &str) -> Result<Option<T>>
+where
+  R: io::Read,
+  T: DeserializeOwned,
+{
+  let tokens: Vec<String> = match pointer {
+    "" => Vec::new(),
+    pointer if pointer.starts_with('/') => pointer
+      .split('/')
+      .skip(1)
+      .map(|x| x.replace("~1", "/").replace("~0", "~"))
+      .collect(),
+    _ => return Ok(None),
+  };
+
+  let mut de = json::Deserializer::from_reader(reader);
+  let value = tri!(Path::new(&tokens).deserialize(&mut de));
+
+  // Make sure the whole stream has been consumed.
+  tri!(de.end());
+  Ok(value)
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `Path` - seed following the remaining reference tokens.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+struct Path<'p, T> {
+  tokens: &'p [String],
+  marker: PhantomData<T>,
+}
+
+impl<'p, T> Path<'p, T> {
+  fn new(tokens: &'p [String]) -> Self {
+    Path {
+      tokens,
+      marker: PhantomData,
+    }
+  }
+}
+
+impl<'de, 'p, T> DeserializeSeed<'de> for Path<'p, T>
+where
+  T: Deserialize<'de>,
+{
+  type Value = Option<T>;
+
+  fn deserialize<D>(self, deserializer: D) -> Result<Option<T>, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    match self.tokens.split_first() {
+      None => T::deserialize(deserializer).map(Some),
+      Some((token, rest)) => deserializer.deserialize_any(Step {
+        token,
+        rest: Path::new(rest),
+      }),
+    }
+  }
+}
+
+/// Looks `token` up in the current array or object, skipping every other
+/// element or entry.
+struct Step<'p, T> {
+  token: &'p str,
+  rest: Path<'p, T>,
+}
+
+macro_rules! step_into_scalar {
+  ($($visit:ident($ty:ty),)*) => {
+    $(
+      fn $visit<E>(self, _: $ty) -> Result<Option<T>, E> {
+        Ok(None)
+      }
+    )*
+  };
+}
+
+impl<'de, 'p, T> Visitor<'de> for Step<'p, T>
+where
+  T: Deserialize<'de>,
+{
+  type Value = Option<T>;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("any JSON value")
+  }
+
+  // Scalars have no children to address.
+  step_into_scalar! {
+    visit_bool(bool),
+    visit_i64(i64),
+    visit_u64(u64),
+    visit_f64(f64),
+    visit_str(&str),
+    visit_bytes(&[u8]),
+  }
+
+  fn visit_unit<E>(self) -> Result<Option<T>, E> {
+    Ok(None)
+  }
+
+  fn visit_seq<A>(self, mut seq: A) -> Result<Option<T>, A::Error>
+  where
+    A: SeqAccess<'de>,
+  {
+    let index = DType::parse_index(self.token);
+    let mut found = None;
+    for i in 0.. {
+      if index == Some(i) {
+        match tri!(seq.next_element_seed(Path::new(self.rest.tokens))) {
+          Some(value) => found = value,
+          None => break,
+        }
+      } else if tri!(seq.next_element::<IgnoredAny>()).is_none() {
+        break;
+      }
+    }
+    Ok(found)
+  }
+
+  fn visit_map<A>(self, mut map: A) -> Result<Option<T>, A::Error>
+  where
+    A: MapAccess<'de>,
+  {
+    // Like `DType::deserialize`, the last of duplicate keys wins.
+    let mut found = None;
+    while let Some(matches) = tri!(map.next_key_seed(KeyIs(self.token))) {
+      if matches {
+        found = tri!(map.next_value_seed(Path::new(self.rest.tokens)));
+      } else {
+        tri!(map.next_value::<IgnoredAny>());
+      }
+    }
+    Ok(found)
+  }
+}
+
+/// Compares an object key with `0`, without allocating it.
+struct KeyIs<'p>(&'p str);
+
+impl<'de, 'p> DeserializeSeed<'de> for KeyIs<'p> {
+  type Value = bool;
+
+  fn deserialize<D>(self, deserializer: D) -> Result<bool, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    deserializer.deserialize_str(self)
+  }
+}
+
+impl<'de, 'p> Visitor<'de> for KeyIs<'p> {
+  type Value = bool;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("an object key")
+  }
+
+  fn visit_str<E>(self, key: &str) -> Result<bool, E>
+  where
+    E: de::Error,
+  {
+    Ok(key == self.0)
+  }
+}
@@ -0,0 +1,234 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+
+use serde::ser::Serialize;
+
+use crate::{datastore::json::ser, dtype::DType, error::Error, Result};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `PrettyConfig`.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// `PrettyConfig` tunes the output of `to_string_pretty_with` & friends, so
+/// emitted JSON can match a downstream style guide.
+///
+/// The default configuration produces the same output as
+/// `to_string_pretty`.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::json::{self, PrettyConfig};
+///
+/// let value = sage::json!({ "name": "sage", "tags": ["rust", "graph"] });
+///
+/// let config = PrettyConfig::new()
+///   .indent("    ")
+///   .inline_arrays(4, 40)
+///   .trailing_newline(true);
+/// let text = json::to_string_pretty_with(&value, &config).unwrap();
+/// assert_eq!(
+///   text,
+///   "{\n    \"name\": \"sage\",\n    \"tags\": [\"rust\", \"graph\"]\n}\n"
+/// );
+///
+/// let default = json::to_string_pretty_with(&value, &PrettyConfig::new());
+/// assert_eq!(default.unwrap(), json::to_string_pretty(&value).unwrap());
+/// ```
+#[derive(Clone, Debug)]
+pub struct PrettyConfig {
+  indent: String,
+  inline_len: usize,
+  inline_width: usize,
+  sort_keys: bool,
+  trailing_newline: bool,
+}
+
+impl Default for PrettyConfig {
+  fn default() -> Self {
+    PrettyConfig {
+      indent: "  ".to_string(),
+      inline_len: 0,
+      inline_width: 0,
+      sort_keys: false,
+      trailing_newline: false,
+    }
+  }
+}
+
+impl PrettyConfig {
+  /// Creates the configuration of `to_string_pretty`: two space indents,
+  /// one array element per line, keys in map order & no trailing newline.
+  pub fn new() -> PrettyConfig {
+    PrettyConfig::default()
+  }
+
+  /// Sets the string written once per nesting level, e.g. `"\t"`.
+  pub fn indent(mut self, indent: &str) -> Self {
+    self.indent = indent.to_string();
+    self
+  }
+
+  /// Writes arrays of at most `len` scalars on a single line (`[1, 2, 3]`)
+  /// as long as that line fits in `width` bytes, not counting indentation.
+  pub fn inline_arrays(mut self, len: usize, width: usize) -> Self {
+    self.inline_len = len;
+    self.inline_width = width;
+    self
+  }
+
+  /// Sets whether object keys are written in lexicographic order rather
+  /// than in map order, which differs with the `preserve_order` feature.
+  pub fn sort_keys(mut self, sort_keys: bool) -> Self {
+    self.sort_keys = sort_keys;
+    self
+  }
+
+  /// Sets whether the output ends with a newline, as most text files do.
+  pub fn trailing_newline(mut self, trailing_newline: bool) -> Self {
+    self.trailing_newline = trailing_newline;
+    self
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `to_string_pretty_with` & friends.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// Serialize the given data structure as JSON into the IO stream,
+/// pretty-printed as `config` dictates.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// fail, or if `T` contains a map with non-string keys.
+pub fn to_writer_pretty_with<W, T>(
+  mut writer: W,
+  value: &T,
+  config: &PrettyConfig,
+) -> Result<()>
+where
+  W: io::Write,
+  T: ?Sized + Serialize,
+{
+  let value = crate::dtype::to_dtype(value)?;
+  let mut out = String::with_capacity(128);
+  write(&mut out, &value, 0, config)?;
+  if config.trailing_newline {
+    out.push('\n');
+  }
+  writer.write_all(out.as_bytes()).map_err(Error::io)
+}
+
+/// Serialize the given data structure as a String of JSON, pretty-printed
+/// as `config` dictates.
+///
+/// # Errors
+///
+/// Serialization can fail if `T`'s implementation of `Serialize` decides to
+/// fail, or if `T` contains a map with non-string keys.
+pub fn to_string_pretty_with<T>(
+  value: &T,
+  config: &PrettyConfig,
+) -> Result<String>
+where
+  T: ?Sized + Serialize,
+{
+  let mut writer = Vec::with_capacity(128);
+  to_writer_pretty_with(&mut writer, value, config)?;
+  // Only valid UTF-8 is ever written.
+  Ok(String::from_utf8(writer).unwrap_or_default())
+}
+
+fn write(
+  out: &mut String,
+  value: &DType,
+  depth: usize,
+  config: &PrettyConfig,
+) -> Result<()> {
+  match value {
+    DType::Array(values) if values.is_empty() => out.push_str("[]"),
+    DType::Array(values) => {
+      if let Some(line) = inline(values, config)? {
+        out.push_str(&line);
+        return Ok(());
+      }
+      out.push('[');
+      for (i, value) in values.iter().enumerate() {
+        out.push_str(if i == 0 { "\n" } else { ",\n" });
+        indent(out, depth + 1, config);
+        write(out, value, depth + 1, config)?;
+      }
+      out.push('\n');
+      indent(out, depth, config);
+      out.push(']');
+    }
+    DType::Object(map) if map.is_empty() => out.push_str("{}"),
+    DType::Object(map) => {
+      let mut entries: Vec<_> = map.iter().collect();
+      if config.sort_keys {
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+      }
+      out.push('{');
+      for (i, (key, value)) in entries.into_iter().enumerate() {
+        out.push_str(if i == 0 { "\n" } else { ",\n" });
+        indent(out, depth + 1, config);
+        out.push_str(&ser::to_string(key)?);
+        out.push_str(": ");
+        write(out, value, depth + 1, config)?;
+      }
+      out.push('\n');
+      indent(out, depth, config);
+      out.push('}');
+    }
+    scalar => out.push_str(&ser::to_string(scalar)?),
+  }
+  Ok(())
+}
+
+/// Returns `values` written on a single line, if `config` allows it.
+fn inline(values: &[DType], config: &PrettyConfig) -> Result<Option<String>> {
+  let scalars = values
+    .iter()
+    .all(|v| !matches!(v, DType::Array(_) | DType::Object(_)));
+  if !scalars || values.len() > config.inline_len {
+    return Ok(None);
+  }
+
+  let mut line = String::from("[");
+  for (i, value) in values.iter().enumerate() {
+    if i > 0 {
+      line.push_str(", ");
+    }
+    line.push_str(&ser::to_string(value)?);
+  }
+  line.push(']');
+  Ok((line.len() <= config.inline_width).then_some(line))
+}
+
+fn indent(out: &mut String, depth: usize, config: &PrettyConfig) {
+  for _ in 0..depth {
+    out.push_str(&config.indent);
+  }
+}
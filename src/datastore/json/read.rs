@@ -75,10 +75,14 @@ pub trait Read<'de>: private::Sealed {
   /// Assumes the previous byte was a quotation mark. Parses a JSON-escaped
   /// string until the next quotation mark using the given scratch space if
   /// necessary. The scratch space is initially empty.
+  ///
+  /// Fails as soon as the string grows past `max_len` bytes, before
+  /// buffering any more of it.
   #[doc(hidden)]
   fn parse_str<'s>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
   ) -> Result<Reference<'de, 's, str>>;
 
   /// Assumes the previous byte was a quotation mark. Parses a JSON-escaped
@@ -86,11 +90,13 @@ pub trait Read<'de>: private::Sealed {
   /// necessary. The scratch space is initially empty.
   ///
   /// This function returns the raw bytes in the string with escape sequences
-  /// expanded but without performing unicode validation.
+  /// expanded but without performing unicode validation. Fails past
+  /// `max_len` bytes like `parse_str`.
   #[doc(hidden)]
   fn parse_str_raw<'s>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
   ) -> Result<Reference<'de, 's, [u8]>>;
 
   /// Assumes the previous byte was a quotation mark. Parses a JSON-escaped
@@ -260,6 +266,7 @@ where
   fn parse_str_bytes<'s, T, F>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
     validate: bool,
     result: F,
   ) -> Result<T>
@@ -268,6 +275,9 @@ where
     F: FnOnce(&'s Self, &'s [u8]) -> Result<T>,
   {
     loop {
+      if scratch.len() > max_len {
+        return error(self, ErrorCode::StringTooLong);
+      }
       let ch = tri!(next_or_eof(self));
       if !ESCAPE[ch as usize] {
         scratch.push(ch);
@@ -376,18 +386,20 @@ where
   fn parse_str<'s>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
   ) -> Result<Reference<'de, 's, str>> {
     self
-      .parse_str_bytes(scratch, true, as_str)
+      .parse_str_bytes(scratch, max_len, true, as_str)
       .map(Reference::Copied)
   }
 
   fn parse_str_raw<'s>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
   ) -> Result<Reference<'de, 's, [u8]>> {
     self
-      .parse_str_bytes(scratch, false, |_, bytes| Ok(bytes))
+      .parse_str_bytes(scratch, max_len, false, |_, bytes| Ok(bytes))
       .map(Reference::Copied)
   }
 
@@ -494,6 +506,7 @@ impl<'a> SliceRead<'a> {
   fn parse_str_bytes<'s, T, F>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
     validate: bool,
     result: F,
   ) -> Result<Reference<'a, 's, T>>
@@ -510,6 +523,9 @@ impl<'a> SliceRead<'a> {
       {
         self.index += 1;
       }
+      if scratch.len() + (self.index - start) > max_len {
+        return error(self, ErrorCode::StringTooLong);
+      }
       if self.index == self.slice.len() {
         return error(self, ErrorCode::EofWhileParsingString);
       }
@@ -593,15 +609,17 @@ impl<'a> Read<'a> for SliceRead<'a> {
   fn parse_str<'s>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
   ) -> Result<Reference<'a, 's, str>> {
-    self.parse_str_bytes(scratch, true, as_str)
+    self.parse_str_bytes(scratch, max_len, true, as_str)
   }
 
   fn parse_str_raw<'s>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
   ) -> Result<Reference<'a, 's, [u8]>> {
-    self.parse_str_bytes(scratch, false, |_, bytes| Ok(bytes))
+    self.parse_str_bytes(scratch, max_len, false, |_, bytes| Ok(bytes))
   }
 
   fn ignore_str(&mut self) -> Result<()> {
@@ -731,19 +749,23 @@ impl<'a> Read<'a> for StrRead<'a> {
   fn parse_str<'s>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
   ) -> Result<Reference<'a, 's, str>> {
-    self.delegate.parse_str_bytes(scratch, true, |_, bytes| {
-      // The input is assumed to be valid UTF-8 and the \u-escapes are
-      // checked along the way, so don't need to check here.
-      Ok(unsafe { str::from_utf8_unchecked(bytes) })
-    })
+    self
+      .delegate
+      .parse_str_bytes(scratch, max_len, true, |_, bytes| {
+        // The input is assumed to be valid UTF-8 and the \u-escapes are
+        // checked along the way, so don't need to check here.
+        Ok(unsafe { str::from_utf8_unchecked(bytes) })
+      })
   }
 
   fn parse_str_raw<'s>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
   ) -> Result<Reference<'a, 's, [u8]>> {
-    self.delegate.parse_str_raw(scratch)
+    self.delegate.parse_str_raw(scratch, max_len)
   }
 
   fn ignore_str(&mut self) -> Result<()> {
@@ -821,15 +843,17 @@ where
   fn parse_str<'s>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
   ) -> Result<Reference<'de, 's, str>> {
-    R::parse_str(self, scratch)
+    R::parse_str(self, scratch, max_len)
   }
 
   fn parse_str_raw<'s>(
     &'s mut self,
     scratch: &'s mut Vec<u8>,
+    max_len: usize,
   ) -> Result<Reference<'de, 's, [u8]>> {
-    R::parse_str_raw(self, scratch)
+    R::parse_str_raw(self, scratch, max_len)
   }
 
   fn ignore_str(&mut self) -> Result<()> {
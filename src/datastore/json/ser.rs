@@ -15,7 +15,12 @@
 //! Serialize a Rust data structure into JSON data.
 //!
 
-use crate::{Error, ErrorCode, Result};
+use crate::{
+  datastore::json::config::{DateTimeFormat, SerializeConfig},
+  DType, Error, ErrorCode, Result,
+};
+
+use chrono::{DateTime as ChronoDateTime, Utc};
 
 use serde::{
   ser::{self, Impossible, Serialize},
@@ -23,7 +28,7 @@ use serde::{
 };
 
 use std::{
-  fmt::{self, Display},
+  fmt::{self, Display, Write as _},
   io,
   num::FpCategory,
 };
@@ -40,6 +45,7 @@ use std::{
 pub struct Serializer<W, F = CompactFormatter> {
   writer: W,
   formatter: F,
+  config: SerializeConfig,
 }
 
 impl<W> Serializer<W>
@@ -73,7 +79,64 @@ where
   /// specified.
   #[inline]
   pub fn with_formatter(writer: W, formatter: F) -> Self {
-    Serializer { writer, formatter }
+    Serializer {
+      writer,
+      formatter,
+      config: SerializeConfig::default(),
+    }
+  }
+
+  /// Sets the representation of values JSON has no native type for, such
+  /// as datetimes.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use chrono::{TimeZone, Utc};
+  /// use sage::json::{DateTimeFormat, SerializeConfig, Serializer};
+  /// use sage::DType;
+  /// use serde::Serialize;
+  ///
+  /// let when = Utc.with_ymd_and_hms(2021, 3, 14, 15, 9, 26).unwrap();
+  ///
+  /// let mut output = Vec::new();
+  /// let mut serializer = Serializer::pretty(&mut output);
+  /// let config = SerializeConfig::new().datetime(DateTimeFormat::EpochMillis);
+  /// serializer.set_config(config);
+  /// DType::from(when).serialize(&mut serializer).unwrap();
+  ///
+  /// assert_eq!(output, b"1615734566000");
+  /// ```
+  pub fn set_config(&mut self, config: SerializeConfig) {
+    self.config = config;
+  }
+
+  /// Writes a `DType::DateTime`, given as its RFC 3339 `value`, in the
+  /// configured format.
+  fn serialize_datetime<T>(&mut self, value: &T) -> Result<()>
+  where
+    T: ?Sized + Serialize,
+  {
+    if self.config.datetime_format() == &DateTimeFormat::Rfc3339 {
+      return value.serialize(self);
+    }
+    let d = match tri!(crate::dtype::to_dtype(value)) {
+      DType::String(ref s) => match ChronoDateTime::parse_from_rfc3339(s) {
+        Ok(d) => d.with_timezone(&Utc),
+        Err(err) => return Err(<Error as ser::Error>::custom(err)),
+      },
+      _ => return Err(ser::Error::custom("expected an RFC 3339 datetime")),
+    };
+    let format = match self.config.datetime_format() {
+      DateTimeFormat::Custom(format) => format,
+      _ => return ser::Serializer::serialize_i64(self, d.timestamp_millis()),
+    };
+    let mut text = String::new();
+    if write!(text, "{}", d.format(format)).is_err() {
+      let msg = format!("invalid datetime format: {:?}", format);
+      return Err(ser::Error::custom(msg));
+    }
+    ser::Serializer::serialize_str(self, &text)
   }
 
   /// Unwrap the `Writer` from the `Serializer`.
@@ -296,12 +359,15 @@ where
   #[inline]
   fn serialize_newtype_struct<T>(
     self,
-    _name: &'static str,
+    name: &'static str,
     value: &T,
   ) -> Result<()>
   where
     T: ?Sized + Serialize,
   {
+    if name == crate::datetime::TOKEN {
+      return self.serialize_datetime(value);
+    }
     value.serialize(self)
   }
 
@@ -2380,6 +2446,47 @@ where
   Ok(())
 }
 
+/// Serialize the given data structure as JSON into the IO stream, compressed
+/// with `compression`. Returns the writer once the compressed stream is
+/// finished.
+///
+/// Available with the `compression` feature. `from_reader` detects and
+/// decompresses the output.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::{compression::Compression, json};
+///
+/// let value = json!({ "name": "Ada", "born": 1815 });
+/// let compressed =
+///   json::to_writer_compressed(Vec::new(), &value, Compression::Gzip)
+///     .unwrap();
+///
+/// let read: sage::DType = json::from_reader(&compressed[..]).unwrap();
+/// assert_eq!(read, value);
+/// ```
+///
+/// # Errors
+///
+/// Fails like `to_writer`, or if the compressed stream can't be written.
+#[cfg(feature = "compression")]
+pub fn to_writer_compressed<W, T>(
+  writer: W,
+  value: &T,
+  compression: crate::compression::Compression,
+) -> Result<W>
+where
+  W: io::Write,
+  T: ?Sized + Serialize,
+{
+  let mut encoder = tri!(
+    crate::compression::Encoder::new(writer, compression).map_err(Error::io)
+  );
+  tri!(to_writer(&mut encoder, value));
+  encoder.finish().map_err(Error::io)
+}
+
 /*
  * +----------------------------------------------------------------------+
  * | +------------------------------------------------------------------+ |
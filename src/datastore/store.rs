@@ -0,0 +1,665 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pluggable key-value persistence for graphs.
+//!
+//! A `GraphStore` is an ordered key-value store: `get`, `put`, `delete` &
+//! `scan_prefix`. `KnowledgeGraph::save_to` & `KnowledgeGraph::load_from`
+//! persist a graph into any of them, so the embedded store can be picked
+//! for its durability & performance without touching the graph code:
+//!
+//! - `MemoryStore` keeps everything in a `BTreeMap`, e.g. for tests &
+//!   caches.
+//! - `FileStore` appends every change to a log file and keeps an index in
+//!   memory; `flush` makes the changes durable and `compact` drops
+//!   overwritten entries.
+//!
+//! Other embedded databases plug in by implementing `GraphStore`.
+//!
+//! Every statement is stored under the key `spo:<subject>\t<predicate>\t
+//! <object>`, with terms written like in N-Triples & an empty value, so the
+//! statements about a subject are a `scan_prefix` of `spo:<subject>\t`
+//! away. Keys outside `spo:` are left alone.
+
+use std::{
+  collections::{BTreeMap, BTreeSet, HashMap},
+  fs::{self, File, OpenOptions},
+  io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+  ops::Bound,
+  path::{Path, PathBuf},
+};
+
+use serde::de::Error as _;
+
+use crate::{
+  error::Error,
+  formats::{self, escape_iri, parse_line},
+  graph::{Entity, KnowledgeGraph, Predicate},
+  Result,
+};
+
+/// Prefix of the keys holding statements.
+const STATEMENTS: &[u8] = b"spo:";
+
+/// `GraphStore` is an ordered key-value store graphs are persisted into.
+pub trait GraphStore {
+  /// Returns the value stored under `key`.
+  fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+
+  /// Stores `value` under `key`, replacing any previous value.
+  fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()>;
+
+  /// Removes `key`, returning `true` if it was stored.
+  fn delete(&mut self, key: &[u8]) -> Result<bool>;
+
+  /// Returns every entry whose key starts with `prefix`, ordered by key.
+  fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+
+  /// Makes the changes so far durable. Does nothing by default.
+  fn flush(&mut self) -> Result<()> {
+    Ok(())
+  }
+}
+
+/// Returns the entries of `map` whose key starts with `prefix`.
+fn scan(
+  map: &BTreeMap<Vec<u8>, Vec<u8>>,
+  prefix: &[u8],
+) -> Vec<(Vec<u8>, Vec<u8>)> {
+  map
+    .range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+    .take_while(|(key, _)| key.starts_with(prefix))
+    .map(|(key, value)| (key.clone(), value.clone()))
+    .collect()
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Memory store.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `MemoryStore` is a `GraphStore` kept in memory, lost once dropped.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::store::{GraphStore, MemoryStore};
+///
+/// let mut store = MemoryStore::new();
+/// store.put(b"a:1", b"one").unwrap();
+/// store.put(b"a:2", b"two").unwrap();
+/// store.put(b"b:1", b"three").unwrap();
+///
+/// assert_eq!(store.get(b"a:2").unwrap().as_deref(), Some(&b"two"[..]));
+/// assert_eq!(store.scan_prefix(b"a:").unwrap().len(), 2);
+/// assert!(store.delete(b"a:1").unwrap());
+/// assert_eq!(store.len(), 2);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemoryStore {
+  entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl MemoryStore {
+  /// Creates an empty store.
+  pub fn new() -> MemoryStore {
+    MemoryStore::default()
+  }
+
+  /// Returns the number of entries.
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Returns `true` if the store holds no entries.
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+}
+
+impl GraphStore for MemoryStore {
+  fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+    Ok(self.entries.get(key).cloned())
+  }
+
+  fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+    self.entries.insert(key.to_vec(), value.to_vec());
+    Ok(())
+  }
+
+  fn delete(&mut self, key: &[u8]) -> Result<bool> {
+    Ok(self.entries.remove(key).is_some())
+  }
+
+  fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    Ok(scan(&self.entries, prefix))
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | File store.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Tag of a log record storing a value.
+const PUT: u8 = 1;
+/// Tag of a log record removing a key.
+const DELETE: u8 = 0;
+
+/// Syncs the directory holding `path`, so a file created or renamed there
+/// survives a crash. Directories can't be synced on Windows, where renames
+/// are durable once the file is.
+fn sync_parent(path: &Path) -> io::Result<()> {
+  if cfg!(unix) {
+    let parent = match path.parent() {
+      Some(parent) if !parent.as_os_str().is_empty() => parent,
+      _ => Path::new("."),
+    };
+    File::open(parent)?.sync_all()?;
+  }
+  Ok(())
+}
+
+/// `FileStore` is a `GraphStore` persisted in an append-only log file.
+///
+/// Each change is appended as a record (tag, key & value lengths, key,
+/// value) and applied to an index kept in memory, which is rebuilt by
+/// replaying the log on `open`. A record cut short by a crash is dropped
+/// along with anything after it. Changes reach the file on every write but
+/// are only durable once `flush`ed; `compact` rewrites the log with just
+/// the live entries.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::store::{FileStore, GraphStore};
+///
+/// let dir = std::env::temp_dir();
+/// let path = dir.join(format!("sage-{:016x}.log", sage::random::u64()));
+///
+/// let mut store = FileStore::open(&path).unwrap();
+/// store.put(b"a:1", b"one").unwrap();
+/// store.put(b"a:1", b"uno").unwrap();
+/// store.put(b"a:2", b"two").unwrap();
+/// store.delete(b"a:2").unwrap();
+/// store.flush().unwrap();
+/// drop(store);
+///
+/// let mut store = FileStore::open(&path).unwrap();
+/// assert_eq!(store.get(b"a:1").unwrap().as_deref(), Some(&b"uno"[..]));
+/// assert_eq!(store.get(b"a:2").unwrap(), None);
+///
+/// let before = std::fs::metadata(&path).unwrap().len();
+/// store.compact().unwrap();
+/// assert!(std::fs::metadata(&path).unwrap().len() < before);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct FileStore {
+  path: PathBuf,
+  file: File,
+  entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+impl FileStore {
+  /// Opens the store logged in `path`, creating the file if needed.
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<FileStore> {
+    let path = path.as_ref().to_path_buf();
+    let created = !path.exists();
+    let mut file = OpenOptions::new()
+      .read(true)
+      .append(true)
+      .create(true)
+      .open(&path)
+      .map_err(Error::io)?;
+    if created {
+      sync_parent(&path).map_err(Error::io)?;
+    }
+
+    let mut entries = BTreeMap::new();
+    let valid = replay(&mut file, &mut entries).map_err(Error::io)?;
+    if valid < file.metadata().map_err(Error::io)?.len() {
+      file.set_len(valid).map_err(Error::io)?;
+    }
+    Ok(FileStore {
+      path,
+      file,
+      entries,
+    })
+  }
+
+  /// Returns the path of the log file.
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Returns the number of entries.
+  pub fn len(&self) -> usize {
+    self.entries.len()
+  }
+
+  /// Returns `true` if the store holds no entries.
+  pub fn is_empty(&self) -> bool {
+    self.entries.is_empty()
+  }
+
+  /// Rewrites the log with only the live entries.
+  ///
+  /// The new log is written next to the old one, synced, and renamed over
+  /// it before the directory is synced, so a crash leaves either log intact
+  /// and the new one is durable once `compact` returns.
+  pub fn compact(&mut self) -> Result<()> {
+    let mut tmp = self.path.clone().into_os_string();
+    tmp.push(".compact");
+    let tmp = PathBuf::from(tmp);
+    let result = (|| {
+      let mut writer = BufWriter::new(File::create(&tmp)?);
+      for (key, value) in &self.entries {
+        writer.write_all(&record(PUT, key, value))?;
+      }
+      writer
+        .into_inner()
+        .map_err(|err| err.into_error())?
+        .sync_all()?;
+      // Opened before the rename, the handle follows the new log.
+      let file = OpenOptions::new().read(true).append(true).open(&tmp)?;
+      fs::rename(&tmp, &self.path)?;
+      Ok(file)
+    })();
+    match result {
+      Ok(file) => {
+        self.file = file;
+        sync_parent(&self.path).map_err(Error::io)
+      }
+      Err(err) => {
+        let _ = fs::remove_file(&tmp);
+        Err(Error::io(err))
+      }
+    }
+  }
+
+  fn append(&mut self, tag: u8, key: &[u8], value: &[u8]) -> Result<()> {
+    if key.len() > u32::MAX as usize || value.len() > u32::MAX as usize {
+      return Err(Error::custom("store keys & values are limited to 4GiB"));
+    }
+    self
+      .file
+      .write_all(&record(tag, key, value))
+      .map_err(Error::io)
+  }
+}
+
+impl GraphStore for FileStore {
+  fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+    Ok(self.entries.get(key).cloned())
+  }
+
+  fn put(&mut self, key: &[u8], value: &[u8]) -> Result<()> {
+    self.append(PUT, key, value)?;
+    self.entries.insert(key.to_vec(), value.to_vec());
+    Ok(())
+  }
+
+  fn delete(&mut self, key: &[u8]) -> Result<bool> {
+    if !self.entries.contains_key(key) {
+      return Ok(false);
+    }
+    self.append(DELETE, key, &[])?;
+    self.entries.remove(key);
+    Ok(true)
+  }
+
+  fn scan_prefix(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    Ok(scan(&self.entries, prefix))
+  }
+
+  fn flush(&mut self) -> Result<()> {
+    self.file.sync_data().map_err(Error::io)
+  }
+}
+
+/// Encodes a log record.
+fn record(tag: u8, key: &[u8], value: &[u8]) -> Vec<u8> {
+  let mut record = Vec::with_capacity(9 + key.len() + value.len());
+  record.push(tag);
+  record.extend_from_slice(&(key.len() as u32).to_le_bytes());
+  record.extend_from_slice(&(value.len() as u32).to_le_bytes());
+  record.extend_from_slice(key);
+  record.extend_from_slice(value);
+  record
+}
+
+/// Applies the records of `file` to `entries`, returning the length of the
+/// log up to the last complete record.
+fn replay(
+  file: &mut File,
+  entries: &mut BTreeMap<Vec<u8>, Vec<u8>>,
+) -> io::Result<u64> {
+  let len = file.metadata()?.len();
+  file.seek(SeekFrom::Start(0))?;
+  let mut reader = BufReader::new(file);
+  let mut valid = 0;
+  loop {
+    let mut header = [0; 9];
+    if valid + header.len() as u64 > len {
+      return Ok(valid);
+    }
+    reader.read_exact(&mut header)?;
+    let length = |bytes: &[u8]| {
+      u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64
+    };
+    let (key_len, value_len) = (length(&header[1..5]), length(&header[5..9]));
+    let end = valid + header.len() as u64 + key_len + value_len;
+    // Anything past a truncated or unknown record is garbage.
+    if end > len || !matches!(header[0], PUT | DELETE) {
+      return Ok(valid);
+    }
+    let mut key = vec![0; key_len as usize];
+    let mut value = vec![0; value_len as usize];
+    reader.read_exact(&mut key)?;
+    reader.read_exact(&mut value)?;
+    if header[0] == PUT {
+      entries.insert(key, value);
+    } else {
+      entries.remove(&key);
+    }
+    valid = end;
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Graphs.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Replaces the statements in `store` with the ones of `graph`, writing
+/// only what changed.
+pub(crate) fn save<S: GraphStore + ?Sized>(
+  graph: &KnowledgeGraph,
+  store: &mut S,
+) -> Result<()> {
+  let keys: BTreeSet<Vec<u8>> = formats::statements(graph)
+    .iter()
+    .map(|statement| {
+      let terms = format!(
+        "{}\t{}\t{}",
+        statement.subject, statement.predicate, statement.object
+      );
+      [STATEMENTS, terms.as_bytes()].concat()
+    })
+    .collect();
+
+  for (key, _) in store.scan_prefix(STATEMENTS)? {
+    if !keys.contains(&key) {
+      store.delete(&key)?;
+    }
+  }
+  for key in &keys {
+    if store.get(key)?.is_none() {
+      store.put(key, &[])?;
+    }
+  }
+  store.flush()
+}
+
+/// Reads the statements in `store` into a new graph.
+pub(crate) fn load<S: GraphStore + ?Sized>(
+  store: &S,
+) -> Result<KnowledgeGraph> {
+  load_prefix(store, STATEMENTS)
+}
+
+/// Reads the statements whose key starts with `prefix` into a new graph.
+fn load_prefix<S: GraphStore + ?Sized>(
+  store: &S,
+  prefix: &[u8],
+) -> Result<KnowledgeGraph> {
+  let mut graph = KnowledgeGraph::new();
+  for (i, (key, _)) in store.scan_prefix(prefix)?.into_iter().enumerate() {
+    let statement = std::str::from_utf8(&key[STATEMENTS.len()..])
+      .ok()
+      .and_then(|terms| {
+        let mut terms = terms.splitn(3, '\t');
+        Some(format!(
+          "{} {} {} .",
+          terms.next()?,
+          terms.next()?,
+          terms.next()?
+        ))
+      })
+      .ok_or_else(|| Error::custom("malformed statement key in store"))?;
+    if let Some((subject, predicate, object)) = parse_line(&statement, i + 1)? {
+      graph.insert(subject, Predicate::Literal(predicate), object);
+    }
+  }
+  Ok(graph)
+}
+
+/// Returns the key prefix of the statements about `iri` (`_:label` for a
+/// blank node).
+fn subject_prefix(iri: &str) -> Vec<u8> {
+  let subject = match iri.strip_prefix("_:") {
+    Some(_) => iri.to_string(),
+    None => format!("<{}>", escape_iri(iri)),
+  };
+  [STATEMENTS, subject.as_bytes(), b"\t"].concat()
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Entity cache.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `CacheStats` counts the lookups of a `CachedGraph`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+  /// Lookups answered from the cache.
+  pub hits: u64,
+  /// Lookups read from the store.
+  pub misses: u64,
+  /// Entities dropped to make room for others.
+  pub evictions: u64,
+}
+
+impl CacheStats {
+  /// Returns the share of lookups answered from the cache, `0.0` before
+  /// any lookup.
+  pub fn hit_ratio(&self) -> f64 {
+    let lookups = self.hits + self.misses;
+    if lookups == 0 {
+      0.0
+    } else {
+      self.hits as f64 / lookups as f64
+    }
+  }
+}
+
+/// `CachedGraph` reads entities from a `GraphStore` through a cache of the
+/// `capacity` most recently used ones, so hot entities don't hit the store
+/// on every lookup.
+///
+/// Each cached entity is a small graph of the statements about it, read
+/// with a single `scan_prefix`. Entities without statements are cached too.
+/// Once full, the least recently used entity is evicted. Writes made
+/// through `CachedGraph::save` clear the cache; call `invalidate` or
+/// `clear` after writing to the store by other means.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::store::{CacheStats, CachedGraph, MemoryStore};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// for name in ["Ada", "Charles"] {
+///   graph.insert(
+///     Node::Http(format!("https://example.com/{}", name)),
+///     Predicate::Literal("https://schema.org/name".to_string()),
+///     Node::Literal(name.into()),
+///   );
+/// }
+/// let mut store = MemoryStore::new();
+/// graph.save_to(&mut store).unwrap();
+///
+/// let mut cache = CachedGraph::new(store, 1);
+/// let ada = cache.entity("https://example.com/Ada").unwrap();
+/// assert_eq!(
+///   ada.get("https://schema.org/name"),
+///   [&Node::Literal("Ada".into())]
+/// );
+///
+/// // Served from the cache, then evicted to make room for Charles.
+/// assert!(cache.entity("https://example.com/Ada").unwrap().exists());
+/// assert!(cache.entity("https://example.com/Charles").unwrap().exists());
+/// assert!(!cache.contains("https://example.com/Ada"));
+///
+/// let stats = cache.stats();
+/// assert_eq!(
+///   stats,
+///   CacheStats {
+///     hits: 1,
+///     misses: 2,
+///     evictions: 1
+///   }
+/// );
+/// ```
+pub struct CachedGraph<S: GraphStore> {
+  store: S,
+  capacity: usize,
+  /// Cached entities by IRI, along with the tick of their last use.
+  entities: HashMap<String, (KnowledgeGraph, u64)>,
+  /// IRIs of the cached entities by tick of last use, oldest first.
+  recency: BTreeMap<u64, String>,
+  tick: u64,
+  stats: CacheStats,
+}
+
+impl<S: GraphStore> CachedGraph<S> {
+  /// Creates a cache of (at least one of) the `capacity` most recently used
+  /// entities of `store`.
+  pub fn new(store: S, capacity: usize) -> CachedGraph<S> {
+    CachedGraph {
+      store,
+      capacity: capacity.max(1),
+      entities: HashMap::new(),
+      recency: BTreeMap::new(),
+      tick: 0,
+      stats: CacheStats::default(),
+    }
+  }
+
+  /// Returns a view of the subject identified by `iri` (`_:label` for a
+  /// blank node), reading its statements from the store unless cached.
+  pub fn entity(&mut self, iri: &str) -> Result<Entity<'_>> {
+    self.tick += 1;
+    match self.entities.get_mut(iri) {
+      Some((_, used)) => {
+        self.stats.hits += 1;
+        self.recency.remove(used);
+        *used = self.tick;
+      }
+      None => {
+        self.stats.misses += 1;
+        let graph = load_prefix(&self.store, &subject_prefix(iri))?;
+        if self.entities.len() >= self.capacity {
+          if let Some((_, oldest)) = self.recency.pop_first() {
+            self.entities.remove(&oldest);
+            self.stats.evictions += 1;
+          }
+        }
+        self.entities.insert(iri.to_string(), (graph, self.tick));
+      }
+    }
+    self.recency.insert(self.tick, iri.to_string());
+    Ok(self.entities[iri].0.entity(iri))
+  }
+
+  /// Returns `true` if the `iri` entity is cached.
+  pub fn contains(&self, iri: &str) -> bool {
+    self.entities.contains_key(iri)
+  }
+
+  /// Drops the `iri` entity from the cache, returning `true` if it was
+  /// cached.
+  pub fn invalidate(&mut self, iri: &str) -> bool {
+    match self.entities.remove(iri) {
+      Some((_, used)) => {
+        self.recency.remove(&used);
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Drops every cached entity.
+  pub fn clear(&mut self) {
+    self.entities.clear();
+    self.recency.clear();
+  }
+
+  /// Replaces the statements in the store with the ones of `graph` (see
+  /// `KnowledgeGraph::save_to`), then clears the cache.
+  pub fn save(&mut self, graph: &KnowledgeGraph) -> Result<()> {
+    save(graph, &mut self.store)?;
+    self.clear();
+    Ok(())
+  }
+
+  /// Returns the number of cached entities.
+  pub fn len(&self) -> usize {
+    self.entities.len()
+  }
+
+  /// Returns `true` if no entity is cached.
+  pub fn is_empty(&self) -> bool {
+    self.entities.is_empty()
+  }
+
+  /// Returns the maximum number of cached entities.
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  /// Returns the counters since the cache was created or `reset_stats`.
+  pub fn stats(&self) -> CacheStats {
+    self.stats
+  }
+
+  /// Resets the counters to zero.
+  pub fn reset_stats(&mut self) {
+    self.stats = CacheStats::default();
+  }
+
+  /// Returns the underlying store.
+  pub fn store(&self) -> &S {
+    &self.store
+  }
+
+  /// Returns the underlying store, dropping the cache.
+  pub fn into_inner(self) -> S {
+    self.store
+  }
+}
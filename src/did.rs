@@ -0,0 +1,655 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::did` models the documents of [Decentralized Identifiers] (see
+//! `sage::iri::Did`) on top of `DType`, along with pluggable resolvers.
+//!
+//! A `DidDocument` is a validated `DType::Object` with typed accessors for
+//! its verification methods & services. Resolvers turn a DID into its
+//! document: `did:key` is resolved locally, `did:web` through a fetch
+//! function of the caller's choosing, and any other method by plugging in
+//! a `Resolver`. `Resolvers` also verifies `sage::vc` proofs by resolving
+//! their verification method.
+//!
+//! [Decentralized Identifiers]: https://www.w3.org/TR/did-core/
+
+use std::{collections::HashMap, mem};
+
+use serde::de::Error as _;
+
+use crate::{
+  datastore::json,
+  dtype::{literal::base64_decode, DType, Map},
+  error::Error,
+  iri::{resolve, Did},
+  sign::VerifyingKey,
+  vc, Result,
+};
+
+/// Base context of DID documents.
+pub const DID_V1: &str = "https://www.w3.org/ns/did/v1";
+
+/// Context of `Multikey` verification methods.
+const MULTIKEY_V1: &str = "https://w3id.org/security/multikey/v1";
+
+/// Verification relationships, whose entries are verification methods or
+/// references to them.
+const RELATIONSHIPS: &[&str] = &[
+  "authentication",
+  "assertionMethod",
+  "keyAgreement",
+  "capabilityInvocation",
+  "capabilityDelegation",
+];
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | DidDocument
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `DidDocument` is a validated DID document.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::did::DidDocument;
+/// use sage::json;
+///
+/// let doc = DidDocument::from_dtype(json!({
+///   "@context": ["https://www.w3.org/ns/did/v1"],
+///   "id": "did:example:ada",
+///   "verificationMethod": [{
+///     "id": "#key-1",
+///     "type": "Multikey",
+///     "controller": "did:example:ada",
+///     "publicKeyMultibase": "z6MkrJVnaZkeFzdQyMZu1cgjg7k1pZZ6pvBQ7XJPt4swbTQ2"
+///   }],
+///   "assertionMethod": ["#key-1"],
+///   "service": [{
+///     "id": "#home",
+///     "type": "LinkedDomains",
+///     "serviceEndpoint": "https://ada.example.com"
+///   }]
+/// }))
+/// .unwrap();
+///
+/// let key = &doc.assertion_method()[0];
+/// assert_eq!(key.id(), "did:example:ada#key-1");
+/// assert_eq!(key.method_type(), Some("Multikey"));
+/// assert!(key.verifying_key().is_ok());
+///
+/// let home = doc.service("#home").unwrap();
+/// assert_eq!(home.endpoint(), "https://ada.example.com");
+///
+/// // Documents must be identified by a DID.
+/// let doc = json!({ "id": "https://example.com" });
+/// assert!(DidDocument::from_dtype(doc).is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DidDocument {
+  doc: Map<String, DType>,
+}
+
+impl DidDocument {
+  /// Validates `value` against the data model and wraps it.
+  pub fn from_dtype(mut value: DType) -> Result<DidDocument> {
+    let doc = match value {
+      DType::Object(ref mut doc) => mem::take(doc),
+      _ => return Err(Error::custom("DID document must be a JSON object")),
+    };
+    let doc = DidDocument { doc };
+    doc.validate()?;
+    Ok(doc)
+  }
+
+  /// Parses & validates a DID document from JSON text.
+  pub fn parse(s: &str) -> Result<DidDocument> {
+    DidDocument::from_dtype(json::from_str(s)?)
+  }
+
+  fn validate(&self) -> Result<()> {
+    let id = self.doc.get("id").and_then(DType::as_str);
+    match id.map(Did::parse) {
+      Some(Ok(did)) if !did.is_url() => {}
+      _ => return Err(Error::custom("`id` must be a DID")),
+    }
+
+    for method in self.verification_methods() {
+      if method.map.get("id").and_then(DType::as_str).is_none()
+        || method.method_type().is_none()
+      {
+        return Err(Error::custom(
+          "every verification method must have an `id` & a `type`",
+        ));
+      }
+    }
+
+    for service in self.services() {
+      if service.map.get("id").and_then(DType::as_str).is_none()
+        || service.service_types().is_empty()
+        || !service.map.contains_key("serviceEndpoint")
+      {
+        return Err(Error::custom(
+          "every service must have an `id`, a `type` & a `serviceEndpoint`",
+        ));
+      }
+    }
+    Ok(())
+  }
+
+  /// Returns the DID of the subject.
+  pub fn id(&self) -> &str {
+    self.doc["id"].as_str().unwrap_or_default()
+  }
+
+  /// Returns the DIDs of the controllers.
+  pub fn controllers(&self) -> Vec<&str> {
+    strings(self.doc.get("controller"))
+  }
+
+  /// Returns the other identifiers of the subject.
+  pub fn also_known_as(&self) -> Vec<&str> {
+    strings(self.doc.get("alsoKnownAs"))
+  }
+
+  /// Returns the methods of the `verificationMethod` property.
+  pub fn verification_methods(&self) -> Vec<VerificationMethod<'_>> {
+    objects(self.doc.get("verificationMethod"))
+      .map(|map| VerificationMethod {
+        map,
+        base: self.id(),
+      })
+      .collect()
+  }
+
+  /// Returns the verification method `id`, absolute or relative to the
+  /// document (e.g. `#key-1`), whether listed in `verificationMethod` or
+  /// embedded in a verification relationship.
+  pub fn verification_method(
+    &self,
+    id: &str,
+  ) -> Option<VerificationMethod<'_>> {
+    let id = resolve(self.id(), id);
+    let embedded = RELATIONSHIPS
+      .iter()
+      .flat_map(|rel| objects(self.doc.get(*rel)))
+      .map(|map| VerificationMethod {
+        map,
+        base: self.id(),
+      });
+    self
+      .verification_methods()
+      .into_iter()
+      .chain(embedded)
+      .find(|method| method.id() == id)
+  }
+
+  /// Returns the verification methods of the verification relationship
+  /// `relationship` (e.g. `"authentication"`), resolving references.
+  /// Dangling references are skipped.
+  pub fn relationship(
+    &self,
+    relationship: &str,
+  ) -> Vec<VerificationMethod<'_>> {
+    let entries = match self.doc.get(relationship) {
+      Some(DType::Array(entries)) => entries.iter().collect(),
+      Some(entry) => vec![entry],
+      None => Vec::new(),
+    };
+    entries
+      .into_iter()
+      .filter_map(|entry| match entry {
+        DType::String(id) => self.verification_method(id),
+        DType::Object(map) => Some(VerificationMethod {
+          map,
+          base: self.id(),
+        }),
+        _ => None,
+      })
+      .collect()
+  }
+
+  /// Returns the methods used to authenticate as the subject.
+  pub fn authentication(&self) -> Vec<VerificationMethod<'_>> {
+    self.relationship("authentication")
+  }
+
+  /// Returns the methods used to issue credentials & other assertions.
+  pub fn assertion_method(&self) -> Vec<VerificationMethod<'_>> {
+    self.relationship("assertionMethod")
+  }
+
+  /// Returns every service.
+  pub fn services(&self) -> Vec<Service<'_>> {
+    objects(self.doc.get("service"))
+      .map(|map| Service {
+        map,
+        base: self.id(),
+      })
+      .collect()
+  }
+
+  /// Returns the service `id`, absolute or relative to the document.
+  pub fn service(&self, id: &str) -> Option<Service<'_>> {
+    let id = resolve(self.id(), id);
+    self
+      .services()
+      .into_iter()
+      .find(|service| service.id() == id)
+  }
+
+  /// Returns the underlying JSON object.
+  pub fn as_dtype(&self) -> DType {
+    DType::Object(self.doc.clone())
+  }
+
+  /// Consumes the document, returning the underlying JSON object.
+  pub fn into_dtype(self) -> DType {
+    DType::Object(self.doc)
+  }
+}
+
+/// `VerificationMethod` is a view of a verification method of a
+/// `DidDocument`.
+#[derive(Clone, Copy, Debug)]
+pub struct VerificationMethod<'d> {
+  map: &'d Map<String, DType>,
+  /// DID of the document, relative IDs are resolved against.
+  base: &'d str,
+}
+
+impl<'d> VerificationMethod<'d> {
+  /// Returns the absolute ID of the method.
+  pub fn id(&self) -> String {
+    let id = self.map.get("id").and_then(DType::as_str);
+    resolve(self.base, id.unwrap_or_default())
+  }
+
+  /// Returns the type of the method, e.g. `Multikey`.
+  pub fn method_type(&self) -> Option<&'d str> {
+    self.map.get("type").and_then(DType::as_str)
+  }
+
+  /// Returns the DID of the controller.
+  pub fn controller(&self) -> Option<&'d str> {
+    self.map.get("controller").and_then(DType::as_str)
+  }
+
+  /// Returns the `publicKeyMultibase`, if any.
+  pub fn public_key_multibase(&self) -> Option<&'d str> {
+    self.map.get("publicKeyMultibase").and_then(DType::as_str)
+  }
+
+  /// Returns the `publicKeyJwk`, if any.
+  pub fn public_key_jwk(&self) -> Option<&'d DType> {
+    self.map.get("publicKeyJwk")
+  }
+
+  /// Returns the underlying JSON object.
+  pub fn as_map(&self) -> &'d Map<String, DType> {
+    self.map
+  }
+
+  /// Decodes the Ed25519 public key of the method, given as a multicodec
+  /// `publicKeyMultibase` (`Multikey`, `Ed25519VerificationKey2020`) or an
+  /// `OKP` `publicKeyJwk` (`JsonWebKey2020`).
+  pub fn verifying_key(&self) -> Result<VerifyingKey> {
+    if let Some(multibase) = self.public_key_multibase() {
+      return VerifyingKey::from_multibase(multibase);
+    }
+    let jwk = self
+      .public_key_jwk()
+      .ok_or_else(|| Error::custom("verification method has no public key"))?;
+    match (jwk["kty"].as_str(), jwk["crv"].as_str(), jwk["x"].as_str()) {
+      (Some("OKP"), Some("Ed25519"), Some(x)) => {
+        let bytes =
+          base64_decode(x, true).and_then(|x| <[u8; 32]>::try_from(x).ok());
+        match bytes {
+          Some(bytes) => VerifyingKey::from_bytes(&bytes),
+          None => Err(Error::custom("invalid Ed25519 JSON Web Key")),
+        }
+      }
+      _ => Err(Error::custom("only Ed25519 public keys are supported")),
+    }
+  }
+}
+
+/// `Service` is a view of a service of a `DidDocument`.
+#[derive(Clone, Copy, Debug)]
+pub struct Service<'d> {
+  map: &'d Map<String, DType>,
+  base: &'d str,
+}
+
+impl<'d> Service<'d> {
+  /// Returns the absolute ID of the service.
+  pub fn id(&self) -> String {
+    let id = self.map.get("id").and_then(DType::as_str);
+    resolve(self.base, id.unwrap_or_default())
+  }
+
+  /// Returns the types of the service.
+  pub fn service_types(&self) -> Vec<&'d str> {
+    strings(self.map.get("type"))
+  }
+
+  /// Returns the `serviceEndpoint`: a URL, a map or a list of them.
+  pub fn endpoint(&self) -> &'d DType {
+    &self.map["serviceEndpoint"]
+  }
+
+  /// Returns the underlying JSON object.
+  pub fn as_map(&self) -> &'d Map<String, DType> {
+    self.map
+  }
+}
+
+/// Returns a string or the strings of an array.
+fn strings(value: Option<&DType>) -> Vec<&str> {
+  match value {
+    Some(DType::String(s)) => vec![s.as_str()],
+    Some(DType::Array(values)) => {
+      values.iter().filter_map(DType::as_str).collect()
+    }
+    _ => Vec::new(),
+  }
+}
+
+/// Returns an object or the objects of an array.
+fn objects(value: Option<&DType>) -> impl Iterator<Item = &Map<String, DType>> {
+  let values = match value {
+    Some(DType::Array(values)) => values.iter().collect(),
+    Some(value) => vec![value],
+    None => Vec::new(),
+  };
+  values.into_iter().filter_map(DType::as_object)
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Resolvers.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Resolver` resolves DIDs of a method into their document.
+///
+/// Closures taking a `&Did` are resolvers too, e.g. to look documents
+/// up in a local registry.
+pub trait Resolver {
+  /// Resolves the DID of `did` (ignoring any path, query or fragment).
+  fn resolve(&self, did: &Did) -> Result<DidDocument>;
+}
+
+impl<F> Resolver for F
+where
+  F: Fn(&Did) -> Result<DidDocument>,
+{
+  fn resolve(&self, did: &Did) -> Result<DidDocument> {
+    self(did)
+  }
+}
+
+/// `KeyResolver` resolves Ed25519 `did:key` DIDs, whose document is
+/// derived from the public key they encode.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::did::{self, KeyResolver, Resolver};
+/// use sage::iri::Did;
+/// use sage::sign::SigningKey;
+///
+/// let key = SigningKey::from_bytes(&[7; 32]).verifying_key();
+/// let did = did::key_did(&key);
+/// assert!(did.starts_with("did:key:z6Mk"));
+///
+/// let doc = KeyResolver.resolve(&Did::parse(&did).unwrap()).unwrap();
+/// assert_eq!(doc.authentication()[0].verifying_key().unwrap(), key);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KeyResolver;
+
+impl Resolver for KeyResolver {
+  fn resolve(&self, did: &Did) -> Result<DidDocument> {
+    if did.method() != "key" {
+      return Err(Error::custom("not a `did:key` DID"));
+    }
+    let multibase = did.method_specific_id();
+    VerifyingKey::from_multibase(multibase)?;
+
+    let id = did.did();
+    let id = id.as_str();
+    let method = format!("{}#{}", id, multibase);
+    let mut doc = Map::new();
+    doc.insert(
+      "@context".to_string(),
+      DType::from(vec![DType::from(DID_V1), DType::from(MULTIKEY_V1)]),
+    );
+    doc.insert("id".to_string(), id.into());
+
+    let mut key = Map::new();
+    key.insert("id".to_string(), method.clone().into());
+    key.insert("type".to_string(), "Multikey".into());
+    key.insert("controller".to_string(), id.into());
+    key.insert("publicKeyMultibase".to_string(), multibase.into());
+    doc.insert(
+      "verificationMethod".to_string(),
+      DType::from(vec![DType::Object(key)]),
+    );
+    for relationship in RELATIONSHIPS.iter().filter(|r| **r != "keyAgreement") {
+      doc.insert(
+        relationship.to_string(),
+        DType::from(vec![DType::from(method.as_str())]),
+      );
+    }
+    DidDocument::from_dtype(DType::Object(doc))
+  }
+}
+
+/// Returns the `did:key` DID of an Ed25519 public key.
+pub fn key_did(key: &VerifyingKey) -> String {
+  format!("did:key:{}", key.to_multibase())
+}
+
+/// `WebResolver` resolves `did:web` DIDs by fetching the `did.json` of
+/// their web location with `fetch`, e.g. an HTTP client of the caller's
+/// choosing.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::did::{Resolver, WebResolver};
+/// use sage::iri::Did;
+///
+/// let resolver = WebResolver::new(|url: &str| {
+///   assert_eq!(url, "https://example.com/users/ada/did.json");
+///   Ok(r#"{"id": "did:web:example.com:users:ada"}"#.to_string())
+/// });
+/// let did = Did::parse("did:web:example.com:users:ada").unwrap();
+/// assert_eq!(resolver.resolve(&did).unwrap().id(), did.did());
+/// ```
+pub struct WebResolver<F> {
+  fetch: F,
+}
+
+impl<F> WebResolver<F>
+where
+  F: Fn(&str) -> Result<String>,
+{
+  /// Creates a resolver fetching documents with `fetch`, given their URL.
+  pub fn new(fetch: F) -> WebResolver<F> {
+    WebResolver { fetch }
+  }
+}
+
+impl<F> Resolver for WebResolver<F>
+where
+  F: Fn(&str) -> Result<String>,
+{
+  fn resolve(&self, did: &Did) -> Result<DidDocument> {
+    let doc = DidDocument::parse(&(self.fetch)(&web_url(did)?)?)?;
+    if doc.id() != did.did() {
+      return Err(Error::custom("DID document `id` doesn't match the DID"));
+    }
+    Ok(doc)
+  }
+}
+
+/// Returns the URL of the document of a `did:web` DID: domain (and port)
+/// first, then path segments, all separated by `:`.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::did;
+/// use sage::iri::Did;
+///
+/// let url = |did| did::web_url(&Did::parse(did).unwrap()).unwrap();
+/// assert_eq!(
+///   url("did:web:example.com"),
+///   "https://example.com/.well-known/did.json"
+/// );
+/// assert_eq!(
+///   url("did:web:localhost%3A8080:ada"),
+///   "https://localhost:8080/ada/did.json"
+/// );
+/// ```
+pub fn web_url(did: &Did) -> Result<String> {
+  if did.method() != "web" {
+    return Err(Error::custom("not a `did:web` DID"));
+  }
+  let mut segments = did.method_specific_id().split(':');
+  let host = segments.next().unwrap_or_default().replace("%3A", ":");
+  let path: Vec<&str> = segments.collect();
+  Ok(if path.is_empty() {
+    format!("https://{}/.well-known/did.json", host)
+  } else {
+    format!("https://{}/{}/did.json", host, path.join("/"))
+  })
+}
+
+/// `Resolvers` dispatches DIDs to the resolver of their method. `did:key`
+/// is resolved out of the box.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::did::{DidDocument, Resolvers};
+/// use sage::iri::Did;
+/// use sage::json;
+/// use sage::sign::SigningKey;
+/// use sage::vc::CredentialBuilder;
+///
+/// let key = SigningKey::from_bytes(&[7; 32])
+///   .verification_method("did:example:university#key-1");
+/// let public = key.verifying_key().to_multibase();
+///
+/// let resolvers = Resolvers::new().register("example", move |did: &Did| {
+///   DidDocument::from_dtype(json!({
+///     "id": did.did(),
+///     "verificationMethod": [{
+///       "id": "#key-1",
+///       "type": "Multikey",
+///       "publicKeyMultibase": public.as_str()
+///     }]
+///   }))
+/// });
+///
+/// let mut vc = CredentialBuilder::new()
+///   .issuer("did:example:university")
+///   .subject(json!({ "id": "did:example:ada", "alumniOf": "Oxford" }))
+///   .build()
+///   .unwrap();
+/// vc.sign(&key).unwrap();
+/// assert!(vc.verify(&resolvers).unwrap());
+///
+/// assert!(resolvers.resolve("did:unknown:123").is_err());
+/// ```
+pub struct Resolvers {
+  methods: HashMap<String, Box<dyn Resolver + Send + Sync>>,
+}
+
+impl Resolvers {
+  /// Creates a registry resolving `did:key` DIDs.
+  pub fn new() -> Resolvers {
+    Resolvers {
+      methods: HashMap::new(),
+    }
+    .register("key", KeyResolver)
+  }
+
+  /// Resolves the DIDs of `method` with `resolver`.
+  pub fn register<R>(mut self, method: &str, resolver: R) -> Self
+  where
+    R: Resolver + Send + Sync + 'static,
+  {
+    self.methods.insert(method.to_string(), Box::new(resolver));
+    self
+  }
+
+  /// Resolves the DID of the DID URL `did`.
+  pub fn resolve(&self, did: &str) -> Result<DidDocument> {
+    Resolver::resolve(self, &Did::parse(did)?)
+  }
+
+  /// Resolves the public key of the verification method `url`, e.g.
+  /// `did:example:ada#key-1`.
+  pub fn verifying_key(&self, url: &str) -> Result<VerifyingKey> {
+    let doc = self.resolve(url)?;
+    match doc.verification_method(url) {
+      Some(method) => method.verifying_key(),
+      None => Err(Error::custom(format_args!(
+        "no verification method `{}`",
+        url
+      ))),
+    }
+  }
+}
+
+impl Default for Resolvers {
+  fn default() -> Self {
+    Resolvers::new()
+  }
+}
+
+impl Resolver for Resolvers {
+  fn resolve(&self, did: &Did) -> Result<DidDocument> {
+    match self.methods.get(did.method()) {
+      Some(resolver) => resolver.resolve(did),
+      None => Err(Error::custom(format_args!(
+        "no resolver for `did:{}`",
+        did.method()
+      ))),
+    }
+  }
+}
+
+impl vc::Verifier for Resolvers {
+  /// Verifies an Ed25519 `proofValue` with the key of the resolved
+  /// verification method.
+  fn verify(
+    &self,
+    verification_method: &str,
+    data: &[u8],
+    proof_value: &str,
+  ) -> Result<bool> {
+    let key = self.verifying_key(verification_method)?;
+    vc::Verifier::verify(&key, verification_method, data, proof_value)
+  }
+}
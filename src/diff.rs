@@ -0,0 +1,196 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::diff` compares two `DType` trees & lists what changed between
+//! them, e.g. to explain a failing assertion or to review a migration.
+//!
+//! Unlike a JSON Patch, the changes aren't meant to be applied: every
+//! change carries both the old & new value, and `render` prints them in a
+//! compact, human readable report.
+//!
+//! ```rust
+//! use sage::diff::{render, structural_diff, Change};
+//! use sage::json;
+//!
+//! let before = json!({ "name": "Ada", "born": 1815, "tags": ["math"] });
+//! let after = json!({ "name": "Ada Lovelace", "tags": ["math", "poetry"] });
+//!
+//! let changes = structural_diff(&before, &after);
+//! assert_eq!(
+//!   changes[0],
+//!   Change::Removed { path: "/born".to_string(), value: json!(1815) }
+//! );
+//! assert_eq!(
+//!   render(&changes),
+//!   "- /born: 1815\n\
+//!    ~ /name: \"Ada\" -> \"Ada Lovelace\"\n\
+//!    + /tags/1: \"poetry\"\n"
+//! );
+//! ```
+
+use std::fmt;
+
+use crate::{datastore::json, dtype::DType};
+
+/// `Change` is a single difference between two `DType` trees.
+///
+/// Paths are [JSON Pointers] into the trees, so the values can be looked
+/// up with `DType::pointer`. The empty path is the root.
+///
+/// [JSON Pointers]: https://www.rfc-editor.org/rfc/rfc6901
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+  /// `value` only exists in the second tree.
+  Added {
+    /// Location of the value.
+    path: String,
+    /// The added value.
+    value: DType,
+  },
+
+  /// `value` only exists in the first tree.
+  Removed {
+    /// Location of the value.
+    path: String,
+    /// The removed value.
+    value: DType,
+  },
+
+  /// The value at `path` changed from `from` to `to`.
+  Changed {
+    /// Location of the value.
+    path: String,
+    /// The value in the first tree.
+    from: DType,
+    /// The value in the second tree.
+    to: DType,
+  },
+}
+
+impl Change {
+  /// Returns the JSON Pointer of the changed value.
+  pub fn path(&self) -> &str {
+    match self {
+      Change::Added { path, .. }
+      | Change::Removed { path, .. }
+      | Change::Changed { path, .. } => path,
+    }
+  }
+}
+
+/// Formats a change as a single line: `+ path: value` for added,
+/// `- path: value` for removed & `~ path: from -> to` for changed values.
+/// Values are written as compact JSON.
+impl fmt::Display for Change {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let path = match self.path() {
+      "" => "(root)",
+      path => path,
+    };
+    match self {
+      Change::Added { value, .. } => {
+        write!(f, "+ {}: {}", path, compact(value))
+      }
+      Change::Removed { value, .. } => {
+        write!(f, "- {}: {}", path, compact(value))
+      }
+      Change::Changed { from, to, .. } => {
+        write!(f, "~ {}: {} -> {}", path, compact(from), compact(to))
+      }
+    }
+  }
+}
+
+fn compact(value: &DType) -> String {
+  json::to_string(value).unwrap_or_else(|_| value.to_string())
+}
+
+/// Lists the differences between `a` & `b`, in depth-first order of their
+/// paths.
+///
+/// Objects are compared key by key & arrays index by index: a longer array
+/// adds its trailing elements, a shorter one removes them. Values of
+/// different types (including `1` & `1.0`) are changed as a whole.
+pub fn structural_diff(a: &DType, b: &DType) -> Vec<Change> {
+  let mut changes = Vec::new();
+  diff(a, b, &mut String::new(), &mut changes);
+  changes
+}
+
+/// Renders `changes` one per line, see the `Display` implementation of
+/// `Change`. Returns an empty string if there are no changes.
+pub fn render(changes: &[Change]) -> String {
+  changes
+    .iter()
+    .map(|change| format!("{}\n", change))
+    .collect()
+}
+
+fn diff(a: &DType, b: &DType, path: &mut String, changes: &mut Vec<Change>) {
+  match (a, b) {
+    (DType::Object(a), DType::Object(b)) => {
+      let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+      keys.sort_unstable();
+      keys.dedup();
+      for key in keys {
+        let len = path.len();
+        push_token(path, key);
+        match (a.get(key), b.get(key)) {
+          (Some(a), Some(b)) => diff(a, b, path, changes),
+          (Some(value), None) => changes.push(Change::Removed {
+            path: path.clone(),
+            value: value.clone(),
+          }),
+          (None, Some(value)) => changes.push(Change::Added {
+            path: path.clone(),
+            value: value.clone(),
+          }),
+          (None, None) => unreachable!(),
+        }
+        path.truncate(len);
+      }
+    }
+    (DType::Array(a), DType::Array(b)) => {
+      for i in 0..a.len().max(b.len()) {
+        let len = path.len();
+        push_token(path, &i.to_string());
+        match (a.get(i), b.get(i)) {
+          (Some(a), Some(b)) => diff(a, b, path, changes),
+          (Some(value), None) => changes.push(Change::Removed {
+            path: path.clone(),
+            value: value.clone(),
+          }),
+          (None, Some(value)) => changes.push(Change::Added {
+            path: path.clone(),
+            value: value.clone(),
+          }),
+          (None, None) => unreachable!(),
+        }
+        path.truncate(len);
+      }
+    }
+    (a, b) if a != b => changes.push(Change::Changed {
+      path: path.clone(),
+      from: a.clone(),
+      to: b.clone(),
+    }),
+    _ => {}
+  }
+}
+
+/// Appends `token` to the JSON Pointer `path`, escaping `~` & `/`.
+fn push_token(path: &mut String, token: &str) {
+  path.push('/');
+  path.push_str(&token.replace('~', "~0").replace('/', "~1"));
+}
@@ -21,17 +21,42 @@
 
 use std::fmt;
 
-use serde::{de::DeserializeOwned, ser::Serialize};
+use serde::{
+  de::{Deserialize, DeserializeOwned},
+  ser::Serialize,
+};
 
 use crate::Result;
 
+mod approx;
+mod builder;
+mod content;
 pub mod datetime;
+pub mod geo;
+pub mod key;
+pub mod lang;
+pub mod literal;
 pub mod map;
 pub mod number;
 mod ops;
+mod select;
+mod size;
 
 // Re-export public members.
-pub use {datetime::DateTime, map::Map, number::Number, ops::*};
+pub use {
+  approx::Tolerance,
+  builder::{ArrayBuilder, DTypeBuilder, ObjectBuilder},
+  datetime::DateTime,
+  geo::{Geo, Point},
+  key::Key,
+  lang::LangString,
+  literal::{xsd, FromLiteral, Literal, ParseMode},
+  map::Map,
+  number::Number,
+  ops::*,
+  select::Selector,
+  size::DeepSizeOf,
+};
 
 /// `IRI` stands for International Resource Identifer. (ex: <name>).
 pub type IRI = String;
@@ -49,14 +74,38 @@ pub type URI = String;
 
 /// `DType` represents the various types which data in the Sage Knowledge
 /// Graph can be represented as.
-#[derive(Clone, Eq, PartialEq)]
+///
+/// Arrays, bytes & objects are boxed so a `DType` takes 24 bytes, which
+/// adds up for arrays of millions of values. With the
+/// `arbitrary_precision` feature numbers keep their digits in a `String`,
+/// which grows it to 32 bytes. Use `DType::from(vec)` to build an array
+/// from a `Vec`.
+///
+/// ```rust
+/// let size = if cfg!(feature = "arbitrary_precision") { 32 } else { 24 };
+/// assert_eq!(std::mem::size_of::<sage::DType>(), size);
+/// ```
+///
+/// `DType` implements `Hash` consistently with `Eq`, so values can be kept
+/// in a `HashSet`, and `Ord` (see its implementation for the order), so
+/// arrays of mixed values can be sorted. `Hash` isn't stable across
+/// processes or releases; use `DType::content_hash` to address values by
+/// their content.
+#[derive(Clone, Eq, Hash, PartialEq)]
 pub enum DType {
   /// Represents a collection of values.
-  Array(Vec<DType>),
+  Array(Box<Vec<DType>>),
 
   /// Represents a boolean (true or false) value.
   Boolean(bool),
 
+  /// Represents binary data, e.g. a thumbnail or an embedding.
+  ///
+  /// Human readable formats like JSON write bytes as a base64 string (and
+  /// read them back as a string), binary formats like CBOR or MessagePack
+  /// as native byte strings.
+  Bytes(Box<Vec<u8>>),
+
   /// Represents date, time or datetime.
   DateTime(DateTime),
 
@@ -92,6 +141,7 @@ impl fmt::Debug for DType {
     match *self {
       DType::Null => f.debug_tuple("Null").finish(),
       DType::Boolean(b) => f.debug_tuple("Boolean").field(&b).finish(),
+      DType::Bytes(ref b) => f.debug_tuple("Bytes").field(b).finish(),
       DType::Number(ref n) => fmt::Debug::fmt(&n, f),
       DType::String(ref s) => f.debug_tuple("String").field(s).finish(),
       DType::Array(ref a) => {
@@ -573,6 +623,74 @@ impl DType {
     }
   }
 
+  /// Returns true if the `DType` is `Bytes`. Returns false otherwise.
+  ///
+  /// ```rust
+  /// use sage::DType;
+  ///
+  /// assert!(DType::Bytes(Box::new(vec![0xff])).is_bytes());
+  ///
+  /// // Arrays of numbers aren't bytes.
+  /// assert!(!sage::json!([255]).is_bytes());
+  /// ```
+  pub fn is_bytes(&self) -> bool {
+    self.as_bytes().is_some()
+  }
+
+  /// If the `DType` is `Bytes`, returns the associated bytes. Returns `None`
+  /// otherwise.
+  ///
+  /// ```rust
+  /// use sage::DType;
+  ///
+  /// let thumbnail = DType::Bytes(Box::new(vec![0x89, b'P', b'N', b'G']));
+  /// assert_eq!(thumbnail.as_bytes(), Some(&b"\x89PNG"[..]));
+  ///
+  /// // JSON has no binary type, bytes are written as base64.
+  /// assert_eq!(sage::json::to_string(&thumbnail).unwrap(), r#""iVBORw==""#);
+  /// ```
+  pub fn as_bytes(&self) -> Option<&[u8]> {
+    match *self {
+      DType::Bytes(ref b) => Some(b.as_slice()),
+      _ => None,
+    }
+  }
+
+  /// If the `DType` is a string holding a UUID, in the simple, hyphenated
+  /// or `urn:uuid:` form, returns the parsed UUID. Returns `None` otherwise.
+  ///
+  /// ```rust
+  /// use sage::json;
+  ///
+  /// let id = json!("urn:uuid:67e55044-10b1-426f-9247-bb680e5fe0c8");
+  /// let id = id.as_uuid().unwrap();
+  /// assert_eq!(id.to_string(), "67e55044-10b1-426f-9247-bb680e5fe0c8");
+  ///
+  /// assert_eq!(json!("67e55044").as_uuid(), None);
+  /// ```
+  #[cfg(feature = "uuid")]
+  pub fn as_uuid(&self) -> Option<uuid::Uuid> {
+    self.as_str().and_then(literal::uuid)
+  }
+
+  /// If the `DType` is a string holding an absolute URL, returns the parsed
+  /// URL. Returns `None` otherwise.
+  ///
+  /// ```rust
+  /// use sage::json;
+  ///
+  /// let entity = json!({ "@id": "https://example.com/people/ada?v=2" });
+  /// let id = entity["@id"].as_url().unwrap();
+  /// assert_eq!(id.host_str(), Some("example.com"));
+  ///
+  /// // Relative references have no scheme to resolve them with.
+  /// assert_eq!(json!("/people/ada").as_url(), None);
+  /// ```
+  #[cfg(feature = "url")]
+  pub fn as_url(&self) -> Option<url::Url> {
+    self.as_str().and_then(|s| url::Url::parse(s).ok())
+  }
+
   /// Looks up a value by a JSON Pointer.
   ///
   /// JSON Pointer defines a string syntax for identifying a specific value
@@ -679,6 +797,164 @@ impl DType {
       })
   }
 
+  /// Returns every value matched by the [JSONPath] expression `path`, e.g.
+  /// `$.store.book[*].author` or `$..author`. See `Selector` for the
+  /// supported syntax; parse a `Selector` once to reuse an expression.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// # use sage::json;
+  /// #
+  /// let store = json!({
+  ///   "store": {
+  ///     "book": [
+  ///       { "author": "Tolkien", "price": 22.99 },
+  ///       { "author": "Herbert", "price": 8.99 }
+  ///     ]
+  ///   }
+  /// });
+  ///
+  /// assert_eq!(
+  ///   store.select("$.store.book[*].author").unwrap(),
+  ///   vec!["Tolkien", "Herbert"]
+  /// );
+  /// assert_eq!(
+  ///   store.select("$..book[?(@.price > 10)].author").unwrap(),
+  ///   vec!["Tolkien"]
+  /// );
+  /// assert!(store.select("store.book").is_err());
+  /// ```
+  ///
+  /// [JSONPath]: https://www.rfc-editor.org/rfc/rfc9535
+  pub fn select(&self, path: &str) -> Result<Vec<&DType>> {
+    Ok(Selector::parse(path)?.select(self))
+  }
+
+  /// Returns mutable references to every value matched by the JSONPath
+  /// expression `path`. Values nested inside another match are skipped,
+  /// see `Selector::select_mut`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// # use sage::json;
+  /// #
+  /// let mut user = json!({ "name": "Ada", "emails": ["ADA@EXAMPLE.COM"] });
+  ///
+  /// for email in user.select_mut("$.emails[*]").unwrap() {
+  ///   *email = email.as_str().unwrap().to_lowercase().into();
+  /// }
+  /// assert_eq!(user["emails"][0], "ada@example.com");
+  /// ```
+  pub fn select_mut(&mut self, path: &str) -> Result<Vec<&mut DType>> {
+    Ok(Selector::parse(path)?.select_mut(self))
+  }
+
+  /// Returns the hex-encoded SHA-256 of the canonical form of the value.
+  ///
+  /// Equal values have the same content hash, independently of the key
+  /// order of objects, the process or the platform, so it can be used to
+  /// deduplicate or address values across processes.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// # use sage::json;
+  /// #
+  /// let a = json!({ "name": "Ada", "born": 1815 });
+  /// let b = json!({ "born": 1815, "name": "Ada" });
+  /// assert_eq!(a.content_hash(), b.content_hash());
+  /// assert_eq!(a.content_hash().len(), 64);
+  ///
+  /// assert_ne!(json!("1").content_hash(), json!(1).content_hash());
+  /// assert_ne!(json!(["a", "b"]).content_hash(), json!(["ab"]).content_hash());
+  /// ```
+  pub fn content_hash(&self) -> String {
+    content::content_hash(self)
+  }
+
+  /// Returns `true` if the values are equal up to `tolerance`: numbers &
+  /// datetimes may differ by the configured tolerance, arrays & objects
+  /// must have the same shape with approximately equal elements, every
+  /// other value must be equal.
+  ///
+  /// Unlike `==`, integers & floats of the same value are approximately
+  /// equal.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use std::time::Duration;
+  ///
+  /// use chrono::{TimeZone, Utc};
+  /// use sage::{dtype::Tolerance, json, DType, DateTime};
+  ///
+  /// let expected = json!({ "pagerank": [0.3, 0.7], "nodes": 2 });
+  /// let computed = json!({ "pagerank": [0.1 + 0.2, 0.7], "nodes": 2.0 });
+  /// assert_ne!(expected, computed);
+  /// assert!(expected.approx_eq(&computed, Tolerance::default()));
+  ///
+  /// let loose = Tolerance::new().relative(0.05);
+  /// assert!(json!(100).approx_eq(&json!(104.5), loose));
+  /// assert!(!json!(100).approx_eq(&json!(106), loose));
+  ///
+  /// let at = |ms: i64| {
+  ///   let utc = Utc.timestamp_millis_opt(1_615_734_566_000 + ms).unwrap();
+  ///   DType::DateTime(DateTime::from(utc))
+  /// };
+  /// let tolerance = Tolerance::new().datetime(Duration::from_millis(250));
+  /// assert!(at(0).approx_eq(&at(200), tolerance));
+  /// assert!(!at(0).approx_eq(&at(300), tolerance));
+  /// ```
+  pub fn approx_eq(&self, other: &DType, tolerance: Tolerance) -> bool {
+    approx::approx_eq(self, other, &tolerance)
+  }
+
+  /// Sorts every array in the value, including nested ones, by the total
+  /// order of `DType`. Object keys aren't reordered.
+  ///
+  /// Together with `content_hash` this canonicalizes values whose arrays
+  /// are really sets.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// # use sage::json;
+  /// #
+  /// let mut doc = json!({
+  ///   "tags": ["rust", "graph", 42],
+  ///   "authors": [{ "name": "Bob" }, { "name": "Ada" }],
+  ///   "ids": [[3, 1], [2]]
+  /// });
+  /// doc.sort_arrays_recursively();
+  ///
+  /// assert_eq!(
+  ///   doc,
+  ///   json!({
+  ///     "tags": [42, "graph", "rust"],
+  ///     "authors": [{ "name": "Ada" }, { "name": "Bob" }],
+  ///     "ids": [[1, 3], [2]]
+  ///   })
+  /// );
+  /// ```
+  pub fn sort_arrays_recursively(&mut self) {
+    match self {
+      DType::Array(values) => {
+        for value in values.iter_mut() {
+          value.sort_arrays_recursively();
+        }
+        values.sort();
+      }
+      DType::Object(map) => {
+        for value in map.values_mut() {
+          value.sort_arrays_recursively();
+        }
+      }
+      _ => {}
+    }
+  }
+
   /// Takes the value of the `DType`, leaving a `Null` in its place.
   ///
   /// # Example
@@ -695,8 +971,74 @@ impl DType {
     std::mem::replace(self, DType::Null)
   }
 
+  /// Replaces the value of the `DType` with `value`, returning the old
+  /// value.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// # use sage::json;
+  ///
+  /// let mut obj = json!({ "x": "y" });
+  /// assert_eq!(obj["x"].replace(json!(42)), json!("y"));
+  ///
+  /// assert_eq!(obj, json!({ "x": 42 }));
+  /// ```
+  pub fn replace(&mut self, value: DType) -> DType {
+    std::mem::replace(self, value)
+  }
+
+  /// Returns a mutable reference to the underlying map, first replacing the
+  /// `DType` with an empty object if it isn't an object.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// # use sage::json;
+  ///
+  /// let mut doc = json!({ "name": null });
+  /// doc["name"]
+  ///   .as_object_mut_or_insert()
+  ///   .insert("first".to_string(), json!("John"));
+  ///
+  /// assert_eq!(doc, json!({ "name": { "first": "John" } }));
+  /// ```
+  pub fn as_object_mut_or_insert(&mut self) -> &mut Map<String, DType> {
+    if !self.is_object() {
+      *self = DType::Object(Map::new());
+    }
+    match self {
+      DType::Object(map) => map,
+      _ => unreachable!(),
+    }
+  }
+
+  /// Returns a mutable reference to the underlying vector, first replacing
+  /// the `DType` with an empty array if it isn't an array.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// # use sage::json;
+  ///
+  /// let mut doc = json!({});
+  /// doc["tags"].as_array_mut_or_insert().push(json!("rust"));
+  /// doc["tags"].as_array_mut_or_insert().push(json!("graph"));
+  ///
+  /// assert_eq!(doc, json!({ "tags": ["rust", "graph"] }));
+  /// ```
+  pub fn as_array_mut_or_insert(&mut self) -> &mut Vec<DType> {
+    if !self.is_array() {
+      *self = DType::Array(Box::default());
+    }
+    match self {
+      DType::Array(list) => list,
+      _ => unreachable!(),
+    }
+  }
+
   #[cold]
-  fn parse_index(s: &str) -> Option<usize> {
+  pub(crate) fn parse_index(s: &str) -> Option<usize> {
     if s.starts_with('+') || (s.starts_with('0') && s.len() != 1) {
       return None;
     }
@@ -739,6 +1081,57 @@ impl Default for DType {
   }
 }
 
+/// Drops nested arrays & objects with an explicit stack instead of
+/// recursion, so arbitrarily deep values (e.g. parsed without a recursion
+/// limit or built programmatically) don't overflow the call stack.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::DType;
+///
+/// let mut value = DType::Null;
+/// for _ in 0..100_000 {
+///   value = DType::Array(Box::new(vec![value]));
+/// }
+/// drop(value);
+/// ```
+impl Drop for DType {
+  fn drop(&mut self) {
+    let nested = |value: &DType| match value {
+      DType::Array(values) => !values.is_empty(),
+      DType::Object(map) => !map.is_empty(),
+      _ => false,
+    };
+    let deep = match self {
+      DType::Array(values) => values.iter().any(nested),
+      DType::Object(map) => map.values().any(nested),
+      _ => false,
+    };
+    if !deep {
+      return;
+    }
+
+    let mut stack = Vec::new();
+    take_children(self, &mut stack);
+    while let Some(mut value) = stack.pop() {
+      // Once emptied, `value` is dropped without recursing.
+      take_children(&mut value, &mut stack);
+    }
+  }
+}
+
+/// Moves the elements of an array or the values of an object into `stack`.
+fn take_children(value: &mut DType, stack: &mut Vec<DType>) {
+  match value {
+    DType::Array(values) => stack.append(values),
+    DType::Object(map) => {
+      stack.extend(std::mem::take(map).into_iter().map(|(_, v)| v))
+    }
+    _ => {}
+  }
+}
+
 /*
  * +----------------------------------------------------------------------+
  * | +------------------------------------------------------------------+ |
@@ -845,3 +1238,40 @@ where
 {
   T::deserialize(value)
 }
+
+/// Interpret a borrowed `sage::DType` as an instance of type `T`.
+///
+/// Unlike [`from_dtype`], strings & bytes in `T` may borrow from `value`
+/// (`&'de str`, `&'de [u8]` or `Cow<'de, str>` with `#[serde(borrow)]`),
+/// so no allocation is made for them.
+///
+/// # Example
+///
+/// ```rust
+/// use serde_derive::Deserialize;
+/// use sage::json;
+///
+/// #[derive(Deserialize)]
+/// struct User<'a> {
+///   fingerprint: &'a str,
+///   location: &'a str,
+/// }
+///
+/// let j = json!({
+///   "fingerprint": "0xF9BA143B95FF6D82",
+///   "location": "Menlo Park, CA",
+/// });
+///
+/// let u: User = sage::from_dtype_ref(&j).unwrap();
+/// assert_eq!(u.location, "Menlo Park, CA");
+/// ```
+///
+/// # Errors
+///
+/// Fails under the same conditions as [`from_dtype`].
+pub fn from_dtype_ref<'de, T>(value: &'de DType) -> Result<T>
+where
+  T: Deserialize<'de>,
+{
+  T::deserialize(value)
+}
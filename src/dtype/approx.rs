@@ -0,0 +1,116 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::time::Duration;
+
+use crate::dtype::DType;
+
+/// `Tolerance` configures `DType::approx_eq`.
+///
+/// Two numbers are approximately equal if they differ by at most the
+/// `absolute` tolerance, or by at most the `relative` tolerance times the
+/// larger magnitude. Two datetimes are approximately equal if they're at
+/// most `datetime` apart.
+///
+/// By default numbers may differ by `1e-9` (absolute & relative), which
+/// absorbs rounding errors of recomputed floats, and datetimes must be
+/// equal.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use sage::dtype::Tolerance;
+///
+/// let tolerance = Tolerance::new()
+///   .absolute(1e-6)
+///   .relative(0.0)
+///   .datetime(Duration::from_millis(500));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+  absolute: f64,
+  relative: f64,
+  datetime: Duration,
+}
+
+impl Default for Tolerance {
+  fn default() -> Self {
+    Tolerance {
+      absolute: 1e-9,
+      relative: 1e-9,
+      datetime: Duration::ZERO,
+    }
+  }
+}
+
+impl Tolerance {
+  /// Creates the default tolerance.
+  pub fn new() -> Self {
+    Tolerance::default()
+  }
+
+  /// Sets the largest absolute difference between two numbers.
+  pub fn absolute(mut self, epsilon: f64) -> Self {
+    self.absolute = epsilon;
+    self
+  }
+
+  /// Sets the largest difference between two numbers relative to the
+  /// larger magnitude, e.g. `0.01` for 1%.
+  pub fn relative(mut self, epsilon: f64) -> Self {
+    self.relative = epsilon;
+    self
+  }
+
+  /// Sets the largest difference between two datetimes.
+  pub fn datetime(mut self, tolerance: Duration) -> Self {
+    self.datetime = tolerance;
+    self
+  }
+}
+
+pub(crate) fn approx_eq(a: &DType, b: &DType, tolerance: &Tolerance) -> bool {
+  match (a, b) {
+    (DType::Number(x), DType::Number(y)) => match (x.as_f64(), y.as_f64()) {
+      (Some(x), Some(y)) => {
+        let difference = (x - y).abs();
+        difference <= tolerance.absolute
+          || difference <= tolerance.relative * x.abs().max(y.abs())
+      }
+      _ => x == y,
+    },
+    (DType::DateTime(x), DType::DateTime(y)) => {
+      let difference = (*x.as_chrono() - *y.as_chrono()).abs();
+      difference
+        .to_std()
+        .is_ok_and(|difference| difference <= tolerance.datetime)
+    }
+    (DType::Array(x), DType::Array(y)) => {
+      x.len() == y.len()
+        && x
+          .iter()
+          .zip(y.iter())
+          .all(|(x, y)| approx_eq(x, y, tolerance))
+    }
+    (DType::Object(x), DType::Object(y)) => {
+      x.len() == y.len()
+        && x.iter().all(|(key, x)| {
+          y.get(key).is_some_and(|y| approx_eq(x, y, tolerance))
+        })
+    }
+    (a, b) => a == b,
+  }
+}
@@ -0,0 +1,256 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fluent builders for `DType` objects & arrays.
+//!
+//! For values whose shape is only known at runtime (dynamic keys, loops,
+//! optional fields) where the `json!` macro can't be used. Values are moved
+//! into the tree as they're added, nothing is cloned.
+
+use crate::{DType, Map};
+
+/// Entry point of the `DType` builders.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::{json, DTypeBuilder};
+///
+/// let languages = vec!["en", "yo"];
+/// let nickname: Option<&str> = None;
+///
+/// let mut person = DTypeBuilder::object()
+///   .field("name", "Ada")
+///   .field_opt("nickname", nickname)
+///   .array_field("languages", languages)
+///   .object_field("address", |address| address.field("city", "Lagos"));
+/// for year in 2020..2022 {
+///   person = person.field(format!("score_{}", year), year - 2000);
+/// }
+///
+/// assert_eq!(
+///   person.build(),
+///   json!({
+///     "name": "Ada",
+///     "languages": ["en", "yo"],
+///     "address": { "city": "Lagos" },
+///     "score_2020": 20,
+///     "score_2021": 21,
+///   })
+/// );
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct DTypeBuilder;
+
+impl DTypeBuilder {
+  /// Starts building an object.
+  pub fn object() -> ObjectBuilder {
+    ObjectBuilder { map: Map::new() }
+  }
+
+  /// Starts building an array.
+  pub fn array() -> ArrayBuilder {
+    ArrayBuilder { values: Vec::new() }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `ObjectBuilder`.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// Builds a `DType::Object`, see [`DTypeBuilder`].
+#[derive(Clone, Debug, Default)]
+pub struct ObjectBuilder {
+  map: Map<String, DType>,
+}
+
+impl ObjectBuilder {
+  /// Sets the `key` field to `value`, replacing any previous value.
+  pub fn field<K, V>(mut self, key: K, value: V) -> Self
+  where
+    K: Into<String>,
+    V: Into<DType>,
+  {
+    self.map.insert(key.into(), value.into());
+    self
+  }
+
+  /// Sets the `key` field to `value` if it's `Some`, leaves it out if not.
+  pub fn field_opt<K, V>(self, key: K, value: Option<V>) -> Self
+  where
+    K: Into<String>,
+    V: Into<DType>,
+  {
+    match value {
+      Some(value) => self.field(key, value),
+      None => self,
+    }
+  }
+
+  /// Sets the `key` field to an array of `values`.
+  pub fn array_field<K, I>(self, key: K, values: I) -> Self
+  where
+    K: Into<String>,
+    I: IntoIterator,
+    I::Item: Into<DType>,
+  {
+    let array = values
+      .into_iter()
+      .fold(DTypeBuilder::array(), ArrayBuilder::push);
+    self.field(key, array.build())
+  }
+
+  /// Sets the `key` field to the object built by `build`.
+  pub fn object_field<K, F>(self, key: K, build: F) -> Self
+  where
+    K: Into<String>,
+    F: FnOnce(ObjectBuilder) -> ObjectBuilder,
+  {
+    self.field(key, build(DTypeBuilder::object()).build())
+  }
+
+  /// Returns true if the object has a `key` field.
+  pub fn contains_key(&self, key: &str) -> bool {
+    self.map.contains_key(key)
+  }
+
+  /// Returns the built object.
+  pub fn build(self) -> DType {
+    DType::Object(self.map)
+  }
+}
+
+impl<K, V> Extend<(K, V)> for ObjectBuilder
+where
+  K: Into<String>,
+  V: Into<DType>,
+{
+  fn extend<T>(&mut self, fields: T)
+  where
+    T: IntoIterator<Item = (K, V)>,
+  {
+    self
+      .map
+      .extend(fields.into_iter().map(|(k, v)| (k.into(), v.into())));
+  }
+}
+
+impl From<ObjectBuilder> for DType {
+  fn from(builder: ObjectBuilder) -> DType {
+    builder.build()
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `ArrayBuilder`.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// Builds a `DType::Array`, see [`DTypeBuilder`].
+///
+/// # Example
+///
+/// ```rust
+/// use sage::{json, DTypeBuilder};
+///
+/// let rows = DTypeBuilder::array()
+///   .push(1)
+///   .push_opt(None::<i32>)
+///   .array(|row| row.push("a").push("b"))
+///   .object(|cell| cell.field("id", 7));
+///
+/// assert_eq!(rows.build(), json!([1, ["a", "b"], { "id": 7 }]));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ArrayBuilder {
+  values: Vec<DType>,
+}
+
+impl ArrayBuilder {
+  /// Appends `value`.
+  pub fn push<V>(mut self, value: V) -> Self
+  where
+    V: Into<DType>,
+  {
+    self.values.push(value.into());
+    self
+  }
+
+  /// Appends `value` if it's `Some`.
+  pub fn push_opt<V>(self, value: Option<V>) -> Self
+  where
+    V: Into<DType>,
+  {
+    match value {
+      Some(value) => self.push(value),
+      None => self,
+    }
+  }
+
+  /// Appends the array built by `build`.
+  pub fn array<F>(self, build: F) -> Self
+  where
+    F: FnOnce(ArrayBuilder) -> ArrayBuilder,
+  {
+    self.push(build(DTypeBuilder::array()).build())
+  }
+
+  /// Appends the object built by `build`.
+  pub fn object<F>(self, build: F) -> Self
+  where
+    F: FnOnce(ObjectBuilder) -> ObjectBuilder,
+  {
+    self.push(build(DTypeBuilder::object()).build())
+  }
+
+  /// Returns the number of values appended so far.
+  pub fn len(&self) -> usize {
+    self.values.len()
+  }
+
+  /// Returns true if no value was appended yet.
+  pub fn is_empty(&self) -> bool {
+    self.values.is_empty()
+  }
+
+  /// Returns the built array.
+  pub fn build(self) -> DType {
+    DType::Array(Box::new(self.values))
+  }
+}
+
+impl<V> Extend<V> for ArrayBuilder
+where
+  V: Into<DType>,
+{
+  fn extend<T>(&mut self, values: T)
+  where
+    T: IntoIterator<Item = V>,
+  {
+    self.values.extend(values.into_iter().map(Into::into));
+  }
+}
+
+impl From<ArrayBuilder> for DType {
+  fn from(builder: ArrayBuilder) -> DType {
+    builder.build()
+  }
+}
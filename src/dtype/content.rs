@@ -0,0 +1,80 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sha2::{Digest, Sha256};
+
+use crate::{datastore::blob::to_hex, dtype::DType};
+
+/// Prefix of every content hash, bumped whenever the encoding changes.
+const VERSION: &[u8] = b"sage-content-v1";
+
+/// Computes the SHA-256 of the canonical encoding of `value`.
+///
+/// Every value is written as a one-byte tag followed by its content;
+/// strings & collections are prefixed with their length so that no two
+/// values share an encoding. Object entries are written in key order, so
+/// the result doesn't depend on the `preserve_order` feature.
+pub(crate) fn content_hash(value: &DType) -> String {
+  let mut hasher = Sha256::new();
+  hasher.update(VERSION);
+  encode(value, &mut hasher);
+  to_hex(&hasher.finalize())
+}
+
+fn encode(value: &DType, hasher: &mut Sha256) {
+  match value {
+    DType::Null => hasher.update(b"n"),
+    DType::Boolean(true) => hasher.update(b"t"),
+    DType::Boolean(false) => hasher.update(b"f"),
+    DType::Number(n) if n.is_f64() => {
+      // `-0.0 == 0.0`, so both must encode the same.
+      match n.as_f64() {
+        Some(0.0) => bytes(b'd', b"0.0", hasher),
+        _ => bytes(b'd', n.to_string().as_bytes(), hasher),
+      }
+    }
+    DType::Number(n) => bytes(b'i', n.to_string().as_bytes(), hasher),
+    DType::String(s) => bytes(b's', s.as_bytes(), hasher),
+    DType::Bytes(b) => bytes(b'b', b, hasher),
+    DType::DateTime(d) => {
+      let d = d
+        .as_chrono()
+        .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true);
+      bytes(b'T', d.as_bytes(), hasher)
+    }
+    DType::Array(values) => {
+      hasher.update(b"a");
+      hasher.update((values.len() as u64).to_be_bytes());
+      for value in values.iter() {
+        encode(value, hasher);
+      }
+    }
+    DType::Object(map) => {
+      let mut entries: Vec<_> = map.iter().collect();
+      entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+      hasher.update(b"o");
+      hasher.update((entries.len() as u64).to_be_bytes());
+      for (key, value) in entries {
+        bytes(b's', key.as_bytes(), hasher);
+        encode(value, hasher);
+      }
+    }
+  }
+}
+
+fn bytes(tag: u8, bytes: &[u8], hasher: &mut Sha256) {
+  hasher.update([tag]);
+  hasher.update((bytes.len() as u64).to_be_bytes());
+  hasher.update(bytes);
+}
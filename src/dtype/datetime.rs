@@ -17,9 +17,16 @@
 //! By default `sage::DType::DateTime` uses Utc timezone.
 //!
 
+pub mod serde;
+
 // Confusing `sage::DateTime` & `chrono::DateTime`.
 use chrono::{prelude::*, DateTime as ChronoDateTime};
 
+/// Name of the newtype struct `DType::DateTime`s are serialized as, so
+/// serializers which know it can pick their own representation (see
+/// `sage::json::SerializeConfig`). Others see the RFC 3339 string inside.
+pub(crate) const TOKEN: &str = "$sage::dtype::DateTime";
+
 /*
 * +----------------------------------------------------------------------+
 * | +------------------------------------------------------------------+ |
@@ -28,10 +35,41 @@ use chrono::{prelude::*, DateTime as ChronoDateTime};
 * +----------------------------------------------------------------------+
 */
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct DateTime {
   d: DateTimeImpl,
 }
 
 // Default timezone is Utc.
 type DateTimeImpl = ChronoDateTime<Utc>;
+
+impl DateTime {
+  /// Returns a reference to the underlying `chrono::DateTime<Utc>`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use chrono::{TimeZone, Utc};
+  /// use sage::DateTime;
+  ///
+  /// let utc = Utc.with_ymd_and_hms(2021, 3, 14, 15, 9, 26).unwrap();
+  /// let d = DateTime::from(utc);
+  ///
+  /// assert_eq!(d.as_chrono(), &utc);
+  /// ```
+  pub fn as_chrono(&self) -> &DateTimeImpl {
+    &self.d
+  }
+}
+
+impl From<DateTimeImpl> for DateTime {
+  fn from(d: DateTimeImpl) -> DateTime {
+    DateTime { d }
+  }
+}
+
+impl From<DateTime> for DateTimeImpl {
+  fn from(d: DateTime) -> DateTimeImpl {
+    d.d
+  }
+}
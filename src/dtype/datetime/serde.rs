@@ -0,0 +1,210 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `#[serde(with = "...")]` helpers for datetime fields.
+//!
+//! Each module works with both `sage::DateTime` & `chrono::DateTime<Utc>`
+//! fields.
+//!
+//! | Module              | Representation                      |
+//! |---------------------|-------------------------------------|
+//! | `iso8601`           | `"2021-03-14T15:09:26Z"`            |
+//! | `rfc2822`           | `"Sun, 14 Mar 2021 15:09:26 +0000"` |
+//! | `timestamp_seconds` | `1615734566`                        |
+//! | `timestamp_millis`  | `1615734566000`                     |
+//!
+//! # Example
+//!
+//! ```rust
+//! use chrono::{DateTime, TimeZone, Utc};
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize, Debug, PartialEq)]
+//! struct Event {
+//!   #[serde(with = "sage::datetime::serde::iso8601")]
+//!   starts: DateTime<Utc>,
+//!   #[serde(with = "sage::datetime::serde::timestamp_millis")]
+//!   created: sage::DateTime,
+//! }
+//!
+//! let when = Utc.with_ymd_and_hms(2021, 3, 14, 15, 9, 26).unwrap();
+//! let event = Event { starts: when, created: when.into() };
+//!
+//! let text = sage::json::to_string(&event).unwrap();
+//! assert_eq!(
+//!   text,
+//!   r#"{"starts":"2021-03-14T15:09:26Z","created":1615734566000}"#
+//! );
+//! assert_eq!(sage::json::from_str::<Event>(&text).unwrap(), event);
+//! ```
+
+use chrono::{DateTime as ChronoDateTime, Utc};
+
+use crate::dtype::DateTime;
+
+/// Datetime types the helpers in this module can (de)serialize.
+pub trait DateTimeField: Sized {
+  /// Returns the value as a `chrono::DateTime<Utc>`.
+  fn to_utc(&self) -> ChronoDateTime<Utc>;
+
+  /// Creates the value from a `chrono::DateTime<Utc>`.
+  fn from_utc(d: ChronoDateTime<Utc>) -> Self;
+}
+
+impl DateTimeField for DateTime {
+  fn to_utc(&self) -> ChronoDateTime<Utc> {
+    *self.as_chrono()
+  }
+
+  fn from_utc(d: ChronoDateTime<Utc>) -> Self {
+    d.into()
+  }
+}
+
+impl DateTimeField for ChronoDateTime<Utc> {
+  fn to_utc(&self) -> ChronoDateTime<Utc> {
+    *self
+  }
+
+  fn from_utc(d: ChronoDateTime<Utc>) -> Self {
+    d
+  }
+}
+
+/// ISO 8601 / RFC 3339 strings, e.g. `"2021-03-14T15:09:26Z"`.
+///
+/// Deserialization accepts any UTC offset, as well as datetimes without an
+/// offset which are taken to be in UTC.
+pub mod iso8601 {
+  use ::serde::{de::Error, Deserialize, Deserializer, Serializer};
+  use chrono::{NaiveDateTime, SecondsFormat, Utc};
+
+  use super::DateTimeField;
+
+  /// Serializes `value` as an RFC 3339 string.
+  pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    T: DateTimeField,
+    S: Serializer,
+  {
+    let text = value.to_utc().to_rfc3339_opts(SecondsFormat::AutoSi, true);
+    serializer.serialize_str(&text)
+  }
+
+  /// Deserializes an ISO 8601 string.
+  pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+  where
+    T: DateTimeField,
+    D: Deserializer<'de>,
+  {
+    let text = String::deserialize(deserializer)?;
+    match chrono::DateTime::parse_from_rfc3339(&text) {
+      Ok(d) => Ok(T::from_utc(d.with_timezone(&Utc))),
+      Err(_) => NaiveDateTime::parse_from_str(&text, "%Y-%m-%dT%H:%M:%S%.f")
+        .map(|d| T::from_utc(d.and_utc()))
+        .map_err(|err| D::Error::custom(format_args!("{}: {:?}", err, text))),
+    }
+  }
+}
+
+/// RFC 2822 strings, e.g. `"Sun, 14 Mar 2021 15:09:26 +0000"`.
+pub mod rfc2822 {
+  use ::serde::{de::Error, Deserialize, Deserializer, Serializer};
+  use chrono::Utc;
+
+  use super::DateTimeField;
+
+  /// Serializes `value` as an RFC 2822 string.
+  pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    T: DateTimeField,
+    S: Serializer,
+  {
+    serializer.serialize_str(&value.to_utc().to_rfc2822())
+  }
+
+  /// Deserializes an RFC 2822 string.
+  pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+  where
+    T: DateTimeField,
+    D: Deserializer<'de>,
+  {
+    let text = String::deserialize(deserializer)?;
+    chrono::DateTime::parse_from_rfc2822(&text)
+      .map(|d| T::from_utc(d.with_timezone(&Utc)))
+      .map_err(|err| D::Error::custom(format_args!("{}: {:?}", err, text)))
+  }
+}
+
+/// Whole seconds since the Unix epoch, e.g. `1615734566`.
+pub mod timestamp_seconds {
+  use ::serde::{de::Error, Deserialize, Deserializer, Serializer};
+  use chrono::{TimeZone, Utc};
+
+  use super::DateTimeField;
+
+  /// Serializes `value` as seconds since the Unix epoch.
+  pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    T: DateTimeField,
+    S: Serializer,
+  {
+    serializer.serialize_i64(value.to_utc().timestamp())
+  }
+
+  /// Deserializes seconds since the Unix epoch.
+  pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+  where
+    T: DateTimeField,
+    D: Deserializer<'de>,
+  {
+    let secs = i64::deserialize(deserializer)?;
+    Utc
+      .timestamp_opt(secs, 0)
+      .single()
+      .map(T::from_utc)
+      .ok_or_else(|| D::Error::custom("timestamp out of range"))
+  }
+}
+
+/// Milliseconds since the Unix epoch, e.g. `1615734566000`.
+pub mod timestamp_millis {
+  use ::serde::{de::Error, Deserialize, Deserializer, Serializer};
+  use chrono::{TimeZone, Utc};
+
+  use super::DateTimeField;
+
+  /// Serializes `value` as milliseconds since the Unix epoch.
+  pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    T: DateTimeField,
+    S: Serializer,
+  {
+    serializer.serialize_i64(value.to_utc().timestamp_millis())
+  }
+
+  /// Deserializes milliseconds since the Unix epoch.
+  pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+  where
+    T: DateTimeField,
+    D: Deserializer<'de>,
+  {
+    let millis = i64::deserialize(deserializer)?;
+    Utc
+      .timestamp_millis_opt(millis)
+      .single()
+      .map(T::from_utc)
+      .ok_or_else(|| D::Error::custom("timestamp out of range"))
+  }
+}
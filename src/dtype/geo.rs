@@ -0,0 +1,287 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Geographic literals: [WKT] strings, [GeoJSON] geometries & schema.org
+//! `GeoCoordinates`, all using WGS 84 longitude/latitude in degrees.
+//!
+//! [WKT]: https://en.wikipedia.org/wiki/Well-known_text_representation_of_geometry
+//! [GeoJSON]: https://datatracker.ietf.org/doc/html/rfc7946
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+  dtype::{DType, Map},
+  error::{Error, ErrorCode},
+};
+
+/// Mean earth radius in meters, as used by the haversine formula.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Point
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Point` is a WGS 84 position in degrees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Point {
+  /// Longitude, east of Greenwich is positive.
+  pub lon: f64,
+  /// Latitude, north of the equator is positive.
+  pub lat: f64,
+}
+
+impl Point {
+  /// Creates a point from longitude & latitude (GeoJSON & WKT order).
+  pub fn new(lon: f64, lat: f64) -> Point {
+    Point { lon, lat }
+  }
+
+  /// Returns the great-circle distance to `other` in meters.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::dtype::Point;
+  ///
+  /// let london = Point::new(-0.1276, 51.5072);
+  /// let paris = Point::new(2.3522, 48.8566);
+  ///
+  /// let km = london.distance(&paris) / 1000.0;
+  /// assert!((km - 343.5).abs() < 1.0);
+  /// ```
+  pub fn distance(&self, other: &Point) -> f64 {
+    let (lat1, lat2) = (self.lat.to_radians(), other.lat.to_radians());
+    let dlat = lat2 - lat1;
+    let dlon = (other.lon - self.lon).to_radians();
+    let a = (dlat / 2.0).sin().powi(2)
+      + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS * a.sqrt().asin()
+  }
+
+  fn is_valid(&self) -> bool {
+    (-180.0..=180.0).contains(&self.lon) && (-90.0..=90.0).contains(&self.lat)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Geo
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Geo` is a geometry recognized in a literal.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::dtype::{Geo, Point};
+/// use sage::json;
+///
+/// let wkt: Geo = "POINT(-0.1276 51.5072)".parse().unwrap();
+/// let geojson =
+///   Geo::from_dtype(&json!({ "type": "Point", "coordinates": [-0.1276, 51.5072] }));
+/// let schema =
+///   Geo::from_dtype(&json!({ "latitude": 51.5072, "longitude": -0.1276 }));
+///
+/// assert_eq!(Some(wkt.clone()), geojson);
+/// assert_eq!(Some(wkt.clone()), schema);
+/// assert_eq!(wkt.to_string(), "POINT(-0.1276 51.5072)");
+/// assert_eq!(wkt.to_geojson()["type"], "Point");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Geo {
+  /// A single position.
+  Point(Point),
+  /// A line through the positions.
+  LineString(Vec<Point>),
+  /// An outer ring followed by its holes, each closed.
+  Polygon(Vec<Vec<Point>>),
+}
+
+impl Geo {
+  /// Recognizes a geometry in `value`: a WKT string, a GeoJSON geometry or
+  /// a schema.org `GeoCoordinates` object (`latitude` & `longitude`).
+  pub fn from_dtype(value: &DType) -> Option<Geo> {
+    match value {
+      DType::String(s) => s.parse().ok(),
+      DType::Object(map) => {
+        if let Some(value) = map.get("@value") {
+          return Geo::from_dtype(value);
+        }
+        match (map.get("latitude"), map.get("longitude")) {
+          (Some(lat), Some(lon)) => {
+            let point = Point::new(number(lon)?, number(lat)?);
+            point.is_valid().then_some(Geo::Point(point))
+          }
+          _ => from_geojson(map),
+        }
+      }
+      _ => None,
+    }
+  }
+
+  /// Returns a point representing the geometry: the point itself or the
+  /// average of the (outer ring) positions.
+  pub fn centroid(&self) -> Point {
+    let points: &[Point] = match self {
+      Geo::Point(point) => return *point,
+      Geo::LineString(points) => points,
+      Geo::Polygon(rings) => {
+        let ring = rings.first().map_or(&[][..], Vec::as_slice);
+        // The closing position repeats the first one.
+        if ring.len() > 1 && ring.first() == ring.last() {
+          &ring[..ring.len() - 1]
+        } else {
+          ring
+        }
+      }
+    };
+    let n = points.len().max(1) as f64;
+    Point::new(
+      points.iter().map(|p| p.lon).sum::<f64>() / n,
+      points.iter().map(|p| p.lat).sum::<f64>() / n,
+    )
+  }
+
+  /// Returns the geometry as a GeoJSON object.
+  pub fn to_geojson(&self) -> DType {
+    let position = |p: &Point| DType::from(vec![p.lon, p.lat]);
+    let (kind, coordinates) = match self {
+      Geo::Point(p) => ("Point", position(p)),
+      Geo::LineString(points) => {
+        ("LineString", points.iter().map(position).collect())
+      }
+      Geo::Polygon(rings) => (
+        "Polygon",
+        rings
+          .iter()
+          .map(|ring| ring.iter().map(position).collect::<DType>())
+          .collect(),
+      ),
+    };
+    let mut map = Map::new();
+    map.insert("type".to_string(), kind.into());
+    map.insert("coordinates".to_string(), coordinates);
+    DType::Object(map)
+  }
+}
+
+impl FromStr for Geo {
+  type Err = Error;
+
+  /// Parses a WKT `POINT`, `LINESTRING` or `POLYGON`.
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let err = || Error::syntax(ErrorCode::InvalidGeo, 0, 0);
+    let s = s.trim();
+    let open = s.find('(').ok_or_else(err)?;
+    let body = s[open..].strip_suffix(')').ok_or_else(err)?;
+    let body = &body[1..];
+
+    match s[..open].trim().to_ascii_uppercase().as_str() {
+      "POINT" => match positions(body).ok_or_else(err)?.as_slice() {
+        [point] => Ok(Geo::Point(*point)),
+        _ => Err(err()),
+      },
+      "LINESTRING" => Ok(Geo::LineString(positions(body).ok_or_else(err)?)),
+      "POLYGON" => {
+        let rings = body
+          .split(')')
+          .map(|ring| ring.trim_start_matches([',', ' ']).trim())
+          .filter(|ring| !ring.is_empty())
+          .map(|ring| positions(ring.strip_prefix('(')?))
+          .collect::<Option<Vec<_>>>()
+          .ok_or_else(err)?;
+        if rings.is_empty() {
+          return Err(err());
+        }
+        Ok(Geo::Polygon(rings))
+      }
+      _ => Err(err()),
+    }
+  }
+}
+
+impl fmt::Display for Geo {
+  /// Formats the geometry as WKT.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let join = |points: &[Point]| {
+      points
+        .iter()
+        .map(|p| format!("{} {}", p.lon, p.lat))
+        .collect::<Vec<_>>()
+        .join(", ")
+    };
+    match self {
+      Geo::Point(p) => write!(f, "POINT({} {})", p.lon, p.lat),
+      Geo::LineString(points) => write!(f, "LINESTRING({})", join(points)),
+      Geo::Polygon(rings) => {
+        let rings: Vec<String> =
+          rings.iter().map(|r| format!("({})", join(r))).collect();
+        write!(f, "POLYGON({})", rings.join(", "))
+      }
+    }
+  }
+}
+
+/// Parses comma separated `lon lat` positions.
+fn positions(s: &str) -> Option<Vec<Point>> {
+  s.split(',')
+    .map(|position| {
+      let mut parts = position.split_whitespace().map(str::parse::<f64>);
+      let point = Point::new(parts.next()?.ok()?, parts.next()?.ok()?);
+      point.is_valid().then_some(point)
+    })
+    .collect()
+}
+
+fn number(value: &DType) -> Option<f64> {
+  match value {
+    DType::String(s) => s.trim().parse().ok(),
+    value => value.as_f64(),
+  }
+}
+
+fn from_geojson(map: &Map<String, DType>) -> Option<Geo> {
+  let position = |value: &DType| -> Option<Point> {
+    let coordinates = value.as_array()?;
+    let point = Point::new(
+      coordinates.first()?.as_f64()?,
+      coordinates.get(1)?.as_f64()?,
+    );
+    point.is_valid().then_some(point)
+  };
+  let line = |value: &DType| -> Option<Vec<Point>> {
+    value.as_array()?.iter().map(position).collect()
+  };
+
+  let coordinates = map.get("coordinates")?;
+  match map.get("type")?.as_str()? {
+    "Point" => Some(Geo::Point(position(coordinates)?)),
+    "LineString" => Some(Geo::LineString(line(coordinates)?)),
+    "Polygon" => Some(Geo::Polygon(
+      coordinates
+        .as_array()?
+        .iter()
+        .map(line)
+        .collect::<Option<_>>()?,
+    )),
+    _ => None,
+  }
+}
@@ -0,0 +1,291 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Non-string keys for `sage::Map`.
+//!
+//! JSON object keys are always strings, so a [`Key`] is encoded as one with
+//! deterministic rules:
+//!
+//! | Key                 | Encoding                                       |
+//! |---------------------|------------------------------------------------|
+//! | `Key::Integer(-42)` | `"-42"`                                        |
+//! | `Key::DateTime(..)` | RFC 3339 in UTC, e.g. `"2021-03-14T15:09:26Z"` |
+//! | `Key::String(..)`   | the string itself                              |
+//!
+//! Decoding ([`Key::parse`]) reverses this: a string in the exact form an
+//! integer or datetime is encoded to decodes to that integer or datetime,
+//! anything else (`"007"`, `"+1"`, `"2021-03-14"`) stays a string. So
+//! encoding a decoded key always gives back the original string.
+
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime as ChronoDateTime, SecondsFormat, Utc};
+use serde::{de, ser};
+
+use crate::DateTime;
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `Key`.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// A key of a `Map<Key, DType>`, for maps keyed by numbers or datetimes such
+/// as columnar & temporal indexes.
+///
+/// Keys order integers first, then datetimes, then strings, so a `BTreeMap`
+/// backed map iterates integer & datetime keys chronologically rather than
+/// in the lexical order of their encoding.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::{json, DType, Key, Map};
+///
+/// let mut index = Map::<Key, DType>::default();
+/// index.insert(Key::from(9), json!("nine"));
+/// index.insert(Key::from(10), json!("ten"));
+///
+/// let encoded = json::to_string(&index).unwrap();
+/// assert_eq!(encoded, r#"{"9":"nine","10":"ten"}"#);
+///
+/// let decoded: Map<Key, DType> = json::from_str(&encoded).unwrap();
+/// assert_eq!(decoded, index);
+/// assert_eq!(decoded[&Key::Integer(9)], json!("nine"));
+/// ```
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Key {
+  /// A signed integer key.
+  Integer(i64),
+  /// A datetime key.
+  DateTime(DateTime),
+  /// A string key.
+  String(String),
+}
+
+impl Key {
+  /// Decodes a key from its string encoding.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::Key;
+  ///
+  /// assert_eq!(Key::parse("-42"), Key::Integer(-42));
+  /// assert!(Key::parse("2021-03-14T15:09:26Z").is_datetime());
+  /// // Not the exact form integers & datetimes are encoded to.
+  /// assert_eq!(Key::parse("007"), Key::from("007"));
+  /// assert_eq!(Key::parse("2021-03-14"), Key::from("2021-03-14"));
+  /// ```
+  pub fn parse(s: &str) -> Key {
+    if let Ok(integer) = s.parse::<i64>() {
+      if integer.to_string() == s {
+        return Key::Integer(integer);
+      }
+    }
+    if let Ok(d) = ChronoDateTime::parse_from_rfc3339(s) {
+      let d = d.with_timezone(&Utc);
+      if encode_datetime(&d) == s {
+        return Key::DateTime(d.into());
+      }
+    }
+    Key::String(s.to_string())
+  }
+
+  /// Returns true if the key is an integer.
+  pub fn is_integer(&self) -> bool {
+    self.as_i64().is_some()
+  }
+
+  /// Returns the integer if the key is one.
+  pub fn as_i64(&self) -> Option<i64> {
+    match self {
+      Key::Integer(i) => Some(*i),
+      _ => None,
+    }
+  }
+
+  /// Returns true if the key is a datetime.
+  pub fn is_datetime(&self) -> bool {
+    self.as_datetime().is_some()
+  }
+
+  /// Returns the datetime if the key is one.
+  pub fn as_datetime(&self) -> Option<&DateTime> {
+    match self {
+      Key::DateTime(d) => Some(d),
+      _ => None,
+    }
+  }
+
+  /// Returns true if the key is a string.
+  pub fn is_string(&self) -> bool {
+    self.as_str().is_some()
+  }
+
+  /// Returns the string if the key is one.
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Key::String(s) => Some(s),
+      _ => None,
+    }
+  }
+}
+
+fn encode_datetime(d: &ChronoDateTime<Utc>) -> String {
+  d.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Writes the string encoding of the key.
+impl fmt::Display for Key {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Key::Integer(i) => write!(f, "{}", i),
+      Key::DateTime(d) => f.write_str(&encode_datetime(d.as_chrono())),
+      Key::String(s) => f.write_str(s),
+    }
+  }
+}
+
+impl FromStr for Key {
+  type Err = std::convert::Infallible;
+
+  /// Same as [`Key::parse`].
+  fn from_str(s: &str) -> Result<Key, Self::Err> {
+    Ok(Key::parse(s))
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Conversions.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+macro_rules! from_integer {
+  ($($ty:ty)*) => {
+    $(
+      impl From<$ty> for Key {
+        fn from(i: $ty) -> Key {
+          Key::Integer(i.into())
+        }
+      }
+    )*
+  };
+}
+
+from_integer!(i8 i16 i32 i64 u8 u16 u32);
+
+impl From<DateTime> for Key {
+  fn from(d: DateTime) -> Key {
+    Key::DateTime(d)
+  }
+}
+
+impl From<ChronoDateTime<Utc>> for Key {
+  fn from(d: ChronoDateTime<Utc>) -> Key {
+    Key::DateTime(d.into())
+  }
+}
+
+/// A string key, without decoding (see [`Key::parse`]).
+impl From<String> for Key {
+  fn from(s: String) -> Key {
+    Key::String(s)
+  }
+}
+
+/// A string key, without decoding (see [`Key::parse`]).
+impl From<&str> for Key {
+  fn from(s: &str) -> Key {
+    Key::String(s.to_string())
+  }
+}
+
+/// Encodes the key as a string.
+impl From<Key> for String {
+  fn from(key: Key) -> String {
+    match key {
+      Key::String(s) => s,
+      key => key.to_string(),
+    }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Serialize & Deserialize.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// Integers are serialized as integers, which JSON quotes as map keys &
+/// binary formats such as CBOR keep as is. Datetimes are serialized as their
+/// RFC 3339 string.
+impl ser::Serialize for Key {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: ser::Serializer,
+  {
+    match self {
+      Key::Integer(i) => serializer.serialize_i64(*i),
+      Key::DateTime(d) => {
+        serializer.serialize_str(&encode_datetime(d.as_chrono()))
+      }
+      Key::String(s) => serializer.serialize_str(s),
+    }
+  }
+}
+
+/// Strings are decoded with [`Key::parse`].
+impl<'de> de::Deserialize<'de> for Key {
+  fn deserialize<D>(deserializer: D) -> Result<Key, D::Error>
+  where
+    D: de::Deserializer<'de>,
+  {
+    struct Visitor;
+
+    impl<'de> de::Visitor<'de> for Visitor {
+      type Value = Key;
+
+      fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an integer, datetime or string key")
+      }
+
+      fn visit_i64<E>(self, i: i64) -> Result<Key, E> {
+        Ok(Key::Integer(i))
+      }
+
+      fn visit_u64<E>(self, u: u64) -> Result<Key, E>
+      where
+        E: de::Error,
+      {
+        i64::try_from(u)
+          .map(Key::Integer)
+          .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(u), &self))
+      }
+
+      fn visit_str<E>(self, s: &str) -> Result<Key, E> {
+        Ok(Key::parse(s))
+      }
+    }
+
+    deserializer.deserialize_any(Visitor)
+  }
+}
@@ -0,0 +1,145 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use crate::{
+  dtype::{DType, Map},
+  error::{Error, ErrorCode},
+  graph::Node,
+  Result,
+};
+
+/// `LangString` is a string tagged with a [BCP 47] language, e.g. `"Hallo"`
+/// in `de`.
+///
+/// Inside a `DType` it's represented like in JSON-LD, as a value object
+/// `{"@value": "Hallo", "@language": "de"}`, so it round-trips through
+/// JSON-LD's `@language` and N-Triples' `"Hallo"@de` unchanged. Tags are
+/// normalized to lower case.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::dtype::LangString;
+/// use sage::json;
+///
+/// let hallo = LangString::new("Hallo", "DE-at").unwrap();
+/// assert_eq!(hallo.language(), "de-at");
+/// assert!(hallo.matches("de"));
+/// assert!(!hallo.matches("en"));
+///
+/// let value = json!({ "@value": "Hallo", "@language": "de-at" });
+/// assert_eq!(sage::DType::from(hallo.clone()), value);
+/// assert_eq!(LangString::from_dtype(&value), Some(hallo));
+///
+/// assert!(LangString::new("Hallo", "not a tag").is_err());
+/// ```
+///
+/// [BCP 47]: https://www.rfc-editor.org/info/bcp47
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LangString {
+  value: String,
+  language: String,
+}
+
+impl LangString {
+  /// Creates a language tagged string, failing if `language` isn't a
+  /// well-formed language tag.
+  pub fn new(value: &str, language: &str) -> Result<LangString> {
+    if !is_language_tag(language) {
+      return Err(Error::syntax(ErrorCode::InvalidLanguageTag, 0, 0));
+    }
+    Ok(LangString {
+      value: value.to_string(),
+      language: language.to_ascii_lowercase(),
+    })
+  }
+
+  /// Reads a JSON-LD value object with a string `@value` & `@language`.
+  pub fn from_dtype(value: &DType) -> Option<LangString> {
+    let map = value.as_object()?;
+    if map.keys().any(|k| k != "@value" && k != "@language") {
+      return None;
+    }
+    let value = map.get("@value")?.as_str()?;
+    let language = map.get("@language")?.as_str()?;
+    LangString::new(value, language).ok()
+  }
+
+  /// Returns the string.
+  pub fn value(&self) -> &str {
+    &self.value
+  }
+
+  /// Returns the lower case language tag.
+  pub fn language(&self) -> &str {
+    &self.language
+  }
+
+  /// Returns `true` if the language matches the [basic language range]
+  /// `range`, like SPARQL's `langMatches`: `"de"` matches `de` & `de-AT`,
+  /// `"*"` matches any language.
+  ///
+  /// [basic language range]: https://www.rfc-editor.org/rfc/rfc4647#section-3.3.1
+  pub fn matches(&self, range: &str) -> bool {
+    language_matches(&self.language, range)
+  }
+}
+
+impl fmt::Display for LangString {
+  /// Formats the string like N-Triples & Turtle, e.g. `"Hallo"@de`.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{:?}@{}", self.value, self.language)
+  }
+}
+
+impl From<LangString> for DType {
+  fn from(s: LangString) -> DType {
+    let mut map = Map::new();
+    map.insert("@value".to_string(), s.value.into());
+    map.insert("@language".to_string(), s.language.into());
+    DType::Object(map)
+  }
+}
+
+impl From<LangString> for Node {
+  fn from(s: LangString) -> Node {
+    Node::Literal(s.into())
+  }
+}
+
+/// Returns `true` if `tag` is a well-formed language tag: alphanumeric
+/// subtags of 1 to 8 characters separated by `-`, starting with letters.
+pub(crate) fn is_language_tag(tag: &str) -> bool {
+  let mut subtags = tag.split('-');
+  let primary = subtags.next().unwrap_or_default();
+  let valid = |s: &str, f: fn(&char) -> bool| {
+    (1..=8).contains(&s.len()) && s.chars().all(|c| f(&c))
+  };
+  valid(primary, char::is_ascii_alphabetic)
+    && subtags.all(|s| valid(s, char::is_ascii_alphanumeric))
+}
+
+/// Basic language range matching (RFC 4647 §3.3.1), case-insensitive.
+pub(crate) fn language_matches(tag: &str, range: &str) -> bool {
+  if range == "*" {
+    return !tag.is_empty();
+  }
+  let (tag, range) = (tag.to_ascii_lowercase(), range.to_ascii_lowercase());
+  tag == range
+    || tag
+      .strip_prefix(range.as_str())
+      .is_some_and(|rest| rest.starts_with('-'))
+}
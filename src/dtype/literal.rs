@@ -0,0 +1,900 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, time::Duration};
+
+use chrono::{
+  DateTime as ChronoDateTime, NaiveDate, NaiveDateTime, NaiveTime,
+  SecondsFormat, TimeZone, Utc,
+};
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+use crate::{
+  datastore::json,
+  dtype::{lang::is_language_tag, DType, DateTime, LangString, Map, Number},
+  error::{Error, ErrorCode},
+  graph::Node,
+  Result,
+};
+
+/// IRIs of the [XML Schema datatypes] used by RDF literals.
+///
+/// [XML Schema datatypes]: https://www.w3.org/TR/xmlschema11-2/
+pub mod xsd {
+  /// The XML Schema namespace.
+  pub const NS: &str = "http://www.w3.org/2001/XMLSchema#";
+
+  pub const STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+  pub const BOOLEAN: &str = "http://www.w3.org/2001/XMLSchema#boolean";
+  pub const DECIMAL: &str = "http://www.w3.org/2001/XMLSchema#decimal";
+  pub const DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
+  pub const FLOAT: &str = "http://www.w3.org/2001/XMLSchema#float";
+
+  pub const INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+  pub const LONG: &str = "http://www.w3.org/2001/XMLSchema#long";
+  pub const INT: &str = "http://www.w3.org/2001/XMLSchema#int";
+  pub const SHORT: &str = "http://www.w3.org/2001/XMLSchema#short";
+  pub const BYTE: &str = "http://www.w3.org/2001/XMLSchema#byte";
+  pub const NON_NEGATIVE_INTEGER: &str =
+    "http://www.w3.org/2001/XMLSchema#nonNegativeInteger";
+  pub const POSITIVE_INTEGER: &str =
+    "http://www.w3.org/2001/XMLSchema#positiveInteger";
+  pub const NON_POSITIVE_INTEGER: &str =
+    "http://www.w3.org/2001/XMLSchema#nonPositiveInteger";
+  pub const NEGATIVE_INTEGER: &str =
+    "http://www.w3.org/2001/XMLSchema#negativeInteger";
+  pub const UNSIGNED_LONG: &str =
+    "http://www.w3.org/2001/XMLSchema#unsignedLong";
+  pub const UNSIGNED_INT: &str = "http://www.w3.org/2001/XMLSchema#unsignedInt";
+  pub const UNSIGNED_SHORT: &str =
+    "http://www.w3.org/2001/XMLSchema#unsignedShort";
+  pub const UNSIGNED_BYTE: &str =
+    "http://www.w3.org/2001/XMLSchema#unsignedByte";
+
+  pub const DATE_TIME: &str = "http://www.w3.org/2001/XMLSchema#dateTime";
+  pub const DATE: &str = "http://www.w3.org/2001/XMLSchema#date";
+  pub const TIME: &str = "http://www.w3.org/2001/XMLSchema#time";
+  pub const DURATION: &str = "http://www.w3.org/2001/XMLSchema#duration";
+
+  pub const BASE64_BINARY: &str =
+    "http://www.w3.org/2001/XMLSchema#base64Binary";
+  pub const HEX_BINARY: &str = "http://www.w3.org/2001/XMLSchema#hexBinary";
+  pub const ANY_URI: &str = "http://www.w3.org/2001/XMLSchema#anyURI";
+}
+
+const RDF_LANG_STRING: &str =
+  "http://www.w3.org/1999/02/22-rdf-syntax-ns#langString";
+const RDF_JSON: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON";
+
+/// Integer datatypes & the bounds of their value space.
+const INTEGERS: &[(&str, i128, i128)] = &[
+  (xsd::INTEGER, i128::MIN, i128::MAX),
+  (xsd::LONG, i64::MIN as i128, i64::MAX as i128),
+  (xsd::INT, i32::MIN as i128, i32::MAX as i128),
+  (xsd::SHORT, i16::MIN as i128, i16::MAX as i128),
+  (xsd::BYTE, i8::MIN as i128, i8::MAX as i128),
+  (xsd::NON_NEGATIVE_INTEGER, 0, i128::MAX),
+  (xsd::POSITIVE_INTEGER, 1, i128::MAX),
+  (xsd::NON_POSITIVE_INTEGER, i128::MIN, 0),
+  (xsd::NEGATIVE_INTEGER, i128::MIN, -1),
+  (xsd::UNSIGNED_LONG, 0, u64::MAX as i128),
+  (xsd::UNSIGNED_INT, 0, u32::MAX as i128),
+  (xsd::UNSIGNED_SHORT, 0, u16::MAX as i128),
+  (xsd::UNSIGNED_BYTE, 0, u8::MAX as i128),
+];
+
+/// `ParseMode` sets how strictly a `Literal` is read into a Rust type.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParseMode {
+  /// The datatype must map to the requested type & the lexical form must
+  /// be valid for the datatype, e.g. `" 42"^^xsd:integer` is rejected.
+  #[default]
+  Strict,
+
+  /// The datatype is ignored & the lexical form only has to parse into
+  /// the requested type: surrounding whitespace is trimmed, booleans &
+  /// special floats are case insensitive, a space may separate the date &
+  /// time, and base64 may be unpadded or contain whitespace.
+  Lenient,
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Literal.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Literal` is an RDF literal: a lexical form with a datatype IRI, and a
+/// language tag for `rdf:langString`s.
+///
+/// Literals convert from Rust values with `From` and into them with
+/// `Literal::value`, which maps XML Schema datatypes as follows:
+///
+/// | Datatype                           | Rust types                     |
+/// |------------------------------------|--------------------------------|
+/// | `xsd:integer` & derived types      | `i8`..`i128`, `u8`..`u128`     |
+/// | `xsd:decimal`, `double` & `float`  | `f64`, `f32`                   |
+/// | `xsd:boolean`                      | `bool`                         |
+/// | `xsd:dateTime`                     | `DateTime`, `chrono::DateTime` |
+/// | `xsd:date` & `xsd:time`            | `NaiveDate` & `NaiveTime`      |
+/// | `xsd:base64Binary` & `hexBinary`   | `Vec<u8>`                      |
+/// | `rdf:langString`                   | `LangString`                   |
+/// | any                                | `String`, `DType`              |
+///
+/// # Example
+///
+/// ```rust
+/// use sage::dtype::{xsd, Literal, ParseMode};
+///
+/// let answer = Literal::new("42", xsd::INTEGER);
+/// assert_eq!(answer.value::<i64>().unwrap(), 42);
+/// assert_eq!(answer.value::<f64>().unwrap(), 42.0);
+/// assert!(answer.value::<bool>().is_err());
+///
+/// // Strict parsing rejects invalid lexical forms, lenient parsing reads
+/// // what it can.
+/// let padded = Literal::new(" 42 ", xsd::INTEGER);
+/// assert!(!padded.is_valid());
+/// assert!(padded.value::<i64>().is_err());
+/// assert_eq!(padded.value_with::<i64>(ParseMode::Lenient).unwrap(), 42);
+///
+/// // Derived types are range checked.
+/// assert!(!Literal::new("300", xsd::UNSIGNED_BYTE).is_valid());
+///
+/// let bytes = Literal::from(&[0xde, 0xad][..]);
+/// assert_eq!(bytes.datatype(), xsd::BASE64_BINARY);
+/// assert_eq!(bytes.lexical(), "3q0=");
+/// assert_eq!(bytes.value::<Vec<u8>>().unwrap(), vec![0xde, 0xad]);
+/// assert_eq!(
+///   bytes.to_string(),
+///   "\"3q0=\"^^<http://www.w3.org/2001/XMLSchema#base64Binary>"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Literal {
+  lexical: String,
+  datatype: String,
+  lang: Option<String>,
+}
+
+impl Literal {
+  /// Creates a literal of `datatype`. The lexical form isn't checked, see
+  /// `Literal::is_valid`.
+  pub fn new(lexical: &str, datatype: &str) -> Literal {
+    Literal {
+      lexical: lexical.to_string(),
+      datatype: datatype.to_string(),
+      lang: None,
+    }
+  }
+
+  /// Creates an `xsd:string` literal.
+  pub fn string(value: &str) -> Literal {
+    Literal::new(value, xsd::STRING)
+  }
+
+  /// Creates an `rdf:langString` literal, failing if `lang` isn't a
+  /// well-formed language tag.
+  pub fn lang_string(value: &str, lang: &str) -> Result<Literal> {
+    if !is_language_tag(lang) {
+      return Err(Error::syntax(ErrorCode::InvalidLanguageTag, 0, 0));
+    }
+    Ok(Literal {
+      lexical: value.to_string(),
+      datatype: RDF_LANG_STRING.to_string(),
+      lang: Some(lang.to_ascii_lowercase()),
+    })
+  }
+
+  /// Returns the lexical form.
+  pub fn lexical(&self) -> &str {
+    &self.lexical
+  }
+
+  /// Returns the datatype IRI.
+  pub fn datatype(&self) -> &str {
+    &self.datatype
+  }
+
+  /// Returns the language tag of `rdf:langString`s.
+  pub fn lang(&self) -> Option<&str> {
+    self.lang.as_deref()
+  }
+
+  /// Returns `true` if the lexical form is valid for the datatype. Unknown
+  /// datatypes accept any lexical form.
+  pub fn is_valid(&self) -> bool {
+    let s = self.lexical.as_str();
+    match self.datatype.as_str() {
+      RDF_LANG_STRING => self.lang.is_some(),
+      _ if self.lang.is_some() => false,
+      xsd::BOOLEAN => matches!(s, "true" | "false" | "1" | "0"),
+      xsd::DECIMAL => is_decimal(s),
+      xsd::DOUBLE | xsd::FLOAT => is_double(s),
+      xsd::DATE_TIME => date_time(s, ParseMode::Strict).is_some(),
+      xsd::DATE => date(s).is_some(),
+      xsd::TIME => time(s).is_some(),
+      xsd::BASE64_BINARY => base64(s, ParseMode::Strict).is_some(),
+      xsd::HEX_BINARY => hex(s).is_some(),
+      #[cfg(feature = "url")]
+      xsd::ANY_URI => uri_reference(s).is_some(),
+      RDF_JSON => json::from_str::<DType>(s).is_ok(),
+      datatype => match integer_bounds(datatype) {
+        Some((min, max)) => {
+          is_integer(s) && (min..=max).contains(&saturating_integer(s))
+        }
+        None => true,
+      },
+    }
+  }
+
+  /// Reads the literal into `T`, strictly.
+  pub fn value<T: FromLiteral>(&self) -> Result<T> {
+    T::from_literal(self, ParseMode::Strict)
+  }
+
+  /// Reads the literal into `T` with the given `mode`.
+  pub fn value_with<T: FromLiteral>(&self, mode: ParseMode) -> Result<T> {
+    T::from_literal(self, mode)
+  }
+
+  /// Converts the literal into the `DType` it's stored as in a
+  /// `KnowledgeGraph`: strings, numbers, booleans, binaries, date times,
+  /// JSON & language tagged strings natively, any other literal (including
+  /// the invalid ones in lenient mode) as a JSON-LD value object with an
+  /// `@type`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::dtype::{xsd, Literal, ParseMode};
+  /// use sage::json;
+  ///
+  /// let mode = ParseMode::Strict;
+  /// let answer = Literal::new("42", xsd::INTEGER);
+  /// assert_eq!(answer.to_dtype(mode).unwrap(), json!(42));
+  ///
+  /// let date = Literal::new("1815-12-10", xsd::DATE);
+  /// assert_eq!(
+  ///   date.to_dtype(mode).unwrap(),
+  ///   json!({ "@value": "1815-12-10", "@type": xsd::DATE })
+  /// );
+  /// let value = date.to_dtype(mode).unwrap();
+  /// assert_eq!(Literal::from_dtype(&value), Some(date));
+  /// ```
+  pub fn to_dtype(&self, mode: ParseMode) -> Result<DType> {
+    if mode == ParseMode::Strict && !self.is_valid() {
+      return Err(invalid_literal());
+    }
+    let value = match self.datatype.as_str() {
+      xsd::STRING => Some(DType::String(self.lexical.clone())),
+      RDF_LANG_STRING => {
+        self.value_with::<LangString>(mode).ok().map(DType::from)
+      }
+      xsd::BOOLEAN => self.value_with::<bool>(mode).ok().map(DType::from),
+      xsd::DECIMAL | xsd::DOUBLE | xsd::FLOAT => self
+        .value_with::<f64>(mode)
+        .ok()
+        .and_then(Number::from_f64)
+        .map(DType::Number),
+      xsd::DATE_TIME => {
+        self.value_with::<DateTime>(mode).ok().map(DType::DateTime)
+      }
+      xsd::BASE64_BINARY | xsd::HEX_BINARY => self
+        .value_with::<Vec<u8>>(mode)
+        .ok()
+        .map(|b| DType::Bytes(Box::new(b))),
+      RDF_JSON => json::from_str(self.lexical.trim()).ok(),
+      datatype if integer_bounds(datatype).is_some() => self
+        .value_with::<i64>(mode)
+        .map(DType::from)
+        .or_else(|_| self.value_with::<u64>(mode).map(DType::from))
+        .ok(),
+      _ => None,
+    };
+    Ok(value.unwrap_or_else(|| {
+      let mut map = Map::new();
+      map.insert("@value".to_string(), self.lexical.clone().into());
+      map.insert("@type".to_string(), self.datatype.clone().into());
+      DType::Object(map)
+    }))
+  }
+
+  /// Reads the literal a `DType` stands for, the reverse of
+  /// `Literal::to_dtype`. Arrays & objects other than value objects become
+  /// `rdf:JSON` literals; `null` has none.
+  pub fn from_dtype(value: &DType) -> Option<Literal> {
+    let literal = match value {
+      DType::Null => return None,
+      DType::String(s) => Literal::string(s),
+      DType::Boolean(b) => Literal::from(*b),
+      DType::Bytes(b) => Literal::from(b.as_slice()),
+      DType::Number(n) => match n.as_f64() {
+        Some(f) if n.is_f64() => Literal::from(f),
+        _ => Literal::new(&n.to_string(), xsd::INTEGER),
+      },
+      DType::DateTime(d) => Literal::from(d.clone()),
+      DType::Array(_) | DType::Object(_) => match value_object(value) {
+        Some(literal) => literal,
+        None => Literal::new(&json::to_string(value).ok()?, RDF_JSON),
+      },
+    };
+    Some(literal)
+  }
+}
+
+impl fmt::Display for Literal {
+  /// Formats the literal like N-Triples & Turtle, e.g. `"42"^^<...>`,
+  /// leaving out the `xsd:string` datatype.
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match (&self.lang, self.datatype.as_str()) {
+      (Some(lang), _) => write!(f, "{:?}@{}", self.lexical, lang),
+      (None, xsd::STRING) => write!(f, "{:?}", self.lexical),
+      (None, datatype) => write!(f, "{:?}^^<{}>", self.lexical, datatype),
+    }
+  }
+}
+
+impl From<Literal> for DType {
+  /// Converts leniently, see `Literal::to_dtype`.
+  fn from(literal: Literal) -> DType {
+    literal
+      .to_dtype(ParseMode::Lenient)
+      .unwrap_or(DType::String(literal.lexical))
+  }
+}
+
+impl From<Literal> for Node {
+  fn from(literal: Literal) -> Node {
+    Node::Literal(literal.into())
+  }
+}
+
+/// Reads a JSON-LD value object with a string `@value` & an `@language`
+/// or `@type`.
+fn value_object(value: &DType) -> Option<Literal> {
+  let map = value.as_object()?;
+  let lexical = map.get("@value")?.as_str()?;
+  match (map.len(), map.get("@language"), map.get("@type")) {
+    (2, Some(DType::String(lang)), None) => {
+      Literal::lang_string(lexical, lang).ok()
+    }
+    (2, None, Some(DType::String(datatype))) => {
+      Some(Literal::new(lexical, datatype))
+    }
+    _ => None,
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Conversions.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `FromLiteral` reads a Rust value from a `Literal`, see `Literal::value`.
+pub trait FromLiteral: Sized {
+  /// Reads a value from `literal`, checking its datatype & lexical form if
+  /// `mode` is strict.
+  fn from_literal(literal: &Literal, mode: ParseMode) -> Result<Self>;
+}
+
+impl Literal {
+  /// Returns the lexical form to parse, checking the datatype against
+  /// `accepts` & the lexical form in strict mode.
+  fn lexical_for(
+    &self,
+    mode: ParseMode,
+    accepts: fn(&str) -> bool,
+  ) -> Result<&str> {
+    match mode {
+      ParseMode::Strict if accepts(&self.datatype) && self.is_valid() => {
+        Ok(&self.lexical)
+      }
+      ParseMode::Strict => Err(invalid_literal()),
+      ParseMode::Lenient => Ok(self.lexical.trim()),
+    }
+  }
+}
+
+macro_rules! integer_literal {
+  ($($ty:ty)*) => {$(
+    impl From<$ty> for Literal {
+      fn from(n: $ty) -> Literal {
+        Literal::new(&n.to_string(), xsd::INTEGER)
+      }
+    }
+
+    impl FromLiteral for $ty {
+      fn from_literal(literal: &Literal, mode: ParseMode) -> Result<$ty> {
+        let lexical = literal.lexical_for(mode, is_integer_type)?;
+        lexical.parse().map_err(|_| invalid_literal())
+      }
+    }
+  )*};
+}
+
+integer_literal!(i8 i16 i32 i64 i128 u8 u16 u32 u64 u128);
+
+macro_rules! float_literal {
+  ($($ty:ty => $datatype:expr)*) => {$(
+    impl From<$ty> for Literal {
+      fn from(f: $ty) -> Literal {
+        let mut buffer = ryu::Buffer::new();
+        let lexical = match f {
+          f if f.is_nan() => "NaN",
+          f if f == <$ty>::INFINITY => "INF",
+          f if f == <$ty>::NEG_INFINITY => "-INF",
+          f => buffer.format_finite(f),
+        };
+        Literal::new(lexical, $datatype)
+      }
+    }
+
+    impl FromLiteral for $ty {
+      fn from_literal(literal: &Literal, mode: ParseMode) -> Result<$ty> {
+        let accepts = |datatype: &str| {
+          matches!(datatype, xsd::DECIMAL | xsd::DOUBLE | xsd::FLOAT)
+            || is_integer_type(datatype)
+        };
+        let lexical = literal.lexical_for(mode, accepts)?;
+        lexical.parse().map_err(|_| invalid_literal())
+      }
+    }
+  )*};
+}
+
+float_literal!(f32 => xsd::FLOAT f64 => xsd::DOUBLE);
+
+impl From<bool> for Literal {
+  fn from(b: bool) -> Literal {
+    Literal::new(if b { "true" } else { "false" }, xsd::BOOLEAN)
+  }
+}
+
+impl FromLiteral for bool {
+  fn from_literal(literal: &Literal, mode: ParseMode) -> Result<bool> {
+    let lexical = literal.lexical_for(mode, |d| d == xsd::BOOLEAN)?;
+    match lexical.to_ascii_lowercase().as_str() {
+      "true" | "1" => Ok(true),
+      "false" | "0" => Ok(false),
+      _ => Err(invalid_literal()),
+    }
+  }
+}
+
+impl From<&str> for Literal {
+  fn from(s: &str) -> Literal {
+    Literal::string(s)
+  }
+}
+
+impl From<String> for Literal {
+  fn from(s: String) -> Literal {
+    Literal {
+      lexical: s,
+      datatype: xsd::STRING.to_string(),
+      lang: None,
+    }
+  }
+}
+
+impl FromLiteral for String {
+  /// Returns the lexical form of any literal.
+  fn from_literal(literal: &Literal, _: ParseMode) -> Result<String> {
+    Ok(literal.lexical.clone())
+  }
+}
+
+impl From<LangString> for Literal {
+  fn from(s: LangString) -> Literal {
+    Literal {
+      lexical: s.value().to_string(),
+      datatype: RDF_LANG_STRING.to_string(),
+      lang: Some(s.language().to_string()),
+    }
+  }
+}
+
+impl FromLiteral for LangString {
+  fn from_literal(literal: &Literal, _: ParseMode) -> Result<LangString> {
+    match &literal.lang {
+      Some(lang) => LangString::new(&literal.lexical, lang),
+      None => Err(invalid_literal()),
+    }
+  }
+}
+
+impl From<ChronoDateTime<Utc>> for Literal {
+  fn from(d: ChronoDateTime<Utc>) -> Literal {
+    let lexical = d.to_rfc3339_opts(SecondsFormat::AutoSi, true);
+    Literal::new(&lexical, xsd::DATE_TIME)
+  }
+}
+
+impl FromLiteral for ChronoDateTime<Utc> {
+  /// Reads date times without a timezone as UTC.
+  fn from_literal(literal: &Literal, mode: ParseMode) -> Result<Self> {
+    let lexical = literal.lexical_for(mode, |d| d == xsd::DATE_TIME)?;
+    date_time(lexical, mode).ok_or_else(invalid_literal)
+  }
+}
+
+impl From<DateTime> for Literal {
+  fn from(d: DateTime) -> Literal {
+    Literal::from(ChronoDateTime::<Utc>::from(d))
+  }
+}
+
+impl FromLiteral for DateTime {
+  fn from_literal(literal: &Literal, mode: ParseMode) -> Result<DateTime> {
+    ChronoDateTime::<Utc>::from_literal(literal, mode).map(DateTime::from)
+  }
+}
+
+impl From<NaiveDate> for Literal {
+  fn from(d: NaiveDate) -> Literal {
+    Literal::new(&d.format("%Y-%m-%d").to_string(), xsd::DATE)
+  }
+}
+
+impl FromLiteral for NaiveDate {
+  /// Ignores the timezone, if any.
+  fn from_literal(literal: &Literal, mode: ParseMode) -> Result<NaiveDate> {
+    let lexical = literal.lexical_for(mode, |d| d == xsd::DATE)?;
+    date(lexical).ok_or_else(invalid_literal)
+  }
+}
+
+impl From<NaiveTime> for Literal {
+  fn from(t: NaiveTime) -> Literal {
+    Literal::new(&t.format("%H:%M:%S%.f").to_string(), xsd::TIME)
+  }
+}
+
+impl FromLiteral for NaiveTime {
+  /// Ignores the timezone, if any.
+  fn from_literal(literal: &Literal, mode: ParseMode) -> Result<NaiveTime> {
+    let lexical = literal.lexical_for(mode, |d| d == xsd::TIME)?;
+    time(lexical).ok_or_else(invalid_literal)
+  }
+}
+
+impl From<Duration> for Literal {
+  /// Writes the duration in seconds, e.g. `PT90.5S`.
+  fn from(d: Duration) -> Literal {
+    let lexical = match d.subsec_nanos() {
+      0 => format!("PT{}S", d.as_secs()),
+      nanos => {
+        let fraction = format!("{:09}", nanos);
+        format!("PT{}.{}S", d.as_secs(), fraction.trim_end_matches('0'))
+      }
+    };
+    Literal::new(&lexical, xsd::DURATION)
+  }
+}
+
+impl FromLiteral for Duration {
+  /// Reads durations in days, hours, minutes & seconds. Negative durations
+  /// & ones with years or months, whose length varies, are rejected.
+  fn from_literal(literal: &Literal, mode: ParseMode) -> Result<Duration> {
+    let lexical = literal.lexical_for(mode, |d| d == xsd::DURATION)?;
+    duration(lexical).ok_or_else(invalid_literal)
+  }
+}
+
+impl From<&[u8]> for Literal {
+  fn from(bytes: &[u8]) -> Literal {
+    Literal::new(&base64_encode(bytes), xsd::BASE64_BINARY)
+  }
+}
+
+impl From<Vec<u8>> for Literal {
+  fn from(bytes: Vec<u8>) -> Literal {
+    Literal::from(bytes.as_slice())
+  }
+}
+
+impl FromLiteral for Vec<u8> {
+  fn from_literal(literal: &Literal, mode: ParseMode) -> Result<Vec<u8>> {
+    let accepts = |d: &str| d == xsd::BASE64_BINARY || d == xsd::HEX_BINARY;
+    let lexical = literal.lexical_for(mode, accepts)?;
+    let bytes = match literal.datatype.as_str() {
+      xsd::HEX_BINARY => hex(lexical),
+      _ => base64(lexical, mode),
+    };
+    bytes.ok_or_else(invalid_literal)
+  }
+}
+
+#[cfg(feature = "uuid")]
+impl From<Uuid> for Literal {
+  /// Writes the UUID as a `urn:uuid:` URI.
+  fn from(u: Uuid) -> Literal {
+    Literal::new(&u.to_urn().to_string(), xsd::ANY_URI)
+  }
+}
+
+#[cfg(feature = "uuid")]
+impl FromLiteral for Uuid {
+  /// Reads `xsd:string` & `xsd:anyURI` literals in the simple, hyphenated
+  /// or `urn:uuid:` form.
+  fn from_literal(literal: &Literal, mode: ParseMode) -> Result<Uuid> {
+    let accepts = |d: &str| d == xsd::STRING || d == xsd::ANY_URI;
+    let lexical = literal.lexical_for(mode, accepts)?;
+    uuid(lexical).ok_or_else(invalid_literal)
+  }
+}
+
+#[cfg(feature = "url")]
+impl From<url::Url> for Literal {
+  fn from(u: url::Url) -> Literal {
+    Literal::new(u.as_str(), xsd::ANY_URI)
+  }
+}
+
+#[cfg(feature = "url")]
+impl FromLiteral for url::Url {
+  /// Reads absolute URLs, from `xsd:anyURI` literals only in strict mode.
+  fn from_literal(literal: &Literal, mode: ParseMode) -> Result<url::Url> {
+    let lexical = literal.lexical_for(mode, |d| d == xsd::ANY_URI)?;
+    url::Url::parse(lexical).map_err(|_| invalid_literal())
+  }
+}
+
+impl FromLiteral for DType {
+  /// Converts with `Literal::to_dtype`.
+  fn from_literal(literal: &Literal, mode: ParseMode) -> Result<DType> {
+    literal.to_dtype(mode)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Lexical forms.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+#[cold]
+fn invalid_literal() -> Error {
+  Error::syntax(ErrorCode::InvalidLiteral, 0, 0)
+}
+
+fn integer_bounds(datatype: &str) -> Option<(i128, i128)> {
+  INTEGERS
+    .iter()
+    .find(|(integer, ..)| *integer == datatype)
+    .map(|(_, min, max)| (*min, *max))
+}
+
+fn is_integer_type(datatype: &str) -> bool {
+  integer_bounds(datatype).is_some()
+}
+
+fn is_integer(s: &str) -> bool {
+  let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+  !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Parses a valid integer lexical form, saturating beyond `i128`.
+fn saturating_integer(s: &str) -> i128 {
+  s.parse().unwrap_or(if s.starts_with('-') {
+    i128::MIN
+  } else {
+    i128::MAX
+  })
+}
+
+fn is_decimal(s: &str) -> bool {
+  let s = s.strip_prefix(['+', '-']).unwrap_or(s);
+  let (int, fraction) = s.split_once('.').unwrap_or((s, ""));
+  let digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+  (!int.is_empty() || !fraction.is_empty()) && digits(int) && digits(fraction)
+}
+
+fn is_double(s: &str) -> bool {
+  if matches!(s, "INF" | "+INF" | "-INF" | "NaN") {
+    return true;
+  }
+  let (mantissa, exponent) = s.split_once(['e', 'E']).unwrap_or((s, "0"));
+  is_decimal(mantissa) && is_integer(exponent)
+}
+
+/// Parses an `xsd:dateTime`, taking date times without a timezone as UTC.
+fn date_time(s: &str, mode: ParseMode) -> Option<ChronoDateTime<Utc>> {
+  let lenient;
+  let s = match s.as_bytes().get(10) {
+    Some(b'T') => s,
+    Some(b' ') if mode == ParseMode::Lenient => {
+      lenient = s.replacen(' ', "T", 1);
+      &lenient
+    }
+    _ => return None,
+  };
+  if let Ok(d) = ChronoDateTime::parse_from_rfc3339(s) {
+    return Some(d.with_timezone(&Utc));
+  }
+  let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+  Some(Utc.from_utc_datetime(&naive))
+}
+
+fn date(s: &str) -> Option<NaiveDate> {
+  NaiveDate::parse_from_str(without_timezone(s), "%Y-%m-%d").ok()
+}
+
+fn time(s: &str) -> Option<NaiveTime> {
+  NaiveTime::parse_from_str(without_timezone(s), "%H:%M:%S%.f").ok()
+}
+
+/// Parses an `xsd:duration` of the form `PnDTnHnMn.nS`, any part of which
+/// may be left out.
+fn duration(s: &str) -> Option<Duration> {
+  let s = s.strip_prefix('P')?;
+  let (days, time) = match s.split_once('T') {
+    Some((_, "")) => return None,
+    Some((days, time)) => (days, time),
+    None => (s, ""),
+  };
+  if days.is_empty() && time.is_empty() {
+    return None;
+  }
+  let digits = |s: &str| match s.bytes().all(|b| b.is_ascii_digit()) {
+    true if !s.is_empty() => s.parse::<u64>().ok(),
+    _ => None,
+  };
+  let mut secs = match days {
+    "" => 0,
+    days => digits(days.strip_suffix('D')?)?.checked_mul(86_400)?,
+  };
+  let mut rest = time;
+  for (unit, unit_secs) in [('H', 3_600), ('M', 60)] {
+    if let Some((n, tail)) = rest.split_once(unit) {
+      secs = secs.checked_add(digits(n)?.checked_mul(unit_secs)?)?;
+      rest = tail;
+    }
+  }
+  let mut nanos = 0;
+  if !rest.is_empty() {
+    let seconds = rest.strip_suffix('S')?;
+    let (whole, fraction) = match seconds.split_once('.') {
+      Some((_, "")) => return None,
+      Some((whole, fraction)) => (whole, fraction),
+      None => (seconds, ""),
+    };
+    secs = secs.checked_add(digits(whole)?)?;
+    if !fraction.is_empty() {
+      if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+      }
+      let fraction = format!("{:0<9}", &fraction[..fraction.len().min(9)]);
+      nanos = fraction.parse().ok()?;
+    }
+  }
+  Some(Duration::new(secs, nanos))
+}
+
+/// Strips a trailing `Z` or `±hh:mm` timezone.
+fn without_timezone(s: &str) -> &str {
+  if let Some(s) = s.strip_suffix('Z') {
+    return s;
+  }
+  let bytes = s.as_bytes();
+  match bytes.len().checked_sub(6).map(|at| (at, bytes[at])) {
+    Some((at, b'+' | b'-')) if bytes[at + 3] == b':' => &s[..at],
+    _ => s,
+  }
+}
+
+/// Parses a UUID in the simple (`67e5504410b1...`), hyphenated
+/// (`67e55044-10b1-...`) or URN (`urn:uuid:67e55044-10b1-...`) form.
+#[cfg(feature = "uuid")]
+pub(crate) fn uuid(s: &str) -> Option<Uuid> {
+  let s = match s.get(..9) {
+    Some(urn) if urn.eq_ignore_ascii_case("urn:uuid:") => &s[9..],
+    _ => s,
+  };
+  Uuid::parse_str(s).ok()
+}
+
+/// Parses an absolute or relative URI reference, resolving relative ones
+/// against a placeholder base.
+#[cfg(feature = "url")]
+fn uri_reference(s: &str) -> Option<url::Url> {
+  let base = url::Url::parse("http://localhost/").ok()?;
+  base.join(s).ok()
+}
+
+fn hex(s: &str) -> Option<Vec<u8>> {
+  if !s.len().is_multiple_of(2) || !s.is_ascii() {
+    return None;
+  }
+  (0..s.len())
+    .step_by(2)
+    .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+    .collect()
+}
+
+/// Decodes an `xsd:base64Binary`, which must be padded & free of
+/// whitespace in strict mode.
+fn base64(s: &str, mode: ParseMode) -> Option<Vec<u8>> {
+  match mode {
+    ParseMode::Strict if !s.len().is_multiple_of(4) => None,
+    ParseMode::Strict => base64_decode(s, false),
+    ParseMode::Lenient => {
+      let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+      base64_decode(&s, false)
+    }
+  }
+}
+
+const BASE64: &[u8; 64] =
+  b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `bytes` as padded base64.
+pub(crate) fn base64_encode(bytes: &[u8]) -> String {
+  let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+  for chunk in bytes.chunks(3) {
+    let n = chunk
+      .iter()
+      .enumerate()
+      .fold(0u32, |n, (i, b)| n | ((*b as u32) << (16 - 8 * i)));
+    for i in 0..4 {
+      if i <= chunk.len() {
+        out.push(BASE64[((n >> (18 - 6 * i)) & 63) as usize] as char);
+      } else {
+        out.push('=');
+      }
+    }
+  }
+  out
+}
+
+/// Decodes base64, or base64url if `url`, with or without padding.
+pub(crate) fn base64_decode(s: &str, url: bool) -> Option<Vec<u8>> {
+  let data = s
+    .strip_suffix("==")
+    .or_else(|| s.strip_suffix('='))
+    .unwrap_or(s);
+  if data.len() % 4 == 1 {
+    return None;
+  }
+  let mut out = Vec::with_capacity(data.len() * 3 / 4);
+  let (mut buf, mut bits) = (0u32, 0);
+  for c in data.bytes() {
+    let value = match c {
+      b'A'..=b'Z' => c - b'A',
+      b'a'..=b'z' => c - b'a' + 26,
+      b'0'..=b'9' => c - b'0' + 52,
+      b'+' if !url => 62,
+      b'/' if !url => 63,
+      b'-' if url => 62,
+      b'_' if url => 63,
+      _ => return None,
+    };
+    buf = (buf << 6) | value as u32;
+    bits += 6;
+    if bits >= 8 {
+      bits -= 8;
+      out.push((buf >> bits) as u8);
+    }
+  }
+  Some(out)
+}
@@ -20,12 +20,12 @@
 //! [`BTreeMap`]: https://doc.rust-lang.org/std/collections/struct.BTreeMap.html
 //! [`IndexMap`]: https://docs.rs/indexmap/*/indexmap/map/struct.IndexMap.html
 
-use super::DType;
+use super::{DType, DeepSizeOf, Key};
 use serde::de;
 use std::{
   borrow::Borrow,
   fmt,
-  hash::Hash,
+  hash::{Hash, Hasher},
   iter::{FromIterator, FusedIterator},
   ops,
 };
@@ -44,8 +44,11 @@ use std::collections::btree_map::{self, BTreeMap};
 */
 
 /// A key/value type representation.
+///
+/// The entries live behind a pointer so that `DType::Object` doesn't widen
+/// `DType`: see `DType` for its size.
 pub struct Map<K, V> {
-  map: MapImpl<K, V>,
+  map: Box<MapImpl<K, V>>,
 }
 #[cfg(not(feature = "preserve_order"))]
 type MapImpl<K, V> = BTreeMap<K, V>;
@@ -57,7 +60,7 @@ impl Map<String, DType> {
   #[inline]
   pub fn new() -> Self {
     Map {
-      map: MapImpl::new(),
+      map: Box::default(),
     }
   }
 
@@ -69,10 +72,10 @@ impl Map<String, DType> {
       map: {
         // does not support with_capacity
         let _ = capacity;
-        BTreeMap::new()
+        Box::default()
       },
       #[cfg(feature = "preserve_order")]
-      map: IndexMap::with_capacity(capacity),
+      map: Box::new(IndexMap::with_capacity(capacity)),
     }
   }
 
@@ -200,7 +203,7 @@ impl Map<String, DType> {
   #[inline]
   pub fn append(&mut self, other: &mut Self) {
     #[cfg(feature = "preserve_order")]
-    for (k, v) in std::mem::take(&mut other.map) {
+    for (k, v) in std::mem::take(&mut *other.map) {
       self.map.insert(k, v);
     }
     #[cfg(not(feature = "preserve_order"))]
@@ -291,7 +294,7 @@ impl Default for Map<String, DType> {
   #[inline]
   fn default() -> Self {
     Map {
-      map: MapImpl::new(),
+      map: Box::default(),
     }
   }
 }
@@ -314,6 +317,26 @@ impl PartialEq for Map<String, DType> {
 
 impl Eq for Map<String, DType> {}
 
+impl Hash for Map<String, DType> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    #[cfg(not(feature = "preserve_order"))]
+    self.map.hash(state);
+    // `IndexMap`s with the same entries in a different order are equal.
+    #[cfg(feature = "preserve_order")]
+    {
+      let mut entries: Vec<_> = self.map.iter().collect();
+      entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+      entries.hash(state)
+    }
+  }
+}
+
+impl DeepSizeOf for Map<String, DType> {
+  fn heap_size_of(&self) -> usize {
+    self.map.heap_size_of()
+  }
+}
+
 /// Access an element of this map. Panics if the given key is not present in the
 /// map.
 ///
@@ -449,7 +472,7 @@ impl FromIterator<(String, DType)> for Map<String, DType> {
     T: IntoIterator<Item = (String, DType)>,
   {
     Map {
-      map: FromIterator::from_iter(iter),
+      map: Box::new(FromIterator::from_iter(iter)),
     }
   }
 }
@@ -926,7 +949,7 @@ impl IntoIterator for Map<String, DType> {
   #[inline]
   fn into_iter(self) -> Self::IntoIter {
     IntoIter {
-      iter: self.map.into_iter(),
+      iter: (*self.map).into_iter(),
     }
   }
 }
@@ -993,3 +1016,227 @@ type ValuesMutImpl<'a> = btree_map::ValuesMut<'a, String, DType>;
 type ValuesMutImpl<'a> = indexmap::map::ValuesMut<'a, String, DType>;
 
 delegate_iterator!((ValuesMut<'a>) => &'a mut DType);
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `Map<Key, DType>` - maps with integer & datetime keys.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+// `Map::new` & `Map::with_capacity` are left to `Map<String, DType>` so
+// they stay unambiguous, use `Map::<Key, DType>::default()` instead.
+impl Map<Key, DType> {
+  /// Clears the map, removing all values.
+  #[inline]
+  pub fn clear(&mut self) {
+    self.map.clear();
+  }
+
+  /// Returns a reference to the value corresponding to the key.
+  #[inline]
+  pub fn get(&self, key: &Key) -> Option<&DType> {
+    self.map.get(key)
+  }
+
+  /// Returns true if the map contains a value for the specific key.
+  #[inline]
+  pub fn contains_key(&self, key: &Key) -> bool {
+    self.map.contains_key(key)
+  }
+
+  /// Returns a mutable reference to the value corresponding to the key.
+  #[inline]
+  pub fn get_mut(&mut self, key: &Key) -> Option<&mut DType> {
+    self.map.get_mut(key)
+  }
+
+  /// Inserts a key-value pair into the map, returning the old value of the
+  /// key if it had one.
+  #[inline]
+  pub fn insert(&mut self, k: Key, v: DType) -> Option<DType> {
+    self.map.insert(k, v)
+  }
+
+  /// Removes a key from the map, returning the value at the key if the key
+  /// was previously in the map.
+  pub fn remove(&mut self, key: &Key) -> Option<DType> {
+    #[cfg(not(feature = "preserve_order"))]
+    return self.map.remove(key);
+    #[cfg(feature = "preserve_order")]
+    return self.map.swap_remove(key);
+  }
+
+  /// Returns the number of elements in the map.
+  #[inline]
+  pub fn len(&self) -> usize {
+    self.map.len()
+  }
+
+  /// Returns true if the map contains no elements.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.map.is_empty()
+  }
+
+  /// Gets an iterator over the entries of the map.
+  #[inline]
+  pub fn iter(&self) -> impl Iterator<Item = (&Key, &DType)> {
+    self.map.iter()
+  }
+
+  /// Gets an iterator over the keys of the map.
+  #[inline]
+  pub fn keys(&self) -> impl Iterator<Item = &Key> {
+    self.map.keys()
+  }
+
+  /// Gets an iterator over the values of the map.
+  #[inline]
+  pub fn values(&self) -> impl Iterator<Item = &DType> {
+    self.map.values()
+  }
+}
+
+impl Default for Map<Key, DType> {
+  #[inline]
+  fn default() -> Self {
+    Map {
+      map: Box::default(),
+    }
+  }
+}
+
+impl Clone for Map<Key, DType> {
+  #[inline]
+  fn clone(&self) -> Self {
+    Map {
+      map: self.map.clone(),
+    }
+  }
+}
+
+impl PartialEq for Map<Key, DType> {
+  #[inline]
+  fn eq(&self, other: &Self) -> bool {
+    self.map.eq(&other.map)
+  }
+}
+
+impl Eq for Map<Key, DType> {}
+
+impl<'a> ops::Index<&'a Key> for Map<Key, DType> {
+  type Output = DType;
+
+  fn index(&self, index: &Key) -> &DType {
+    self.map.index(index)
+  }
+}
+
+impl fmt::Debug for Map<Key, DType> {
+  #[inline]
+  fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+    self.map.fmt(formatter)
+  }
+}
+
+impl serde::ser::Serialize for Map<Key, DType> {
+  #[inline]
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: serde::ser::Serializer,
+  {
+    use serde::ser::SerializeMap;
+    let mut map = tri!(serializer.serialize_map(Some(self.len())));
+    for (k, v) in self.iter() {
+      tri!(map.serialize_entry(k, v));
+    }
+    map.end()
+  }
+}
+
+impl<'de> de::Deserialize<'de> for Map<Key, DType> {
+  #[inline]
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: de::Deserializer<'de>,
+  {
+    struct Visitor;
+
+    impl<'de> de::Visitor<'de> for Visitor {
+      type Value = Map<Key, DType>;
+
+      fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map")
+      }
+
+      #[inline]
+      fn visit_map<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
+      where
+        V: de::MapAccess<'de>,
+      {
+        let mut values = Map::<Key, DType>::default();
+
+        while let Some((key, value)) = tri!(visitor.next_entry()) {
+          values.insert(key, value);
+        }
+
+        Ok(values)
+      }
+    }
+
+    deserializer.deserialize_map(Visitor)
+  }
+}
+
+impl FromIterator<(Key, DType)> for Map<Key, DType> {
+  fn from_iter<T>(iter: T) -> Self
+  where
+    T: IntoIterator<Item = (Key, DType)>,
+  {
+    Map {
+      map: Box::new(FromIterator::from_iter(iter)),
+    }
+  }
+}
+
+impl Extend<(Key, DType)> for Map<Key, DType> {
+  fn extend<T>(&mut self, iter: T)
+  where
+    T: IntoIterator<Item = (Key, DType)>,
+  {
+    self.map.extend(iter);
+  }
+}
+
+impl IntoIterator for Map<Key, DType> {
+  type Item = (Key, DType);
+  type IntoIter = <MapImpl<Key, DType> as IntoIterator>::IntoIter;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    (*self.map).into_iter()
+  }
+}
+
+/// Decodes every key with [`Key::parse`].
+impl From<Map<String, DType>> for Map<Key, DType> {
+  fn from(map: Map<String, DType>) -> Self {
+    map.into_iter().map(|(k, v)| (Key::parse(&k), v)).collect()
+  }
+}
+
+/// Encodes every key as a string, see [`Key`].
+impl From<Map<Key, DType>> for Map<String, DType> {
+  fn from(map: Map<Key, DType>) -> Self {
+    map.into_iter().map(|(k, v)| (String::from(k), v)).collect()
+  }
+}
+
+/// An object with every key encoded as a string, see [`Key`].
+impl From<Map<Key, DType>> for DType {
+  fn from(map: Map<Key, DType>) -> Self {
+    DType::Object(map.into())
+  }
+}
@@ -15,6 +15,8 @@
 #![allow(dead_code)]
 
 use std::fmt;
+#[cfg(not(feature = "arbitrary_precision"))]
+use std::hash::{Hash, Hasher};
 
 use serde::{
   de::{self, Unexpected, Visitor},
@@ -39,7 +41,7 @@ pub(crate) const TOKEN: &str = "$sage::dtype::Number";
 */
 
 /// Represents a number, whether integer or floating point.
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, Hash, PartialEq)]
 pub struct Number {
   /// Number enum implementation.
   pub(crate) n: NumImpl,
@@ -61,6 +63,19 @@ pub enum NumImpl {
 #[cfg(not(feature = "arbitrary_precision"))]
 impl Eq for NumImpl {}
 
+#[cfg(not(feature = "arbitrary_precision"))]
+impl Hash for NumImpl {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    match *self {
+      // `-0.0 == 0.0`, so both must hash the same.
+      NumImpl::Float(0.0) => 0.0f64.to_bits().hash(state),
+      NumImpl::Float(f) => f.to_bits().hash(state),
+      NumImpl::PositiveInt(u) => u.hash(state),
+      NumImpl::NegativeInt(i) => i.hash(state),
+    }
+  }
+}
+
 /// Number representation with arbitrary precision.
 #[cfg(feature = "arbitrary_precision")]
 pub type NumImpl = String;
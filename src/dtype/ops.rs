@@ -15,6 +15,7 @@
 mod de;
 mod from;
 mod index;
+mod ord;
 mod partial_eq;
 mod ser;
 
@@ -25,4 +26,5 @@ pub use ser::{
   Serializer,
 };
 
+pub use de::DuplicateKeys;
 pub use index::Index;
@@ -19,12 +19,13 @@
 use crate::dtype::number::NumberFromString;
 use crate::{DType, DateTime, Error, Map, Number};
 
-use std::{borrow::Cow, fmt, str::FromStr};
+use std::{borrow::Cow, collections::HashSet, fmt, mem, str::FromStr};
 
 use serde::{
   de::{
-    self, Deserialize, DeserializeSeed, EnumAccess, Expected, IntoDeserializer,
-    MapAccess, SeqAccess, Unexpected, VariantAccess, Visitor,
+    self, value::BorrowedStrDeserializer, Deserialize, DeserializeSeed,
+    EnumAccess, Expected, IntoDeserializer, MapAccess, SeqAccess, Unexpected,
+    VariantAccess, Visitor,
   },
   forward_to_deserialize_any, serde_if_integer128,
 };
@@ -37,117 +38,238 @@ use serde::{
  * +----------------------------------------------------------------------+
 */
 
+/// What to do when an object being deserialized into a `DType` contains the
+/// same key more than once.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::{json, DType, DuplicateKeys};
+///
+/// let parse = |duplicate_keys| {
+///   let mut de = json::Deserializer::from_str(r#"{"a": 1, "a": 2}"#);
+///   DType::deserialize_with(&mut de, duplicate_keys)
+/// };
+///
+/// assert_eq!(parse(DuplicateKeys::LastWins).unwrap(), json!({ "a": 2 }));
+/// assert_eq!(parse(DuplicateKeys::FirstWins).unwrap(), json!({ "a": 1 }));
+/// assert_eq!(
+///   parse(DuplicateKeys::CollectIntoArray).unwrap(),
+///   json!({ "a": [1, 2] })
+/// );
+/// assert!(parse(DuplicateKeys::Error).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DuplicateKeys {
+  /// Keep the last value, as `DType::deserialize` does.
+  #[default]
+  LastWins,
+  /// Keep the first value & skip the others.
+  FirstWins,
+  /// Fail with a "duplicate key" error.
+  Error,
+  /// Collect every value of the key into an array, in input order. Keys
+  /// that appear once keep their value as is.
+  CollectIntoArray,
+}
+
 impl<'de> Deserialize<'de> for DType {
   #[inline]
   fn deserialize<D>(deserializer: D) -> Result<DType, D::Error>
   where
     D: serde::Deserializer<'de>,
   {
-    struct DTypeVisitor;
+    deserializer.deserialize_any(DTypeVisitor::default())
+  }
+}
 
-    impl<'de> Visitor<'de> for DTypeVisitor {
-      type Value = DType;
+impl DType {
+  /// Deserializes a `DType`, resolving keys repeated within an object with
+  /// `duplicate_keys` rather than letting the last value win.
+  ///
+  /// See [`DuplicateKeys`] for an example.
+  ///
+  /// # Errors
+  ///
+  /// Fails like `DType::deserialize`, or on a repeated key with
+  /// `DuplicateKeys::Error`.
+  pub fn deserialize_with<'de, D>(
+    deserializer: D,
+    duplicate_keys: DuplicateKeys,
+  ) -> Result<DType, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    deserializer.deserialize_any(DTypeVisitor { duplicate_keys })
+  }
+}
 
-      fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("any valid JSON value")
-      }
+/// Builds a `DType`, used as the seed of its nested values too so they share
+/// the duplicate key policy.
+#[derive(Clone, Copy, Default)]
+struct DTypeVisitor {
+  duplicate_keys: DuplicateKeys,
+}
 
-      #[inline]
-      fn visit_bool<E>(self, value: bool) -> Result<DType, E> {
-        Ok(DType::Boolean(value))
-      }
+impl<'de> DeserializeSeed<'de> for DTypeVisitor {
+  type Value = DType;
 
-      #[inline]
-      fn visit_i64<E>(self, value: i64) -> Result<DType, E> {
-        Ok(DType::Number(value.into()))
-      }
+  #[inline]
+  fn deserialize<D>(self, deserializer: D) -> Result<DType, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    deserializer.deserialize_any(self)
+  }
+}
 
-      #[inline]
-      fn visit_u64<E>(self, value: u64) -> Result<DType, E> {
-        Ok(DType::Number(value.into()))
-      }
+impl<'de> Visitor<'de> for DTypeVisitor {
+  type Value = DType;
 
-      #[inline]
-      fn visit_f64<E>(self, value: f64) -> Result<DType, E> {
-        Ok(Number::from_f64(value).map_or(DType::Null, DType::Number))
-      }
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("any valid JSON value")
+  }
 
-      #[inline]
-      fn visit_str<E>(self, value: &str) -> Result<DType, E>
-      where
-        E: serde::de::Error,
-      {
-        self.visit_string(String::from(value))
-      }
+  #[inline]
+  fn visit_bool<E>(self, value: bool) -> Result<DType, E> {
+    Ok(DType::Boolean(value))
+  }
 
-      #[inline]
-      fn visit_string<E>(self, value: String) -> Result<DType, E> {
-        Ok(DType::String(value))
-      }
+  #[inline]
+  fn visit_i64<E>(self, value: i64) -> Result<DType, E> {
+    Ok(DType::Number(value.into()))
+  }
 
-      #[inline]
-      fn visit_none<E>(self) -> Result<DType, E> {
-        Ok(DType::Null)
-      }
+  #[inline]
+  fn visit_u64<E>(self, value: u64) -> Result<DType, E> {
+    Ok(DType::Number(value.into()))
+  }
 
-      #[inline]
-      fn visit_some<D>(self, deserializer: D) -> Result<DType, D::Error>
-      where
-        D: serde::Deserializer<'de>,
-      {
-        Deserialize::deserialize(deserializer)
-      }
+  #[inline]
+  fn visit_f64<E>(self, value: f64) -> Result<DType, E> {
+    Ok(Number::from_f64(value).map_or(DType::Null, DType::Number))
+  }
 
-      #[inline]
-      fn visit_unit<E>(self) -> Result<DType, E> {
-        Ok(DType::Null)
-      }
+  #[inline]
+  fn visit_str<E>(self, value: &str) -> Result<DType, E>
+  where
+    E: serde::de::Error,
+  {
+    self.visit_string(String::from(value))
+  }
 
-      #[inline]
-      fn visit_seq<V>(self, mut visitor: V) -> Result<DType, V::Error>
-      where
-        V: SeqAccess<'de>,
-      {
-        let mut vec = Vec::new();
+  #[inline]
+  fn visit_string<E>(self, value: String) -> Result<DType, E> {
+    Ok(DType::String(value))
+  }
 
-        while let Some(elem) = tri!(visitor.next_element()) {
-          vec.push(elem);
-        }
+  #[inline]
+  fn visit_bytes<E>(self, value: &[u8]) -> Result<DType, E>
+  where
+    E: serde::de::Error,
+  {
+    self.visit_byte_buf(value.to_vec())
+  }
 
-        Ok(DType::Array(vec))
-      }
+  #[inline]
+  fn visit_byte_buf<E>(self, value: Vec<u8>) -> Result<DType, E> {
+    Ok(DType::Bytes(Box::new(value)))
+  }
 
-      fn visit_map<V>(self, mut visitor: V) -> Result<DType, V::Error>
-      where
-        V: MapAccess<'de>,
-      {
-        match visitor.next_key_seed(KeyClassifier)? {
-          #[cfg(feature = "arbitrary_precision")]
-          Some(KeyClass::Number) => {
-            let number: NumberFromString = visitor.next_value()?;
-            Ok(DType::Number(number.value))
-          }
-          #[cfg(feature = "raw_value")]
-          Some(KeyClass::RawDType) => {
-            let value = visitor.next_value_seed(crate::raw::BoxedFromString)?;
-            crate::from_str(value.get()).map_err(de::Error::custom)
-          }
-          Some(KeyClass::Map(first_key)) => {
-            let mut values = Map::new();
+  #[inline]
+  fn visit_none<E>(self) -> Result<DType, E> {
+    Ok(DType::Null)
+  }
 
-            values.insert(first_key, tri!(visitor.next_value()));
-            while let Some((key, value)) = tri!(visitor.next_entry()) {
-              values.insert(key, value);
-            }
+  #[inline]
+  fn visit_some<D>(self, deserializer: D) -> Result<DType, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    deserializer.deserialize_any(self)
+  }
+
+  #[inline]
+  fn visit_unit<E>(self) -> Result<DType, E> {
+    Ok(DType::Null)
+  }
 
-            Ok(DType::Object(values))
+  #[inline]
+  fn visit_seq<V>(self, mut visitor: V) -> Result<DType, V::Error>
+  where
+    V: SeqAccess<'de>,
+  {
+    let mut vec = Vec::new();
+
+    while let Some(elem) = tri!(visitor.next_element_seed(self)) {
+      vec.push(elem);
+    }
+
+    Ok(DType::Array(Box::new(vec)))
+  }
+
+  fn visit_map<V>(self, mut visitor: V) -> Result<DType, V::Error>
+  where
+    V: MapAccess<'de>,
+  {
+    match visitor.next_key_seed(KeyClassifier)? {
+      #[cfg(feature = "arbitrary_precision")]
+      Some(KeyClass::Number) => {
+        let number: NumberFromString = visitor.next_value()?;
+        Ok(DType::Number(number.value))
+      }
+      #[cfg(feature = "raw_value")]
+      Some(KeyClass::RawDType) => {
+        let value = visitor.next_value_seed(crate::raw::BoxedFromString)?;
+        crate::from_str(value.get()).map_err(de::Error::custom)
+      }
+      Some(KeyClass::Map(first_key)) => {
+        let mut values = Map::new();
+        // Keys whose values were collected into an array.
+        let mut collected = HashSet::new();
+
+        values.insert(first_key, tri!(visitor.next_value_seed(self)));
+        while let Some(key) = tri!(visitor.next_key::<String>()) {
+          let existing = match values.get_mut(&key) {
+            Some(existing) => existing,
+            None => {
+              values.insert(key, tri!(visitor.next_value_seed(self)));
+              continue;
+            }
+          };
+          match self.duplicate_keys {
+            DuplicateKeys::LastWins => {
+              *existing = tri!(visitor.next_value_seed(self));
+            }
+            DuplicateKeys::FirstWins => {
+              tri!(visitor.next_value::<de::IgnoredAny>());
+            }
+            DuplicateKeys::Error => {
+              return Err(de::Error::custom(format_args!(
+                "duplicate key `{}`",
+                key
+              )));
+            }
+            DuplicateKeys::CollectIntoArray => {
+              let value = tri!(visitor.next_value_seed(self));
+              match existing {
+                DType::Array(items) if collected.contains(&key) => {
+                  items.push(value)
+                }
+                _ => {
+                  let first = existing.take();
+                  *existing = DType::Array(Box::new(vec![first, value]));
+                  collected.insert(key);
+                }
+              }
+            }
           }
-          None => Ok(DType::Object(Map::new())),
         }
+
+        Ok(DType::Object(values))
       }
+      None => Ok(DType::Object(Map::new())),
     }
-
-    deserializer.deserialize_any(DTypeVisitor)
   }
 }
 
@@ -174,7 +296,7 @@ macro_rules! deserialize_number {
       V: Visitor<'de>,
     {
       match self {
-        DType::Number(n) => n.deserialize_any(visitor),
+        DType::Number(ref n) => n.clone().deserialize_any(visitor),
         _ => Err(self.invalid_type(&visitor)),
       }
     }
@@ -185,7 +307,7 @@ macro_rules! deserialize_number {
       V: Visitor<'de>,
     {
       match self {
-        DType::Number(n) => n.$method(visitor),
+        DType::Number(ref n) => n.clone().$method(visitor),
         _ => self.deserialize_any(visitor),
       }
     }
@@ -262,18 +384,19 @@ impl<'de> serde::Deserializer<'de> for DType {
   type Error = Error;
 
   #[inline]
-  fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Error>
+  fn deserialize_any<V>(mut self, visitor: V) -> Result<V::Value, Error>
   where
     V: Visitor<'de>,
   {
     match self {
       DType::Null => visitor.visit_unit(),
       DType::Boolean(v) => visitor.visit_bool(v),
-      DType::Number(n) => n.deserialize_any(visitor),
-      DType::String(v) => visitor.visit_string(v),
-      DType::Array(v) => visit_array(v, visitor),
-      DType::Object(v) => visit_object(v, visitor),
-      DType::DateTime(d) => visit_datetime(d, visitor),
+      DType::Bytes(ref mut v) => visitor.visit_byte_buf(mem::take(&mut **v)),
+      DType::Number(ref n) => n.clone().deserialize_any(visitor),
+      DType::String(ref mut v) => visitor.visit_string(mem::take(v)),
+      DType::Array(ref mut v) => visit_array(mem::take(&mut **v), visitor),
+      DType::Object(ref mut v) => visit_object(mem::take(v), visitor),
+      DType::DateTime(ref d) => visit_datetime(d.clone(), visitor),
     }
   }
 
@@ -306,7 +429,7 @@ impl<'de> serde::Deserializer<'de> for DType {
 
   #[inline]
   fn deserialize_enum<V>(
-    self,
+    mut self,
     _name: &str,
     _variants: &'static [&'static str],
     visitor: V,
@@ -315,8 +438,8 @@ impl<'de> serde::Deserializer<'de> for DType {
     V: Visitor<'de>,
   {
     let (variant, value) = match self {
-      DType::Object(value) => {
-        let mut iter = value.into_iter();
+      DType::Object(ref mut value) => {
+        let mut iter = mem::take(value).into_iter();
         let (variant, value) = match iter.next() {
           Some(v) => v,
           None => {
@@ -335,8 +458,8 @@ impl<'de> serde::Deserializer<'de> for DType {
         }
         (variant, Some(value))
       }
-      DType::String(variant) => (variant, None),
-      other => {
+      DType::String(ref mut variant) => (mem::take(variant), None),
+      ref other => {
         return Err(serde::de::Error::invalid_type(
           other.unexpected(),
           &"string or map",
@@ -393,12 +516,12 @@ impl<'de> serde::Deserializer<'de> for DType {
     self.deserialize_string(visitor)
   }
 
-  fn deserialize_string<V>(self, visitor: V) -> Result<V::Value, Error>
+  fn deserialize_string<V>(mut self, visitor: V) -> Result<V::Value, Error>
   where
     V: Visitor<'de>,
   {
     match self {
-      DType::String(v) => visitor.visit_string(v),
+      DType::String(ref mut v) => visitor.visit_string(mem::take(v)),
       _ => Err(self.invalid_type(&visitor)),
     }
   }
@@ -410,13 +533,14 @@ impl<'de> serde::Deserializer<'de> for DType {
     self.deserialize_byte_buf(visitor)
   }
 
-  fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Error>
+  fn deserialize_byte_buf<V>(mut self, visitor: V) -> Result<V::Value, Error>
   where
     V: Visitor<'de>,
   {
     match self {
-      DType::String(v) => visitor.visit_string(v),
-      DType::Array(v) => visit_array(v, visitor),
+      DType::Bytes(ref mut v) => visitor.visit_byte_buf(mem::take(&mut **v)),
+      DType::String(ref mut v) => visitor.visit_string(mem::take(v)),
+      DType::Array(ref mut v) => visit_array(mem::take(&mut **v), visitor),
       _ => Err(self.invalid_type(&visitor)),
     }
   }
@@ -442,12 +566,12 @@ impl<'de> serde::Deserializer<'de> for DType {
     self.deserialize_unit(visitor)
   }
 
-  fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Error>
+  fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value, Error>
   where
     V: Visitor<'de>,
   {
     match self {
-      DType::Array(v) => visit_array(v, visitor),
+      DType::Array(ref mut v) => visit_array(mem::take(&mut **v), visitor),
       _ => Err(self.invalid_type(&visitor)),
     }
   }
@@ -475,18 +599,18 @@ impl<'de> serde::Deserializer<'de> for DType {
     self.deserialize_seq(visitor)
   }
 
-  fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Error>
+  fn deserialize_map<V>(mut self, visitor: V) -> Result<V::Value, Error>
   where
     V: Visitor<'de>,
   {
     match self {
-      DType::Object(v) => visit_object(v, visitor),
+      DType::Object(ref mut v) => visit_object(mem::take(v), visitor),
       _ => Err(self.invalid_type(&visitor)),
     }
   }
 
   fn deserialize_struct<V>(
-    self,
+    mut self,
     _name: &'static str,
     _fields: &'static [&'static str],
     visitor: V,
@@ -495,8 +619,8 @@ impl<'de> serde::Deserializer<'de> for DType {
     V: Visitor<'de>,
   {
     match self {
-      DType::Array(v) => visit_array(v, visitor),
-      DType::Object(v) => visit_object(v, visitor),
+      DType::Array(ref mut v) => visit_array(mem::take(&mut **v), visitor),
+      DType::Object(ref mut v) => visit_object(mem::take(v), visitor),
       _ => Err(self.invalid_type(&visitor)),
     }
   }
@@ -594,19 +718,23 @@ impl<'de> VariantAccess<'de> for VariantDeserializer {
     }
   }
 
-  fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Error>
+  fn tuple_variant<V>(
+    mut self,
+    _len: usize,
+    visitor: V,
+  ) -> Result<V::Value, Error>
   where
     V: Visitor<'de>,
   {
     match self.value {
-      Some(DType::Array(v)) => {
+      Some(DType::Array(ref mut v)) => {
         if v.is_empty() {
           visitor.visit_unit()
         } else {
-          visit_array(v, visitor)
+          visit_array(mem::take(&mut **v), visitor)
         }
       }
-      Some(other) => Err(serde::de::Error::invalid_type(
+      Some(ref other) => Err(serde::de::Error::invalid_type(
         other.unexpected(),
         &"tuple variant",
       )),
@@ -618,7 +746,7 @@ impl<'de> VariantAccess<'de> for VariantDeserializer {
   }
 
   fn struct_variant<V>(
-    self,
+    mut self,
     _fields: &'static [&'static str],
     visitor: V,
   ) -> Result<V::Value, Error>
@@ -626,8 +754,8 @@ impl<'de> VariantAccess<'de> for VariantDeserializer {
     V: Visitor<'de>,
   {
     match self.value {
-      Some(DType::Object(v)) => visit_object(v, visitor),
-      Some(other) => Err(serde::de::Error::invalid_type(
+      Some(DType::Object(ref mut v)) => visit_object(mem::take(v), visitor),
+      Some(ref other) => Err(serde::de::Error::invalid_type(
         other.unexpected(),
         &"struct variant",
       )),
@@ -844,6 +972,7 @@ impl<'de> serde::Deserializer<'de> for &'de DType {
     match *self {
       DType::Null => visitor.visit_unit(),
       DType::Boolean(v) => visitor.visit_bool(v),
+      DType::Bytes(ref v) => visitor.visit_borrowed_bytes(v),
       DType::Number(ref n) => n.deserialize_any(visitor),
       DType::String(ref v) => visitor.visit_borrowed_str(v),
       DType::Array(ref v) => visit_array_ref(v, visitor),
@@ -981,6 +1110,7 @@ impl<'de> serde::Deserializer<'de> for &'de DType {
     V: Visitor<'de>,
   {
     match *self {
+      DType::Bytes(ref v) => visitor.visit_borrowed_bytes(v),
       DType::String(ref v) => visitor.visit_borrowed_str(v),
       DType::Array(ref v) => visit_array_ref(v, visitor),
       _ => Err(self.invalid_type(&visitor)),
@@ -1110,7 +1240,7 @@ impl<'de> EnumAccess<'de> for EnumRefDeserializer<'de> {
   where
     V: DeserializeSeed<'de>,
   {
-    let variant = self.variant.into_deserializer();
+    let variant = BorrowedStrDeserializer::new(self.variant);
     let visitor = VariantRefDeserializer { value: self.value };
     seed.deserialize(variant).map(|v| (v, visitor))
   }
@@ -1375,9 +1505,7 @@ impl<'de> serde::Deserializer<'de> for MapKeyDeserializer<'de> {
   where
     V: Visitor<'de>,
   {
-    self
-      .key
-      .into_deserializer()
+    BorrowedCowStrDeserializer::new(self.key)
       .deserialize_enum(name, variants, visitor)
   }
 
@@ -1452,7 +1580,7 @@ impl<'de> Visitor<'de> for KeyClassifier {
 
 impl DType {
   #[cold]
-  fn invalid_type<E>(&self, exp: &dyn Expected) -> E
+  pub(crate) fn invalid_type<E>(&self, exp: &dyn Expected) -> E
   where
     E: serde::de::Error,
   {
@@ -1464,6 +1592,7 @@ impl DType {
     match *self {
       DType::Null => Unexpected::Unit,
       DType::Boolean(b) => Unexpected::Bool(b),
+      DType::Bytes(ref b) => Unexpected::Bytes(b),
       DType::Number(ref n) => n.unexpected(),
       DType::String(ref s) => Unexpected::Str(s),
       DType::Array(_) => Unexpected::Seq,
@@ -12,9 +12,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::dtype::{map::Map, number::Number, DType};
+use crate::dtype::{
+  literal::{self, FromLiteral, Literal, ParseMode},
+  map::Map,
+  number::Number,
+  DType, DateTime,
+};
+use crate::{Error, Result};
 
-use std::{borrow::Cow, iter::FromIterator};
+use std::{
+  borrow::Cow,
+  collections::{BTreeMap, HashMap},
+  convert::TryFrom,
+  hash::BuildHasher,
+  iter::FromIterator,
+  time::Duration,
+};
+
+use chrono::{DateTime as ChronoDateTime, NaiveDate, Utc};
+use serde::de::{DeserializeOwned, Error as _, Unexpected};
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
 
 macro_rules! from_integer {
   ($($ty:ident)*) => {
@@ -183,7 +201,7 @@ impl<T: Into<DType>> From<Vec<T>> for DType {
   /// let x: DType = v.into();
   /// ```
   fn from(f: Vec<T>) -> Self {
-    DType::Array(f.into_iter().map(Into::into).collect())
+    DType::Array(Box::new(f.into_iter().map(Into::into).collect()))
   }
 }
 
@@ -199,7 +217,7 @@ impl<'a, T: Clone + Into<DType>> From<&'a [T]> for DType {
   /// let x: DType = v.into();
   /// ```
   fn from(f: &'a [T]) -> Self {
-    DType::Array(f.iter().cloned().map(Into::into).collect())
+    DType::Array(Box::new(f.iter().cloned().map(Into::into).collect()))
   }
 }
 
@@ -229,7 +247,7 @@ impl<T: Into<DType>> FromIterator<T> for DType {
   /// let x: DType = DType::from_iter(vec!["lorem", "ipsum", "dolor"]);
   /// ```
   fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-    DType::Array(iter.into_iter().map(Into::into).collect())
+    DType::Array(Box::new(iter.into_iter().map(Into::into).collect()))
   }
 }
 
@@ -269,3 +287,335 @@ impl From<()> for DType {
     DType::Null
   }
 }
+
+impl<T: Into<DType>> From<Option<T>> for DType {
+  /// Convert `Some(value)` to `value` & `None` to `DType::Null`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::DType;
+  ///
+  /// let nickname: Option<&str> = None;
+  /// assert_eq!(DType::from(nickname), DType::Null);
+  /// assert_eq!(DType::from(Some("Ada")), DType::from("Ada"));
+  /// ```
+  fn from(f: Option<T>) -> Self {
+    f.map_or(DType::Null, Into::into)
+  }
+}
+
+impl<K, V, S> From<HashMap<K, V, S>> for DType
+where
+  K: Into<String>,
+  V: Into<DType>,
+{
+  /// Convert a `HashMap` to a `DType` object.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::{json, DType};
+  /// use std::collections::HashMap;
+  ///
+  /// let mut scores = HashMap::new();
+  /// scores.insert("ada", 36);
+  /// assert_eq!(DType::from(scores), json!({ "ada": 36 }));
+  /// ```
+  fn from(f: HashMap<K, V, S>) -> Self {
+    f.into_iter().collect()
+  }
+}
+
+impl<K, V> From<BTreeMap<K, V>> for DType
+where
+  K: Into<String>,
+  V: Into<DType>,
+{
+  /// Convert a `BTreeMap` to a `DType` object.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::{json, DType};
+  /// use std::collections::BTreeMap;
+  ///
+  /// let mut scores = BTreeMap::new();
+  /// scores.insert("ada", 36);
+  /// assert_eq!(DType::from(scores), json!({ "ada": 36 }));
+  /// ```
+  fn from(f: BTreeMap<K, V>) -> Self {
+    f.into_iter().collect()
+  }
+}
+
+impl From<DateTime> for DType {
+  /// Convert `DateTime` to `DType`.
+  fn from(f: DateTime) -> Self {
+    DType::DateTime(f)
+  }
+}
+
+impl From<ChronoDateTime<Utc>> for DType {
+  /// Convert chrono's `DateTime<Utc>` to `DType`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use chrono::{TimeZone, Utc};
+  /// use sage::DType;
+  ///
+  /// let d = Utc.with_ymd_and_hms(1815, 12, 10, 0, 0, 0).unwrap();
+  /// let x: DType = d.into();
+  /// assert!(matches!(x, DType::DateTime(_)));
+  /// ```
+  fn from(f: ChronoDateTime<Utc>) -> Self {
+    DType::DateTime(f.into())
+  }
+}
+
+impl From<NaiveDate> for DType {
+  /// Convert a date to an `xsd:date` value object, see `Literal::to_dtype`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use chrono::NaiveDate;
+  /// use sage::{dtype::xsd, json, DType};
+  ///
+  /// let d = NaiveDate::from_ymd_opt(1815, 12, 10).unwrap();
+  /// assert_eq!(
+  ///   DType::from(d),
+  ///   json!({ "@value": "1815-12-10", "@type": xsd::DATE })
+  /// );
+  /// ```
+  fn from(f: NaiveDate) -> Self {
+    Literal::from(f).into()
+  }
+}
+
+impl From<Duration> for DType {
+  /// Convert a `Duration` to an `xsd:duration` value object, see
+  /// `Literal::to_dtype`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::{dtype::xsd, json, DType};
+  /// use std::time::Duration;
+  ///
+  /// assert_eq!(
+  ///   DType::from(Duration::from_millis(90_500)),
+  ///   json!({ "@value": "PT90.5S", "@type": xsd::DURATION })
+  /// );
+  /// ```
+  fn from(f: Duration) -> Self {
+    Literal::from(f).into()
+  }
+}
+
+#[cfg(feature = "uuid")]
+impl From<Uuid> for DType {
+  /// Convert a `Uuid` to its hyphenated string.
+  fn from(f: Uuid) -> Self {
+    DType::String(f.to_hyphenated().to_string())
+  }
+}
+
+#[cfg(feature = "url")]
+impl From<url::Url> for DType {
+  /// Convert a `Url` to its serialized string.
+  fn from(f: url::Url) -> Self {
+    DType::String(f.into())
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | TryFrom<DType>.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+// `TryFrom<DType> for Option<T>` would overlap with the standard library's
+// `TryFrom<U> for T where U: Into<T>`, use `sage::from_dtype` for those.
+
+macro_rules! try_from_deserialize {
+  ($($ty:ty)*) => {
+    $(
+      impl TryFrom<DType> for $ty {
+        type Error = Error;
+
+        /// Converts with `sage::from_dtype`.
+        fn try_from(value: DType) -> Result<Self> {
+          crate::dtype::from_dtype(value)
+        }
+      }
+    )*
+  };
+}
+
+try_from_deserialize! {
+  bool String
+  i8 i16 i32 i64 isize
+  u8 u16 u32 u64 usize
+  f32 f64
+}
+
+impl<T: DeserializeOwned> TryFrom<DType> for Vec<T> {
+  type Error = Error;
+
+  /// Converts an array with `sage::from_dtype`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::json;
+  /// use std::convert::TryFrom;
+  ///
+  /// let v = Vec::<u8>::try_from(json!([1, 2, 3])).unwrap();
+  /// assert_eq!(v, vec![1, 2, 3]);
+  /// assert!(Vec::<u8>::try_from(json!([1, -2])).is_err());
+  /// ```
+  fn try_from(value: DType) -> Result<Self> {
+    crate::dtype::from_dtype(value)
+  }
+}
+
+impl<T, S> TryFrom<DType> for HashMap<String, T, S>
+where
+  T: DeserializeOwned,
+  S: BuildHasher + Default,
+{
+  type Error = Error;
+
+  /// Converts an object with `sage::from_dtype`.
+  fn try_from(value: DType) -> Result<Self> {
+    crate::dtype::from_dtype(value)
+  }
+}
+
+impl<T: DeserializeOwned> TryFrom<DType> for BTreeMap<String, T> {
+  type Error = Error;
+
+  /// Converts an object with `sage::from_dtype`.
+  fn try_from(value: DType) -> Result<Self> {
+    crate::dtype::from_dtype(value)
+  }
+}
+
+/// Reads the literal `value` stands for (see `Literal::from_dtype`)
+/// leniently, so plain strings in the right lexical form are accepted too.
+fn literal_value<T: FromLiteral>(value: &DType, exp: &str) -> Result<T> {
+  match Literal::from_dtype(value) {
+    Some(literal) => literal
+      .value_with(ParseMode::Lenient)
+      .map_err(|_| value.invalid_type(&exp)),
+    None => Err(Error::invalid_type(Unexpected::Unit, &exp)),
+  }
+}
+
+impl TryFrom<DType> for DateTime {
+  type Error = Error;
+
+  /// Converts a `DType::DateTime`, or a string or `xsd:dateTime` value
+  /// object in RFC 3339 form.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::{json, DateTime};
+  /// use std::convert::TryFrom;
+  ///
+  /// assert!(DateTime::try_from(json!("1815-12-10T00:00:00Z")).is_ok());
+  /// assert!(DateTime::try_from(json!("someday")).is_err());
+  /// ```
+  fn try_from(value: DType) -> Result<Self> {
+    match value {
+      DType::DateTime(ref d) => Ok(d.clone()),
+      value => literal_value(&value, "a datetime"),
+    }
+  }
+}
+
+impl TryFrom<DType> for ChronoDateTime<Utc> {
+  type Error = Error;
+
+  /// Converts like `DateTime::try_from`.
+  fn try_from(value: DType) -> Result<Self> {
+    DateTime::try_from(value).map(Into::into)
+  }
+}
+
+impl TryFrom<DType> for NaiveDate {
+  type Error = Error;
+
+  /// Converts a string or `xsd:date` value object, the reverse of
+  /// `DType::from(NaiveDate)`.
+  fn try_from(value: DType) -> Result<Self> {
+    literal_value(&value, "a date")
+  }
+}
+
+impl TryFrom<DType> for Duration {
+  type Error = Error;
+
+  /// Converts a string or `xsd:duration` value object in days, hours,
+  /// minutes & seconds, the reverse of `DType::from(Duration)`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::{json, DType};
+  /// use std::{convert::TryFrom, time::Duration};
+  ///
+  /// let d = Duration::try_from(json!("PT1H30M")).unwrap();
+  /// assert_eq!(d, Duration::from_secs(5_400));
+  ///
+  /// let value = DType::from(Duration::from_millis(1_500));
+  /// assert_eq!(Duration::try_from(value).unwrap().as_millis(), 1_500);
+  /// ```
+  fn try_from(value: DType) -> Result<Self> {
+    literal_value(&value, "a duration")
+  }
+}
+
+#[cfg(feature = "uuid")]
+impl TryFrom<DType> for Uuid {
+  type Error = Error;
+
+  /// Parses a UUID string, see `DType::as_uuid`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::json;
+  /// use std::convert::TryFrom;
+  /// use uuid::Uuid;
+  ///
+  /// let id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+  /// let uuid = Uuid::try_from(json!(id)).unwrap();
+  /// assert_eq!(uuid.to_string(), id);
+  /// ```
+  fn try_from(value: DType) -> Result<Self> {
+    match value {
+      DType::String(ref s) => literal::uuid(s)
+        .ok_or_else(|| Error::invalid_value(Unexpected::Str(s), &"a UUID")),
+      value => Err(value.invalid_type(&"a UUID string")),
+    }
+  }
+}
+
+#[cfg(feature = "url")]
+impl TryFrom<DType> for url::Url {
+  type Error = Error;
+
+  /// Parses an absolute URL string.
+  fn try_from(value: DType) -> Result<Self> {
+    match value {
+      DType::String(ref s) => url::Url::parse(s).map_err(Error::custom),
+      value => Err(value.invalid_type(&"a URL string")),
+    }
+  }
+}
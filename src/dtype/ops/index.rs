@@ -53,10 +53,11 @@ pub trait Index: private::Sealed {
   #[doc(hidden)]
   fn index_into_mut<'v>(&self, v: &'v mut DType) -> Option<&'v mut DType>;
 
-  /// Panic if array index out of bounds. If key is not already in the object,
-  /// insert it with a value of null. Panic if DType is a type that cannot be
-  /// indexed into, except if DType is null then it can be treated as an empty
-  /// object.
+  /// Append a null to the array if the index is its length, and panic if
+  /// the index is further out of bounds. If key is not already in the
+  /// object, insert it with a value of null. Panic if DType is a type that
+  /// cannot be indexed into, except if DType is null then it can be treated
+  /// as an empty array or object.
   #[doc(hidden)]
   fn index_or_insert<'v>(&self, v: &'v mut DType) -> &'v mut DType;
 }
@@ -75,15 +76,22 @@ impl Index for usize {
     }
   }
   fn index_or_insert<'v>(&self, v: &'v mut DType) -> &'v mut DType {
+    if let DType::Null = *v {
+      *v = DType::Array(Box::default());
+    }
     match *v {
       DType::Array(ref mut vec) => {
+        // Only append, so a stray index can't allocate a huge array.
         let len = vec.len();
-        vec.get_mut(*self).unwrap_or_else(|| {
+        if *self == len {
+          vec.push(DType::Null);
+        } else if *self > len {
           panic!(
             "cannot access index {} of JSON array of length {}",
             self, len
-          )
-        })
+          );
+        }
+        &mut vec[*self]
       }
       _ => panic!("cannot access index {} of JSON {}", self, Type(v)),
     }
@@ -162,6 +170,7 @@ impl<'a> fmt::Display for Type<'a> {
       DType::Boolean(_) => formatter.write_str("boolean"),
       DType::Number(_) => formatter.write_str("number"),
       DType::String(_) => formatter.write_str("string"),
+      DType::Bytes(_) => formatter.write_str("bytes"),
       DType::Array(_) => formatter.write_str("array"),
       DType::Object(_) => formatter.write_str("object"),
       DType::DateTime(_) => formatter.write_str("datetime"),
@@ -235,9 +244,10 @@ where
   /// Write into a `sage::DType` using the syntax `value[0] = ...` or
   /// `value["k"] = ...`.
   ///
-  /// If the index is a number, the value must be an array of length bigger
-  /// than the index. Indexing into a value that is not an array or an array
-  /// that is too small will panic.
+  /// If the index is a number, the value must be an array or null which is
+  /// treated like an empty array. Indexing an array at its length appends
+  /// a null to write into. Indexing past its length, or into a value that
+  /// is neither an array nor null, will panic.
   ///
   /// If the index is a string, the value must be an object or null which is
   /// treated like an empty object. If the key is not already present in the
@@ -263,6 +273,14 @@ where
   /// // inserted a deeply nested key
   /// data["a"]["b"]["c"]["d"] = json!(true);
   ///
+  /// // paths auto-vivify objects & arrays
+  /// data["person"]["aliases"][0] = json!("Ada");
+  /// data["person"]["aliases"][1] = json!("Countess of Lovelace");
+  /// assert_eq!(
+  ///   data["person"]["aliases"],
+  ///   json!(["Ada", "Countess of Lovelace"])
+  /// );
+  ///
   /// println!("{}", data);
   /// ```
   fn index_mut(&mut self, index: I) -> &mut DType {
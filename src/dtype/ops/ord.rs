@@ -0,0 +1,149 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::cmp::Ordering;
+
+use crate::dtype::{DType, Map, Number};
+
+/// Values of different types are ordered by the rank of their type:
+///
+/// `null` < booleans < numbers < strings < bytes < datetimes < arrays <
+/// objects
+///
+/// Values of the same type are ordered by value: `false` < `true`, numbers
+/// numerically, strings & bytes by their bytes, datetimes chronologically,
+/// arrays lexicographically & objects lexicographically by their entries
+/// sorted by key.
+///
+/// The order is consistent with `Eq`: an integer & a float with the same
+/// value (`1` & `1.0`) aren't equal, so the integer sorts first.
+///
+/// # Example
+///
+/// ```rust
+/// # use sage::json;
+/// #
+/// let mut values = vec![
+///   json!({ "a": 1 }),
+///   json!([1, 2]),
+///   json!("b"),
+///   json!(1.0),
+///   json!(1),
+///   json!(-2.5),
+///   json!(true),
+///   json!(null),
+/// ];
+/// values.sort();
+///
+/// assert_eq!(
+///   values,
+///   vec![
+///     json!(null),
+///     json!(true),
+///     json!(-2.5),
+///     json!(1),
+///     json!(1.0),
+///     json!("b"),
+///     json!([1, 2]),
+///     json!({ "a": 1 }),
+///   ]
+/// );
+/// ```
+impl Ord for DType {
+  fn cmp(&self, other: &DType) -> Ordering {
+    match (self, other) {
+      (DType::Null, DType::Null) => Ordering::Equal,
+      (DType::Boolean(a), DType::Boolean(b)) => a.cmp(b),
+      (DType::Number(a), DType::Number(b)) => a.cmp(b),
+      (DType::String(a), DType::String(b)) => a.cmp(b),
+      (DType::Bytes(a), DType::Bytes(b)) => a.cmp(b),
+      (DType::DateTime(a), DType::DateTime(b)) => a.cmp(b),
+      (DType::Array(a), DType::Array(b)) => a.cmp(b),
+      (DType::Object(a), DType::Object(b)) => a.cmp(b),
+      (a, b) => rank(a).cmp(&rank(b)),
+    }
+  }
+}
+
+impl PartialOrd for DType {
+  fn partial_cmp(&self, other: &DType) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+fn rank(value: &DType) -> u8 {
+  match value {
+    DType::Null => 0,
+    DType::Boolean(_) => 1,
+    DType::Number(_) => 2,
+    DType::String(_) => 3,
+    DType::Bytes(_) => 4,
+    DType::DateTime(_) => 5,
+    DType::Array(_) => 6,
+    DType::Object(_) => 7,
+  }
+}
+
+/// Numbers are ordered numerically; integers sort before floats of the same
+/// value.
+impl Ord for Number {
+  fn cmp(&self, other: &Number) -> Ordering {
+    let ordering = match (integer(self), integer(other)) {
+      (Some(a), Some(b)) => return a.cmp(&b),
+      _ => {
+        let (a, b) = (self.as_f64(), other.as_f64());
+        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+      }
+    };
+    let ordering = ordering.then_with(|| self.is_f64().cmp(&other.is_f64()));
+    // Distinct representations of the same value (`1.0` & `1.00`) aren't
+    // equal either.
+    #[cfg(feature = "arbitrary_precision")]
+    let ordering = ordering.then_with(|| self.n.cmp(&other.n));
+    ordering
+  }
+}
+
+impl PartialOrd for Number {
+  fn partial_cmp(&self, other: &Number) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+fn integer(n: &Number) -> Option<i128> {
+  n.as_i64()
+    .map(i128::from)
+    .or_else(|| n.as_u64().map(i128::from))
+}
+
+/// Objects are ordered lexicographically by their entries sorted by key.
+impl Ord for Map<String, DType> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    sorted(self).cmp(&sorted(other))
+  }
+}
+
+impl PartialOrd for Map<String, DType> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+fn sorted(map: &Map<String, DType>) -> Vec<(&String, &DType)> {
+  #[cfg_attr(not(feature = "preserve_order"), allow(unused_mut))]
+  let mut entries: Vec<_> = map.iter().collect();
+  #[cfg(feature = "preserve_order")]
+  entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+  entries
+}
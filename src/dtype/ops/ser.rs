@@ -14,7 +14,10 @@
 
 use std::fmt;
 
-use crate::{to_dtype, DType, Error, ErrorCode, Map, Number, Result};
+use crate::{
+  dtype::literal::base64_encode, to_dtype, DType, Error, ErrorCode, Map,
+  Number, Result,
+};
 
 use serde::ser::{Impossible, Serialize};
 #[cfg(feature = "arbitrary_precision")]
@@ -37,6 +40,11 @@ impl Serialize for DType {
     match *self {
       DType::Null => serializer.serialize_unit(),
       DType::Boolean(b) => serializer.serialize_bool(b),
+      // Base64 in human readable formats, native bytes otherwise.
+      DType::Bytes(ref b) if serializer.is_human_readable() => {
+        serializer.serialize_str(&base64_encode(b))
+      }
+      DType::Bytes(ref b) => serializer.serialize_bytes(b),
       DType::Number(ref n) => n.serialize(serializer),
       DType::String(ref s) => serializer.serialize_str(s),
       DType::Array(ref v) => v.serialize(serializer),
@@ -48,8 +56,14 @@ impl Serialize for DType {
         }
         map.end()
       }
-      // TODO: Handle `DateTime`.
-      DType::DateTime(_) => todo!(),
+      // Serialized as an RFC 3339 string, e.g. `2021-03-14T15:09:26Z`,
+      // wrapped in a newtype struct for serializers with a configurable
+      // representation.
+      DType::DateTime(ref d) => serializer.serialize_newtype_struct(
+        crate::datetime::TOKEN,
+        &d.as_chrono()
+          .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+      ),
     }
   }
 }
@@ -177,8 +191,7 @@ impl serde::Serializer for Serializer {
   }
 
   fn serialize_bytes(self, value: &[u8]) -> Result<DType> {
-    let vec = value.iter().map(|&b| DType::Number(b.into())).collect();
-    Ok(DType::Array(vec))
+    Ok(DType::Bytes(Box::new(value.to_vec())))
   }
 
   #[inline]
@@ -369,7 +382,7 @@ impl serde::ser::SerializeSeq for SerializeVec {
   }
 
   fn end(self) -> Result<DType> {
-    Ok(DType::Array(self.vec))
+    Ok(DType::Array(Box::new(self.vec)))
   }
 }
 
@@ -428,7 +441,7 @@ impl serde::ser::SerializeTupleVariant for SerializeTupleVariant {
   fn end(self) -> Result<DType> {
     let mut object = Map::new();
 
-    object.insert(self.name, DType::Array(self.vec));
+    object.insert(self.name, DType::Array(Box::new(self.vec)));
 
     Ok(DType::Object(object))
   }
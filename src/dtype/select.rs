@@ -0,0 +1,735 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JSONPath selectors over `DType` documents.
+
+use crate::{
+  datastore::json,
+  dtype::DType,
+  error::{Error, ErrorCode},
+  Result,
+};
+
+/// `Selector` is a compiled [JSONPath] expression, selecting values from
+/// nested `DType` documents. Parse it once & reuse it for many documents.
+///
+/// | Syntax                  | Selects                                    |
+/// |-------------------------|--------------------------------------------|
+/// | `$`                     | the root value                             |
+/// | `.name`, `['name']`     | a member of an object                      |
+/// | `.*`, `[*]`             | every member or element                    |
+/// | `[0]`, `[-1]`           | an element, negative from the end          |
+/// | `[start:end:step]`      | a slice of an array                        |
+/// | `[0,2]`, `['a','b']`    | several selectors at once                  |
+/// | `..name`, `..*`, `..[]` | the same, at any depth                     |
+/// | `[?(@.price < 10)]`     | members or elements matching a filter      |
+///
+/// Filters compare `@` (the candidate) or `$` queries with each other or
+/// with literals using `==`, `!=`, `<`, `<=`, `>` & `>=`, test for
+/// existence (`[?(@.isbn)]`) and combine with `&&`, `||`, `!` and
+/// parentheses. A query compared with a value must select a single value.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::dtype::Selector;
+/// use sage::json;
+///
+/// let store = json!({
+///   "book": [
+///     { "author": "Tolkien", "price": 22.99, "isbn": "0-395-19395-8" },
+///     { "author": "Herbert", "price": 8.99 },
+///     { "author": "Le Guin", "price": 9.5 }
+///   ],
+///   "bicycle": { "price": 19.95 }
+/// });
+///
+/// let cheap = Selector::parse("$.book[?(@.price < 10)].author").unwrap();
+/// assert_eq!(cheap.select(&store), vec!["Herbert", "Le Guin"]);
+///
+/// let prices = Selector::parse("$..price").unwrap();
+/// assert_eq!(prices.select(&store).len(), 4);
+///
+/// let last = Selector::parse("$.book[-1:]['author','price']").unwrap();
+/// assert_eq!(last.select(&store), vec![&json!("Le Guin"), &json!(9.5)]);
+///
+/// assert!(Selector::parse("$.book[").is_err());
+/// ```
+///
+/// [JSONPath]: https://www.rfc-editor.org/rfc/rfc9535
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selector {
+  segments: Vec<Segment>,
+}
+
+impl Selector {
+  /// Parses a JSONPath expression starting with `$`.
+  pub fn parse(s: &str) -> Result<Selector> {
+    let mut parser = Parser { s, pos: 0 };
+    if !parser.eat('$') {
+      return Err(parser.error());
+    }
+    let segments = parser.segments()?;
+    parser.skip_whitespace();
+    if parser.pos < s.len() {
+      return Err(parser.error());
+    }
+    Ok(Selector { segments })
+  }
+
+  /// Returns every value of `root` the selector matches, in document
+  /// order.
+  pub fn select<'a>(&self, root: &'a DType) -> Vec<&'a DType> {
+    evaluate(&self.segments, root, root)
+  }
+
+  /// Returns mutable references to the values of `root` the selector
+  /// matches.
+  ///
+  /// Values nested inside another match (e.g. with `$..*`) can't be
+  /// borrowed at the same time as their parent, so only the outermost
+  /// match is returned.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::dtype::Selector;
+  /// use sage::json;
+  ///
+  /// let mut order = json!({ "items": [{ "price": 10 }, { "price": 4 }] });
+  ///
+  /// let prices = Selector::parse("$.items[*].price").unwrap();
+  /// for price in prices.select_mut(&mut order) {
+  ///   *price = json!(price.as_i64().unwrap() * 2);
+  /// }
+  /// assert_eq!(order, json!({ "items": [{ "price": 20 }, { "price": 8 }] }));
+  /// ```
+  pub fn select_mut<'a>(&self, root: &'a mut DType) -> Vec<&'a mut DType> {
+    // Filters may refer to `$`, which can't be read while it's borrowed
+    // mutably.
+    let snapshot = self.refers_to_root().then(|| root.clone());
+    let null = DType::Null;
+    let snapshot = snapshot.as_ref().unwrap_or(&null);
+
+    let mut nodes = vec![root];
+    for segment in &self.segments {
+      let mut next = Vec::new();
+      for node in nodes {
+        segment.apply_mut(node, snapshot, &mut next);
+      }
+      nodes = next;
+    }
+    nodes
+  }
+
+  fn refers_to_root(&self) -> bool {
+    self.segments.iter().any(Segment::refers_to_root)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Evaluation
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+#[derive(Clone, Debug, PartialEq)]
+struct Segment {
+  /// `..` segments apply to the node & all of its descendants.
+  descendant: bool,
+  selectors: Vec<Select>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Select {
+  Name(String),
+  Index(i64),
+  Slice(Option<i64>, Option<i64>, i64),
+  Wildcard,
+  Filter(Expr),
+}
+
+fn evaluate<'a>(
+  segments: &[Segment],
+  root: &'a DType,
+  current: &'a DType,
+) -> Vec<&'a DType> {
+  let mut nodes = vec![current];
+  for segment in segments {
+    let mut next = Vec::new();
+    for node in nodes {
+      if segment.descendant {
+        let mut descendants = Vec::new();
+        descend(node, &mut descendants);
+        for node in descendants {
+          segment.apply(node, root, &mut next);
+        }
+      } else {
+        segment.apply(node, root, &mut next);
+      }
+    }
+    nodes = next;
+  }
+  nodes
+}
+
+/// Collects `node` & its descendants in document order.
+fn descend<'a>(node: &'a DType, out: &mut Vec<&'a DType>) {
+  out.push(node);
+  match node {
+    DType::Array(list) => list.iter().for_each(|child| descend(child, out)),
+    DType::Object(map) => map.values().for_each(|child| descend(child, out)),
+    _ => {}
+  }
+}
+
+impl Segment {
+  fn apply<'a>(&self, node: &'a DType, root: &DType, out: &mut Vec<&'a DType>) {
+    for selector in &self.selectors {
+      match (selector, node) {
+        (Select::Name(name), DType::Object(map)) => out.extend(map.get(name)),
+        (Select::Index(i), DType::Array(list)) => {
+          out.extend(index(*i, list.len()).and_then(|i| list.get(i)))
+        }
+        (Select::Slice(start, end, step), DType::Array(list)) => out.extend(
+          slice(list.len(), *start, *end, *step)
+            .into_iter()
+            .map(|i| &list[i]),
+        ),
+        (Select::Wildcard, DType::Array(list)) => out.extend(list.iter()),
+        (Select::Wildcard, DType::Object(map)) => out.extend(map.values()),
+        (Select::Filter(expr), DType::Array(list)) => {
+          out.extend(list.iter().filter(|child| expr.test(root, child)))
+        }
+        (Select::Filter(expr), DType::Object(map)) => {
+          out.extend(map.values().filter(|child| expr.test(root, child)))
+        }
+        _ => {}
+      }
+    }
+  }
+
+  /// Selects from the children of `node`, descending into the children
+  /// which don't match for `..` segments.
+  fn apply_mut<'a>(
+    &self,
+    node: &'a mut DType,
+    root: &DType,
+    out: &mut Vec<&'a mut DType>,
+  ) {
+    match node {
+      DType::Array(list) => {
+        let matched = self.matched_elements(list, root);
+        for (child, matched) in list.iter_mut().zip(matched) {
+          if matched {
+            out.push(child);
+          } else if self.descendant {
+            self.apply_mut(child, root, out);
+          }
+        }
+      }
+      DType::Object(map) => {
+        for (key, child) in map.iter_mut() {
+          if self.matches_member(key, child, root) {
+            out.push(child);
+          } else if self.descendant {
+            self.apply_mut(child, root, out);
+          }
+        }
+      }
+      _ => {}
+    }
+  }
+
+  fn matched_elements(&self, list: &[DType], root: &DType) -> Vec<bool> {
+    let mut matched = vec![false; list.len()];
+    for selector in &self.selectors {
+      match selector {
+        Select::Index(i) => {
+          if let Some(i) = index(*i, list.len()) {
+            matched[i] = true;
+          }
+        }
+        Select::Slice(start, end, step) => {
+          for i in slice(list.len(), *start, *end, *step) {
+            matched[i] = true;
+          }
+        }
+        Select::Wildcard => matched.iter_mut().for_each(|m| *m = true),
+        Select::Filter(expr) => {
+          for (m, child) in matched.iter_mut().zip(list) {
+            *m |= expr.test(root, child);
+          }
+        }
+        Select::Name(_) => {}
+      }
+    }
+    matched
+  }
+
+  fn matches_member(&self, key: &str, child: &DType, root: &DType) -> bool {
+    self.selectors.iter().any(|selector| match selector {
+      Select::Name(name) => name == key,
+      Select::Wildcard => true,
+      Select::Filter(expr) => expr.test(root, child),
+      Select::Index(_) | Select::Slice(..) => false,
+    })
+  }
+
+  fn refers_to_root(&self) -> bool {
+    self.selectors.iter().any(|selector| match selector {
+      Select::Filter(expr) => expr.refers_to_root(),
+      _ => false,
+    })
+  }
+}
+
+/// Resolves a possibly negative index into an array of `len` elements.
+fn index(i: i64, len: usize) -> Option<usize> {
+  let i = if i < 0 { len as i64 + i } else { i };
+  (0..len as i64).contains(&i).then_some(i as usize)
+}
+
+/// Returns the indices selected by `[start:end:step]`, following the slice
+/// semantics of RFC 9535.
+fn slice(
+  len: usize,
+  start: Option<i64>,
+  end: Option<i64>,
+  step: i64,
+) -> Vec<usize> {
+  let len = len as i64;
+  let normalize = |i: i64| if i < 0 { len + i } else { i };
+  let mut indices = Vec::new();
+  if step > 0 {
+    let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+    let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+    let mut i = lower;
+    while i < upper {
+      indices.push(i as usize);
+      i += step;
+    }
+  } else if step < 0 {
+    let upper = start.map_or(len - 1, normalize).clamp(-1, len - 1);
+    let lower = end.map_or(-1, normalize).clamp(-1, len - 1);
+    let mut i = upper;
+    while lower < i {
+      indices.push(i as usize);
+      i += step;
+    }
+  }
+  indices
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Filters
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+  Or(Vec<Expr>),
+  And(Vec<Expr>),
+  Not(Box<Expr>),
+  Exists(Query),
+  Compare(Operand, Op, Operand),
+}
+
+/// A query embedded in a filter, relative to `@` or to `$`.
+#[derive(Clone, Debug, PartialEq)]
+struct Query {
+  relative: bool,
+  segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Operand {
+  Query(Query),
+  Literal(DType),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+  Eq,
+  Ne,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+}
+
+impl Expr {
+  fn test(&self, root: &DType, current: &DType) -> bool {
+    match self {
+      Expr::Or(exprs) => exprs.iter().any(|e| e.test(root, current)),
+      Expr::And(exprs) => exprs.iter().all(|e| e.test(root, current)),
+      Expr::Not(expr) => !expr.test(root, current),
+      Expr::Exists(query) => !query.select(root, current).is_empty(),
+      Expr::Compare(left, op, right) => {
+        let left = left.value(root, current);
+        let right = right.value(root, current);
+        compare(left, *op, right)
+      }
+    }
+  }
+
+  fn refers_to_root(&self) -> bool {
+    match self {
+      Expr::Or(exprs) | Expr::And(exprs) => {
+        exprs.iter().any(Expr::refers_to_root)
+      }
+      Expr::Not(expr) => expr.refers_to_root(),
+      Expr::Exists(query) => query.refers_to_root(),
+      Expr::Compare(left, _, right) => {
+        [left, right].iter().any(|operand| match operand {
+          Operand::Query(query) => query.refers_to_root(),
+          Operand::Literal(_) => false,
+        })
+      }
+    }
+  }
+}
+
+impl Query {
+  fn select<'a>(&self, root: &'a DType, current: &'a DType) -> Vec<&'a DType> {
+    let start = if self.relative { current } else { root };
+    evaluate(&self.segments, root, start)
+  }
+
+  fn refers_to_root(&self) -> bool {
+    !self.relative || self.segments.iter().any(Segment::refers_to_root)
+  }
+}
+
+impl Operand {
+  /// Returns the single value of the operand; queries selecting none or
+  /// several values have none.
+  fn value<'a>(
+    &'a self,
+    root: &'a DType,
+    current: &'a DType,
+  ) -> Option<&'a DType> {
+    match self {
+      Operand::Literal(value) => Some(value),
+      Operand::Query(query) => match query.select(root, current)[..] {
+        [value] => Some(value),
+        _ => None,
+      },
+    }
+  }
+}
+
+fn compare(left: Option<&DType>, op: Op, right: Option<&DType>) -> bool {
+  match op {
+    Op::Eq => match (left, right) {
+      (None, None) => true,
+      (Some(a), Some(b)) => equal(a, b),
+      _ => false,
+    },
+    Op::Ne => !compare(left, Op::Eq, right),
+    Op::Lt => match (left, right) {
+      (Some(a), Some(b)) => less(a, b),
+      _ => false,
+    },
+    Op::Le => compare(left, Op::Lt, right) || compare(left, Op::Eq, right),
+    Op::Gt => compare(right, Op::Lt, left),
+    Op::Ge => compare(right, Op::Le, left),
+  }
+}
+
+fn equal(a: &DType, b: &DType) -> bool {
+  match (a, b) {
+    (DType::Number(x), DType::Number(y)) => x.as_f64() == y.as_f64(),
+    (a, b) => a == b,
+  }
+}
+
+fn less(a: &DType, b: &DType) -> bool {
+  match (a, b) {
+    (DType::Number(x), DType::Number(y)) => x.as_f64() < y.as_f64(),
+    (DType::String(x), DType::String(y)) => x < y,
+    _ => false,
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Parser
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+struct Parser<'a> {
+  s: &'a str,
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn peek(&self) -> Option<char> {
+    self.s[self.pos..].chars().next()
+  }
+
+  fn skip_whitespace(&mut self) {
+    while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+      self.pos += 1;
+    }
+  }
+
+  fn eat(&mut self, c: char) -> bool {
+    self.skip_whitespace();
+    if self.peek() == Some(c) {
+      self.pos += c.len_utf8();
+      true
+    } else {
+      false
+    }
+  }
+
+  fn eat_str(&mut self, s: &str) -> bool {
+    self.skip_whitespace();
+    if self.s[self.pos..].starts_with(s) {
+      self.pos += s.len();
+      true
+    } else {
+      false
+    }
+  }
+
+  fn error(&self) -> Error {
+    Error::syntax(ErrorCode::InvalidSelector, 1, self.pos + 1)
+  }
+
+  fn segments(&mut self) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    loop {
+      let descendant = if self.eat_str("..") {
+        true
+      } else if self.eat('.') {
+        false
+      } else if self.s[self.pos..].trim_start().starts_with('[') {
+        segments.push(Segment {
+          descendant: false,
+          selectors: self.bracket()?,
+        });
+        continue;
+      } else {
+        return Ok(segments);
+      };
+
+      let selectors = if self.eat('*') {
+        vec![Select::Wildcard]
+      } else if descendant && self.s[self.pos..].starts_with('[') {
+        self.bracket()?
+      } else {
+        vec![Select::Name(self.name()?)]
+      };
+      segments.push(Segment {
+        descendant,
+        selectors,
+      });
+    }
+  }
+
+  fn name(&mut self) -> Result<String> {
+    let start = self.pos;
+    while matches!(
+      self.peek(),
+      Some(c) if c.is_alphanumeric() || c == '_' || !c.is_ascii()
+    ) {
+      self.pos += self.peek().map_or(1, char::len_utf8);
+    }
+    if start == self.pos {
+      return Err(self.error());
+    }
+    Ok(self.s[start..self.pos].to_string())
+  }
+
+  fn bracket(&mut self) -> Result<Vec<Select>> {
+    if !self.eat('[') {
+      return Err(self.error());
+    }
+    let mut selectors = vec![self.select()?];
+    while self.eat(',') {
+      selectors.push(self.select()?);
+    }
+    if !self.eat(']') {
+      return Err(self.error());
+    }
+    Ok(selectors)
+  }
+
+  fn select(&mut self) -> Result<Select> {
+    self.skip_whitespace();
+    match self.peek() {
+      Some('*') => {
+        self.pos += 1;
+        Ok(Select::Wildcard)
+      }
+      Some('\'' | '"') => Ok(Select::Name(self.string()?)),
+      Some('?') => {
+        self.pos += 1;
+        Ok(Select::Filter(self.or()?))
+      }
+      _ => {
+        let start = self.integer()?;
+        if !self.eat(':') {
+          return start.map(Select::Index).ok_or_else(|| self.error());
+        }
+        let end = self.integer()?;
+        let step = if self.eat(':') { self.integer()? } else { None };
+        Ok(Select::Slice(start, end, step.unwrap_or(1)))
+      }
+    }
+  }
+
+  fn integer(&mut self) -> Result<Option<i64>> {
+    self.skip_whitespace();
+    let start = self.pos;
+    if self.peek() == Some('-') {
+      self.pos += 1;
+    }
+    while matches!(self.peek(), Some(c) if c.is_ascii_digit()) {
+      self.pos += 1;
+    }
+    match &self.s[start..self.pos] {
+      "" => Ok(None),
+      digits => digits.parse().map(Some).map_err(|_| self.error()),
+    }
+  }
+
+  fn string(&mut self) -> Result<String> {
+    let quote = self.peek().ok_or_else(|| self.error())?;
+    self.pos += 1;
+    let mut out = String::new();
+    loop {
+      let c = self.peek().ok_or_else(|| self.error())?;
+      self.pos += c.len_utf8();
+      match c {
+        c if c == quote => return Ok(out),
+        '\\' => {
+          let escaped = self.peek().ok_or_else(|| self.error())?;
+          self.pos += escaped.len_utf8();
+          out.push(match escaped {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            'u' => {
+              let hex = self.s.get(self.pos..self.pos + 4);
+              let c = hex
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .and_then(char::from_u32)
+                .ok_or_else(|| self.error())?;
+              self.pos += 4;
+              c
+            }
+            c => c,
+          });
+        }
+        c => out.push(c),
+      }
+    }
+  }
+
+  fn or(&mut self) -> Result<Expr> {
+    let mut exprs = vec![self.and()?];
+    while self.eat_str("||") {
+      exprs.push(self.and()?);
+    }
+    Ok(match exprs.len() {
+      1 => exprs.remove(0),
+      _ => Expr::Or(exprs),
+    })
+  }
+
+  fn and(&mut self) -> Result<Expr> {
+    let mut exprs = vec![self.unary()?];
+    while self.eat_str("&&") {
+      exprs.push(self.unary()?);
+    }
+    Ok(match exprs.len() {
+      1 => exprs.remove(0),
+      _ => Expr::And(exprs),
+    })
+  }
+
+  fn unary(&mut self) -> Result<Expr> {
+    if self.eat('!') {
+      return Ok(Expr::Not(Box::new(self.unary()?)));
+    }
+    if self.eat('(') {
+      let expr = self.or()?;
+      return if self.eat(')') {
+        Ok(expr)
+      } else {
+        Err(self.error())
+      };
+    }
+
+    let left = self.operand()?;
+    let op = [
+      ("==", Op::Eq),
+      ("!=", Op::Ne),
+      ("<=", Op::Le),
+      (">=", Op::Ge),
+      ("<", Op::Lt),
+      (">", Op::Gt),
+    ]
+    .into_iter()
+    .find(|(token, _)| self.eat_str(token));
+
+    match (left, op) {
+      (left, Some((_, op))) => Ok(Expr::Compare(left, op, self.operand()?)),
+      (Operand::Query(query), None) => Ok(Expr::Exists(query)),
+      (Operand::Literal(_), None) => Err(self.error()),
+    }
+  }
+
+  fn operand(&mut self) -> Result<Operand> {
+    self.skip_whitespace();
+    let relative = match self.peek() {
+      Some('@') => true,
+      Some('$') => false,
+      Some('\'' | '"') => {
+        return Ok(Operand::Literal(DType::String(self.string()?)))
+      }
+      _ => return self.literal().map(Operand::Literal),
+    };
+    self.pos += 1;
+    let segments = self.segments()?;
+    Ok(Operand::Query(Query { relative, segments }))
+  }
+
+  /// Parses a number, `true`, `false` or `null`.
+  fn literal(&mut self) -> Result<DType> {
+    let start = self.pos;
+    while matches!(
+      self.peek(),
+      Some(c) if c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.')
+    ) {
+      self.pos += 1;
+    }
+    match &self.s[start..self.pos] {
+      "" => Err(self.error()),
+      token => json::from_str(token).map_err(|_| self.error()),
+    }
+  }
+}
@@ -0,0 +1,237 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deep memory accounting of values & graphs.
+
+use std::{
+  collections::{BTreeMap, HashMap, HashSet},
+  mem,
+};
+
+use crate::dtype::{DType, DateTime, Number};
+
+/// Entries per `BTreeMap` node.
+const BTREE_CAPACITY: usize = 11;
+
+/// Control bytes `HashMap` allocates past its buckets.
+const GROUP_WIDTH: usize = 16;
+
+/// `DeepSizeOf` reports the memory a value holds: its own size plus every
+/// heap allocation it owns, recursively.
+///
+/// Vectors & strings count their capacity, not their length. Maps count
+/// their entries along with an estimate of their internal overhead (node
+/// headers, control bytes & unused buckets). Allocator bookkeeping &
+/// shared data aren't counted, so sizes are estimates that are accurate
+/// enough to monitor datasets & drive eviction policies, not exact byte
+/// counts.
+///
+/// # Example
+///
+/// ```rust
+/// use std::mem::size_of;
+///
+/// use sage::{json, DType, DeepSizeOf};
+///
+/// assert_eq!(DType::Null.deep_size_of(), size_of::<DType>());
+///
+/// let name = DType::from(String::with_capacity(100));
+/// assert_eq!(name.deep_size_of(), size_of::<DType>() + 100);
+///
+/// let person = json!({ "name": "Ada", "languages": ["en", "yo"] });
+/// assert!(person.heap_size_of() > 2 * size_of::<DType>());
+/// ```
+pub trait DeepSizeOf {
+  /// Returns the bytes of the heap allocations owned by the value.
+  fn heap_size_of(&self) -> usize;
+
+  /// Returns the size of the value itself plus `heap_size_of`.
+  fn deep_size_of(&self) -> usize {
+    mem::size_of_val(self) + self.heap_size_of()
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `DType`.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+impl DeepSizeOf for DType {
+  fn heap_size_of(&self) -> usize {
+    match self {
+      DType::Array(values) => values.heap_size_of(),
+      DType::Bytes(bytes) => bytes.heap_size_of(),
+      DType::Number(number) => number.heap_size_of(),
+      DType::Object(map) => map.heap_size_of(),
+      DType::String(string) => string.heap_size_of(),
+      DType::Boolean(_) | DType::DateTime(_) | DType::Null => 0,
+    }
+  }
+}
+
+impl DeepSizeOf for Number {
+  #[cfg(not(feature = "arbitrary_precision"))]
+  fn heap_size_of(&self) -> usize {
+    0
+  }
+
+  #[cfg(feature = "arbitrary_precision")]
+  fn heap_size_of(&self) -> usize {
+    self.n.heap_size_of()
+  }
+}
+
+impl DeepSizeOf for DateTime {
+  fn heap_size_of(&self) -> usize {
+    0
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Standard library types.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+macro_rules! impl_no_heap {
+  ($($ty:ty),*) => {
+    $(
+      impl DeepSizeOf for $ty {
+        fn heap_size_of(&self) -> usize {
+          0
+        }
+      }
+    )*
+  };
+}
+
+impl_no_heap!(
+  (),
+  bool,
+  char,
+  u8,
+  u16,
+  u32,
+  u64,
+  u128,
+  usize,
+  i8,
+  i16,
+  i32,
+  i64,
+  i128,
+  isize,
+  f32,
+  f64
+);
+
+impl DeepSizeOf for String {
+  fn heap_size_of(&self) -> usize {
+    self.capacity()
+  }
+}
+
+impl<T: DeepSizeOf> DeepSizeOf for Box<T> {
+  fn heap_size_of(&self) -> usize {
+    (**self).deep_size_of()
+  }
+}
+
+impl<T: DeepSizeOf> DeepSizeOf for Option<T> {
+  fn heap_size_of(&self) -> usize {
+    self.as_ref().map_or(0, T::heap_size_of)
+  }
+}
+
+impl<A: DeepSizeOf, B: DeepSizeOf> DeepSizeOf for (A, B) {
+  fn heap_size_of(&self) -> usize {
+    self.0.heap_size_of() + self.1.heap_size_of()
+  }
+}
+
+impl<T: DeepSizeOf> DeepSizeOf for Vec<T> {
+  fn heap_size_of(&self) -> usize {
+    self.capacity() * mem::size_of::<T>()
+      + self.iter().map(T::heap_size_of).sum::<usize>()
+  }
+}
+
+impl<K: DeepSizeOf, V: DeepSizeOf> DeepSizeOf for BTreeMap<K, V> {
+  fn heap_size_of(&self) -> usize {
+    btree_size::<(K, V)>(self.len())
+      + self
+        .iter()
+        .map(|(k, v)| k.heap_size_of() + v.heap_size_of())
+        .sum::<usize>()
+  }
+}
+
+impl<K: DeepSizeOf, V: DeepSizeOf, S> DeepSizeOf for HashMap<K, V, S> {
+  fn heap_size_of(&self) -> usize {
+    hash_table_size::<(K, V)>(self.capacity())
+      + self
+        .iter()
+        .map(|(k, v)| k.heap_size_of() + v.heap_size_of())
+        .sum::<usize>()
+  }
+}
+
+impl<T: DeepSizeOf, S> DeepSizeOf for HashSet<T, S> {
+  fn heap_size_of(&self) -> usize {
+    hash_table_size::<T>(self.capacity())
+      + self.iter().map(T::heap_size_of).sum::<usize>()
+  }
+}
+
+#[cfg(feature = "preserve_order")]
+impl<K: DeepSizeOf, V: DeepSizeOf> DeepSizeOf for indexmap::IndexMap<K, V> {
+  fn heap_size_of(&self) -> usize {
+    // Entries are kept in a vector (with their hash), indexed by a table of
+    // positions.
+    self.capacity() * mem::size_of::<(usize, K, V)>()
+      + hash_table_size::<usize>(self.capacity())
+      + self
+        .iter()
+        .map(|(k, v)| k.heap_size_of() + v.heap_size_of())
+        .sum::<usize>()
+  }
+}
+
+/// Estimates the nodes of a `BTreeMap` holding `len` entries of type `T`,
+/// assuming they're two thirds full on average.
+fn btree_size<T>(len: usize) -> usize {
+  let nodes = len.div_ceil(BTREE_CAPACITY * 2 / 3);
+  // Each node holds its entries, a parent pointer, its index & length.
+  nodes * (BTREE_CAPACITY * mem::size_of::<T>() + 2 * mem::size_of::<usize>())
+}
+
+/// Estimates the table of a `HashMap` with room for `capacity` entries of
+/// type `T`: buckets are a power of two, at most 7/8 full, each with a
+/// control byte.
+fn hash_table_size<T>(capacity: usize) -> usize {
+  if capacity == 0 {
+    return 0;
+  }
+  let buckets = if capacity < 8 {
+    (capacity + 1).next_power_of_two()
+  } else {
+    (capacity * 8 / 7).next_power_of_two()
+  };
+  buckets * (mem::size_of::<T>() + 1) + GROUP_WIDTH
+}
@@ -87,7 +87,10 @@ impl Error {
   /// - `Category::Eof` - unexpected end of the input data
   pub fn classify(&self) -> Category {
     match self.err.code {
-      ErrorCode::Message(_) => Category::Data,
+      ErrorCode::Message(_)
+      | ErrorCode::VersionMismatch
+      | ErrorCode::UnknownPredicate
+      | ErrorCode::ConstraintViolation => Category::Data,
 
       ErrorCode::Io(_) | ErrorCode::Json(_) => Category::Io,
 
@@ -117,7 +120,30 @@ impl Error {
       | ErrorCode::TrailingCharacters
       | ErrorCode::UnexpectedEndOfHexEscape
       | ErrorCode::RecursionLimitExceeded
-      | ErrorCode::RegexParser => Category::Syntax,
+      | ErrorCode::InputTooLarge
+      | ErrorCode::ArrayTooLong
+      | ErrorCode::ObjectTooLarge
+      | ErrorCode::StringTooLong
+      | ErrorCode::RegexParser
+      | ErrorCode::InvalidIri
+      | ErrorCode::InvalidUrn
+      | ErrorCode::InvalidDid
+      | ErrorCode::InvalidBlobRef
+      | ErrorCode::InvalidStatement
+      | ErrorCode::InvalidPath
+      | ErrorCode::InvalidGeo
+      | ErrorCode::InvalidLanguageTag
+      | ErrorCode::InvalidFilter
+      | ErrorCode::InvalidSelector
+      | ErrorCode::InvalidTransform
+      | ErrorCode::InvalidQuery
+      | ErrorCode::InvalidUpdate
+      | ErrorCode::InvalidTurtle
+      | ErrorCode::InvalidRules
+      | ErrorCode::InvalidMapping
+      | ErrorCode::InvalidCsv
+      | ErrorCode::InvalidRdfXml
+      | ErrorCode::InvalidLiteral => Category::Syntax,
     }
   }
 
@@ -349,11 +375,93 @@ pub(crate) enum ErrorCode {
   /// Unexpected end of hex escape.
   UnexpectedEndOfHexEscape,
 
-  /// Encountered nesting of JSON maps and arrays more than 128 layers deep.
+  /// Encountered nesting of JSON maps and arrays deeper than the
+  /// deserializer's recursion limit.
   RecursionLimitExceeded,
 
+  /// JSON input is longer than `DeserializeOptions::max_bytes`.
+  InputTooLarge,
+
+  /// JSON array has more elements than `DeserializeOptions::max_array_len`.
+  ArrayTooLong,
+
+  /// JSON object has more entries than
+  /// `DeserializeOptions::max_object_entries`.
+  ObjectTooLarge,
+
+  /// JSON string is longer than `DeserializeOptions::max_string_len`.
+  StringTooLong,
+
   /// Could not parse regular expression pattern or pattern wasn't a match.
   RegexParser,
+
+  /// Malformed IRI or IRI containing illegal characters.
+  InvalidIri,
+
+  /// Malformed URN or URN with an invalid check digit.
+  InvalidUrn,
+
+  /// Malformed Decentralized Identifier (DID) or DID URL.
+  InvalidDid,
+
+  /// Malformed `sage:blob:<hash>` reference.
+  InvalidBlobRef,
+
+  /// Malformed N-Triples statement.
+  InvalidStatement,
+
+  /// Malformed property path.
+  InvalidPath,
+
+  /// Malformed WKT geometry or coordinates out of range.
+  InvalidGeo,
+
+  /// Malformed BCP 47 language tag.
+  InvalidLanguageTag,
+
+  /// Malformed or unsupported query filter.
+  InvalidFilter,
+
+  /// Malformed or unsupported JSONPath selector.
+  InvalidSelector,
+
+  /// Malformed transformation or call of an unknown function.
+  InvalidTransform,
+
+  /// Malformed or unsupported SPARQL query.
+  InvalidQuery,
+
+  /// Malformed or unsupported SPARQL Update request.
+  InvalidUpdate,
+
+  /// Malformed or unsupported Turtle document.
+  InvalidTurtle,
+
+  /// Malformed, unsafe or unstratifiable Datalog rules.
+  InvalidRules,
+
+  /// Malformed mapping of records to triples.
+  InvalidMapping,
+
+  /// Malformed CSV document, e.g. with an unterminated quote or a record
+  /// with more or fewer fields than the header.
+  InvalidCsv,
+
+  /// Malformed XML or RDF/XML document.
+  InvalidRdfXml,
+
+  /// Lexical form that isn't valid for its datatype, or a literal whose
+  /// datatype doesn't map to the requested Rust type.
+  InvalidLiteral,
+
+  /// A conditional update was made against a stale subject version.
+  VersionMismatch,
+
+  /// A statement used a predicate the ontology doesn't declare.
+  UnknownPredicate,
+
+  /// A statement violated a datatype or cardinality constraint.
+  ConstraintViolation,
 }
 
 impl Display for ErrorCode {
@@ -406,9 +514,39 @@ impl Display for ErrorCode {
       ErrorCode::RecursionLimitExceeded => {
         f.write_str("recursion limit exceeded")
       }
+      ErrorCode::InputTooLarge => f.write_str("input exceeds the size limit"),
+      ErrorCode::ArrayTooLong => f.write_str("array exceeds the length limit"),
+      ErrorCode::ObjectTooLarge => {
+        f.write_str("object exceeds the entry limit")
+      }
+      ErrorCode::StringTooLong => {
+        f.write_str("string exceeds the length limit")
+      }
       ErrorCode::RegexParser => {
         f.write_str("regular expression wasn't a match or malformed.")
       }
+      ErrorCode::InvalidIri => f.write_str("invalid IRI"),
+      ErrorCode::InvalidUrn => f.write_str("invalid URN"),
+      ErrorCode::InvalidDid => f.write_str("invalid DID"),
+      ErrorCode::InvalidBlobRef => f.write_str("invalid blob reference"),
+      ErrorCode::InvalidStatement => f.write_str("invalid N-Triples statement"),
+      ErrorCode::InvalidPath => f.write_str("invalid property path"),
+      ErrorCode::InvalidGeo => f.write_str("invalid geometry"),
+      ErrorCode::InvalidLanguageTag => f.write_str("invalid language tag"),
+      ErrorCode::InvalidFilter => f.write_str("invalid filter"),
+      ErrorCode::InvalidSelector => f.write_str("invalid JSONPath selector"),
+      ErrorCode::InvalidTransform => f.write_str("invalid transformation"),
+      ErrorCode::InvalidQuery => f.write_str("invalid SPARQL query"),
+      ErrorCode::InvalidUpdate => f.write_str("invalid SPARQL update"),
+      ErrorCode::InvalidTurtle => f.write_str("invalid Turtle document"),
+      ErrorCode::InvalidRules => f.write_str("invalid Datalog rules"),
+      ErrorCode::InvalidMapping => f.write_str("invalid mapping"),
+      ErrorCode::InvalidCsv => f.write_str("invalid CSV document"),
+      ErrorCode::InvalidRdfXml => f.write_str("invalid RDF/XML document"),
+      ErrorCode::InvalidLiteral => f.write_str("invalid typed literal"),
+      ErrorCode::VersionMismatch => f.write_str("version mismatch"),
+      ErrorCode::UnknownPredicate => f.write_str("unknown predicate"),
+      ErrorCode::ConstraintViolation => f.write_str("constraint violation"),
     }
   }
 }
@@ -0,0 +1,48 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::formats` contains readers & writers for exchanging a
+//! `KnowledgeGraph` with other tools.
+//!
+
+mod estimate;
+mod hdt;
+mod jsonld;
+mod ndjson;
+mod nquads;
+mod ntriples;
+pub mod rdfxml;
+mod shard;
+mod trig;
+mod turtle;
+pub mod viz;
+mod writer;
+
+pub use estimate::{ExportEstimate, GraphEstimate};
+pub use hdt::{Hdt, Matches};
+pub use jsonld::JsonLd;
+#[cfg(feature = "elastic")]
+pub(crate) use ndjson::entities;
+pub(crate) use ndjson::{node_object, to_writer as write_ndjson};
+pub use nquads::{NQuads, NQuadsWriter};
+pub use ntriples::NTriples;
+pub(crate) use ntriples::{
+  canonical_statements, canonical_statements_of, escape_iri, escape_literal,
+  parse_line, statements, statements_with, typed_value,
+};
+pub use rdfxml::RdfXml;
+pub use shard::{Manifest, ShardFile, ShardedExport, MANIFEST_FILE};
+pub use trig::{TriG, TriGWriter};
+pub use turtle::Turtle;
+pub use writer::{GraphFormat, GraphWriter};
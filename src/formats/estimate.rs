@@ -0,0 +1,122 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, io};
+
+use crate::graph::KnowledgeGraph;
+
+/// `ExportEstimate` is the result of an exporter dry-run: what an export
+/// would contain & how large it would be, without writing anything.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::viz::GraphMl;
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   Node::Http("https://example.com/John".to_string()),
+///   Predicate::Literal("knows".to_string()),
+///   Node::Http("https://example.com/Jane".to_string()),
+/// );
+///
+/// let exporter = GraphMl::new(&graph);
+/// let estimate = exporter.estimate().unwrap();
+///
+/// assert_eq!(estimate.triples, 1);
+/// assert_eq!(estimate.bytes, exporter.to_string().len() as u64);
+/// assert_eq!(estimate.graphs[0].name, None);
+/// assert!(estimate.fits(1024 * 1024));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExportEstimate {
+  /// Number of triples that would be exported.
+  pub triples: usize,
+  /// Number of distinct nodes in the output.
+  pub nodes: usize,
+  /// Number of edges in the output.
+  pub edges: usize,
+  /// Size of the output in bytes.
+  pub bytes: u64,
+  /// Breakdown per named graph. The default graph has no name.
+  pub graphs: Vec<GraphEstimate>,
+}
+
+/// `GraphEstimate` is the share of a single (named) graph in an
+/// `ExportEstimate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GraphEstimate {
+  /// Graph name or `None` for the default graph.
+  pub name: Option<String>,
+  /// Number of triples exported from this graph.
+  pub triples: usize,
+}
+
+impl ExportEstimate {
+  pub(crate) fn new(
+    graph: &KnowledgeGraph,
+    nodes: usize,
+    edges: usize,
+    bytes: u64,
+  ) -> ExportEstimate {
+    ExportEstimate {
+      triples: graph.len(),
+      nodes,
+      edges,
+      bytes,
+      graphs: vec![GraphEstimate {
+        name: None,
+        triples: graph.len(),
+      }],
+    }
+  }
+
+  /// Returns `true` if the export fits into `available` bytes.
+  pub fn fits(&self, available: u64) -> bool {
+    self.bytes <= available
+  }
+}
+
+impl fmt::Display for ExportEstimate {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(
+      f,
+      "{} triples, {} nodes, {} edges, {} bytes",
+      self.triples, self.nodes, self.edges, self.bytes
+    )?;
+    for graph in &self.graphs {
+      let name = graph.name.as_deref().unwrap_or("(default graph)");
+      writeln!(f, "  {}: {} triples", name, graph.triples)?;
+    }
+    Ok(())
+  }
+}
+
+/// `ByteCounter` is an `io::Write` sink which only counts what's written.
+#[derive(Default)]
+pub(crate) struct ByteCounter {
+  pub(crate) bytes: u64,
+}
+
+impl io::Write for ByteCounter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.bytes += buf.len() as u64;
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
@@ -0,0 +1,676 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+  collections::HashMap,
+  fs::File,
+  io::{self, BufReader, Read},
+  path::Path,
+};
+
+use serde::de::Error as _;
+
+use crate::{
+  dtype::{DType, Map},
+  error::Error,
+  formats::typed_value,
+  graph::{KnowledgeGraph, Node, Predicate},
+  Result,
+};
+
+const GLOBAL: u8 = 1;
+const HEADER: u8 = 2;
+const DICTIONARY: u8 = 3;
+const TRIPLES: u8 = 4;
+
+const HDT_V1: &str = "<http://purl.org/HDT/hdt#HDTv1>";
+const DICTIONARY_FOUR: &str = "<http://purl.org/HDT/hdt#dictionaryFour>";
+const TRIPLES_BITMAP: &str = "<http://purl.org/HDT/hdt#triplesBitmap>";
+
+/// `Hdt` reads [HDT] (Header-Dictionary-Triples) files, the compressed
+/// binary format large datasets like DBpedia & Wikidata subsets are
+/// distributed in.
+///
+/// The file is kept in its compressed form: every term is stored once in a
+/// front-coded dictionary and the statements as bitmap indexed ids, so a
+/// file takes about as much memory as it takes on disk. `triples` answers
+/// triple patterns straight from these structures, fast when the subject
+/// is given, and `to_graph` loads everything into a `KnowledgeGraph`.
+///
+/// Terms are written like in the dictionary: IRIs bare, literals quoted
+/// with their `@language` or `^^<datatype>` suffix and blank nodes as
+/// `_:label`. Files with the "four section" dictionary & bitmap triples in
+/// SPO order (what `rdf2hdt` & the HDT libraries write) are supported; the
+/// companion `.hdt.index` file isn't needed. Checksums aren't verified.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use sage::{formats::Hdt, graph::Node};
+///
+/// let hdt = Hdt::open("dbpedia.hdt").unwrap();
+/// println!("{} statements", hdt.len());
+///
+/// for (_, predicate, object) in
+///   hdt.triples(Some("http://dbpedia.org/resource/Ada_Lovelace"), None, None)
+/// {
+///   println!("{} {}", predicate, object);
+/// }
+/// let mut names =
+///   hdt.triples(None, Some("http://xmlns.com/foaf/0.1/name"), None);
+/// assert!(names.all(|(_, _, name)| matches!(name, Node::Literal(_))));
+/// ```
+///
+/// [HDT]: https://www.rdfhdt.org/hdt-binary-format/
+pub struct Hdt {
+  header: String,
+  shared: Section,
+  subjects: Section,
+  predicates: Section,
+  objects: Section,
+  triples: Triples,
+}
+
+impl Hdt {
+  /// Reads the HDT file at `path`.
+  pub fn open<P: AsRef<Path>>(path: P) -> Result<Hdt> {
+    Hdt::from_reader(File::open(path).map_err(Error::io)?)
+  }
+
+  /// Reads an HDT file from `reader`.
+  ///
+  /// With the `compression` feature, gzip & Zstandard compressed files are
+  /// decompressed on the fly.
+  pub fn from_reader<R: Read>(reader: R) -> Result<Hdt> {
+    let reader = BufReader::new(reader);
+    #[cfg(feature = "compression")]
+    let reader = crate::compression::Decoder::new(reader).map_err(Error::io)?;
+    let mut input = Input { inner: reader };
+
+    let global = input.control(GLOBAL)?;
+    if global.format != HDT_V1 {
+      return Err(unsupported("format", &global.format));
+    }
+
+    let header = input.control(HEADER)?;
+    let len = header.number("length")?;
+    let header = String::from_utf8(input.bytes(len)?)
+      .map_err(|_| Error::custom("HDT header isn't valid UTF-8"))?;
+
+    let dictionary = input.control(DICTIONARY)?;
+    if dictionary.format != DICTIONARY_FOUR {
+      return Err(unsupported("dictionary", &dictionary.format));
+    }
+    let shared = Section::read(&mut input)?;
+    let subjects = Section::read(&mut input)?;
+    let predicates = Section::read(&mut input)?;
+    let objects = Section::read(&mut input)?;
+
+    let triples = input.control(TRIPLES)?;
+    if triples.format != TRIPLES_BITMAP {
+      return Err(unsupported("triples", &triples.format));
+    }
+    if triples.properties.get("order").map(String::as_str) != Some("1") {
+      return Err(Error::custom("only HDT triples in SPO order are supported"));
+    }
+    let triples = Triples {
+      y_ends: Bitmap::read(&mut input)?,
+      z_ends: Bitmap::read(&mut input)?,
+      y: LogArray::read(&mut input)?,
+      z: LogArray::read(&mut input)?,
+    };
+    if triples.y.len != triples.y_ends.len
+      || triples.z.len != triples.z_ends.len
+      || triples.y_ends.ones() > subjects.len + shared.len
+      || triples.z_ends.ones() != triples.y.len
+    {
+      return Err(Error::custom("inconsistent HDT triples"));
+    }
+
+    Ok(Hdt {
+      header,
+      shared,
+      subjects,
+      predicates,
+      objects,
+      triples,
+    })
+  }
+
+  /// Returns the header: metadata about the dataset, as N-Triples.
+  pub fn header(&self) -> &str {
+    &self.header
+  }
+
+  /// Returns the number of statements.
+  pub fn len(&self) -> usize {
+    self.triples.z.len
+  }
+
+  /// Returns `true` if the file holds no statement.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Returns the statements matching a triple pattern, where `None`
+  /// matches any term, in SPO order.
+  ///
+  /// Statements about a given subject are found directly, other patterns
+  /// scan every statement.
+  pub fn triples(
+    &self,
+    subject: Option<&str>,
+    predicate: Option<&str>,
+    object: Option<&str>,
+  ) -> Matches<'_> {
+    let none = Matches {
+      hdt: self,
+      predicate: None,
+      object: None,
+      subject: 0,
+      y: 0,
+      z: 0,
+      end: 0,
+    };
+    let predicate = match predicate.map(|p| self.predicates.locate(p)) {
+      Some(None) => return none,
+      predicate => predicate.flatten(),
+    };
+    let object = match object.map(|o| self.object_id(o)) {
+      Some(None) => return none,
+      object => object.flatten(),
+    };
+    let triples = &self.triples;
+    let mut matches = Matches {
+      predicate,
+      object,
+      subject: 1,
+      end: triples.z.len,
+      ..none
+    };
+    if let Some(subject) = subject {
+      let subject = match self.subject_id(subject) {
+        Some(subject) if subject <= triples.y_ends.ones() => subject,
+        _ => return none,
+      };
+      let y = match subject {
+        1 => 0,
+        s => triples.y_ends.select(s - 1).map_or(0, |y| y + 1),
+      };
+      let y_last = triples.y_ends.select(subject).unwrap_or(0);
+      matches.subject = subject;
+      matches.y = y;
+      matches.z = match y {
+        0 => 0,
+        y => triples.z_ends.select(y).map_or(0, |z| z + 1),
+      };
+      matches.end = triples.z_ends.select(y_last + 1).map_or(0, |z| z + 1);
+    }
+    matches
+  }
+
+  /// Loads every statement into a new `KnowledgeGraph`.
+  pub fn to_graph(&self) -> Result<KnowledgeGraph> {
+    let mut graph = KnowledgeGraph::new();
+    graph.batch(|graph| {
+      for (subject, predicate, object) in self.triples(None, None, None) {
+        graph.insert(subject, Predicate::Literal(predicate), object);
+      }
+    });
+    Ok(graph)
+  }
+
+  /// Returns the id of `term` as a subject.
+  fn subject_id(&self, term: &str) -> Option<usize> {
+    self
+      .shared
+      .locate(term)
+      .or_else(|| Some(self.shared.len + self.subjects.locate(term)?))
+  }
+
+  /// Returns the id of `term` as an object.
+  fn object_id(&self, term: &str) -> Option<usize> {
+    self
+      .shared
+      .locate(term)
+      .or_else(|| Some(self.shared.len + self.objects.locate(term)?))
+  }
+
+  fn subject(&self, id: usize) -> String {
+    match id.checked_sub(self.shared.len + 1) {
+      None => self.shared.extract(id),
+      Some(i) => self.subjects.extract(i + 1),
+    }
+  }
+
+  fn object(&self, id: usize) -> String {
+    match id.checked_sub(self.shared.len + 1) {
+      None => self.shared.extract(id),
+      Some(i) => self.objects.extract(i + 1),
+    }
+  }
+}
+
+fn unsupported(what: &str, format: &str) -> Error {
+  Error::custom(format_args!("unsupported HDT {}: {}", what, format))
+}
+
+/// Converts a dictionary term into a `Node`.
+fn node(term: String) -> Node {
+  if let Some(label) = term.strip_prefix("_:") {
+    return Node::BlankId(label.to_string());
+  }
+  let end = match term.rfind('"') {
+    Some(end) if end > 0 && term.starts_with('"') => end,
+    _ => return Node::Http(term),
+  };
+  let lexical = term[1..end].to_string();
+  let suffix = &term[end + 1..];
+  let value = if let Some(language) = suffix.strip_prefix('@') {
+    let mut value = Map::new();
+    value.insert("@value".to_string(), lexical.into());
+    value.insert("@language".to_string(), language.into());
+    DType::Object(value)
+  } else if let Some(datatype) = suffix.strip_prefix("^^") {
+    let datatype = datatype.trim_start_matches('<').trim_end_matches('>');
+    typed_value(lexical, datatype)
+  } else {
+    lexical.into()
+  };
+  Node::Literal(value)
+}
+
+/// `Matches` iterates over the statements of an `Hdt` matching a triple
+/// pattern (see `Hdt::triples`).
+pub struct Matches<'a> {
+  hdt: &'a Hdt,
+  predicate: Option<usize>,
+  object: Option<usize>,
+  /// Subject, predicate position & object position of the next statement.
+  subject: usize,
+  y: usize,
+  z: usize,
+  /// Object position past the last statement.
+  end: usize,
+}
+
+impl<'a> Iterator for Matches<'a> {
+  type Item = (Node, String, Node);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let triples = &self.hdt.triples;
+    while self.z < self.end {
+      let (subject, y, z) = (self.subject, self.y, self.z);
+      self.z += 1;
+      if triples.z_ends.get(z) {
+        self.y += 1;
+        if triples.y_ends.get(y) {
+          self.subject += 1;
+        }
+      }
+
+      let predicate = triples.y.get(y) as usize;
+      let object = triples.z.get(z) as usize;
+      if self.predicate.is_some_and(|p| p != predicate)
+        || self.object.is_some_and(|o| o != object)
+      {
+        continue;
+      }
+      return Some((
+        node(self.hdt.subject(subject)),
+        self.hdt.predicates.extract(predicate),
+        node(self.hdt.object(object)),
+      ));
+    }
+    None
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Binary structures.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+struct Input<R> {
+  inner: R,
+}
+
+impl<R: Read> Input<R> {
+  fn byte(&mut self) -> Result<u8> {
+    let mut byte = [0];
+    self.inner.read_exact(&mut byte).map_err(Error::io)?;
+    Ok(byte[0])
+  }
+
+  fn bytes(&mut self, len: usize) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    (&mut self.inner)
+      .take(len as u64)
+      .read_to_end(&mut bytes)
+      .map_err(Error::io)?;
+    if bytes.len() < len {
+      return Err(Error::io(io::ErrorKind::UnexpectedEof.into()));
+    }
+    Ok(bytes)
+  }
+
+  /// Reads a variable length number: 7 bits per byte, least significant
+  /// first, the last byte flagged by its high bit.
+  fn vbyte(&mut self) -> Result<usize> {
+    let mut value = 0usize;
+    for shift in (0..64).step_by(7) {
+      let byte = self.byte()?;
+      value |= ((byte & 0x7F) as usize) << shift;
+      if byte & 0x80 != 0 {
+        return Ok(value);
+      }
+    }
+    Err(Error::custom("invalid HDT number"))
+  }
+
+  /// Reads a zero terminated string.
+  fn string(&mut self) -> Result<String> {
+    let mut bytes = Vec::new();
+    loop {
+      match self.byte()? {
+        0 => break,
+        byte => bytes.push(byte),
+      }
+    }
+    String::from_utf8(bytes).map_err(|_| Error::custom("invalid HDT string"))
+  }
+
+  fn expect(&mut self, kind: u8, what: &str) -> Result<()> {
+    match self.byte()? {
+      byte if byte == kind => Ok(()),
+      byte => Err(Error::custom(format_args!(
+        "unsupported HDT {} type {}",
+        what, byte
+      ))),
+    }
+  }
+
+  /// Reads a control information block of type `kind`.
+  fn control(&mut self, kind: u8) -> Result<Control> {
+    if self.bytes(4)? != b"$HDT" {
+      return Err(Error::custom("not an HDT file"));
+    }
+    self.expect(kind, "section")?;
+    let format = self.string()?;
+    let properties = self
+      .string()?
+      .split(';')
+      .filter_map(|property| property.split_once('='))
+      .map(|(key, value)| (key.to_string(), value.to_string()))
+      .collect();
+    // CRC16.
+    self.bytes(2)?;
+    Ok(Control { format, properties })
+  }
+}
+
+/// Control information, introducing every part of a file.
+struct Control {
+  format: String,
+  properties: HashMap<String, String>,
+}
+
+impl Control {
+  fn number(&self, key: &str) -> Result<usize> {
+    self
+      .properties
+      .get(key)
+      .and_then(|value| value.parse().ok())
+      .ok_or_else(|| Error::custom(format_args!("missing HDT `{}`", key)))
+  }
+}
+
+/// Reads little endian 64 bit words, zero padded.
+fn words(bytes: &[u8]) -> Vec<u64> {
+  bytes
+    .chunks(8)
+    .map(|chunk| {
+      let mut word = [0; 8];
+      word[..chunk.len()].copy_from_slice(chunk);
+      u64::from_le_bytes(word)
+    })
+    .collect()
+}
+
+/// A sequence of numbers of `bits` bits each.
+struct LogArray {
+  bits: usize,
+  len: usize,
+  words: Vec<u64>,
+}
+
+impl LogArray {
+  fn read<R: Read>(input: &mut Input<R>) -> Result<LogArray> {
+    input.expect(1, "sequence")?;
+    let bits = input.byte()? as usize;
+    let len = input.vbyte()?;
+    if bits > 64 {
+      return Err(Error::custom("invalid HDT sequence"));
+    }
+    // CRC8.
+    input.byte()?;
+    let size = bits
+      .checked_mul(len)
+      .ok_or_else(|| Error::custom("invalid HDT sequence"))?;
+    let words = words(&input.bytes(size.div_ceil(8))?);
+    // CRC32.
+    input.bytes(4)?;
+    Ok(LogArray { bits, len, words })
+  }
+
+  fn get(&self, i: usize) -> u64 {
+    if self.bits == 0 {
+      return 0;
+    }
+    let bit = i * self.bits;
+    let (word, offset) = (bit / 64, bit % 64);
+    let mut value = self.words[word] >> offset;
+    if offset + self.bits > 64 {
+      value |= self.words[word + 1] << (64 - offset);
+    }
+    match self.bits {
+      64 => value,
+      bits => value & ((1 << bits) - 1),
+    }
+  }
+}
+
+/// Words per rank sample of a `Bitmap`.
+const SAMPLE: usize = 8;
+
+/// A sequence of bits, with the rank samples finding the n-th set bit.
+struct Bitmap {
+  len: usize,
+  words: Vec<u64>,
+  /// Set bits before every `SAMPLE` words.
+  ranks: Vec<usize>,
+}
+
+impl Bitmap {
+  fn read<R: Read>(input: &mut Input<R>) -> Result<Bitmap> {
+    input.expect(1, "bitmap")?;
+    let len = input.vbyte()?;
+    // CRC8.
+    input.byte()?;
+    let words = words(&input.bytes(len.div_ceil(8))?);
+    // CRC32.
+    input.bytes(4)?;
+    let mut ranks = vec![0];
+    for sample in words.chunks(SAMPLE) {
+      let ones = sample
+        .iter()
+        .map(|w| w.count_ones() as usize)
+        .sum::<usize>();
+      ranks.push(ranks[ranks.len() - 1] + ones);
+    }
+    Ok(Bitmap { len, words, ranks })
+  }
+
+  fn get(&self, i: usize) -> bool {
+    self.words[i / 64] >> (i % 64) & 1 == 1
+  }
+
+  fn ones(&self) -> usize {
+    self.ranks[self.ranks.len() - 1]
+  }
+
+  /// Returns the position of the `n`th set bit, from 1.
+  fn select(&self, n: usize) -> Option<usize> {
+    if n == 0 || n > self.ones() {
+      return None;
+    }
+    let sample = self.ranks.partition_point(|&rank| rank < n) - 1;
+    let mut ones = self.ranks[sample];
+    for (i, &word) in self.words.iter().enumerate().skip(sample * SAMPLE) {
+      let count = word.count_ones() as usize;
+      if ones + count >= n {
+        let mut word = word;
+        for _ in 0..n - ones - 1 {
+          word &= word - 1;
+        }
+        return Some(i * 64 + word.trailing_zeros() as usize);
+      }
+      ones += count;
+    }
+    None
+  }
+}
+
+/// Statements as ids: the predicates of every subject in `y`, the objects
+/// of every subject & predicate in `z`, the last of each flagged in
+/// `y_ends` & `z_ends`.
+struct Triples {
+  y_ends: Bitmap,
+  z_ends: Bitmap,
+  y: LogArray,
+  z: LogArray,
+}
+
+/// A dictionary section of sorted terms, in blocks of front coded strings:
+/// the first of a block in full, the others as the length of the prefix
+/// they share with the previous one & the rest.
+struct Section {
+  len: usize,
+  block_size: usize,
+  /// Offset of every block in `text`.
+  blocks: LogArray,
+  text: Vec<u8>,
+}
+
+impl Section {
+  fn read<R: Read>(input: &mut Input<R>) -> Result<Section> {
+    input.expect(2, "dictionary section")?;
+    let len = input.vbyte()?;
+    let size = input.vbyte()?;
+    let block_size = input.vbyte()?;
+    // CRC8.
+    input.byte()?;
+    let blocks = LogArray::read(input)?;
+    let text = input.bytes(size)?;
+    // CRC32.
+    input.bytes(4)?;
+    let valid = block_size > 0
+      && blocks.len >= len.div_ceil(block_size)
+      && (0..blocks.len).all(|b| (blocks.get(b) as usize) <= text.len());
+    if !valid {
+      return Err(Error::custom("invalid HDT dictionary section"));
+    }
+    Ok(Section {
+      len,
+      block_size,
+      blocks,
+      text,
+    })
+  }
+
+  /// Returns the zero terminated string at `pos`, and the position past it.
+  fn string(&self, pos: usize) -> (&[u8], usize) {
+    let rest = self.text.get(pos..).unwrap_or_default();
+    let len = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+    (&rest[..len], pos + len + 1)
+  }
+
+  /// Decodes the strings of `block` while `f` returns `true`, returning the
+  /// last one.
+  fn scan<F>(&self, block: usize, mut f: F) -> Vec<u8>
+  where
+    F: FnMut(usize, &[u8]) -> bool,
+  {
+    let (first, mut pos) = self.string(self.blocks.get(block) as usize);
+    let mut term = first.to_vec();
+    let count = self.block_size.min(self.len - block * self.block_size);
+    for i in 0..count {
+      if i > 0 {
+        let mut prefix = 0;
+        for shift in (0..64).step_by(7) {
+          let byte = self.text.get(pos).copied().unwrap_or(0x80);
+          pos += 1;
+          prefix |= ((byte & 0x7F) as usize) << shift;
+          if byte & 0x80 != 0 {
+            break;
+          }
+        }
+        let (suffix, next) = self.string(pos);
+        pos = next;
+        term.truncate(prefix);
+        term.extend_from_slice(suffix);
+      }
+      if !f(i, &term) {
+        break;
+      }
+    }
+    term
+  }
+
+  /// Returns the term of `id`, from 1.
+  fn extract(&self, id: usize) -> String {
+    let (block, index) =
+      ((id - 1) / self.block_size, (id - 1) % self.block_size);
+    let term = self.scan(block, |i, _| i < index);
+    String::from_utf8_lossy(&term).into_owned()
+  }
+
+  /// Returns the id of `term`, from 1.
+  fn locate(&self, term: &str) -> Option<usize> {
+    let term = term.as_bytes();
+    let blocks = self.len.div_ceil(self.block_size);
+    // The last block starting at or before `term`.
+    let (mut low, mut high) = (0, blocks);
+    while low < high {
+      let mid = (low + high) / 2;
+      if self.string(self.blocks.get(mid) as usize).0 <= term {
+        low = mid + 1;
+      } else {
+        high = mid;
+      }
+    }
+    let block = low.checked_sub(1)?;
+    let mut found = None;
+    self.scan(block, |i, candidate| {
+      if candidate == term {
+        found = Some(block * self.block_size + i + 1);
+      }
+      candidate < term
+    });
+    found
+  }
+}
@@ -0,0 +1,954 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+  collections::{BTreeMap, HashSet},
+  fmt, io, mem,
+};
+
+use serde::de::Error as _;
+
+use crate::{
+  datastore::json,
+  dtype::{literal::base64_encode, DType, Map},
+  error::Error,
+  formats::typed_value,
+  graph::{KnowledgeGraph, Node, Predicate},
+  Result,
+};
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Full IRI of `rdf:type`.
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/// `JsonLd` reads & writes a `KnowledgeGraph` as [JSON-LD].
+///
+/// [JSON-LD]: https://www.w3.org/TR/json-ld11/
+pub struct JsonLd<'a> {
+  graph: &'a KnowledgeGraph,
+}
+
+impl<'a> JsonLd<'a> {
+  /// Creates a JSON-LD serializer for `graph`.
+  pub fn new(graph: &'a KnowledgeGraph) -> JsonLd<'a> {
+    JsonLd { graph }
+  }
+
+  /// Reads a JSON-LD document into a new `KnowledgeGraph`.
+  ///
+  /// Node objects may be nested, listed in an array or under `@graph`.
+  /// Terms, prefixes & `@vocab` of (nested) `@context`s are expanded, along
+  /// with term definitions typed `@id` or with a datatype. Value objects
+  /// become literals like in `NTriples::parse`, `@list`s & `@set`s plain
+  /// values. Nodes without `@id` get a fresh blank node. Remote contexts
+  /// aren't fetched, so their terms are kept as written.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::formats::JsonLd;
+  /// use sage::graph::Node;
+  ///
+  /// let graph = JsonLd::parse(
+  ///   r#"{
+  ///     "@context": {
+  ///       "@vocab": "https://schema.org/",
+  ///       "ex": "https://example.com/",
+  ///       "knows": { "@type": "@id" }
+  ///     },
+  ///     "@id": "ex:Ada",
+  ///     "@type": "Person",
+  ///     "name": { "@value": "Ada", "@language": "en" },
+  ///     "knows": "ex:Charles",
+  ///     "address": { "addressLocality": "London" }
+  ///   }"#,
+  /// )
+  /// .unwrap();
+  ///
+  /// assert_eq!(graph.len(), 5);
+  /// let ada = graph.entity("https://example.com/Ada");
+  /// assert_eq!(
+  ///   ada.get("https://schema.org/knows"),
+  ///   [&Node::Http("https://example.com/Charles".to_string())]
+  /// );
+  /// ```
+  pub fn parse(input: &str) -> Result<KnowledgeGraph> {
+    JsonLd::from_dtype(&json::from_str(input)?)
+  }
+
+  /// Reads a JSON-LD document from `reader` into a new `KnowledgeGraph`.
+  ///
+  /// With the `compression` feature, gzip & Zstandard compressed documents
+  /// are decompressed on the fly.
+  pub fn from_reader<R: io::Read>(reader: R) -> Result<KnowledgeGraph> {
+    JsonLd::from_dtype(&json::from_reader(reader)?)
+  }
+
+  /// Reads a parsed JSON-LD document into a new `KnowledgeGraph`, see
+  /// `JsonLd::parse`.
+  pub fn from_dtype(document: &DType) -> Result<KnowledgeGraph> {
+    let mut labels = HashSet::new();
+    blank_labels(document, &mut labels);
+    let mut reader = Reader {
+      graph: KnowledgeGraph::new(),
+      labels,
+      blanks: 0,
+    };
+    reader.nodes(document, &Context::default())?;
+    Ok(reader.graph)
+  }
+
+  /// Returns the graph as a flattened JSON-LD document: one node object
+  /// per subject under `@graph`, with full IRIs & blank node labels.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::formats::JsonLd;
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  /// use sage::json;
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(
+  ///   Node::Http("https://example.com/Ada".to_string()),
+  ///   Predicate::Literal("https://schema.org/knows".to_string()),
+  ///   Node::BlankId("b0".to_string()),
+  /// );
+  /// graph.insert(
+  ///   Node::BlankId("b0".to_string()),
+  ///   Predicate::Literal("https://schema.org/name".to_string()),
+  ///   Node::Literal("Charles".into()),
+  /// );
+  ///
+  /// let document = JsonLd::new(&graph).to_dtype();
+  /// assert_eq!(
+  ///   document,
+  ///   json!({ "@graph": [
+  ///     { "@id": "_:b0", "https://schema.org/name": "Charles" },
+  ///     {
+  ///       "@id": "https://example.com/Ada",
+  ///       "https://schema.org/knows": { "@id": "_:b0" }
+  ///     }
+  ///   ]})
+  /// );
+  /// assert_eq!(JsonLd::from_dtype(&document).unwrap().len(), 2);
+  /// ```
+  pub fn to_dtype(&self) -> DType {
+    let nodes = NodeIndex::new(self.graph);
+    let graph: Vec<DType> = nodes
+      .subjects
+      .iter()
+      .map(|(id, (types, properties))| {
+        let mut node = Map::new();
+        node.insert("@id".to_string(), id.as_str().into());
+        if !types.is_empty() {
+          node.insert(
+            "@type".to_string(),
+            single_or_array(
+              types.iter().map(|t| DType::from(t.as_str())).collect(),
+            ),
+          );
+        }
+        for (predicate, values) in properties {
+          let values = values.iter().map(expanded_value).collect();
+          node.insert(predicate.clone(), single_or_array(values));
+        }
+        DType::Object(node)
+      })
+      .collect();
+    let mut document = Map::new();
+    document.insert("@graph".to_string(), DType::from(graph));
+    DType::Object(document)
+  }
+
+  /// Writes the flattened JSON-LD document (see `JsonLd::to_dtype`) into
+  /// `writer`.
+  pub fn to_writer<W: io::Write>(&self, writer: W) -> Result<()> {
+    json::to_writer_pretty(writer, &self.to_dtype())
+  }
+
+  /// Shapes the graph into the nested JSON structure described by a
+  /// [JSON-LD frame].
+  ///
+  /// Supported frame features:
+  ///
+  /// - Matching on `@id`, `@type` (`{}` matches any type) and, for frames
+  ///   without either, on the presence of every listed property.
+  /// - Nested frames per property, embedding linked nodes.
+  /// - `@embed` (`@once`, `@always`, `@never`), `@explicit`, `@default`
+  ///   and `@omitDefault`.
+  /// - `@context` terms, prefixes & `@vocab` used to expand the frame and
+  ///   compact the output.
+  ///
+  /// A single match is returned as a node object, several matches under
+  /// `@graph`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::formats::JsonLd;
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  /// use sage::json;
+  ///
+  /// let library = Node::Http("https://example.com/library".to_string());
+  /// let book = Node::Http("https://example.com/book".to_string());
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// let mut add = |s: &Node, p: &str, o: Node| {
+  ///   graph.insert(s.clone(), Predicate::Literal(p.to_string()), o)
+  /// };
+  /// add(&library, "@type", Node::Http("https://schema.org/Library".into()));
+  /// add(&library, "https://schema.org/name", Node::Literal("City".into()));
+  /// add(&library, "https://schema.org/contains", book.clone());
+  /// add(&book, "@type", Node::Http("https://schema.org/Book".into()));
+  /// add(&book, "https://schema.org/name", Node::Literal("Dune".into()));
+  ///
+  /// let framed = JsonLd::new(&graph)
+  ///   .frame(&json!({
+  ///     "@context": { "@vocab": "https://schema.org/" },
+  ///     "@type": "Library",
+  ///     "contains": { "@type": "Book", "@explicit": true, "name": {} }
+  ///   }))
+  ///   .unwrap();
+  ///
+  /// assert_eq!(
+  ///   framed,
+  ///   json!({
+  ///     "@context": { "@vocab": "https://schema.org/" },
+  ///     "@id": "https://example.com/library",
+  ///     "@type": "Library",
+  ///     "name": "City",
+  ///     "contains": {
+  ///       "@id": "https://example.com/book",
+  ///       "@type": "Book",
+  ///       "name": "Dune"
+  ///     }
+  ///   })
+  /// );
+  /// ```
+  ///
+  /// [JSON-LD frame]: https://www.w3.org/TR/json-ld11-framing/
+  pub fn frame(&self, frame: &DType) -> Result<DType> {
+    let frame = frame
+      .as_object()
+      .ok_or_else(|| Error::custom("JSON-LD frame must be an object"))?;
+    let context = Context::new(frame.get("@context"))?;
+    let nodes = NodeIndex::new(self.graph);
+
+    let mut framer = Framer {
+      context: &context,
+      nodes: &nodes,
+      embedded: HashSet::new(),
+      path: Vec::new(),
+    };
+    let mut matches: Vec<DType> = nodes
+      .subjects
+      .keys()
+      .filter(|id| framer.matches(id, frame))
+      .cloned()
+      .collect::<Vec<_>>()
+      .iter()
+      .map(|id| framer.frame_node(id, frame))
+      .collect();
+
+    let mut out = Map::new();
+    if let Some(ctx) = frame.get("@context") {
+      out.insert("@context".to_string(), ctx.clone());
+    }
+    if matches.len() == 1 && !frame.contains_key("@graph") {
+      if let DType::Object(ref mut node) = matches[0] {
+        out.extend(mem::take(node));
+      }
+    } else {
+      out.insert("@graph".to_string(), DType::from(matches));
+    }
+    Ok(DType::Object(out))
+  }
+}
+
+impl<'a> fmt::Display for JsonLd<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let document =
+      json::to_string_pretty(&self.to_dtype()).map_err(|_| fmt::Error)?;
+    f.write_str(&document)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Node index.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Property values of a node, keyed by the full predicate IRI.
+type Properties = BTreeMap<String, Vec<Value>>;
+
+#[derive(Clone)]
+enum Value {
+  Literal(DType),
+  Reference(String),
+}
+
+/// Every subject of a graph along with its types & properties.
+struct NodeIndex {
+  subjects: BTreeMap<String, (Vec<String>, Properties)>,
+}
+
+impl NodeIndex {
+  fn new(graph: &KnowledgeGraph) -> NodeIndex {
+    let mut subjects: BTreeMap<String, (Vec<String>, Properties)> =
+      BTreeMap::new();
+    let mut blanks = 0;
+
+    for triple in graph.triples() {
+      let predicate = triple.predicate().to_string();
+      for source in flatten(triple.source()) {
+        let id = node_id(source, &mut blanks);
+        let (types, properties) = subjects.entry(id).or_default();
+        for destination in flatten(triple.destination()) {
+          let value = match destination {
+            Node::Literal(value) => Value::Literal(value.clone()),
+            node => Value::Reference(node_id(node, &mut blanks)),
+          };
+          if predicate == "@type" || predicate == RDF_TYPE {
+            match value {
+              Value::Reference(t) => types.push(t),
+              Value::Literal(DType::String(ref t)) => types.push(t.clone()),
+              Value::Literal(_) => {}
+            }
+          } else {
+            properties.entry(predicate.clone()).or_default().push(value);
+          }
+        }
+      }
+    }
+
+    NodeIndex { subjects }
+  }
+}
+
+fn flatten(node: &Node) -> Vec<&Node> {
+  match node {
+    Node::Multiple(nodes) => nodes.iter().flat_map(flatten).collect(),
+    node => vec![node],
+  }
+}
+
+fn node_id(node: &Node, blanks: &mut usize) -> String {
+  match node {
+    Node::Blank => {
+      let id = format!("_:b{}", blanks);
+      *blanks += 1;
+      id
+    }
+    node => node.to_string(),
+  }
+}
+
+/// Returns a node value of the flattened document.
+fn expanded_value(value: &Value) -> DType {
+  let mut object = Map::new();
+  match value {
+    Value::Reference(id) => {
+      object.insert("@id".to_string(), id.as_str().into());
+    }
+    Value::Literal(DType::DateTime(d)) => {
+      let lexical = d
+        .as_chrono()
+        .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true);
+      object.insert("@value".to_string(), lexical.into());
+      object.insert("@type".to_string(), format!("{}dateTime", XSD).into());
+    }
+    Value::Literal(DType::Bytes(b)) => {
+      object.insert("@value".to_string(), base64_encode(b).into());
+      object.insert("@type".to_string(), format!("{}base64Binary", XSD).into());
+    }
+    Value::Literal(value @ (DType::Array(_) | DType::Object(_)))
+      if !is_language_string(value) =>
+    {
+      object.insert("@value".to_string(), value.clone());
+      object.insert("@type".to_string(), "@json".into());
+    }
+    Value::Literal(value) => return value.clone(),
+  }
+  DType::Object(object)
+}
+
+fn is_language_string(value: &DType) -> bool {
+  match value {
+    DType::Object(map) => {
+      map.len() == 2
+        && map.get("@value").is_some_and(DType::is_string)
+        && map.get("@language").is_some_and(DType::is_string)
+    }
+    _ => false,
+  }
+}
+
+fn single_or_array(mut values: Vec<DType>) -> DType {
+  if values.len() == 1 {
+    values.remove(0)
+  } else {
+    DType::from(values)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Reader.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Inserts the statements of a JSON-LD document into `graph`.
+struct Reader {
+  graph: KnowledgeGraph,
+  /// Blank node labels used by the document.
+  labels: HashSet<String>,
+  blanks: usize,
+}
+
+impl Reader {
+  /// Reads the node objects of `value`: a node, an array of nodes or a
+  /// `@graph`.
+  fn nodes(&mut self, value: &DType, context: &Context) -> Result<()> {
+    match value {
+      DType::Array(values) => {
+        for value in values.iter() {
+          self.nodes(value, context)?;
+        }
+        Ok(())
+      }
+      DType::Object(object) => self.node(object, context).map(|_| ()),
+      _ => Err(Error::custom("expected a JSON-LD node object")),
+    }
+  }
+
+  /// Reads a node object, returning its node.
+  fn node(
+    &mut self,
+    object: &Map<String, DType>,
+    context: &Context,
+  ) -> Result<Node> {
+    let scoped;
+    let context = match object.get("@context") {
+      Some(local) => {
+        scoped = context.clone().extend(Some(local))?;
+        &scoped
+      }
+      None => context,
+    };
+
+    let subject = match object.get("@id") {
+      Some(DType::String(id)) => node_of(context.expand_prefix(id)),
+      Some(_) => return Err(Error::custom("JSON-LD `@id` must be a string")),
+      None => self.blank_node(),
+    };
+    if let Some(types) = object.get("@type") {
+      for t in values_of(types) {
+        self.graph.insert(
+          subject.clone(),
+          Predicate::Literal(RDF_TYPE.to_string()),
+          Node::Http(context.expand(t)),
+        );
+      }
+    }
+    if let Some(graph) = object.get("@graph") {
+      self.nodes(graph, context)?;
+    }
+
+    for (key, value) in object.iter().filter(|(k, _)| !k.starts_with('@')) {
+      let predicate = context.expand(key);
+      let mut objects = Vec::new();
+      self.values(key, value, context, &mut objects)?;
+      for object in objects {
+        self.graph.insert(
+          subject.clone(),
+          Predicate::Literal(predicate.clone()),
+          object,
+        );
+      }
+    }
+    Ok(subject)
+  }
+
+  /// Reads the values of the property `term` into `objects`.
+  fn values(
+    &mut self,
+    term: &str,
+    value: &DType,
+    context: &Context,
+    objects: &mut Vec<Node>,
+  ) -> Result<()> {
+    let node = match value {
+      DType::Null => return Ok(()),
+      DType::Array(values) => {
+        for value in values.iter() {
+          self.values(term, value, context, objects)?;
+        }
+        return Ok(());
+      }
+      DType::Object(object) => {
+        if let Some(list) = object.get("@list").or_else(|| object.get("@set")) {
+          return self.values(term, list, context, objects);
+        }
+        match object.get("@value") {
+          Some(value) => literal(object, value, context),
+          None if object.len() == 1 && object.contains_key("@id") => {
+            match &object["@id"] {
+              DType::String(id) => node_of(context.expand_prefix(id)),
+              _ => return Err(Error::custom("JSON-LD `@id` must be a string")),
+            }
+          }
+          None => self.node(object, context)?,
+        }
+      }
+      DType::String(s) if context.references.contains(term) => {
+        node_of(context.expand(s))
+      }
+      DType::String(s) => match context.datatypes.get(term) {
+        Some(datatype) => Node::Literal(typed_value(s.clone(), datatype)),
+        None => Node::Literal(value.clone()),
+      },
+      value => Node::Literal(value.clone()),
+    };
+    objects.push(node);
+    Ok(())
+  }
+
+  /// Returns a blank node whose label isn't used by the document.
+  fn blank_node(&mut self) -> Node {
+    loop {
+      let label = format!("b{}", self.blanks);
+      self.blanks += 1;
+      if !self.labels.contains(&label) {
+        return Node::BlankId(label);
+      }
+    }
+  }
+}
+
+/// Returns the literal of a value object.
+fn literal(
+  object: &Map<String, DType>,
+  value: &DType,
+  context: &Context,
+) -> Node {
+  let literal = match (value, object.get("@language"), object.get("@type")) {
+    (DType::String(s), Some(DType::String(language)), _) => {
+      let mut literal = Map::new();
+      literal.insert("@value".to_string(), s.as_str().into());
+      literal.insert("@language".to_string(), language.as_str().into());
+      DType::Object(literal)
+    }
+    (DType::String(s), _, Some(DType::String(datatype)))
+      if datatype != "@json" =>
+    {
+      typed_value(s.clone(), &context.expand(datatype))
+    }
+    (value, _, _) => value.clone(),
+  };
+  Node::Literal(literal)
+}
+
+/// Returns the node identified by an expanded `@id`.
+fn node_of(id: String) -> Node {
+  match id.strip_prefix("_:") {
+    Some(label) => Node::BlankId(label.to_string()),
+    None => Node::Http(id),
+  }
+}
+
+/// Collects the labels of the blank nodes identified in `value`.
+fn blank_labels(value: &DType, labels: &mut HashSet<String>) {
+  match value {
+    DType::String(s) => {
+      if let Some(label) = s.strip_prefix("_:") {
+        labels.insert(label.to_string());
+      }
+    }
+    DType::Array(values) => {
+      values.iter().for_each(|v| blank_labels(v, labels));
+    }
+    DType::Object(object) => {
+      object.values().for_each(|v| blank_labels(v, labels));
+    }
+    _ => {}
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Context.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// The subset of a JSON-LD `@context` used for expanding & compacting IRIs.
+#[derive(Clone, Default)]
+struct Context {
+  vocab: Option<String>,
+  /// Term -> IRI, including prefixes.
+  terms: BTreeMap<String, String>,
+  /// Terms declared with `"@container": "@set"`.
+  sets: HashSet<String>,
+  /// Terms whose string values are IRIs (`"@type": "@id"` or `"@vocab"`).
+  references: HashSet<String>,
+  /// Terms whose string values are typed literals, with their datatype.
+  datatypes: BTreeMap<String, String>,
+}
+
+impl Context {
+  fn new(context: Option<&DType>) -> Result<Context> {
+    Context::default().extend(context)
+  }
+
+  /// Adds the definitions of `context` to the ones in scope.
+  fn extend(self, context: Option<&DType>) -> Result<Context> {
+    let mut ctx = self;
+    let definitions = match context {
+      None => return Ok(ctx),
+      Some(DType::Object(definitions)) => vec![definitions],
+      Some(DType::Array(list)) => {
+        list.iter().filter_map(DType::as_object).collect()
+      }
+      // Remote contexts can't be resolved here.
+      Some(DType::String(_)) => return Ok(ctx),
+      Some(_) => return Err(Error::custom("invalid JSON-LD `@context`")),
+    };
+
+    for definitions in definitions {
+      for (term, definition) in definitions {
+        if term == "@vocab" {
+          ctx.vocab = definition.as_str().map(str::to_string);
+          continue;
+        }
+        let iri = match definition {
+          DType::String(iri) => iri,
+          DType::Object(def) => {
+            if def.get("@container").and_then(DType::as_str) == Some("@set") {
+              ctx.sets.insert(term.clone());
+            }
+            match def.get("@type").and_then(DType::as_str) {
+              Some("@id" | "@vocab") => {
+                ctx.references.insert(term.clone());
+              }
+              Some(datatype) => {
+                ctx.datatypes.insert(term.clone(), datatype.to_string());
+              }
+              None => {}
+            }
+            match def.get("@id").and_then(DType::as_str) {
+              Some(iri) => iri,
+              None => continue,
+            }
+          }
+          _ => continue,
+        };
+        ctx.terms.insert(term.clone(), iri.to_string());
+      }
+    }
+
+    // Resolve terms defined as compact IRIs, e.g. `"name": "schema:name"`.
+    let resolved: Vec<(String, String)> = ctx
+      .terms
+      .iter()
+      .map(|(term, iri)| (term.clone(), ctx.expand_prefix(iri)))
+      .collect();
+    ctx.terms.extend(resolved);
+    let datatypes: Vec<(String, String)> = ctx
+      .datatypes
+      .iter()
+      .map(|(term, datatype)| (term.clone(), ctx.expand(datatype)))
+      .collect();
+    ctx.datatypes.extend(datatypes);
+    Ok(ctx)
+  }
+
+  fn expand_prefix(&self, value: &str) -> String {
+    if let Some((prefix, suffix)) = value.split_once(':') {
+      if !suffix.starts_with("//") {
+        if let Some(iri) = self.terms.get(prefix) {
+          return format!("{}{}", iri, suffix);
+        }
+      }
+    }
+    value.to_string()
+  }
+
+  /// Expands a term, compact IRI or vocabulary relative IRI.
+  fn expand(&self, value: &str) -> String {
+    if value.starts_with('@') {
+      return value.to_string();
+    }
+    if let Some(iri) = self.terms.get(value) {
+      return iri.clone();
+    }
+    if value.contains(':') {
+      return self.expand_prefix(value);
+    }
+    match &self.vocab {
+      Some(vocab) => format!("{}{}", vocab, value),
+      None => value.to_string(),
+    }
+  }
+
+  /// Compacts a full IRI using terms, then prefixes, then `@vocab`.
+  fn compact(&self, iri: &str) -> String {
+    if let Some((term, _)) = self.terms.iter().find(|(_, v)| *v == iri) {
+      return term.clone();
+    }
+    let prefixed = self
+      .terms
+      .iter()
+      .filter(|(_, v)| !v.is_empty() && iri.starts_with(v.as_str()))
+      .max_by_key(|(_, v)| v.len())
+      .map(|(prefix, v)| format!("{}:{}", prefix, &iri[v.len()..]));
+    if let Some(prefixed) = prefixed {
+      return prefixed;
+    }
+    match &self.vocab {
+      Some(vocab) if iri.len() > vocab.len() && iri.starts_with(vocab) => {
+        iri[vocab.len()..].to_string()
+      }
+      _ => iri.to_string(),
+    }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Framing.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+#[derive(Clone, Copy, PartialEq)]
+enum Embed {
+  Once,
+  Always,
+  Never,
+}
+
+struct Framer<'a> {
+  context: &'a Context,
+  nodes: &'a NodeIndex,
+  /// Nodes already embedded (for `@embed: @once`).
+  embedded: HashSet<String>,
+  /// Nodes currently being framed, to break cycles.
+  path: Vec<String>,
+}
+
+impl<'a> Framer<'a> {
+  fn matches(&self, id: &str, frame: &Map<String, DType>) -> bool {
+    let (types, properties) = match self.nodes.subjects.get(id) {
+      Some(node) => node,
+      None => return false,
+    };
+
+    let mut keyed = false;
+    if let Some(frame_id) = frame.get("@id") {
+      keyed = true;
+      let ids = values_of(frame_id);
+      if !ids.is_empty() && !ids.iter().any(|i| self.context.expand(i) == id) {
+        return false;
+      }
+    }
+    if let Some(frame_type) = frame.get("@type") {
+      keyed = true;
+      let wanted = values_of(frame_type);
+      let any_type = wanted.is_empty() && frame_type.is_object();
+      if any_type {
+        if types.is_empty() {
+          return false;
+        }
+      } else if !wanted.is_empty()
+        && !wanted
+          .iter()
+          .any(|t| types.contains(&self.context.expand(t)))
+      {
+        return false;
+      }
+    }
+    if keyed {
+      return true;
+    }
+
+    // Duck typing: the node must have every property in the frame.
+    frame
+      .iter()
+      .filter(|(k, _)| !k.starts_with('@'))
+      .all(|(k, v)| {
+        let present = properties.contains_key(&self.context.expand(k));
+        // `[]` or a `@default` frame match nodes without the property.
+        present || matches!(v, DType::Array(a) if a.is_empty())
+      })
+  }
+
+  fn frame_node(&mut self, id: &str, frame: &Map<String, DType>) -> DType {
+    let (types, properties) = match self.nodes.subjects.get(id) {
+      Some(node) => node,
+      None => return reference(self.context, id),
+    };
+
+    let explicit = flag(frame, "@explicit");
+    let omit_default = flag(frame, "@omitDefault");
+    let embed = embed_of(frame);
+
+    let mut out = Map::new();
+    if !id.starts_with("_:") {
+      out.insert("@id".to_string(), self.context.compact(id).into());
+    }
+    if !types.is_empty() {
+      let mut types: Vec<DType> = types
+        .iter()
+        .map(|t| DType::from(self.context.compact(t)))
+        .collect();
+      let value = if types.len() == 1 {
+        types.remove(0)
+      } else {
+        DType::from(types)
+      };
+      out.insert("@type".to_string(), value);
+    }
+
+    self.path.push(id.to_string());
+    self.embedded.insert(id.to_string());
+
+    // Properties named in the frame, in frame order.
+    let mut seen = HashSet::new();
+    for (key, sub_frame) in frame.iter().filter(|(k, _)| !k.starts_with('@')) {
+      let iri = self.context.expand(key);
+      seen.insert(iri.clone());
+      let sub_frame = sub_frame_of(sub_frame);
+
+      match properties.get(&iri) {
+        Some(values) => {
+          let values = self.frame_values(values, &sub_frame, embed);
+          let term = self.context.compact(&iri);
+          out.insert(term.clone(), self.compact_values(&term, values));
+        }
+        None if !omit_default && !flag(&sub_frame, "@omitDefault") => {
+          let default = sub_frame.get("@default").cloned().unwrap_or_default();
+          out.insert(self.context.compact(&iri), default);
+        }
+        None => {}
+      }
+    }
+
+    // Every other property, unless the frame is explicit.
+    if !explicit {
+      for (iri, values) in properties {
+        if seen.contains(iri) {
+          continue;
+        }
+        let values = self.frame_values(values, &Map::new(), embed);
+        let term = self.context.compact(iri);
+        out.insert(term.clone(), self.compact_values(&term, values));
+      }
+    }
+
+    self.path.pop();
+    DType::Object(out)
+  }
+
+  fn frame_values(
+    &mut self,
+    values: &[Value],
+    sub_frame: &Map<String, DType>,
+    embed: Embed,
+  ) -> Vec<DType> {
+    let embed = match sub_frame.get("@embed") {
+      Some(_) => embed_of(sub_frame),
+      None => embed,
+    };
+    let filtered = sub_frame.keys().any(|k| k == "@type" || k == "@id");
+
+    values
+      .iter()
+      .filter_map(|value| match value {
+        Value::Literal(value) => Some(value.clone()),
+        Value::Reference(id) => {
+          if filtered && !self.matches(id, sub_frame) {
+            return None;
+          }
+          let can_embed = match embed {
+            Embed::Never => false,
+            Embed::Once => !self.embedded.contains(id),
+            Embed::Always => true,
+          };
+          if can_embed && !self.path.contains(id) {
+            Some(self.frame_node(id, sub_frame))
+          } else {
+            Some(reference(self.context, id))
+          }
+        }
+      })
+      .collect()
+  }
+
+  fn compact_values(&self, term: &str, mut values: Vec<DType>) -> DType {
+    if values.len() == 1 && !self.context.sets.contains(term) {
+      values.remove(0)
+    } else {
+      DType::from(values)
+    }
+  }
+}
+
+fn reference(context: &Context, id: &str) -> DType {
+  let mut map = Map::new();
+  map.insert("@id".to_string(), context.compact(id).into());
+  DType::Object(map)
+}
+
+/// Returns the string values of a frame's `@id` or `@type`.
+fn values_of(value: &DType) -> Vec<&str> {
+  match value {
+    DType::String(s) => vec![s.as_str()],
+    DType::Array(list) => list.iter().filter_map(DType::as_str).collect(),
+    _ => Vec::new(),
+  }
+}
+
+fn sub_frame_of(value: &DType) -> Map<String, DType> {
+  match value {
+    DType::Object(frame) => frame.clone(),
+    DType::Array(list) => list
+      .first()
+      .and_then(DType::as_object)
+      .cloned()
+      .unwrap_or_default(),
+    _ => Map::new(),
+  }
+}
+
+fn flag(frame: &Map<String, DType>, key: &str) -> bool {
+  frame.get(key).and_then(DType::as_bool).unwrap_or(false)
+}
+
+fn embed_of(frame: &Map<String, DType>) -> Embed {
+  match frame.get("@embed") {
+    Some(DType::String(s)) if s == "@always" => Embed::Always,
+    Some(DType::String(s)) if s == "@never" => Embed::Never,
+    Some(DType::Boolean(false)) => Embed::Never,
+    _ => Embed::Once,
+  }
+}
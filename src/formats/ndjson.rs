@@ -0,0 +1,138 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, io};
+
+use crate::{
+  datastore::json,
+  dtype::{DType, Map},
+  error::Error,
+  graph::{KnowledgeGraph, Node, Triple},
+  Result,
+};
+
+/// Full IRI of `rdf:type`.
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/// Writes `graph` into `writer` as [NDJSON], one entity per line.
+///
+/// [NDJSON]: https://github.com/ndjson/ndjson-spec
+pub(crate) fn to_writer<W: io::Write>(
+  graph: &KnowledgeGraph,
+  mut writer: W,
+) -> Result<()> {
+  entities(graph, |entity| {
+    json::to_writer(&mut writer, &entity)?;
+    writer.write_all(b"\n").map_err(Error::io)
+  })?;
+  writer.flush().map_err(Error::io)
+}
+
+/// Calls `f` with the JSON-LD style node object of every subject in
+/// `graph`, in order of first appearance.
+///
+/// Triples are grouped by subject through borrowed references, so only a
+/// single entity is built at a time.
+pub(crate) fn entities<F>(graph: &KnowledgeGraph, mut f: F) -> Result<()>
+where
+  F: FnMut(DType) -> Result<()>,
+{
+  let mut blanks = 0;
+  let mut index: HashMap<String, usize> = HashMap::new();
+  let mut subjects: Vec<(String, Vec<&Triple>)> = Vec::new();
+
+  for triple in graph.triples() {
+    for source in flatten(triple.source()) {
+      let id = node_id(source, &mut blanks);
+      let i = *index.entry(id.clone()).or_insert_with(|| {
+        subjects.push((id, Vec::new()));
+        subjects.len() - 1
+      });
+      subjects[i].1.push(triple);
+    }
+  }
+
+  for (id, triples) in subjects {
+    f(node_object(id, &triples, &mut blanks))?;
+  }
+  Ok(())
+}
+
+/// Builds the JSON-LD style object of a single subject from its triples.
+pub(crate) fn node_object(
+  id: String,
+  triples: &[&Triple],
+  blanks: &mut usize,
+) -> DType {
+  let mut types: Vec<DType> = Vec::new();
+  let mut properties: Vec<(String, Vec<DType>)> = Vec::new();
+
+  for triple in triples {
+    let predicate = triple.predicate().to_string();
+    let is_type = predicate == "@type" || predicate == RDF_TYPE;
+    for destination in flatten(triple.destination()) {
+      let value = match destination {
+        Node::Literal(value) => value.clone(),
+        node if is_type => DType::String(node_id(node, blanks)),
+        node => crate::json!({ "@id": node_id(node, blanks) }),
+      };
+      if is_type {
+        types.push(value);
+      } else if let Some((_, values)) =
+        properties.iter_mut().find(|(p, _)| *p == predicate)
+      {
+        values.push(value);
+      } else {
+        properties.push((predicate.clone(), vec![value]));
+      }
+    }
+  }
+
+  let mut out = Map::new();
+  out.insert("@id".to_string(), DType::String(id));
+  if !types.is_empty() {
+    out.insert("@type".to_string(), collapse(types));
+  }
+  for (predicate, values) in properties {
+    out.insert(predicate, collapse(values));
+  }
+  DType::Object(out)
+}
+
+/// A single value is written as is, several as an array.
+fn collapse(mut values: Vec<DType>) -> DType {
+  if values.len() == 1 {
+    values.remove(0)
+  } else {
+    DType::from(values)
+  }
+}
+
+pub(crate) fn flatten(node: &Node) -> Vec<&Node> {
+  match node {
+    Node::Multiple(nodes) => nodes.iter().flat_map(flatten).collect(),
+    node => vec![node],
+  }
+}
+
+pub(crate) fn node_id(node: &Node, blanks: &mut usize) -> String {
+  match node {
+    Node::Blank => {
+      let id = format!("_:b{}", blanks);
+      *blanks += 1;
+      id
+    }
+    node => node.to_string(),
+  }
+}
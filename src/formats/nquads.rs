@@ -0,0 +1,198 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+  collections::HashSet,
+  fmt,
+  io::{self, Write as _},
+};
+
+use crate::{
+  error::Error,
+  formats::{
+    estimate::ByteCounter,
+    ntriples::{statements, statements_with, Statement},
+    ExportEstimate, GraphEstimate,
+  },
+  graph::{KnowledgeGraph, Triple},
+  Result,
+};
+
+/// `NQuads` exports a `KnowledgeGraph` as [N-Quads]: N-Triples statements
+/// along with their named graph, which is the source of their
+/// `Provenance`. Statements without a source are in the default graph.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::NQuads;
+/// use sage::graph::{KnowledgeGraph, Node, Predicate, Provenance};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert_with_provenance(
+///   Node::Http("https://example.com/Ada".to_string()),
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal("Ada".into()),
+///   Provenance::new("https://example.com/people"),
+/// );
+/// graph.insert(
+///   Node::Http("https://example.com/Ada".to_string()),
+///   Predicate::Literal("https://schema.org/knows".to_string()),
+///   Node::Blank,
+/// );
+///
+/// assert_eq!(
+///   NQuads::new(&graph).to_string(),
+///   "<https://example.com/Ada> <https://schema.org/name> \"Ada\" \
+///    <https://example.com/people> .\n\
+///    <https://example.com/Ada> <https://schema.org/knows> _:b0 .\n"
+/// );
+/// ```
+///
+/// [N-Quads]: https://www.w3.org/TR/n-quads/
+pub struct NQuads<'a> {
+  graph: &'a KnowledgeGraph,
+}
+
+impl<'a> NQuads<'a> {
+  /// Creates an N-Quads exporter for `graph`.
+  pub fn new(graph: &'a KnowledgeGraph) -> NQuads<'a> {
+    NQuads { graph }
+  }
+
+  /// Writes the N-Quads document into `writer`.
+  pub fn to_writer<W: io::Write>(&self, mut writer: W) -> Result<()> {
+    for statement in statements(self.graph) {
+      writeln!(writer, "{}", quad(&statement)).map_err(Error::io)?;
+    }
+    Ok(())
+  }
+
+  /// Dry-runs the export and reports its size, broken down per named
+  /// graph, without writing anything.
+  pub fn estimate(&self) -> Result<ExportEstimate> {
+    let statements = statements(self.graph);
+    let mut nodes = HashSet::new();
+    let mut graphs: Vec<GraphEstimate> = Vec::new();
+    let mut counter = ByteCounter::default();
+    for statement in &statements {
+      nodes.insert(statement.subject.as_str());
+      nodes.insert(statement.object.as_str());
+      writeln!(counter, "{}", quad(statement)).map_err(Error::io)?;
+
+      let name: Option<String> =
+        statement.graph.as_deref().map(|g| g[1..g.len() - 1].into());
+      match graphs.iter_mut().find(|graph| graph.name == name) {
+        Some(graph) => graph.triples += 1,
+        None => graphs.push(GraphEstimate { name, triples: 1 }),
+      }
+    }
+
+    let mut estimate = ExportEstimate::new(
+      self.graph,
+      nodes.len(),
+      statements.len(),
+      counter.bytes,
+    );
+    estimate.graphs = graphs;
+    Ok(estimate)
+  }
+}
+
+impl<'a> fmt::Display for NQuads<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for statement in statements(self.graph) {
+      writeln!(f, "{}", quad(&statement))?;
+    }
+    Ok(())
+  }
+}
+
+/// `NQuadsWriter` writes triples as N-Quads while they're produced, e.g.
+/// by an importer, without holding the whole dataset in a
+/// `KnowledgeGraph`. Anonymous blank nodes are labelled `_:b0`, `_:b1`,
+/// ... across the whole stream.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::NQuadsWriter;
+/// use sage::graph::{Node, Predicate, Provenance, Triple};
+///
+/// let mut writer = NQuadsWriter::new(Vec::new());
+/// for name in ["Ada", "Charles"] {
+///   let triple = Triple::from_nodes(
+///     Node::Http(format!("https://example.com/{}", name)),
+///     Predicate::Literal("https://schema.org/name".to_string()),
+///     Node::Literal(name.into()),
+///   )
+///   .with_provenance(Provenance::new("https://example.com/people"));
+///   writer.write(&triple).unwrap();
+/// }
+///
+/// let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+/// assert_eq!(output.lines().count(), 2);
+/// assert!(output.ends_with("\"Charles\" <https://example.com/people> .\n"));
+/// ```
+pub struct NQuadsWriter<W: io::Write> {
+  writer: W,
+  blanks: usize,
+}
+
+impl<W: io::Write> NQuadsWriter<W> {
+  /// Creates a streaming N-Quads writer into `writer`.
+  pub fn new(writer: W) -> NQuadsWriter<W> {
+    NQuadsWriter { writer, blanks: 0 }
+  }
+
+  /// Writes the statements of `triple`.
+  pub fn write(&mut self, triple: &Triple) -> Result<()> {
+    for statement in stream_statements(triple, &mut self.blanks) {
+      writeln!(self.writer, "{}", quad(&statement)).map_err(Error::io)?;
+    }
+    Ok(())
+  }
+
+  /// Flushes & returns the underlying writer.
+  pub fn finish(mut self) -> Result<W> {
+    self.writer.flush().map_err(Error::io)?;
+    Ok(self.writer)
+  }
+}
+
+/// Returns the N-Quads line of `statement` (without the trailing newline).
+fn quad(statement: &Statement) -> String {
+  match &statement.graph {
+    Some(graph) => format!(
+      "{} {} {} {} .",
+      statement.subject, statement.predicate, statement.object, graph
+    ),
+    None => statement.line.clone(),
+  }
+}
+
+/// Serializes `triple` like `statements` does, numbering anonymous blank
+/// nodes from `blanks` on.
+pub(crate) fn stream_statements(
+  triple: &Triple,
+  blanks: &mut usize,
+) -> Vec<Statement> {
+  statements_with(Some(triple), &mut |label| match label {
+    Some(label) => format!("_:{}", label),
+    None => {
+      *blanks += 1;
+      format!("_:b{}", *blanks - 1)
+    }
+  })
+}
@@ -0,0 +1,577 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+  collections::HashSet,
+  fmt::{self, Write as _},
+  io::{self, BufRead, Write as _},
+};
+
+use crate::{
+  datastore::json,
+  dtype::{literal::base64_encode, DType, DateTime, Literal, Map},
+  error::{Error, ErrorCode},
+  formats::{estimate::ByteCounter, ExportEstimate},
+  graph::{KnowledgeGraph, Node, Predicate, Triple},
+  iri::Rewriter,
+  Result,
+};
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+const RDF_JSON: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON";
+
+/// `NTriples` exports a `KnowledgeGraph` as [N-Triples], one statement per
+/// line.
+///
+/// `Node::Multiple` & array literals expand into one statement per value,
+/// numbers, booleans & datetimes are typed with their XML Schema datatype,
+/// `{"@value": ..., "@language": ...}` objects become language tagged
+/// literals, other objects `rdf:JSON` literals and `null`s are skipped.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::NTriples;
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   Node::Http("https://example.com/John".to_string()),
+///   Predicate::Literal("https://schema.org/age".to_string()),
+///   Node::Literal(42.into()),
+/// );
+///
+/// assert_eq!(
+///   NTriples::new(&graph).to_string(),
+///   "<https://example.com/John> <https://schema.org/age> \
+///    \"42\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n"
+/// );
+/// ```
+///
+/// [N-Triples]: https://www.w3.org/TR/n-triples/
+pub struct NTriples<'a> {
+  graph: &'a KnowledgeGraph,
+  rewriter: Option<&'a Rewriter>,
+}
+
+impl<'a> NTriples<'a> {
+  /// Creates an N-Triples exporter for `graph`.
+  pub fn new(graph: &'a KnowledgeGraph) -> NTriples<'a> {
+    NTriples {
+      graph,
+      rewriter: None,
+    }
+  }
+
+  /// Rewrites every identifier with `rewriter` while exporting.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::formats::NTriples;
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  /// use sage::iri::Rewriter;
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(
+  ///   Node::Http("http://old.example/Ada".to_string()),
+  ///   Predicate::Literal("https://schema.org/name".to_string()),
+  ///   Node::Literal("Ada".into()),
+  /// );
+  ///
+  /// let rewriter = Rewriter::new().prefix("http://old.example/", "https://data.example/");
+  /// assert_eq!(
+  ///   NTriples::new(&graph).rewrite(&rewriter).to_string(),
+  ///   "<https://data.example/Ada> <https://schema.org/name> \"Ada\" .\n"
+  /// );
+  /// ```
+  pub fn rewrite(mut self, rewriter: &'a Rewriter) -> Self {
+    self.rewriter = Some(rewriter);
+    self
+  }
+
+  /// Returns the statements to export, after rewriting.
+  fn statements(&self) -> Vec<Statement> {
+    match self.rewriter {
+      Some(rewriter) => statements(&rewriter.apply(self.graph)),
+      None => statements(self.graph),
+    }
+  }
+
+  /// Writes the N-Triples document into `writer`.
+  pub fn to_writer<W: io::Write>(&self, mut writer: W) -> Result<()> {
+    for statement in self.statements() {
+      writeln!(writer, "{}", statement.line).map_err(Error::io)?;
+    }
+    Ok(())
+  }
+
+  /// Reads an N-Triples document into a new `KnowledgeGraph`.
+  ///
+  /// Literals typed with the XML Schema datatypes written by the exporter
+  /// are converted back into numbers, booleans & datetimes, `rdf:JSON`
+  /// literals into `DType`s and language tagged literals into
+  /// `{"@value": ..., "@language": ...}` objects. Blank nodes keep their
+  /// label as `Node::BlankId`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::formats::NTriples;
+  ///
+  /// let graph = NTriples::parse(
+  ///   "<https://example.com/John> <https://schema.org/name> \"John\" .\n\
+  ///    #comments are skipped\n\
+  ///    <https://example.com/John> <https://schema.org/age> \
+  ///    \"42\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n",
+  /// )
+  /// .unwrap();
+  ///
+  /// assert_eq!(graph.len(), 2);
+  /// let err = NTriples::parse("<a> <b> .").err().unwrap();
+  /// assert_eq!(err.line(), 1);
+  /// ```
+  pub fn parse(input: &str) -> Result<KnowledgeGraph> {
+    NTriples::from_reader(input.as_bytes())
+  }
+
+  /// Reads an N-Triples document from `reader` into a new `KnowledgeGraph`.
+  ///
+  /// With the `compression` feature, gzip & Zstandard compressed documents
+  /// are decompressed on the fly.
+  pub fn from_reader<R: BufRead>(reader: R) -> Result<KnowledgeGraph> {
+    NTriples::from_reader_with(reader, &Rewriter::new())
+  }
+
+  /// Reads an N-Triples document from `reader` into a new `KnowledgeGraph`,
+  /// rewriting every identifier with `rewriter`.
+  pub fn from_reader_with<R: BufRead>(
+    reader: R,
+    rewriter: &Rewriter,
+  ) -> Result<KnowledgeGraph> {
+    #[cfg(feature = "compression")]
+    let reader = crate::compression::Decoder::new(reader).map_err(Error::io)?;
+    let mut graph = KnowledgeGraph::new();
+    for (idx, line) in reader.lines().enumerate() {
+      let line = line.map_err(Error::io)?;
+      if let Some((subject, predicate, object)) = parse_line(&line, idx + 1)? {
+        graph.insert(
+          rewriter.rewrite_node(&subject),
+          Predicate::Literal(rewriter.rewrite(&predicate).into_owned()),
+          rewriter.rewrite_node(&object),
+        );
+      }
+    }
+    Ok(graph)
+  }
+
+  /// Dry-runs the export and reports its size without writing anything.
+  pub fn estimate(&self) -> Result<ExportEstimate> {
+    let statements = self.statements();
+    let mut nodes = HashSet::new();
+    let mut counter = ByteCounter::default();
+    for statement in &statements {
+      nodes.insert(statement.subject.as_str());
+      nodes.insert(statement.object.as_str());
+      writeln!(counter, "{}", statement.line).map_err(Error::io)?;
+    }
+    Ok(ExportEstimate::new(
+      self.graph,
+      nodes.len(),
+      statements.len(),
+      counter.bytes,
+    ))
+  }
+}
+
+impl<'a> fmt::Display for NTriples<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for statement in self.statements() {
+      writeln!(f, "{}", statement.line)?;
+    }
+    Ok(())
+  }
+}
+
+/// A single N-Triples statement along with its terms.
+pub(crate) struct Statement {
+  pub(crate) subject: String,
+  pub(crate) predicate: String,
+  pub(crate) object: String,
+  /// Named graph of the statement, i.e. the source of its provenance.
+  pub(crate) graph: Option<String>,
+  pub(crate) line: String,
+}
+
+/// Serializes every triple in `graph` into N-Triples statements (without
+/// the trailing newline), in graph order. Labelled blank nodes keep their
+/// label, anonymous ones are labelled `_:b0`, `_:b1`, ... in order of
+/// appearance.
+pub(crate) fn statements(graph: &KnowledgeGraph) -> Vec<Statement> {
+  let mut blanks = 0;
+  statements_with(graph.triples(), &mut |label| match label {
+    Some(label) => format!("_:{}", label),
+    None => {
+      let label = format!("_:b{}", blanks);
+      blanks += 1;
+      label
+    }
+  })
+}
+
+/// Serializes every triple like `statements`, but labels every blank node
+/// `_:b` so the output doesn't depend on graph order.
+pub(crate) fn canonical_statements(graph: &KnowledgeGraph) -> Vec<Statement> {
+  canonical_statements_of(graph.triples())
+}
+
+/// Serializes `triples` like `canonical_statements`.
+pub(crate) fn canonical_statements_of<'t>(
+  triples: impl IntoIterator<Item = &'t Triple>,
+) -> Vec<Statement> {
+  statements_with(triples, &mut |_| "_:b".to_string())
+}
+
+/// Serializes `triples`, labelling blank nodes with `blank` (given the
+/// label of `Node::BlankId`s, `None` for anonymous ones).
+pub(crate) fn statements_with<'t>(
+  triples: impl IntoIterator<Item = &'t Triple>,
+  blank: &mut dyn FnMut(Option<&str>) -> String,
+) -> Vec<Statement> {
+  let mut statements = Vec::new();
+
+  for triple in triples {
+    let predicate =
+      format!("<{}>", escape_iri(&triple.predicate().to_string()));
+    let graph = triple
+      .provenance()
+      .and_then(|provenance| provenance.source.as_deref())
+      .map(|source| format!("<{}>", escape_iri(source)));
+    for source in flatten(triple.source()) {
+      let subject = term(source, blank);
+      for destination in flatten(triple.destination()) {
+        let objects = match destination {
+          Node::Literal(value) => literals(value),
+          node => vec![term(node, blank)],
+        };
+        for object in objects {
+          statements.push(Statement {
+            subject: subject.clone(),
+            predicate: predicate.clone(),
+            graph: graph.clone(),
+            line: format!("{} {} {} .", subject, predicate, object),
+            object,
+          });
+        }
+      }
+    }
+  }
+
+  statements
+}
+
+fn flatten(node: &Node) -> Vec<&Node> {
+  match node {
+    Node::Multiple(nodes) => nodes.iter().flat_map(flatten).collect(),
+    node => vec![node],
+  }
+}
+
+/// Returns the IRI or blank node label of a non-literal node.
+fn term(node: &Node, blank: &mut dyn FnMut(Option<&str>) -> String) -> String {
+  match node {
+    Node::Blank => blank(None),
+    Node::BlankId(label) => blank(Some(label)),
+    node => format!("<{}>", escape_iri(&node.to_string())),
+  }
+}
+
+fn literals(value: &DType) -> Vec<String> {
+  match value {
+    DType::Null => Vec::new(),
+    DType::Array(values) => values.iter().flat_map(literals).collect(),
+    DType::String(s) => vec![format!("\"{}\"", escape_literal(s))],
+    DType::Bytes(b) => vec![typed(&base64_encode(b), "base64Binary")],
+    DType::Boolean(b) => vec![typed(&b.to_string(), "boolean")],
+    DType::Number(n) if n.is_f64() => vec![typed(&n.to_string(), "double")],
+    DType::Number(n) => vec![typed(&n.to_string(), "integer")],
+    DType::DateTime(d) => vec![typed(
+      &d.as_chrono()
+        .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+      "dateTime",
+    )],
+    DType::Object(map) if map.len() == 2 => {
+      match (map.get("@value"), map.get("@language"), map.get("@type")) {
+        (Some(DType::String(s)), Some(DType::String(lang)), _) => {
+          vec![format!("\"{}\"@{}", escape_literal(s), lang)]
+        }
+        (Some(DType::String(s)), _, Some(DType::String(datatype))) => {
+          vec![format!(
+            "\"{}\"^^<{}>",
+            escape_literal(s),
+            escape_iri(datatype)
+          )]
+        }
+        _ => vec![json_literal(value)],
+      }
+    }
+    DType::Object(_) => vec![json_literal(value)],
+  }
+}
+
+fn json_literal(value: &DType) -> String {
+  let text = json::to_string(value).unwrap_or_default();
+  format!("\"{}\"^^<{}>", escape_literal(&text), RDF_JSON)
+}
+
+fn typed(lexical: &str, datatype: &str) -> String {
+  format!("\"{}\"^^<{}{}>", escape_literal(lexical), XSD, datatype)
+}
+
+pub(crate) fn escape_literal(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      c if c.is_control() => {
+        let _ = write!(out, "\\u{:04X}", c as u32);
+      }
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+/// Percent-encodes characters which aren't allowed inside `<...>`.
+pub(crate) fn escape_iri(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  for c in s.chars() {
+    match c {
+      '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\' => {
+        let _ = write!(out, "%{:02X}", c as u32);
+      }
+      c if c <= ' ' => {
+        let _ = write!(out, "%{:02X}", c as u32);
+      }
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Reader
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Parses the subject, predicate & object of the `number`th line of an
+/// N-Triples document, `None` for blank & comment lines.
+pub(crate) fn parse_line(
+  line: &str,
+  number: usize,
+) -> Result<Option<(Node, String, Node)>> {
+  LineParser {
+    line,
+    pos: 0,
+    number,
+  }
+  .statement()
+}
+
+/// Parses the terms of a single N-Triples line.
+struct LineParser<'a> {
+  line: &'a str,
+  pos: usize,
+  number: usize,
+}
+
+impl<'a> LineParser<'a> {
+  /// Returns `None` for blank & comment lines.
+  fn statement(&mut self) -> Result<Option<(Node, String, Node)>> {
+    self.skip_whitespace();
+    if self.rest().is_empty() || self.rest().starts_with('#') {
+      return Ok(None);
+    }
+
+    let subject = match self.peek() {
+      Some('_') => Node::BlankId(self.label()?.to_string()),
+      _ => Node::Http(self.iri()?),
+    };
+    self.skip_whitespace();
+    let predicate = self.iri()?;
+    self.skip_whitespace();
+    let object = match self.peek() {
+      Some('_') => Node::BlankId(self.label()?.to_string()),
+      Some('"') => Node::Literal(self.literal()?),
+      _ => Node::Http(self.iri()?),
+    };
+    self.skip_whitespace();
+    self.expect('.')?;
+    self.skip_whitespace();
+    if !(self.rest().is_empty() || self.rest().starts_with('#')) {
+      return Err(self.error());
+    }
+    Ok(Some((subject, predicate, object)))
+  }
+
+  fn rest(&self) -> &'a str {
+    &self.line[self.pos..]
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.rest().chars().next()
+  }
+
+  fn bump(&mut self) -> Option<char> {
+    let c = self.peek()?;
+    self.pos += c.len_utf8();
+    Some(c)
+  }
+
+  fn skip_whitespace(&mut self) {
+    while matches!(self.peek(), Some(' ' | '\t')) {
+      self.pos += 1;
+    }
+  }
+
+  fn expect(&mut self, expected: char) -> Result<()> {
+    match self.bump() {
+      Some(c) if c == expected => Ok(()),
+      _ => Err(self.error()),
+    }
+  }
+
+  fn error(&self) -> Error {
+    Error::syntax(ErrorCode::InvalidStatement, self.number, self.pos + 1)
+  }
+
+  fn iri(&mut self) -> Result<String> {
+    self.expect('<')?;
+    let end = self.rest().find('>').ok_or_else(|| self.error())?;
+    let iri = self.rest()[..end].to_string();
+    self.pos += end + 1;
+    Ok(iri)
+  }
+
+  /// Returns the label of a blank node, without the leading `_:`.
+  fn label(&mut self) -> Result<&'a str> {
+    self.expect('_')?;
+    self.expect(':')?;
+    let start = self.pos;
+    while let Some(c) = self.peek().filter(|c| !c.is_whitespace()) {
+      self.pos += c.len_utf8();
+    }
+    // A label can't end with `.`, so it belongs to the statement.
+    while self.pos > start && self.line[..self.pos].ends_with('.') {
+      self.pos -= 1;
+    }
+    if start == self.pos {
+      return Err(self.error());
+    }
+    Ok(&self.line[start..self.pos])
+  }
+
+  fn literal(&mut self) -> Result<DType> {
+    self.expect('"')?;
+    let mut lexical = String::new();
+    loop {
+      match self.bump().ok_or_else(|| self.error())? {
+        '"' => break,
+        '\\' => lexical.push(self.escape()?),
+        c => lexical.push(c),
+      }
+    }
+
+    match self.peek() {
+      Some('@') => {
+        self.pos += 1;
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if c.is_alphanumeric() || c == '-')
+        {
+          self.pos += 1;
+        }
+        let mut value = Map::new();
+        value.insert("@value".to_string(), lexical.into());
+        value
+          .insert("@language".to_string(), self.line[start..self.pos].into());
+        Ok(DType::Object(value))
+      }
+      Some('^') => {
+        self.expect('^')?;
+        self.expect('^')?;
+        let datatype = self.iri()?;
+        Ok(typed_value(lexical, &datatype))
+      }
+      _ => Ok(lexical.into()),
+    }
+  }
+
+  fn escape(&mut self) -> Result<char> {
+    let c = match self.bump().ok_or_else(|| self.error())? {
+      't' => '\t',
+      'b' => '\u{8}',
+      'n' => '\n',
+      'r' => '\r',
+      'f' => '\u{c}',
+      '"' => '"',
+      '\'' => '\'',
+      '\\' => '\\',
+      'u' => self.unicode(4)?,
+      'U' => self.unicode(8)?,
+      _ => return Err(self.error()),
+    };
+    Ok(c)
+  }
+
+  fn unicode(&mut self, digits: usize) -> Result<char> {
+    let hex = self.rest().get(..digits).ok_or_else(|| self.error())?;
+    let c = u32::from_str_radix(hex, 16)
+      .ok()
+      .and_then(char::from_u32)
+      .ok_or_else(|| self.error())?;
+    self.pos += digits;
+    Ok(c)
+  }
+}
+
+/// Converts a typed literal back into a `DType`, falling back to the
+/// lexical form for unknown datatypes or invalid values.
+pub(crate) fn typed_value(lexical: String, datatype: &str) -> DType {
+  let value = match datatype.strip_prefix(XSD) {
+    Some("integer" | "int" | "long") => {
+      lexical.parse::<i64>().ok().map(DType::from)
+    }
+    Some("double" | "float" | "decimal") => {
+      lexical.parse::<f64>().ok().map(DType::from)
+    }
+    Some("boolean") => lexical.parse::<bool>().ok().map(DType::from),
+    Some("base64Binary" | "hexBinary") => Literal::new(&lexical, datatype)
+      .value::<Vec<u8>>()
+      .ok()
+      .map(|b| DType::Bytes(Box::new(b))),
+    Some("dateTime") => chrono::DateTime::parse_from_rfc3339(&lexical)
+      .ok()
+      .map(|d| DType::DateTime(DateTime::from(d.with_timezone(&chrono::Utc)))),
+    _ if datatype == RDF_JSON => json::from_str(&lexical).ok(),
+    _ => None,
+  };
+  value.unwrap_or(DType::String(lexical))
+}
@@ -0,0 +1,972 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::formats::rdfxml` reads & writes the [RDF/XML] syntax, which many
+//! ontologies (e.g. OWL files) are only published in.
+//!
+//! [RDF/XML]: https://www.w3.org/TR/rdf-syntax-grammar/
+
+use std::{
+  collections::{HashMap, HashSet},
+  fmt::{self, Write as _},
+  io::{self, Read as _},
+};
+
+use crate::{
+  datastore::json,
+  dtype::{literal::base64_encode, DType, LangString},
+  error::{Error, ErrorCode},
+  formats::{typed_value, viz::XmlEscape},
+  graph::{KnowledgeGraph, Node, Predicate},
+  iri::resolve,
+  vocab::Namespaces,
+  Result,
+};
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const XML: &str = "http://www.w3.org/XML/1998/namespace";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Attributes of the RDF/XML syntax, which don't denote statements.
+const SYNTAX_ATTRIBUTES: &[&str] = &[
+  "about",
+  "ID",
+  "nodeID",
+  "resource",
+  "datatype",
+  "parseType",
+  "bagID",
+  "aboutEach",
+  "aboutEachPrefix",
+];
+
+/// `RdfXml` reads & writes a `KnowledgeGraph` as [RDF/XML].
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::RdfXml;
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   Node::Http("https://example.com/Ada".to_string()),
+///   Predicate::Literal("https://schema.org/knows".to_string()),
+///   Node::Http("https://example.com/Charles".to_string()),
+/// );
+/// graph.insert(
+///   Node::Http("https://example.com/Ada".to_string()),
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal("Ada & Co".into()),
+/// );
+///
+/// assert_eq!(
+///   RdfXml::new(&graph).to_string(),
+///   r#"<?xml version="1.0" encoding="utf-8"?>
+/// <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:schema="https://schema.org/">
+///   <rdf:Description rdf:about="https://example.com/Ada">
+///     <schema:knows rdf:resource="https://example.com/Charles"/>
+///     <schema:name>Ada &amp; Co</schema:name>
+///   </rdf:Description>
+/// </rdf:RDF>
+/// "#
+/// );
+/// let document = RdfXml::new(&graph).to_string();
+/// assert_eq!(RdfXml::parse(&document).unwrap().len(), 2);
+/// ```
+///
+/// [RDF/XML]: https://www.w3.org/TR/rdf-syntax-grammar/
+pub struct RdfXml<'a> {
+  graph: &'a KnowledgeGraph,
+  namespaces: Namespaces,
+}
+
+impl<'a> RdfXml<'a> {
+  /// Creates an RDF/XML exporter for `graph`, declaring the default `rdf`,
+  /// `rdfs` & `schema` prefixes where they apply and `ns0`, `ns1`, ... for
+  /// other namespaces.
+  pub fn new(graph: &'a KnowledgeGraph) -> RdfXml<'a> {
+    RdfXml {
+      graph,
+      namespaces: Namespaces::default(),
+    }
+  }
+
+  /// Declares the prefixes of `namespaces` instead.
+  pub fn namespaces(mut self, namespaces: &Namespaces) -> Self {
+    self.namespaces = namespaces.clone();
+    self
+  }
+
+  /// Reads an RDF/XML document into a new `KnowledgeGraph`.
+  ///
+  /// Node & property elements, property attributes, `rdf:parseType`
+  /// `Resource`, `Literal` & `Collection`, `rdf:li`, `xml:base`,
+  /// `xml:lang` & entities declared in the internal DTD subset are
+  /// supported. Typed literals are converted like `NTriples::parse` does,
+  /// XML literals kept as strings. Reification of statements with a
+  /// property element's `rdf:ID` isn't supported.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::formats::RdfXml;
+  /// use sage::graph::Node;
+  ///
+  /// let graph = RdfXml::parse(
+  ///   r##"<?xml version="1.0"?>
+  ///   <!DOCTYPE rdf:RDF [
+  ///     <!ENTITY xsd "http://www.w3.org/2001/XMLSchema#">
+  ///   ]>
+  ///   <rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#"
+  ///            xmlns:owl="http://www.w3.org/2002/07/owl#"
+  ///            xmlns:ex="https://example.com/terms#"
+  ///            xml:base="https://example.com/people">
+  ///     <owl:NamedIndividual rdf:about="#Ada" ex:name="Ada">
+  ///       <ex:born rdf:datatype="&xsd;integer">1815</ex:born>
+  ///       <ex:title xml:lang="en">Countess of Lovelace</ex:title>
+  ///       <ex:knows>
+  ///         <rdf:Description rdf:about="#Charles" ex:name="Charles"/>
+  ///       </ex:knows>
+  ///       <ex:address rdf:parseType="Resource">
+  ///         <ex:city>London</ex:city>
+  ///       </ex:address>
+  ///     </owl:NamedIndividual>
+  ///   </rdf:RDF>"##,
+  /// )
+  /// .unwrap();
+  ///
+  /// assert_eq!(graph.len(), 8);
+  /// let ada = graph.entity("https://example.com/people#Ada");
+  /// assert_eq!(
+  ///   ada.get("https://example.com/terms#born"),
+  ///   [&Node::Literal(1815.into())]
+  /// );
+  ///
+  /// let err = RdfXml::parse("<a xmlns=\"https://example.com/\">\n<b></c>");
+  /// assert_eq!(err.err().unwrap().line(), 2);
+  /// ```
+  pub fn parse(input: &str) -> Result<KnowledgeGraph> {
+    let document = XmlParser::new(input).document()?;
+    let mut labels = HashSet::new();
+    node_ids(&document, &mut labels);
+    let mut reader = Reader {
+      graph: KnowledgeGraph::new(),
+      labels,
+      blanks: 0,
+    };
+    let (base, lang) = scope(&document, "", None);
+    if document.is(RDF, "RDF") {
+      for el in document.elements() {
+        reader.node_element(el, &base, lang.as_deref())?;
+      }
+    } else {
+      reader.node_element(&document, "", None)?;
+    }
+    Ok(reader.graph)
+  }
+
+  /// Reads an RDF/XML document from `reader` into a new `KnowledgeGraph`.
+  ///
+  /// With the `compression` feature, gzip & Zstandard compressed documents
+  /// are decompressed on the fly.
+  pub fn from_reader<R: io::Read>(reader: R) -> Result<KnowledgeGraph> {
+    #[cfg(feature = "compression")]
+    let reader = crate::compression::Decoder::new(io::BufReader::new(reader))
+      .map_err(Error::io)?;
+    let mut input = String::new();
+    io::BufReader::new(reader)
+      .read_to_string(&mut input)
+      .map_err(Error::io)?;
+    RdfXml::parse(&input)
+  }
+
+  /// Writes the RDF/XML document into `writer`. Fails if a predicate can't
+  /// be written as an XML name, i.e. doesn't end with a letter, digit or
+  /// `_`, `-` & `.`.
+  pub fn to_writer<W: io::Write>(&self, mut writer: W) -> Result<()> {
+    let (document, skipped) = self.document();
+    if skipped {
+      return Err(Error::syntax(ErrorCode::InvalidRdfXml, 0, 0));
+    }
+    writer.write_all(document.as_bytes()).map_err(Error::io)
+  }
+
+  /// Returns the document, and whether statements had to be skipped.
+  fn document(&self) -> (String, bool) {
+    let prefixes: HashMap<String, String> = self
+      .namespaces
+      .prefixes()
+      .into_iter()
+      .map(|(prefix, namespace)| (namespace, prefix))
+      .collect();
+    let mut declared: Vec<(String, String)> =
+      vec![("rdf".to_string(), RDF.to_string())];
+    let mut subjects: Vec<(String, String)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut blanks = 0;
+    let mut skipped = false;
+
+    for triple in self.graph.triples() {
+      let iri = triple.predicate().to_string();
+      let (namespace, local) = match split_iri(&iri) {
+        Some(parts) => parts,
+        None => {
+          skipped = true;
+          continue;
+        }
+      };
+      let prefix = match declared.iter().find(|(_, ns)| ns == namespace) {
+        Some((prefix, _)) => prefix.clone(),
+        None => {
+          let prefix = prefixes
+            .get(namespace)
+            .filter(|p| !p.is_empty() && !declared.iter().any(|(d, _)| d == *p))
+            .cloned()
+            .unwrap_or_else(|| format!("ns{}", declared.len() - 1));
+          declared.push((prefix.clone(), namespace.to_string()));
+          prefix
+        }
+      };
+      let name = format!("{}:{}", prefix, local);
+
+      for source in flatten(triple.source()) {
+        let subject = match source {
+          Node::Blank => {
+            blanks += 1;
+            format!("rdf:nodeID=\"b{}\"", blanks - 1)
+          }
+          Node::BlankId(label) => {
+            format!("rdf:nodeID=\"{}\"", XmlEscape(label))
+          }
+          node => format!("rdf:about=\"{}\"", XmlEscape(&node.to_string())),
+        };
+        let i = *index.entry(subject.clone()).or_insert_with(|| {
+          subjects.push((subject, String::new()));
+          subjects.len() - 1
+        });
+        let body = &mut subjects[i].1;
+        for destination in flatten(triple.destination()) {
+          match destination {
+            Node::Literal(value) => {
+              for (lexical, attr) in literals(value) {
+                let _ = writeln!(
+                  body,
+                  "    <{}{}>{}</{}>",
+                  name,
+                  attr,
+                  XmlEscape(&lexical),
+                  name
+                );
+              }
+            }
+            Node::Blank => {
+              blanks += 1;
+              let _ = writeln!(
+                body,
+                "    <{} rdf:nodeID=\"b{}\"/>",
+                name,
+                blanks - 1
+              );
+            }
+            Node::BlankId(label) => {
+              let _ = writeln!(
+                body,
+                "    <{} rdf:nodeID=\"{}\"/>",
+                name,
+                XmlEscape(label)
+              );
+            }
+            node => {
+              let _ = writeln!(
+                body,
+                "    <{} rdf:resource=\"{}\"/>",
+                name,
+                XmlEscape(&node.to_string())
+              );
+            }
+          }
+        }
+      }
+    }
+
+    let mut out =
+      String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<rdf:RDF");
+    for (prefix, namespace) in &declared {
+      let _ = write!(out, " xmlns:{}=\"{}\"", prefix, XmlEscape(namespace));
+    }
+    out.push_str(">\n");
+    for (subject, body) in subjects {
+      let _ = write!(out, "  <rdf:Description {}>\n{}", subject, body);
+      out.push_str("  </rdf:Description>\n");
+    }
+    out.push_str("</rdf:RDF>\n");
+    (out, skipped)
+  }
+}
+
+impl<'a> fmt::Display for RdfXml<'a> {
+  /// Writes the RDF/XML document, skipping statements whose predicate
+  /// can't be written as an XML name (see `RdfXml::to_writer`).
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&self.document().0)
+  }
+}
+
+fn flatten(node: &Node) -> Vec<&Node> {
+  match node {
+    Node::Multiple(nodes) => nodes.iter().flat_map(flatten).collect(),
+    node => vec![node],
+  }
+}
+
+/// Returns the lexical forms of a literal along with their `rdf:datatype`
+/// or `xml:lang` attribute, like `NTriples` writes them.
+fn literals(value: &DType) -> Vec<(String, String)> {
+  let typed = |lexical: String, datatype: &str| {
+    (lexical, format!(" rdf:datatype=\"{}{}\"", XSD, datatype))
+  };
+  match value {
+    DType::Null => Vec::new(),
+    DType::Array(values) => values.iter().flat_map(literals).collect(),
+    DType::String(s) => vec![(s.clone(), String::new())],
+    DType::Bytes(b) => vec![typed(base64_encode(b), "base64Binary")],
+    DType::Boolean(b) => vec![typed(b.to_string(), "boolean")],
+    DType::Number(n) if n.is_f64() => vec![typed(n.to_string(), "double")],
+    DType::Number(n) => vec![typed(n.to_string(), "integer")],
+    DType::DateTime(d) => vec![typed(
+      d.as_chrono()
+        .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+      "dateTime",
+    )],
+    DType::Object(map) if map.len() == 2 => {
+      match (map.get("@value"), map.get("@language")) {
+        (Some(DType::String(s)), Some(DType::String(lang))) => {
+          vec![(s.clone(), format!(" xml:lang=\"{}\"", XmlEscape(lang)))]
+        }
+        _ => vec![json_literal(value)],
+      }
+    }
+    DType::Object(_) => vec![json_literal(value)],
+  }
+}
+
+fn json_literal(value: &DType) -> (String, String) {
+  let text = json::to_string(value).unwrap_or_default();
+  (text, format!(" rdf:datatype=\"{}JSON\"", RDF))
+}
+
+/// Splits an IRI into a namespace & the longest local name which is a
+/// valid XML name.
+fn split_iri(iri: &str) -> Option<(&str, &str)> {
+  let is_name_char =
+    |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | '.');
+  let mut start = iri.len();
+  for (idx, c) in iri.char_indices().rev() {
+    if !is_name_char(c) {
+      break;
+    }
+    start = idx;
+  }
+  // Local names can't start with a digit, `-` or `.`.
+  let local =
+    iri[start..].trim_start_matches(|c: char| !c.is_alphabetic() && c != '_');
+  let start = iri.len() - local.len();
+  if local.is_empty() || start == 0 {
+    return None;
+  }
+  Some((&iri[..start], local))
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Reader.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Inserts the statements of an RDF/XML document into `graph`.
+struct Reader {
+  graph: KnowledgeGraph,
+  /// Blank node labels used by the document.
+  labels: HashSet<String>,
+  blanks: usize,
+}
+
+impl Reader {
+  /// Reads a node element, returning its node.
+  fn node_element(
+    &mut self,
+    el: &XmlElement,
+    base: &str,
+    lang: Option<&str>,
+  ) -> Result<Node> {
+    let (base, lang) = scope(el, base, lang);
+    let subject = if let Some(about) = el.rdf_attr("about") {
+      Node::Http(resolve(&base, about))
+    } else if let Some(id) = el.rdf_attr("ID") {
+      Node::Http(resolve(&base, &format!("#{}", id)))
+    } else if let Some(label) = el.rdf_attr("nodeID") {
+      Node::BlankId(label.to_string())
+    } else {
+      self.blank_node()
+    };
+
+    if !el.is(RDF, "Description") {
+      self.insert(&subject, &format!("{}type", RDF), Node::Http(el.iri()));
+    }
+    self.property_attributes(el, &subject, &base, lang.as_deref());
+    if el.has_text() {
+      return Err(el.error());
+    }
+
+    let mut li = 0;
+    for child in el.elements() {
+      self.property_element(
+        child,
+        &subject,
+        &base,
+        lang.as_deref(),
+        &mut li,
+      )?;
+    }
+    Ok(subject)
+  }
+
+  fn property_element(
+    &mut self,
+    el: &XmlElement,
+    subject: &Node,
+    base: &str,
+    lang: Option<&str>,
+    li: &mut usize,
+  ) -> Result<()> {
+    let (base, lang) = scope(el, base, lang);
+    let lang = lang.as_deref();
+    let predicate = if el.is(RDF, "li") {
+      *li += 1;
+      format!("{}_{}", RDF, li)
+    } else {
+      el.iri()
+    };
+
+    match el.rdf_attr("parseType") {
+      Some("Resource") => {
+        let object = self.blank_node();
+        self.insert(subject, &predicate, object.clone());
+        let mut li = 0;
+        for child in el.elements() {
+          self.property_element(child, &object, &base, lang, &mut li)?;
+        }
+      }
+      Some("Collection") => {
+        let mut items = Vec::new();
+        for child in el.elements() {
+          items.push(self.node_element(child, &base, lang)?);
+        }
+        let mut list = Node::Http(format!("{}nil", RDF));
+        for item in items.into_iter().rev() {
+          let node = self.blank_node();
+          self.insert(&node, &format!("{}first", RDF), item);
+          self.insert(&node, &format!("{}rest", RDF), list);
+          list = node;
+        }
+        self.insert(subject, &predicate, list);
+      }
+      Some(_) => {
+        let literal = Node::Literal(el.inner.clone().into());
+        self.insert(subject, &predicate, literal);
+      }
+      None => {
+        let children: Vec<&XmlElement> = el.elements().collect();
+        if children.len() > 1 || (!children.is_empty() && el.has_text()) {
+          return Err(el.error());
+        }
+        if let Some(child) = children.first() {
+          let object = self.node_element(child, &base, lang)?;
+          self.insert(subject, &predicate, object);
+        } else if el.rdf_attr("resource").is_some()
+          || el.rdf_attr("nodeID").is_some()
+          || el.property_attrs().next().is_some()
+        {
+          let object = match (el.rdf_attr("resource"), el.rdf_attr("nodeID")) {
+            (Some(iri), _) => Node::Http(resolve(&base, iri)),
+            (None, Some(label)) => Node::BlankId(label.to_string()),
+            (None, None) => self.blank_node(),
+          };
+          self.insert(subject, &predicate, object.clone());
+          self.property_attributes(el, &object, &base, lang);
+        } else {
+          let text = el.text();
+          let literal = match (el.rdf_attr("datatype"), lang) {
+            (Some(datatype), _) => typed_value(text, &resolve(&base, datatype)),
+            (None, Some(lang)) => match LangString::new(&text, lang) {
+              Ok(s) => DType::from(s),
+              Err(_) => DType::String(text),
+            },
+            (None, None) => DType::String(text),
+          };
+          self.insert(subject, &predicate, Node::Literal(literal));
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// Inserts the statements of the property attributes of `el`.
+  fn property_attributes(
+    &mut self,
+    el: &XmlElement,
+    subject: &Node,
+    base: &str,
+    lang: Option<&str>,
+  ) {
+    for attr in el.property_attrs() {
+      let predicate = format!("{}{}", attr.ns, attr.local);
+      let object = if attr.ns == RDF && attr.local == "type" {
+        Node::Http(resolve(base, &attr.value))
+      } else {
+        match lang.map(|lang| LangString::new(&attr.value, lang)) {
+          Some(Ok(s)) => Node::from(s),
+          _ => Node::Literal(attr.value.clone().into()),
+        }
+      };
+      self.insert(subject, &predicate, object);
+    }
+  }
+
+  fn insert(&mut self, subject: &Node, predicate: &str, object: Node) {
+    self.graph.insert(
+      subject.clone(),
+      Predicate::Literal(predicate.to_string()),
+      object,
+    );
+  }
+
+  /// Returns a blank node whose label isn't used by the document.
+  fn blank_node(&mut self) -> Node {
+    loop {
+      let label = format!("b{}", self.blanks);
+      self.blanks += 1;
+      if !self.labels.contains(&label) {
+        return Node::BlankId(label);
+      }
+    }
+  }
+}
+
+/// Applies the `xml:base` & `xml:lang` of `el` to the inherited ones.
+fn scope(
+  el: &XmlElement,
+  base: &str,
+  lang: Option<&str>,
+) -> (String, Option<String>) {
+  let base = match el.attr(XML, "base") {
+    Some(relative) => resolve(base, relative),
+    None => base.to_string(),
+  };
+  let lang = match el.attr(XML, "lang") {
+    Some("") => None,
+    Some(lang) => Some(lang.to_string()),
+    None => lang.map(str::to_string),
+  };
+  (base, lang)
+}
+
+/// Collects the `rdf:nodeID`s of the document.
+fn node_ids(el: &XmlElement, labels: &mut HashSet<String>) {
+  if let Some(label) = el.rdf_attr("nodeID") {
+    labels.insert(label.to_string());
+  }
+  for child in el.elements() {
+    node_ids(child, labels);
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | XML.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// An element with its name & attributes resolved against the namespaces
+/// in scope.
+struct XmlElement {
+  ns: String,
+  local: String,
+  attrs: Vec<XmlAttr>,
+  children: Vec<XmlContent>,
+  /// Source of the element's content, for `rdf:parseType="Literal"`.
+  inner: String,
+  line: usize,
+  column: usize,
+}
+
+struct XmlAttr {
+  ns: String,
+  local: String,
+  value: String,
+}
+
+enum XmlContent {
+  Element(XmlElement),
+  Text(String),
+}
+
+impl XmlElement {
+  fn is(&self, ns: &str, local: &str) -> bool {
+    self.ns == ns && self.local == local
+  }
+
+  fn iri(&self) -> String {
+    format!("{}{}", self.ns, self.local)
+  }
+
+  fn attr(&self, ns: &str, local: &str) -> Option<&str> {
+    self
+      .attrs
+      .iter()
+      .find(|a| a.ns == ns && a.local == local)
+      .map(|a| a.value.as_str())
+  }
+
+  /// Returns the RDF syntax attribute `local`, also accepted without the
+  /// `rdf:` prefix.
+  fn rdf_attr(&self, local: &str) -> Option<&str> {
+    self.attr(RDF, local).or_else(|| self.attr("", local))
+  }
+
+  /// Returns the attributes denoting statements.
+  fn property_attrs(&self) -> impl Iterator<Item = &XmlAttr> {
+    self.attrs.iter().filter(|a| {
+      let syntax = a.ns == RDF && SYNTAX_ATTRIBUTES.contains(&a.local.as_str());
+      !syntax && a.ns != XML && !a.ns.is_empty()
+    })
+  }
+
+  fn elements(&self) -> impl Iterator<Item = &XmlElement> {
+    self.children.iter().filter_map(|child| match child {
+      XmlContent::Element(el) => Some(el),
+      XmlContent::Text(_) => None,
+    })
+  }
+
+  fn text(&self) -> String {
+    self
+      .children
+      .iter()
+      .filter_map(|child| match child {
+        XmlContent::Text(text) => Some(text.as_str()),
+        XmlContent::Element(_) => None,
+      })
+      .collect()
+  }
+
+  /// Returns `true` if the element contains non-whitespace text.
+  fn has_text(&self) -> bool {
+    self.children.iter().any(|child| match child {
+      XmlContent::Text(text) => !text.trim().is_empty(),
+      XmlContent::Element(_) => false,
+    })
+  }
+
+  #[cold]
+  fn error(&self) -> Error {
+    Error::syntax(ErrorCode::InvalidRdfXml, self.line, self.column)
+  }
+}
+
+/// Parses a namespace aware XML document.
+struct XmlParser<'a> {
+  input: &'a str,
+  pos: usize,
+  /// Entities declared in the internal DTD subset.
+  entities: HashMap<String, String>,
+  /// Namespace declarations of the open elements, `""` for the default
+  /// namespace.
+  scopes: Vec<HashMap<String, String>>,
+}
+
+impl<'a> XmlParser<'a> {
+  fn new(input: &'a str) -> XmlParser<'a> {
+    XmlParser {
+      input,
+      pos: 0,
+      entities: HashMap::new(),
+      scopes: Vec::new(),
+    }
+  }
+
+  fn rest(&self) -> &'a str {
+    &self.input[self.pos..]
+  }
+
+  /// Skips whitespace, comments, processing instructions & the document
+  /// type declaration, recording its entities.
+  fn misc(&mut self) -> Result<()> {
+    loop {
+      let trimmed = self.rest().trim_start();
+      self.pos = self.input.len() - trimmed.len();
+      if trimmed.starts_with("<!--") {
+        self.skip_past("-->")?;
+      } else if trimmed.starts_with("<?") {
+        self.skip_past("?>")?;
+      } else if trimmed.starts_with("<!DOCTYPE") {
+        self.doctype()?;
+      } else {
+        return Ok(());
+      }
+    }
+  }
+
+  fn skip_past(&mut self, end: &str) -> Result<()> {
+    match self.rest().find(end) {
+      Some(idx) => {
+        self.pos += idx + end.len();
+        Ok(())
+      }
+      None => Err(self.error()),
+    }
+  }
+
+  fn doctype(&mut self) -> Result<()> {
+    let rest = self.rest();
+    let (subset, end) = match (rest.find('['), rest.find('>')) {
+      (Some(open), Some(close)) if open < close => {
+        let len = rest[open..].find("]").ok_or_else(|| self.error())?;
+        let subset = &rest[open + 1..open + len];
+        let close = rest[open + len..].find('>').ok_or_else(|| self.error())?;
+        (subset, open + len + close + 1)
+      }
+      (_, Some(close)) => ("", close + 1),
+      _ => return Err(self.error()),
+    };
+
+    let mut decls = subset;
+    while let Some(idx) = decls.find("<!ENTITY") {
+      decls = decls[idx + "<!ENTITY".len()..].trim_start();
+      let name_end = decls.find(char::is_whitespace).unwrap_or(decls.len());
+      let name = &decls[..name_end];
+      let value = decls[name_end..].trim_start();
+      if let Some(quote @ ('"' | '\'')) = value.chars().next() {
+        if let Some(len) = value[1..].find(quote) {
+          let value = self.decode(&value[1..=len])?;
+          self.entities.insert(name.to_string(), value);
+        }
+      }
+    }
+    self.pos += end;
+    Ok(())
+  }
+
+  /// Parses the root element.
+  fn document(mut self) -> Result<XmlElement> {
+    self.misc()?;
+    let root = self.element()?;
+    self.misc()?;
+    if !self.rest().is_empty() {
+      return Err(self.error());
+    }
+    Ok(root)
+  }
+
+  fn element(&mut self) -> Result<XmlElement> {
+    let (line, column) = self.position();
+    if !self.rest().starts_with('<') {
+      return Err(self.error());
+    }
+    self.pos += 1;
+    let qname = self.name()?;
+
+    let mut raw_attrs = Vec::new();
+    let mut declared = HashMap::new();
+    let empty = loop {
+      let trimmed = self.rest().trim_start();
+      self.pos = self.input.len() - trimmed.len();
+      if let Some(rest) = trimmed.strip_prefix("/>") {
+        self.pos = self.input.len() - rest.len();
+        break true;
+      } else if let Some(rest) = trimmed.strip_prefix('>') {
+        self.pos = self.input.len() - rest.len();
+        break false;
+      }
+      let name = self.name()?;
+      let rest = self.rest().trim_start();
+      let rest = rest.strip_prefix('=').ok_or_else(|| self.error())?;
+      let rest = rest.trim_start();
+      let quote = match rest.chars().next() {
+        Some(quote @ ('"' | '\'')) => quote,
+        _ => return Err(self.error()),
+      };
+      let len = rest[1..].find(quote).ok_or_else(|| self.error())?;
+      let value = self.decode(&rest[1..=len])?;
+      self.pos = self.input.len() - rest.len() + len + 2;
+
+      if name == "xmlns" {
+        declared.insert(String::new(), value);
+      } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+        declared.insert(prefix.to_string(), value);
+      } else {
+        raw_attrs.push((name, value));
+      }
+    };
+    self.scopes.push(declared);
+
+    let (ns, local) = self.resolve(&qname, false, line, column)?;
+    let mut attrs = Vec::new();
+    for (name, value) in raw_attrs {
+      let (ns, local) = self.resolve(&name, true, line, column)?;
+      attrs.push(XmlAttr { ns, local, value });
+    }
+
+    let mut el = XmlElement {
+      ns,
+      local,
+      attrs,
+      children: Vec::new(),
+      inner: String::new(),
+      line,
+      column,
+    };
+    if !empty {
+      let start = self.pos;
+      let end = self.content(&mut el, &qname)?;
+      el.inner = self.input[start..end].to_string();
+    }
+    self.scopes.pop();
+    Ok(el)
+  }
+
+  /// Reads the content of `el` up to its end tag, returning where the end
+  /// tag starts.
+  fn content(&mut self, el: &mut XmlElement, qname: &str) -> Result<usize> {
+    loop {
+      let rest = self.rest();
+      if rest.is_empty() {
+        return Err(self.error());
+      } else if rest.starts_with("<!--") {
+        self.skip_past("-->")?;
+      } else if rest.starts_with("<?") {
+        self.skip_past("?>")?;
+      } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+        let len = cdata.find("]]>").ok_or_else(|| self.error())?;
+        el.children.push(XmlContent::Text(cdata[..len].to_string()));
+        self.pos += "<![CDATA[".len() + len + "]]>".len();
+      } else if let Some(end_tag) = rest.strip_prefix("</") {
+        let start = self.pos;
+        let len = end_tag.find('>').ok_or_else(|| self.error())?;
+        if end_tag[..len].trim_end() != qname {
+          return Err(self.error());
+        }
+        self.pos += 2 + len + 1;
+        return Ok(start);
+      } else if rest.starts_with('<') {
+        let child = self.element()?;
+        el.children.push(XmlContent::Element(child));
+      } else {
+        let len = rest.find('<').unwrap_or(rest.len());
+        let text = self.decode(&rest[..len])?;
+        el.children.push(XmlContent::Text(text));
+        self.pos += len;
+      }
+    }
+  }
+
+  fn name(&mut self) -> Result<String> {
+    let rest = self.rest();
+    let len = rest
+      .find(|c: char| c.is_whitespace() || matches!(c, '=' | '>' | '/'))
+      .unwrap_or(rest.len());
+    if len == 0 {
+      return Err(self.error());
+    }
+    self.pos += len;
+    Ok(rest[..len].to_string())
+  }
+
+  /// Resolves a qualified name into its namespace & local name. Unprefixed
+  /// attributes have no namespace.
+  fn resolve(
+    &self,
+    qname: &str,
+    attribute: bool,
+    line: usize,
+    column: usize,
+  ) -> Result<(String, String)> {
+    let (prefix, local) = match qname.split_once(':') {
+      Some((prefix, local)) => (prefix, local),
+      None if attribute => return Ok((String::new(), qname.to_string())),
+      None => ("", qname),
+    };
+    if prefix == "xml" {
+      return Ok((XML.to_string(), local.to_string()));
+    }
+    let ns = self.scopes.iter().rev().find_map(|scope| scope.get(prefix));
+    match ns {
+      Some(ns) => Ok((ns.clone(), local.to_string())),
+      None if prefix.is_empty() => Ok((String::new(), local.to_string())),
+      None => Err(Error::syntax(ErrorCode::InvalidRdfXml, line, column)),
+    }
+  }
+
+  /// Replaces character & entity references.
+  fn decode(&self, text: &str) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+      out.push_str(&rest[..start]);
+      let len = rest[start..].find(';').ok_or_else(|| self.error())?;
+      let name = &rest[start + 1..start + len];
+      match name {
+        "amp" => out.push('&'),
+        "lt" => out.push('<'),
+        "gt" => out.push('>'),
+        "quot" => out.push('"'),
+        "apos" => out.push('\''),
+        _ => {
+          if let Some(code) = name.strip_prefix('#') {
+            let code = match code.strip_prefix('x') {
+              Some(hex) => u32::from_str_radix(hex, 16).ok(),
+              None => code.parse().ok(),
+            };
+            out
+              .push(code.and_then(char::from_u32).ok_or_else(|| self.error())?);
+          } else {
+            out.push_str(self.entities.get(name).ok_or_else(|| self.error())?);
+          }
+        }
+      }
+      rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+  }
+
+  /// Returns the one-based line & column of the current position.
+  fn position(&self) -> (usize, usize) {
+    let before = &self.input[..self.pos];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |idx| idx + 1) + 1;
+    (line, column)
+  }
+
+  #[cold]
+  fn error(&self) -> Error {
+    let (line, column) = self.position();
+    Error::syntax(ErrorCode::InvalidRdfXml, line, column)
+  }
+}
@@ -0,0 +1,194 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+  fs::{self, File},
+  io::{BufWriter, Write},
+  path::Path,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+  datastore::{blob::to_hex, json},
+  dtype::DType,
+  error::Error,
+  formats::ntriples::statements,
+  graph::KnowledgeGraph,
+  Result,
+};
+
+/// Name of the manifest written next to the shards.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// `ShardedExport` splits an N-Triples export into a fixed number of files
+/// so distributed loaders (e.g. Spark) can consume them in parallel.
+///
+/// Every statement is routed to shard `sha256(subject) mod shards`, so all
+/// statements about a subject land in the same file and the assignment is
+/// stable across runs & platforms. Within a shard, statements are sorted
+/// lexicographically. A `manifest.json` lists every shard with its triple
+/// count, size & SHA-256 checksum.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::ShardedExport;
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// for name in ["John", "Jane", "Joe", "Jill"] {
+///   graph.insert(
+///     Node::Http(format!("https://example.com/{}", name)),
+///     Predicate::Literal("https://schema.org/name".to_string()),
+///     Node::Literal(name.into()),
+///   );
+/// }
+///
+/// let dir = std::env::temp_dir().join("sage-shard-doctest");
+/// let manifest = ShardedExport::new(&graph, 3).write_to_dir(&dir).unwrap();
+///
+/// assert_eq!(manifest.files.len(), 3);
+/// assert_eq!(manifest.triples, 4);
+/// assert!(dir.join("part-00000.nt").exists());
+/// assert!(dir.join("manifest.json").exists());
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub struct ShardedExport<'a> {
+  graph: &'a KnowledgeGraph,
+  shards: usize,
+  prefix: String,
+}
+
+/// `Manifest` describes the files written by a `ShardedExport`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Manifest {
+  /// Total number of statements across all shards.
+  pub triples: usize,
+  /// Every shard, in shard order.
+  pub files: Vec<ShardFile>,
+}
+
+/// `ShardFile` is a single shard listed in a `Manifest`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShardFile {
+  /// File name relative to the export directory.
+  pub path: String,
+  /// Number of statements in the file.
+  pub triples: usize,
+  /// Size of the file in bytes.
+  pub bytes: u64,
+  /// Lower case, hex encoded SHA-256 of the file content.
+  pub sha256: String,
+}
+
+impl<'a> ShardedExport<'a> {
+  /// Creates an exporter splitting `graph` into `shards` files. At least one
+  /// shard is always written.
+  pub fn new(graph: &'a KnowledgeGraph, shards: usize) -> ShardedExport<'a> {
+    ShardedExport {
+      graph,
+      shards: shards.max(1),
+      prefix: "part".to_string(),
+    }
+  }
+
+  /// Sets the shard file name prefix. Defaults to `"part"`, i.e.
+  /// `part-00000.nt`, `part-00001.nt`, ...
+  pub fn prefix(mut self, prefix: &str) -> Self {
+    self.prefix = prefix.to_string();
+    self
+  }
+
+  /// Returns the shard `subject` is routed to.
+  fn shard_of(&self, subject: &str) -> usize {
+    let digest = Sha256::digest(subject.as_bytes());
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(bytes) % self.shards as u64) as usize
+  }
+
+  /// Writes every shard and the manifest into `dir`, creating it if needed.
+  pub fn write_to_dir<P: AsRef<Path>>(&self, dir: P) -> Result<Manifest> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir).map_err(Error::io)?;
+
+    let mut shards = vec![Vec::new(); self.shards];
+    for statement in statements(self.graph) {
+      shards[self.shard_of(&statement.subject)].push(statement.line);
+    }
+
+    let mut manifest = Manifest {
+      triples: 0,
+      files: Vec::with_capacity(self.shards),
+    };
+    for (idx, mut lines) in shards.into_iter().enumerate() {
+      lines.sort_unstable();
+
+      let path = format!("{}-{:05}.nt", self.prefix, idx);
+      let mut hasher = Sha256::new();
+      let mut bytes = 0;
+      let mut writer =
+        BufWriter::new(File::create(dir.join(&path)).map_err(Error::io)?);
+      for line in &lines {
+        for chunk in [line.as_bytes(), b"\n"] {
+          hasher.update(chunk);
+          writer.write_all(chunk).map_err(Error::io)?;
+          bytes += chunk.len() as u64;
+        }
+      }
+      writer.flush().map_err(Error::io)?;
+
+      manifest.triples += lines.len();
+      manifest.files.push(ShardFile {
+        path,
+        triples: lines.len(),
+        bytes,
+        sha256: to_hex(&hasher.finalize()),
+      });
+    }
+
+    let file = File::create(dir.join(MANIFEST_FILE)).map_err(Error::io)?;
+    json::to_writer_pretty(file, &manifest.to_dtype())?;
+    Ok(manifest)
+  }
+}
+
+impl Manifest {
+  /// Returns the manifest document as written to `manifest.json`.
+  pub fn to_dtype(&self) -> DType {
+    let files: Vec<DType> = self
+      .files
+      .iter()
+      .map(|file| {
+        crate::json!({
+          "path": file.path,
+          "triples": file.triples,
+          "bytes": file.bytes,
+          "sha256": file.sha256,
+        })
+      })
+      .collect();
+
+    crate::json!({
+      "version": 1,
+      "format": "application/n-triples",
+      "partitioning": "sha256(subject) mod shards",
+      "ordering": "lexicographic",
+      "shards": self.files.len(),
+      "triples": self.triples,
+      "files": files,
+    })
+  }
+}
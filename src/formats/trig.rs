@@ -0,0 +1,242 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeSet, fmt, io};
+
+use crate::{
+  error::Error,
+  formats::{
+    nquads::stream_statements,
+    ntriples::{statements, Statement},
+    turtle::{compact, prefixes, subjects},
+  },
+  graph::{KnowledgeGraph, Triple},
+  vocab::Namespaces,
+  Result,
+};
+
+/// `TriG` exports a `KnowledgeGraph` as [TriG]: Turtle with a block per
+/// named graph, which is the source of the statements' `Provenance`.
+///
+/// Statements without a source (the default graph) come first, outside of
+/// any block; named graphs follow in order of first appearance. Within a
+/// graph, statements are written like `Turtle` does.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::TriG;
+/// use sage::graph::{KnowledgeGraph, Node, Predicate, Provenance};
+/// use sage::vocab::Namespaces;
+///
+/// let ada = Node::Http("https://example.com/Ada".to_string());
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert_with_provenance(
+///   ada.clone(),
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal("Ada".into()),
+///   Provenance::new("https://example.com/people"),
+/// );
+/// graph.insert(
+///   ada,
+///   Predicate::Literal("https://schema.org/knows".to_string()),
+///   Node::Http("https://example.com/Charles".to_string()),
+/// );
+///
+/// let mut ns = Namespaces::default();
+/// ns.bind("ex", "https://example.com/");
+///
+/// assert_eq!(
+///   TriG::new(&graph).namespaces(&ns).to_string(),
+///   "@prefix ex: <https://example.com/> .\n\
+///    @prefix schema: <https://schema.org/> .\n\
+///    \n\
+///    ex:Ada schema:knows ex:Charles .\n\
+///    \n\
+///    ex:people {\n  ex:Ada schema:name \"Ada\" .\n}\n"
+/// );
+/// ```
+///
+/// [TriG]: https://www.w3.org/TR/trig/
+pub struct TriG<'a> {
+  graph: &'a KnowledgeGraph,
+  namespaces: Namespaces,
+}
+
+impl<'a> TriG<'a> {
+  /// Creates a TriG exporter for `graph` using the default `rdf`, `rdfs` &
+  /// `schema` prefixes.
+  pub fn new(graph: &'a KnowledgeGraph) -> TriG<'a> {
+    TriG {
+      graph,
+      namespaces: Namespaces::default(),
+    }
+  }
+
+  /// Compacts IRIs with the prefixes of `namespaces` instead.
+  pub fn namespaces(mut self, namespaces: &Namespaces) -> Self {
+    self.namespaces = namespaces.clone();
+    self
+  }
+
+  /// Writes the TriG document into `writer`.
+  pub fn to_writer<W: io::Write>(&self, mut writer: W) -> Result<()> {
+    writer
+      .write_all(self.to_string().as_bytes())
+      .map_err(Error::io)
+  }
+}
+
+impl<'a> fmt::Display for TriG<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let statements = statements(self.graph);
+    let mut graphs: Vec<(Option<&str>, Vec<&Statement>)> = vec![(None, vec![])];
+    for statement in &statements {
+      let name = statement.graph.as_deref();
+      match graphs.iter_mut().find(|(graph, _)| *graph == name) {
+        Some((_, statements)) => statements.push(statement),
+        None => graphs.push((name, vec![statement])),
+      }
+    }
+
+    let mut used = BTreeSet::new();
+    let mut blocks = Vec::new();
+    for (name, statements) in graphs {
+      let block = match name {
+        None if statements.is_empty() => continue,
+        None => subjects(statements, &self.namespaces, "", &mut used),
+        Some(name) => format!(
+          "{} {{\n{}}}\n",
+          compact(&self.namespaces, name, &mut used),
+          subjects(statements, &self.namespaces, "  ", &mut used)
+        ),
+      };
+      blocks.push(block);
+    }
+
+    prefixes(f, &self.namespaces, &used)?;
+    f.write_str(&blocks.join("\n"))
+  }
+}
+
+/// `TriGWriter` writes triples as TriG while they're produced, without
+/// holding the whole dataset in a `KnowledgeGraph`.
+///
+/// Every prefix of the registry is declared up front, and a graph block is
+/// opened whenever the named graph changes, so triples should arrive
+/// grouped by source to keep the output small. Statements are written one
+/// per line.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::TriGWriter;
+/// use sage::graph::{Node, Predicate, Provenance, Triple};
+/// use sage::vocab::Namespaces;
+///
+/// let mut writer = TriGWriter::new(Vec::new(), &Namespaces::new());
+/// for name in ["Ada", "Charles"] {
+///   let triple = Triple::from_nodes(
+///     Node::Http(format!("https://example.com/{}", name)),
+///     Predicate::Literal("https://schema.org/name".to_string()),
+///     Node::Literal(name.into()),
+///   )
+///   .with_provenance(Provenance::new("https://example.com/people"));
+///   writer.write(&triple).unwrap();
+/// }
+///
+/// assert_eq!(
+///   String::from_utf8(writer.finish().unwrap()).unwrap(),
+///   "<https://example.com/people> {\n  \
+///    <https://example.com/Ada> <https://schema.org/name> \"Ada\" .\n  \
+///    <https://example.com/Charles> <https://schema.org/name> \"Charles\" .\n\
+///    }\n"
+/// );
+/// ```
+pub struct TriGWriter<W: io::Write> {
+  writer: W,
+  namespaces: Namespaces,
+  blanks: usize,
+  /// Whether the prefixes have been declared.
+  started: bool,
+  /// Named graph of the open block, if any.
+  graph: Option<String>,
+}
+
+impl<W: io::Write> TriGWriter<W> {
+  /// Creates a streaming TriG writer into `writer`, compacting IRIs with
+  /// the prefixes of `namespaces`.
+  pub fn new(writer: W, namespaces: &Namespaces) -> TriGWriter<W> {
+    TriGWriter {
+      writer,
+      namespaces: namespaces.clone(),
+      blanks: 0,
+      started: false,
+      graph: None,
+    }
+  }
+
+  /// Writes the statements of `triple`.
+  pub fn write(&mut self, triple: &Triple) -> Result<()> {
+    if !self.started {
+      self.started = true;
+      let mut header = String::new();
+      let all = self.namespaces.prefixes().into_keys().collect();
+      let _ = prefixes(&mut header, &self.namespaces, &all);
+      self
+        .writer
+        .write_all(header.as_bytes())
+        .map_err(Error::io)?;
+    }
+
+    let mut unused = BTreeSet::new();
+    for statement in stream_statements(triple, &mut self.blanks) {
+      if statement.graph != self.graph {
+        self.close()?;
+        if let Some(name) = &statement.graph {
+          let name = compact(&self.namespaces, name, &mut unused);
+          writeln!(self.writer, "{} {{", name).map_err(Error::io)?;
+        }
+        self.graph = statement.graph.clone();
+      }
+
+      let indent = if self.graph.is_some() { "  " } else { "" };
+      writeln!(
+        self.writer,
+        "{}{} {} {} .",
+        indent,
+        compact(&self.namespaces, &statement.subject, &mut unused),
+        compact(&self.namespaces, &statement.predicate, &mut unused),
+        compact(&self.namespaces, &statement.object, &mut unused),
+      )
+      .map_err(Error::io)?;
+    }
+    Ok(())
+  }
+
+  /// Closes the open graph block, then flushes & returns the underlying
+  /// writer.
+  pub fn finish(mut self) -> Result<W> {
+    self.close()?;
+    self.writer.flush().map_err(Error::io)?;
+    Ok(self.writer)
+  }
+
+  fn close(&mut self) -> Result<()> {
+    if self.graph.take().is_some() {
+      self.writer.write_all(b"}\n").map_err(Error::io)?;
+    }
+    Ok(())
+  }
+}
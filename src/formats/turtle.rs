@@ -0,0 +1,253 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+  collections::{BTreeSet, HashMap},
+  fmt::{self, Write as _},
+  io::{self, Read as _},
+};
+
+use crate::{
+  error::Error,
+  formats::ntriples::{statements, Statement},
+  graph::{KnowledgeGraph, Predicate},
+  query::Parser,
+  vocab::Namespaces,
+  Result,
+};
+
+/// Full IRI of `rdf:type`, written as `a`.
+const RDF_TYPE: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>";
+
+/// `Turtle` exports a `KnowledgeGraph` as [Turtle], writing IRIs as
+/// `prefix:name` wherever a namespace of the registry applies.
+///
+/// Statements are grouped by subject (in order of first appearance) and
+/// only the prefixes used are declared. Literals are written like
+/// `NTriples` does.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::Turtle;
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::vocab::Namespaces;
+///
+/// let ada = Node::Http("https://example.com/Ada".to_string());
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   ada.clone(),
+///   Predicate::Literal(
+///     "http://www.w3.org/1999/02/22-rdf-syntax-ns#type".to_string(),
+///   ),
+///   Node::Http("https://schema.org/Person".to_string()),
+/// );
+/// graph.insert(
+///   ada,
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal("Ada".into()),
+/// );
+///
+/// let mut ns = Namespaces::default();
+/// ns.bind("ex", "https://example.com/");
+///
+/// assert_eq!(
+///   Turtle::new(&graph).namespaces(&ns).to_string(),
+///   "@prefix ex: <https://example.com/> .\n\
+///    @prefix schema: <https://schema.org/> .\n\
+///    \n\
+///    ex:Ada a schema:Person ;\n    schema:name \"Ada\" .\n"
+/// );
+/// ```
+///
+/// [Turtle]: https://www.w3.org/TR/turtle/
+pub struct Turtle<'a> {
+  graph: &'a KnowledgeGraph,
+  namespaces: Namespaces,
+}
+
+impl<'a> Turtle<'a> {
+  /// Creates a Turtle exporter for `graph` using the default `rdf`,
+  /// `rdfs` & `schema` prefixes.
+  pub fn new(graph: &'a KnowledgeGraph) -> Turtle<'a> {
+    Turtle {
+      graph,
+      namespaces: Namespaces::default(),
+    }
+  }
+
+  /// Compacts IRIs with the prefixes of `namespaces` instead.
+  pub fn namespaces(mut self, namespaces: &Namespaces) -> Self {
+    self.namespaces = namespaces.clone();
+    self
+  }
+
+  /// Reads a Turtle document into a new `KnowledgeGraph`.
+  ///
+  /// Prefixed names expand against `@prefix`/`PREFIX` declarations along
+  /// with the default `rdf`, `rdfs`, `schema` & `xsd` prefixes. Literals are
+  /// read like `NTriples::parse` does; numbers & booleans may be written
+  /// bare. Blank node property lists (`[ ... ]`), collections, `@base` &
+  /// relative IRIs aren't supported.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::formats::Turtle;
+  ///
+  /// let graph = Turtle::parse(
+  ///   r#"@prefix ex: <https://example.com/> .
+  ///
+  ///      ex:Ada a schema:Person ;
+  ///        schema:name "Ada"@en , """Augusta
+  ///          Ada""" ;
+  ///        schema:birthDate "1815-12-10"^^xsd:date ;
+  ///        ex:children 3 ."#,
+  /// )
+  /// .unwrap();
+  /// assert_eq!(graph.len(), 5);
+  ///
+  /// let err = Turtle::parse("schema:Ada schema:name ?name .").err().unwrap();
+  /// assert_eq!((err.line(), err.column()), (1, 1));
+  /// ```
+  pub fn parse(input: &str) -> Result<KnowledgeGraph> {
+    let mut graph = KnowledgeGraph::new();
+    for (subject, predicate, object) in Parser::turtle(input).document()? {
+      graph.insert(subject, Predicate::Literal(predicate), object);
+    }
+    Ok(graph)
+  }
+
+  /// Reads a Turtle document from `reader` into a new `KnowledgeGraph`.
+  ///
+  /// With the `compression` feature, gzip & Zstandard compressed documents
+  /// are decompressed on the fly.
+  pub fn from_reader<R: io::Read>(reader: R) -> Result<KnowledgeGraph> {
+    #[cfg(feature = "compression")]
+    let reader = crate::compression::Decoder::new(io::BufReader::new(reader))
+      .map_err(Error::io)?;
+    let mut input = String::new();
+    io::BufReader::new(reader)
+      .read_to_string(&mut input)
+      .map_err(Error::io)?;
+    Turtle::parse(&input)
+  }
+
+  /// Writes the Turtle document into `writer`.
+  pub fn to_writer<W: io::Write>(&self, mut writer: W) -> Result<()> {
+    writer
+      .write_all(self.to_string().as_bytes())
+      .map_err(Error::io)
+  }
+}
+
+impl<'a> fmt::Display for Turtle<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let statements = statements(self.graph);
+    let mut used = BTreeSet::new();
+    let body = subjects(&statements, &self.namespaces, "", &mut used);
+    prefixes(f, &self.namespaces, &used)?;
+    f.write_str(&body)
+  }
+}
+
+/// Writes `statements` grouped by subject (in order of first appearance),
+/// each line prefixed with `indent`, recording the prefixes used.
+pub(crate) fn subjects<'s>(
+  statements: impl IntoIterator<Item = &'s Statement>,
+  namespaces: &Namespaces,
+  indent: &str,
+  used: &mut BTreeSet<String>,
+) -> String {
+  let mut index: HashMap<&str, usize> = HashMap::new();
+  let mut subjects: Vec<Vec<&Statement>> = Vec::new();
+  for statement in statements {
+    let i = *index.entry(&statement.subject).or_insert_with(|| {
+      subjects.push(Vec::new());
+      subjects.len() - 1
+    });
+    subjects[i].push(statement);
+  }
+
+  let mut body = String::new();
+  for statements in subjects {
+    let subject = compact(namespaces, &statements[0].subject, used);
+    let _ = write!(body, "{}{}", indent, subject);
+    let mut previous: Option<&str> = None;
+    for statement in statements {
+      let object = compact(namespaces, &statement.object, used);
+      if previous == Some(statement.predicate.as_str()) {
+        let _ = write!(body, ", {}", object);
+        continue;
+      }
+      let predicate = if statement.predicate == RDF_TYPE {
+        "a".to_string()
+      } else {
+        compact(namespaces, &statement.predicate, used)
+      };
+      let separator = match previous {
+        Some(_) => format!(" ;\n{}   ", indent),
+        None => String::new(),
+      };
+      let _ = write!(body, "{} {} {}", separator, predicate, object);
+      previous = Some(&statement.predicate);
+    }
+    body.push_str(" .\n");
+  }
+  body
+}
+
+/// Writes the `@prefix` declarations of the `used` prefixes, followed by a
+/// blank line if there are any.
+pub(crate) fn prefixes(
+  f: &mut dyn fmt::Write,
+  namespaces: &Namespaces,
+  used: &BTreeSet<String>,
+) -> fmt::Result {
+  let prefixes = namespaces.prefixes();
+  for prefix in used {
+    writeln!(f, "@prefix {}: <{}> .", prefix, prefixes[prefix])?;
+  }
+  if !used.is_empty() {
+    f.write_str("\n")?;
+  }
+  Ok(())
+}
+
+/// Compacts an `<iri>` term (or the datatype of a typed literal) with
+/// `namespaces`, recording the prefix used.
+pub(crate) fn compact(
+  namespaces: &Namespaces,
+  term: &str,
+  used: &mut BTreeSet<String>,
+) -> String {
+  if let Some((lexical, datatype)) = term.rsplit_once("^^") {
+    if lexical.ends_with('"') {
+      return format!("{}^^{}", lexical, compact(namespaces, datatype, used));
+    }
+  }
+  let iri = match term.strip_prefix('<').and_then(|t| t.strip_suffix('>')) {
+    Some(iri) => iri,
+    None => return term.to_string(),
+  };
+  match namespaces.compact(iri) {
+    Some(curie) => {
+      if let Some((prefix, _)) = curie.split_once(':') {
+        used.insert(prefix.to_string());
+      }
+      curie
+    }
+    None => term.to_string(),
+  }
+}
@@ -0,0 +1,129 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::formats::viz` exports a `KnowledgeGraph` into formats understood
+//! by graph visualization & analysis tools.
+//!
+//! - [`GraphMl`] writes [GraphML] documents which can be opened in Gephi,
+//!   yEd or loaded with NetworkX.
+//! - [`Cytoscape`] writes [Cytoscape.js] JSON elements which can be imported
+//!   into Cytoscape desktop or rendered in the browser.
+//!
+//! [GraphML]: http://graphml.graphdrawing.org/
+//! [Cytoscape.js]: https://js.cytoscape.org/#notation/elements-json
+
+mod cytoscape;
+mod graphml;
+
+use std::collections::HashMap;
+
+use crate::graph::{KnowledgeGraph, Node};
+
+pub use cytoscape::Cytoscape;
+pub use graphml::GraphMl;
+pub(crate) use graphml::XmlEscape;
+
+/// A node as seen by the visualization exporters.
+struct VizNode {
+  id: String,
+  label: String,
+  kind: &'static str,
+}
+
+/// A directed edge as seen by the visualization exporters.
+struct VizEdge {
+  id: String,
+  source: String,
+  target: String,
+  label: String,
+}
+
+/// Flattened view of a `KnowledgeGraph` where every distinct node gets a
+/// stable, document local ID (`n0`, `n1`, ...) in order of first appearance.
+struct VizGraph {
+  nodes: Vec<VizNode>,
+  edges: Vec<VizEdge>,
+}
+
+impl VizGraph {
+  fn new(graph: &KnowledgeGraph) -> VizGraph {
+    let mut viz = VizGraph {
+      nodes: Vec::new(),
+      edges: Vec::new(),
+    };
+    let mut ids: HashMap<(&'static str, String), String> = HashMap::new();
+
+    for triple in graph.triples() {
+      let sources = viz.node_ids(triple.source(), &mut ids);
+      let targets = viz.node_ids(triple.destination(), &mut ids);
+      let label = triple.predicate().to_string();
+
+      for source in &sources {
+        for target in &targets {
+          viz.edges.push(VizEdge {
+            id: format!("e{}", viz.edges.len()),
+            source: source.clone(),
+            target: target.clone(),
+            label: label.clone(),
+          });
+        }
+      }
+    }
+
+    viz
+  }
+
+  /// Returns the IDs for `node`, registering it if it hasn't been seen.
+  /// `Node::Multiple` expands into one ID per contained node and every
+  /// anonymous `Node::Blank` is treated as a distinct node.
+  fn node_ids(
+    &mut self,
+    node: &Node,
+    ids: &mut HashMap<(&'static str, String), String>,
+  ) -> Vec<String> {
+    let kind = match node {
+      Node::Multiple(nodes) => {
+        return nodes.iter().flat_map(|n| self.node_ids(n, ids)).collect();
+      }
+      Node::Blank | Node::BlankId(_) => "blank",
+      Node::Schema => "schema",
+      Node::Http(_) => "http",
+      Node::Literal(_) => "literal",
+    };
+    let label = node.to_string();
+
+    let anonymous = matches!(node, Node::Blank);
+    let id = if anonymous {
+      None
+    } else {
+      ids.get(&(kind, label.clone())).cloned()
+    };
+
+    match id {
+      Some(id) => vec![id],
+      None => {
+        let id = format!("n{}", self.nodes.len());
+        if !anonymous {
+          ids.insert((kind, label.clone()), id.clone());
+        }
+        self.nodes.push(VizNode {
+          id: id.clone(),
+          label,
+          kind,
+        });
+        vec![id]
+      }
+    }
+  }
+}
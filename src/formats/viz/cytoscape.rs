@@ -0,0 +1,114 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+
+use crate::{
+  datastore::json::to_writer,
+  dtype::DType,
+  formats::{estimate::ByteCounter, viz::VizGraph, ExportEstimate},
+  graph::KnowledgeGraph,
+  Result,
+};
+
+/// `Cytoscape` exports a `KnowledgeGraph` as [Cytoscape.js] elements JSON.
+///
+/// The output has the shape
+/// `{"elements": {"nodes": [{"data": {..}}], "edges": [{"data": {..}}]}}`.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::viz::Cytoscape;
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::json;
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   Node::Http("https://example.com/John".to_string()),
+///   Predicate::Literal("name".to_string()),
+///   Node::Literal("John".into()),
+/// );
+///
+/// let elements = Cytoscape::new(&graph).to_dtype();
+/// assert_eq!(
+///   elements["elements"]["edges"][0],
+///   json!({
+///     "data": { "id": "e0", "source": "n0", "target": "n1", "label": "name" }
+///   })
+/// );
+/// assert_eq!(elements["elements"]["nodes"][1]["data"]["kind"], "literal");
+/// ```
+///
+/// [Cytoscape.js]: https://js.cytoscape.org/#notation/elements-json
+pub struct Cytoscape<'a> {
+  graph: &'a KnowledgeGraph,
+}
+
+impl<'a> Cytoscape<'a> {
+  /// Creates a Cytoscape.js exporter for `graph`.
+  pub fn new(graph: &'a KnowledgeGraph) -> Cytoscape<'a> {
+    Cytoscape { graph }
+  }
+
+  /// Returns the Cytoscape.js elements document as a `DType`.
+  pub fn to_dtype(&self) -> DType {
+    let viz = VizGraph::new(self.graph);
+
+    let nodes: Vec<DType> = viz
+      .nodes
+      .into_iter()
+      .map(|node| {
+        crate::json!({
+          "data": { "id": node.id, "label": node.label, "kind": node.kind }
+        })
+      })
+      .collect();
+
+    let edges: Vec<DType> = viz
+      .edges
+      .into_iter()
+      .map(|edge| {
+        crate::json!({
+          "data": {
+            "id": edge.id,
+            "source": edge.source,
+            "target": edge.target,
+            "label": edge.label,
+          }
+        })
+      })
+      .collect();
+
+    crate::json!({ "elements": { "nodes": nodes, "edges": edges } })
+  }
+
+  /// Writes the Cytoscape.js elements JSON into `writer`.
+  pub fn to_writer<W: io::Write>(&self, writer: W) -> Result<()> {
+    to_writer(writer, &self.to_dtype())
+  }
+
+  /// Dry-runs the export and reports its size without writing anything.
+  pub fn estimate(&self) -> Result<ExportEstimate> {
+    let viz = VizGraph::new(self.graph);
+    let mut counter = ByteCounter::default();
+    self.to_writer(&mut counter)?;
+    Ok(ExportEstimate::new(
+      self.graph,
+      viz.nodes.len(),
+      viz.edges.len(),
+      counter.bytes,
+    ))
+  }
+}
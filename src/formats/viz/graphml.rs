@@ -0,0 +1,148 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, io};
+
+use crate::{
+  error::Error,
+  formats::{estimate::ByteCounter, viz::VizGraph, ExportEstimate},
+  graph::KnowledgeGraph,
+  Result,
+};
+
+/// `GraphMl` exports a `KnowledgeGraph` as a [GraphML] document.
+///
+/// Every node carries a `label` & `kind` attribute and every edge carries
+/// the predicate as its `label` attribute.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::viz::GraphMl;
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   Node::Http("https://example.com/John".to_string()),
+///   Predicate::Literal("knows".to_string()),
+///   Node::Http("https://example.com/Jane".to_string()),
+/// );
+///
+/// let xml = GraphMl::new(&graph).to_string();
+/// assert!(xml.contains(r#"<node id="n0">"#));
+/// assert!(xml.contains(r#"<edge id="e0" source="n0" target="n1">"#));
+/// ```
+///
+/// [GraphML]: http://graphml.graphdrawing.org/
+pub struct GraphMl<'a> {
+  graph: &'a KnowledgeGraph,
+}
+
+impl<'a> GraphMl<'a> {
+  /// Creates a GraphML exporter for `graph`.
+  pub fn new(graph: &'a KnowledgeGraph) -> GraphMl<'a> {
+    GraphMl { graph }
+  }
+
+  /// Writes the GraphML document into `writer`.
+  pub fn to_writer<W: io::Write>(&self, mut writer: W) -> Result<()> {
+    write!(writer, "{}", self).map_err(Error::io)
+  }
+
+  /// Dry-runs the export and reports its size without writing anything.
+  pub fn estimate(&self) -> Result<ExportEstimate> {
+    let viz = VizGraph::new(self.graph);
+    let mut counter = ByteCounter::default();
+    self.to_writer(&mut counter)?;
+    Ok(ExportEstimate::new(
+      self.graph,
+      viz.nodes.len(),
+      viz.edges.len(),
+      counter.bytes,
+    ))
+  }
+}
+
+impl<'a> fmt::Display for GraphMl<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let viz = VizGraph::new(self.graph);
+
+    f.write_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+    f.write_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n")?;
+    f.write_str(
+      "  <key id=\"label\" for=\"node\" attr.name=\"label\" \
+       attr.type=\"string\"/>\n",
+    )?;
+    f.write_str(
+      "  <key id=\"kind\" for=\"node\" attr.name=\"kind\" \
+       attr.type=\"string\"/>\n",
+    )?;
+    f.write_str(
+      "  <key id=\"predicate\" for=\"edge\" attr.name=\"label\" \
+       attr.type=\"string\"/>\n",
+    )?;
+    f.write_str("  <graph id=\"G\" edgedefault=\"directed\">\n")?;
+
+    for node in &viz.nodes {
+      writeln!(f, "    <node id=\"{}\">", node.id)?;
+      writeln!(
+        f,
+        "      <data key=\"label\">{}</data>",
+        XmlEscape(&node.label)
+      )?;
+      writeln!(f, "      <data key=\"kind\">{}</data>", node.kind)?;
+      f.write_str("    </node>\n")?;
+    }
+
+    for edge in &viz.edges {
+      writeln!(
+        f,
+        "    <edge id=\"{}\" source=\"{}\" target=\"{}\">",
+        edge.id, edge.source, edge.target
+      )?;
+      writeln!(
+        f,
+        "      <data key=\"predicate\">{}</data>",
+        XmlEscape(&edge.label)
+      )?;
+      f.write_str("    </edge>\n")?;
+    }
+
+    f.write_str("  </graph>\n")?;
+    f.write_str("</graphml>\n")
+  }
+}
+
+/// Escapes XML special characters when displayed.
+pub(crate) struct XmlEscape<'a>(pub(crate) &'a str);
+
+impl<'a> fmt::Display for XmlEscape<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let mut start = 0;
+    for (i, c) in self.0.char_indices() {
+      let escaped = match c {
+        '&' => "&amp;",
+        '<' => "&lt;",
+        '>' => "&gt;",
+        '"' => "&quot;",
+        '\'' => "&apos;",
+        _ => continue,
+      };
+      f.write_str(&self.0[start..i])?;
+      f.write_str(escaped)?;
+      start = i + c.len_utf8();
+    }
+    f.write_str(&self.0[start..])
+  }
+}
@@ -0,0 +1,389 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeSet, io};
+
+use crate::{
+  datastore::json,
+  dtype::{DType, Map},
+  error::Error,
+  formats::{
+    ndjson::{flatten, node_id},
+    node_object,
+    nquads::stream_statements,
+    turtle::{compact, prefixes},
+  },
+  graph::{Entity, Triple},
+  vocab::Namespaces,
+  Result,
+};
+
+/// Full `<iri>` term of `rdf:type`.
+const RDF_TYPE: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>";
+
+/// `GraphFormat` is the serialization a `GraphWriter` produces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphFormat {
+  /// [Turtle](https://www.w3.org/TR/turtle/).
+  Turtle,
+  /// Flattened [JSON-LD](https://www.w3.org/TR/json-ld11/), one node
+  /// object per line under `@graph`.
+  JsonLd,
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `GraphWriter`.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// `GraphWriter` writes triples & entities as Turtle or JSON-LD while
+/// they're produced, for exports too large to hold in a `KnowledgeGraph`.
+///
+/// The prefix header (`@prefix` declarations, or the JSON-LD `@context`)
+/// declares every prefix of the registry and is written before the first
+/// statement. The body is then streamed: only the statements of the current
+/// subject are held back, to be grouped under it, so memory stays bounded
+/// whatever the size of the graph. Triples should arrive grouped by
+/// subject to keep the output small; a subject seen again later is written
+/// again, which is still valid. Named graphs aren't part of either format
+/// and are dropped, see `TriGWriter` to keep them.
+///
+/// Call `GraphWriter::finish` to close the document, dropping the writer
+/// leaves it incomplete. Wrap files in an `io::BufWriter`.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::formats::{GraphFormat, GraphWriter};
+/// use sage::graph::{Node, Predicate, Triple};
+/// use sage::vocab::Namespaces;
+/// use sage::{json, DType};
+///
+/// let mut ns = Namespaces::new();
+/// ns.bind("ex", "https://example.com/")
+///   .bind("schema", "https://schema.org/");
+///
+/// let triples = [
+///   ("Ada", "name", Node::Literal("Ada".into())),
+///   ("Ada", "knows", Node::Http("https://example.com/Charles".into())),
+///   ("Ada", "knows", Node::Http("https://example.com/Alan".into())),
+///   ("Alan", "name", Node::Literal("Alan".into())),
+/// ];
+/// let mut turtle = GraphWriter::new(Vec::new(), GraphFormat::Turtle)
+///   .namespaces(&ns);
+/// let mut jsonld = GraphWriter::new(Vec::new(), GraphFormat::JsonLd)
+///   .namespaces(&ns);
+/// for (subject, predicate, object) in triples {
+///   let triple = Triple::from_nodes(
+///     Node::Http(format!("https://example.com/{}", subject)),
+///     Predicate::Literal(format!("https://schema.org/{}", predicate)),
+///     object,
+///   );
+///   turtle.write(&triple).unwrap();
+///   jsonld.write(&triple).unwrap();
+/// }
+///
+/// assert_eq!(
+///   String::from_utf8(turtle.finish().unwrap()).unwrap(),
+///   "@prefix ex: <https://example.com/> .\n\
+///    @prefix schema: <https://schema.org/> .\n\
+///    \n\
+///    ex:Ada schema:name \"Ada\" ;\n    \
+///    schema:knows ex:Charles, ex:Alan .\n\
+///    ex:Alan schema:name \"Alan\" .\n"
+/// );
+/// let document = jsonld.finish().unwrap();
+/// assert_eq!(
+///   json::from_slice::<DType>(&document).unwrap(),
+///   json!({
+///     "@context": {
+///       "ex": "https://example.com/",
+///       "schema": "https://schema.org/"
+///     },
+///     "@graph": [
+///       {
+///         "@id": "ex:Ada",
+///         "schema:name": "Ada",
+///         "schema:knows": [{ "@id": "ex:Charles" }, { "@id": "ex:Alan" }]
+///       },
+///       { "@id": "ex:Alan", "schema:name": "Alan" }
+///     ]
+///   })
+/// );
+/// ```
+pub struct GraphWriter<W: io::Write> {
+  writer: W,
+  format: GraphFormat,
+  namespaces: Namespaces,
+  blanks: usize,
+  /// Whether the prefix header has been written.
+  started: bool,
+  /// Turtle: subject & predicate of the last statement, if it's still open.
+  open: Option<(String, String)>,
+  /// JSON-LD: node object of the current subject, not yet written.
+  node: Option<Map<String, DType>>,
+  /// JSON-LD: whether a node object has been written.
+  written: bool,
+}
+
+impl<W: io::Write> GraphWriter<W> {
+  /// Creates a streaming writer of `format` into `writer`, without any
+  /// prefixes.
+  pub fn new(writer: W, format: GraphFormat) -> GraphWriter<W> {
+    GraphWriter {
+      writer,
+      format,
+      namespaces: Namespaces::new(),
+      blanks: 0,
+      started: false,
+      open: None,
+      node: None,
+      written: false,
+    }
+  }
+
+  /// Declares the prefixes of `namespaces` and compacts IRIs with them.
+  pub fn namespaces(mut self, namespaces: &Namespaces) -> Self {
+    self.namespaces = namespaces.clone();
+    self
+  }
+
+  /// Writes the statements of `triple`.
+  pub fn write(&mut self, triple: &Triple) -> Result<()> {
+    self.start()?;
+    match self.format {
+      GraphFormat::Turtle => self.write_turtle(triple),
+      GraphFormat::JsonLd => self.write_jsonld(triple),
+    }
+  }
+
+  /// Writes the statements of every triple of `entity`.
+  pub fn write_entity(&mut self, entity: &Entity) -> Result<()> {
+    for triple in entity.triples() {
+      self.write(triple)?;
+    }
+    Ok(())
+  }
+
+  /// Writes the pending statements & closes the document, then flushes &
+  /// returns the underlying writer.
+  pub fn finish(mut self) -> Result<W> {
+    self.start()?;
+    match self.format {
+      GraphFormat::Turtle => {
+        if self.open.take().is_some() {
+          self.writer.write_all(b" .\n").map_err(Error::io)?;
+        }
+      }
+      GraphFormat::JsonLd => {
+        self.flush_node()?;
+        let end: &[u8] = if self.written { b"\n]}\n" } else { b"]}\n" };
+        self.writer.write_all(end).map_err(Error::io)?;
+      }
+    }
+    self.writer.flush().map_err(Error::io)?;
+    Ok(self.writer)
+  }
+
+  /// Writes the prefix header, once.
+  fn start(&mut self) -> Result<()> {
+    if self.started {
+      return Ok(());
+    }
+    self.started = true;
+
+    let bound = self.namespaces.prefixes();
+    let header = match self.format {
+      GraphFormat::Turtle => {
+        let mut header = String::new();
+        let all = bound.into_keys().collect();
+        let _ = prefixes(&mut header, &self.namespaces, &all);
+        header
+      }
+      GraphFormat::JsonLd if bound.is_empty() => "{\"@graph\":[".into(),
+      GraphFormat::JsonLd => {
+        let context: Map<String, DType> = bound
+          .into_iter()
+          .map(|(prefix, iri)| (prefix, DType::String(iri)))
+          .collect();
+        format!(
+          "{{\"@context\":{},\"@graph\":[",
+          json::to_string(&DType::Object(context))?
+        )
+      }
+    };
+    self.writer.write_all(header.as_bytes()).map_err(Error::io)
+  }
+
+  fn write_turtle(&mut self, triple: &Triple) -> Result<()> {
+    let mut unused = BTreeSet::new();
+    for statement in stream_statements(triple, &mut self.blanks) {
+      let object = compact(&self.namespaces, &statement.object, &mut unused);
+      let predicate = if statement.predicate == RDF_TYPE {
+        "a".to_string()
+      } else {
+        compact(&self.namespaces, &statement.predicate, &mut unused)
+      };
+
+      match &self.open {
+        Some((s, p))
+          if *s == statement.subject && *p == statement.predicate =>
+        {
+          write!(self.writer, ", {}", object)
+        }
+        Some((s, _)) if *s == statement.subject => {
+          write!(self.writer, " ;\n    {} {}", predicate, object)
+        }
+        open => {
+          if open.is_some() {
+            self.writer.write_all(b" .\n").map_err(Error::io)?;
+          }
+          let subject =
+            compact(&self.namespaces, &statement.subject, &mut unused);
+          write!(self.writer, "{} {} {}", subject, predicate, object)
+        }
+      }
+      .map_err(Error::io)?;
+      self.open = Some((statement.subject, statement.predicate));
+    }
+    Ok(())
+  }
+
+  fn write_jsonld(&mut self, triple: &Triple) -> Result<()> {
+    for source in flatten(triple.source()) {
+      let id = node_id(source, &mut self.blanks);
+      let mut node = node_object(id, &[triple], &mut self.blanks);
+      let node = match node {
+        DType::Object(ref mut node) => std::mem::take(node),
+        _ => continue,
+      };
+      match &mut self.node {
+        Some(current) if current.get("@id") == node.get("@id") => {
+          merge(current, node)
+        }
+        _ => {
+          self.flush_node()?;
+          self.node = Some(node);
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// Writes the node object of the current subject, if any.
+  fn flush_node(&mut self) -> Result<()> {
+    let node = match self.node.take() {
+      Some(node) => compact_node(&self.namespaces, node),
+      None => return Ok(()),
+    };
+    let separator: &[u8] = if self.written { b",\n" } else { b"\n" };
+    self.written = true;
+    self.writer.write_all(separator).map_err(Error::io)?;
+    json::to_writer(&mut self.writer, &DType::Object(node))
+  }
+}
+
+/// Adds the properties of `node` to those of `current`, for the same
+/// subject.
+fn merge(current: &mut Map<String, DType>, node: Map<String, DType>) {
+  for (key, mut value) in node {
+    if key == "@id" {
+      continue;
+    }
+    let values = match value {
+      DType::Array(ref mut values) => std::mem::take(&mut **values),
+      value => vec![value],
+    };
+    match current.get_mut(&key) {
+      Some(DType::Array(existing)) => existing.extend(values),
+      Some(existing) => {
+        let first = std::mem::replace(existing, DType::Null);
+        let mut all = vec![first];
+        all.extend(values);
+        *existing = DType::from(all);
+      }
+      None => {
+        current.insert(key, collapse(values));
+      }
+    }
+  }
+}
+
+/// A single value is written as is, several as an array.
+fn collapse(mut values: Vec<DType>) -> DType {
+  if values.len() == 1 {
+    values.remove(0)
+  } else {
+    DType::from(values)
+  }
+}
+
+/// Compacts the properties, `@id`s & `@type`s of a node object with the
+/// prefixes of `namespaces`.
+fn compact_node(
+  namespaces: &Namespaces,
+  node: Map<String, DType>,
+) -> Map<String, DType> {
+  node
+    .into_iter()
+    .map(|(key, value)| match key.as_str() {
+      "@id" | "@type" => (key, compact_iris(namespaces, value)),
+      _ => {
+        let key = namespaces.compact(&key).unwrap_or(key);
+        (key, compact_references(namespaces, value))
+      }
+    })
+    .collect()
+}
+
+/// Compacts an IRI string, or an array of them.
+fn compact_iris(namespaces: &Namespaces, mut value: DType) -> DType {
+  match value {
+    DType::String(ref mut iri) => {
+      if let Some(compact) = namespaces.compact(iri) {
+        *iri = compact;
+      }
+    }
+    DType::Array(ref mut values) => {
+      for value in values.iter_mut() {
+        *value = compact_iris(namespaces, std::mem::take(value));
+      }
+    }
+    _ => {}
+  }
+  value
+}
+
+/// Compacts the `@id` of node references, in a property value.
+fn compact_references(namespaces: &Namespaces, mut value: DType) -> DType {
+  match value {
+    DType::Array(ref mut values) => {
+      for value in values.iter_mut() {
+        *value = compact_references(namespaces, std::mem::take(value));
+      }
+    }
+    DType::Object(ref mut object)
+      if object.len() == 1 && object.contains_key("@id") =>
+    {
+      if let Some(id) = object.remove("@id") {
+        object.insert("@id".to_string(), compact_iris(namespaces, id));
+      }
+    }
+    _ => {}
+  }
+  value
+}
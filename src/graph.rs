@@ -12,14 +12,48 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod algo;
+mod checksum;
 mod connection;
+#[cfg(feature = "embeddings")]
+mod embedding;
+mod entity;
+mod history;
+mod index;
+mod isomorphism;
+mod knowledge_graph;
+mod merge;
 mod node;
+mod observer;
 mod predicate;
+mod profile;
+mod provenance;
+mod same_as;
+#[cfg(feature = "fts")]
+mod search;
+mod spatial;
+mod stats;
 mod triple;
 
 pub use connection::Connection;
+#[cfg(feature = "embeddings")]
+pub use embedding::Neighbor;
+pub use entity::{Entity, EntityMut};
+pub use history::{Diff, Snapshot};
+pub(crate) use index::Cardinality;
+pub use knowledge_graph::{Change, KnowledgeGraph};
+pub use merge::{
+  BlankNodes, Conflict, Duplicates, MergePolicy, MergeReport, Resolution,
+};
 pub use node::{Node, NodeStore};
+pub use observer::{Mutation, Subscription};
 pub use predicate::Predicate;
+pub use profile::{DateTimeFormat, Profile};
+pub use provenance::Provenance;
+pub use same_as::Canonical;
+#[cfg(feature = "fts")]
+pub use search::SearchHit;
+pub use spatial::GeoHit;
 pub use triple::Triple;
 
 // TODO(victor): Generate unique ID for the  Knowledge `GraphScore`. Node ID will be inform of "sg:N4286" while predicate will be inform of "sg:P5245".
@@ -0,0 +1,385 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::graph::algo` runs classic graph algorithms over the topology of a
+//! `KnowledgeGraph`: its non-literal nodes, linked by a directed edge from
+//! the subject to the object of every statement. Literal values are
+//! ignored, parallel statements count as a single edge.
+//!
+//! Results map every node (as its IRI or `_:label`) to its score or to the
+//! id of its component.
+//!
+//! ```rust
+//! use sage::graph::{algo, KnowledgeGraph, Node, Predicate};
+//!
+//! let node = |id: &str| Node::Http(format!("https://example.com/{}", id));
+//! let links = Predicate::Literal("https://schema.org/relatedLink".into());
+//!
+//! let mut graph = KnowledgeGraph::new();
+//! for (a, b) in [("a", "b"), ("b", "c"), ("c", "a"), ("d", "a")] {
+//!   graph.insert(node(a), links.clone(), node(b));
+//! }
+//!
+//! let ranks = algo::pagerank(&graph, 0.85);
+//! assert!(ranks["https://example.com/a"] > ranks["https://example.com/d"]);
+//!
+//! let components = algo::strongly_connected_components(&graph);
+//! assert_eq!(components["https://example.com/a"], 0);
+//! assert_eq!(components["https://example.com/c"], 0);
+//! assert_eq!(components["https://example.com/d"], 1);
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::graph::{KnowledgeGraph, Node};
+
+/// PageRank stops once the ranks move by less than this in total.
+const TOLERANCE: f64 = 1e-10;
+/// PageRank stops after this many iterations at most.
+const MAX_ITERATIONS: usize = 100;
+
+/// Returns the PageRank of every node: the probability a random surfer
+/// following edges, and jumping to a random node with probability
+/// `1 - damping`, is on it. Ranks sum to 1; dangling nodes link to every
+/// node.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{algo, KnowledgeGraph, Node, Predicate};
+///
+/// let cites = Predicate::Literal("https://schema.org/citation".into());
+/// let mut graph = KnowledgeGraph::new();
+/// for paper in ["a", "b", "c"] {
+///   graph.insert(
+///     Node::Http(format!("https://example.com/{}", paper)),
+///     cites.clone(),
+///     Node::Http("https://example.com/turing".into()),
+///   );
+/// }
+///
+/// let ranks = algo::pagerank(&graph, 0.85);
+/// let total: f64 = ranks.values().sum();
+/// assert!((total - 1.0).abs() < 1e-9);
+/// let turing = ranks["https://example.com/turing"];
+/// assert!(turing > ranks["https://example.com/a"]);
+/// ```
+pub fn pagerank(graph: &KnowledgeGraph, damping: f64) -> BTreeMap<String, f64> {
+  let topology = Topology::of(graph);
+  let n = topology.len();
+  if n == 0 {
+    return BTreeMap::new();
+  }
+  let damping = damping.clamp(0.0, 1.0);
+  let mut ranks = vec![1.0 / n as f64; n];
+  for _ in 0..MAX_ITERATIONS {
+    let dangling: f64 = (0..n)
+      .filter(|&v| topology.outgoing[v].is_empty())
+      .map(|v| ranks[v])
+      .sum();
+    let base = (1.0 - damping + damping * dangling) / n as f64;
+    let mut next = vec![base; n];
+    for (v, targets) in topology.outgoing.iter().enumerate() {
+      let share = damping * ranks[v] / targets.len().max(1) as f64;
+      for &w in targets {
+        next[w] += share;
+      }
+    }
+    let delta: f64 = next.iter().zip(&ranks).map(|(a, b)| (a - b).abs()).sum();
+    ranks = next;
+    if delta < TOLERANCE {
+      break;
+    }
+  }
+  topology.scores(ranks)
+}
+
+/// Returns the weakly connected component of every node, i.e. ignoring the
+/// direction of edges. Components are numbered from 0, in the order of
+/// their first node.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{algo, KnowledgeGraph, Node, Predicate};
+///
+/// let knows = Predicate::Literal("https://schema.org/knows".into());
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(Node::Http("a".into()), knows.clone(), Node::Http("b".into()));
+/// graph.insert(Node::Http("c".into()), knows.clone(), Node::Http("b".into()));
+/// graph.insert(Node::Http("x".into()), knows, Node::Http("y".into()));
+///
+/// let components = algo::weakly_connected_components(&graph);
+/// assert_eq!(components["a"], components["c"]);
+/// assert_ne!(components["a"], components["x"]);
+/// ```
+pub fn weakly_connected_components(
+  graph: &KnowledgeGraph,
+) -> BTreeMap<String, usize> {
+  let topology = Topology::of(graph);
+  let mut component = vec![usize::MAX; topology.len()];
+  let mut count = 0;
+  for start in 0..topology.len() {
+    if component[start] != usize::MAX {
+      continue;
+    }
+    component[start] = count;
+    let mut stack = vec![start];
+    while let Some(v) = stack.pop() {
+      let neighbors = topology.outgoing[v].iter().chain(&topology.incoming[v]);
+      for &w in neighbors {
+        if component[w] == usize::MAX {
+          component[w] = count;
+          stack.push(w);
+        }
+      }
+    }
+    count += 1;
+  }
+  topology.scores(component)
+}
+
+/// Returns the strongly connected component of every node: two nodes are
+/// in the same component iff each is reachable from the other. Components
+/// are numbered from 0, in the order of their first node.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{algo, KnowledgeGraph, Node, Predicate};
+///
+/// let knows = Predicate::Literal("https://schema.org/knows".into());
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(Node::Http("a".into()), knows.clone(), Node::Http("b".into()));
+/// graph.insert(Node::Http("b".into()), knows.clone(), Node::Http("a".into()));
+/// graph.insert(Node::Http("b".into()), knows, Node::Http("c".into()));
+///
+/// let components = algo::strongly_connected_components(&graph);
+/// assert_eq!(components["a"], components["b"]);
+/// assert_ne!(components["a"], components["c"]);
+/// ```
+pub fn strongly_connected_components(
+  graph: &KnowledgeGraph,
+) -> BTreeMap<String, usize> {
+  let topology = Topology::of(graph);
+  let n = topology.len();
+  // Tarjan's algorithm, with an explicit stack of (node, next edge).
+  let mut index = vec![usize::MAX; n];
+  let mut low = vec![0; n];
+  let mut on_stack = vec![false; n];
+  let mut stack = Vec::new();
+  let mut components: Vec<Vec<usize>> = Vec::new();
+  let mut next = 0;
+  for root in 0..n {
+    if index[root] != usize::MAX {
+      continue;
+    }
+    let mut calls = vec![(root, 0)];
+    while let Some((v, edge)) = calls.pop() {
+      if edge == 0 {
+        index[v] = next;
+        low[v] = next;
+        next += 1;
+        stack.push(v);
+        on_stack[v] = true;
+      }
+      if let Some(&w) = topology.outgoing[v].get(edge) {
+        calls.push((v, edge + 1));
+        if index[w] == usize::MAX {
+          calls.push((w, 0));
+        } else if on_stack[w] {
+          low[v] = low[v].min(index[w]);
+        }
+        continue;
+      }
+      if low[v] == index[v] {
+        let mut members = Vec::new();
+        while let Some(w) = stack.pop() {
+          on_stack[w] = false;
+          members.push(w);
+          if w == v {
+            break;
+          }
+        }
+        components.push(members);
+      }
+      if let Some(&(parent, _)) = calls.last() {
+        low[parent] = low[parent].min(low[v]);
+      }
+    }
+  }
+
+  components.sort_by_key(|members| members.iter().min().copied());
+  let mut component = vec![0; n];
+  for (id, members) in components.iter().enumerate() {
+    for &v in members {
+      component[v] = id;
+    }
+  }
+  topology.scores(component)
+}
+
+/// Returns the degree centrality of every node: the number of nodes it
+/// links to or is linked from, over the number of other nodes. As edges
+/// are directed, a node both linking to & linked from every other node
+/// scores 2.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{algo, KnowledgeGraph, Node, Predicate};
+///
+/// let knows = Predicate::Literal("https://schema.org/knows".into());
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(Node::Http("a".into()), knows.clone(), Node::Http("b".into()));
+/// graph.insert(Node::Http("a".into()), knows, Node::Http("c".into()));
+///
+/// let centrality = algo::degree_centrality(&graph);
+/// assert_eq!(centrality["a"], 1.0);
+/// assert_eq!(centrality["b"], 0.5);
+/// ```
+pub fn degree_centrality(graph: &KnowledgeGraph) -> BTreeMap<String, f64> {
+  let topology = Topology::of(graph);
+  let others = topology.len().saturating_sub(1).max(1) as f64;
+  let degrees = (0..topology.len())
+    .map(|v| {
+      let degree = topology.outgoing[v].len() + topology.incoming[v].len();
+      degree as f64 / others
+    })
+    .collect();
+  topology.scores(degrees)
+}
+
+/// Returns the betweenness centrality of every node: the fraction of
+/// shortest paths between pairs of other nodes passing through it, summed
+/// over those pairs & normalized by their number, `(n - 1)(n - 2)`.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{algo, KnowledgeGraph, Node, Predicate};
+///
+/// let next = Predicate::Literal("https://schema.org/nextItem".into());
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(Node::Http("a".into()), next.clone(), Node::Http("b".into()));
+/// graph.insert(Node::Http("b".into()), next, Node::Http("c".into()));
+///
+/// let centrality = algo::betweenness_centrality(&graph);
+/// assert_eq!(centrality["a"], 0.0);
+/// assert_eq!(centrality["b"], 0.5);
+/// ```
+pub fn betweenness_centrality(graph: &KnowledgeGraph) -> BTreeMap<String, f64> {
+  let topology = Topology::of(graph);
+  let n = topology.len();
+  let mut centrality = vec![0.0; n];
+  // Brandes' algorithm: one breadth-first search per source, then
+  // dependencies accumulated in reverse order of distance.
+  for source in 0..n {
+    let mut order = Vec::with_capacity(n);
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut paths = vec![0.0; n];
+    let mut distance = vec![usize::MAX; n];
+    paths[source] = 1.0;
+    distance[source] = 0;
+    let mut queue = VecDeque::from([source]);
+    while let Some(v) = queue.pop_front() {
+      order.push(v);
+      for &w in &topology.outgoing[v] {
+        if distance[w] == usize::MAX {
+          distance[w] = distance[v] + 1;
+          queue.push_back(w);
+        }
+        if distance[w] == distance[v] + 1 {
+          paths[w] += paths[v];
+          predecessors[w].push(v);
+        }
+      }
+    }
+
+    let mut dependency = vec![0.0; n];
+    for &w in order.iter().rev() {
+      for &v in &predecessors[w] {
+        dependency[v] += paths[v] / paths[w] * (1.0 + dependency[w]);
+      }
+      if w != source {
+        centrality[w] += dependency[w];
+      }
+    }
+  }
+
+  if n > 2 {
+    let pairs = ((n - 1) * (n - 2)) as f64;
+    centrality.iter_mut().for_each(|c| *c /= pairs);
+  }
+  topology.scores(centrality)
+}
+
+/// Nodes of a graph, in order, with their distinct neighbors.
+struct Topology {
+  names: Vec<String>,
+  outgoing: Vec<Vec<usize>>,
+  incoming: Vec<Vec<usize>>,
+}
+
+impl Topology {
+  fn of(graph: &KnowledgeGraph) -> Topology {
+    let mut names = BTreeSet::new();
+    let mut edges = BTreeSet::new();
+    for triple in graph.triples() {
+      for source in flatten(triple.source()) {
+        let source = source.to_string();
+        for destination in flatten(triple.destination()) {
+          let destination = destination.to_string();
+          names.insert(destination.clone());
+          if source != destination {
+            edges.insert((source.clone(), destination));
+          }
+        }
+        names.insert(source);
+      }
+    }
+
+    let names: Vec<String> = names.into_iter().collect();
+    let index = |name: &String| names.binary_search(name).unwrap_or_default();
+    let mut outgoing = vec![Vec::new(); names.len()];
+    let mut incoming = vec![Vec::new(); names.len()];
+    for (source, destination) in &edges {
+      let (v, w) = (index(source), index(destination));
+      outgoing[v].push(w);
+      incoming[w].push(v);
+    }
+    Topology {
+      names,
+      outgoing,
+      incoming,
+    }
+  }
+
+  fn len(&self) -> usize {
+    self.names.len()
+  }
+
+  fn scores<T>(&self, scores: Vec<T>) -> BTreeMap<String, T> {
+    self.names.iter().cloned().zip(scores).collect()
+  }
+}
+
+/// Returns the non-literal nodes in `node`.
+fn flatten(node: &Node) -> Vec<&Node> {
+  match node {
+    Node::Literal(_) => Vec::new(),
+    Node::Multiple(nodes) => nodes.iter().flat_map(flatten).collect(),
+    node => vec![node],
+  }
+}
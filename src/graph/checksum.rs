@@ -0,0 +1,85 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::thread;
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+  datastore::blob::to_hex, formats::canonical_statements, graph::KnowledgeGraph,
+};
+
+/// Prefix of every checksum, bumped whenever the algorithm changes.
+const VERSION: &[u8] = b"sage-checksum-v1";
+
+/// Computes the checksum of `graph` over `partitions` threads.
+///
+/// Every statement is serialized as canonical N-Triples and hashed with
+/// SHA-256. The hashes are summed as 256-bit integers (wrapping), which is
+/// commutative, so the result neither depends on the order of the triples
+/// nor on how they're partitioned. The sum & statement count are hashed
+/// once more into the final checksum.
+pub(crate) fn checksum(graph: &KnowledgeGraph, partitions: usize) -> String {
+  let lines: Vec<String> = canonical_statements(graph)
+    .into_iter()
+    .map(|statement| statement.line)
+    .collect();
+  let chunk = lines.len().div_ceil(partitions.max(1)).max(1);
+
+  let sums: Vec<[u64; 4]> = thread::scope(|scope| {
+    let handles: Vec<_> = lines
+      .chunks(chunk)
+      .map(|lines| scope.spawn(move || partition_sum(lines)))
+      .collect();
+    handles
+      .into_iter()
+      .map(|handle| handle.join().expect("checksum partition panicked"))
+      .collect()
+  });
+  let sum = sums.iter().fold([0; 4], |acc, sum| add(&acc, sum));
+
+  let mut hasher = Sha256::new();
+  hasher.update(VERSION);
+  hasher.update((lines.len() as u64).to_be_bytes());
+  for limb in sum {
+    hasher.update(limb.to_be_bytes());
+  }
+  to_hex(&hasher.finalize())
+}
+
+fn partition_sum(lines: &[String]) -> [u64; 4] {
+  lines.iter().fold([0; 4], |acc, line| {
+    let digest = Sha256::digest(line.as_bytes());
+    let mut limbs = [0; 4];
+    for (limb, bytes) in limbs.iter_mut().zip(digest.chunks(8)) {
+      let mut buf = [0; 8];
+      buf.copy_from_slice(bytes);
+      *limb = u64::from_be_bytes(buf);
+    }
+    add(&acc, &limbs)
+  })
+}
+
+/// Adds two big-endian 256-bit integers, wrapping on overflow.
+fn add(a: &[u64; 4], b: &[u64; 4]) -> [u64; 4] {
+  let mut out = [0; 4];
+  let mut carry = false;
+  for i in (0..4).rev() {
+    let (sum, c1) = a[i].overflowing_add(b[i]);
+    let (sum, c2) = sum.overflowing_add(carry as u64);
+    out[i] = sum;
+    carry = c1 || c2;
+  }
+  out
+}
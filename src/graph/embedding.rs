@@ -0,0 +1,376 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+  cmp::{Ordering, Reverse},
+  collections::{BinaryHeap, HashMap, HashSet},
+  mem,
+};
+
+use serde::de::Error as _;
+
+use crate::{error::Error, graph::Node, random, Result};
+
+/// Maximum number of links per node on the upper layers.
+const M: usize = 16;
+/// Size of the candidate list while inserting.
+const EF_CONSTRUCTION: usize = 64;
+/// Minimum size of the candidate list while searching.
+const EF_SEARCH: usize = 64;
+
+/// `Neighbor` is an entity returned by `KnowledgeGraph::nearest`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Neighbor {
+  /// The entity.
+  pub node: Node,
+  /// Cosine similarity of its embedding to the query, between -1 and 1.
+  pub similarity: f32,
+}
+
+/// A distance ordered by `f32::total_cmp`, paired with a node id.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Scored(f32, usize);
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Scored {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+  }
+}
+
+/// An embedded entity.
+#[derive(Debug)]
+struct Point {
+  iri: String,
+  vector: Vec<f32>,
+  /// `1 / |vector|`, `0` for the zero vector.
+  inverse_norm: f32,
+  /// Links on every layer the point lives on, from layer 0 up.
+  links: Vec<Vec<usize>>,
+  /// Replaced or removed points stay in the graph as waypoints, until
+  /// they outnumber half the live ones & the graph is rebuilt.
+  deleted: bool,
+}
+
+/// `EmbeddingIndex` is a [Hierarchical Navigable Small World] graph over
+/// the embeddings of entities, for approximate nearest neighbor search by
+/// cosine distance.
+///
+/// [Hierarchical Navigable Small World]: https://arxiv.org/abs/1603.09320
+#[derive(Debug, Default)]
+pub(crate) struct EmbeddingIndex {
+  points: Vec<Point>,
+  /// Live point of every entity.
+  ids: HashMap<String, usize>,
+  entry: Option<usize>,
+  dimensions: usize,
+}
+
+impl EmbeddingIndex {
+  /// Sets the embedding of `iri`, replacing the previous one. Fails if the
+  /// dimensions differ from those of the other embeddings.
+  pub(crate) fn insert(&mut self, iri: &str, vector: Vec<f32>) -> Result<()> {
+    if vector.is_empty() || vector.iter().any(|x| !x.is_finite()) {
+      return Err(Error::custom("embeddings must be non-empty & finite"));
+    }
+    if !self.ids.is_empty() && vector.len() != self.dimensions {
+      return Err(Error::custom(format!(
+        "expected an embedding of {} dimensions, got {}",
+        self.dimensions,
+        vector.len()
+      )));
+    }
+    self.remove(iri);
+    self.dimensions = vector.len();
+
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let level = random_level();
+    let id = self.points.len();
+    self.points.push(Point {
+      iri: iri.to_string(),
+      vector,
+      inverse_norm: if norm > 0.0 { 1.0 / norm } else { 0.0 },
+      links: vec![Vec::new(); level + 1],
+      deleted: false,
+    });
+    self.ids.insert(iri.to_string(), id);
+
+    let entry = match self.entry {
+      Some(entry) => entry,
+      None => {
+        self.entry = Some(id);
+        return Ok(());
+      }
+    };
+    let top = self.points[entry].links.len() - 1;
+
+    let mut nearest = entry;
+    for layer in (level + 1..=top).rev() {
+      nearest = self.greedy(id, nearest, layer);
+    }
+    let mut entries = vec![nearest];
+    for layer in (0..=level.min(top)).rev() {
+      let candidates = self.search_layer(id, &entries, EF_CONSTRUCTION, layer);
+      let limit = if layer == 0 { 2 * M } else { M };
+      let neighbors: Vec<usize> =
+        candidates.iter().take(M).map(|s| s.1).collect();
+      for &neighbor in &neighbors {
+        self.points[neighbor].links[layer].push(id);
+        if self.points[neighbor].links[layer].len() > limit {
+          self.prune(neighbor, layer, limit);
+        }
+      }
+      self.points[id].links[layer] = neighbors;
+      entries = candidates.iter().map(|s| s.1).collect();
+    }
+    if level > top {
+      self.entry = Some(id);
+    }
+    Ok(())
+  }
+
+  /// Removes the embedding of `iri`, returning `false` if it had none.
+  pub(crate) fn remove(&mut self, iri: &str) -> bool {
+    match self.ids.remove(iri) {
+      Some(id) => {
+        self.points[id].deleted = true;
+        if self.points.len() - self.ids.len() > self.ids.len() / 2 {
+          self.rebuild();
+        }
+        true
+      }
+      None => false,
+    }
+  }
+
+  /// Rebuilds the graph from the live points only.
+  fn rebuild(&mut self) {
+    let points = mem::take(&mut self.points);
+    self.ids.clear();
+    self.entry = None;
+    for point in points.into_iter().filter(|point| !point.deleted) {
+      // Vectors were checked when first inserted, so this can't fail.
+      let _ = self.insert(&point.iri, point.vector);
+    }
+  }
+
+  /// Returns the embedding of `iri`.
+  pub(crate) fn get(&self, iri: &str) -> Option<&[f32]> {
+    self
+      .ids
+      .get(iri)
+      .map(|&id| self.points[id].vector.as_slice())
+  }
+
+  /// Returns up to `k` entities nearest to the embedding of `iri`, without
+  /// `iri` itself.
+  pub(crate) fn nearest(&self, iri: &str, k: usize) -> Vec<Neighbor> {
+    let id = match self.ids.get(iri) {
+      Some(&id) => id,
+      None => return Vec::new(),
+    };
+    let point = &self.points[id];
+    let query = Query {
+      vector: &point.vector,
+      inverse_norm: point.inverse_norm,
+    };
+    self
+      .search(&query, k + 1)
+      .into_iter()
+      .filter(|s| s.1 != id)
+      .take(k)
+      .map(|s| self.neighbor(s))
+      .collect()
+  }
+
+  /// Returns up to `k` entities nearest to `vector`, most similar first.
+  pub(crate) fn nearest_to(&self, vector: &[f32], k: usize) -> Vec<Neighbor> {
+    if vector.len() != self.dimensions {
+      return Vec::new();
+    }
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let query = Query {
+      vector,
+      inverse_norm: if norm > 0.0 { 1.0 / norm } else { 0.0 },
+    };
+    self
+      .search(&query, k)
+      .into_iter()
+      .map(|s| self.neighbor(s))
+      .collect()
+  }
+
+  /// Returns up to `k` live points nearest to `query`, nearest first.
+  fn search(&self, query: &Query, k: usize) -> Vec<Scored> {
+    let entry = match self.entry {
+      Some(entry) if k > 0 => entry,
+      _ => return Vec::new(),
+    };
+    let mut nearest = entry;
+    for layer in (1..self.points[entry].links.len()).rev() {
+      nearest = self.greedy_to(query, nearest, layer);
+    }
+    // Deleted points still take up room in the candidate list.
+    let ef = EF_SEARCH.max(k) + self.points.len() - self.ids.len();
+    self
+      .search_layer_to(query, &[nearest], ef, 0)
+      .into_iter()
+      .filter(|s| !self.points[s.1].deleted)
+      .take(k)
+      .collect()
+  }
+
+  fn neighbor(&self, Scored(distance, id): Scored) -> Neighbor {
+    Neighbor {
+      node: super::entity::node(&self.points[id].iri),
+      similarity: 1.0 - distance,
+    }
+  }
+
+  fn greedy(&self, id: usize, entry: usize, layer: usize) -> usize {
+    let point = &self.points[id];
+    let query = Query {
+      vector: &point.vector,
+      inverse_norm: point.inverse_norm,
+    };
+    self.greedy_to(&query, entry, layer)
+  }
+
+  /// Walks to the node nearest to `query` on `layer`, starting at `entry`.
+  fn greedy_to(&self, query: &Query, entry: usize, layer: usize) -> usize {
+    let mut nearest = Scored(query.distance(&self.points[entry]), entry);
+    loop {
+      let closer = self.points[nearest.1].links[layer]
+        .iter()
+        .map(|&n| Scored(query.distance(&self.points[n]), n))
+        .filter(|candidate| *candidate < nearest)
+        .min();
+      match closer {
+        Some(closer) => nearest = closer,
+        None => return nearest.1,
+      }
+    }
+  }
+
+  fn search_layer(
+    &self,
+    id: usize,
+    entries: &[usize],
+    ef: usize,
+    layer: usize,
+  ) -> Vec<Scored> {
+    let point = &self.points[id];
+    let query = Query {
+      vector: &point.vector,
+      inverse_norm: point.inverse_norm,
+    };
+    self
+      .search_layer_to(&query, entries, ef, layer)
+      .into_iter()
+      .filter(|s| s.1 != id)
+      .collect()
+  }
+
+  /// Returns the (up to) `ef` nodes nearest to `query` on `layer`, nearest
+  /// first.
+  fn search_layer_to(
+    &self,
+    query: &Query,
+    entries: &[usize],
+    ef: usize,
+    layer: usize,
+  ) -> Vec<Scored> {
+    let mut visited: HashSet<usize> = entries.iter().copied().collect();
+    let mut candidates = BinaryHeap::new();
+    let mut results = BinaryHeap::new();
+    for &entry in entries {
+      let scored = Scored(query.distance(&self.points[entry]), entry);
+      candidates.push(Reverse(scored));
+      results.push(scored);
+    }
+    while results.len() > ef {
+      results.pop();
+    }
+
+    while let Some(Reverse(candidate)) = candidates.pop() {
+      if results.len() >= ef && results.peek().is_some_and(|f| candidate > *f) {
+        break;
+      }
+      for &neighbor in &self.points[candidate.1].links[layer] {
+        if !visited.insert(neighbor) {
+          continue;
+        }
+        let scored = Scored(query.distance(&self.points[neighbor]), neighbor);
+        if results.len() < ef || results.peek().is_some_and(|f| scored < *f) {
+          candidates.push(Reverse(scored));
+          results.push(scored);
+          if results.len() > ef {
+            results.pop();
+          }
+        }
+      }
+    }
+    results.into_sorted_vec()
+  }
+
+  /// Keeps the `limit` nearest links of `id` on `layer`.
+  fn prune(&mut self, id: usize, layer: usize, limit: usize) {
+    let point = &self.points[id];
+    let query = Query {
+      vector: &point.vector,
+      inverse_norm: point.inverse_norm,
+    };
+    let mut links: Vec<Scored> = point.links[layer]
+      .iter()
+      .map(|&n| Scored(query.distance(&self.points[n]), n))
+      .collect();
+    links.sort_unstable();
+    links.truncate(limit);
+    self.points[id].links[layer] = links.into_iter().map(|s| s.1).collect();
+  }
+}
+
+/// A vector compared against the indexed points.
+struct Query<'a> {
+  vector: &'a [f32],
+  inverse_norm: f32,
+}
+
+impl Query<'_> {
+  /// Cosine distance to `point`, between 0 & 2.
+  fn distance(&self, point: &Point) -> f32 {
+    let dot: f32 = self
+      .vector
+      .iter()
+      .zip(&point.vector)
+      .map(|(a, b)| a * b)
+      .sum();
+    1.0 - dot * self.inverse_norm * point.inverse_norm
+  }
+}
+
+/// Draws the top layer of a new point from an exponential distribution, so
+/// each layer holds about `1 / M` of the points of the layer below.
+fn random_level() -> usize {
+  let uniform = (random::u64() >> 11) as f64 / (1u64 << 53) as f64;
+  let level = -(1.0 - uniform).ln() / (M as f64).ln();
+  (level as usize).min(16)
+}
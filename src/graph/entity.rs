@@ -0,0 +1,227 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+  datastore::blob::to_hex,
+  dtype::DType,
+  formats,
+  graph::{Change, KnowledgeGraph, Node, Predicate, Triple},
+};
+
+/// Prefix of every entity hash, bumped whenever the encoding changes.
+const VERSION: &[u8] = b"sage-entity-v1";
+
+/// Full IRI of `rdf:type`.
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/// Returns the node identified by `iri`; `_:label` denotes a blank node.
+pub(crate) fn node(iri: &str) -> Node {
+  match iri.strip_prefix("_:") {
+    Some(label) => Node::BlankId(label.to_string()),
+    None => Node::Http(iri.to_string()),
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Entity
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Entity` is a read-only view of a subject and every statement about it,
+/// returned by `KnowledgeGraph::entity`.
+pub struct Entity<'g> {
+  graph: &'g KnowledgeGraph,
+  node: Node,
+}
+
+impl<'g> Entity<'g> {
+  pub(crate) fn new(graph: &'g KnowledgeGraph, node: Node) -> Entity<'g> {
+    Entity { graph, node }
+  }
+
+  /// Returns the node of the entity.
+  pub fn node(&self) -> &Node {
+    &self.node
+  }
+
+  /// Returns `true` if the graph holds at least one statement about the
+  /// entity.
+  pub fn exists(&self) -> bool {
+    !self.triples().is_empty()
+  }
+
+  /// Returns every value of `predicate`, given by its full IRI.
+  pub fn get(&self, predicate: &str) -> Vec<&'g Node> {
+    self
+      .triples()
+      .into_iter()
+      .filter(|triple| triple.predicate().to_string() == predicate)
+      .flat_map(|triple| flatten(triple.destination()))
+      .collect()
+  }
+
+  /// Returns the `rdf:type`s (or `@type`s) of the entity.
+  pub fn types(&self) -> Vec<String> {
+    let mut types = self.get("@type");
+    types.extend(self.get(RDF_TYPE));
+    types
+      .into_iter()
+      .filter_map(|node| match node {
+        Node::Literal(DType::String(t)) => Some(t.clone()),
+        Node::Literal(_) => None,
+        node => Some(node.to_string()),
+      })
+      .collect()
+  }
+
+  /// Returns a JSON-LD style node object: the IRI under `@id`, the types
+  /// under `@type` and every other predicate keyed by its IRI. Links are
+  /// written as `{"@id": ...}`, predicates with several values as arrays.
+  pub fn to_dtype(&self) -> DType {
+    formats::node_object(self.node.to_string(), &self.triples(), &mut 0)
+  }
+
+  /// Returns the hex-encoded SHA-256 of the statements about the entity.
+  ///
+  /// The statements are hashed as sorted, deduplicated canonical N-Triples,
+  /// so the hash doesn't depend on the order they were added in, and is
+  /// stable across processes.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  ///
+  /// let ada = Node::Http("https://example.com/ada".to_string());
+  /// let name = Predicate::Literal("https://schema.org/name".to_string());
+  /// let born = Predicate::Literal("https://schema.org/birthDate".to_string());
+  ///
+  /// let mut a = KnowledgeGraph::new();
+  /// a.insert(ada.clone(), name.clone(), Node::Literal("Ada".into()));
+  /// a.insert(ada.clone(), born.clone(), Node::Literal(1815.into()));
+  ///
+  /// let mut b = KnowledgeGraph::new();
+  /// b.insert(ada.clone(), born, Node::Literal(1815.into()));
+  /// b.insert(ada.clone(), name, Node::Literal("Ada".into()));
+  ///
+  /// let hash = a.entity("https://example.com/ada").content_hash();
+  /// assert_eq!(hash, b.entity("https://example.com/ada").content_hash());
+  /// assert_ne!(hash, a.entity("https://example.com/bob").content_hash());
+  /// ```
+  pub fn content_hash(&self) -> String {
+    let mut lines: Vec<String> =
+      formats::canonical_statements_of(self.triples())
+        .into_iter()
+        .map(|statement| statement.line)
+        .collect();
+    lines.sort_unstable();
+    lines.dedup();
+
+    let mut hasher = Sha256::new();
+    hasher.update(VERSION);
+    for line in lines {
+      hasher.update(line.as_bytes());
+      hasher.update(b"\n");
+    }
+    to_hex(&hasher.finalize())
+  }
+
+  pub(crate) fn triples(&self) -> Vec<&'g Triple> {
+    let graph: &'g KnowledgeGraph = self.graph;
+    graph
+      .triples()
+      .filter(|t| t.source() == &self.node)
+      .collect()
+  }
+}
+
+/// `EntityMut` is a mutable view of a subject, returned by
+/// `KnowledgeGraph::entity_mut`.
+///
+/// Every edit bumps the version of the subject, like `update_if_version`.
+pub struct EntityMut<'g> {
+  graph: &'g mut KnowledgeGraph,
+  node: Node,
+}
+
+impl<'g> EntityMut<'g> {
+  pub(crate) fn new(graph: &'g mut KnowledgeGraph, node: Node) -> Self {
+    EntityMut { graph, node }
+  }
+
+  /// Returns a read-only view of the entity.
+  pub fn as_entity(&self) -> Entity<'_> {
+    Entity::new(self.graph, self.node.clone())
+  }
+
+  /// Returns every value of `predicate`. See `Entity::get`.
+  pub fn get(&self, predicate: &str) -> Vec<&Node> {
+    self.as_entity().get(predicate)
+  }
+
+  /// Returns the types of the entity. See `Entity::types`.
+  pub fn types(&self) -> Vec<String> {
+    self.as_entity().types()
+  }
+
+  /// Returns the entity as a JSON-LD style object. See `Entity::to_dtype`.
+  pub fn to_dtype(&self) -> DType {
+    self.as_entity().to_dtype()
+  }
+
+  /// Returns the content hash of the entity. See `Entity::content_hash`.
+  pub fn content_hash(&self) -> String {
+    self.as_entity().content_hash()
+  }
+
+  /// Replaces every value of `predicate` with `value`.
+  pub fn set(&mut self, predicate: &str, value: Node) -> &mut Self {
+    self.apply(Change::Set(
+      Predicate::Literal(predicate.to_string()),
+      value,
+    ))
+  }
+
+  /// Adds `value` to `predicate`, keeping existing values.
+  pub fn add(&mut self, predicate: &str, value: Node) -> &mut Self {
+    self.apply(Change::Add(
+      Predicate::Literal(predicate.to_string()),
+      value,
+    ))
+  }
+
+  /// Removes every value of `predicate`.
+  pub fn remove(&mut self, predicate: &str) -> &mut Self {
+    self.apply(Change::Remove(Predicate::Literal(predicate.to_string())))
+  }
+
+  fn apply(&mut self, change: Change) -> &mut Self {
+    let version = self.graph.version(&self.node);
+    // The version was just read, so the update can't be rejected.
+    let _ = self.graph.update_if_version(&self.node, version, [change]);
+    self
+  }
+}
+
+fn flatten(node: &Node) -> Vec<&Node> {
+  match node {
+    Node::Multiple(nodes) => nodes.iter().flat_map(flatten).collect(),
+    node => vec![node],
+  }
+}
@@ -0,0 +1,255 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+  dtype::DeepSizeOf,
+  graph::{Mutation, Node, Triple},
+};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | History
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// A statement along with the transactions it was valid in.
+struct Entry {
+  triple: Triple,
+  /// Transaction which inserted the statement.
+  from: u64,
+  /// Transaction which removed the statement, if any.
+  to: Option<u64>,
+}
+
+impl Entry {
+  fn is_valid_at(&self, version: u64) -> bool {
+    self.from <= version && self.to.is_none_or(|to| to > version)
+  }
+}
+
+/// Transaction ids of a graph, and every statement it ever held once
+/// enabled.
+#[derive(Default)]
+pub(crate) struct History {
+  entries: Vec<Entry>,
+  /// Id of the last transaction.
+  version: u64,
+  /// First version the history covers, `None` while disabled.
+  since: Option<u64>,
+  /// Nesting depth of `KnowledgeGraph::batch` calls.
+  depth: usize,
+  /// Whether the current batch already started a transaction.
+  open: bool,
+}
+
+impl History {
+  pub(crate) fn version(&self) -> u64 {
+    self.version
+  }
+
+  pub(crate) fn is_enabled(&self) -> bool {
+    self.since.is_some()
+  }
+
+  /// Starts keeping history, with `triples` as the state at the current
+  /// version.
+  pub(crate) fn enable<'a>(
+    &mut self,
+    triples: impl Iterator<Item = &'a Triple>,
+  ) {
+    if self.is_enabled() {
+      return;
+    }
+    self.since = Some(self.version);
+    self.entries = triples
+      .map(|t| Entry {
+        triple: copy(t),
+        from: self.version,
+        to: None,
+      })
+      .collect();
+  }
+
+  /// Returns `true` if the state at `version` is known.
+  pub(crate) fn covers(&self, version: u64) -> bool {
+    self.since.is_some_and(|since| since <= version) && version <= self.version
+  }
+
+  pub(crate) fn begin(&mut self) {
+    self.depth += 1;
+  }
+
+  pub(crate) fn end(&mut self) {
+    self.depth -= 1;
+    if self.depth == 0 {
+      self.open = false;
+    }
+  }
+
+  /// Starts a new transaction for a change, unless the current batch
+  /// already did.
+  pub(crate) fn stamp(&mut self) {
+    if !self.open {
+      self.version += 1;
+      self.open = self.depth > 0;
+    }
+  }
+
+  /// Records `mutation` as part of the current transaction.
+  pub(crate) fn record(&mut self, mutation: &Mutation) {
+    if !self.is_enabled() {
+      return;
+    }
+
+    match mutation {
+      Mutation::Inserted(subject, predicate, object) => {
+        self.entries.push(Entry {
+          triple: Triple::from_nodes(
+            subject.clone(),
+            predicate.clone(),
+            object.clone(),
+          ),
+          from: self.version,
+          to: None,
+        });
+      }
+      Mutation::Removed(subject, predicate, object) => {
+        let version = self.version;
+        let removed = self.entries.iter_mut().find(|e| {
+          e.to.is_none()
+            && e.triple.source() == subject
+            && e.triple.predicate() == predicate
+            && e.triple.destination() == object
+        });
+        if let Some(entry) = removed {
+          entry.to = Some(version);
+        }
+      }
+    }
+  }
+
+  /// Every statement valid at `version`, in insertion order.
+  pub(crate) fn at(&self, version: u64) -> impl Iterator<Item = &Triple> {
+    self
+      .entries
+      .iter()
+      .filter(move |e| e.is_valid_at(version))
+      .map(|e| &e.triple)
+  }
+
+  /// Statements valid at `to` but not at `from`, and the other way round.
+  pub(crate) fn diff(
+    &self,
+    from: u64,
+    to: u64,
+  ) -> (Vec<&Triple>, Vec<&Triple>) {
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for entry in &self.entries {
+      match (entry.is_valid_at(from), entry.is_valid_at(to)) {
+        (false, true) => added.push(&entry.triple),
+        (true, false) => removed.push(&entry.triple),
+        _ => {}
+      }
+    }
+    (added, removed)
+  }
+}
+
+impl DeepSizeOf for Entry {
+  fn heap_size_of(&self) -> usize {
+    self.triple.heap_size_of()
+  }
+}
+
+impl DeepSizeOf for History {
+  fn heap_size_of(&self) -> usize {
+    self.entries.heap_size_of()
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Snapshot
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Snapshot` is a read-only view of a `KnowledgeGraph` as it was at a past
+/// version, returned by `KnowledgeGraph::at_version`.
+pub struct Snapshot<'a> {
+  history: &'a History,
+  version: u64,
+}
+
+impl<'a> Snapshot<'a> {
+  pub(crate) fn new(history: &'a History, version: u64) -> Snapshot<'a> {
+    Snapshot { history, version }
+  }
+
+  /// Returns the version the snapshot shows.
+  pub fn version(&self) -> u64 {
+    self.version
+  }
+
+  /// Returns an iterator over every `Triple` valid at the version.
+  pub fn triples(&self) -> impl Iterator<Item = &'a Triple> + 'a {
+    self.history.at(self.version)
+  }
+
+  /// Returns the triples valid at the version which match every given
+  /// part, like `KnowledgeGraph::matches`.
+  pub fn triples_matching(
+    &self,
+    source: Option<&'a Node>,
+    predicate: Option<&'a str>,
+    destination: Option<&'a Node>,
+  ) -> impl Iterator<Item = &'a Triple> + 'a {
+    self.triples().filter(move |triple| {
+      source.is_none_or(|s| triple.source() == s)
+        && predicate.is_none_or(|p| triple.predicate().to_string() == p)
+        && destination.is_none_or(|d| triple.destination() == d)
+    })
+  }
+
+  /// Returns the number of triples valid at the version.
+  pub fn len(&self) -> usize {
+    self.triples().count()
+  }
+
+  /// Returns `true` if no triple was valid at the version.
+  pub fn is_empty(&self) -> bool {
+    self.triples().next().is_none()
+  }
+}
+
+/// `Diff` lists the statements added & removed between two versions of a
+/// `KnowledgeGraph`, returned by `KnowledgeGraph::diff`.
+pub struct Diff<'a> {
+  /// Statements valid at the later version only.
+  pub added: Vec<&'a Triple>,
+  /// Statements valid at the earlier version only.
+  pub removed: Vec<&'a Triple>,
+}
+
+fn copy(triple: &Triple) -> Triple {
+  Triple::from_nodes(
+    triple.source().clone(),
+    triple.predicate().clone(),
+    triple.destination().clone(),
+  )
+}
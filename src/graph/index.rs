@@ -0,0 +1,137 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::{Node, Triple};
+
+/// `Cardinality` counts the statements matching a triple pattern, and the
+/// distinct subjects, predicates & objects among them.
+///
+/// Statement counts are exact. Distinct counts are exact when at most the
+/// predicate is fixed, and otherwise an upper bound.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Cardinality {
+  pub(crate) statements: usize,
+  pub(crate) subjects: usize,
+  pub(crate) predicates: usize,
+  pub(crate) objects: usize,
+}
+
+/// The statements of a single predicate.
+#[derive(Debug, Default)]
+struct PredicateIndex {
+  positions: Vec<usize>,
+  /// Distinct subjects & objects of the statements.
+  subjects: HashSet<Node>,
+  objects: HashSet<Node>,
+}
+
+/// `TripleIndex` maps every subject, predicate (by full IRI) & object to
+/// the positions of its statements in the graph, in insertion order.
+///
+/// Positions shift when statements are removed, so the graph rebuilds the
+/// index after removing any.
+#[derive(Debug, Default)]
+pub(crate) struct TripleIndex {
+  subjects: HashMap<Node, Vec<usize>>,
+  predicates: HashMap<String, PredicateIndex>,
+  objects: HashMap<Node, Vec<usize>>,
+}
+
+impl TripleIndex {
+  /// Indexes every statement of `triples`.
+  pub(crate) fn new(triples: &[Triple]) -> TripleIndex {
+    let mut index = TripleIndex::default();
+    for (position, triple) in triples.iter().enumerate() {
+      index.insert(triple, position);
+    }
+    index
+  }
+
+  /// Indexes `triple`, stored at `position`, after every indexed statement.
+  pub(crate) fn insert(&mut self, triple: &Triple, position: usize) {
+    let (subject, object) = (triple.source(), triple.destination());
+    self
+      .subjects
+      .entry(subject.clone())
+      .or_default()
+      .push(position);
+    self
+      .objects
+      .entry(object.clone())
+      .or_default()
+      .push(position);
+
+    let predicate = self
+      .predicates
+      .entry(triple.predicate().to_string())
+      .or_default();
+    predicate.positions.push(position);
+    predicate.subjects.insert(subject.clone());
+    predicate.objects.insert(object.clone());
+  }
+
+  /// Returns the positions of the statements which may match the fixed
+  /// terms: those of the most selective one. `None` if no term is fixed.
+  pub(crate) fn candidates(
+    &self,
+    subject: Option<&Node>,
+    predicate: Option<&str>,
+    object: Option<&Node>,
+  ) -> Option<&[usize]> {
+    let lists = [
+      subject.map(|s| self.subjects.get(s).map(Vec::as_slice)),
+      predicate.map(|p| self.predicates.get(p).map(|p| &p.positions[..])),
+      object.map(|o| self.objects.get(o).map(Vec::as_slice)),
+    ];
+    lists
+      .into_iter()
+      .flatten()
+      .map(|positions| positions.unwrap_or(&[]))
+      .min_by_key(|positions| positions.len())
+  }
+
+  /// Returns the cardinality of the pattern, where `statements` is the
+  /// number of statements matching it (see `candidates`).
+  pub(crate) fn cardinality(
+    &self,
+    subject: Option<&Node>,
+    predicate: Option<&str>,
+    object: Option<&Node>,
+    statements: usize,
+  ) -> Cardinality {
+    let (subjects, objects) = match predicate {
+      Some(predicate) => match self.predicates.get(predicate) {
+        Some(index) => (index.subjects.len(), index.objects.len()),
+        None => (0, 0),
+      },
+      None => (self.subjects.len(), self.objects.len()),
+    };
+    // A fixed term has a single value, if any statement matches.
+    let distinct = |fixed: bool, distinct: usize| {
+      if fixed {
+        statements.min(1)
+      } else {
+        distinct.min(statements)
+      }
+    };
+    Cardinality {
+      statements,
+      subjects: distinct(subject.is_some(), subjects),
+      predicates: distinct(predicate.is_some(), self.predicates.len()),
+      objects: distinct(object.is_some(), objects),
+    }
+  }
+}
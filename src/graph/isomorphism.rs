@@ -0,0 +1,221 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+  collections::{hash_map::DefaultHasher, BTreeSet, HashMap, HashSet},
+  hash::{Hash, Hasher},
+};
+
+use crate::{formats::statements_with, graph::KnowledgeGraph};
+
+/// A statement as N-Triples terms.
+type Statement = [String; 3];
+
+/// The statements of a graph, split on whether they contain blank nodes.
+struct Side {
+  ground: HashSet<Statement>,
+  blank: HashSet<Statement>,
+  /// Blank node terms & the blank statements they appear in.
+  nodes: HashMap<String, Vec<Statement>>,
+}
+
+impl Side {
+  fn new(graph: &KnowledgeGraph) -> Side {
+    let mut anonymous = 0;
+    let statements =
+      statements_with(graph.triples(), &mut |label| match label {
+        Some(label) => format!("_:l{}", label),
+        None => {
+          anonymous += 1;
+          format!("_:a{}", anonymous)
+        }
+      });
+
+    let mut side = Side {
+      ground: HashSet::new(),
+      blank: HashSet::new(),
+      nodes: HashMap::new(),
+    };
+    for statement in statements {
+      let statement =
+        [statement.subject, statement.predicate, statement.object];
+      if !is_blank(&statement[0]) && !is_blank(&statement[2]) {
+        side.ground.insert(statement);
+      } else if side.blank.insert(statement.clone()) {
+        let mut terms: Vec<&String> = vec![&statement[0], &statement[2]];
+        terms.dedup();
+        for term in terms.into_iter().filter(|term| is_blank(term)) {
+          let node = side.nodes.entry(term.clone()).or_default();
+          node.push(statement.clone());
+        }
+      }
+    }
+    side
+  }
+
+  /// Colors the blank nodes by their neighborhood, refining until the
+  /// number of colors stops growing. Isomorphic graphs get the same
+  /// colors.
+  fn colors(&self) -> HashMap<&str, u64> {
+    let mut colors: HashMap<&str, u64> =
+      self.nodes.keys().map(|node| (node.as_str(), 0)).collect();
+    let mut classes = 1;
+    loop {
+      let refined: HashMap<&str, u64> = self
+        .nodes
+        .iter()
+        .map(|(node, statements)| {
+          let mut signatures: Vec<u64> = statements
+            .iter()
+            .map(|statement| {
+              let mut hasher = DefaultHasher::new();
+              for term in statement {
+                match colors.get(term.as_str()) {
+                  Some(_) if term == node => "self".hash(&mut hasher),
+                  Some(color) => color.hash(&mut hasher),
+                  None => term.hash(&mut hasher),
+                }
+              }
+              hasher.finish()
+            })
+            .collect();
+          signatures.sort_unstable();
+
+          let mut hasher = DefaultHasher::new();
+          colors[node.as_str()].hash(&mut hasher);
+          signatures.hash(&mut hasher);
+          (node.as_str(), hasher.finish())
+        })
+        .collect();
+
+      let count = refined.values().collect::<HashSet<_>>().len();
+      colors = refined;
+      if count <= classes {
+        return colors;
+      }
+      classes = count;
+    }
+  }
+}
+
+/// Returns `true` if `a` & `b` contain the same statements up to a
+/// bijection between their blank nodes.
+///
+/// Blank nodes are first colored by their neighborhood, then a bijection
+/// between nodes of the same color is searched for with backtracking,
+/// which is only slow for large graphs of highly symmetric blank nodes.
+pub(crate) fn isomorphic(a: &KnowledgeGraph, b: &KnowledgeGraph) -> bool {
+  let (a, b) = (Side::new(a), Side::new(b));
+  if a.ground != b.ground
+    || a.blank.len() != b.blank.len()
+    || a.nodes.len() != b.nodes.len()
+  {
+    return false;
+  }
+
+  let (colors_a, colors_b) = (a.colors(), b.colors());
+  let (classes_a, classes_b) = (classes(&colors_a), classes(&colors_b));
+  let sizes = |classes: &HashMap<u64, Vec<&str>>| {
+    classes
+      .iter()
+      .map(|(color, nodes)| (*color, nodes.len()))
+      .collect::<BTreeSet<_>>()
+  };
+  if sizes(&classes_a) != sizes(&classes_b) {
+    return false;
+  }
+
+  // Map the nodes of the smallest classes first, they constrain the most.
+  let mut order: Vec<&str> = colors_a.keys().copied().collect();
+  order.sort_by_key(|node| (classes_a[&colors_a[node]].len(), *node));
+
+  Search {
+    a: &a,
+    b: &b,
+    candidates: order
+      .iter()
+      .map(|node| classes_b[&colors_a[node]].clone())
+      .collect(),
+    order,
+    mapping: HashMap::new(),
+    used: HashSet::new(),
+  }
+  .run(0)
+}
+
+/// Backtracking search for a bijection from the blank nodes of `a` to
+/// those of `b`.
+struct Search<'s> {
+  a: &'s Side,
+  b: &'s Side,
+  order: Vec<&'s str>,
+  /// Nodes of `b` with the same color as each node of `order`.
+  candidates: Vec<Vec<&'s str>>,
+  mapping: HashMap<&'s str, &'s str>,
+  used: HashSet<&'s str>,
+}
+
+impl<'s> Search<'s> {
+  fn run(&mut self, depth: usize) -> bool {
+    let node = match self.order.get(depth) {
+      Some(node) => *node,
+      None => return true,
+    };
+    for candidate in self.candidates[depth].clone() {
+      if self.used.contains(candidate) {
+        continue;
+      }
+      self.mapping.insert(node, candidate);
+      self.used.insert(candidate);
+      if self.consistent(node) && self.run(depth + 1) {
+        return true;
+      }
+      self.mapping.remove(node);
+      self.used.remove(candidate);
+    }
+    false
+  }
+
+  /// Checks that the statements of `node` whose blank nodes are all mapped
+  /// are statements of `b`.
+  fn consistent(&self, node: &str) -> bool {
+    self.a.nodes[node].iter().all(|statement| {
+      let mut mapped: Statement = Default::default();
+      for (term, out) in statement.iter().zip(mapped.iter_mut()) {
+        *out = if is_blank(term) {
+          match self.mapping.get(term.as_str()) {
+            Some(image) => image.to_string(),
+            None => return true,
+          }
+        } else {
+          term.clone()
+        };
+      }
+      self.b.blank.contains(&mapped)
+    })
+  }
+}
+
+/// Groups the nodes by color.
+fn classes<'c>(colors: &HashMap<&'c str, u64>) -> HashMap<u64, Vec<&'c str>> {
+  let mut classes: HashMap<u64, Vec<&str>> = HashMap::new();
+  for (node, color) in colors {
+    classes.entry(*color).or_default().push(node);
+  }
+  classes
+}
+
+fn is_blank(term: &str) -> bool {
+  term.starts_with("_:")
+}
@@ -0,0 +1,1632 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+  collections::{BTreeMap, HashMap, HashSet},
+  io, slice,
+  sync::mpsc::Receiver,
+  thread,
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+  datastore::store::{self, GraphStore},
+  dtype::{DType, DeepSizeOf, Point},
+  error::{Error, ErrorCode},
+  formats,
+  graph::{
+    checksum,
+    entity::{self, Entity, EntityMut},
+    history::{Diff, History, Snapshot},
+    index::{Cardinality, TripleIndex},
+    isomorphism, merge,
+    observer::{self, Observers},
+    same_as, spatial, stats, Canonical, GeoHit, MergePolicy, MergeReport,
+    Mutation, Node, Predicate, Provenance, Subscription, Triple,
+  },
+  query::{Query, Update, UpdateReport},
+  random,
+  schema::{Constraints, Ontology},
+  Result,
+};
+
+#[cfg(feature = "embeddings")]
+use crate::graph::embedding::{EmbeddingIndex, Neighbor};
+#[cfg(feature = "fts")]
+use crate::graph::search::{SearchHit, SearchIndex};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | KnowledgeGraph
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `KnowledgeGraph` is an in-memory collection of `Triple`s.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   Node::Http("https://example.com/John".to_string()),
+///   Predicate::Literal("name".to_string()),
+///   Node::Literal("John Doe".into()),
+/// );
+///
+/// assert_eq!(graph.len(), 1);
+/// ```
+#[derive(Default)]
+pub struct KnowledgeGraph {
+  triples: Vec<Triple>,
+  /// Positions of the statements of every subject, predicate & object.
+  triple_index: TripleIndex,
+  /// Per-subject version, bumped on every change to the subject.
+  versions: HashMap<String, u64>,
+  /// Validates statements added through `try_add`.
+  ontology: Option<Ontology>,
+  /// Checks statements added through `try_add`.
+  constraints: Option<Constraints>,
+  /// Counter for labels handed out by `blank_node`.
+  blanks: u64,
+  /// Subscribers notified of inserted & removed statements.
+  observers: Observers,
+  /// Transaction ids and, if kept, past statements.
+  history: History,
+  /// Nodes collapsed by `resolve_same_as`, mapped to their canonical node.
+  redirects: BTreeMap<String, String>,
+  /// Full-text index over string literals.
+  #[cfg(feature = "fts")]
+  index: SearchIndex,
+  /// Vector embeddings of entities, for nearest neighbor search.
+  #[cfg(feature = "embeddings")]
+  embeddings: EmbeddingIndex,
+}
+
+/// `Change` is a single edit to a subject applied by
+/// `KnowledgeGraph::update_if_version`.
+#[derive(Clone, Debug)]
+pub enum Change {
+  /// Adds a value for the predicate, keeping existing values.
+  Add(Predicate, Node),
+  /// Replaces every value of the predicate with the given value.
+  Set(Predicate, Node),
+  /// Removes every value of the predicate.
+  Remove(Predicate),
+}
+
+impl KnowledgeGraph {
+  /// Creates an empty `KnowledgeGraph`.
+  pub fn new() -> KnowledgeGraph {
+    KnowledgeGraph {
+      triples: Vec::new(),
+      triple_index: TripleIndex::default(),
+      versions: HashMap::new(),
+      ontology: None,
+      constraints: None,
+      blanks: 0,
+      observers: Observers::default(),
+      history: History::default(),
+      redirects: BTreeMap::new(),
+      #[cfg(feature = "fts")]
+      index: SearchIndex::default(),
+      #[cfg(feature = "embeddings")]
+      embeddings: EmbeddingIndex::default(),
+    }
+  }
+
+  /// Creates an empty `KnowledgeGraph` validating statements added through
+  /// `try_insert` & `try_add` against `ontology`. The other ways of adding
+  /// statements, e.g. `insert` & `add`, don't validate them.
+  pub fn with_ontology(ontology: Ontology) -> KnowledgeGraph {
+    KnowledgeGraph {
+      ontology: Some(ontology),
+      ..KnowledgeGraph::new()
+    }
+  }
+
+  /// Returns the ontology statements are validated against, including the
+  /// statistics it observed.
+  pub fn ontology(&self) -> Option<&Ontology> {
+    self.ontology.as_ref()
+  }
+
+  /// Checks statements added through `try_insert` & `try_add` against
+  /// `constraints` from now on. Statements added any other way, e.g. with
+  /// `insert` & `add`, or already in the graph aren't checked, see
+  /// `Constraints::validate`.
+  pub fn set_constraints(&mut self, constraints: Constraints) {
+    self.constraints = Some(constraints);
+  }
+
+  /// Returns the constraints statements are checked against, including the
+  /// violations they recorded.
+  pub fn constraints(&self) -> Option<&Constraints> {
+    self.constraints.as_ref()
+  }
+
+  /// Returns the constraints statements are checked against, e.g. to
+  /// `Constraints::clear` the recorded violations.
+  pub fn constraints_mut(&mut self) -> Option<&mut Constraints> {
+    self.constraints.as_mut()
+  }
+
+  /// Adds a new forward triple to the graph.
+  ///
+  /// The triple isn't checked against the ontology or the constraints, even
+  /// if they're set; use `try_insert` for that.
+  pub fn insert(
+    &mut self,
+    source: Node,
+    predicate: Predicate,
+    destination: Node,
+  ) {
+    self.add(Triple::from_nodes(source, predicate, destination));
+  }
+
+  /// Adds a new forward triple to the graph, recording where it comes from.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate, Provenance};
+  ///
+  /// let ada = Node::Http("https://example.com/Ada".to_string());
+  /// let born = Predicate::Literal("https://schema.org/birthDate".to_string());
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert_with_provenance(
+  ///   ada.clone(),
+  ///   born.clone(),
+  ///   Node::Literal("1815-12-10".into()),
+  ///   Provenance::new("census.csv").confidence(0.95),
+  /// );
+  /// graph.insert_with_provenance(
+  ///   ada,
+  ///   born,
+  ///   Node::Literal("1815-12-01".into()),
+  ///   Provenance::new("forum-post.html").confidence(0.3),
+  /// );
+  ///
+  /// assert_eq!(graph.triples_from_source("census.csv").count(), 1);
+  /// assert_eq!(graph.triples_with_confidence(0.5).count(), 1);
+  /// ```
+  pub fn insert_with_provenance(
+    &mut self,
+    source: Node,
+    predicate: Predicate,
+    destination: Node,
+    provenance: Provenance,
+  ) {
+    let triple = Triple::from_nodes(source, predicate, destination);
+    self.add(triple.with_provenance(provenance));
+  }
+
+  /// Adds an existing `Triple` to the graph.
+  ///
+  /// The triple isn't checked against the ontology or the constraints, even
+  /// if they're set; `try_add` & `try_insert` are the only ways of adding
+  /// triples which enforce them.
+  pub fn add(&mut self, triple: Triple) {
+    self.bump(triple.source());
+    self.push(triple);
+  }
+
+  /// Adds a new forward triple to the graph after validating it against the
+  /// ontology & the constraints, if any.
+  pub fn try_insert(
+    &mut self,
+    source: Node,
+    predicate: Predicate,
+    destination: Node,
+  ) -> Result<()> {
+    self.try_add(Triple::from_nodes(source, predicate, destination))
+  }
+
+  /// Adds an existing `Triple` to the graph after validating it against the
+  /// ontology & the constraints, if any. Fails with an `UnknownPredicate`
+  /// error in `ValidationMode::Strict` if the predicate isn't declared, and
+  /// with a `ConstraintViolation` error in `Enforcement::Reject` if the
+  /// triple violates a constraint.
+  pub fn try_add(&mut self, triple: Triple) -> Result<()> {
+    if let Some(ontology) = self.ontology.as_mut() {
+      ontology.check(&triple)?;
+    }
+    let violated = self
+      .constraints
+      .as_ref()
+      .and_then(|constraints| constraints.check(self, &triple));
+    if let (Some(constraint), Some(constraints)) =
+      (violated, self.constraints.as_mut())
+    {
+      if !constraints.enforce(&triple, constraint)? {
+        return Ok(());
+      }
+    }
+    self.add(triple);
+    Ok(())
+  }
+
+  /// Returns the current version of `subject`. Every change to the subject
+  /// increments its version, subjects which were never written are at `0`.
+  ///
+  /// The version can be handed out as an HTTP `ETag` and checked with
+  /// `update_if_version` to detect concurrent edits.
+  pub fn version(&self, subject: &Node) -> u64 {
+    self
+      .versions
+      .get(&subject.to_string())
+      .copied()
+      .unwrap_or_default()
+  }
+
+  /// Applies `changes` to `subject` only if its version still equals
+  /// `expected`, returning the new version. Fails with a
+  /// `VersionMismatch` error (and leaves the graph untouched) if another
+  /// writer changed the subject in the meantime.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{Change, KnowledgeGraph, Node, Predicate};
+  ///
+  /// let john = Node::Http("https://example.com/John".to_string());
+  /// let name = Predicate::Literal("name".to_string());
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(john.clone(), name.clone(), Node::Literal("John".into()));
+  /// let version = graph.version(&john);
+  ///
+  /// let changes = vec![Change::Set(name.clone(), Node::Literal("Jo".into()))];
+  /// let version = graph.update_if_version(&john, version, changes).unwrap();
+  ///
+  /// // A writer holding the old version is rejected.
+  /// let stale = vec![Change::Remove(name)];
+  /// assert!(graph.update_if_version(&john, version - 1, stale).is_err());
+  /// assert_eq!(graph.len(), 1);
+  /// ```
+  pub fn update_if_version<I>(
+    &mut self,
+    subject: &Node,
+    expected: u64,
+    changes: I,
+  ) -> Result<u64>
+  where
+    I: IntoIterator<Item = Change>,
+  {
+    if self.version(subject) != expected {
+      return Err(Error::syntax(ErrorCode::VersionMismatch, 0, 0));
+    }
+
+    self.batch(|graph| {
+      for change in changes {
+        match change {
+          Change::Add(predicate, object) => {
+            graph.push(Triple::from_nodes(subject.clone(), predicate, object));
+          }
+          Change::Set(predicate, object) => {
+            graph.remove_values(subject, &predicate);
+            graph.push(Triple::from_nodes(subject.clone(), predicate, object));
+          }
+          Change::Remove(predicate) => graph.remove_values(subject, &predicate),
+        }
+      }
+    });
+
+    self.bump(subject);
+    Ok(self.version(subject))
+  }
+
+  /// Calls `f` for every batch of statements inserted into or removed from
+  /// the graph, until `unsubscribe`d.
+  ///
+  /// Outside of `batch`, every statement is delivered on its own, right
+  /// after the graph changed.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use std::sync::{Arc, Mutex};
+  ///
+  /// use sage::graph::{KnowledgeGraph, Mutation, Node, Predicate};
+  ///
+  /// let john = Node::Http("https://example.com/John".to_string());
+  /// let name = Predicate::Literal("name".to_string());
+  ///
+  /// let seen = Arc::new(Mutex::new(Vec::new()));
+  /// let mut graph = KnowledgeGraph::new();
+  /// let log = Arc::clone(&seen);
+  /// let subscription = graph.subscribe(move |batch: &[Mutation]| {
+  ///   log.lock().unwrap().extend_from_slice(batch);
+  /// });
+  ///
+  /// graph.insert(john.clone(), name.clone(), Node::Literal("John".into()));
+  /// assert!(graph.unsubscribe(subscription));
+  /// graph.insert(john.clone(), name.clone(), Node::Literal("Jo".into()));
+  ///
+  /// let seen = seen.lock().unwrap();
+  /// assert_eq!(
+  ///   *seen,
+  ///   vec![Mutation::Inserted(john, name, Node::Literal("John".into()))]
+  /// );
+  /// ```
+  pub fn subscribe<F>(&mut self, f: F) -> Subscription
+  where
+    F: FnMut(&[Mutation]) + Send + 'static,
+  {
+    self.observers.subscribe(Box::new(f))
+  }
+
+  /// Stops notifying `subscription`, returning `false` if it wasn't
+  /// subscribed.
+  pub fn unsubscribe(&mut self, subscription: Subscription) -> bool {
+    self.observers.unsubscribe(subscription)
+  }
+
+  /// Subscribes a channel holding up to `capacity` batches, e.g. to
+  /// replicate the graph or invalidate caches on another thread.
+  ///
+  /// Writers are never blocked by a slow receiver: while the channel is
+  /// full, batches are coalesced & sent along with the next one that fits.
+  /// Batches are discarded once the receiver is dropped.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Mutation, Node, Predicate};
+  ///
+  /// let john = Node::Http("https://example.com/John".to_string());
+  /// let name = Predicate::Literal("name".to_string());
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// let (_, changes) = graph.watch(1);
+  ///
+  /// graph.batch(|graph| {
+  ///   graph.insert(john.clone(), name.clone(), Node::Literal("John".into()));
+  ///   graph.insert(john.clone(), name.clone(), Node::Literal("Jo".into()));
+  /// });
+  /// // The channel is full, so this one waits for the next batch.
+  /// graph.insert(john.clone(), name.clone(), Node::Literal("J".into()));
+  ///
+  /// assert_eq!(changes.recv().unwrap().len(), 2);
+  /// assert!(changes.try_recv().is_err());
+  /// ```
+  pub fn watch(
+    &mut self,
+    capacity: usize,
+  ) -> (Subscription, Receiver<Vec<Mutation>>) {
+    let (callback, receiver) = observer::channel(capacity);
+    (self.observers.subscribe(callback), receiver)
+  }
+
+  /// Runs `f` on the graph, delivering every statement it inserts or
+  /// removes to subscribers as one batch once it returns.
+  ///
+  /// Everything `f` changes also shares a single version (see
+  /// `current_version`).
+  pub fn batch<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+    self.begin();
+    let result = f(self);
+    self.end();
+    result
+  }
+
+  fn begin(&mut self) {
+    self.observers.begin();
+    self.history.begin();
+  }
+
+  fn end(&mut self) {
+    self.history.end();
+    self.observers.end();
+  }
+
+  /// Returns `true` if mutations need to be recorded.
+  fn is_tracked(&self) -> bool {
+    self.observers.is_active() || self.history.is_enabled()
+  }
+
+  fn notify(&mut self, mutation: Mutation) {
+    self.history.record(&mutation);
+    if self.observers.is_active() {
+      self.observers.record(mutation);
+    }
+  }
+
+  /// Returns the id of the last transaction which changed the graph, `0`
+  /// for a new graph. Every insertion & removal is a transaction of its
+  /// own, unless grouped by `batch`.
+  pub fn current_version(&self) -> u64 {
+    self.history.version()
+  }
+
+  /// Starts keeping every statement along with the versions it was valid
+  /// in, so `at_version` & `diff` can look back at any version from now on.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  ///
+  /// let john = Node::Http("https://example.com/John".to_string());
+  /// let name = Predicate::Literal("name".to_string());
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.keep_history();
+  /// graph.insert(john.clone(), name.clone(), Node::Literal("John".into()));
+  /// let v1 = graph.current_version();
+  ///
+  /// let version = graph.version(&john);
+  /// let rename = vec![sage::graph::Change::Set(name, Node::Literal("Jo".into()))];
+  /// graph.update_if_version(&john, version, rename).unwrap();
+  /// let v2 = graph.current_version();
+  ///
+  /// let then = graph.at_version(v1).unwrap();
+  /// let names: Vec<_> = then.triples_matching(Some(&john), Some("name"), None)
+  ///   .map(|t| t.destination().to_string())
+  ///   .collect();
+  /// assert_eq!(names, vec!["John"]);
+  ///
+  /// let diff = graph.diff(v1, v2).unwrap();
+  /// assert_eq!(diff.added.len(), 1);
+  /// assert_eq!(diff.removed.len(), 1);
+  /// assert!(graph.at_version(v2 + 1).is_none());
+  /// ```
+  pub fn keep_history(&mut self) {
+    self.history.enable(self.triples.iter());
+  }
+
+  /// Returns the graph as it was at `version`, or `None` if the version is
+  /// in the future or predates `keep_history`.
+  pub fn at_version(&self, version: u64) -> Option<Snapshot<'_>> {
+    if !self.history.covers(version) {
+      return None;
+    }
+    Some(Snapshot::new(&self.history, version))
+  }
+
+  /// Returns the statements added & removed from version `from` to `to`,
+  /// or `None` if either isn't covered by `at_version`.
+  pub fn diff(&self, from: u64, to: u64) -> Option<Diff<'_>> {
+    if !self.history.covers(from) || !self.history.covers(to) {
+      return None;
+    }
+    let (added, removed) = self.history.diff(from, to);
+    Some(Diff { added, removed })
+  }
+
+  fn push(&mut self, triple: Triple) {
+    #[cfg(feature = "fts")]
+    self.index.insert(&triple);
+    self.history.stamp();
+    if self.is_tracked() {
+      self.notify(Mutation::inserted(&triple));
+    }
+    self.triple_index.insert(&triple, self.triples.len());
+    self.triples.push(triple);
+  }
+
+  /// Rebuilds the index of statement positions, after removing statements.
+  fn reindex(&mut self) {
+    self.triple_index = TripleIndex::new(&self.triples);
+  }
+
+  pub(crate) fn remove_values(
+    &mut self,
+    subject: &Node,
+    predicate: &Predicate,
+  ) {
+    let predicate = predicate.to_string();
+    #[cfg(feature = "fts")]
+    self.index.remove(subject, &predicate);
+    let tracked = self.is_tracked();
+    let len = self.triples.len();
+    let mut removed = Vec::new();
+    self.triples.retain(|t| {
+      let keep =
+        t.source() != subject || t.predicate().to_string() != predicate;
+      if !keep && tracked {
+        removed.push(Mutation::removed(t));
+      }
+      keep
+    });
+
+    if self.triples.len() == len {
+      return;
+    }
+    self.reindex();
+    self.begin();
+    self.history.stamp();
+    removed.into_iter().for_each(|m| self.notify(m));
+    self.end();
+  }
+
+  /// Removes every statement in `statements`, returning how many were
+  /// removed.
+  pub(crate) fn remove_statements(
+    &mut self,
+    statements: &HashSet<(Node, String, Node)>,
+  ) -> usize {
+    if statements.is_empty() {
+      return 0;
+    }
+    let tracked = self.is_tracked();
+    let mut removed = Vec::new();
+    let mut subjects = Vec::new();
+    self.triples.retain(|t| {
+      let statement = (
+        t.source().clone(),
+        t.predicate().to_string(),
+        t.destination().clone(),
+      );
+      let keep = !statements.contains(&statement);
+      if !keep {
+        subjects.push(statement.0);
+        if tracked {
+          removed.push(Mutation::removed(t));
+        }
+      }
+      keep
+    });
+
+    if subjects.is_empty() {
+      return 0;
+    }
+    self.reindex();
+    subjects.iter().for_each(|subject| self.bump(subject));
+    self.begin();
+    self.history.stamp();
+    removed.into_iter().for_each(|m| self.notify(m));
+    self.end();
+    #[cfg(feature = "fts")]
+    {
+      self.index = SearchIndex::default();
+      for triple in &self.triples {
+        self.index.insert(triple);
+      }
+    }
+    subjects.len()
+  }
+
+  fn bump(&mut self, subject: &Node) {
+    match subject {
+      Node::Multiple(nodes) => nodes.iter().for_each(|n| self.bump(n)),
+      subject => *self.versions.entry(subject.to_string()).or_default() += 1,
+    }
+  }
+
+  /// Returns a new labelled blank node whose label isn't used in the graph
+  /// yet, e.g. `_:b0`.
+  pub fn blank_node(&mut self) -> Node {
+    let used = self.blank_labels();
+    loop {
+      let label = format!("b{}", self.blanks);
+      self.blanks += 1;
+      if !used.contains(&label) {
+        return Node::BlankId(label);
+      }
+    }
+  }
+
+  /// Replaces every blank node with a stable IRI made of `base` and its
+  /// label, returning the mapping from each IRI to the label it replaced.
+  /// Anonymous `Node::Blank`s are labelled first, each as a distinct node.
+  ///
+  /// Skolemized graphs can be merged deterministically: equal labels map to
+  /// equal IRIs. `deskolemize` turns the IRIs back into blank nodes.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// let address = graph.blank_node();
+  /// graph.insert(
+  ///   Node::Http("https://example.com/Ada".to_string()),
+  ///   Predicate::Literal("https://schema.org/address".to_string()),
+  ///   address.clone(),
+  /// );
+  /// graph.insert(
+  ///   address.clone(),
+  ///   Predicate::Literal("https://schema.org/addressLocality".to_string()),
+  ///   Node::Literal("London".into()),
+  /// );
+  ///
+  /// let mapping = graph.skolemize("https://example.com/.well-known/genid/");
+  /// let iri = "https://example.com/.well-known/genid/b0";
+  /// assert_eq!(mapping[iri], "b0");
+  /// let skolem = Node::Http(iri.to_string());
+  /// assert_eq!(graph.matches(Some(&skolem), None, None).count(), 1);
+  ///
+  /// graph.deskolemize(&mapping);
+  /// assert_eq!(graph.matches(Some(&address), None, None).count(), 1);
+  /// ```
+  pub fn skolemize(&mut self, base: &str) -> BTreeMap<String, String> {
+    let mut used = self.blank_labels();
+    let mut next = 0;
+    let mut mapping = BTreeMap::new();
+    self.map_nodes(&mut |node| {
+      let label = match node {
+        Node::BlankId(label) => label.clone(),
+        Node::Blank => loop {
+          let label = format!("b{}", next);
+          next += 1;
+          if used.insert(label.clone()) {
+            break label;
+          }
+        },
+        _ => return None,
+      };
+      let iri = format!("{}{}", base, label);
+      mapping.insert(iri.clone(), label);
+      Some(Node::Http(iri))
+    });
+    mapping
+  }
+
+  /// Turns the IRIs of a `skolemize` mapping back into labelled blank
+  /// nodes.
+  pub fn deskolemize(&mut self, mapping: &BTreeMap<String, String>) {
+    self.map_nodes(&mut |node| match node {
+      Node::Http(iri) => mapping.get(iri).map(|l| Node::BlankId(l.clone())),
+      _ => None,
+    });
+  }
+
+  /// Labels of every labelled blank node in the graph.
+  pub(crate) fn blank_labels(&self) -> HashSet<String> {
+    fn collect(node: &Node, labels: &mut HashSet<String>) {
+      match node {
+        Node::BlankId(label) => {
+          labels.insert(label.clone());
+        }
+        Node::Multiple(nodes) => nodes.iter().for_each(|n| collect(n, labels)),
+        _ => {}
+      }
+    }
+
+    let mut labels = HashSet::new();
+    for triple in &self.triples {
+      collect(triple.source(), &mut labels);
+      collect(triple.destination(), &mut labels);
+    }
+    labels
+  }
+
+  /// Replaces every subject & object for which `f` returns a new node,
+  /// keeping versions & indexes in sync.
+  fn map_nodes(&mut self, f: &mut dyn FnMut(&Node) -> Option<Node>) {
+    fn map(node: &Node, f: &mut dyn FnMut(&Node) -> Option<Node>) -> Node {
+      match node {
+        Node::Multiple(nodes) => {
+          Node::Multiple(nodes.iter().map(|n| map(n, f)).collect())
+        }
+        node => f(node).unwrap_or_else(|| node.clone()),
+      }
+    }
+
+    self.begin();
+    let triples = std::mem::take(&mut self.triples);
+    for triple in triples {
+      let source = map(triple.source(), f);
+      if source != *triple.source() {
+        let old = triple.source().to_string();
+        if let Some(version) = self.versions.remove(&old) {
+          self.versions.insert(source.to_string(), version);
+        }
+      }
+      let destination = map(triple.destination(), f);
+      let mapped = triple.with_nodes(source, destination);
+      let changed = mapped.source() != triple.source()
+        || mapped.destination() != triple.destination();
+      if changed {
+        self.history.stamp();
+        if self.is_tracked() {
+          self.notify(Mutation::removed(&triple));
+          self.notify(Mutation::inserted(&mapped));
+        }
+      }
+      self.triples.push(mapped);
+    }
+    self.end();
+    self.reindex();
+
+    #[cfg(feature = "fts")]
+    {
+      self.index = SearchIndex::default();
+      for triple in &self.triples {
+        self.index.insert(triple);
+      }
+    }
+  }
+
+  /// Merges the statements of `other` into the graph, returning a conflict
+  /// report for graphs built independently of each other.
+  ///
+  /// Duplicate statements are added once. The report is a document like
+  ///
+  /// ```json
+  /// {
+  ///   "added": 3,
+  ///   "conflicts": [
+  ///     {"kind": "functional", "subject": "...", "predicate": "...",
+  ///      "ours": [...], "theirs": [...], "resolution": "ours"},
+  ///     {"kind": "type", "subject": "...", "ours": [...], "theirs": [...]}
+  ///   ],
+  ///   "renamed": {"b0": "b3"}
+  /// }
+  /// ```
+  ///
+  /// where a `functional` conflict is a subject with different values for
+  /// a predicate `policy` declares functional, resolved by the policy, and
+  /// a `type` conflict a subject whose `rdf:type`s in both graphs have
+  /// nothing in common. Types are merged regardless. See `merge` for the
+  /// report as a `MergeReport`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, MergePolicy, Node, Predicate};
+  ///
+  /// let ada = Node::Http("https://example.com/Ada".to_string());
+  /// let born = Predicate::Literal("https://schema.org/birthDate".to_string());
+  /// let name = Predicate::Literal("https://schema.org/name".to_string());
+  ///
+  /// let mut ours = KnowledgeGraph::new();
+  /// ours.insert(ada.clone(), born.clone(), Node::Literal("1815-12-10".into()));
+  ///
+  /// let mut theirs = KnowledgeGraph::new();
+  /// theirs.insert(ada.clone(), born, Node::Literal("1815-12-01".into()));
+  /// theirs.insert(ada, name, Node::Literal("Ada Lovelace".into()));
+  ///
+  /// let policy = MergePolicy::new().functional("https://schema.org/birthDate");
+  /// let report = ours.merge_from(&theirs, policy);
+  ///
+  /// assert_eq!(report["added"], 1);
+  /// assert_eq!(report["conflicts"][0]["kind"], "functional");
+  /// assert_eq!(report["conflicts"][0]["theirs"][0], "1815-12-01");
+  /// assert_eq!(ours.len(), 2);
+  /// ```
+  pub fn merge_from(
+    &mut self,
+    other: &KnowledgeGraph,
+    policy: MergePolicy,
+  ) -> DType {
+    self.merge(other, policy).to_dtype()
+  }
+
+  /// Merges the statements of `other` into the graph as `policy` dictates,
+  /// returning the statements added & conflicts found.
+  ///
+  /// The policy decides whether statements both graphs contain are added
+  /// again, which values of a clashing functional property are kept, and
+  /// whether blank node labels of `other` already used in the graph are
+  /// renamed to fresh ones (the default) or denote the same node.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{
+  ///   Conflict, KnowledgeGraph, MergePolicy, Node, Predicate, Resolution,
+  /// };
+  ///
+  /// let ada = Node::Http("https://example.com/Ada".to_string());
+  /// let born = Predicate::Literal("https://schema.org/birthDate".to_string());
+  /// let address = Predicate::Literal("https://schema.org/address".to_string());
+  ///
+  /// let mut ours = KnowledgeGraph::new();
+  /// ours.insert(ada.clone(), born.clone(), Node::Literal("1815-12-10".into()));
+  /// ours.insert(ada.clone(), address.clone(), Node::BlankId("b0".into()));
+  ///
+  /// let mut theirs = KnowledgeGraph::new();
+  /// theirs.insert(ada.clone(), born.clone(), Node::Literal("1815-12-01".into()));
+  /// theirs.insert(ada.clone(), address, Node::BlankId("b0".into()));
+  ///
+  /// let policy = MergePolicy::new()
+  ///   .functional("https://schema.org/birthDate")
+  ///   .resolution(Resolution::Theirs);
+  /// let report = ours.merge(&theirs, policy);
+  ///
+  /// // Their `_:b0` isn't ours, so it was renamed.
+  /// assert_eq!(report.renamed["b0"], "b1");
+  /// assert_eq!(report.added.len(), 2);
+  /// assert!(matches!(report.conflicts[0], Conflict::Functional { .. }));
+  ///
+  /// let iri = born.to_string();
+  /// let dates: Vec<_> = ours.matches(Some(&ada), Some(&iri), None).collect();
+  /// assert_eq!(dates.len(), 1);
+  /// assert_eq!(dates[0].destination(), &Node::Literal("1815-12-01".into()));
+  /// ```
+  pub fn merge(
+    &mut self,
+    other: &KnowledgeGraph,
+    policy: MergePolicy,
+  ) -> MergeReport {
+    merge::merge(self, other, &policy)
+  }
+
+  /// Collapses every cluster of nodes connected by `owl:sameAs` into a
+  /// single canonical node picked by `canonical`, returning the mapping
+  /// from each collapsed node to its canonical node.
+  ///
+  /// Statements about or pointing to collapsed nodes are rewritten to the
+  /// canonical node, the `owl:sameAs` statements are dropped, and so are
+  /// statements duplicated by the rewrite. The mapping is also added to the
+  /// graph's redirect table, see `redirect`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{Canonical, KnowledgeGraph, Node, Predicate};
+  ///
+  /// let ours = Node::Http("https://example.com/ada".to_string());
+  /// let wikidata = Node::Http("http://www.wikidata.org/entity/Q7259".to_string());
+  /// let same_as = "http://www.w3.org/2002/07/owl#sameAs";
+  /// let name = Predicate::Literal("https://schema.org/name".to_string());
+  /// let born = Predicate::Literal("https://schema.org/birthDate".to_string());
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(ours.clone(), name.clone(), Node::Literal("Ada".into()));
+  /// graph.insert(wikidata.clone(), name, Node::Literal("Ada".into()));
+  /// graph.insert(wikidata.clone(), born, Node::Literal("1815-12-10".into()));
+  /// graph.insert(ours.clone(), Predicate::Literal(same_as.into()), wikidata);
+  ///
+  /// let canonical = Canonical::Prefer(vec!["https://example.com/".into()]);
+  /// let redirects = graph.resolve_same_as(canonical);
+  ///
+  /// let q7259 = "http://www.wikidata.org/entity/Q7259";
+  /// assert_eq!(redirects[q7259], "https://example.com/ada");
+  /// assert_eq!(graph.redirect(q7259), "https://example.com/ada");
+  /// assert_eq!(graph.len(), 2);
+  /// assert_eq!(graph.matches(Some(&ours), None, None).count(), 2);
+  /// ```
+  pub fn resolve_same_as(
+    &mut self,
+    canonical: Canonical,
+  ) -> BTreeMap<String, String> {
+    let redirects = same_as::redirects(self, &canonical);
+    if redirects.is_empty() {
+      return redirects;
+    }
+    self.map_nodes(&mut |node| {
+      let target = redirects.get(&node.to_string())?;
+      Some(entity::node(target))
+    });
+
+    let tracked = self.is_tracked();
+    let mut seen = HashSet::new();
+    let mut removed = Vec::new();
+    self.triples.retain(|t| {
+      let predicate = t.predicate().to_string();
+      let same_as = predicate == same_as::OWL_SAME_AS
+        && !same_as::ids(t.source()).is_empty()
+        && !same_as::ids(t.destination()).is_empty();
+      let statement = (t.source().clone(), predicate, t.destination().clone());
+      let keep = !same_as && seen.insert(statement);
+      if !keep && tracked {
+        removed.push(Mutation::removed(t));
+      }
+      keep
+    });
+    self.reindex();
+    self.begin();
+    self.history.stamp();
+    removed.into_iter().for_each(|m| self.notify(m));
+    self.end();
+    #[cfg(feature = "fts")]
+    {
+      self.index = SearchIndex::default();
+      for triple in &self.triples {
+        self.index.insert(triple);
+      }
+    }
+    #[cfg(feature = "embeddings")]
+    for (node, canonical) in &redirects {
+      if let Some(embedding) = self.embeddings.get(node).map(<[f32]>::to_vec) {
+        self.embeddings.remove(node);
+        if self.embeddings.get(canonical).is_none() {
+          // Same dimensions as every other embedding, so this can't fail.
+          let _ = self.embeddings.insert(canonical, embedding);
+        }
+      }
+    }
+
+    for target in self.redirects.values_mut() {
+      if let Some(canonical) = redirects.get(target) {
+        *target = canonical.clone();
+      }
+    }
+    self
+      .redirects
+      .extend(redirects.iter().map(|(a, b)| (a.clone(), b.clone())));
+    redirects
+  }
+
+  /// Returns the canonical node `iri` was collapsed into by
+  /// `resolve_same_as`, or `iri` itself.
+  pub fn redirect<'a>(&'a self, iri: &'a str) -> &'a str {
+    self.redirects.get(iri).map_or(iri, String::as_str)
+  }
+
+  /// Returns every node collapsed by `resolve_same_as`, mapped to its
+  /// canonical node.
+  pub fn redirects(&self) -> &BTreeMap<String, String> {
+    &self.redirects
+  }
+
+  /// Returns `n` distinct triples picked at random (or every triple if the
+  /// graph is smaller), in insertion order.
+  ///
+  /// Draws from `sage::random`, so seeding it makes samples reproducible.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  /// use sage::random;
+  ///
+  /// let graph: KnowledgeGraph = (0..100)
+  ///   .map(|i| {
+  ///     sage::graph::Triple::from_nodes(
+  ///       Node::Http(format!("https://example.com/{}", i)),
+  ///       Predicate::Literal("rank".to_string()),
+  ///       Node::Literal(i.into()),
+  ///     )
+  ///   })
+  ///   .collect();
+  ///
+  /// random::seed(7);
+  /// let first: Vec<_> = graph.sample(5).iter().map(|t| t.to_string()).collect();
+  /// random::seed(7);
+  /// let again: Vec<_> = graph.sample(5).iter().map(|t| t.to_string()).collect();
+  /// assert_eq!(first.len(), 5);
+  /// assert_eq!(first, again);
+  /// random::entropy();
+  /// ```
+  pub fn sample(&self, n: usize) -> Vec<&Triple> {
+    let n = n.min(self.triples.len());
+    let mut picked = random::with_rng(|rng| {
+      rand::seq::index::sample(rng, self.triples.len(), n).into_vec()
+    });
+    picked.sort_unstable();
+    picked.into_iter().map(|i| &self.triples[i]).collect()
+  }
+
+  /// Streams the graph into `writer` as newline-delimited JSON, one entity
+  /// per line, e.g. for Elasticsearch or BigQuery loaders.
+  ///
+  /// Each line is a JSON-LD style node object: the subject under `@id`,
+  /// `rdf:type` values under `@type` and every other predicate keyed by its
+  /// IRI. Links to other nodes are written as `{"@id": ...}`, and predicates
+  /// with several values as arrays. Only one entity is built at a time.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  /// use sage::json;
+  ///
+  /// let ada = Node::Http("https://example.com/Ada".to_string());
+  /// let charles = Node::Http("https://example.com/Charles".to_string());
+  /// let name = Predicate::Literal("https://schema.org/name".to_string());
+  /// let knows = Predicate::Literal("https://schema.org/knows".to_string());
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(ada.clone(), name.clone(), Node::Literal("Ada".into()));
+  /// graph.insert(charles.clone(), name, Node::Literal("Charles".into()));
+  /// graph.insert(ada, knows, charles);
+  ///
+  /// let mut out = Vec::new();
+  /// graph.export_ndjson(&mut out).unwrap();
+  ///
+  /// let lines: Vec<_> = std::str::from_utf8(&out).unwrap().lines().collect();
+  /// assert_eq!(lines.len(), 2);
+  /// let ada: sage::dtype::DType = json::from_str(lines[0]).unwrap();
+  /// assert_eq!(ada["https://schema.org/name"], json!("Ada"));
+  /// assert_eq!(
+  ///   ada["https://schema.org/knows"],
+  ///   json!({ "@id": "https://example.com/Charles" })
+  /// );
+  /// ```
+  pub fn export_ndjson<W: io::Write>(&self, writer: W) -> Result<()> {
+    formats::write_ndjson(self, writer)
+  }
+
+  /// Persists the graph into `store`, replacing the statements it held and
+  /// writing only what changed, then flushes it.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  /// use sage::store::{GraphStore, MemoryStore};
+  ///
+  /// let ada = Node::Http("https://example.com/Ada".to_string());
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(
+  ///   ada.clone(),
+  ///   Predicate::Literal("https://schema.org/name".to_string()),
+  ///   Node::Literal("Ada".into()),
+  /// );
+  /// graph.insert(
+  ///   ada,
+  ///   Predicate::Literal("https://schema.org/address".to_string()),
+  ///   Node::BlankId("home".to_string()),
+  /// );
+  ///
+  /// let mut store = MemoryStore::new();
+  /// graph.save_to(&mut store).unwrap();
+  ///
+  /// // Statements are keyed by subject.
+  /// let about_ada = store.scan_prefix(b"spo:<https://example.com/Ada>\t");
+  /// assert_eq!(about_ada.unwrap().len(), 2);
+  ///
+  /// let loaded = KnowledgeGraph::load_from(&store).unwrap();
+  /// assert_eq!(loaded.checksum(), graph.checksum());
+  /// ```
+  pub fn save_to<S: GraphStore + ?Sized>(&self, store: &mut S) -> Result<()> {
+    store::save(self, store)
+  }
+
+  /// Reads the statements persisted in `store` (see `save_to`) into a new
+  /// graph.
+  pub fn load_from<S: GraphStore + ?Sized>(
+    store: &S,
+  ) -> Result<KnowledgeGraph> {
+    store::load(store)
+  }
+
+  /// Returns a view of the subject identified by `iri` (`_:label` for a
+  /// blank node), grouping every statement about it.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  /// use sage::json;
+  ///
+  /// let ada = Node::Http("https://example.com/Ada".to_string());
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(
+  ///   ada.clone(),
+  ///   Predicate::Literal("@type".to_string()),
+  ///   Node::Http("https://schema.org/Person".to_string()),
+  /// );
+  /// graph.insert(
+  ///   ada,
+  ///   Predicate::Literal("https://schema.org/name".to_string()),
+  ///   Node::Literal("Ada".into()),
+  /// );
+  ///
+  /// let entity = graph.entity("https://example.com/Ada");
+  /// assert_eq!(entity.types(), vec!["https://schema.org/Person"]);
+  /// assert_eq!(
+  ///   entity.get("https://schema.org/name"),
+  ///   vec![&Node::Literal("Ada".into())]
+  /// );
+  /// assert_eq!(
+  ///   entity.to_dtype(),
+  ///   json!({
+  ///     "@id": "https://example.com/Ada",
+  ///     "@type": "https://schema.org/Person",
+  ///     "https://schema.org/name": "Ada"
+  ///   })
+  /// );
+  /// ```
+  pub fn entity(&self, iri: &str) -> Entity<'_> {
+    Entity::new(self, entity::node(iri))
+  }
+
+  /// Returns a mutable view of the subject identified by `iri`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node};
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph
+  ///   .entity_mut("https://example.com/Ada")
+  ///   .add("https://schema.org/name", Node::Literal("Ada".into()))
+  ///   .set("https://schema.org/name", Node::Literal("Ada Lovelace".into()));
+  ///
+  /// let ada = graph.entity("https://example.com/Ada");
+  /// assert_eq!(
+  ///   ada.get("https://schema.org/name"),
+  ///   vec![&Node::Literal("Ada Lovelace".into())]
+  /// );
+  /// assert_eq!(graph.version(ada.node()), 2);
+  /// ```
+  pub fn entity_mut(&mut self, iri: &str) -> EntityMut<'_> {
+    EntityMut::new(self, entity::node(iri))
+  }
+
+  /// Returns an iterator over every `Triple` in the graph.
+  pub fn triples(&self) -> slice::Iter<'_, Triple> {
+    self.triples.iter()
+  }
+
+  /// Consumes the graph, returning its triples in insertion order.
+  pub(crate) fn into_triples(self) -> Vec<Triple> {
+    self.triples
+  }
+
+  /// Returns every triple whose provenance names `source`.
+  pub fn triples_from_source<'a>(
+    &'a self,
+    source: &'a str,
+  ) -> impl Iterator<Item = &'a Triple> {
+    self.triples.iter().filter(move |triple| {
+      triple
+        .provenance()
+        .is_some_and(|p| p.source.as_deref() == Some(source))
+    })
+  }
+
+  /// Returns every triple with a confidence of at least `threshold`.
+  /// Triples without a confidence are taken for certain.
+  pub fn triples_with_confidence(
+    &self,
+    threshold: f64,
+  ) -> impl Iterator<Item = &Triple> {
+    self
+      .triples
+      .iter()
+      .filter(move |triple| triple.confidence().unwrap_or(1.0) >= threshold)
+  }
+
+  /// Returns every triple matching the given pattern, in insertion order.
+  /// `None` matches anything. The predicate is compared against its full
+  /// IRI.
+  ///
+  /// Only the statements of the most selective fixed term are scanned, so
+  /// a lookup costs the number of statements of, e.g., its subject rather
+  /// than the size of the graph.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  ///
+  /// let john = Node::Http("https://example.com/John".to_string());
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(
+  ///   john.clone(),
+  ///   Predicate::Literal("name".to_string()),
+  ///   Node::Literal("John Doe".into()),
+  /// );
+  /// graph.insert(
+  ///   john.clone(),
+  ///   Predicate::Literal("knows".to_string()),
+  ///   Node::Http("https://example.com/Jane".to_string()),
+  /// );
+  ///
+  /// assert_eq!(graph.matches(Some(&john), None, None).count(), 2);
+  /// assert_eq!(graph.matches(None, Some("knows"), None).count(), 1);
+  /// ```
+  pub fn matches<'a>(
+    &'a self,
+    source: Option<&'a Node>,
+    predicate: Option<&'a str>,
+    destination: Option<&'a Node>,
+  ) -> impl Iterator<Item = &'a Triple> + 'a {
+    let candidates: Box<dyn Iterator<Item = &'a Triple>> =
+      match self.triple_index.candidates(source, predicate, destination) {
+        Some(positions) => {
+          Box::new(positions.iter().map(move |&i| &self.triples[i]))
+        }
+        None => Box::new(self.triples.iter()),
+      };
+    candidates.filter(move |triple| {
+      source.is_none_or(|s| triple.source() == s)
+        && predicate.is_none_or(|p| triple.predicate().to_string() == p)
+        && destination.is_none_or(|d| triple.destination() == d)
+    })
+  }
+
+  /// Returns the number of statements matching the given pattern (like
+  /// `matches`) & their distinct terms, for query planning.
+  pub(crate) fn cardinality(
+    &self,
+    source: Option<&Node>,
+    predicate: Option<&str>,
+    destination: Option<&Node>,
+  ) -> Cardinality {
+    let fixed = [source.is_some(), predicate.is_some(), destination.is_some()];
+    let statements = match fixed.iter().filter(|&&fixed| fixed).count() {
+      0 => self.len(),
+      1 => self
+        .triple_index
+        .candidates(source, predicate, destination)
+        .map_or(0, <[usize]>::len),
+      _ => self.matches(source, predicate, destination).count(),
+    };
+    self
+      .triple_index
+      .cardinality(source, predicate, destination, statements)
+  }
+
+  /// Evaluates `query`, returning one object per solution which maps every
+  /// variable (without the `?`) to its value. Literals are bound to their
+  /// value, other nodes to their IRI.
+  pub fn query(&self, query: &Query) -> Vec<DType> {
+    query.rows(self)
+  }
+
+  /// Applies a SPARQL Update request, see `Update`. Subscribers receive
+  /// every statement it inserts or removes as one batch.
+  pub fn update(&mut self, update: &Update) -> UpdateReport {
+    self.batch(|graph| update.apply(graph))
+  }
+
+  /// Evaluates `query` and deserializes every binding row into `T`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use serde_derive::Deserialize;
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  /// use sage::query::Query;
+  ///
+  /// #[derive(Deserialize)]
+  /// struct PersonRow {
+  ///   person: String,
+  ///   name: String,
+  /// }
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(
+  ///   Node::Http("https://example.com/John".to_string()),
+  ///   Predicate::Literal("https://schema.org/name".to_string()),
+  ///   Node::Literal("John".into()),
+  /// );
+  ///
+  /// let query =
+  ///   Query::new().pattern("?person", "https://schema.org/name", "?name");
+  /// let rows = graph.select::<PersonRow>(&query).unwrap();
+  ///
+  /// assert_eq!(rows[0].person, "https://example.com/John");
+  /// assert_eq!(rows[0].name, "John");
+  /// ```
+  pub fn select<T>(&self, query: &Query) -> Result<Vec<T>>
+  where
+    T: DeserializeOwned,
+  {
+    query.select(self)
+  }
+
+  /// Searches the string literals of the graph, returning every literal
+  /// containing all words of `query` (case-insensitive). Each word also
+  /// matches as a prefix, so `"ada love"` finds `"Ada Lovelace"`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  ///
+  /// let ada = Node::Http("https://example.com/Ada".to_string());
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(
+  ///   ada.clone(),
+  ///   Predicate::Literal("https://schema.org/name".to_string()),
+  ///   Node::Literal("Ada Lovelace".into()),
+  /// );
+  /// graph.insert(
+  ///   Node::Http("https://example.com/Charles".to_string()),
+  ///   Predicate::Literal("https://schema.org/name".to_string()),
+  ///   Node::Literal("Charles Babbage".into()),
+  /// );
+  ///
+  /// let hits = graph.search("ada love");
+  /// assert_eq!(hits.len(), 1);
+  /// assert_eq!(hits[0].subject, ada);
+  /// assert_eq!(hits[0].predicate, "https://schema.org/name");
+  /// ```
+  #[cfg(feature = "fts")]
+  pub fn search(&self, query: &str) -> Vec<SearchHit> {
+    self.index.search(query)
+  }
+
+  /// Sets the vector embedding of the entity `iri`, replacing any previous
+  /// one. Every embedding in a graph must have the same number of
+  /// dimensions; embeddings are compared by cosine similarity.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::KnowledgeGraph;
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.set_embedding("sage:Ada", vec![1.0, 0.0]).unwrap();
+  /// assert!(graph.set_embedding("sage:Alan", vec![1.0]).is_err());
+  /// assert_eq!(graph.embedding("sage:Ada"), Some(&[1.0, 0.0][..]));
+  /// ```
+  #[cfg(feature = "embeddings")]
+  pub fn set_embedding(
+    &mut self,
+    iri: &str,
+    embedding: Vec<f32>,
+  ) -> Result<()> {
+    self.embeddings.insert(iri, embedding)
+  }
+
+  /// Returns the vector embedding of the entity `iri`.
+  #[cfg(feature = "embeddings")]
+  pub fn embedding(&self, iri: &str) -> Option<&[f32]> {
+    self.embeddings.get(iri)
+  }
+
+  /// Removes the vector embedding of the entity `iri`, returning `false` if
+  /// it had none.
+  #[cfg(feature = "embeddings")]
+  pub fn remove_embedding(&mut self, iri: &str) -> bool {
+    self.embeddings.remove(iri)
+  }
+
+  /// Returns up to `k` entities whose embeddings are most similar to that
+  /// of `iri`, most similar first. The search is approximate, over an HNSW
+  /// index maintained by `set_embedding`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node};
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.set_embedding("sage:Ada", vec![0.9, 0.1, 0.0]).unwrap();
+  /// graph.set_embedding("sage:Alan", vec![0.8, 0.2, 0.1]).unwrap();
+  /// graph.set_embedding("sage:Paris", vec![0.0, 0.1, 0.9]).unwrap();
+  ///
+  /// let neighbors = graph.nearest("sage:Ada", 2);
+  /// assert_eq!(neighbors.len(), 2);
+  /// assert_eq!(neighbors[0].node, Node::Http("sage:Alan".to_string()));
+  /// assert!(neighbors[0].similarity > neighbors[1].similarity);
+  /// ```
+  #[cfg(feature = "embeddings")]
+  pub fn nearest(&self, iri: &str, k: usize) -> Vec<Neighbor> {
+    self.embeddings.nearest(iri, k)
+  }
+
+  /// Returns up to `k` entities whose embeddings are most similar to
+  /// `embedding`, most similar first.
+  #[cfg(feature = "embeddings")]
+  pub fn nearest_to(&self, embedding: &[f32], k: usize) -> Vec<Neighbor> {
+    self.embeddings.nearest_to(embedding, k)
+  }
+
+  /// Returns a hex encoded SHA-256 checksum of the graph's content, so a
+  /// leader & replica can cheaply verify they hold identical data.
+  ///
+  /// The checksum only depends on the set of statements (with
+  /// multiplicity), not on the order they were inserted in, and is hashed
+  /// in parallel over one partition per available CPU.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  ///
+  /// let john = Node::Http("https://example.com/John".to_string());
+  /// let name = Predicate::Literal("https://schema.org/name".to_string());
+  /// let age = Predicate::Literal("https://schema.org/age".to_string());
+  ///
+  /// let mut leader = KnowledgeGraph::new();
+  /// leader.insert(john.clone(), name.clone(), Node::Literal("John".into()));
+  /// leader.insert(john.clone(), age.clone(), Node::Literal(42.into()));
+  ///
+  /// let mut replica = KnowledgeGraph::new();
+  /// replica.insert(john.clone(), age.clone(), Node::Literal(42.into()));
+  /// replica.insert(john.clone(), name, Node::Literal("John".into()));
+  /// assert_eq!(leader.checksum(), replica.checksum());
+  ///
+  /// replica.insert(john, age, Node::Literal(43.into()));
+  /// assert_ne!(leader.checksum(), replica.checksum());
+  /// ```
+  pub fn checksum(&self) -> String {
+    let partitions = thread::available_parallelism().map_or(1, |n| n.get());
+    checksum::checksum(self, partitions)
+  }
+
+  /// Returns `true` if `other` contains the same statements, up to a
+  /// renaming of blank nodes, e.g. to check a round trip through a format
+  /// which relabels them. Provenance is ignored and duplicate statements
+  /// count once.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::formats::NTriples;
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  ///
+  /// let ada = Node::Http("https://example.com/Ada".to_string());
+  /// let knows = Predicate::Literal("https://schema.org/knows".to_string());
+  /// let name = Predicate::Literal("https://schema.org/name".to_string());
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// let friend = graph.blank_node();
+  /// graph.insert(ada.clone(), knows.clone(), friend.clone());
+  /// graph.insert(friend, name.clone(), Node::Literal("Charles".into()));
+  ///
+  /// let parsed = NTriples::parse(
+  ///   "<https://example.com/Ada> <https://schema.org/knows> _:friend .\n\
+  ///    _:friend <https://schema.org/name> \"Charles\" .\n",
+  /// )
+  /// .unwrap();
+  /// assert!(graph.isomorphic_to(&parsed));
+  ///
+  /// // Charles is no longer the friend of Ada.
+  /// let mut other = KnowledgeGraph::new();
+  /// other.insert(ada, knows, Node::Blank);
+  /// other.insert(Node::Blank, name, Node::Literal("Charles".into()));
+  /// assert!(!graph.isomorphic_to(&other));
+  /// ```
+  pub fn isomorphic_to(&self, other: &KnowledgeGraph) -> bool {
+    isomorphism::isomorphic(self, other)
+  }
+
+  /// Returns a profiling report of the graph, for quick quality checks of
+  /// ingested data. The report is an object with:
+  ///
+  /// - `statements`, `nodes` & `subjects`: the number of statements,
+  ///   distinct non-literal nodes & distinct subjects.
+  /// - `predicates`: the number of statements per predicate.
+  /// - `classes`: the number of instances per `rdf:type` (or `@type`).
+  /// - `datatypes`: the number of literals per datatype (`string`,
+  ///   `integer`, `double`, `boolean`, `dateTime`, `langString` or `json`).
+  /// - `degrees`: the degree distribution, as `{"degree", "nodes"}` objects
+  ///   by increasing degree. The degree of a node is the number of
+  ///   statements it's the subject or (non-literal) object of.
+  /// - `most_connected`: up to 10 `{"@id", "degree"}` objects of the nodes
+  ///   with the highest degree.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  ///
+  /// let ada = Node::Http("https://example.com/ada".to_string());
+  /// let bob = Node::Http("https://example.com/bob".to_string());
+  /// let rdf_type = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+  /// let person = Node::Http("https://schema.org/Person".to_string());
+  /// let knows = Predicate::Literal("https://schema.org/knows".to_string());
+  /// let name = Predicate::Literal("https://schema.org/name".to_string());
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(ada.clone(), Predicate::Literal(rdf_type.into()), person);
+  /// graph.insert(ada.clone(), name, Node::Literal("Ada".into()));
+  /// graph.insert(ada, knows, bob);
+  ///
+  /// let stats = graph.stats();
+  /// assert_eq!(stats["statements"], 3);
+  /// assert_eq!(stats["classes"]["https://schema.org/Person"], 1);
+  /// assert_eq!(stats["datatypes"]["string"], 1);
+  /// assert_eq!(stats["most_connected"][0]["@id"], "https://example.com/ada");
+  /// assert_eq!(stats["most_connected"][0]["degree"], 3);
+  /// ```
+  pub fn stats(&self) -> DType {
+    stats::stats(self)
+  }
+
+  /// Returns every located entity within `radius` meters of `center`,
+  /// nearest first.
+  ///
+  /// Entities are located by WKT, GeoJSON & schema.org `GeoCoordinates`
+  /// literals, `geo:lat`/`geo:long` or `schema:latitude`/`schema:longitude`
+  /// pairs, or a linked node (e.g. `schema:geo`) located by either.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::dtype::Point;
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// let museum = Node::Http("https://example.com/BritishMuseum".to_string());
+  /// graph.insert(
+  ///   museum.clone(),
+  ///   Predicate::Literal("https://schema.org/latitude".to_string()),
+  ///   Node::Literal(51.5194.into()),
+  /// );
+  /// graph.insert(
+  ///   museum.clone(),
+  ///   Predicate::Literal("https://schema.org/longitude".to_string()),
+  ///   Node::Literal((-0.1270).into()),
+  /// );
+  /// graph.insert(
+  ///   Node::Http("https://example.com/Louvre".to_string()),
+  ///   Predicate::Literal("http://www.opengis.net/ont/geosparql#asWKT".to_string()),
+  ///   Node::Literal("POINT(2.3376 48.8606)".into()),
+  /// );
+  ///
+  /// let trafalgar = Point::new(-0.1281, 51.5080);
+  /// let hits = graph.within_radius(&trafalgar, 5_000.0);
+  /// assert_eq!(hits.len(), 1);
+  /// assert_eq!(hits[0].subject, museum);
+  /// assert!(hits[0].distance < 1_500.0);
+  ///
+  /// assert_eq!(graph.within_radius(&trafalgar, 500_000.0).len(), 2);
+  /// ```
+  pub fn within_radius(&self, center: &Point, radius: f64) -> Vec<GeoHit> {
+    let mut hits: Vec<GeoHit> = spatial::locations(self)
+      .into_iter()
+      .map(|(subject, point)| GeoHit {
+        distance: center.distance(&point),
+        subject,
+        point,
+      })
+      .filter(|hit| hit.distance <= radius)
+      .collect();
+    hits.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    hits
+  }
+
+  /// Returns the number of triples in the graph.
+  pub fn len(&self) -> usize {
+    self.triples.len()
+  }
+
+  /// Returns `true` if the graph contains no triples.
+  pub fn is_empty(&self) -> bool {
+    self.triples.is_empty()
+  }
+}
+
+impl Extend<Triple> for KnowledgeGraph {
+  fn extend<I: IntoIterator<Item = Triple>>(&mut self, iter: I) {
+    for triple in iter {
+      self.add(triple);
+    }
+  }
+}
+
+impl FromIterator<Triple> for KnowledgeGraph {
+  fn from_iter<I: IntoIterator<Item = Triple>>(iter: I) -> Self {
+    let mut graph = KnowledgeGraph::new();
+    graph.extend(iter);
+    graph
+  }
+}
+
+impl DeepSizeOf for KnowledgeGraph {
+  /// Counts the statements, the per-subject versions, the `sameAs`
+  /// redirects & the kept history. The ontology, constraints, subscribers
+  /// and statement, search & embedding indexes aren't counted.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  /// use sage::DeepSizeOf;
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// let empty = graph.deep_size_of();
+  /// graph.insert(
+  ///   Node::Http("https://example.com/Ada".to_string()),
+  ///   Predicate::Literal("https://schema.org/name".to_string()),
+  ///   Node::Literal("Ada".into()),
+  /// );
+  /// assert!(graph.deep_size_of() > empty);
+  /// ```
+  fn heap_size_of(&self) -> usize {
+    self.triples.heap_size_of()
+      + self.versions.heap_size_of()
+      + self.redirects.heap_size_of()
+      + self.history.heap_size_of()
+  }
+}
@@ -0,0 +1,397 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+  dtype::DType,
+  graph::{KnowledgeGraph, Node, Predicate, Triple},
+};
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/// A statement borrowed from either side of a merge.
+type Statement<'a> = (&'a Node, &'a Predicate, &'a Node);
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | MergePolicy
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Resolution` decides which values a functional property keeps when both
+/// graphs of a merge disagree on it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Resolution {
+  /// Keep the values of the graph being merged into.
+  #[default]
+  Ours,
+  /// Replace the values with those of the graph being merged from.
+  Theirs,
+  /// Keep the values of both graphs.
+  Both,
+}
+
+/// `Duplicates` decides what happens to statements both graphs of a merge
+/// contain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Duplicates {
+  /// Add the statement once.
+  #[default]
+  Skip,
+  /// Add the statement again, keeping its multiplicity.
+  Keep,
+}
+
+/// `BlankNodes` decides how labelled blank nodes of the graph being merged
+/// from relate to those of the graph being merged into.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlankNodes {
+  /// Blank node labels are local to their graph: incoming labels already
+  /// used are renamed to fresh ones.
+  #[default]
+  Rename,
+  /// Equal labels denote the same node in both graphs.
+  Share,
+}
+
+/// `MergePolicy` configures `KnowledgeGraph::merge`.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{BlankNodes, Duplicates, MergePolicy, Resolution};
+///
+/// let policy = MergePolicy::new()
+///   .functional("https://schema.org/birthDate")
+///   .resolution(Resolution::Theirs)
+///   .duplicates(Duplicates::Keep)
+///   .blank_nodes(BlankNodes::Share);
+/// assert!(policy.is_functional("https://schema.org/birthDate"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MergePolicy {
+  functional: HashSet<String>,
+  resolution: Resolution,
+  duplicates: Duplicates,
+  blank_nodes: BlankNodes,
+}
+
+impl MergePolicy {
+  /// Creates a policy without functional properties which keeps our
+  /// values, skips duplicates and renames clashing blank nodes.
+  pub fn new() -> MergePolicy {
+    MergePolicy::default()
+  }
+
+  /// Declares `predicate` (a full IRI) as functional: a subject has at most
+  /// one value for it.
+  pub fn functional(mut self, predicate: &str) -> Self {
+    self.functional.insert(predicate.to_string());
+    self
+  }
+
+  /// Sets how clashing functional properties are resolved.
+  pub fn resolution(mut self, resolution: Resolution) -> Self {
+    self.resolution = resolution;
+    self
+  }
+
+  /// Sets what happens to statements both graphs contain.
+  pub fn duplicates(mut self, duplicates: Duplicates) -> Self {
+    self.duplicates = duplicates;
+    self
+  }
+
+  /// Sets how blank node labels of both graphs relate.
+  pub fn blank_nodes(mut self, blank_nodes: BlankNodes) -> Self {
+    self.blank_nodes = blank_nodes;
+    self
+  }
+
+  /// Returns `true` if `predicate` was declared functional.
+  pub fn is_functional(&self, predicate: &str) -> bool {
+    self.functional.contains(predicate)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | MergeReport
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Conflict` is a disagreement between both graphs of a merge.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Conflict {
+  /// A subject has different values for a functional property.
+  Functional {
+    subject: Node,
+    predicate: Predicate,
+    ours: Vec<Node>,
+    theirs: Vec<Node>,
+    resolution: Resolution,
+  },
+  /// A subject's `rdf:type`s in both graphs have nothing in common. Types
+  /// are merged regardless.
+  Type {
+    subject: Node,
+    ours: Vec<Node>,
+    theirs: Vec<Node>,
+  },
+}
+
+/// `MergeReport` is what `KnowledgeGraph::merge` did.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergeReport {
+  /// Statements added to the graph, after renaming blank nodes.
+  pub added: Vec<(Node, Predicate, Node)>,
+  /// Conflicts found, in order of discovery.
+  pub conflicts: Vec<Conflict>,
+  /// Renamed blank node labels of the merged graph, old to new.
+  pub renamed: BTreeMap<String, String>,
+}
+
+impl MergeReport {
+  /// Returns the report as the document described in
+  /// `KnowledgeGraph::merge_from`.
+  pub fn to_dtype(&self) -> DType {
+    let conflicts: Vec<DType> = self
+      .conflicts
+      .iter()
+      .map(|conflict| match conflict {
+        Conflict::Functional {
+          subject,
+          predicate,
+          ours,
+          theirs,
+          resolution,
+        } => crate::json!({
+          "kind": "functional",
+          "subject": subject.to_string(),
+          "predicate": predicate.to_string(),
+          "ours": values(ours),
+          "theirs": values(theirs),
+          "resolution": format!("{:?}", resolution).to_lowercase(),
+        }),
+        Conflict::Type {
+          subject,
+          ours,
+          theirs,
+        } => crate::json!({
+          "kind": "type",
+          "subject": subject.to_string(),
+          "ours": values(ours),
+          "theirs": values(theirs),
+        }),
+      })
+      .collect();
+
+    crate::json!({
+      "added": self.added.len(),
+      "conflicts": conflicts,
+      "renamed": self.renamed.clone(),
+    })
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Merge
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Merges `other` into `graph` following `policy`.
+pub(crate) fn merge(
+  graph: &mut KnowledgeGraph,
+  other: &KnowledgeGraph,
+  policy: &MergePolicy,
+) -> MergeReport {
+  let mut report = MergeReport::default();
+
+  let mut labels = HashMap::new();
+  if policy.blank_nodes == BlankNodes::Rename {
+    let ours = graph.blank_labels();
+    let theirs = other.blank_labels();
+    let mut clashing: Vec<&String> = theirs.intersection(&ours).collect();
+    clashing.sort();
+    for label in clashing {
+      let fresh = loop {
+        match graph.blank_node() {
+          Node::BlankId(fresh) if !theirs.contains(&fresh) => break fresh,
+          _ => continue,
+        }
+      };
+      report.renamed.insert(label.clone(), fresh.clone());
+      labels.insert(label.clone(), fresh);
+    }
+  }
+  let incoming: Vec<(Node, Predicate, Node)> = other
+    .triples()
+    .map(|t| {
+      (
+        rename(t.source(), &labels),
+        t.predicate().clone(),
+        rename(t.destination(), &labels),
+      )
+    })
+    .collect();
+  let theirs = || incoming.iter().map(|(s, p, o)| (s, p, o));
+
+  // Functional properties & types are compared against the graph as it was
+  // before the merge, so each clash is reported once.
+  let mut functional: Vec<(&Node, &Predicate)> = Vec::new();
+  for (subject, predicate, _) in theirs() {
+    if policy.is_functional(&predicate.to_string())
+      && !functional.contains(&(subject, predicate))
+    {
+      functional.push((subject, predicate));
+    }
+  }
+
+  let mut skipped = Vec::new();
+  let mut replaced = Vec::new();
+  for (subject, predicate) in functional {
+    let iri = predicate.to_string();
+    let ours = lookup(ours(graph), subject, &iri);
+    let theirs = lookup(theirs(), subject, &iri);
+    if ours.is_empty() || same(&ours, &theirs) {
+      continue;
+    }
+
+    report.conflicts.push(Conflict::Functional {
+      subject: subject.clone(),
+      predicate: predicate.clone(),
+      ours,
+      theirs,
+      resolution: policy.resolution,
+    });
+    match policy.resolution {
+      Resolution::Ours => skipped.push((subject, predicate)),
+      Resolution::Theirs => replaced.push((subject, predicate)),
+      Resolution::Both => {}
+    }
+  }
+
+  for subject in subjects(theirs()) {
+    let ours = lookup(ours(graph), subject, RDF_TYPE);
+    let theirs = lookup(theirs(), subject, RDF_TYPE);
+    if !ours.is_empty()
+      && !theirs.is_empty()
+      && !ours.iter().any(|t| theirs.contains(t))
+    {
+      report.conflicts.push(Conflict::Type {
+        subject: subject.clone(),
+        ours,
+        theirs,
+      });
+    }
+  }
+
+  for (subject, predicate) in replaced {
+    graph.remove_values(subject, predicate);
+  }
+
+  for (subject, predicate, object) in theirs() {
+    if skipped.contains(&(subject, predicate)) {
+      continue;
+    }
+    if policy.duplicates == Duplicates::Skip {
+      let iri = predicate.to_string();
+      let mut found = graph.matches(Some(subject), Some(&iri), Some(object));
+      if found.next().is_some() {
+        continue;
+      }
+    }
+    graph.add(Triple::from_nodes(
+      subject.clone(),
+      predicate.clone(),
+      object.clone(),
+    ));
+    report
+      .added
+      .push((subject.clone(), predicate.clone(), object.clone()));
+  }
+
+  report
+}
+
+/// Statements of `graph`.
+fn ours(graph: &KnowledgeGraph) -> impl Iterator<Item = Statement<'_>> {
+  graph
+    .triples()
+    .map(|t| (t.source(), t.predicate(), t.destination()))
+}
+
+/// Every value of `predicate` on `subject`.
+fn lookup<'a>(
+  statements: impl Iterator<Item = Statement<'a>>,
+  subject: &Node,
+  predicate: &str,
+) -> Vec<Node> {
+  statements
+    .filter(|(s, p, _)| *s == subject && p.to_string() == predicate)
+    .map(|(_, _, o)| o.clone())
+    .collect()
+}
+
+/// Returns `true` if both lists hold the same values, in any order.
+fn same(a: &[Node], b: &[Node]) -> bool {
+  a.len() == b.len() && a.iter().all(|n| b.contains(n))
+}
+
+/// `node` with its blank node labels renamed through `labels`.
+fn rename(node: &Node, labels: &HashMap<String, String>) -> Node {
+  match node {
+    Node::BlankId(label) => match labels.get(label) {
+      Some(fresh) => Node::BlankId(fresh.clone()),
+      None => node.clone(),
+    },
+    Node::Multiple(nodes) => {
+      Node::Multiple(nodes.iter().map(|n| rename(n, labels)).collect())
+    }
+    node => node.clone(),
+  }
+}
+
+/// Distinct subjects of `statements` in order of first appearance.
+fn subjects<'a>(
+  statements: impl Iterator<Item = Statement<'a>>,
+) -> Vec<&'a Node> {
+  let mut subjects: Vec<&Node> = Vec::new();
+  for (subject, ..) in statements {
+    if !subjects.contains(&subject) {
+      subjects.push(subject);
+    }
+  }
+  subjects
+}
+
+/// `nodes` as report values: literals as themselves, anything else as its
+/// identifier.
+fn values(nodes: &[Node]) -> Vec<DType> {
+  nodes
+    .iter()
+    .map(|node| match node {
+      Node::Literal(value) => value.clone(),
+      node => DType::from(node.to_string()),
+    })
+    .collect()
+}
@@ -19,7 +19,7 @@ use std::{fmt, str::FromStr};
 use regex::Regex;
 
 use crate::{
-  dtype::{DType, URI},
+  dtype::{DType, DeepSizeOf, URI},
   error::{Error, ErrorCode},
 };
 
@@ -39,11 +39,16 @@ use crate::{
 /// `Node` is the crux of a `sage` knowledge graph, in which every *entity*
 /// in the Knowledge Graph is regarded as a `Node` in `sage`.
 ///
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Node {
   /// `Blank` node containing node with empty or null data.
   Blank,
 
+  /// `BlankId` is a blank node with an explicit label (e.g. `b0` for
+  /// `_:b0`). Unlike `Blank`, every occurrence of a label refers to the same
+  /// node, see `KnowledgeGraph::blank_node` & `KnowledgeGraph::skolemize`.
+  BlankId(String),
+
   /// `Schema` node is created from some type of structured data.
   /// For example: wikidata, jsonld, rdf, ntriple or even structs.
   Schema,
@@ -66,6 +71,24 @@ pub enum Node {
 
 /// Implementation for `Node` enum.
 impl Node {
+  /// Creates a `Node::Http` from a validated & normalized IRI, so nodes
+  /// written with differently encoded IRIs are equal. Prefer this over
+  /// `Node::Http` for identifiers from external sources.
+  ///
+  /// ```rust
+  /// # use sage::graph::Node;
+  /// #
+  /// let a = Node::iri("HTTPS://Example.com/%7eada").unwrap();
+  /// let b = Node::iri("https://example.com/~ada").unwrap();
+  /// assert_eq!(a, b);
+  ///
+  /// assert!(Node::iri("not an iri").is_err());
+  /// ```
+  ///
+  pub fn iri(iri: &str) -> Result<Node, Error> {
+    crate::iri::Iri::parse(iri).map(Node::from)
+  }
+
   /// Check of `Node` is of type `Node::Blank`.
   ///
   /// ```rust
@@ -74,12 +97,13 @@ impl Node {
   /// let node_type = Node::Blank;
   /// assert!(node_type.is_blank());
   ///
+  /// # assert!(Node::BlankId("b0".to_string()).is_blank());
   /// # assert!(!Node::Schema.is_blank());
   /// # assert!(!Node::Http(URI::from("https://schema.org/Person")).is_blank());
   /// ```
   ///
   pub fn is_blank(&self) -> bool {
-    matches!(*self, Node::Blank)
+    matches!(*self, Node::Blank | Node::BlankId(_))
   }
 
   /// Check if `Node` is of type `Node::Schema`.
@@ -168,7 +192,35 @@ impl Node {
 
 impl fmt::Display for Node {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "{}", self.get_type())
+    match self {
+      Node::Blank => f.write_str("_:blank"),
+      Node::BlankId(label) => write!(f, "_:{}", label),
+      Node::Schema => f.write_str("schema"),
+      Node::Http(uri) => f.write_str(uri),
+      Node::Literal(dtype) => fmt::Display::fmt(dtype, f),
+      Node::Multiple(nodes) => {
+        f.write_str("[")?;
+        for (i, node) in nodes.iter().enumerate() {
+          if i > 0 {
+            f.write_str(", ")?;
+          }
+          fmt::Display::fmt(node, f)?;
+        }
+        f.write_str("]")
+      }
+    }
+  }
+}
+
+impl DeepSizeOf for Node {
+  fn heap_size_of(&self) -> usize {
+    match self {
+      Node::BlankId(label) => label.heap_size_of(),
+      Node::Http(uri) => uri.heap_size_of(),
+      Node::Literal(value) => value.heap_size_of(),
+      Node::Multiple(nodes) => nodes.heap_size_of(),
+      Node::Blank | Node::Schema => 0,
+    }
   }
 }
 
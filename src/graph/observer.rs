@@ -0,0 +1,168 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::{
+  mpsc::{self, Receiver, TrySendError},
+  Mutex,
+};
+
+use crate::graph::{Node, Predicate, Triple};
+
+/// Callback receiving batches of mutations.
+type Callback = Box<dyn FnMut(&[Mutation]) + Send>;
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Mutation
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Mutation` is a statement inserted into or removed from a
+/// `KnowledgeGraph`, as delivered to its subscribers.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Mutation {
+  /// The statement was inserted.
+  Inserted(Node, Predicate, Node),
+  /// The statement was removed.
+  Removed(Node, Predicate, Node),
+}
+
+impl Mutation {
+  pub(crate) fn inserted(triple: &Triple) -> Mutation {
+    Mutation::Inserted(
+      triple.source().clone(),
+      triple.predicate().clone(),
+      triple.destination().clone(),
+    )
+  }
+
+  pub(crate) fn removed(triple: &Triple) -> Mutation {
+    Mutation::Removed(
+      triple.source().clone(),
+      triple.predicate().clone(),
+      triple.destination().clone(),
+    )
+  }
+
+  /// Returns the subject of the mutated statement.
+  pub fn subject(&self) -> &Node {
+    match self {
+      Mutation::Inserted(subject, ..) | Mutation::Removed(subject, ..) => {
+        subject
+      }
+    }
+  }
+}
+
+/// `Subscription` identifies a subscriber, to `unsubscribe` it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Subscription(u64);
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Observers
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Subscribers of a graph, and the mutations of the batch being recorded.
+#[derive(Default)]
+pub(crate) struct Observers {
+  // `Mutex` keeps the graph `Sync` without requiring `Sync` callbacks;
+  // it's only ever accessed through `get_mut`.
+  callbacks: Vec<(Subscription, Mutex<Callback>)>,
+  next: u64,
+  /// Nesting depth of `KnowledgeGraph::batch` calls.
+  depth: usize,
+  pending: Vec<Mutation>,
+}
+
+impl Observers {
+  pub(crate) fn subscribe(&mut self, callback: Callback) -> Subscription {
+    let subscription = Subscription(self.next);
+    self.next += 1;
+    self.callbacks.push((subscription, Mutex::new(callback)));
+    subscription
+  }
+
+  pub(crate) fn unsubscribe(&mut self, subscription: Subscription) -> bool {
+    let len = self.callbacks.len();
+    self.callbacks.retain(|(s, _)| *s != subscription);
+    self.callbacks.len() != len
+  }
+
+  /// Returns `true` if anyone listens, so mutations are worth recording.
+  pub(crate) fn is_active(&self) -> bool {
+    !self.callbacks.is_empty()
+  }
+
+  /// Records `mutation`, delivering it right away outside of a batch.
+  pub(crate) fn record(&mut self, mutation: Mutation) {
+    self.pending.push(mutation);
+    if self.depth == 0 {
+      self.flush();
+    }
+  }
+
+  pub(crate) fn begin(&mut self) {
+    self.depth += 1;
+  }
+
+  /// Ends a batch, delivering its mutations once the outermost one ends.
+  pub(crate) fn end(&mut self) {
+    self.depth -= 1;
+    if self.depth == 0 {
+      self.flush();
+    }
+  }
+
+  fn flush(&mut self) {
+    if self.pending.is_empty() {
+      return;
+    }
+    let batch = std::mem::take(&mut self.pending);
+    for (_, callback) in &mut self.callbacks {
+      let callback = callback.get_mut().unwrap_or_else(|e| e.into_inner());
+      callback(&batch);
+    }
+  }
+}
+
+/// Returns a callback forwarding batches into a channel of `capacity`
+/// batches, and the channel's receiving end.
+///
+/// The callback never blocks the graph: while the channel is full, batches
+/// are coalesced & sent along with the next one once there is room again.
+/// After the receiver is dropped, batches are discarded.
+pub(crate) fn channel(capacity: usize) -> (Callback, Receiver<Vec<Mutation>>) {
+  let (sender, receiver) = mpsc::sync_channel(capacity.max(1));
+  let mut backlog: Vec<Mutation> = Vec::new();
+  let mut connected = true;
+
+  let callback = move |batch: &[Mutation]| {
+    if !connected {
+      return;
+    }
+    backlog.extend_from_slice(batch);
+    match sender.try_send(std::mem::take(&mut backlog)) {
+      Ok(()) => {}
+      Err(TrySendError::Full(batch)) => backlog = batch,
+      Err(TrySendError::Disconnected(_)) => connected = false,
+    }
+  };
+  (Box::new(callback), receiver)
+}
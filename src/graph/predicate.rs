@@ -17,6 +17,7 @@
 use std::{fmt, str::FromStr};
 
 use crate::{
+  dtype::DeepSizeOf,
   error::{Error, ErrorCode},
   vocab::Namespace,
 };
@@ -75,7 +76,7 @@ impl fmt::Display for PredicateId {
   }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Predicate {
   /// *Literal predicate* describes the connection between two `Node`s
   /// in form of a string slice (`&str`) or `String`.
@@ -146,7 +147,19 @@ impl Predicate {
 
 impl fmt::Display for Predicate {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    write!(f, "{}", self.get_type())
+    match self {
+      Predicate::Literal(s) => f.write_str(s),
+      Predicate::Uri(ns) => f.write_str(ns.full()),
+    }
+  }
+}
+
+impl DeepSizeOf for Predicate {
+  fn heap_size_of(&self) -> usize {
+    match self {
+      Predicate::Literal(s) => s.heap_size_of(),
+      Predicate::Uri(ns) => ns.heap_size_of(),
+    }
   }
 }
 
@@ -0,0 +1,351 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+
+use crate::{
+  dtype::{DType, Map},
+  graph::{KnowledgeGraph, Node},
+};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | DateTimeFormat
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `DateTimeFormat` controls how `DType::DateTime` values are rendered by a
+/// `Profile`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum DateTimeFormat {
+  /// Leave datetimes as `DType::DateTime`.
+  #[default]
+  Native,
+  /// RFC 3339 string, e.g. `"2021-03-14T15:09:26Z"`.
+  Rfc3339,
+  /// RFC 2822 string, e.g. `"Sun, 14 Mar 2021 15:09:26 +0000"`.
+  Rfc2822,
+  /// Seconds since the Unix epoch.
+  TimestampSeconds,
+  /// Milliseconds since the Unix epoch.
+  TimestampMillis,
+  /// Custom `strftime` pattern, e.g. `"%Y-%m-%d"`.
+  Custom(String),
+}
+
+impl DateTimeFormat {
+  fn apply(&self, value: &DType) -> DType {
+    let d = match value {
+      DType::DateTime(d) => d.as_chrono(),
+      _ => return value.clone(),
+    };
+    match self {
+      DateTimeFormat::Native => value.clone(),
+      DateTimeFormat::Rfc3339 => d
+        .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+        .into(),
+      DateTimeFormat::Rfc2822 => d.to_rfc2822().into(),
+      DateTimeFormat::TimestampSeconds => d.timestamp().into(),
+      DateTimeFormat::TimestampMillis => d.timestamp_millis().into(),
+      DateTimeFormat::Custom(pattern) => d.format(pattern).to_string().into(),
+    }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Profile
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Profile` declares how a node is projected into a `DType` for API
+/// responses: which predicates to include, how deep to follow links, which
+/// language to pick labels in and how to render datetimes.
+///
+/// The projection is an object with the node IRI under `"@id"` and one key
+/// per predicate. A predicate with a single value maps to that value,
+/// otherwise to an array of values. Linked resources are expanded into
+/// nested objects up to `depth` levels and rendered as their IRI beyond
+/// that.
+///
+/// Language tagged literals are JSON-LD value objects, i.e.
+/// `{"@value": "Hallo", "@language": "de"}`. With a `language` set, only
+/// values in that language (or untagged values) are kept and unwrapped to
+/// plain strings.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{DateTimeFormat, KnowledgeGraph, Node, Profile, Predicate};
+/// use sage::json;
+///
+/// let john = Node::Http("https://example.com/John".to_string());
+/// let jane = Node::Http("https://example.com/Jane".to_string());
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   john.clone(),
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal(json!({ "@value": "John", "@language": "en" })),
+/// );
+/// graph.insert(
+///   john.clone(),
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal(json!({ "@value": "Johann", "@language": "de" })),
+/// );
+/// graph.insert(
+///   john.clone(),
+///   Predicate::Literal("https://schema.org/knows".to_string()),
+///   jane.clone(),
+/// );
+/// graph.insert(
+///   john.clone(),
+///   Predicate::Literal("https://schema.org/email".to_string()),
+///   Node::Literal("john@example.com".into()),
+/// );
+/// graph.insert(
+///   jane,
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal("Jane".into()),
+/// );
+///
+/// let profile = Profile::new()
+///   .include("https://schema.org/name")
+///   .include("https://schema.org/knows")
+///   .depth(1)
+///   .language("de")
+///   .compact_keys(true)
+///   .datetime_format(DateTimeFormat::Rfc3339);
+///
+/// assert_eq!(
+///   profile.project(&graph, &john),
+///   json!({
+///     "@id": "https://example.com/John",
+///     "name": "Johann",
+///     "knows": { "@id": "https://example.com/Jane", "name": "Jane" }
+///   })
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Profile {
+  include: Option<HashSet<String>>,
+  exclude: HashSet<String>,
+  depth: usize,
+  language: Option<String>,
+  datetime_format: DateTimeFormat,
+  compact_keys: bool,
+}
+
+impl Default for Profile {
+  fn default() -> Self {
+    Profile::new()
+  }
+}
+
+impl Profile {
+  /// Creates a profile including every predicate, following links one
+  /// level deep.
+  pub fn new() -> Profile {
+    Profile {
+      include: None,
+      exclude: HashSet::new(),
+      depth: 1,
+      language: None,
+      datetime_format: DateTimeFormat::default(),
+      compact_keys: false,
+    }
+  }
+
+  /// Only includes the given predicates (may be called repeatedly).
+  pub fn include(mut self, predicate: &str) -> Self {
+    self
+      .include
+      .get_or_insert_with(HashSet::new)
+      .insert(predicate.to_string());
+    self
+  }
+
+  /// Never includes `predicate`.
+  pub fn exclude(mut self, predicate: &str) -> Self {
+    self.exclude.insert(predicate.to_string());
+    self
+  }
+
+  /// Number of link levels expanded into nested objects. `0` renders every
+  /// linked resource as its IRI.
+  pub fn depth(mut self, depth: usize) -> Self {
+    self.depth = depth;
+    self
+  }
+
+  /// Preferred language of language tagged literals.
+  pub fn language(mut self, language: &str) -> Self {
+    self.language = Some(language.to_string());
+    self
+  }
+
+  /// How datetimes are rendered.
+  pub fn datetime_format(mut self, format: DateTimeFormat) -> Self {
+    self.datetime_format = format;
+    self
+  }
+
+  /// Uses the local name of each predicate as key, e.g. `name` instead of
+  /// `https://schema.org/name`.
+  pub fn compact_keys(mut self, compact: bool) -> Self {
+    self.compact_keys = compact;
+    self
+  }
+
+  /// Projects `node` from `graph` into a `DType`.
+  pub fn project(&self, graph: &KnowledgeGraph, node: &Node) -> DType {
+    let mut visiting = Vec::new();
+    self.project_node(graph, node, self.depth, &mut visiting)
+  }
+
+  fn project_node<'g>(
+    &self,
+    graph: &'g KnowledgeGraph,
+    node: &'g Node,
+    depth: usize,
+    visiting: &mut Vec<&'g Node>,
+  ) -> DType {
+    let iri = match node {
+      Node::Literal(value) => return self.literal(value),
+      Node::Multiple(nodes) => {
+        return nodes
+          .iter()
+          .map(|n| self.project_node(graph, n, depth, visiting))
+          .collect();
+      }
+      node => node.to_string(),
+    };
+
+    let mut object = Map::new();
+    object.insert("@id".to_string(), iri.clone().into());
+    // Anonymous blank nodes aren't distinguishable, so their properties are
+    // unknown.
+    if matches!(node, Node::Blank) || visiting.contains(&node) {
+      return DType::Object(object);
+    }
+
+    visiting.push(node);
+    let mut values: Vec<(String, Vec<DType>)> = Vec::new();
+    for triple in graph.matches(Some(node), None, None) {
+      let predicate = triple.predicate().to_string();
+      if !self.includes(&predicate) {
+        continue;
+      }
+
+      let value = match triple.destination() {
+        Node::Literal(value) => self.literal(value),
+        linked if depth == 0 => match linked {
+          Node::Multiple(nodes) => {
+            nodes.iter().map(|n| DType::from(n.to_string())).collect()
+          }
+          linked => linked.to_string().into(),
+        },
+        linked => self.project_node(graph, linked, depth - 1, visiting),
+      };
+      if value.is_null() {
+        continue;
+      }
+
+      match values.iter_mut().find(|(p, _)| *p == predicate) {
+        Some((_, existing)) => existing.push(value),
+        None => values.push((predicate, vec![value])),
+      }
+    }
+    visiting.pop();
+
+    for (predicate, values) in values {
+      let mut values = self.pick_language(values);
+      let value = match values.len() {
+        0 => continue,
+        1 => values.remove(0),
+        _ => DType::from(values),
+      };
+      object.insert(self.key(&predicate), value);
+    }
+    DType::Object(object)
+  }
+
+  fn includes(&self, predicate: &str) -> bool {
+    !self.exclude.contains(predicate)
+      && self.include.as_ref().is_none_or(|p| p.contains(predicate))
+  }
+
+  fn key(&self, predicate: &str) -> String {
+    if !self.compact_keys {
+      return predicate.to_string();
+    }
+    predicate
+      .rsplit(['/', '#', ':'])
+      .find(|s| !s.is_empty())
+      .unwrap_or(predicate)
+      .to_string()
+  }
+
+  fn literal(&self, value: &DType) -> DType {
+    match value {
+      DType::DateTime(_) => self.datetime_format.apply(value),
+      DType::Array(values) => values.iter().map(|v| self.literal(v)).collect(),
+      DType::Object(map) => DType::Object(
+        map
+          .iter()
+          .map(|(k, v)| (k.clone(), self.literal(v)))
+          .collect(),
+      ),
+      value => value.clone(),
+    }
+  }
+
+  /// Keeps values in the preferred language (and untagged values) and
+  /// unwraps language tagged value objects. Falls back to every value if
+  /// none match.
+  fn pick_language(&self, values: Vec<DType>) -> Vec<DType> {
+    let language = match &self.language {
+      Some(language) => language,
+      None => return values,
+    };
+    let matching: Vec<DType> = values
+      .iter()
+      .filter(|v| {
+        language_tag(v).is_none_or(|t| t.eq_ignore_ascii_case(language))
+      })
+      .cloned()
+      .collect();
+    let values = if matching.is_empty() {
+      values
+    } else {
+      matching
+    };
+
+    values
+      .into_iter()
+      .map(|v| match v.get("@value") {
+        Some(value) if language_tag(&v).is_some() => value.clone(),
+        _ => v,
+      })
+      .collect()
+  }
+}
+
+fn language_tag(value: &DType) -> Option<&str> {
+  value.get("@language").and_then(DType::as_str)
+}
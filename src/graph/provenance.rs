@@ -0,0 +1,70 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chrono::Utc;
+
+use crate::dtype::{DateTime, DeepSizeOf};
+
+/// `Provenance` records where a statement comes from: the document it was
+/// extracted from, when it was ingested & how confident the extractor is.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::Provenance;
+///
+/// let provenance = Provenance::new("https://en.wikipedia.org/wiki/Ada_Lovelace")
+///   .confidence(0.8);
+///
+/// assert_eq!(provenance.confidence, Some(0.8));
+/// assert!(provenance.ingested_at.is_some());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Provenance {
+  /// IRI or name of the source document.
+  pub source: Option<String>,
+  /// When the statement was ingested.
+  pub ingested_at: Option<DateTime>,
+  /// Confidence in the statement, between `0.0` & `1.0`.
+  pub confidence: Option<f64>,
+}
+
+impl Provenance {
+  /// Creates the provenance of a statement from `source`, ingested now.
+  pub fn new(source: &str) -> Provenance {
+    Provenance {
+      source: Some(source.to_string()),
+      ingested_at: Some(DateTime::from(Utc::now())),
+      confidence: None,
+    }
+  }
+
+  /// Sets when the statement was ingested.
+  pub fn ingested_at(mut self, ingested_at: DateTime) -> Self {
+    self.ingested_at = Some(ingested_at);
+    self
+  }
+
+  /// Sets the confidence in the statement, clamped to `0.0..=1.0`.
+  pub fn confidence(mut self, confidence: f64) -> Self {
+    self.confidence = Some(confidence.clamp(0.0, 1.0));
+    self
+  }
+}
+
+impl DeepSizeOf for Provenance {
+  fn heap_size_of(&self) -> usize {
+    self.source.heap_size_of()
+  }
+}
@@ -0,0 +1,143 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::graph::{KnowledgeGraph, Node};
+
+/// Full IRI of `owl:sameAs`.
+pub(crate) const OWL_SAME_AS: &str = "http://www.w3.org/2002/07/owl#sameAs";
+
+/// `Canonical` picks the node an identity cluster collapses into in
+/// `KnowledgeGraph::resolve_same_as`.
+///
+/// IRIs are always preferred over blank nodes, which are local to a graph.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Canonical {
+  /// The smallest IRI (lexicographically).
+  #[default]
+  Smallest,
+  /// The node which is the subject of the most statements, ties broken by
+  /// the smallest IRI.
+  MostStatements,
+  /// The first IRI starting with one of the namespaces, in order of
+  /// preference, e.g. to prefer your own IRIs over Wikidata's. Falls back
+  /// to the smallest IRI.
+  Prefer(Vec<String>),
+}
+
+impl Canonical {
+  /// Returns the canonical node of `cluster`, by rank.
+  fn pick<'a>(
+    &self,
+    cluster: &[&'a str],
+    statements: &HashMap<String, usize>,
+  ) -> &'a str {
+    let rank = |id: &str| {
+      let preference = match self {
+        Canonical::Prefer(namespaces) => namespaces
+          .iter()
+          .position(|ns| id.starts_with(ns.as_str()))
+          .unwrap_or(namespaces.len()),
+        _ => 0,
+      };
+      let count = match self {
+        Canonical::MostStatements => statements.get(id).copied().unwrap_or(0),
+        _ => 0,
+      };
+      (id.starts_with("_:"), preference, usize::MAX - count)
+    };
+    cluster
+      .iter()
+      .copied()
+      .min_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.cmp(b)))
+      .expect("clusters aren't empty")
+  }
+}
+
+/// Groups the nodes connected by `owl:sameAs` into clusters & returns the
+/// mapping from each non-canonical node to its canonical one.
+pub(crate) fn redirects(
+  graph: &KnowledgeGraph,
+  canonical: &Canonical,
+) -> BTreeMap<String, String> {
+  let mut parents: HashMap<String, String> = HashMap::new();
+  let mut statements: HashMap<String, usize> = HashMap::new();
+
+  for triple in graph.triples() {
+    for source in ids(triple.source()) {
+      *statements.entry(source.clone()).or_default() += 1;
+    }
+    if triple.predicate().to_string() != OWL_SAME_AS {
+      continue;
+    }
+    for source in ids(triple.source()) {
+      for destination in ids(triple.destination()) {
+        let a = find(&mut parents, &source);
+        let b = find(&mut parents, &destination);
+        if a != b {
+          parents.insert(a, b);
+        }
+      }
+    }
+  }
+
+  let ids: Vec<String> = parents.keys().cloned().collect();
+  let mut clusters: BTreeMap<String, Vec<String>> = BTreeMap::new();
+  for id in ids {
+    let root = find(&mut parents, &id);
+    clusters.entry(root).or_default().push(id);
+  }
+
+  let mut redirects = BTreeMap::new();
+  for members in clusters.values() {
+    let members: Vec<&str> = members.iter().map(String::as_str).collect();
+    let target = canonical.pick(&members, &statements);
+    for member in members {
+      if member != target {
+        redirects.insert(member.to_string(), target.to_string());
+      }
+    }
+  }
+  redirects
+}
+
+/// Returns the root of `id`'s cluster, compressing the path to it.
+fn find(parents: &mut HashMap<String, String>, id: &str) -> String {
+  let mut root = id.to_string();
+  while let Some(parent) = parents.get(&root) {
+    if *parent == root {
+      break;
+    }
+    root = parent.clone();
+  }
+  let mut node = id.to_string();
+  while node != root {
+    let next = parents
+      .insert(node, root.clone())
+      .unwrap_or_else(|| root.clone());
+    node = next;
+  }
+  parents.entry(root.clone()).or_insert_with(|| root.clone());
+  root
+}
+
+/// Returns the ids of the IRIs & labelled blank nodes of `node`.
+pub(crate) fn ids(node: &Node) -> Vec<String> {
+  match node {
+    Node::Multiple(nodes) => nodes.iter().flat_map(ids).collect(),
+    Node::Http(_) | Node::BlankId(_) => vec![node.to_string()],
+    _ => Vec::new(),
+  }
+}
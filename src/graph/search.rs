@@ -0,0 +1,163 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+  dtype::DType,
+  graph::{Node, Triple},
+};
+
+/// `SearchHit` is a literal matching a `KnowledgeGraph::search` query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchHit {
+  /// The entity the literal belongs to.
+  pub subject: Node,
+  /// Full IRI of the predicate holding the literal.
+  pub predicate: String,
+  /// The matching text.
+  pub text: String,
+  /// Relevance of the hit: each query term adds 2 if it matched a whole
+  /// token and 1 if it matched a prefix.
+  pub score: u32,
+}
+
+/// A single indexed string literal.
+#[derive(Debug)]
+struct Document {
+  subject: Node,
+  predicate: String,
+  text: String,
+}
+
+/// `SearchIndex` is an inverted index from the lower case tokens of every
+/// string literal to the statements containing them.
+#[derive(Debug, Default)]
+pub(crate) struct SearchIndex {
+  /// Removed documents are left as `None` so ids stay stable.
+  documents: Vec<Option<Document>>,
+  postings: BTreeMap<String, Vec<usize>>,
+}
+
+impl SearchIndex {
+  /// Indexes the string literals of `triple`.
+  pub(crate) fn insert(&mut self, triple: &Triple) {
+    let mut texts = Vec::new();
+    if let Node::Literal(value) = triple.destination() {
+      strings(value, &mut texts);
+    }
+
+    for text in texts {
+      let id = self.documents.len();
+      for token in tokenize(&text) {
+        let ids = self.postings.entry(token).or_default();
+        if ids.last() != Some(&id) {
+          ids.push(id);
+        }
+      }
+      self.documents.push(Some(Document {
+        subject: triple.source().clone(),
+        predicate: triple.predicate().to_string(),
+        text,
+      }));
+    }
+  }
+
+  /// Drops every literal of `predicate` on `subject`.
+  pub(crate) fn remove(&mut self, subject: &Node, predicate: &str) {
+    for document in self.documents.iter_mut() {
+      let matches = document
+        .as_ref()
+        .is_some_and(|d| d.subject == *subject && d.predicate == predicate);
+      if matches {
+        *document = None;
+      }
+    }
+  }
+
+  /// Returns the literals containing every query term, either as a whole
+  /// token or as a prefix of one, best matches first.
+  pub(crate) fn search(&self, query: &str) -> Vec<SearchHit> {
+    let terms = tokenize(query);
+    if terms.is_empty() {
+      return Vec::new();
+    }
+
+    let mut scores: HashMap<usize, (usize, u32)> = HashMap::new();
+    for term in &terms {
+      // Best score of this term per document.
+      let mut best: HashMap<usize, u32> = HashMap::new();
+      for (token, ids) in self.postings.range(term.clone()..) {
+        if !token.starts_with(term.as_str()) {
+          break;
+        }
+        let score = if token == term { 2 } else { 1 };
+        for id in ids {
+          let entry = best.entry(*id).or_default();
+          *entry = (*entry).max(score);
+        }
+      }
+      for (id, score) in best {
+        let entry = scores.entry(id).or_default();
+        entry.0 += 1;
+        entry.1 += score;
+      }
+    }
+
+    let mut hits: Vec<(usize, SearchHit)> = scores
+      .into_iter()
+      .filter(|(_, (matched, _))| *matched == terms.len())
+      .filter_map(|(id, (_, score))| {
+        let document = self.documents[id].as_ref()?;
+        Some((
+          id,
+          SearchHit {
+            subject: document.subject.clone(),
+            predicate: document.predicate.clone(),
+            text: document.text.clone(),
+            score,
+          },
+        ))
+      })
+      .collect();
+    hits.sort_by(|(a_id, a), (b_id, b)| {
+      b.score.cmp(&a.score).then(a_id.cmp(b_id))
+    });
+    hits.into_iter().map(|(_, hit)| hit).collect()
+  }
+}
+
+/// Collects the strings of a literal, including the `@value` of language
+/// tagged value objects.
+fn strings(value: &DType, texts: &mut Vec<String>) {
+  match value {
+    DType::String(s) => texts.push(s.clone()),
+    DType::Array(values) => values.iter().for_each(|v| strings(v, texts)),
+    DType::Object(map) => {
+      if let Some(value) = map.get("@value") {
+        strings(value, texts);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// Splits `text` into lower case alphanumeric tokens.
+fn tokenize(text: &str) -> Vec<String> {
+  text
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|t| !t.is_empty())
+    .map(str::to_lowercase)
+    .collect()
+}
@@ -0,0 +1,128 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use crate::{
+  dtype::{DType, Geo, Point},
+  graph::{KnowledgeGraph, Node},
+};
+
+const WGS84_LAT: &str = "http://www.w3.org/2003/01/geo/wgs84_pos#lat";
+const WGS84_LONG: &str = "http://www.w3.org/2003/01/geo/wgs84_pos#long";
+
+/// `GeoHit` is an entity found by `KnowledgeGraph::within_radius`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GeoHit {
+  /// The located entity.
+  pub subject: Node,
+  /// Where the entity is, the centroid for lines & polygons.
+  pub point: Point,
+  /// Distance from the query point in meters.
+  pub distance: f64,
+}
+
+/// Returns the location of every entity in `graph`, in order of first
+/// appearance. An entity is located by
+///
+/// - a literal recognized by `Geo::from_dtype` (WKT, GeoJSON,
+///   `GeoCoordinates`),
+/// - a pair of `geo:lat`/`geo:long` or `schema:latitude`/`schema:longitude`
+///   literals, or
+/// - a linked node (e.g. through `schema:geo`) located by either of the
+///   above.
+pub(crate) fn locations(graph: &KnowledgeGraph) -> Vec<(Node, Point)> {
+  let mut found: Vec<(Node, Point)> = Vec::new();
+  let mut coordinates: Vec<(Node, Option<f64>, Option<f64>)> = Vec::new();
+
+  for triple in graph.triples() {
+    let value = match triple.destination() {
+      Node::Literal(value) => value,
+      _ => continue,
+    };
+    let predicate = triple.predicate().to_string();
+    let axis = if predicate == WGS84_LAT || is_schema(&predicate, "latitude") {
+      Some(true)
+    } else if predicate == WGS84_LONG || is_schema(&predicate, "longitude") {
+      Some(false)
+    } else {
+      None
+    };
+
+    match axis {
+      Some(is_lat) => {
+        let subject = triple.source();
+        let idx = match coordinates.iter().position(|(s, ..)| s == subject) {
+          Some(idx) => idx,
+          None => {
+            coordinates.push((subject.clone(), None, None));
+            coordinates.len() - 1
+          }
+        };
+        let number = number(value);
+        if is_lat {
+          coordinates[idx].1 = number;
+        } else {
+          coordinates[idx].2 = number;
+        }
+      }
+      None => {
+        if let Some(geo) = Geo::from_dtype(value) {
+          push(&mut found, triple.source(), geo.centroid());
+        }
+      }
+    }
+  }
+  for (subject, lat, lon) in coordinates {
+    if let (Some(lat), Some(lon)) = (lat, lon) {
+      push(&mut found, &subject, Point::new(lon, lat));
+    }
+  }
+
+  // Entities pointing at a located node, e.g. `schema:geo`.
+  let direct: HashMap<String, Point> = found
+    .iter()
+    .map(|(node, point)| (node.to_string(), *point))
+    .collect();
+  for triple in graph.triples() {
+    let destination = triple.destination();
+    if destination.is_literal() {
+      continue;
+    }
+    if let Some(point) = direct.get(&destination.to_string()) {
+      push(&mut found, triple.source(), *point);
+    }
+  }
+  found
+}
+
+fn push(found: &mut Vec<(Node, Point)>, subject: &Node, point: Point) {
+  if !found.iter().any(|(s, _)| s == subject) {
+    found.push((subject.clone(), point));
+  }
+}
+
+fn is_schema(predicate: &str, name: &str) -> bool {
+  ["https://schema.org/", "http://schema.org/"]
+    .iter()
+    .any(|ns| predicate.strip_prefix(ns) == Some(name))
+}
+
+fn number(value: &DType) -> Option<f64> {
+  match value {
+    DType::String(s) => s.trim().parse().ok(),
+    DType::Object(map) => map.get("@value").and_then(number),
+    value => value.as_f64(),
+  }
+}
@@ -0,0 +1,160 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+  dtype::{DType, Map},
+  graph::{KnowledgeGraph, Node},
+};
+
+/// Full IRI of `rdf:type`.
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/// Number of nodes listed under `most_connected`.
+const MOST_CONNECTED: usize = 10;
+
+/// Builds the report of `KnowledgeGraph::stats`.
+pub(crate) fn stats(graph: &KnowledgeGraph) -> DType {
+  let mut statements = 0;
+  let mut subjects = HashSet::new();
+  let mut predicates: BTreeMap<String, usize> = BTreeMap::new();
+  let mut classes: BTreeMap<String, HashSet<String>> = BTreeMap::new();
+  let mut datatypes: BTreeMap<&str, usize> = BTreeMap::new();
+  let mut degrees: HashMap<String, usize> = HashMap::new();
+
+  for triple in graph.triples() {
+    let predicate = triple.predicate().to_string();
+    let is_type = predicate == RDF_TYPE || predicate == "@type";
+    for source in flatten(triple.source()) {
+      let subject = source.to_string();
+      subjects.insert(subject.clone());
+      for destination in flatten(triple.destination()) {
+        statements += 1;
+        *predicates.entry(predicate.clone()).or_default() += 1;
+        *degrees.entry(subject.clone()).or_default() += 1;
+        match destination {
+          Node::Literal(value) => {
+            for datatype in datatypes_of(value) {
+              *datatypes.entry(datatype).or_default() += 1;
+            }
+            if let (true, DType::String(class)) = (is_type, value) {
+              let instances = classes.entry(class.clone()).or_default();
+              instances.insert(subject.clone());
+            }
+          }
+          node => {
+            let object = node.to_string();
+            if is_type {
+              let instances = classes.entry(object.clone()).or_default();
+              instances.insert(subject.clone());
+            }
+            *degrees.entry(object).or_default() += 1;
+          }
+        }
+      }
+    }
+  }
+
+  let mut distribution: BTreeMap<usize, usize> = BTreeMap::new();
+  for degree in degrees.values() {
+    *distribution.entry(*degree).or_default() += 1;
+  }
+  let mut ranked: Vec<(&String, &usize)> = degrees.iter().collect();
+  ranked.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+  let mut report = Map::new();
+  report.insert("statements".to_string(), statements.into());
+  report.insert("nodes".to_string(), degrees.len().into());
+  report.insert("subjects".to_string(), subjects.len().into());
+  report.insert("predicates".to_string(), counts(predicates));
+  report.insert(
+    "classes".to_string(),
+    counts(
+      classes
+        .into_iter()
+        .map(|(class, nodes)| (class, nodes.len())),
+    ),
+  );
+  report.insert(
+    "datatypes".to_string(),
+    counts(datatypes.into_iter().map(|(t, n)| (t.to_string(), n))),
+  );
+  report.insert(
+    "degrees".to_string(),
+    distribution
+      .into_iter()
+      .map(|(degree, nodes)| {
+        let mut entry = Map::new();
+        entry.insert("degree".to_string(), degree.into());
+        entry.insert("nodes".to_string(), nodes.into());
+        DType::Object(entry)
+      })
+      .collect(),
+  );
+  report.insert(
+    "most_connected".to_string(),
+    ranked
+      .into_iter()
+      .take(MOST_CONNECTED)
+      .map(|(node, degree)| {
+        let mut entry = Map::new();
+        entry.insert("@id".to_string(), node.clone().into());
+        entry.insert("degree".to_string(), (*degree).into());
+        DType::Object(entry)
+      })
+      .collect(),
+  );
+  DType::Object(report)
+}
+
+fn flatten(node: &Node) -> Vec<&Node> {
+  match node {
+    Node::Multiple(nodes) => nodes.iter().flat_map(flatten).collect(),
+    node => vec![node],
+  }
+}
+
+/// Returns the XSD-style datatype of every literal `value` is written as,
+/// mirroring the N-Triples export: arrays are one literal per element,
+/// `null`s aren't written.
+fn datatypes_of(value: &DType) -> Vec<&'static str> {
+  match value {
+    DType::Null => Vec::new(),
+    DType::Array(values) => values.iter().flat_map(datatypes_of).collect(),
+    DType::String(_) => vec!["string"],
+    DType::Bytes(_) => vec!["base64Binary"],
+    DType::Boolean(_) => vec!["boolean"],
+    DType::Number(n) if n.is_f64() => vec!["double"],
+    DType::Number(_) => vec!["integer"],
+    DType::DateTime(_) => vec!["dateTime"],
+    DType::Object(map)
+      if map.len() == 2
+        && map.contains_key("@value")
+        && map.contains_key("@language") =>
+    {
+      vec!["langString"]
+    }
+    DType::Object(_) => vec!["json"],
+  }
+}
+
+fn counts(counts: impl IntoIterator<Item = (String, usize)>) -> DType {
+  DType::Object(
+    counts
+      .into_iter()
+      .map(|(key, count)| (key, count.into()))
+      .collect(),
+  )
+}
@@ -17,6 +17,7 @@
 use std::{fmt, str::FromStr};
 
 use crate::{
+  dtype::DeepSizeOf,
   error::{Error, ErrorCode},
   graph::*,
 };
@@ -85,6 +86,7 @@ pub struct Triple {
   predicate: Predicate,
   destination: Node,
   connection: Connection,
+  provenance: Option<Box<Provenance>>,
 }
 
 impl Triple {
@@ -95,6 +97,94 @@ impl Triple {
       predicate: Predicate::Literal("".to_string()),
       destination: Node::Blank,
       connection: Connection::Forward,
+      provenance: None,
+    }
+  }
+
+  /// Creates a new `Triple` connecting `source` to `destination` through a
+  /// forward `predicate`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{Node, Predicate, Triple};
+  ///
+  /// let triple = Triple::from_nodes(
+  ///   Node::Http("https://example.com/John".to_string()),
+  ///   Predicate::Literal("knows".to_string()),
+  ///   Node::Http("https://example.com/Jane".to_string()),
+  /// );
+  ///
+  /// assert_eq!(triple.predicate(), &Predicate::Literal("knows".to_string()));
+  /// assert!(triple.connection().is_forward());
+  /// ```
+  pub fn from_nodes(
+    source: Node,
+    predicate: Predicate,
+    destination: Node,
+  ) -> Triple {
+    Triple {
+      source,
+      predicate,
+      destination,
+      ..Triple::new()
+    }
+  }
+
+  /// Returns the subject of the `Triple`.
+  pub fn source(&self) -> &Node {
+    &self.source
+  }
+
+  /// Returns the predicate connecting the source & destination nodes.
+  pub fn predicate(&self) -> &Predicate {
+    &self.predicate
+  }
+
+  /// Returns the object of the `Triple`.
+  pub fn destination(&self) -> &Node {
+    &self.destination
+  }
+
+  /// Attaches `provenance` to the `Triple`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{Node, Predicate, Provenance, Triple};
+  ///
+  /// let triple = Triple::from_nodes(
+  ///   Node::Http("https://example.com/Ada".to_string()),
+  ///   Predicate::Literal("https://schema.org/birthDate".to_string()),
+  ///   Node::Literal("1815-12-10".into()),
+  /// )
+  /// .with_provenance(Provenance::new("census.csv").confidence(0.9));
+  ///
+  /// assert_eq!(triple.confidence(), Some(0.9));
+  /// let provenance = triple.provenance().unwrap();
+  /// assert_eq!(provenance.source.as_deref(), Some("census.csv"));
+  /// ```
+  pub fn with_provenance(mut self, provenance: Provenance) -> Triple {
+    self.provenance = Some(Box::new(provenance));
+    self
+  }
+
+  /// Returns the provenance of the `Triple`, if any.
+  pub fn provenance(&self) -> Option<&Provenance> {
+    self.provenance.as_deref()
+  }
+
+  /// Returns the confidence in the `Triple`, if its provenance has one.
+  pub fn confidence(&self) -> Option<f64> {
+    self.provenance.as_ref().and_then(|p| p.confidence)
+  }
+
+  /// Returns a copy of the `Triple` between other nodes, keeping its
+  /// predicate & provenance.
+  pub(crate) fn with_nodes(&self, source: Node, destination: Node) -> Triple {
+    Triple {
+      provenance: self.provenance.clone(),
+      ..Triple::from_nodes(source, self.predicate.clone(), destination)
     }
   }
 
@@ -123,6 +213,16 @@ impl Default for Triple {
   }
 }
 
+impl DeepSizeOf for Triple {
+  fn heap_size_of(&self) -> usize {
+    self.id.0.heap_size_of()
+      + self.source.heap_size_of()
+      + self.predicate.heap_size_of()
+      + self.destination.heap_size_of()
+      + self.provenance.heap_size_of()
+  }
+}
+
 impl fmt::Display for Triple {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     match self.connection() {
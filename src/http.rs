@@ -0,0 +1,168 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The HTTP(S) client shared by `query::Endpoint` &
+//! `interop::elastic::Elastic`.
+
+use std::{
+  io::{self, Read},
+  time::Duration,
+};
+
+use serde::de::Error as _;
+
+use crate::{error::Error, Result};
+
+/// Default time allowed for a whole request, from connecting to reading
+/// the last byte of the response.
+pub(crate) const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Most bytes read from a response body.
+pub(crate) const MAX_BODY: u64 = 64 << 20;
+
+/// `Client` sends requests to the URLs under a base `http://` or
+/// `https://` URL, with a set of extra headers & a timeout.
+#[derive(Clone, Debug)]
+pub(crate) struct Client {
+  agent: ureq::Agent,
+  url: String,
+  headers: Vec<(String, String)>,
+  timeout: Duration,
+  /// What the URL points at, e.g. "SPARQL endpoint", for error messages.
+  service: &'static str,
+}
+
+impl Client {
+  /// Creates a client for the `service` at `url`.
+  pub(crate) fn new(url: &str, service: &'static str) -> Result<Client> {
+    let invalid =
+      || Error::custom(format!("unsupported {} URL `{}`", service, url));
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+      return Err(invalid());
+    }
+    let agent = ureq::Agent::new();
+    agent.get(url).request_url().map_err(|_| invalid())?;
+    Ok(Client {
+      agent,
+      url: url.to_string(),
+      headers: Vec::new(),
+      timeout: TIMEOUT,
+      service,
+    })
+  }
+
+  /// Sends `name: value` with every request.
+  ///
+  /// # Errors
+  ///
+  /// Fails if `name` isn't a header name, or `value` holds a control
+  /// character (e.g. CR or LF) which could smuggle in other headers.
+  pub(crate) fn header(mut self, name: &str, value: &str) -> Result<Self> {
+    let token =
+      |b: u8| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b);
+    let visible = |b: u8| b == b'\t' || (b' '..=b'~').contains(&b);
+    if name.is_empty() || !name.bytes().all(token) {
+      return Err(Error::custom(format!("invalid header name `{}`", name)));
+    }
+    if !value.bytes().all(visible) {
+      return Err(Error::custom(format!(
+        "invalid value for header `{}`",
+        name
+      )));
+    }
+    self.headers.push((name.to_string(), value.to_string()));
+    Ok(self)
+  }
+
+  /// Fails requests which take longer than `timeout`. Defaults to
+  /// `TIMEOUT`.
+  pub(crate) fn timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  /// Sends a `GET` request to the base URL with the `query` parameters &
+  /// returns the response body.
+  pub(crate) fn get(
+    &self,
+    query: &[(&str, &str)],
+    accept: &str,
+  ) -> Result<Vec<u8>> {
+    let request = self
+      .request("GET", &self.url)
+      .set("Accept", accept)
+      .query_pairs(query.iter().copied());
+    self.read(request.call())
+  }
+
+  /// Sends `body` in a `POST` request to `path` under the base URL &
+  /// returns the response body.
+  #[cfg(feature = "elastic")]
+  pub(crate) fn post(
+    &self,
+    path: &str,
+    content_type: &str,
+    body: &[u8],
+  ) -> Result<Vec<u8>> {
+    let url = format!("{}{}", self.url.trim_end_matches('/'), path);
+    let request = self.request("POST", &url).set("Content-Type", content_type);
+    self.read(request.send_bytes(body))
+  }
+
+  fn request(&self, method: &str, url: &str) -> ureq::Request {
+    let mut request = self.agent.request(method, url).timeout(self.timeout);
+    for (name, value) in &self.headers {
+      request = request.set(name, value);
+    }
+    request
+  }
+
+  /// Reads the body of `response`, failing on non-2xx status codes.
+  fn read(
+    &self,
+    response: std::result::Result<ureq::Response, ureq::Error>,
+  ) -> Result<Vec<u8>> {
+    match response {
+      Ok(response) => read_body(response),
+      Err(ureq::Error::Status(status, response)) => {
+        let body = read_body(response).unwrap_or_default();
+        Err(Error::custom(format!(
+          "{} {} responded with {}: {}",
+          self.service,
+          self.url,
+          status,
+          String::from_utf8_lossy(&body)
+        )))
+      }
+      Err(ureq::Error::Transport(err)) => Err(Error::io(io::Error::other(err))),
+    }
+  }
+}
+
+/// Reads at most `MAX_BODY` bytes of the body of `response`.
+fn read_body(response: ureq::Response) -> Result<Vec<u8>> {
+  let mut body = Vec::new();
+  response
+    .into_reader()
+    .take(MAX_BODY + 1)
+    .read_to_end(&mut body)
+    .map_err(Error::io)?;
+  if body.len() as u64 > MAX_BODY {
+    return Err(Error::io(io::Error::other(format!(
+      "response body exceeds {} bytes",
+      MAX_BODY
+    ))));
+  }
+  Ok(body)
+}
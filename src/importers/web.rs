@@ -0,0 +1,540 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::importers::web` extracts the structured data embedded in web
+//! pages: [JSON-LD] `<script type="application/ld+json">` blocks,
+//! [microdata] & [RDFa Lite] annotations. Together with `links`, which
+//! lists the pages of sitemaps & RSS/Atom feeds, it's the on-ramp for
+//! building a knowledge graph from a website.
+//!
+//! Microdata & RDFa are read into flattened JSON-LD node objects with full
+//! IRIs, so every kind of annotation can be handled like JSON-LD.
+//!
+//! [JSON-LD]: https://www.w3.org/TR/json-ld11/#embedding-json-ld-in-html-documents
+//! [microdata]: https://html.spec.whatwg.org/multipage/microdata.html
+//! [RDFa Lite]: https://www.w3.org/TR/rdfa-lite/
+
+mod html;
+
+use crate::{
+  datastore::json,
+  dtype::{DType, Map},
+  formats::JsonLd,
+  graph::{KnowledgeGraph, MergePolicy},
+  iri::resolve,
+  vocab::Namespaces,
+};
+
+use html::Element;
+
+/// Elements whose microdata value is the URL in their `src` attribute.
+const SRC_ELEMENTS: &[&str] = &[
+  "audio", "embed", "iframe", "img", "source", "track", "video",
+];
+
+/// Elements whose microdata value is the URL in their `href` attribute.
+const HREF_ELEMENTS: &[&str] = &["a", "area", "link"];
+
+/// `WebPage` holds the structured data extracted from an HTML page.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::importers::web::WebPage;
+///
+/// let page = WebPage::parse(
+///   r##"<html><head>
+///     <script type="application/ld+json">
+///       { "@context": { "@vocab": "https://schema.org/" },
+///         "@id": "https://example.com/books/1", "@type": "Book",
+///         "name": "Sketches of the Analytical Engine" }
+///     </script>
+///   </head><body>
+///     <div itemscope itemtype="https://schema.org/Person" itemid="/people/ada">
+///       <span itemprop="name">Ada Lovelace</span>
+///       <a itemprop="url" href="/ada">home page</a>
+///     </div>
+///     <p vocab="https://schema.org/" typeof="Event" resource="#talk">
+///       <span property="name">Notes on the engine</span>
+///     </p>
+///   </body></html>"##,
+///   "https://example.com/index.html",
+/// );
+///
+/// assert_eq!(page.json_ld().len(), 1);
+/// assert_eq!(page.microdata()[0]["@id"], "https://example.com/people/ada");
+/// assert_eq!(page.microdata()[0]["https://schema.org/name"], "Ada Lovelace");
+/// assert_eq!(page.rdfa()[0]["@id"], "https://example.com/index.html#talk");
+///
+/// let graph = page.to_graph();
+/// let ada = graph.entity("https://example.com/people/ada");
+/// assert_eq!(ada.get("https://schema.org/url").len(), 1);
+/// // Two statements each for the book & the talk, three for Ada.
+/// assert_eq!(graph.len(), 2 + 2 + 3);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WebPage {
+  json_ld: Vec<DType>,
+  microdata: Vec<DType>,
+  rdfa: Vec<DType>,
+}
+
+impl WebPage {
+  /// Extracts the structured data of the HTML page found at `url`, which
+  /// (or the page's `<base href>`) relative URLs are resolved against.
+  ///
+  /// Pages are often hand written, so the HTML is read forgivingly and
+  /// JSON-LD blocks which aren't valid JSON are skipped.
+  pub fn parse(html: &str, url: &str) -> WebPage {
+    let document = html::parse(html, false);
+    let base = find(&document, &|el| el.name == "base" && el.has("href"))
+      .and_then(|el| el.attr("href"))
+      .map_or_else(|| url.to_string(), |href| resolve(url, href));
+
+    let mut page = WebPage::default();
+    page.scripts(&document);
+
+    let mut microdata = Microdata {
+      base: &base,
+      nodes: Vec::new(),
+    };
+    microdata.walk(&document, None);
+    page.microdata = microdata.nodes.into_iter().map(DType::Object).collect();
+
+    let mut rdfa = Rdfa {
+      base: &base,
+      nodes: Vec::new(),
+    };
+    let context = RdfaContext {
+      vocab: None,
+      namespaces: Namespaces::default(),
+      subject: base.clone(),
+    };
+    rdfa.walk(&document, &context);
+    page.rdfa = rdfa.nodes.into_iter().map(DType::Object).collect();
+
+    page
+  }
+
+  /// Returns the JSON-LD documents embedded in the page.
+  pub fn json_ld(&self) -> &[DType] {
+    &self.json_ld
+  }
+
+  /// Returns the microdata items of the page as JSON-LD node objects, nested
+  /// items referenced by their `@id`.
+  pub fn microdata(&self) -> &[DType] {
+    &self.microdata
+  }
+
+  /// Returns the RDFa resources of the page as JSON-LD node objects, nested
+  /// resources referenced by their `@id`.
+  pub fn rdfa(&self) -> &[DType] {
+    &self.rdfa
+  }
+
+  /// Returns `true` if the page holds no structured data.
+  pub fn is_empty(&self) -> bool {
+    self.json_ld.is_empty() && self.microdata.is_empty() && self.rdfa.is_empty()
+  }
+
+  /// Returns every JSON-LD document of the page, followed by a document
+  /// with the microdata & RDFa node objects under `@graph`.
+  pub fn to_dtype(&self) -> DType {
+    let mut documents = self.json_ld.clone();
+    let nodes: Vec<DType> =
+      self.microdata.iter().chain(&self.rdfa).cloned().collect();
+    if !nodes.is_empty() {
+      let mut document = Map::new();
+      document.insert("@graph".to_string(), nodes.into());
+      documents.push(DType::Object(document));
+    }
+    documents.into()
+  }
+
+  /// Reads the structured data of the page into a new `KnowledgeGraph`.
+  ///
+  /// Every document is read like `JsonLd::from_dtype` and merged into the
+  /// graph, renaming clashing blank nodes. JSON-LD blocks which aren't
+  /// valid JSON-LD are skipped.
+  pub fn to_graph(&self) -> KnowledgeGraph {
+    let mut graph = KnowledgeGraph::new();
+    if let DType::Array(ref documents) = self.to_dtype() {
+      for document in documents.iter() {
+        if let Ok(other) = JsonLd::from_dtype(document) {
+          graph.merge(&other, MergePolicy::new());
+        }
+      }
+    }
+    graph
+  }
+
+  fn scripts(&mut self, el: &Element) {
+    let is_json_ld = el.name == "script"
+      && el.attr("type").is_some_and(|t| {
+        let t = t.split(';').next().unwrap_or_default().trim();
+        t.eq_ignore_ascii_case("application/ld+json")
+      });
+    if is_json_ld {
+      if let Ok(document) = json::from_str::<DType>(el.raw_text().trim()) {
+        self.json_ld.push(document);
+      }
+    }
+    for child in el.elements() {
+      self.scripts(child);
+    }
+  }
+}
+
+/// Returns the page URLs listed by a sitemap (or sitemap index), an RSS
+/// feed or an Atom feed, in document order.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::importers::web::links;
+///
+/// let sitemap = r#"<?xml version="1.0" encoding="UTF-8"?>
+/// <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+///   <url><loc>https://example.com/</loc></url>
+///   <url><loc>https://example.com/about?lang=en&amp;v=2</loc></url>
+/// </urlset>"#;
+/// assert_eq!(
+///   links(sitemap),
+///   ["https://example.com/", "https://example.com/about?lang=en&v=2"]
+/// );
+///
+/// let rss = r#"<rss version="2.0"><channel>
+///   <link>https://example.com/</link>
+///   <item><title>Hello</title><link><![CDATA[https://example.com/hello]]></link></item>
+/// </channel></rss>"#;
+/// assert_eq!(links(rss), ["https://example.com/hello"]);
+///
+/// let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+///   <entry><link rel="alternate" href="https://example.com/atom"/></entry>
+/// </feed>"#;
+/// assert_eq!(links(atom), ["https://example.com/atom"]);
+/// ```
+pub fn links(document: &str) -> Vec<String> {
+  let mut links = Vec::new();
+  collect_links(&html::parse(document, true), false, &mut links);
+  links
+}
+
+fn collect_links(el: &Element, in_entry: bool, links: &mut Vec<String>) {
+  let in_entry = in_entry || el.name == "item" || el.name == "entry";
+  match el.name.as_str() {
+    "loc" => links.push(el.text()),
+    "link" if in_entry => match el.attr("href") {
+      Some(href) if el.attr("rel").is_none_or(|rel| rel == "alternate") => {
+        links.push(href.trim().to_string())
+      }
+      Some(_) => {}
+      None => links.push(el.text()),
+    },
+    _ => {}
+  }
+  for child in el.elements() {
+    collect_links(child, in_entry, links);
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Microdata.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Collects microdata items as flattened node objects.
+struct Microdata<'a> {
+  base: &'a str,
+  nodes: Vec<Map<String, DType>>,
+}
+
+impl<'a> Microdata<'a> {
+  /// Walks `el`, adding the properties it declares to the item `owner`
+  /// (its index & vocabulary).
+  fn walk(&mut self, el: &Element, owner: Option<(usize, &str)>) {
+    let props: Vec<&str> = el
+      .attr("itemprop")
+      .unwrap_or_default()
+      .split_whitespace()
+      .collect();
+
+    if el.has("itemscope") {
+      let (idx, vocab) = self.item(el);
+      if let Some((owner, owner_vocab)) = owner {
+        let id = self.nodes[idx]["@id"].clone();
+        for prop in &props {
+          let value = reference(id.as_str().unwrap_or_default());
+          add(&mut self.nodes[owner], expand(prop, owner_vocab), value);
+        }
+      }
+      for child in el.elements() {
+        self.walk(child, Some((idx, &vocab)));
+      }
+      return;
+    }
+
+    if let Some((owner, vocab)) = owner {
+      if !props.is_empty() {
+        let value = self.value(el);
+        for prop in &props {
+          add(&mut self.nodes[owner], expand(prop, vocab), value.clone());
+        }
+      }
+    }
+    for child in el.elements() {
+      self.walk(child, owner);
+    }
+  }
+
+  /// Creates the node of an `itemscope` element, returning its index &
+  /// the vocabulary of its type.
+  fn item(&mut self, el: &Element) -> (usize, String) {
+    let mut node = Map::new();
+    let id = match el.attr("itemid") {
+      Some(id) => resolve(self.base, id),
+      None => format!("_:md{}", self.nodes.len()),
+    };
+    node.insert("@id".to_string(), id.into());
+
+    let types: Vec<&str> = el
+      .attr("itemtype")
+      .unwrap_or_default()
+      .split_whitespace()
+      .collect();
+    let vocab = types.first().map_or("", |t| vocabulary(t)).to_string();
+    match types.as_slice() {
+      [] => {}
+      [t] => {
+        node.insert("@type".to_string(), (*t).into());
+      }
+      types => {
+        node.insert("@type".to_string(), types.to_vec().into());
+      }
+    }
+
+    self.nodes.push(node);
+    (self.nodes.len() - 1, vocab)
+  }
+
+  /// Returns the value of a property element.
+  fn value(&self, el: &Element) -> DType {
+    let name = el.name.as_str();
+    let url = if SRC_ELEMENTS.contains(&name) {
+      el.attr("src")
+    } else if HREF_ELEMENTS.contains(&name) {
+      el.attr("href")
+    } else if name == "object" {
+      el.attr("data")
+    } else {
+      None
+    };
+    if let Some(url) = url {
+      return reference(&resolve(self.base, url));
+    }
+
+    let value = match name {
+      _ if el.has("content") => el.attr("content"),
+      "data" | "meter" => el.attr("value"),
+      "time" => el.attr("datetime"),
+      _ => None,
+    };
+    value.map_or_else(|| el.text(), str::to_string).into()
+  }
+}
+
+/// Returns the vocabulary of a microdata type: the type's IRI up to its
+/// last `/` or `#`.
+fn vocabulary(itemtype: &str) -> &str {
+  itemtype
+    .rfind(['/', '#'])
+    .map_or(itemtype, |idx| &itemtype[..=idx])
+}
+
+/// Expands a microdata property name with the item's vocabulary, unless
+/// it's an absolute URL already.
+fn expand(name: &str, vocab: &str) -> String {
+  if name.contains(':') {
+    name.to_string()
+  } else {
+    format!("{}{}", vocab, name)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | RDFa Lite.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Collects RDFa resources as flattened node objects.
+struct Rdfa<'a> {
+  base: &'a str,
+  nodes: Vec<Map<String, DType>>,
+}
+
+/// Evaluation context inherited by the descendants of an element.
+#[derive(Clone)]
+struct RdfaContext {
+  vocab: Option<String>,
+  namespaces: Namespaces,
+  /// `@id` of the resource properties are about.
+  subject: String,
+}
+
+impl RdfaContext {
+  /// Expands a term, CURIE or IRI.
+  fn expand(&self, term: &str) -> String {
+    if term.contains(':') {
+      return self
+        .namespaces
+        .expand(term)
+        .unwrap_or_else(|_| term.to_string());
+    }
+    match &self.vocab {
+      Some(vocab) => format!("{}{}", vocab, term),
+      None => term.to_string(),
+    }
+  }
+}
+
+impl<'a> Rdfa<'a> {
+  fn walk(&mut self, el: &Element, parent: &RdfaContext) {
+    let mut context = parent.clone();
+    if let Some(vocab) = el.attr("vocab") {
+      context.vocab = Some(vocab.trim().to_string()).filter(|v| !v.is_empty());
+    }
+    if let Some(prefixes) = el.attr("prefix") {
+      let mut words = prefixes.split_whitespace();
+      while let (Some(prefix), Some(namespace)) = (words.next(), words.next()) {
+        if let Some(prefix) = prefix.strip_suffix(':') {
+          context.namespaces.bind(prefix, namespace);
+        }
+      }
+    }
+
+    let props: Vec<String> = el
+      .attr("property")
+      .unwrap_or_default()
+      .split_whitespace()
+      .map(|p| context.expand(p))
+      .collect();
+    let resource = ["resource", "href", "src"]
+      .iter()
+      .find_map(|attr| el.attr(attr))
+      .map(|iri| resolve(self.base, iri));
+
+    if let Some(types) = el.attr("typeof") {
+      // A new resource, the value of the properties of the parent resource.
+      let id = match el.attr("about").map(|iri| resolve(self.base, iri)) {
+        Some(id) => id,
+        None => resource
+          .clone()
+          .unwrap_or_else(|| format!("_:rdfa{}", self.nodes.len())),
+      };
+      let idx = self.node(&id);
+      for t in types.split_whitespace() {
+        add(
+          &mut self.nodes[idx],
+          "@type".to_string(),
+          context.expand(t).into(),
+        );
+      }
+      for prop in props {
+        let subject = self.node(&parent.subject);
+        add(&mut self.nodes[subject], prop, reference(&id));
+      }
+      context.subject = id;
+    } else {
+      if let Some(about) = el.attr("about") {
+        context.subject = resolve(self.base, about);
+      }
+      if !props.is_empty() {
+        let value = match (&resource, el.attr("content")) {
+          (Some(iri), _) => reference(iri),
+          (None, Some(content)) => content.into(),
+          (None, None) if el.name == "time" && el.has("datetime") => {
+            el.attr("datetime").unwrap_or_default().into()
+          }
+          (None, None) => el.text().into(),
+        };
+        let subject = self.node(&context.subject);
+        for prop in props {
+          add(&mut self.nodes[subject], prop, value.clone());
+        }
+      }
+    }
+
+    for child in el.elements() {
+      self.walk(child, &context);
+    }
+  }
+
+  /// Returns the index of the node with `id`, creating it if needed.
+  fn node(&mut self, id: &str) -> usize {
+    if let Some(idx) = self.nodes.iter().position(|n| n["@id"] == id) {
+      return idx;
+    }
+    let mut node = Map::new();
+    node.insert("@id".to_string(), id.into());
+    self.nodes.push(node);
+    self.nodes.len() - 1
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Helpers.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Adds `value` to the values of `key`, turning them into an array if
+/// there are several.
+fn add(node: &mut Map<String, DType>, key: String, value: DType) {
+  match node.get_mut(&key) {
+    Some(DType::Array(values)) => values.push(value),
+    Some(existing) => {
+      let first = existing.take();
+      *existing = vec![first, value].into();
+    }
+    None => {
+      node.insert(key, value);
+    }
+  }
+}
+
+fn reference(id: &str) -> DType {
+  let mut map = Map::new();
+  map.insert("@id".to_string(), id.into());
+  DType::Object(map)
+}
+
+/// Returns the first element (in document order) satisfying `f`.
+fn find<'e>(
+  el: &'e Element,
+  f: &dyn Fn(&Element) -> bool,
+) -> Option<&'e Element> {
+  if f(el) {
+    return Some(el);
+  }
+  el.elements().find_map(|child| find(child, f))
+}
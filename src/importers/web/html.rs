@@ -0,0 +1,292 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A forgiving HTML & XML tree builder, just enough to find the structured
+//! data of a page: unknown end tags are ignored, unclosed elements are
+//! closed by the end tag of an ancestor or the end of the document.
+
+/// Elements which never have content in HTML.
+const VOID: &[&str] = &[
+  "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta",
+  "param", "source", "track", "wbr",
+];
+
+/// Elements whose content is read as is, up to their end tag.
+const RAW_TEXT: &[&str] = &["script", "style"];
+
+/// An element with lower case tag & attribute names.
+#[derive(Debug, Default)]
+pub(crate) struct Element {
+  pub(crate) name: String,
+  pub(crate) attrs: Vec<(String, String)>,
+  pub(crate) children: Vec<Content>,
+}
+
+#[derive(Debug)]
+pub(crate) enum Content {
+  Element(Element),
+  Text(String),
+}
+
+impl Element {
+  /// Returns the value of the attribute `name`.
+  pub(crate) fn attr(&self, name: &str) -> Option<&str> {
+    self
+      .attrs
+      .iter()
+      .find(|(n, _)| n == name)
+      .map(|(_, v)| v.as_str())
+  }
+
+  /// Returns `true` if the element has the attribute `name`.
+  pub(crate) fn has(&self, name: &str) -> bool {
+    self.attr(name).is_some()
+  }
+
+  /// Returns the child elements.
+  pub(crate) fn elements(&self) -> impl Iterator<Item = &Element> {
+    self.children.iter().filter_map(|child| match child {
+      Content::Element(el) => Some(el),
+      Content::Text(_) => None,
+    })
+  }
+
+  /// Returns the text of the direct children, unchanged.
+  pub(crate) fn raw_text(&self) -> String {
+    self
+      .children
+      .iter()
+      .filter_map(|child| match child {
+        Content::Text(text) => Some(text.as_str()),
+        Content::Element(_) => None,
+      })
+      .collect()
+  }
+
+  /// Returns the text of every descendant, with runs of whitespace
+  /// collapsed into single spaces.
+  pub(crate) fn text(&self) -> String {
+    let mut text = String::new();
+    self.collect_text(&mut text);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+  }
+
+  fn collect_text(&self, out: &mut String) {
+    for child in &self.children {
+      match child {
+        Content::Text(text) => out.push_str(text),
+        Content::Element(el) => {
+          out.push(' ');
+          el.collect_text(out);
+        }
+      }
+    }
+  }
+}
+
+/// Parses an HTML (or, with `xml`, an XML) document into a root element
+/// named `#document`. XML elements only lack content when written `<a/>`.
+pub(crate) fn parse(input: &str, xml: bool) -> Element {
+  let mut stack = vec![Element {
+    name: "#document".to_string(),
+    ..Element::default()
+  }];
+  let mut rest = input;
+
+  while !rest.is_empty() {
+    if let Some(after) = rest.strip_prefix("<!--") {
+      rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+    } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+      let end = after.find("]]>").unwrap_or(after.len());
+      push_text(&mut stack, after[..end].to_string());
+      rest = after.get(end + 3..).unwrap_or("");
+    } else if rest.starts_with("<!") || rest.starts_with("<?") {
+      rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+    } else if let Some(after) = rest.strip_prefix("</") {
+      let end = after.find('>').unwrap_or(after.len());
+      let name = after[..end].trim().to_ascii_lowercase();
+      close(&mut stack, &name);
+      rest = after.get(end + 1..).unwrap_or("");
+    } else if rest.starts_with('<')
+      && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic())
+    {
+      let (el, self_closing, after) = start_tag(&rest[1..]);
+      rest = after;
+      if RAW_TEXT.contains(&el.name.as_str()) && !self_closing {
+        let (text, after) = raw_text(rest, &el.name);
+        rest = after;
+        let mut el = el;
+        el.children.push(Content::Text(text.to_string()));
+        push_element(&mut stack, el);
+      } else if self_closing || (!xml && VOID.contains(&el.name.as_str())) {
+        push_element(&mut stack, el);
+      } else {
+        stack.push(el);
+      }
+    } else {
+      let first = rest.chars().next().map_or(1, char::len_utf8);
+      let end = rest[first..]
+        .find('<')
+        .map_or(rest.len(), |end| end + first);
+      push_text(&mut stack, decode(&rest[..end]));
+      rest = &rest[end..];
+    }
+  }
+
+  while stack.len() > 1 {
+    let el = stack.pop().unwrap();
+    push_element(&mut stack, el);
+  }
+  stack.pop().unwrap()
+}
+
+fn push_element(stack: &mut [Element], el: Element) {
+  if let Some(parent) = stack.last_mut() {
+    parent.children.push(Content::Element(el));
+  }
+}
+
+fn push_text(stack: &mut [Element], text: String) {
+  if let Some(parent) = stack.last_mut() {
+    parent.children.push(Content::Text(text));
+  }
+}
+
+/// Closes the innermost open element named `name` and every element opened
+/// inside it. End tags without an open element are ignored.
+fn close(stack: &mut Vec<Element>, name: &str) {
+  let open = match stack.iter().skip(1).rposition(|el| el.name == name) {
+    Some(idx) => idx + 1,
+    None => return,
+  };
+  while stack.len() > open {
+    let el = stack.pop().unwrap();
+    push_element(stack, el);
+  }
+}
+
+/// Reads a start tag (after its `<`), returning the element, whether it was
+/// self-closing & the rest of the input.
+fn start_tag(input: &str) -> (Element, bool, &str) {
+  let end = input
+    .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+    .unwrap_or(input.len());
+  let mut el = Element {
+    name: input[..end].to_ascii_lowercase(),
+    ..Element::default()
+  };
+  let mut rest = &input[end..];
+
+  loop {
+    rest = rest.trim_start();
+    if let Some(after) = rest.strip_prefix("/>") {
+      return (el, true, after);
+    } else if let Some(after) = rest.strip_prefix('>') {
+      return (el, false, after);
+    } else if let Some(after) = rest.strip_prefix('/') {
+      rest = after;
+      continue;
+    } else if rest.is_empty() {
+      return (el, false, rest);
+    }
+
+    let end = rest
+      .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')
+      .unwrap_or(rest.len())
+      .max(1);
+    let name = rest[..end].to_ascii_lowercase();
+    rest = rest[end..].trim_start();
+
+    let value = match rest.strip_prefix('=') {
+      Some(after) => {
+        let after = after.trim_start();
+        match after.chars().next() {
+          Some(quote @ ('"' | '\'')) => {
+            let after = &after[1..];
+            let end = after.find(quote).unwrap_or(after.len());
+            rest = after.get(end + 1..).unwrap_or("");
+            decode(&after[..end])
+          }
+          _ => {
+            let end = after
+              .find(|c: char| c.is_whitespace() || c == '>')
+              .unwrap_or(after.len());
+            rest = &after[end..];
+            decode(&after[..end])
+          }
+        }
+      }
+      None => String::new(),
+    };
+    el.attrs.push((name, value));
+  }
+}
+
+/// Splits the content of a raw text element from the rest of the input,
+/// after its end tag.
+fn raw_text<'a>(input: &'a str, name: &str) -> (&'a str, &'a str) {
+  let end_tag = format!("</{}", name);
+  let end = input
+    .to_ascii_lowercase()
+    .find(&end_tag)
+    .unwrap_or(input.len());
+  let rest = &input[end..];
+  let rest = rest.find('>').map_or("", |close| &rest[close + 1..]);
+  (&input[..end], rest)
+}
+
+/// Replaces character references, keeping unknown ones as written.
+pub(crate) fn decode(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  let mut rest = text;
+  while let Some(start) = rest.find('&') {
+    out.push_str(&rest[..start]);
+    rest = &rest[start..];
+    let decoded =
+      rest[1..]
+        .find(';')
+        .filter(|&end| end <= 10)
+        .and_then(|end| {
+          let c = match &rest[1..=end] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{a0}'),
+            reference => {
+              let code = reference.strip_prefix('#')?;
+              let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+              };
+              char::from_u32(code)
+            }
+          }?;
+          Some((c, end + 2))
+        });
+    match decoded {
+      Some((c, len)) => {
+        out.push(c);
+        rest = &rest[len..];
+      }
+      None => {
+        out.push('&');
+        rest = &rest[1..];
+      }
+    }
+  }
+  out.push_str(rest);
+  out
+}
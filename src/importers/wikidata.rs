@@ -0,0 +1,472 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::importers::wikidata` streams [Wikidata JSON dumps] into a
+//! `KnowledgeGraph`, following the [Wikidata RDF mapping].
+//!
+//! Every statement of an entity becomes a direct ("truthy") triple
+//! `wd:Q42 wdt:P31 wd:Q5` if it has the best rank of its property, and a
+//! statement node `wd:Q42 p:P31 wds:<id>` carrying its value (`ps:`), rank,
+//! qualifiers (`pq:`) & references (`prov:wasDerivedFrom wdref:<hash>`,
+//! `pr:`). Labels, descriptions & aliases become language tagged literals.
+//! Every triple records the entity it was imported from as its provenance.
+//!
+//! Full dumps hold around a hundred million entities, so imports can be
+//! restricted to a whitelist of entities, properties & languages.
+//!
+//! [Wikidata JSON dumps]: https://www.wikidata.org/wiki/Wikidata:Database_download#JSON_dumps_(recommended)
+//! [Wikidata RDF mapping]: https://www.mediawiki.org/wiki/Wikibase/Indexing/RDF_Dump_Format
+
+use std::{collections::HashSet, io::BufRead};
+
+use crate::{
+  datastore::json,
+  dtype::{DType, DateTime, Geo, LangString, Point},
+  error::{Error, ErrorCode},
+  graph::{KnowledgeGraph, Node, Predicate, Provenance},
+  Result,
+};
+
+const WD: &str = "http://www.wikidata.org/entity/";
+const WDS: &str = "http://www.wikidata.org/entity/statement/";
+const WDREF: &str = "http://www.wikidata.org/reference/";
+const WDT: &str = "http://www.wikidata.org/prop/direct/";
+const P: &str = "http://www.wikidata.org/prop/";
+const PS: &str = "http://www.wikidata.org/prop/statement/";
+const PQ: &str = "http://www.wikidata.org/prop/qualifier/";
+const PR: &str = "http://www.wikidata.org/prop/reference/";
+const WIKIBASE: &str = "http://wikiba.se/ontology#";
+const ENTITY_DATA: &str = "https://www.wikidata.org/wiki/Special:EntityData/";
+
+const RDFS_LABEL: &str = "http://www.w3.org/2000/01/rdf-schema#label";
+const SCHEMA_DESCRIPTION: &str = "http://schema.org/description";
+const SKOS_ALT_LABEL: &str = "http://www.w3.org/2004/02/skos/core#altLabel";
+const PROV_WAS_DERIVED_FROM: &str = "http://www.w3.org/ns/prov#wasDerivedFrom";
+
+/// `WikidataImporter` maps Wikidata entities into triples.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node};
+/// use sage::importers::wikidata::WikidataImporter;
+/// use sage::json;
+///
+/// let item = |id: &str| {
+///   json!({
+///     "snaktype": "value",
+///     "datatype": "wikibase-item",
+///     "datavalue": { "type": "wikibase-entityid", "value": { "id": id } },
+///   })
+/// };
+/// let adams = json!({
+///   "id": "Q42",
+///   "labels": {
+///     "en": { "language": "en", "value": "Douglas Adams" },
+///     "de": { "language": "de", "value": "Douglas Adams" },
+///   },
+///   "claims": {
+///     "P31": [{
+///       "id": "Q42$F078E5B3",
+///       "rank": "normal",
+///       "mainsnak": item("Q5"),
+///       "references": [{ "hash": "fa278ebf", "snaks": { "P248": [item("Q36578")] } }],
+///     }],
+///     "P18": [{ "id": "Q42$C0DE", "rank": "normal", "mainsnak": item("Q0") }],
+///   },
+/// });
+/// let human = json!({ "id": "Q5", "labels": {}, "claims": {} });
+/// let dump = format!(
+///   "[\n{},\n{}\n]\n",
+///   json::to_string(&adams).unwrap(),
+///   json::to_string(&human).unwrap(),
+/// );
+///
+/// let mut graph = KnowledgeGraph::new();
+/// let report = WikidataImporter::new()
+///   .entities(["Q42"])
+///   .properties(["P31", "P248"])
+///   .languages(["en"])
+///   .import(dump.as_bytes(), &mut graph)
+///   .unwrap();
+/// assert_eq!((report.entities, report.skipped), (1, 1));
+///
+/// let q42 = Node::Http("http://www.wikidata.org/entity/Q42".to_string());
+/// let q5 = Node::Http("http://www.wikidata.org/entity/Q5".to_string());
+/// let wdt = |p: &str| format!("http://www.wikidata.org/prop/direct/{}", p);
+/// assert_eq!(graph.matches(Some(&q42), Some(&wdt("P31")), Some(&q5)).count(), 1);
+/// // Neither the image nor the German label are whitelisted.
+/// assert_eq!(graph.matches(None, Some(&wdt("P18")), None).count(), 0);
+/// let label = "http://www.w3.org/2000/01/rdf-schema#label";
+/// assert_eq!(graph.matches(None, Some(label), None).count(), 1);
+///
+/// let source = "https://www.wikidata.org/wiki/Special:EntityData/Q42";
+/// assert_eq!(graph.triples_from_source(source).count(), report.triples);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct WikidataImporter {
+  entities: Option<HashSet<String>>,
+  properties: Option<HashSet<String>>,
+  languages: Option<HashSet<String>>,
+  statements: bool,
+}
+
+/// `ImportReport` counts what an import did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ImportReport {
+  /// Number of entities imported.
+  pub entities: usize,
+  /// Number of entities left out by the entity whitelist.
+  pub skipped: usize,
+  /// Number of triples inserted.
+  pub triples: usize,
+}
+
+impl WikidataImporter {
+  /// Creates an importer of every entity, property & language, emitting
+  /// statement nodes along with the direct triples.
+  pub fn new() -> WikidataImporter {
+    WikidataImporter {
+      statements: true,
+      ..WikidataImporter::default()
+    }
+  }
+
+  /// Imports only the entities with the given ids, e.g. `"Q42"`.
+  pub fn entities<I, S>(mut self, ids: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    self.entities = Some(ids.into_iter().map(Into::into).collect());
+    self
+  }
+
+  /// Imports only claims, qualifiers & reference values of the properties
+  /// with the given ids, e.g. `"P31"`.
+  pub fn properties<I, S>(mut self, ids: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    self.properties = Some(ids.into_iter().map(Into::into).collect());
+    self
+  }
+
+  /// Imports only labels, descriptions & aliases in the given languages,
+  /// e.g. `"en"`.
+  pub fn languages<I, S>(mut self, languages: I) -> Self
+  where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+  {
+    self.languages = Some(languages.into_iter().map(Into::into).collect());
+    self
+  }
+
+  /// Sets whether statement nodes (with their rank, qualifiers &
+  /// references) are imported. Otherwise only direct triples are.
+  pub fn statements(mut self, statements: bool) -> Self {
+    self.statements = statements;
+    self
+  }
+
+  /// Streams a JSON dump from `reader` into `graph`, one entity per line.
+  ///
+  /// Dumps are a JSON array with every entity on a line of its own, so the
+  /// surrounding brackets & trailing commas are skipped; a file with one
+  /// entity object per line is read as well. Fails with the line of the
+  /// first malformed entity. With the `compression` feature, gzip &
+  /// Zstandard compressed dumps are decompressed on the fly.
+  pub fn import<R: BufRead>(
+    &self,
+    reader: R,
+    graph: &mut KnowledgeGraph,
+  ) -> Result<ImportReport> {
+    #[cfg(feature = "compression")]
+    let reader = crate::compression::Decoder::new(reader).map_err(Error::io)?;
+    graph.batch(|graph| {
+      let mut report = ImportReport::default();
+      for (idx, line) in reader.lines().enumerate() {
+        let line = line.map_err(Error::io)?;
+        let line = line.trim().trim_end_matches(',');
+        if line.is_empty() || line == "[" || line == "]" {
+          continue;
+        }
+        let entity: DType = json::from_str(line).map_err(|err| {
+          Error::syntax(ErrorCode::ParseError, idx + 1, err.column())
+        })?;
+        if entity["id"].as_str().is_none() {
+          return Err(Error::syntax(ErrorCode::ParseError, idx + 1, 1));
+        }
+        match self.import_entity(&entity, graph) {
+          Some(triples) => {
+            report.entities += 1;
+            report.triples += triples;
+          }
+          None => report.skipped += 1,
+        }
+      }
+      Ok(report)
+    })
+  }
+
+  /// Maps a single entity object into `graph`, returning the number of
+  /// triples inserted, or `None` if it isn't whitelisted (or has no id).
+  pub fn import_entity(
+    &self,
+    entity: &DType,
+    graph: &mut KnowledgeGraph,
+  ) -> Option<usize> {
+    let id = entity["id"].as_str()?;
+    if !allowed(&self.entities, id) {
+      return None;
+    }
+
+    let len = graph.len();
+    let mut out = Emitter {
+      graph,
+      provenance: Provenance::new(&format!("{}{}", ENTITY_DATA, id)),
+    };
+    let subject = Node::Http(format!("{}{}", WD, id));
+
+    self.terms(&mut out, &subject, &entity["labels"], RDFS_LABEL);
+    self.terms(
+      &mut out,
+      &subject,
+      &entity["descriptions"],
+      SCHEMA_DESCRIPTION,
+    );
+    if let Some(aliases) = entity["aliases"].as_object() {
+      for alias in aliases.values().filter_map(DType::as_array).flatten() {
+        self.term(&mut out, &subject, alias, SKOS_ALT_LABEL);
+      }
+    }
+
+    if let Some(claims) = entity["claims"].as_object() {
+      for (property, statements) in claims {
+        if !allowed(&self.properties, property) {
+          continue;
+        }
+        let statements = match statements.as_array() {
+          Some(statements) => statements,
+          None => continue,
+        };
+        let best = best_rank(statements);
+        for statement in statements {
+          self.statement(&mut out, &subject, property, statement, best);
+        }
+      }
+    }
+
+    Some(out.graph.len() - len)
+  }
+
+  /// Maps a language map of labels or descriptions.
+  fn terms(
+    &self,
+    out: &mut Emitter,
+    subject: &Node,
+    terms: &DType,
+    predicate: &str,
+  ) {
+    if let Some(terms) = terms.as_object() {
+      for term in terms.values() {
+        self.term(out, subject, term, predicate);
+      }
+    }
+  }
+
+  /// Maps a `{"language": ..., "value": ...}` term.
+  fn term(
+    &self,
+    out: &mut Emitter,
+    subject: &Node,
+    term: &DType,
+    predicate: &str,
+  ) {
+    let (language, value) =
+      match (term["language"].as_str(), term["value"].as_str()) {
+        (Some(language), Some(value)) => (language, value),
+        _ => return,
+      };
+    if !allowed(&self.languages, language) {
+      return;
+    }
+    if let Ok(literal) = LangString::new(value, language) {
+      out.emit(subject.clone(), predicate, literal.into());
+    }
+  }
+
+  fn statement(
+    &self,
+    out: &mut Emitter,
+    subject: &Node,
+    property: &str,
+    statement: &DType,
+    best: &str,
+  ) {
+    let rank = statement["rank"].as_str().unwrap_or("normal");
+    let value = snak_value(&statement["mainsnak"], out.graph);
+
+    if let Some(value) = &value {
+      if rank == best && rank != "deprecated" {
+        out.emit(
+          subject.clone(),
+          &format!("{}{}", WDT, property),
+          value.clone(),
+        );
+      }
+    }
+    if !self.statements {
+      return;
+    }
+    let id = match statement["id"].as_str() {
+      Some(id) => id,
+      None => return,
+    };
+
+    let node = Node::Http(format!("{}{}", WDS, id.replacen('$', "-", 1)));
+    out.emit(subject.clone(), &format!("{}{}", P, property), node.clone());
+    if let Some(value) = value {
+      out.emit(node.clone(), &format!("{}{}", PS, property), value);
+    }
+    out.emit(
+      node.clone(),
+      &format!("{}rank", WIKIBASE),
+      Node::Http(format!("{}{}", WIKIBASE, rank_class(rank))),
+    );
+
+    self.snaks(out, &node, &statement["qualifiers"], PQ);
+    if let Some(references) = statement["references"].as_array() {
+      for reference in references {
+        let hash = match reference["hash"].as_str() {
+          Some(hash) => hash,
+          None => continue,
+        };
+        let reference_node = Node::Http(format!("{}{}", WDREF, hash));
+        out.emit(node.clone(), PROV_WAS_DERIVED_FROM, reference_node.clone());
+        self.snaks(out, &reference_node, &reference["snaks"], PR);
+      }
+    }
+  }
+
+  /// Maps a map of property ids to snaks of qualifiers or references.
+  fn snaks(&self, out: &mut Emitter, subject: &Node, snaks: &DType, ns: &str) {
+    let snaks = match snaks.as_object() {
+      Some(snaks) => snaks,
+      None => return,
+    };
+    for (property, snaks) in snaks {
+      if !allowed(&self.properties, property) {
+        continue;
+      }
+      for snak in snaks.as_array().into_iter().flatten() {
+        if let Some(value) = snak_value(snak, out.graph) {
+          out.emit(subject.clone(), &format!("{}{}", ns, property), value);
+        }
+      }
+    }
+  }
+}
+
+/// Inserts triples sharing the provenance of the entity being imported.
+struct Emitter<'g> {
+  graph: &'g mut KnowledgeGraph,
+  provenance: Provenance,
+}
+
+impl<'g> Emitter<'g> {
+  fn emit(&mut self, subject: Node, predicate: &str, object: Node) {
+    self.graph.insert_with_provenance(
+      subject,
+      Predicate::Literal(predicate.to_string()),
+      object,
+      self.provenance.clone(),
+    );
+  }
+}
+
+fn allowed(whitelist: &Option<HashSet<String>>, id: &str) -> bool {
+  whitelist.as_ref().is_none_or(|ids| ids.contains(id))
+}
+
+/// Returns the rank of the statements which get direct triples: preferred
+/// if any statement is, normal otherwise.
+fn best_rank(statements: &[DType]) -> &'static str {
+  if statements.iter().any(|s| s["rank"] == "preferred") {
+    "preferred"
+  } else {
+    "normal"
+  }
+}
+
+fn rank_class(rank: &str) -> &'static str {
+  match rank {
+    "preferred" => "PreferredRank",
+    "deprecated" => "DeprecatedRank",
+    _ => "NormalRank",
+  }
+}
+
+/// Returns the value of a snak: a blank node for an unknown value
+/// (`somevalue`), `None` for no value or an unsupported datatype.
+fn snak_value(snak: &DType, graph: &mut KnowledgeGraph) -> Option<Node> {
+  match snak["snaktype"].as_str()? {
+    "value" => data_value(&snak["datavalue"], snak["datatype"].as_str()),
+    "somevalue" => Some(graph.blank_node()),
+    _ => None,
+  }
+}
+
+fn data_value(datavalue: &DType, datatype: Option<&str>) -> Option<Node> {
+  let value = &datavalue["value"];
+  match datavalue["type"].as_str()? {
+    "wikibase-entityid" => value["id"]
+      .as_str()
+      .map(|id| Node::Http(format!("{}{}", WD, id))),
+    "string" if datatype == Some("url") => {
+      value.as_str().map(|url| Node::Http(url.to_string()))
+    }
+    "string" => value.as_str().map(|s| Node::Literal(s.into())),
+    "monolingualtext" => {
+      LangString::new(value["text"].as_str()?, value["language"].as_str()?)
+        .ok()
+        .map(Node::from)
+    }
+    "quantity" => {
+      let amount = value["amount"].as_str()?.trim_start_matches('+');
+      match amount.parse::<i64>() {
+        Ok(n) => Some(Node::Literal(n.into())),
+        Err(_) => amount.parse::<f64>().ok().map(|n| Node::Literal(n.into())),
+      }
+    }
+    "time" => Some(Node::Literal(time(value["time"].as_str()?))),
+    "globecoordinate" => {
+      let point =
+        Point::new(value["longitude"].as_f64()?, value["latitude"].as_f64()?);
+      Some(Node::Literal(Geo::Point(point).to_string().into()))
+    }
+    _ => None,
+  }
+}
+
+/// Reads a Wikidata timestamp, e.g. `+1952-03-11T00:00:00Z`. Timestamps
+/// less precise than a day (`+1952-00-00T00:00:00Z`) or outside of
+/// chrono's range are kept as strings.
+fn time(timestamp: &str) -> DType {
+  chrono::DateTime::parse_from_rfc3339(timestamp.trim_start_matches('+'))
+    .map(|d| DType::DateTime(DateTime::from(d.with_timezone(&chrono::Utc))))
+    .unwrap_or_else(|_| DType::String(timestamp.to_string()))
+}
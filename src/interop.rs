@@ -0,0 +1,24 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::interop` moves data between a `KnowledgeGraph` and external
+//! databases & services. Each integration lives behind its own feature flag.
+//!
+
+#[cfg(feature = "elastic")]
+pub mod elastic;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "neo4j")]
+pub mod neo4j;
@@ -0,0 +1,358 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::interop::elastic` converts a `KnowledgeGraph` into [Elasticsearch]
+//! bulk API payloads and pushes them over HTTP(S).
+//!
+//! Enable with the `elastic` feature.
+//!
+//! Every subject becomes one document:
+//!
+//! - Its IRI (optionally stripped of a prefix) is the document `_id`.
+//! - `rdf:type` values go into the `type` field.
+//! - Every other predicate becomes a field named after its local name, e.g.
+//!   `https://schema.org/name` -> `name`, unless mapped explicitly.
+//! - Links to other nodes are stored as their IRI.
+//!
+//! [Elasticsearch]: https://www.elastic.co/elasticsearch
+
+use std::{collections::BTreeMap, io, mem, time::Duration};
+
+use tokio::task::{JoinError, JoinSet};
+
+use crate::{
+  datastore::json,
+  dtype::{DType, Map},
+  error::Error,
+  formats,
+  graph::KnowledgeGraph,
+  http, Result,
+};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Mapping
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Mapping` decides the index, document ids & field names of the
+/// documents built from a graph.
+#[derive(Clone, Debug)]
+pub struct Mapping {
+  index: String,
+  id_prefix: Option<String>,
+  fields: BTreeMap<String, String>,
+  excluded: Vec<String>,
+}
+
+impl Mapping {
+  /// Creates a mapping writing into `index`.
+  pub fn new(index: &str) -> Mapping {
+    Mapping {
+      index: index.to_string(),
+      id_prefix: None,
+      fields: BTreeMap::new(),
+      excluded: Vec::new(),
+    }
+  }
+
+  /// Strips `prefix` from subject IRIs to form document ids, e.g.
+  /// `https://example.com/Ada` -> `Ada`.
+  pub fn id_prefix(mut self, prefix: &str) -> Self {
+    self.id_prefix = Some(prefix.to_string());
+    self
+  }
+
+  /// Stores the values of `predicate` in `field`. Use `"@type"` to rename
+  /// the `type` field.
+  pub fn field(mut self, predicate: &str, field: &str) -> Self {
+    self.fields.insert(predicate.to_string(), field.to_string());
+    self
+  }
+
+  /// Leaves `predicate` out of the documents.
+  pub fn exclude(mut self, predicate: &str) -> Self {
+    self.excluded.push(predicate.to_string());
+    self
+  }
+
+  fn id(&self, iri: &str) -> String {
+    self
+      .id_prefix
+      .as_deref()
+      .and_then(|prefix| iri.strip_prefix(prefix))
+      .unwrap_or(iri)
+      .to_string()
+  }
+
+  fn field_name(&self, predicate: &str) -> String {
+    if let Some(field) = self.fields.get(predicate) {
+      return field.clone();
+    }
+    if predicate == "@type" {
+      return "type".to_string();
+    }
+    // Dots would be read as object paths by Elasticsearch.
+    predicate
+      .rsplit(['/', '#', ':'])
+      .find(|s| !s.is_empty())
+      .unwrap_or(predicate)
+      .replace('.', "_")
+  }
+
+  /// Returns the `_id` & source of the document built from `entity`.
+  fn document(&self, mut entity: DType) -> Option<(String, DType)> {
+    let DType::Object(ref mut entity) = entity else {
+      return None;
+    };
+    let mut id = None;
+    let mut source = Map::new();
+    for (key, value) in mem::take(entity) {
+      if key == "@id" {
+        id = value.as_str().map(|iri| self.id(iri));
+        continue;
+      }
+      if self.excluded.contains(&key) {
+        continue;
+      }
+      let field = self.field_name(&key);
+      let value = link_to_iri(value);
+      let value = match source.remove(&field) {
+        Some(DType::Array(ref mut values)) => {
+          values.extend(into_values(value));
+          DType::Array(mem::take(values))
+        }
+        Some(existing) => {
+          let mut values = vec![existing];
+          values.extend(into_values(value));
+          DType::from(values)
+        }
+        None => value,
+      };
+      source.insert(field, value);
+    }
+    Some((id?, DType::Object(source)))
+  }
+}
+
+/// Replaces `{"@id": iri}` links with the bare IRI.
+fn link_to_iri(mut value: DType) -> DType {
+  match value {
+    DType::Array(ref mut values) => {
+      for v in values.iter_mut() {
+        *v = link_to_iri(mem::take(v));
+      }
+      value
+    }
+    DType::Object(ref map) if map.len() == 1 && map.contains_key("@id") => {
+      map.get("@id").cloned().unwrap_or(DType::Null)
+    }
+    value => value,
+  }
+}
+
+fn into_values(mut value: DType) -> Vec<DType> {
+  match value {
+    DType::Array(ref mut values) => mem::take(&mut **values),
+    value => vec![value],
+  }
+}
+
+/// Returns the action & source lines of every document, one entry per
+/// document.
+fn documents(graph: &KnowledgeGraph, mapping: &Mapping) -> Result<Vec<String>> {
+  let mut documents = Vec::new();
+  formats::entities(graph, |entity| {
+    if let Some((id, source)) = mapping.document(entity) {
+      let action = crate::json!({
+        "index": { "_id": id, "_index": mapping.index.as_str() }
+      });
+      documents.push(format!(
+        "{}\n{}\n",
+        json::to_string(&action)?,
+        json::to_string(&source)?
+      ));
+    }
+    Ok(())
+  })?;
+  Ok(documents)
+}
+
+/// `to_bulk` renders `graph` as an Elasticsearch [bulk API] payload, an
+/// `index` action followed by the document source for every subject.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::interop::elastic::{to_bulk, Mapping};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   Node::Http("https://example.com/Ada".to_string()),
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal("Ada Lovelace".into()),
+/// );
+///
+/// let mapping = Mapping::new("people")
+///   .id_prefix("https://example.com/")
+///   .field("https://schema.org/name", "full_name");
+///
+/// assert_eq!(
+///   to_bulk(&graph, &mapping).unwrap(),
+///   "{\"index\":{\"_id\":\"Ada\",\"_index\":\"people\"}}\n\
+///    {\"full_name\":\"Ada Lovelace\"}\n"
+/// );
+/// ```
+///
+/// [bulk API]: https://www.elastic.co/guide/en/elasticsearch/reference/current/docs-bulk.html
+pub fn to_bulk(graph: &KnowledgeGraph, mapping: &Mapping) -> Result<String> {
+  Ok(documents(graph, mapping)?.concat())
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Elastic
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `BulkReport` summarizes the responses of a `push`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BulkReport {
+  /// Documents Elasticsearch accepted.
+  pub indexed: usize,
+  /// Response items of the documents Elasticsearch rejected.
+  pub failed: Vec<DType>,
+}
+
+/// `Elastic` is a client of an Elasticsearch cluster, over HTTP or HTTPS.
+///
+/// Requests time out after 30 seconds unless set otherwise.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use sage::graph::KnowledgeGraph;
+/// use sage::interop::elastic::{Elastic, Mapping};
+///
+/// async fn index(graph: &KnowledgeGraph) -> sage::Result<()> {
+///   let report = Elastic::new("https://localhost:9200")?
+///     .header("Authorization", "ApiKey c2FnZTpzZWNyZXQ=")?
+///     .batch_size(500)
+///     .concurrency(8)
+///     .push(graph, &Mapping::new("graph"))
+///     .await?;
+///   assert!(report.failed.is_empty());
+///   Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Elastic {
+  client: http::Client,
+  batch_size: usize,
+  concurrency: usize,
+}
+
+impl Elastic {
+  /// Creates a client for the cluster at `url`, e.g.
+  /// `"https://localhost:9200"`.
+  pub fn new(url: &str) -> Result<Elastic> {
+    Ok(Elastic {
+      client: http::Client::new(url, "Elasticsearch")?,
+      batch_size: 1000,
+      concurrency: 4,
+    })
+  }
+
+  /// Sends `name: value` with every request, e.g. for authorization.
+  ///
+  /// # Errors
+  ///
+  /// Fails if `name` isn't a valid header name or `value` holds control
+  /// characters, such as CR or LF.
+  pub fn header(mut self, name: &str, value: &str) -> Result<Self> {
+    self.client = self.client.header(name, value)?;
+    Ok(self)
+  }
+
+  /// Fails requests which take longer than `timeout`, from connecting to
+  /// reading the whole response. Defaults to 30 seconds.
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.client = self.client.timeout(timeout);
+    self
+  }
+
+  /// Number of documents sent per `_bulk` request. Defaults to `1000`.
+  pub fn batch_size(mut self, batch_size: usize) -> Self {
+    self.batch_size = batch_size.max(1);
+    self
+  }
+
+  /// Number of `_bulk` requests in flight at once. Defaults to `4`.
+  pub fn concurrency(mut self, concurrency: usize) -> Self {
+    self.concurrency = concurrency.max(1);
+    self
+  }
+
+  /// Indexes every subject of `graph` with `_bulk` requests of
+  /// `batch_size` documents, `concurrency` of them at a time.
+  ///
+  /// Requests block, so they run on Tokio's blocking thread pool.
+  pub async fn push(
+    &self,
+    graph: &KnowledgeGraph,
+    mapping: &Mapping,
+  ) -> Result<BulkReport> {
+    let documents = documents(graph, mapping)?;
+    let mut report = BulkReport::default();
+    let mut requests = JoinSet::new();
+    for batch in documents.chunks(self.batch_size) {
+      if requests.len() == self.concurrency {
+        if let Some(response) = requests.join_next().await {
+          tally(&mut report, response)?;
+        }
+      }
+      let (client, body) = (self.client.clone(), batch.concat());
+      requests.spawn_blocking(move || {
+        client.post("/_bulk", "application/x-ndjson", body.as_bytes())
+      });
+    }
+    while let Some(response) = requests.join_next().await {
+      tally(&mut report, response)?;
+    }
+    Ok(report)
+  }
+}
+
+/// Adds the items of a `_bulk` response to `report`.
+fn tally(
+  report: &mut BulkReport,
+  response: std::result::Result<Result<Vec<u8>>, JoinError>,
+) -> Result<()> {
+  let body = response.map_err(|e| Error::io(io::Error::other(e)))??;
+  let response: DType = json::from_slice(&body)?;
+  let items = response.get("items").and_then(DType::as_array);
+  for item in items.into_iter().flatten() {
+    let result = item.as_object().and_then(|item| item.values().next());
+    match result.and_then(|result| result.get("error")) {
+      Some(_) => report.failed.push(item.clone()),
+      None => report.indexed += 1,
+    }
+  }
+  Ok(())
+}
@@ -0,0 +1,327 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::interop::kafka` keeps a `KnowledgeGraph` continuously updated
+//! from [Kafka] topics, and publishes its changes back out.
+//!
+//! Enable with the `kafka` feature.
+//!
+//! - `KafkaSource` is a `pipeline::Source` consuming JSON or JSON-LD
+//!   messages, so they go through the stages, validators & sinks of a
+//!   `Pipeline` like any other input.
+//! - `ChangeFeed` publishes the statements inserted into & removed from a
+//!   graph as JSON events (see `event`), keyed by subject so the events of
+//!   a subject stay in order.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::thread;
+//!
+//! use sage::graph::KnowledgeGraph;
+//! use sage::interop::kafka::{ChangeFeed, KafkaSource};
+//! use sage::pipeline::Pipeline;
+//!
+//! let hosts = ["localhost:9092"];
+//! let mut graph = KnowledgeGraph::new();
+//!
+//! // Publish every change of the graph on another thread.
+//! let (_, changes) = graph.watch(64);
+//! let mut feed = ChangeFeed::new(&hosts, "graph-changes").unwrap();
+//! thread::spawn(move || feed.run(changes));
+//!
+//! // Consume JSON-LD messages until the process is stopped.
+//! let source = KafkaSource::new(&hosts, "sage", &["entities"])
+//!   .unwrap()
+//!   .follow(true)
+//!   .skip_malformed(true);
+//! Pipeline::new(source).sink(&mut graph).run().unwrap();
+//! ```
+//!
+//! [Kafka]: https://kafka.apache.org/
+
+use std::{io, sync::mpsc::Receiver, time::Duration};
+
+use kafka::{
+  consumer::{Consumer, FetchOffset, GroupOffsetStorage},
+  producer::{Producer, Record, RequiredAcks},
+};
+
+use crate::{
+  datastore::json,
+  dtype::{DType, Map},
+  error::Error,
+  graph::{Mutation, Node, Triple},
+  pipeline::{self, Mapping, Source},
+  transform::Transform,
+  Result,
+};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | KafkaSource
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `KafkaSource` reads the JSON messages of Kafka topics as batches of
+/// triples, one batch per poll.
+///
+/// Messages are read as JSON-LD, or reshaped by a `Transform` and mapped by
+/// a `Mapping` like `pipeline::Documents` does. Blank node labels are
+/// scoped to their message, e.g. `_:b0` of the message at offset 7 of
+/// partition 0 of `entities` becomes `_:kentities_0_7_b0`.
+///
+/// Offsets are committed when the next batch is read, i.e. once the
+/// `Pipeline` has written the previous one: messages are delivered at least
+/// once, even if the process stops in between.
+pub struct KafkaSource {
+  consumer: Consumer,
+  transform: Option<Transform>,
+  mapping: Option<Mapping>,
+  follow: bool,
+  skip_malformed: bool,
+  /// Whether messages were consumed since offsets were last committed.
+  uncommitted: bool,
+}
+
+impl KafkaSource {
+  /// Consumes `topics` on the brokers at `hosts` (e.g. `localhost:9092`)
+  /// as a member of the consumer `group`. Without committed offsets, a
+  /// group starts from the earliest message.
+  pub fn new(
+    hosts: &[&str],
+    group: &str,
+    topics: &[&str],
+  ) -> Result<KafkaSource> {
+    let mut builder =
+      Consumer::from_hosts(hosts.iter().map(|h| h.to_string()).collect())
+        .with_group(group.to_string())
+        .with_fallback_offset(FetchOffset::Earliest)
+        .with_offset_storage(Some(GroupOffsetStorage::Kafka));
+    for topic in topics {
+      builder = builder.with_topic(topic.to_string());
+    }
+    Ok(KafkaSource::from_consumer(
+      builder.create().map_err(kafka_error)?,
+    ))
+  }
+
+  /// Wraps an already configured `kafka::consumer::Consumer`, which must
+  /// belong to a group for offsets to be committed.
+  pub fn from_consumer(consumer: Consumer) -> KafkaSource {
+    KafkaSource {
+      consumer,
+      transform: None,
+      mapping: None,
+      follow: false,
+      skip_malformed: false,
+      uncommitted: false,
+    }
+  }
+
+  /// Reshapes every message with `transform` before it's mapped.
+  pub fn transform(mut self, transform: Transform) -> Self {
+    self.transform = Some(transform);
+    self
+  }
+
+  /// Maps every message to triples with `mapping`, instead of reading it
+  /// as JSON-LD.
+  pub fn mapping(mut self, mapping: Mapping) -> Self {
+    self.mapping = Some(mapping);
+    self
+  }
+
+  /// Sets whether to keep polling once the topics are drained, so the
+  /// pipeline runs until it fails. Otherwise it ends at the first poll
+  /// without messages.
+  pub fn follow(mut self, follow: bool) -> Self {
+    self.follow = follow;
+    self
+  }
+
+  /// Sets whether messages which aren't JSON, or can't be mapped, are
+  /// skipped instead of failing the pipeline.
+  pub fn skip_malformed(mut self, skip: bool) -> Self {
+    self.skip_malformed = skip;
+    self
+  }
+}
+
+impl Source for KafkaSource {
+  fn read(&mut self) -> Result<Option<Vec<Triple>>> {
+    if self.uncommitted {
+      self.consumer.commit_consumed().map_err(kafka_error)?;
+      self.uncommitted = false;
+    }
+
+    let sets = self.consumer.poll().map_err(kafka_error)?;
+    if sets.is_empty() && !self.follow {
+      return Ok(None);
+    }
+
+    let mut batch = Vec::new();
+    for set in sets.iter() {
+      for message in set.messages() {
+        let (topic, partition) = (set.topic(), set.partition());
+        let scope = format!("k{}_{}_{}", topic, partition, message.offset);
+        let triples = json::from_slice(message.value).and_then(|document| {
+          pipeline::triples(
+            document,
+            self.transform.as_ref(),
+            self.mapping.as_ref(),
+            &scope,
+          )
+        });
+        match triples {
+          Ok(triples) => batch.extend(triples),
+          Err(_) if self.skip_malformed => {}
+          Err(e) => return Err(e),
+        }
+      }
+      self.consumer.consume_messageset(set).map_err(kafka_error)?;
+      self.uncommitted = true;
+    }
+    Ok(Some(batch))
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | ChangeFeed
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Returns the JSON event of `mutation`, as published by `ChangeFeed`.
+///
+/// The subject & predicate are strings, the object is a JSON-LD node
+/// reference for IRIs & blank nodes, or the literal's value.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{Mutation, Node, Predicate};
+/// use sage::interop::kafka::event;
+/// use sage::json;
+///
+/// let mutation = Mutation::Inserted(
+///   Node::Http("https://example.com/Ada".to_string()),
+///   Predicate::Literal("https://schema.org/knows".to_string()),
+///   Node::BlankId("b0".to_string()),
+/// );
+/// assert_eq!(
+///   event(&mutation),
+///   json!({
+///     "op": "insert",
+///     "subject": "https://example.com/Ada",
+///     "predicate": "https://schema.org/knows",
+///     "object": { "@id": "_:b0" }
+///   })
+/// );
+/// ```
+pub fn event(mutation: &Mutation) -> DType {
+  let (op, subject, predicate, object) = match mutation {
+    Mutation::Inserted(s, p, o) => ("insert", s, p, o),
+    Mutation::Removed(s, p, o) => ("remove", s, p, o),
+  };
+  let object = match object {
+    Node::Literal(value) => value.clone(),
+    node => {
+      let mut reference = Map::new();
+      reference.insert("@id".to_string(), DType::String(node.to_string()));
+      DType::Object(reference)
+    }
+  };
+
+  let mut event = Map::new();
+  event.insert("op".to_string(), DType::String(op.to_string()));
+  event.insert("subject".to_string(), DType::String(subject.to_string()));
+  let predicate = DType::String(predicate.to_string());
+  event.insert("predicate".to_string(), predicate);
+  event.insert("object".to_string(), object);
+  DType::Object(event)
+}
+
+/// `ChangeFeed` publishes the mutations of a `KnowledgeGraph` to a Kafka
+/// topic, one `event` per mutation keyed by its subject.
+pub struct ChangeFeed {
+  producer: Producer,
+  topic: String,
+}
+
+impl ChangeFeed {
+  /// Publishes to `topic` on the brokers at `hosts`, waiting for the
+  /// partition leader to acknowledge every batch.
+  pub fn new(hosts: &[&str], topic: &str) -> Result<ChangeFeed> {
+    let producer =
+      Producer::from_hosts(hosts.iter().map(|h| h.to_string()).collect())
+        .with_ack_timeout(Duration::from_secs(1))
+        .with_required_acks(RequiredAcks::One)
+        .create()
+        .map_err(kafka_error)?;
+    Ok(ChangeFeed::from_producer(producer, topic))
+  }
+
+  /// Wraps an already configured `kafka::producer::Producer`.
+  pub fn from_producer(producer: Producer, topic: &str) -> ChangeFeed {
+    ChangeFeed {
+      producer,
+      topic: topic.to_string(),
+    }
+  }
+
+  /// Publishes a batch of mutations, e.g. from `KnowledgeGraph::subscribe`.
+  ///
+  /// # Errors
+  ///
+  /// Fails if a broker can't be reached or rejects an event.
+  pub fn publish(&mut self, mutations: &[Mutation]) -> Result<()> {
+    let mut records = Vec::with_capacity(mutations.len());
+    for mutation in mutations {
+      let key = mutation.subject().to_string();
+      let value = json::to_vec(&event(mutation))?;
+      records.push(Record::from_key_value(&self.topic, key, value));
+    }
+    let confirms = self.producer.send_all(&records).map_err(kafka_error)?;
+    for confirm in confirms {
+      for partition in confirm.partition_confirms {
+        partition.offset.map_err(|code| {
+          let message = format!("Kafka rejected an event: {:?}", code);
+          Error::io(io::Error::other(message))
+        })?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Publishes the batches of `changes` (see `KnowledgeGraph::watch`) until
+  /// the graph is dropped or a batch fails to be published.
+  pub fn run(&mut self, changes: Receiver<Vec<Mutation>>) -> Result<()> {
+    for batch in changes {
+      self.publish(&batch)?;
+    }
+    Ok(())
+  }
+}
+
+fn kafka_error<E>(err: E) -> Error
+where
+  E: std::error::Error + Send + Sync + 'static,
+{
+  Error::io(io::Error::other(err))
+}
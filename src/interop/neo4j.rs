@@ -0,0 +1,255 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::interop::neo4j` pushes a `KnowledgeGraph` into [Neo4j] over the
+//! Bolt protocol and pulls Cypher query results back as `DType` rows.
+//!
+//! Enable with the `neo4j` feature.
+//!
+//! The graph is mapped onto the property graph model as follows:
+//!
+//! - Every IRI (and blank node) becomes a `:Resource` node keyed by its
+//!   `iri` property.
+//! - A triple whose object is a literal becomes a property on the subject
+//!   node, named after the predicate.
+//! - A triple whose object is another resource becomes a relationship. The
+//!   relationship type is derived from the local name of the predicate and
+//!   the full predicate is kept in its `predicate` property.
+//!
+//! [Neo4j]: https://neo4j.com/
+
+use std::{collections::HashMap, io};
+
+use neo4rs::{BoltBytes, BoltNull, BoltType, Graph, Query};
+
+use crate::{
+  dtype::{DType, Map},
+  error::Error,
+  graph::{KnowledgeGraph, Node},
+  Result,
+};
+
+/// `CypherStatement` is a parameterized Cypher query generated from a
+/// `KnowledgeGraph`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CypherStatement {
+  /// Cypher query text.
+  pub query: String,
+  /// Query parameters referenced as `$name` from the query text.
+  pub params: Map<String, DType>,
+}
+
+/// `to_cypher` translates every triple in `graph` into idempotent `MERGE`
+/// statements, so pushing the same graph twice doesn't duplicate data.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::interop::neo4j::to_cypher;
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   Node::Http("https://example.com/John".to_string()),
+///   Predicate::Literal("https://schema.org/knows".to_string()),
+///   Node::Http("https://example.com/Jane".to_string()),
+/// );
+///
+/// let statements = to_cypher(&graph);
+/// assert!(statements[0].query.contains("MERGE (s)-[r:`knows`]->(o)"));
+/// ```
+pub fn to_cypher(graph: &KnowledgeGraph) -> Vec<CypherStatement> {
+  let mut statements = Vec::new();
+  let mut blanks = 0;
+
+  for triple in graph.triples() {
+    let predicate = triple.predicate().to_string();
+    let subjects = resource_keys(triple.source(), &mut blanks);
+
+    for subject in subjects {
+      for object in flatten(triple.destination()) {
+        let mut params = Map::new();
+        params.insert("s".to_string(), DType::String(subject.clone()));
+        params.insert("p".to_string(), DType::String(predicate.clone()));
+
+        let query = match object {
+          Node::Literal(value) => {
+            params.insert("v".to_string(), property_value(value));
+            format!(
+              "MERGE (s:Resource {{iri: $s}}) SET s.`{}` = $v",
+              escape_identifier(&predicate)
+            )
+          }
+          node => {
+            let object = resource_keys(node, &mut blanks).remove(0);
+            params.insert("o".to_string(), DType::String(object));
+            format!(
+              "MERGE (s:Resource {{iri: $s}}) \
+               MERGE (o:Resource {{iri: $o}}) \
+               MERGE (s)-[r:`{}`]->(o) SET r.predicate = $p",
+              escape_identifier(&relationship_type(&predicate))
+            )
+          }
+        };
+
+        statements.push(CypherStatement { query, params });
+      }
+    }
+  }
+
+  statements
+}
+
+/// `Neo4j` is a connection to a Neo4j database.
+pub struct Neo4j {
+  graph: Graph,
+}
+
+impl Neo4j {
+  /// Connects to a Neo4j database, e.g. `"127.0.0.1:7687"`.
+  pub async fn connect(uri: &str, user: &str, password: &str) -> Result<Neo4j> {
+    let graph = Graph::new(uri, user, password).await.map_err(bolt_error)?;
+    Ok(Neo4j { graph })
+  }
+
+  /// Wraps an already configured `neo4rs::Graph`.
+  pub fn from_graph(graph: Graph) -> Neo4j {
+    Neo4j { graph }
+  }
+
+  /// Pushes every triple of `graph` into Neo4j in a single transaction.
+  pub async fn push(&self, graph: &KnowledgeGraph) -> Result<()> {
+    let mut txn = self.graph.start_txn().await.map_err(bolt_error)?;
+    for statement in to_cypher(graph) {
+      txn.run(to_query(&statement)).await.map_err(bolt_error)?;
+    }
+    txn.commit().await.map_err(bolt_error)
+  }
+
+  /// Runs a Cypher query and returns each row as a `DType::Object` keyed by
+  /// the returned column names.
+  pub async fn query(
+    &self,
+    query: &str,
+    params: Map<String, DType>,
+  ) -> Result<Vec<DType>> {
+    let statement = CypherStatement {
+      query: query.to_string(),
+      params,
+    };
+    let mut stream = self
+      .graph
+      .execute(to_query(&statement))
+      .await
+      .map_err(bolt_error)?;
+
+    let mut rows = Vec::new();
+    while let Some(row) = stream.next().await.map_err(bolt_error)? {
+      let row: DType = row.to_strict().map_err(bolt_error)?;
+      rows.push(row);
+    }
+    Ok(rows)
+  }
+}
+
+fn to_query(statement: &CypherStatement) -> Query {
+  let params: HashMap<String, BoltType> = statement
+    .params
+    .iter()
+    .map(|(k, v)| (k.clone(), to_bolt(v)))
+    .collect();
+  Query::new(statement.query.clone()).params(params)
+}
+
+/// Converts a `DType` into the equivalent Bolt value.
+fn to_bolt(value: &DType) -> BoltType {
+  match value {
+    DType::Null => BoltType::Null(BoltNull),
+    DType::Boolean(b) => (*b).into(),
+    DType::Number(n) => match n.as_i64() {
+      Some(i) => i.into(),
+      None => n.as_f64().unwrap_or(f64::NAN).into(),
+    },
+    DType::String(s) => s.as_str().into(),
+    DType::Bytes(b) => BoltType::Bytes(BoltBytes::new(b.to_vec().into())),
+    DType::DateTime(d) => d.as_chrono().fixed_offset().into(),
+    DType::Array(a) => a.iter().map(to_bolt).collect::<Vec<_>>().into(),
+    DType::Object(o) => o
+      .iter()
+      .map(|(k, v)| (k.clone(), to_bolt(v)))
+      .collect::<HashMap<_, _>>()
+      .into(),
+  }
+}
+
+/// Neo4j properties can't hold maps, so objects are stored as JSON text.
+fn property_value(value: &DType) -> DType {
+  let nested = |v: &DType| v.is_object() || v.is_array();
+  match value {
+    DType::Object(_) => DType::String(value_to_json(value)),
+    DType::Array(a) if a.iter().any(nested) => {
+      DType::String(value_to_json(value))
+    }
+    _ => value.clone(),
+  }
+}
+
+fn value_to_json(value: &DType) -> String {
+  crate::datastore::json::to_string(value).unwrap_or_default()
+}
+
+/// Returns the `iri` key(s) of `node`.
+fn resource_keys(node: &Node, blanks: &mut usize) -> Vec<String> {
+  flatten(node)
+    .into_iter()
+    .map(|node| match node {
+      Node::Blank => {
+        *blanks += 1;
+        format!("_:b{}", blanks)
+      }
+      node => node.to_string(),
+    })
+    .collect()
+}
+
+fn flatten(node: &Node) -> Vec<&Node> {
+  match node {
+    Node::Multiple(nodes) => nodes.iter().flat_map(flatten).collect(),
+    node => vec![node],
+  }
+}
+
+/// Derives a relationship type from the local name of a predicate IRI,
+/// e.g. `https://schema.org/knows` -> `knows`.
+fn relationship_type(predicate: &str) -> String {
+  let local = predicate
+    .rsplit(['/', '#', ':'])
+    .find(|s| !s.is_empty())
+    .unwrap_or(predicate);
+  local
+    .chars()
+    .map(|c| if c.is_alphanumeric() { c } else { '_' })
+    .collect()
+}
+
+fn escape_identifier(s: &str) -> String {
+  s.replace('`', "``")
+}
+
+fn bolt_error<E>(err: E) -> Error
+where
+  E: std::error::Error + Send + Sync + 'static,
+{
+  Error::io(io::Error::other(err))
+}
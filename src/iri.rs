@@ -0,0 +1,742 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::iri` parses and normalizes [Internationalized Resource Identifiers]
+//! (IRIs).
+//!
+//! Two IRIs which differ only in their encoding (e.g. `%7e` vs `~`, upper vs
+//! lower case hosts, Unicode vs punycode host names) identify the same
+//! resource. `Iri` normalizes both forms so they compare equal and hash to the
+//! same value, which keeps a single entity from being split into many nodes.
+//!
+//! `Iri` is the canonical identifier of graph nodes: `Node::iri` creates
+//! nodes from normalized IRIs, and `Iri::expand` & `Iri::compact` convert
+//! between IRIs and [CURIEs] such as `schema:Person`.
+//!
+//! Non hierarchical identifier schemes commonly used as subject IDs have
+//! dedicated types with scheme specific validation: [`Urn`] for `urn:` &
+//! [`Did`] for `did:` identifiers.
+//!
+//! Identifier migrations are handled by [`Rewriter`], which maps IRIs
+//! through prefix & regex rules while importing or exporting a graph.
+//!
+//! [Internationalized Resource Identifiers]: https://tools.ietf.org/html/rfc3987
+//! [CURIEs]: https://www.w3.org/TR/curie/
+
+mod did;
+mod punycode;
+mod rewrite;
+mod urn;
+
+pub use did::Did;
+pub use rewrite::Rewriter;
+pub use urn::Urn;
+
+use std::{
+  cmp::Ordering,
+  collections::BTreeMap,
+  fmt,
+  hash::{Hash, Hasher},
+  str::FromStr,
+};
+
+use crate::{
+  error::{Error, ErrorCode},
+  graph::Node,
+  Result,
+};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Iri
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Iri` is a parsed & normalized Internationalized Resource Identifier.
+///
+/// The normalization applied follows [RFC 3986 §6.2] & [RFC 3987 §5.3]:
+///
+/// - The scheme and host are lower cased.
+/// - Percent-encoded octets use upper case hex digits and unreserved
+///   characters are decoded (`%7E` -> `~`).
+/// - Dot segments (`.` & `..`) are removed from hierarchical paths.
+/// - Default ports are dropped & empty `http(s)` paths become `/`.
+/// - Unicode host labels are mapped to punycode in the URI form.
+///
+/// Every `Iri` has two representations: the IRI form returned by
+/// `Iri::as_str` which keeps Unicode characters readable and the URI form
+/// returned by `Iri::as_uri` which is plain ASCII. Equality, ordering and
+/// hashing are based on the URI form.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::iri::Iri;
+///
+/// let a = Iri::parse("HTTP://Example.COM:80/a/./b/../%7euser").unwrap();
+/// let b = Iri::parse("http://example.com/a/~user").unwrap();
+///
+/// assert_eq!(a, b);
+/// assert_eq!(a.as_str(), "http://example.com/a/~user");
+/// ```
+///
+/// [RFC 3986 §6.2]: https://tools.ietf.org/html/rfc3986#section-6.2
+/// [RFC 3987 §5.3]: https://tools.ietf.org/html/rfc3987#section-5.3
+#[derive(Clone, Debug)]
+pub struct Iri {
+  /// Normalized IRI form.
+  iri: String,
+
+  /// Normalized ASCII (URI) form.
+  uri: String,
+
+  scheme: String,
+  host: Option<String>,
+  port: Option<String>,
+  path: String,
+  query: Option<String>,
+  fragment: Option<String>,
+}
+
+impl Iri {
+  /// `Iri::parse` parses & normalizes an absolute IRI.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::iri::Iri;
+  ///
+  /// let iri = Iri::parse("https://bücher.example/straße?q=1#top").unwrap();
+  ///
+  /// assert_eq!(iri.scheme(), "https");
+  /// assert_eq!(iri.host(), Some("bücher.example"));
+  /// assert_eq!(iri.path(), "/straße");
+  /// assert_eq!(iri.query(), Some("q=1"));
+  /// assert_eq!(iri.fragment(), Some("top"));
+  ///
+  /// // Relative references & illegal characters are rejected.
+  /// assert!(Iri::parse("/relative/path").is_err());
+  /// assert!(Iri::parse("http://example.com/a b").is_err());
+  /// ```
+  pub fn parse(s: &str) -> Result<Iri> {
+    let (scheme, rest) = split_scheme(s)?;
+    let scheme = scheme.to_ascii_lowercase();
+
+    let (rest, fragment) = match rest.find('#') {
+      Some(idx) => (&rest[..idx], Some(&rest[idx + 1..])),
+      None => (rest, None),
+    };
+    let (rest, query) = match rest.find('?') {
+      Some(idx) => (&rest[..idx], Some(&rest[idx + 1..])),
+      None => (rest, None),
+    };
+
+    let (authority, path) = match rest.strip_prefix("//") {
+      Some(hier) => {
+        let idx = hier.find('/').unwrap_or(hier.len());
+        (Some(&hier[..idx]), &hier[idx..])
+      }
+      None => (None, rest),
+    };
+
+    let mut userinfo = None;
+    let mut host = None;
+    let mut port = None;
+    if let Some(authority) = authority {
+      let (info, host_port) = match authority.rfind('@') {
+        Some(idx) => (Some(&authority[..idx]), &authority[idx + 1..]),
+        None => (None, authority),
+      };
+      userinfo = info.map(normalize_component).transpose()?;
+
+      let (h, p) = split_port(host_port)?;
+      host = Some(normalize_host(h)?);
+      port = p
+        .filter(|p| !p.is_empty() && default_port(&scheme) != Some(*p))
+        .map(String::from);
+    }
+
+    let mut path = normalize_component(path)?;
+    if host.is_some() || path.starts_with('/') {
+      path = remove_dot_segments(&path);
+    }
+    if path.is_empty()
+      && host.is_some()
+      && matches!(scheme.as_str(), "http" | "https")
+    {
+      path.push('/');
+    }
+
+    let query = query.map(normalize_component).transpose()?;
+    let fragment = fragment.map(normalize_component).transpose()?;
+
+    let iri = compose(
+      &scheme,
+      userinfo.as_deref(),
+      host.as_deref(),
+      port.as_deref(),
+      &path,
+      query.as_deref(),
+      fragment.as_deref(),
+    );
+    let ascii_host = match host.as_deref() {
+      Some(h) => Some(punycode::to_ascii(h).ok_or_else(invalid_iri)?),
+      None => None,
+    };
+    let uri = compose(
+      &scheme,
+      userinfo.as_deref().map(to_ascii).as_deref(),
+      ascii_host.as_deref(),
+      port.as_deref(),
+      &to_ascii(&path),
+      query.as_deref().map(to_ascii).as_deref(),
+      fragment.as_deref().map(to_ascii).as_deref(),
+    );
+
+    Ok(Iri {
+      iri,
+      uri,
+      scheme,
+      host,
+      port,
+      path,
+      query,
+      fragment,
+    })
+  }
+
+  /// Returns the normalized IRI form. Unicode characters are kept as-is.
+  pub fn as_str(&self) -> &str {
+    &self.iri
+  }
+
+  /// Returns the normalized URI form, which is pure ASCII. Unicode host
+  /// labels are punycode encoded & other non-ASCII characters are
+  /// percent-encoded.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::iri::Iri;
+  ///
+  /// let iri = Iri::parse("http://例え.jp/ä").unwrap();
+  /// assert_eq!(iri.as_uri(), "http://xn--r8jz45g.jp/%C3%A4");
+  ///
+  /// // The ASCII form parses back into an equal `Iri`.
+  /// let uri = Iri::parse("http://XN--R8JZ45G.jp/%c3%a4").unwrap();
+  /// assert_eq!(iri, uri);
+  /// assert_eq!(uri.as_str(), "http://例え.jp/ä");
+  /// ```
+  pub fn as_uri(&self) -> &str {
+    &self.uri
+  }
+
+  /// Returns the lower-cased scheme, e.g. `"https"`.
+  pub fn scheme(&self) -> &str {
+    &self.scheme
+  }
+
+  /// Returns the host in its Unicode form, if the IRI has an authority.
+  pub fn host(&self) -> Option<&str> {
+    self.host.as_deref()
+  }
+
+  /// Returns the port if it's not the default port of the scheme.
+  pub fn port(&self) -> Option<&str> {
+    self.port.as_deref()
+  }
+
+  /// Returns the normalized path.
+  pub fn path(&self) -> &str {
+    &self.path
+  }
+
+  /// Returns the query component without the leading `?`.
+  pub fn query(&self) -> Option<&str> {
+    self.query.as_deref()
+  }
+
+  /// Returns the fragment component without the leading `#`.
+  pub fn fragment(&self) -> Option<&str> {
+    self.fragment.as_deref()
+  }
+
+  /// Consumes the `Iri`, returning the normalized IRI string.
+  pub fn into_string(self) -> String {
+    self.iri
+  }
+
+  /// Expands a [CURIE] such as `schema:Person` against `prefixes`, a map of
+  /// prefix -> namespace IRI. `:name` uses the empty prefix.
+  ///
+  /// Absolute IRIs whose scheme isn't a bound prefix are parsed as is.
+  /// Fails with an `IllegalNamespace` error for unbound prefixes which don't
+  /// look like an IRI either.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use std::collections::BTreeMap;
+  ///
+  /// use sage::iri::Iri;
+  ///
+  /// let mut prefixes = BTreeMap::new();
+  /// prefixes.insert("schema".to_string(), "https://schema.org/".to_string());
+  ///
+  /// let person = Iri::expand("schema:Person", &prefixes).unwrap();
+  /// assert_eq!(person.as_str(), "https://schema.org/Person");
+  /// assert_eq!(person.compact(&prefixes).as_deref(), Some("schema:Person"));
+  ///
+  /// let absolute = Iri::expand("https://example.com/Ada", &prefixes).unwrap();
+  /// assert_eq!(absolute.compact(&prefixes), None);
+  /// assert!(Iri::expand("foaf name", &prefixes).is_err());
+  /// ```
+  ///
+  /// [CURIE]: https://www.w3.org/TR/curie/
+  pub fn expand(
+    curie: &str,
+    prefixes: &BTreeMap<String, String>,
+  ) -> Result<Iri> {
+    let (prefix, local) = curie.split_once(':').ok_or_else(unbound_prefix)?;
+    match prefixes.get(prefix) {
+      // `//` marks an authority, i.e. an absolute IRI like `http://...`.
+      Some(namespace) if !local.starts_with("//") => {
+        Iri::parse(&format!("{}{}", namespace, local))
+      }
+      _ => Iri::parse(curie).map_err(|_| unbound_prefix()),
+    }
+  }
+
+  /// Compacts the IRI into a CURIE using the longest namespace of
+  /// `prefixes` it starts with. Returns `None` if no namespace matches or
+  /// the remaining local name can't be written in a CURIE.
+  pub fn compact(&self, prefixes: &BTreeMap<String, String>) -> Option<String> {
+    prefixes
+      .iter()
+      .filter_map(|(prefix, namespace)| {
+        let local = self.iri.strip_prefix(namespace.as_str())?;
+        let valid = !local.contains(['/', '?', '#', ':']);
+        valid.then_some((namespace.len(), prefix, local))
+      })
+      .max_by_key(|(len, _, _)| *len)
+      .map(|(_, prefix, local)| format!("{}:{}", prefix, local))
+  }
+}
+
+impl PartialEq for Iri {
+  fn eq(&self, other: &Iri) -> bool {
+    self.uri == other.uri
+  }
+}
+
+impl Eq for Iri {}
+
+impl Hash for Iri {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.uri.hash(state)
+  }
+}
+
+impl PartialOrd for Iri {
+  fn partial_cmp(&self, other: &Iri) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Iri {
+  fn cmp(&self, other: &Iri) -> Ordering {
+    self.uri.cmp(&other.uri)
+  }
+}
+
+impl FromStr for Iri {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Iri::parse(s)
+  }
+}
+
+impl AsRef<str> for Iri {
+  fn as_ref(&self) -> &str {
+    self.as_str()
+  }
+}
+
+impl fmt::Display for Iri {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&self.iri)
+  }
+}
+
+impl From<Iri> for String {
+  fn from(iri: Iri) -> String {
+    iri.into_string()
+  }
+}
+
+impl TryFrom<&Node> for Iri {
+  type Error = Error;
+
+  /// Parses & normalizes the IRI of a `Node::Http`. Other nodes fail with
+  /// an `InvalidIri` error.
+  fn try_from(node: &Node) -> Result<Iri> {
+    match node {
+      Node::Http(iri) => Iri::parse(iri),
+      _ => Err(invalid_iri()),
+    }
+  }
+}
+
+impl From<Iri> for Node {
+  /// Creates a `Node::Http` from the normalized URI form so that nodes
+  /// created from differently encoded IRIs are equal.
+  fn from(iri: Iri) -> Node {
+    Node::Http(iri.uri)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Parsing & normalization helpers.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+#[cold]
+fn invalid_iri() -> Error {
+  Error::syntax(ErrorCode::InvalidIri, 0, 0)
+}
+
+#[cold]
+fn unbound_prefix() -> Error {
+  Error::syntax(ErrorCode::IllegalNamespace, 0, 0)
+}
+
+fn split_scheme(s: &str) -> Result<(&str, &str)> {
+  let idx = s.find(':').ok_or_else(invalid_iri)?;
+  let scheme = &s[..idx];
+
+  let mut chars = scheme.chars();
+  let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+    && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+
+  if valid {
+    Ok((scheme, &s[idx + 1..]))
+  } else {
+    Err(invalid_iri())
+  }
+}
+
+fn split_port(host_port: &str) -> Result<(&str, Option<&str>)> {
+  // IP literals (e.g. `[::1]:8080`) may contain colons.
+  let search_from = if host_port.starts_with('[') {
+    host_port.find(']').ok_or_else(invalid_iri)?
+  } else {
+    0
+  };
+
+  match host_port[search_from..].rfind(':') {
+    Some(idx) => {
+      let idx = search_from + idx;
+      let port = &host_port[idx + 1..];
+      if !port.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid_iri());
+      }
+      Ok((&host_port[..idx], Some(port)))
+    }
+    None => Ok((host_port, None)),
+  }
+}
+
+fn default_port(scheme: &str) -> Option<&'static str> {
+  match scheme {
+    "http" | "ws" => Some("80"),
+    "https" | "wss" => Some("443"),
+    "ftp" => Some("21"),
+    _ => None,
+  }
+}
+
+fn normalize_host(host: &str) -> Result<String> {
+  let host = normalize_component(host)?.to_lowercase();
+  if host.starts_with('[') {
+    return Ok(host);
+  }
+  // Show punycode labels in their Unicode form; fall back to the ASCII
+  // label when it isn't valid punycode.
+  Ok(punycode::to_unicode(&host).unwrap_or(host))
+}
+
+fn is_unreserved(b: u8) -> bool {
+  b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~')
+}
+
+fn is_illegal(c: char) -> bool {
+  c.is_control()
+    || c.is_whitespace()
+    || matches!(c, '<' | '>' | '"' | '{' | '}' | '|' | '\\' | '^' | '`')
+}
+
+fn hex_value(b: u8) -> Option<u8> {
+  match b {
+    b'0'..=b'9' => Some(b - b'0'),
+    b'a'..=b'f' => Some(b - b'a' + 10),
+    b'A'..=b'F' => Some(b - b'A' + 10),
+    _ => None,
+  }
+}
+
+/// Normalizes the percent-encoding of a single IRI component.
+///
+/// Unreserved ASCII octets are decoded, percent-encoded UTF-8 sequences of
+/// non-ASCII characters are decoded into Unicode, and every remaining octet
+/// is re-encoded with upper case hex digits.
+fn normalize_component(s: &str) -> Result<String> {
+  if s.chars().any(is_illegal) {
+    return Err(invalid_iri());
+  }
+
+  let bytes = s.as_bytes();
+  let mut out = String::with_capacity(s.len());
+  let mut i = 0;
+
+  while i < bytes.len() {
+    if bytes[i] != b'%' {
+      // Copy the (possibly multi-byte) character through untouched.
+      let ch = s[i..].chars().next().ok_or_else(invalid_iri)?;
+      out.push(ch);
+      i += ch.len_utf8();
+      continue;
+    }
+
+    // Collect a run of percent-encoded octets.
+    let mut octets = Vec::new();
+    while i < bytes.len() && bytes[i] == b'%' {
+      let hi = bytes.get(i + 1).copied().and_then(hex_value);
+      let lo = bytes.get(i + 2).copied().and_then(hex_value);
+      match (hi, lo) {
+        (Some(hi), Some(lo)) => octets.push((hi << 4) | lo),
+        _ => return Err(invalid_iri()),
+      }
+      i += 3;
+    }
+    push_octets(&mut out, &octets);
+  }
+
+  Ok(out)
+}
+
+fn push_octets(out: &mut String, octets: &[u8]) {
+  let mut j = 0;
+  while j < octets.len() {
+    let b = octets[j];
+    if b < 0x80 {
+      if is_unreserved(b) {
+        out.push(b as char);
+      } else {
+        push_encoded(out, b);
+      }
+      j += 1;
+      continue;
+    }
+
+    // Try to decode a full UTF-8 sequence starting at `j`.
+    let width = match b {
+      0xC2..=0xDF => 2,
+      0xE0..=0xEF => 3,
+      0xF0..=0xF4 => 4,
+      _ => 1,
+    };
+    let decoded = octets
+      .get(j..j + width)
+      .and_then(|seq| std::str::from_utf8(seq).ok())
+      .and_then(|s| s.chars().next())
+      .filter(|&c| !is_illegal(c));
+
+    match decoded {
+      Some(c) => {
+        out.push(c);
+        j += width;
+      }
+      None => {
+        push_encoded(out, b);
+        j += 1;
+      }
+    }
+  }
+}
+
+fn push_encoded(out: &mut String, b: u8) {
+  const HEX: &[u8; 16] = b"0123456789ABCDEF";
+  out.push('%');
+  out.push(HEX[(b >> 4) as usize] as char);
+  out.push(HEX[(b & 0xF) as usize] as char);
+}
+
+/// Maps an IRI component to a URI component by percent-encoding the UTF-8
+/// octets of every non-ASCII character.
+fn to_ascii(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  for c in s.chars() {
+    if c.is_ascii() {
+      out.push(c);
+    } else {
+      let mut buf = [0; 4];
+      for &b in c.encode_utf8(&mut buf).as_bytes() {
+        push_encoded(&mut out, b);
+      }
+    }
+  }
+  out
+}
+
+/// Removes `.` and `..` segments from a path as described by
+/// [RFC 3986 §5.2.4](https://tools.ietf.org/html/rfc3986#section-5.2.4).
+fn remove_dot_segments(path: &str) -> String {
+  let mut input = path;
+  let mut output: Vec<&str> = Vec::new();
+
+  while !input.is_empty() {
+    if let Some(rest) = input.strip_prefix("../") {
+      input = rest;
+    } else if let Some(rest) = input.strip_prefix("./") {
+      input = rest;
+    } else if input.starts_with("/./") {
+      input = &input[2..];
+    } else if input == "/." {
+      input = "/";
+    } else if input.starts_with("/../") {
+      input = &input[3..];
+      output.pop();
+    } else if input == "/.." {
+      input = "/";
+      output.pop();
+    } else if input == "." || input == ".." {
+      input = "";
+    } else {
+      let start = usize::from(input.starts_with('/'));
+      let end = input[start..].find('/').map_or(input.len(), |i| i + start);
+      output.push(&input[..end]);
+      input = &input[end..];
+    }
+  }
+
+  output.concat()
+}
+
+fn compose(
+  scheme: &str,
+  userinfo: Option<&str>,
+  host: Option<&str>,
+  port: Option<&str>,
+  path: &str,
+  query: Option<&str>,
+  fragment: Option<&str>,
+) -> String {
+  let mut out = String::from(scheme);
+  out.push(':');
+  if let Some(host) = host {
+    out.push_str("//");
+    if let Some(userinfo) = userinfo {
+      out.push_str(userinfo);
+      out.push('@');
+    }
+    out.push_str(host);
+    if let Some(port) = port {
+      out.push(':');
+      out.push_str(port);
+    }
+  }
+  out.push_str(path);
+  if let Some(query) = query {
+    out.push('?');
+    out.push_str(query);
+  }
+  if let Some(fragment) = fragment {
+    out.push('#');
+    out.push_str(fragment);
+  }
+  out
+}
+
+/// Resolves the relative `reference` against the absolute IRI `base` as
+/// described by [RFC 3986 §5.2](https://tools.ietf.org/html/rfc3986#section-5.2).
+/// References with a scheme are returned as is, and so is every reference
+/// if `base` isn't absolute.
+pub(crate) fn resolve(base: &str, reference: &str) -> String {
+  let reference = reference.trim();
+  let (scheme, base) = match split_scheme(base) {
+    Ok(parts) if split_scheme(reference).is_err() => parts,
+    _ => return reference.to_string(),
+  };
+  if reference.starts_with("//") {
+    return format!("{}:{}", scheme, reference);
+  }
+
+  let base = base.split('#').next().unwrap_or_default();
+  let (authority, base) = match base.strip_prefix("//") {
+    Some(rest) => {
+      let end = rest.find(['/', '?']).unwrap_or(rest.len());
+      (Some(&rest[..end]), &rest[end..])
+    }
+    None => (None, base),
+  };
+  let (base_path, base_query) = match base.split_once('?') {
+    Some((path, query)) => (path, Some(query)),
+    None => (base, None),
+  };
+
+  let (reference, fragment) = match reference.split_once('#') {
+    Some((reference, fragment)) => (reference, Some(fragment)),
+    None => (reference, None),
+  };
+  let (path, query) = match reference.split_once('?') {
+    Some((path, query)) => (path, Some(query)),
+    None => (reference, None),
+  };
+
+  let (path, query) = if path.is_empty() {
+    (base_path.to_string(), query.or(base_query))
+  } else if path.starts_with('/') {
+    (remove_dot_segments(path), query)
+  } else {
+    let merged = match base_path.rfind('/') {
+      Some(idx) => format!("{}{}", &base_path[..=idx], path),
+      None if authority.is_some() => format!("/{}", path),
+      None => path.to_string(),
+    };
+    (remove_dot_segments(&merged), query)
+  };
+
+  let mut out = String::from(scheme);
+  out.push(':');
+  if let Some(authority) = authority {
+    out.push_str("//");
+    out.push_str(authority);
+  }
+  out.push_str(&path);
+  if let Some(query) = query {
+    out.push('?');
+    out.push_str(query);
+  }
+  if let Some(fragment) = fragment {
+    out.push('#');
+    out.push_str(fragment);
+  }
+  out
+}
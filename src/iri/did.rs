@@ -0,0 +1,190 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+  error::{Error, ErrorCode},
+  graph::Node,
+  Result,
+};
+
+/// Bitcoin base58 alphabet used by the `z` multibase prefix.
+const BASE58_ALPHABET: &str =
+  "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// `Did` is a [Decentralized Identifier] or DID URL.
+///
+/// `did:<method>:<method-specific-id>[/path][?query][#fragment]`
+///
+/// The method name must consist of lower case letters & digits. For the
+/// `did:key` method the identifier is further checked to be a base58btc
+/// (`z` prefixed) multibase value.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::iri::Did;
+///
+/// let did = Did::parse("did:web:example.com:user:alice#key-1").unwrap();
+///
+/// assert_eq!(did.method(), "web");
+/// assert_eq!(did.method_specific_id(), "example.com:user:alice");
+/// assert_eq!(did.fragment(), Some("key-1"));
+/// assert_eq!(did.did(), "did:web:example.com:user:alice");
+///
+/// // Method names must be lower case.
+/// assert!(Did::parse("did:WEB:example.com").is_err());
+/// // `did:key` identifiers must be base58btc multibase encoded.
+/// assert!(Did::parse("did:key:z6Mk0OIl").is_err());
+/// ```
+///
+/// [Decentralized Identifier]: https://www.w3.org/TR/did-core/#did-syntax
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Did {
+  method: String,
+  id: String,
+  path: Option<String>,
+  query: Option<String>,
+  fragment: Option<String>,
+}
+
+impl Did {
+  /// `Did::parse` parses & validates a DID or DID URL.
+  pub fn parse(s: &str) -> Result<Did> {
+    let rest = s.strip_prefix("did:").ok_or_else(invalid_did)?;
+
+    let (rest, fragment) = match rest.find('#') {
+      Some(idx) => (&rest[..idx], Some(rest[idx + 1..].to_string())),
+      None => (rest, None),
+    };
+    let (rest, query) = match rest.find('?') {
+      Some(idx) => (&rest[..idx], Some(rest[idx + 1..].to_string())),
+      None => (rest, None),
+    };
+    let (rest, path) = match rest.find('/') {
+      Some(idx) => (&rest[..idx], Some(rest[idx..].to_string())),
+      None => (rest, None),
+    };
+
+    let idx = rest.find(':').ok_or_else(invalid_did)?;
+    let (method, id) = (&rest[..idx], &rest[idx + 1..]);
+
+    let valid_method = !method.is_empty()
+      && method
+        .bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit());
+    // The last segment of the method specific ID can't be empty.
+    let valid_id = !id.is_empty()
+      && !id.ends_with(':')
+      && id.chars().all(|c| {
+        c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_' | ':' | '%')
+      });
+    if !valid_method || !valid_id {
+      return Err(invalid_did());
+    }
+
+    if method == "key" && !is_base58btc_multibase(id) {
+      return Err(invalid_did());
+    }
+
+    Ok(Did {
+      method: method.to_string(),
+      id: id.to_string(),
+      path,
+      query,
+      fragment,
+    })
+  }
+
+  /// Returns the DID method, e.g. `"key"` for `did:key:...`.
+  pub fn method(&self) -> &str {
+    &self.method
+  }
+
+  /// Returns the method specific identifier.
+  pub fn method_specific_id(&self) -> &str {
+    &self.id
+  }
+
+  /// Returns the path component of a DID URL.
+  pub fn path(&self) -> Option<&str> {
+    self.path.as_deref()
+  }
+
+  /// Returns the query component of a DID URL without the leading `?`.
+  pub fn query(&self) -> Option<&str> {
+    self.query.as_deref()
+  }
+
+  /// Returns the fragment of a DID URL without the leading `#`.
+  pub fn fragment(&self) -> Option<&str> {
+    self.fragment.as_deref()
+  }
+
+  /// Returns `true` if this is a DID URL rather than a plain DID.
+  pub fn is_url(&self) -> bool {
+    self.path.is_some() || self.query.is_some() || self.fragment.is_some()
+  }
+
+  /// Returns the plain DID without any path, query or fragment.
+  pub fn did(&self) -> String {
+    format!("did:{}:{}", self.method, self.id)
+  }
+}
+
+impl FromStr for Did {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Did::parse(s)
+  }
+}
+
+impl fmt::Display for Did {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "did:{}:{}", self.method, self.id)?;
+    if let Some(path) = &self.path {
+      f.write_str(path)?;
+    }
+    if let Some(query) = &self.query {
+      write!(f, "?{}", query)?;
+    }
+    if let Some(fragment) = &self.fragment {
+      write!(f, "#{}", fragment)?;
+    }
+    Ok(())
+  }
+}
+
+impl From<Did> for Node {
+  fn from(did: Did) -> Node {
+    Node::Http(did.to_string())
+  }
+}
+
+#[cold]
+fn invalid_did() -> Error {
+  Error::syntax(ErrorCode::InvalidDid, 0, 0)
+}
+
+fn is_base58btc_multibase(id: &str) -> bool {
+  match id.strip_prefix('z') {
+    Some(encoded) => {
+      !encoded.is_empty()
+        && encoded.chars().all(|c| BASE58_ALPHABET.contains(c))
+    }
+    None => false,
+  }
+}
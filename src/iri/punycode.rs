@@ -0,0 +1,192 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bootstring encoding of Unicode host labels as described by [RFC 3492].
+//!
+//! [RFC 3492]: https://tools.ietf.org/html/rfc3492
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 0x80;
+
+/// ACE prefix used by IDNA to mark punycode encoded labels.
+pub(crate) const ACE_PREFIX: &str = "xn--";
+
+fn adapt(mut delta: u32, num_points: u32, first_time: bool) -> u32 {
+  delta /= if first_time { DAMP } else { 2 };
+  delta += delta / num_points;
+
+  let mut k = 0;
+  while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+    delta /= BASE - T_MIN;
+    k += BASE;
+  }
+  k + (((BASE - T_MIN + 1) * delta) / (delta + SKEW))
+}
+
+fn encode_digit(d: u32) -> char {
+  // 0..25 map to 'a'..'z', 26..35 map to '0'..'9'.
+  match d {
+    0..=25 => (b'a' + d as u8) as char,
+    _ => (b'0' + (d - 26) as u8) as char,
+  }
+}
+
+fn decode_digit(c: char) -> Option<u32> {
+  match c {
+    'a'..='z' => Some(c as u32 - 'a' as u32),
+    'A'..='Z' => Some(c as u32 - 'A' as u32),
+    '0'..='9' => Some(c as u32 - '0' as u32 + 26),
+    _ => None,
+  }
+}
+
+fn threshold(k: u32, bias: u32) -> u32 {
+  if k <= bias {
+    T_MIN
+  } else if k >= bias + T_MAX {
+    T_MAX
+  } else {
+    k - bias
+  }
+}
+
+/// Encodes a single Unicode label into its punycode form (without the
+/// `xn--` prefix). Returns `None` on arithmetic overflow.
+pub(crate) fn encode(input: &str) -> Option<String> {
+  let input: Vec<u32> = input.chars().map(|c| c as u32).collect();
+  let mut output: String = input
+    .iter()
+    .filter(|&&c| c < 0x80)
+    .map(|&c| c as u8 as char)
+    .collect();
+
+  let basic_len = output.len() as u32;
+  let mut handled = basic_len;
+  if basic_len > 0 {
+    output.push('-');
+  }
+
+  let mut n = INITIAL_N;
+  let mut delta: u32 = 0;
+  let mut bias = INITIAL_BIAS;
+
+  while (handled as usize) < input.len() {
+    let m = *input.iter().filter(|&&c| c >= n).min()?;
+    delta = delta.checked_add((m - n).checked_mul(handled + 1)?)?;
+    n = m;
+
+    for &c in &input {
+      if c < n {
+        delta = delta.checked_add(1)?;
+      }
+      if c == n {
+        let mut q = delta;
+        let mut k = BASE;
+        loop {
+          let t = threshold(k, bias);
+          if q < t {
+            break;
+          }
+          output.push(encode_digit(t + (q - t) % (BASE - t)));
+          q = (q - t) / (BASE - t);
+          k += BASE;
+        }
+        output.push(encode_digit(q));
+        bias = adapt(delta, handled + 1, handled == basic_len);
+        delta = 0;
+        handled += 1;
+      }
+    }
+    delta += 1;
+    n += 1;
+  }
+
+  Some(output)
+}
+
+/// Decodes a punycode label (without the `xn--` prefix) back into Unicode.
+/// Returns `None` if the label is not valid punycode.
+pub(crate) fn decode(input: &str) -> Option<String> {
+  let (basic, extended) = match input.rfind('-') {
+    Some(idx) => (&input[..idx], &input[idx + 1..]),
+    None => ("", input),
+  };
+  if !basic.is_ascii() {
+    return None;
+  }
+
+  let mut output: Vec<char> = basic.chars().collect();
+  let mut n = INITIAL_N;
+  let mut i: u32 = 0;
+  let mut bias = INITIAL_BIAS;
+  let mut chars = extended.chars().peekable();
+
+  while chars.peek().is_some() {
+    let old_i = i;
+    let mut w: u32 = 1;
+    let mut k = BASE;
+    loop {
+      let digit = decode_digit(chars.next()?)?;
+      i = i.checked_add(digit.checked_mul(w)?)?;
+      let t = threshold(k, bias);
+      if digit < t {
+        break;
+      }
+      w = w.checked_mul(BASE - t)?;
+      k += BASE;
+    }
+
+    let len = output.len() as u32 + 1;
+    bias = adapt(i - old_i, len, old_i == 0);
+    n = n.checked_add(i / len)?;
+    i %= len;
+
+    output.insert(i as usize, char::from_u32(n)?);
+    i += 1;
+  }
+
+  Some(output.into_iter().collect())
+}
+
+/// Converts a (lowercased) host name into its ASCII compatible form by
+/// punycode encoding every label that contains non-ASCII characters.
+pub(crate) fn to_ascii(host: &str) -> Option<String> {
+  let mut labels = Vec::new();
+  for label in host.split('.') {
+    if label.is_ascii() {
+      labels.push(label.to_string());
+    } else {
+      labels.push(format!("{}{}", ACE_PREFIX, encode(label)?));
+    }
+  }
+  Some(labels.join("."))
+}
+
+/// Converts an ASCII compatible host name back into its Unicode form by
+/// decoding every `xn--` label.
+pub(crate) fn to_unicode(host: &str) -> Option<String> {
+  let mut labels = Vec::new();
+  for label in host.split('.') {
+    match label.strip_prefix(ACE_PREFIX) {
+      Some(encoded) => labels.push(decode(encoded)?),
+      None => labels.push(label.to_string()),
+    }
+  }
+  Some(labels.join("."))
+}
@@ -0,0 +1,172 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+
+use regex::Regex;
+
+use crate::{
+  error::{Error, ErrorCode},
+  graph::{KnowledgeGraph, Node, Predicate, Triple},
+  Result,
+};
+
+/// A single rewriting rule.
+#[derive(Clone, Debug)]
+enum Rule {
+  Prefix { from: String, to: String },
+  Regex { pattern: Regex, replacement: String },
+}
+
+/// `Rewriter` maps identifiers through prefix & regex rules, e.g. to move a
+/// dataset from `http://old.example/` to `https://data.example/` while
+/// importing or exporting it.
+///
+/// Rules are tried in the order they were added and the first matching rule
+/// wins. Literals are never rewritten.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::iri::Rewriter;
+///
+/// let rewriter = Rewriter::new()
+///   .prefix("http://old.example/", "https://data.example/")
+///   .regex(r"^https://example\.com/people/(\d+)$", "https://data.example/person/$1")
+///   .unwrap();
+///
+/// assert_eq!(
+///   rewriter.rewrite("http://old.example/Ada"),
+///   "https://data.example/Ada"
+/// );
+/// assert_eq!(
+///   rewriter.rewrite("https://example.com/people/42"),
+///   "https://data.example/person/42"
+/// );
+/// assert_eq!(rewriter.rewrite("urn:isbn:0451450523"), "urn:isbn:0451450523");
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   Node::Http("http://old.example/Ada".to_string()),
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal("http://old.example/Ada".into()),
+/// );
+///
+/// let migrated = rewriter.apply(&graph);
+/// let triple = migrated.triples().next().unwrap();
+/// assert_eq!(triple.source(), &Node::Http("https://data.example/Ada".to_string()));
+/// assert_eq!(triple.destination(), &Node::Literal("http://old.example/Ada".into()));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Rewriter {
+  rules: Vec<Rule>,
+}
+
+impl Rewriter {
+  /// Creates a rewriter without rules, leaving every identifier unchanged.
+  pub fn new() -> Rewriter {
+    Rewriter::default()
+  }
+
+  /// Replaces the leading `from` of identifiers with `to`.
+  pub fn prefix(mut self, from: &str, to: &str) -> Self {
+    self.rules.push(Rule::Prefix {
+      from: from.to_string(),
+      to: to.to_string(),
+    });
+    self
+  }
+
+  /// Replaces identifiers matching `pattern` with `replacement`, which may
+  /// refer to capture groups as `$1` or `$name`.
+  pub fn regex(mut self, pattern: &str, replacement: &str) -> Result<Self> {
+    let pattern = Regex::new(pattern)
+      .map_err(|_| Error::syntax(ErrorCode::RegexParser, 0, 0))?;
+    self.rules.push(Rule::Regex {
+      pattern,
+      replacement: replacement.to_string(),
+    });
+    Ok(self)
+  }
+
+  /// Returns `true` if there are no rules.
+  pub fn is_empty(&self) -> bool {
+    self.rules.is_empty()
+  }
+
+  /// Rewrites a single identifier with the first matching rule.
+  pub fn rewrite<'a>(&self, iri: &'a str) -> Cow<'a, str> {
+    for rule in &self.rules {
+      match rule {
+        Rule::Prefix { from, to } => {
+          if let Some(rest) = iri.strip_prefix(from.as_str()) {
+            return Cow::Owned(format!("{}{}", to, rest));
+          }
+        }
+        Rule::Regex {
+          pattern,
+          replacement,
+        } => {
+          if pattern.is_match(iri) {
+            return Cow::Owned(
+              pattern.replace(iri, replacement.as_str()).into_owned(),
+            );
+          }
+        }
+      }
+    }
+    Cow::Borrowed(iri)
+  }
+
+  /// Rewrites the identifiers of `node`, leaving literals & blank nodes
+  /// untouched.
+  pub fn rewrite_node(&self, node: &Node) -> Node {
+    match node {
+      Node::Http(iri) => Node::Http(self.rewrite(iri).into_owned()),
+      Node::Multiple(nodes) => {
+        Node::Multiple(nodes.iter().map(|n| self.rewrite_node(n)).collect())
+      }
+      node => node.clone(),
+    }
+  }
+
+  /// Rewrites the full IRI of `predicate`.
+  pub fn rewrite_predicate(&self, predicate: &Predicate) -> Predicate {
+    let iri = predicate.to_string();
+    match self.rewrite(&iri) {
+      Cow::Borrowed(_) => predicate.clone(),
+      Cow::Owned(iri) => Predicate::Literal(iri),
+    }
+  }
+
+  /// Rewrites a single triple.
+  pub fn rewrite_triple(&self, triple: &Triple) -> Triple {
+    let rewritten = Triple::from_nodes(
+      self.rewrite_node(triple.source()),
+      self.rewrite_predicate(triple.predicate()),
+      self.rewrite_node(triple.destination()),
+    );
+    match triple.provenance() {
+      Some(provenance) => rewritten.with_provenance(provenance.clone()),
+      None => rewritten,
+    }
+  }
+
+  /// Returns a copy of `graph` with every subject, predicate & object
+  /// rewritten.
+  pub fn apply(&self, graph: &KnowledgeGraph) -> KnowledgeGraph {
+    graph.triples().map(|t| self.rewrite_triple(t)).collect()
+  }
+}
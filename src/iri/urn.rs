@@ -0,0 +1,283 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+  error::{Error, ErrorCode},
+  graph::Node,
+  Result,
+};
+
+/// `Urn` is a Uniform Resource Name as described by [RFC 8141].
+///
+/// `urn:<NID>:<NSS>` where the namespace identifier (NID) is compared case
+/// insensitively and is normalized to lower case. For the `isbn`, `issn`
+/// and `uuid` namespaces the namespace specific string (NSS) is validated as
+/// well, including the ISBN/ISSN check digit.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::iri::Urn;
+///
+/// let urn = Urn::parse("URN:ISBN:978-0-306-40615-7").unwrap();
+/// assert_eq!(urn.nid(), "isbn");
+/// assert_eq!(urn.nss(), "978-0-306-40615-7");
+/// assert_eq!(urn.to_string(), "urn:isbn:978-0-306-40615-7");
+///
+/// // Wrong check digit.
+/// assert!(Urn::parse("urn:isbn:978-0-306-40615-8").is_err());
+/// ```
+///
+/// [RFC 8141]: https://tools.ietf.org/html/rfc8141
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Urn {
+  nid: String,
+  nss: String,
+  /// `?+` resolution component.
+  r_component: Option<String>,
+  /// `?=` query component.
+  q_component: Option<String>,
+  fragment: Option<String>,
+}
+
+impl Urn {
+  /// `Urn::parse` parses & validates a URN.
+  pub fn parse(s: &str) -> Result<Urn> {
+    let rest = match s.get(..4) {
+      Some(scheme) if scheme.eq_ignore_ascii_case("urn:") => &s[4..],
+      _ => return Err(invalid_urn()),
+    };
+
+    let (rest, fragment) = match rest.find('#') {
+      Some(idx) => (&rest[..idx], Some(rest[idx + 1..].to_string())),
+      None => (rest, None),
+    };
+    let (rest, q_component) = match rest.find("?=") {
+      Some(idx) => (&rest[..idx], Some(rest[idx + 2..].to_string())),
+      None => (rest, None),
+    };
+    let (rest, r_component) = match rest.find("?+") {
+      Some(idx) => (&rest[..idx], Some(rest[idx + 2..].to_string())),
+      None => (rest, None),
+    };
+
+    let idx = rest.find(':').ok_or_else(invalid_urn)?;
+    let (nid, nss) = (&rest[..idx], &rest[idx + 1..]);
+    if !is_valid_nid(nid) || nss.is_empty() || !nss.chars().all(is_nss_char) {
+      return Err(invalid_urn());
+    }
+
+    let nid = nid.to_ascii_lowercase();
+    let valid = match nid.as_str() {
+      "isbn" => is_valid_isbn(nss),
+      "issn" => is_valid_issn(nss),
+      "uuid" => is_valid_uuid(nss),
+      _ => true,
+    };
+    if !valid {
+      return Err(invalid_urn());
+    }
+
+    // UUIDs are compared case insensitively (RFC 4122).
+    let nss = if nid == "uuid" {
+      nss.to_ascii_lowercase()
+    } else {
+      normalize_pct(nss)
+    };
+
+    Ok(Urn {
+      nid,
+      nss,
+      r_component,
+      q_component,
+      fragment,
+    })
+  }
+
+  /// Returns the (lower-cased) namespace identifier. e.g. `"isbn"`.
+  pub fn nid(&self) -> &str {
+    &self.nid
+  }
+
+  /// Returns the namespace specific string.
+  pub fn nss(&self) -> &str {
+    &self.nss
+  }
+
+  /// Returns the `?+` resolution component.
+  pub fn r_component(&self) -> Option<&str> {
+    self.r_component.as_deref()
+  }
+
+  /// Returns the `?=` query component.
+  pub fn q_component(&self) -> Option<&str> {
+    self.q_component.as_deref()
+  }
+
+  /// Returns the fragment without the leading `#`.
+  pub fn fragment(&self) -> Option<&str> {
+    self.fragment.as_deref()
+  }
+}
+
+impl FromStr for Urn {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Urn::parse(s)
+  }
+}
+
+impl fmt::Display for Urn {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "urn:{}:{}", self.nid, self.nss)?;
+    if let Some(r) = &self.r_component {
+      write!(f, "?+{}", r)?;
+    }
+    if let Some(q) = &self.q_component {
+      write!(f, "?={}", q)?;
+    }
+    if let Some(fragment) = &self.fragment {
+      write!(f, "#{}", fragment)?;
+    }
+    Ok(())
+  }
+}
+
+impl From<Urn> for Node {
+  fn from(urn: Urn) -> Node {
+    Node::Http(urn.to_string())
+  }
+}
+
+#[cold]
+fn invalid_urn() -> Error {
+  Error::syntax(ErrorCode::InvalidUrn, 0, 0)
+}
+
+/// NID is 2-32 characters of letters, digits & hyphens which starts and
+/// ends with a letter or digit.
+fn is_valid_nid(nid: &str) -> bool {
+  let bytes = nid.as_bytes();
+  (2..=32).contains(&bytes.len())
+    && bytes
+      .iter()
+      .all(|b| b.is_ascii_alphanumeric() || *b == b'-')
+    && bytes[0].is_ascii_alphanumeric()
+    && bytes[bytes.len() - 1].is_ascii_alphanumeric()
+}
+
+fn is_nss_char(c: char) -> bool {
+  c.is_ascii_alphanumeric()
+    || matches!(
+      c,
+      '-'
+        | '.'
+        | '_'
+        | '~'
+        | '%'
+        | '!'
+        | '$'
+        | '&'
+        | '\''
+        | '('
+        | ')'
+        | '*'
+        | '+'
+        | ','
+        | ';'
+        | '='
+        | ':'
+        | '@'
+        | '/'
+    )
+}
+
+/// Upper-cases the hex digits of percent-encoded octets.
+fn normalize_pct(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  let mut chars = s.chars();
+  while let Some(c) = chars.next() {
+    out.push(c);
+    if c == '%' {
+      out.extend(chars.by_ref().take(2).map(|c| c.to_ascii_uppercase()));
+    }
+  }
+  out
+}
+
+fn digits(s: &str) -> Vec<char> {
+  s.chars().filter(|c| !matches!(c, '-' | ' ')).collect()
+}
+
+fn is_valid_isbn(nss: &str) -> bool {
+  let d = digits(nss);
+  match d.len() {
+    10 => {
+      let mut sum = 0;
+      for (i, c) in d.iter().enumerate() {
+        let v = match c {
+          'X' | 'x' if i == 9 => 10,
+          c => match c.to_digit(10) {
+            Some(v) => v,
+            None => return false,
+          },
+        };
+        sum += v * (10 - i as u32);
+      }
+      sum % 11 == 0
+    }
+    13 => {
+      let mut sum = 0;
+      for (i, c) in d.iter().enumerate() {
+        match c.to_digit(10) {
+          Some(v) => sum += if i % 2 == 0 { v } else { v * 3 },
+          None => return false,
+        }
+      }
+      sum % 10 == 0
+    }
+    _ => false,
+  }
+}
+
+fn is_valid_issn(nss: &str) -> bool {
+  let d = digits(nss);
+  if d.len() != 8 {
+    return false;
+  }
+  let mut sum = 0;
+  for (i, c) in d.iter().enumerate() {
+    let v = match c {
+      'X' | 'x' if i == 7 => 10,
+      c => match c.to_digit(10) {
+        Some(v) => v,
+        None => return false,
+      },
+    };
+    sum += v * (8 - i as u32);
+  }
+  sum % 11 == 0
+}
+
+fn is_valid_uuid(nss: &str) -> bool {
+  let bytes = nss.as_bytes();
+  bytes.len() == 36
+    && bytes.iter().enumerate().all(|(i, b)| match i {
+      8 | 13 | 18 | 23 => *b == b'-',
+      _ => b.is_ascii_hexdigit(),
+    })
+}
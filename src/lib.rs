@@ -25,16 +25,47 @@
   clippy::needless_doctest_main
 )]
 
+pub mod arena;
+#[cfg(feature = "compression")]
+pub mod compression;
 pub mod error;
 pub mod graph;
+#[cfg(feature = "http")]
+mod http;
+pub mod importers;
+pub mod iri;
+pub mod linkage;
+pub mod load;
 #[macro_use]
 mod macros;
 mod datastore;
+pub mod did;
+pub mod diff;
 pub mod dtype;
+pub mod formats;
+pub mod interop;
+pub mod pipeline;
 mod processor;
-mod query;
+#[cfg(feature = "pyo3")]
+pub mod python;
+pub mod query;
+pub mod random;
+#[cfg(feature = "repl")]
+pub mod repl;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+pub mod rules;
+pub mod runtime;
 pub mod schema;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod sign;
+pub mod transcode;
+pub mod transform;
+pub mod vc;
 pub mod vocab;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 /// Sage `Result` type.
 pub type Result<T, E = error::Error> = std::result::Result<T, E>;
@@ -52,7 +83,7 @@ pub mod prelude {
   pub use crate::error::*;
 
   // Sage datastore.
-  pub use crate::datastore::json;
+  pub use crate::datastore::{blob, json, store};
 
   // Sage types.
   pub use crate::dtype::*;
@@ -69,6 +100,9 @@ pub mod prelude {
 
   // Export macros.
   pub use crate::macros::*;
+
+  // Transcoding between serde formats.
+  pub use crate::transcode::{transcode, Transcoder};
 }
 
 // Expose `sage::prelude` by default.
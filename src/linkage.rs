@@ -0,0 +1,408 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::linkage` finds probable duplicate entities in a graph (record
+//! linkage) and proposes `owl:sameAs` links between them.
+//!
+//! Comparing every pair of entities is quadratic, so a `Linker` only
+//! compares entities which share a *blocking key*, e.g. the first letters
+//! of their name. Candidate pairs are then scored: a shared identifier
+//! (e.g. an ISBN or a Wikidata ID) is a certain match, otherwise the score
+//! is the weighted average of the string similarities of the configured
+//! fields. Pairs scoring at least the threshold are proposed as links.
+//!
+//! ```rust
+//! use sage::graph::{KnowledgeGraph, Node, Predicate};
+//! use sage::linkage::{BlockingKey, Linker};
+//!
+//! let name = "https://schema.org/name";
+//! let mut graph = KnowledgeGraph::new();
+//! let people = [
+//!   ("a1", "Ada Lovelace"),
+//!   ("a2", "Ada Lovelace."),
+//!   ("b", "Alan Turing"),
+//! ];
+//! for (id, label) in people {
+//!   graph.insert(
+//!     Node::Http(format!("https://example.com/{}", id)),
+//!     Predicate::Literal(name.to_string()),
+//!     Node::Literal(label.into()),
+//!   );
+//! }
+//!
+//! let linker = Linker::new()
+//!   .block_on(BlockingKey::Prefix(name.to_string(), 2))
+//!   .compare(name, 1.0);
+//! let links = linker.propose(&graph);
+//!
+//! assert_eq!(links.len(), 1);
+//! assert_eq!(links[0].a, Node::Http("https://example.com/a1".to_string()));
+//! assert_eq!(links[0].b, Node::Http("https://example.com/a2".to_string()));
+//! assert!(links[0].score > 0.9);
+//!
+//! sage::linkage::apply(&mut graph, &links);
+//! assert_eq!(graph.len(), 4);
+//! ```
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{
+  dtype::DType,
+  graph::{KnowledgeGraph, Node, Predicate},
+};
+
+/// Full IRI of `owl:sameAs`.
+pub const OWL_SAME_AS: &str = "http://www.w3.org/2002/07/owl#sameAs";
+
+/// Default score a pair needs to be linked.
+const THRESHOLD: f64 = 0.9;
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Linker
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `BlockingKey` derives the keys an entity is blocked on from the values
+/// of a predicate. Values are normalized first: lowercased, punctuation
+/// removed & whitespace collapsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BlockingKey {
+  /// The whole normalized value.
+  Value(String),
+  /// The first `n` characters of the normalized value.
+  Prefix(String, usize),
+}
+
+impl BlockingKey {
+  fn predicate(&self) -> &str {
+    match self {
+      BlockingKey::Value(predicate) | BlockingKey::Prefix(predicate, _) => {
+        predicate
+      }
+    }
+  }
+
+  fn key(&self, value: &str) -> String {
+    match self {
+      BlockingKey::Value(_) => value.to_string(),
+      BlockingKey::Prefix(_, n) => value.chars().take(*n).collect(),
+    }
+  }
+}
+
+/// `Link` is a proposed `owl:sameAs` link between two entities.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Link {
+  /// The entity whose IRI sorts first.
+  pub a: Node,
+  /// The other entity.
+  pub b: Node,
+  /// Score of the match, between `0.0` & `1.0`. Identifier matches score
+  /// `1.0`.
+  pub score: f64,
+}
+
+/// `Linker` proposes `owl:sameAs` links between probable duplicates.
+///
+/// Without any blocking key, identifiers act as blocking keys, so only
+/// entities sharing an identifier are compared.
+#[derive(Clone, Debug)]
+pub struct Linker {
+  blocking: Vec<BlockingKey>,
+  identifiers: Vec<String>,
+  fields: Vec<(String, f64)>,
+  threshold: f64,
+}
+
+impl Default for Linker {
+  fn default() -> Self {
+    Linker {
+      blocking: Vec::new(),
+      identifiers: Vec::new(),
+      fields: Vec::new(),
+      threshold: THRESHOLD,
+    }
+  }
+}
+
+impl Linker {
+  /// Creates a linker without any blocking key, identifier or field.
+  pub fn new() -> Self {
+    Linker::default()
+  }
+
+  /// Compares entities sharing `key`. Entities sharing any of several keys
+  /// are compared.
+  pub fn block_on(mut self, key: BlockingKey) -> Self {
+    self.blocking.push(key);
+    self
+  }
+
+  /// Links entities sharing a value of `predicate`, e.g. an ISBN,
+  /// regardless of the other fields. Identifiers are compared ignoring
+  /// case, punctuation & whitespace.
+  pub fn identifier(mut self, predicate: &str) -> Self {
+    self.identifiers.push(predicate.to_string());
+    self
+  }
+
+  /// Scores the string similarity of the values of `predicate`, counting
+  /// `weight` times in the average.
+  pub fn compare(mut self, predicate: &str, weight: f64) -> Self {
+    self.fields.push((predicate.to_string(), weight));
+    self
+  }
+
+  /// Sets the score a pair needs to be linked, `0.9` by default.
+  pub fn threshold(mut self, threshold: f64) -> Self {
+    self.threshold = threshold;
+    self
+  }
+
+  /// Proposes links between the entities of `graph`, best first.
+  pub fn propose(&self, graph: &KnowledgeGraph) -> Vec<Link> {
+    let records = records(graph);
+    let ids: Vec<&String> = records.keys().collect();
+
+    let mut blocks: HashMap<(usize, String), Vec<usize>> = HashMap::new();
+    for (i, record) in records.values().enumerate() {
+      for (k, key) in self.blocking.iter().enumerate() {
+        for value in record.get(key.predicate()).into_iter().flatten() {
+          blocks.entry((k, key.key(value))).or_default().push(i);
+        }
+      }
+      if self.blocking.is_empty() {
+        for (k, predicate) in self.identifiers.iter().enumerate() {
+          for value in record.get(predicate).into_iter().flatten() {
+            blocks.entry((k, identifier(value))).or_default().push(i);
+          }
+        }
+      }
+    }
+
+    let mut pairs = BTreeSet::new();
+    for members in blocks.values() {
+      for (n, &i) in members.iter().enumerate() {
+        for &j in &members[n + 1..] {
+          if i != j {
+            pairs.insert((i.min(j), i.max(j)));
+          }
+        }
+      }
+    }
+
+    let records: Vec<&Record> = records.values().collect();
+    let mut links: Vec<Link> = pairs
+      .into_iter()
+      .filter_map(|(i, j)| {
+        let score = self.score(records[i], records[j]);
+        (score >= self.threshold).then(|| Link {
+          a: node(ids[i]),
+          b: node(ids[j]),
+          score,
+        })
+      })
+      .collect();
+    links.sort_by(|x, y| y.score.total_cmp(&x.score));
+    links
+  }
+
+  fn score(&self, a: &Record, b: &Record) -> f64 {
+    let shares_identifier = self.identifiers.iter().any(|predicate| {
+      match (a.get(predicate), b.get(predicate)) {
+        (Some(x), Some(y)) => x
+          .iter()
+          .any(|x| y.iter().any(|y| identifier(x) == identifier(y))),
+        _ => false,
+      }
+    });
+    if shares_identifier {
+      return 1.0;
+    }
+
+    let (mut total, mut weights) = (0.0, 0.0);
+    for (predicate, weight) in &self.fields {
+      if let (Some(x), Some(y)) = (a.get(predicate), b.get(predicate)) {
+        let similarity = x
+          .iter()
+          .flat_map(|x| y.iter().map(move |y| jaro_winkler(x, y)))
+          .fold(0.0, f64::max);
+        total += weight * similarity;
+        weights += weight;
+      }
+    }
+    if weights > 0.0 {
+      total / weights
+    } else {
+      0.0
+    }
+  }
+}
+
+/// Inserts an `owl:sameAs` statement for every link into `graph`.
+pub fn apply(graph: &mut KnowledgeGraph, links: &[Link]) {
+  for link in links {
+    graph.insert(
+      link.a.clone(),
+      Predicate::Literal(OWL_SAME_AS.to_string()),
+      link.b.clone(),
+    );
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Records
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Normalized values of an entity by predicate.
+type Record = HashMap<String, Vec<String>>;
+
+/// Collects the records of every named (IRI or labelled blank) subject,
+/// keyed by IRI.
+fn records(graph: &KnowledgeGraph) -> BTreeMap<String, Record> {
+  let mut records: BTreeMap<String, Record> = BTreeMap::new();
+  for triple in graph.triples() {
+    let subject = match triple.source() {
+      node @ (Node::Http(_) | Node::BlankId(_)) => node.to_string(),
+      _ => continue,
+    };
+    let values = records
+      .entry(subject)
+      .or_default()
+      .entry(triple.predicate().to_string())
+      .or_default();
+    collect(triple.destination(), values);
+  }
+  records
+}
+
+fn collect(node: &Node, values: &mut Vec<String>) {
+  match node {
+    Node::Multiple(nodes) => nodes.iter().for_each(|n| collect(n, values)),
+    Node::Literal(value) => literal(value, values),
+    Node::Blank => {}
+    node => values.push(node.to_string()),
+  }
+}
+
+/// Collects the normalized, non-empty strings of a literal, looking into
+/// arrays & `{"@value": ...}` objects.
+fn literal(value: &DType, values: &mut Vec<String>) {
+  match value {
+    DType::Null => {}
+    DType::Array(items) => items.iter().for_each(|item| literal(item, values)),
+    DType::Object(map) => {
+      if let Some(value) = map.get("@value") {
+        literal(value, values)
+      }
+    }
+    value => {
+      let value = normalize(&value.to_string());
+      if !value.is_empty() {
+        values.push(value);
+      }
+    }
+  }
+}
+
+/// Lowercases `s`, drops punctuation & collapses whitespace.
+fn normalize(s: &str) -> String {
+  s.chars()
+    .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+    .flat_map(char::to_lowercase)
+    .collect::<String>()
+    .split_whitespace()
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+/// Drops the whitespace left in a normalized identifier, so `978-0441` &
+/// `978 0441` match.
+fn identifier(value: &str) -> String {
+  value.split_whitespace().collect()
+}
+
+fn node(id: &str) -> Node {
+  match id.strip_prefix("_:") {
+    Some(label) => Node::BlankId(label.to_string()),
+    None => Node::Http(id.to_string()),
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Similarity
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Jaro-Winkler similarity of `a` & `b`, between `0.0` & `1.0`.
+fn jaro_winkler(a: &str, b: &str) -> f64 {
+  let jaro = jaro(a, b);
+  let prefix = a
+    .chars()
+    .zip(b.chars())
+    .take(4)
+    .take_while(|(x, y)| x == y)
+    .count();
+  jaro + prefix as f64 * 0.1 * (1.0 - jaro)
+}
+
+fn jaro(a: &str, b: &str) -> f64 {
+  let (a, b): (Vec<char>, Vec<char>) =
+    (a.chars().collect(), b.chars().collect());
+  if a.is_empty() && b.is_empty() {
+    return 1.0;
+  }
+  if a.is_empty() || b.is_empty() {
+    return 0.0;
+  }
+
+  let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+  let mut matched = vec![false; b.len()];
+  let mut matches_a = Vec::new();
+  for (i, x) in a.iter().enumerate() {
+    let start = i.saturating_sub(window);
+    let end = (i + window + 1).min(b.len());
+    for j in start..end {
+      if !matched[j] && b[j] == *x {
+        matched[j] = true;
+        matches_a.push(*x);
+        break;
+      }
+    }
+  }
+  if matches_a.is_empty() {
+    return 0.0;
+  }
+
+  let matches_b = b.iter().zip(&matched).filter(|(_, m)| **m).map(|(c, _)| *c);
+  let transpositions = matches_a
+    .iter()
+    .zip(matches_b)
+    .filter(|(x, y)| **x != *y)
+    .count()
+    / 2;
+  let m = matches_a.len() as f64;
+  (m / a.len() as f64 + m / b.len() as f64 + (m - transpositions as f64) / m)
+    / 3.0
+}
@@ -0,0 +1,269 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::load` loads large dumps split over many files, much faster than
+//! reading & inserting them one statement at a time.
+//!
+//! Files are parsed in parallel, each into its own table of interned terms.
+//! A final single-threaded step merges the tables, drops duplicate
+//! statements and inserts the rest (building the graph's indexes) in a
+//! single batch.
+
+use std::{
+  collections::{HashMap, HashSet},
+  fs::File,
+  io::{BufRead, BufReader},
+  path::Path,
+  sync::atomic::{AtomicUsize, Ordering},
+  thread,
+};
+
+use crate::{
+  datastore::json,
+  error::Error,
+  formats,
+  graph::{KnowledgeGraph, Node, Predicate},
+  Result,
+};
+
+/// `Format` is the serialization of the files given to `bulk_load`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+  /// [N-Triples](https://www.w3.org/TR/n-triples/), one statement per line.
+  NTriples,
+}
+
+/// `LoadOptions` configures `bulk_load`.
+#[derive(Clone, Debug)]
+pub struct LoadOptions {
+  threads: usize,
+  deduplicate: bool,
+  share_blank_nodes: bool,
+}
+
+impl Default for LoadOptions {
+  fn default() -> Self {
+    LoadOptions {
+      threads: thread::available_parallelism().map_or(1, |n| n.get()),
+      deduplicate: true,
+      share_blank_nodes: false,
+    }
+  }
+}
+
+impl LoadOptions {
+  /// Creates options parsing on one thread per available CPU, dropping
+  /// duplicate statements and scoping blank nodes to their file.
+  pub fn new() -> LoadOptions {
+    LoadOptions::default()
+  }
+
+  /// Sets the number of files parsed at once.
+  pub fn threads(mut self, threads: usize) -> Self {
+    self.threads = threads.max(1);
+    self
+  }
+
+  /// Sets whether statements occurring more than once (within or across
+  /// files) are loaded once.
+  pub fn deduplicate(mut self, deduplicate: bool) -> Self {
+    self.deduplicate = deduplicate;
+    self
+  }
+
+  /// Sets whether equal blank node labels in different files denote the
+  /// same node. Otherwise labels are prefixed with their file's position,
+  /// e.g. `_:b0` of the second file becomes `_:f1_b0`.
+  pub fn share_blank_nodes(mut self, share: bool) -> Self {
+    self.share_blank_nodes = share;
+    self
+  }
+}
+
+/// Statements of a file, as indices into its table of terms.
+#[derive(Default)]
+struct Parsed {
+  terms: Vec<Node>,
+  statements: Vec<(usize, usize, usize)>,
+}
+
+/// Interned terms, keyed by `key`.
+#[derive(Default)]
+struct Interner {
+  ids: HashMap<String, usize>,
+  terms: Vec<Node>,
+}
+
+impl Interner {
+  fn intern(&mut self, node: Node) -> usize {
+    let terms = &mut self.terms;
+    *self.ids.entry(key(&node)).or_insert_with(|| {
+      terms.push(node);
+      terms.len() - 1
+    })
+  }
+}
+
+/// Loads every file of `paths` into a new `KnowledgeGraph`.
+///
+/// Statements are inserted in the order of `paths`, and in file order
+/// within each file. The first error (in the order of `paths`) is returned,
+/// with the line it occurred on. With the `compression` feature, gzip &
+/// Zstandard compressed files are decompressed on the fly.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::load::{bulk_load, Format, LoadOptions};
+///
+/// let dir = std::env::temp_dir().join("sage-bulk-load-example");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// let people = dir.join("people.nt");
+/// let places = dir.join("places.nt");
+/// std::fs::write(
+///   &people,
+///   "<https://example.com/Ada> <https://schema.org/name> \"Ada\" .\n\
+///    <https://example.com/Ada> <https://schema.org/address> _:b0 .\n",
+/// )
+/// .unwrap();
+/// std::fs::write(
+///   &places,
+///   "<https://example.com/Ada> <https://schema.org/name> \"Ada\" .\n\
+///    _:b0 <https://schema.org/name> \"London\" .\n",
+/// )
+/// .unwrap();
+///
+/// let graph =
+///   bulk_load(&[&people, &places], Format::NTriples, &LoadOptions::new())
+///     .unwrap();
+/// // The duplicate name is loaded once, `_:b0` are distinct nodes.
+/// assert_eq!(graph.len(), 3);
+/// assert_eq!(graph.triples().last().unwrap().source().to_string(), "_:f1_b0");
+///
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+pub fn bulk_load<P: AsRef<Path> + Sync>(
+  paths: &[P],
+  format: Format,
+  opts: &LoadOptions,
+) -> Result<KnowledgeGraph> {
+  let next = AtomicUsize::new(0);
+  let mut parsed: Vec<(usize, Result<Parsed>)> = thread::scope(|scope| {
+    let workers: Vec<_> = (0..opts.threads.min(paths.len()))
+      .map(|_| {
+        scope.spawn(|| {
+          let mut done = Vec::new();
+          loop {
+            let idx = next.fetch_add(1, Ordering::Relaxed);
+            let path = match paths.get(idx) {
+              Some(path) => path.as_ref(),
+              None => return done,
+            };
+            let scope = (!opts.share_blank_nodes).then_some(idx);
+            done.push((idx, parse(path, format, scope)));
+          }
+        })
+      })
+      .collect();
+    workers
+      .into_iter()
+      .flat_map(|worker| worker.join().expect("bulk load worker panicked"))
+      .collect()
+  });
+  parsed.sort_by_key(|(idx, _)| *idx);
+
+  // Merge every file's terms into one table.
+  let mut interner = Interner::default();
+  let mut seen = HashSet::new();
+  let mut statements = Vec::new();
+  for (_, file) in parsed {
+    let file = file?;
+    let ids: Vec<usize> = file
+      .terms
+      .into_iter()
+      .map(|term| interner.intern(term))
+      .collect();
+    for (s, p, o) in file.statements {
+      let statement = (ids[s], ids[p], ids[o]);
+      if !opts.deduplicate || seen.insert(statement) {
+        statements.push(statement);
+      }
+    }
+  }
+
+  let terms = interner.terms;
+  let mut graph = KnowledgeGraph::new();
+  graph.batch(|graph| {
+    for (s, p, o) in statements {
+      let predicate = match &terms[p] {
+        Node::Http(iri) => Predicate::Literal(iri.clone()),
+        node => Predicate::Literal(node.to_string()),
+      };
+      graph.insert(terms[s].clone(), predicate, terms[o].clone());
+    }
+  });
+  Ok(graph)
+}
+
+/// Parses the file at `path`, prefixing blank node labels with `scope`.
+fn parse(path: &Path, format: Format, scope: Option<usize>) -> Result<Parsed> {
+  let reader = BufReader::new(File::open(path).map_err(Error::io)?);
+  #[cfg(feature = "compression")]
+  let reader = crate::compression::Decoder::new(reader).map_err(Error::io)?;
+  let mut interner = Interner::default();
+  let mut statements = Vec::new();
+
+  match format {
+    Format::NTriples => {
+      for (idx, line) in reader.lines().enumerate() {
+        let line = line.map_err(Error::io)?;
+        let (subject, predicate, object) =
+          match formats::parse_line(&line, idx + 1)? {
+            Some(statement) => statement,
+            None => continue,
+          };
+        statements.push((
+          interner.intern(scoped(subject, scope)),
+          interner.intern(Node::Http(predicate)),
+          interner.intern(scoped(object, scope)),
+        ));
+      }
+    }
+  }
+
+  Ok(Parsed {
+    terms: interner.terms,
+    statements,
+  })
+}
+
+fn scoped(node: Node, scope: Option<usize>) -> Node {
+  match (node, scope) {
+    (Node::BlankId(label), Some(idx)) => {
+      Node::BlankId(format!("f{}_{}", idx, label))
+    }
+    (node, _) => node,
+  }
+}
+
+/// Identifies a term: literals by their JSON, other nodes by their IRI or
+/// blank node label.
+fn key(node: &Node) -> String {
+  match node {
+    Node::Literal(value) => {
+      format!("\"{}", json::to_string(value).unwrap_or_default())
+    }
+    node => node.to_string(),
+  }
+}
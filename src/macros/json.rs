@@ -36,6 +36,25 @@ macro_rules! json {
   }
 }
 
+/// Construct a [`sage::DType`] from a JSON literal. Alias of `json!`.
+///
+/// ```rust
+/// # use sage::dtype;
+/// #
+/// let mut value = dtype!({});
+/// value["person"]["aliases"][0] = dtype!("Ada");
+///
+/// assert_eq!(value, dtype!({ "person": { "aliases": ["Ada"] } }));
+/// ```
+///
+/// [`sage::DType`]: struct crate::DType.html
+#[macro_export(local_inner_macros)]
+macro_rules! dtype {
+  ($($json:tt)+) => {
+    json_internal!($($json)+)
+  }
+}
+
 #[macro_export(local_inner_macros)]
 #[doc(hidden)]
 macro_rules! json_internal {
@@ -224,11 +243,13 @@ macro_rules! json_internal {
   };
 
   ([]) => {
-    $crate::DType::Array(json_internal_vec![])
+    $crate::DType::Array(::std::boxed::Box::new(json_internal_vec![]))
   };
 
   ([ $($tt:tt)+ ]) => {
-    $crate::DType::Array(json_internal!(@array [] $($tt)+))
+    $crate::DType::Array(::std::boxed::Box::new(
+      json_internal!(@array [] $($tt)+)
+    ))
   };
 
   ({}) => {
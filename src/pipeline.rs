@@ -0,0 +1,278 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::pipeline` assembles ETL jobs out of stages: a `Source` reads
+//! batches of triples, `Stage`s transform them, `Validator`s check them and
+//! `Sink`s write them.
+//!
+//! Built-in stages wrap what Sage already offers:
+//!
+//! - Sources: `Reader` parses N-Triples, Turtle, JSON-LD or RDF/XML,
+//!   `Documents` maps JSON documents to triples with a `Transform` or a
+//!   `Mapping`, and `Csv` maps CSV records with a `Mapping`.
+//! - Stages: `Rewriter` rewrites identifiers; closures taking a `Triple`
+//!   may change or drop it.
+//! - Validators: `Ontology` rejects undeclared predicates; closures taking a
+//!   `&Triple` may reject it.
+//! - Sinks: `KnowledgeGraph` (checking its ontology & `Constraints`),
+//!   `GraphWriter` & `NQuadsWriter`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use sage::graph::KnowledgeGraph;
+//! use sage::iri::Rewriter;
+//! use sage::pipeline::{Format, Pipeline, Reader};
+//! use sage::schema::Ontology;
+//!
+//! let input = "<http://old.example/Ada> <https://schema.org/name> \"Ada\" .\n\
+//!   <http://old.example/Ada> <https://example.com/nickname> \"Ada\" .\n";
+//!
+//! let mut ontology = Ontology::new().namespace("https://schema.org/");
+//! let mut graph = KnowledgeGraph::new();
+//! let mut batches = 0;
+//! let metrics = Pipeline::new(Reader::new(input.as_bytes(), Format::NTriples))
+//!   .transform(Rewriter::new().prefix("http://old.example/", "urn:person:"))
+//!   .validate(&mut ontology)
+//!   .skip_invalid(true)
+//!   .sink(&mut graph)
+//!   .on_batch(|_| batches += 1)
+//!   .run()
+//!   .unwrap();
+//!
+//! assert_eq!((metrics.read, metrics.invalid, metrics.written), (2, 1, 1));
+//! assert_eq!(batches, 1);
+//! let subject = graph.triples().next().unwrap().source().to_string();
+//! assert_eq!(subject, "urn:person:Ada");
+//! ```
+
+use std::time::{Duration, Instant};
+
+use crate::{graph::Triple, iri::Rewriter, schema::Ontology, Result};
+
+mod mapping;
+mod sink;
+mod source;
+
+pub use mapping::{Csv, Mapping};
+pub use sink::Sink;
+pub use source::{Documents, Format, Reader, Source};
+
+#[cfg(feature = "kafka")]
+pub(crate) use source::triples;
+
+/// A callback given the metrics so far after every batch.
+type Hook<'a> = Box<dyn FnMut(&Metrics) + 'a>;
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Stages
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Stage` transforms the triples flowing through a `Pipeline`.
+pub trait Stage {
+  /// Returns the transformed `triple`, or `None` to drop it.
+  fn apply(&mut self, triple: Triple) -> Result<Option<Triple>>;
+}
+
+impl<F> Stage for F
+where
+  F: FnMut(Triple) -> Result<Option<Triple>>,
+{
+  fn apply(&mut self, triple: Triple) -> Result<Option<Triple>> {
+    self(triple)
+  }
+}
+
+impl Stage for Rewriter {
+  fn apply(&mut self, triple: Triple) -> Result<Option<Triple>> {
+    Ok(Some(self.rewrite_triple(&triple)))
+  }
+}
+
+/// `Validator` checks the triples flowing through a `Pipeline`, after
+/// they've been transformed.
+pub trait Validator {
+  /// Fails if `triple` is invalid.
+  fn validate(&mut self, triple: &Triple) -> Result<()>;
+}
+
+impl<F> Validator for F
+where
+  F: FnMut(&Triple) -> Result<()>,
+{
+  fn validate(&mut self, triple: &Triple) -> Result<()> {
+    self(triple)
+  }
+}
+
+/// Rejects triples with an undeclared predicate in
+/// `ValidationMode::Strict`, and records them in `ValidationMode::Observe`.
+impl Validator for &mut Ontology {
+  fn validate(&mut self, triple: &Triple) -> Result<()> {
+    self.check(triple)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Metrics
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Metrics` count the triples a `Pipeline` processed so far.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+  /// Batches read from the source.
+  pub batches: usize,
+  /// Triples read from the source.
+  pub read: usize,
+  /// Triples dropped by a stage.
+  pub dropped: usize,
+  /// Triples rejected by a validator & skipped.
+  pub invalid: usize,
+  /// Triples written to the sinks.
+  pub written: usize,
+  /// Time spent since the pipeline started.
+  pub elapsed: Duration,
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Pipeline
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Pipeline` moves triples from a `Source` to `Sink`s, batch by batch,
+/// see the module documentation.
+///
+/// Every triple goes through the stages, then the validators, in the order
+/// they were added. Every sink receives every valid triple.
+pub struct Pipeline<'a> {
+  source: Box<dyn Source + 'a>,
+  stages: Vec<Box<dyn Stage + 'a>>,
+  validators: Vec<Box<dyn Validator + 'a>>,
+  sinks: Vec<Box<dyn Sink + 'a>>,
+  hooks: Vec<Hook<'a>>,
+  skip_invalid: bool,
+}
+
+impl<'a> Pipeline<'a> {
+  /// Creates a pipeline reading from `source`, without stages or sinks.
+  pub fn new(source: impl Source + 'a) -> Pipeline<'a> {
+    Pipeline {
+      source: Box::new(source),
+      stages: Vec::new(),
+      validators: Vec::new(),
+      sinks: Vec::new(),
+      hooks: Vec::new(),
+      skip_invalid: false,
+    }
+  }
+
+  /// Adds a transformation stage.
+  pub fn transform(mut self, stage: impl Stage + 'a) -> Self {
+    self.stages.push(Box::new(stage));
+    self
+  }
+
+  /// Adds a validator.
+  pub fn validate(mut self, validator: impl Validator + 'a) -> Self {
+    self.validators.push(Box::new(validator));
+    self
+  }
+
+  /// Adds a sink.
+  pub fn sink(mut self, sink: impl Sink + 'a) -> Self {
+    self.sinks.push(Box::new(sink));
+    self
+  }
+
+  /// Sets whether triples rejected by a validator are skipped & counted,
+  /// instead of failing the pipeline.
+  pub fn skip_invalid(mut self, skip: bool) -> Self {
+    self.skip_invalid = skip;
+    self
+  }
+
+  /// Calls `hook` with the metrics so far after every batch, e.g. to
+  /// report progress or export them.
+  pub fn on_batch(mut self, hook: impl FnMut(&Metrics) + 'a) -> Self {
+    self.hooks.push(Box::new(hook));
+    self
+  }
+
+  /// Runs the pipeline until the source is exhausted, then flushes the
+  /// sinks, returning the final metrics.
+  ///
+  /// # Errors
+  ///
+  /// Fails with the first error of the source, a stage, a validator (unless
+  /// invalid triples are skipped) or a sink. Batches written before then
+  /// stay written.
+  pub fn run(mut self) -> Result<Metrics> {
+    let start = Instant::now();
+    let mut metrics = Metrics::default();
+    while let Some(batch) = self.source.read()? {
+      metrics.batches += 1;
+      metrics.read += batch.len();
+
+      let mut valid = Vec::with_capacity(batch.len());
+      'triples: for mut triple in batch {
+        for stage in &mut self.stages {
+          triple = match stage.apply(triple)? {
+            Some(triple) => triple,
+            None => {
+              metrics.dropped += 1;
+              continue 'triples;
+            }
+          };
+        }
+        for validator in &mut self.validators {
+          match validator.validate(&triple) {
+            Ok(()) => {}
+            Err(_) if self.skip_invalid => {
+              metrics.invalid += 1;
+              continue 'triples;
+            }
+            Err(e) => return Err(e),
+          }
+        }
+        valid.push(triple);
+      }
+
+      for sink in &mut self.sinks {
+        sink.write(&valid)?;
+      }
+      metrics.written += valid.len();
+      metrics.elapsed = start.elapsed();
+      for hook in &mut self.hooks {
+        hook(&metrics);
+      }
+    }
+
+    for sink in &mut self.sinks {
+      sink.flush()?;
+    }
+    metrics.elapsed = start.elapsed();
+    Ok(metrics)
+  }
+}
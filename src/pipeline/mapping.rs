@@ -0,0 +1,653 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashMap, fmt::Write as _, io::BufRead};
+
+use crate::{
+  datastore::json,
+  dtype::{DType, LangString, Map},
+  error::{Error, ErrorCode},
+  formats::typed_value,
+  graph::{Node, Predicate, Triple},
+  pipeline::Source,
+  transform::Transform,
+  Result,
+};
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/// Prefixes every mapping declares.
+const PREFIXES: [(&str, &str); 3] = [
+  ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+  ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+  ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+];
+
+/// Number of CSV records per batch read by default.
+const BATCH_SIZE: usize = 1024;
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Templates
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// A string with `{field}` placeholders, e.g. `ex:people/{id}`.
+#[derive(Clone, Debug)]
+struct Template(Vec<Part>);
+
+#[derive(Clone, Debug)]
+enum Part {
+  Text(String),
+  /// Path of a field, e.g. `address.city`.
+  Field(Vec<String>),
+}
+
+impl Template {
+  fn parse(s: &str) -> Result<Template> {
+    let mut parts = Vec::new();
+    let mut rest = s;
+    while let Some(start) = rest.find('{') {
+      if start > 0 {
+        parts.push(Part::Text(rest[..start].to_string()));
+      }
+      let end = start + rest[start..].find('}').ok_or_else(invalid)?;
+      let field = &rest[start + 1..end];
+      if field.is_empty() || field.contains('{') {
+        return Err(invalid());
+      }
+      parts.push(Part::Field(field.split('.').map(String::from).collect()));
+      rest = &rest[end + 1..];
+    }
+    if rest.contains('}') {
+      return Err(invalid());
+    }
+    if !rest.is_empty() {
+      parts.push(Part::Text(rest.to_string()));
+    }
+    Ok(Template(parts))
+  }
+
+  /// Fills in the fields of `record`, percent-encoding them in IRIs.
+  /// Returns `None` if a field is missing.
+  fn render(&self, record: &DType, iri: bool) -> Option<String> {
+    let mut out = String::new();
+    for part in &self.0 {
+      match part {
+        Part::Text(text) => out.push_str(text),
+        Part::Field(path) => {
+          let value = lexical(field(record, path)?);
+          if iri {
+            encode(&mut out, &value);
+          } else {
+            out.push_str(&value);
+          }
+        }
+      }
+    }
+    Some(out)
+  }
+}
+
+/// Returns the non-null value at `path` in `record`.
+fn field<'a>(record: &'a DType, path: &[String]) -> Option<&'a DType> {
+  let mut value = record;
+  for key in path {
+    value = value.as_object()?.get(key.as_str())?;
+  }
+  (!value.is_null()).then_some(value)
+}
+
+/// Returns the lexical form of `value`: strings as is, other values as
+/// JSON.
+fn lexical(value: &DType) -> String {
+  match value {
+    DType::String(s) => s.clone(),
+    DType::Number(n) => n.to_string(),
+    value => json::to_string(value).unwrap_or_default(),
+  }
+}
+
+/// Appends `s` percent-encoding every byte outside of the unreserved URI
+/// characters.
+fn encode(out: &mut String, s: &str) {
+  for byte in s.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+        out.push(byte as char)
+      }
+      byte => {
+        let _ = write!(out, "%{:02X}", byte);
+      }
+    }
+  }
+}
+
+/// Returns the IRI or `_:label` blank node `s` stands for.
+fn node(s: String) -> Node {
+  match s.strip_prefix("_:") {
+    Some(label) => Node::BlankId(label.to_string()),
+    None => Node::Http(s),
+  }
+}
+
+fn invalid() -> Error {
+  Error::syntax(ErrorCode::InvalidMapping, 0, 0)
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Objects
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Where the objects of a property come from.
+#[derive(Clone, Debug)]
+enum Value {
+  /// A field, keeping its JSON type. Arrays make an object per element.
+  Field(Vec<String>),
+  Template(Template),
+  Constant(DType),
+}
+
+/// How the objects of a property are made.
+#[derive(Clone, Debug)]
+struct Object {
+  value: Value,
+  /// `true` for IRIs (or blank nodes), `false` for literals.
+  iri: bool,
+  /// Full IRI of the datatype of literals.
+  datatype: Option<String>,
+  language: Option<String>,
+}
+
+impl Object {
+  /// Returns the objects made from `record`.
+  fn nodes(&self, record: &DType) -> Vec<Node> {
+    let values: Vec<DType> = match &self.value {
+      Value::Field(path) => match field(record, path) {
+        Some(DType::Array(items)) => {
+          items.iter().filter(|v| !v.is_null()).cloned().collect()
+        }
+        Some(value) => vec![value.clone()],
+        None => Vec::new(),
+      },
+      Value::Template(template) => template
+        .render(record, self.iri)
+        .map(DType::String)
+        .into_iter()
+        .collect(),
+      Value::Constant(value) => vec![value.clone()],
+    };
+
+    values
+      .into_iter()
+      .map(|value| {
+        if self.iri {
+          return node(lexical(&value));
+        }
+        Node::Literal(match (&self.datatype, &self.language) {
+          (Some(datatype), _) => typed_value(lexical(&value), datatype),
+          (None, Some(language)) => LangString::new(&lexical(&value), language)
+            .map(DType::from)
+            .unwrap_or(value),
+          (None, None) => value,
+        })
+      })
+      .collect()
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Mapping
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Mapping` describes how the fields of CSV or JSON records become
+/// subjects, predicates & typed literals, like a subset of [RML].
+///
+/// A mapping is a JSON object:
+///
+/// - `prefixes` (optional) declares prefixes for the IRIs below, besides
+///   `rdf`, `rdfs` & `xsd`.
+/// - `iterator` (optional, JSON only) is a `Transform` selecting the
+///   records of a document, e.g. `.results`. Documents are records
+///   otherwise, and every element of an array is a record.
+/// - `subject` is the template of the subjects' IRI: `{field}` is replaced
+///   by the percent-encoded value of a field of the record, `{a.b}` by
+///   that of a nested field. Templates starting with `_:` make blank nodes.
+/// - `classes` (optional) is the `rdf:type` (or the array of types) of
+///   every subject.
+/// - `properties` maps predicates to objects (or arrays of objects). A
+///   string is the template of a plain literal, otherwise an object has
+///   one of `field` (keeping JSON types), `template` or `constant`, and
+///   optionally `"type": "iri"` for IRIs, a `datatype` or a `language`.
+///
+/// Records missing a field of the subject are skipped, as are the objects
+/// missing a field. CSV fields are strings, empty ones are missing: use
+/// `datatype` to type them.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node};
+/// use sage::pipeline::{Csv, Mapping, Pipeline};
+///
+/// let mapping = Mapping::parse(
+///   r#"{
+///     "prefixes": {
+///       "ex": "https://example.com/",
+///       "schema": "https://schema.org/"
+///     },
+///     "subject": "ex:people/{id}",
+///     "classes": "schema:Person",
+///     "properties": {
+///       "schema:name": "{first} {last}",
+///       "schema:birthDate": { "field": "born", "datatype": "xsd:integer" },
+///       "schema:knows": { "template": "ex:people/{friend}", "type": "iri" }
+///     }
+///   }"#,
+/// )
+/// .unwrap();
+///
+/// let csv = "id,first,last,born,friend\n\
+///            1,Ada,Lovelace,1815,2\n\
+///            2,Charles,Babbage,1791,\n";
+/// let mut graph = KnowledgeGraph::new();
+/// Pipeline::new(Csv::new(csv.as_bytes(), mapping))
+///   .sink(&mut graph)
+///   .run()
+///   .unwrap();
+///
+/// // Charles has no friend.
+/// assert_eq!(graph.len(), 7);
+/// let ada = Node::Http("https://example.com/people/1".to_string());
+/// let born = "https://schema.org/birthDate";
+/// let year = graph.matches(Some(&ada), Some(born), None).next().unwrap();
+/// assert_eq!(year.destination(), &Node::Literal(1815.into()));
+/// ```
+///
+/// [RML]: https://rml.io/specs/rml/
+#[derive(Clone, Debug)]
+pub struct Mapping {
+  iterator: Option<Transform>,
+  subject: Template,
+  /// Full IRIs of the classes of every subject.
+  classes: Vec<String>,
+  /// Full IRIs of the predicates & their objects.
+  properties: Vec<(String, Object)>,
+}
+
+impl Mapping {
+  /// Parses a mapping written in JSON.
+  ///
+  /// # Errors
+  ///
+  /// Fails with an `InvalidMapping` error if the mapping is malformed, e.g.
+  /// has an unknown key or an unbalanced template.
+  pub fn parse(s: &str) -> Result<Mapping> {
+    Mapping::from_dtype(&json::from_str(s)?)
+  }
+
+  /// Reads a mapping from its JSON value, see `Mapping::parse`.
+  pub fn from_dtype(spec: &DType) -> Result<Mapping> {
+    let spec = spec.as_object().ok_or_else(invalid)?;
+    let mut prefixes: HashMap<String, String> = PREFIXES
+      .iter()
+      .map(|(prefix, ns)| (prefix.to_string(), ns.to_string()))
+      .collect();
+    if let Some(declared) = spec.get("prefixes") {
+      for (prefix, ns) in declared.as_object().ok_or_else(invalid)? {
+        let ns = ns.as_str().ok_or_else(invalid)?;
+        prefixes.insert(prefix.clone(), ns.to_string());
+      }
+    }
+    let expand = |s: &str| expand(&prefixes, s);
+
+    let mut mapping = Mapping {
+      iterator: None,
+      subject: Template(Vec::new()),
+      classes: Vec::new(),
+      properties: Vec::new(),
+    };
+    let mut subject = false;
+    for (key, value) in spec {
+      match key.as_str() {
+        "prefixes" => {}
+        "iterator" => {
+          let source = value.as_str().ok_or_else(invalid)?;
+          mapping.iterator = Some(Transform::compile(source)?);
+        }
+        "subject" => {
+          let template = value.as_str().ok_or_else(invalid)?;
+          mapping.subject = Template::parse(&expand(template))?;
+          subject = true;
+        }
+        "classes" => {
+          for class in one_or_many(value) {
+            let class = class.as_str().ok_or_else(invalid)?;
+            mapping.classes.push(expand(class));
+          }
+        }
+        "properties" => {
+          for (predicate, objects) in value.as_object().ok_or_else(invalid)? {
+            let predicate = expand(predicate);
+            for object in one_or_many(objects) {
+              let object = Mapping::object(object, &expand)?;
+              mapping.properties.push((predicate.clone(), object));
+            }
+          }
+        }
+        _ => return Err(invalid()),
+      }
+    }
+    if !subject {
+      return Err(invalid());
+    }
+    Ok(mapping)
+  }
+
+  /// Reads the object of a property.
+  fn object(spec: &DType, expand: &dyn Fn(&str) -> String) -> Result<Object> {
+    if let Some(template) = spec.as_str() {
+      return Ok(Object {
+        value: Value::Template(Template::parse(template)?),
+        iri: false,
+        datatype: None,
+        language: None,
+      });
+    }
+
+    let spec = spec.as_object().ok_or_else(invalid)?;
+    let mut object = Object {
+      value: Value::Constant(DType::Null),
+      iri: false,
+      datatype: None,
+      language: None,
+    };
+    let mut values = 0;
+    for (key, value) in spec {
+      let text = value.as_str();
+      match key.as_str() {
+        "field" => {
+          let path = text.ok_or_else(invalid)?;
+          object.value =
+            Value::Field(path.split('.').map(String::from).collect());
+          values += 1;
+        }
+        "template" => {
+          let template = text.ok_or_else(invalid)?;
+          object.value = Value::Template(Template::parse(template)?);
+          values += 1;
+        }
+        "constant" => {
+          object.value = Value::Constant(value.clone());
+          values += 1;
+        }
+        "type" => match text {
+          Some("iri") => object.iri = true,
+          Some("literal") => object.iri = false,
+          _ => return Err(invalid()),
+        },
+        "datatype" => {
+          object.datatype = Some(expand(text.ok_or_else(invalid)?));
+        }
+        "language" => {
+          let language = text.ok_or_else(invalid)?;
+          LangString::new("", language)?;
+          object.language = Some(language.to_string());
+        }
+        _ => return Err(invalid()),
+      }
+    }
+
+    let literal = object.datatype.is_some() || object.language.is_some();
+    if values != 1 || (object.iri && literal) {
+      return Err(invalid());
+    }
+    // Prefixed names of IRIs are expanded before their fields are filled.
+    if let (true, Value::Template(template)) = (object.iri, &mut object.value) {
+      if let Some(Part::Text(text)) = template.0.first_mut() {
+        *text = expand(text);
+      }
+    }
+    if let (true, Value::Constant(constant)) = (object.iri, &object.value) {
+      let iri = constant.as_str().ok_or_else(invalid)?;
+      object.value = Value::Constant(DType::String(expand(iri)));
+    }
+    Ok(object)
+  }
+
+  /// Maps the records of `document` to triples, in order.
+  ///
+  /// # Errors
+  ///
+  /// Fails if the `iterator` can't be applied to `document`.
+  pub fn apply(&self, document: &DType) -> Result<Vec<Triple>> {
+    let selected;
+    let records = match &self.iterator {
+      Some(iterator) => {
+        selected = iterator.apply(document)?;
+        &selected
+      }
+      None => document,
+    };
+
+    let mut triples = Vec::new();
+    match records {
+      DType::Array(records) => {
+        for record in records.iter() {
+          self.record(record, &mut triples);
+        }
+      }
+      record => self.record(record, &mut triples),
+    }
+    Ok(triples)
+  }
+
+  /// Maps a single record to triples.
+  fn record(&self, record: &DType, triples: &mut Vec<Triple>) {
+    let subject = match self.subject.render(record, true) {
+      Some(subject) => node(subject),
+      None => return,
+    };
+    for class in &self.classes {
+      triples.push(Triple::from_nodes(
+        subject.clone(),
+        Predicate::Literal(RDF_TYPE.to_string()),
+        Node::Http(class.clone()),
+      ));
+    }
+    for (predicate, object) in &self.properties {
+      for node in object.nodes(record) {
+        let predicate = Predicate::Literal(predicate.clone());
+        triples.push(Triple::from_nodes(subject.clone(), predicate, node));
+      }
+    }
+  }
+}
+
+/// Returns the elements of `value` if it's an array, or `value`.
+fn one_or_many(value: &DType) -> Vec<&DType> {
+  match value {
+    DType::Array(values) => values.iter().collect(),
+    value => vec![value],
+  }
+}
+
+/// Expands the prefix of the prefixed name `s`. Other IRIs (like
+/// `https://...` or `urn:...`) are kept as is.
+fn expand(prefixes: &HashMap<String, String>, s: &str) -> String {
+  match s.split_once(':') {
+    Some((prefix, local)) if prefixes.contains_key(prefix) => {
+      format!("{}{}", prefixes[prefix], local)
+    }
+    _ => s.to_string(),
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Csv
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Csv` maps the records of a [CSV] document to triples with a `Mapping`,
+/// streaming them in batches.
+///
+/// The first record is the header naming the fields. Fields may be quoted
+/// with `"` (doubled inside quotes), to hold delimiters or line breaks.
+///
+/// [CSV]: https://www.rfc-editor.org/rfc/rfc4180
+pub struct Csv<R: BufRead> {
+  reader: Option<R>,
+  mapping: Mapping,
+  delimiter: char,
+  batch_size: usize,
+  header: Option<Vec<String>>,
+  /// Number of lines read so far.
+  line: usize,
+}
+
+impl<R: BufRead> Csv<R> {
+  /// Creates a source mapping the comma-separated records of `reader`.
+  pub fn new(reader: R, mapping: Mapping) -> Csv<R> {
+    Csv {
+      reader: Some(reader),
+      mapping,
+      delimiter: ',',
+      batch_size: BATCH_SIZE,
+      header: None,
+      line: 0,
+    }
+  }
+
+  /// Sets the field delimiter, e.g. `\t` for TSV.
+  pub fn delimiter(mut self, delimiter: char) -> Self {
+    self.delimiter = delimiter;
+    self
+  }
+
+  /// Sets the number of records per batch.
+  pub fn batch_size(mut self, batch_size: usize) -> Self {
+    self.batch_size = batch_size.max(1);
+    self
+  }
+
+  /// Reads the fields of the next record, skipping blank lines.
+  fn fields(&mut self) -> Result<Option<Vec<String>>> {
+    let reader = match self.reader.as_mut() {
+      Some(reader) => reader,
+      None => return Ok(None),
+    };
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = String::new();
+    loop {
+      line.clear();
+      if reader.read_line(&mut line).map_err(Error::io)? == 0 {
+        self.reader = None;
+        if quoted {
+          return Err(Error::syntax(ErrorCode::InvalidCsv, self.line, 0));
+        }
+        if fields.is_empty() && field.is_empty() {
+          return Ok(None);
+        }
+        fields.push(field);
+        return Ok(Some(fields));
+      }
+      self.line += 1;
+      if !quoted && line.trim_end_matches(['\n', '\r']).is_empty() {
+        continue;
+      }
+
+      let mut chars = line.chars().peekable();
+      while let Some(c) = chars.next() {
+        match c {
+          '"' if quoted => {
+            if chars.peek() == Some(&'"') {
+              chars.next();
+              field.push('"');
+            } else {
+              quoted = false;
+            }
+          }
+          '"' if field.is_empty() => quoted = true,
+          c if quoted => field.push(c),
+          c if c == self.delimiter => fields.push(std::mem::take(&mut field)),
+          '\r' | '\n' => {
+            fields.push(field);
+            return Ok(Some(fields));
+          }
+          c => field.push(c),
+        }
+      }
+      // Quoted fields go on over the next line.
+      if !quoted {
+        fields.push(field);
+        return Ok(Some(fields));
+      }
+    }
+  }
+}
+
+impl<R: BufRead> Source for Csv<R> {
+  fn read(&mut self) -> Result<Option<Vec<Triple>>> {
+    let header = match self.header.take() {
+      Some(header) => header,
+      None => match self.fields()? {
+        Some(mut header) => {
+          if let Some(first) = header.first_mut() {
+            *first = first.trim_start_matches('\u{feff}').to_string();
+          }
+          header
+        }
+        None => return Ok(None),
+      },
+    };
+
+    let mut batch = Vec::new();
+    let mut records = 0;
+    while records < self.batch_size {
+      let fields = match self.fields()? {
+        Some(fields) => fields,
+        None => break,
+      };
+      if fields.len() != header.len() {
+        return Err(Error::syntax(ErrorCode::InvalidCsv, self.line, 0));
+      }
+      records += 1;
+
+      let mut record = Map::new();
+      for (name, value) in header.iter().zip(fields) {
+        if !value.is_empty() {
+          record.insert(name.clone(), DType::String(value));
+        }
+      }
+      self.mapping.record(&DType::Object(record), &mut batch);
+    }
+    self.header = Some(header);
+    Ok((records > 0).then_some(batch))
+  }
+}
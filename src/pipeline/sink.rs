@@ -0,0 +1,77 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+
+use crate::{
+  formats::{GraphWriter, NQuadsWriter},
+  graph::{KnowledgeGraph, Triple},
+  Result,
+};
+
+/// `Sink` receives the valid triples of a `Pipeline`.
+///
+/// Writers are passed by reference, so they can be finished once the
+/// pipeline has run.
+pub trait Sink {
+  /// Writes a batch of triples.
+  fn write(&mut self, triples: &[Triple]) -> Result<()>;
+
+  /// Called once the source is exhausted.
+  fn flush(&mut self) -> Result<()> {
+    Ok(())
+  }
+}
+
+/// Inserts triples through `KnowledgeGraph::try_add`, so they're checked
+/// against the graph's ontology & constraints. Subscribers receive one
+/// batch of mutations per batch of triples.
+impl Sink for &mut KnowledgeGraph {
+  fn write(&mut self, triples: &[Triple]) -> Result<()> {
+    self.batch(|graph| {
+      triples
+        .iter()
+        .try_for_each(|triple| graph.try_add(copy(triple)))
+    })
+  }
+}
+
+impl<W: io::Write> Sink for &mut GraphWriter<W> {
+  fn write(&mut self, triples: &[Triple]) -> Result<()> {
+    triples
+      .iter()
+      .try_for_each(|triple| GraphWriter::write(self, triple))
+  }
+}
+
+impl<W: io::Write> Sink for &mut NQuadsWriter<W> {
+  fn write(&mut self, triples: &[Triple]) -> Result<()> {
+    triples
+      .iter()
+      .try_for_each(|triple| NQuadsWriter::write(self, triple))
+  }
+}
+
+/// Returns a copy of `triple`, with its provenance.
+fn copy(triple: &Triple) -> Triple {
+  let copy = Triple::from_nodes(
+    triple.source().clone(),
+    triple.predicate().clone(),
+    triple.destination().clone(),
+  );
+  match triple.provenance() {
+    Some(provenance) => copy.with_provenance(provenance.clone()),
+    None => copy,
+  }
+}
@@ -0,0 +1,265 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{io::BufRead, vec};
+
+use crate::{
+  dtype::DType,
+  error::Error,
+  formats::{self, JsonLd, RdfXml, Turtle},
+  graph::{Node, Predicate, Triple},
+  pipeline::Mapping,
+  transform::Transform,
+  Result,
+};
+
+/// Number of triples per batch read by default.
+const BATCH_SIZE: usize = 1024;
+
+/// `Source` feeds batches of triples to a `Pipeline`.
+pub trait Source {
+  /// Returns the next batch, or `None` once exhausted.
+  fn read(&mut self) -> Result<Option<Vec<Triple>>>;
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Reader
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Format` is the serialization parsed by a `Reader`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+  /// [N-Triples](https://www.w3.org/TR/n-triples/), streamed line by line.
+  NTriples,
+  /// [Turtle](https://www.w3.org/TR/turtle/).
+  Turtle,
+  /// [JSON-LD](https://www.w3.org/TR/json-ld11/).
+  JsonLd,
+  /// [RDF/XML](https://www.w3.org/TR/rdf-syntax-grammar/).
+  RdfXml,
+}
+
+/// `Reader` parses a document into batches of triples.
+///
+/// N-Triples documents are streamed, so memory stays bounded whatever
+/// their size. Documents in the other formats are parsed in full when the
+/// first batch is read.
+pub struct Reader<R: BufRead> {
+  reader: Option<R>,
+  format: Format,
+  batch_size: usize,
+  /// N-Triples: number of lines read so far.
+  line: usize,
+  /// Other formats: triples of the parsed document, not yet read.
+  parsed: Option<vec::IntoIter<Triple>>,
+}
+
+impl<R: BufRead> Reader<R> {
+  /// Creates a source reading `format` from `reader`.
+  pub fn new(reader: R, format: Format) -> Reader<R> {
+    Reader {
+      reader: Some(reader),
+      format,
+      batch_size: BATCH_SIZE,
+      line: 0,
+      parsed: None,
+    }
+  }
+
+  /// Sets the number of triples per batch.
+  pub fn batch_size(mut self, batch_size: usize) -> Self {
+    self.batch_size = batch_size.max(1);
+    self
+  }
+
+  /// Reads the next batch of N-Triples lines.
+  fn lines(&mut self) -> Result<Option<Vec<Triple>>> {
+    let reader = match self.reader.as_mut() {
+      Some(reader) => reader,
+      None => return Ok(None),
+    };
+    let mut batch = Vec::new();
+    let mut line = String::new();
+    while batch.len() < self.batch_size {
+      line.clear();
+      if reader.read_line(&mut line).map_err(Error::io)? == 0 {
+        self.reader = None;
+        break;
+      }
+      self.line += 1;
+      let line = line.trim_end_matches(['\n', '\r']);
+      if let Some((s, p, o)) = formats::parse_line(line, self.line)? {
+        batch.push(Triple::from_nodes(s, Predicate::Literal(p), o));
+      }
+    }
+    Ok((!batch.is_empty()).then_some(batch))
+  }
+}
+
+impl<R: BufRead> Source for Reader<R> {
+  fn read(&mut self) -> Result<Option<Vec<Triple>>> {
+    if self.format == Format::NTriples {
+      return self.lines();
+    }
+
+    if let Some(reader) = self.reader.take() {
+      let graph = match self.format {
+        Format::Turtle => Turtle::from_reader(reader)?,
+        Format::JsonLd => JsonLd::from_reader(reader)?,
+        Format::RdfXml => RdfXml::from_reader(reader)?,
+        Format::NTriples => unreachable!("N-Triples are streamed"),
+      };
+      self.parsed = Some(graph.into_triples().into_iter());
+    }
+    let parsed = match self.parsed.as_mut() {
+      Some(parsed) => parsed,
+      None => return Ok(None),
+    };
+    let batch: Vec<Triple> = parsed.take(self.batch_size).collect();
+    Ok((!batch.is_empty()).then_some(batch))
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Documents
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Documents` maps JSON documents (e.g. API payloads or NDJSON records)
+/// to triples, one batch per document.
+///
+/// Every document is reshaped by the `Transform`, if any, then mapped to
+/// triples by the `Mapping` or, without one, read as JSON-LD. Blank node
+/// labels are scoped to their document.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::KnowledgeGraph;
+/// use sage::json;
+/// use sage::pipeline::{Documents, Pipeline};
+/// use sage::transform::Transform;
+///
+/// let records = vec![
+///   json!({ "uid": "Ada", "name": "Ada Lovelace" }),
+///   json!({ "uid": "Alan", "name": "Alan Turing" }),
+/// ];
+/// let mapping = Transform::compile(
+///   r#"{ "@id": "https://example.com/" + .uid,
+///        "https://schema.org/name": .name }"#,
+/// )
+/// .unwrap();
+///
+/// let mut graph = KnowledgeGraph::new();
+/// let metrics = Pipeline::new(Documents::new(records).transform(mapping))
+///   .sink(&mut graph)
+///   .run()
+///   .unwrap();
+/// assert_eq!((metrics.batches, metrics.written), (2, 2));
+/// assert_eq!(graph.len(), 2);
+/// ```
+pub struct Documents<'a> {
+  documents: Box<dyn Iterator<Item = DType> + 'a>,
+  transform: Option<Transform>,
+  mapping: Option<Mapping>,
+  /// Number of documents read so far.
+  count: usize,
+}
+
+impl<'a> Documents<'a> {
+  /// Creates a source reading every document of `documents`.
+  pub fn new<I>(documents: I) -> Documents<'a>
+  where
+    I: IntoIterator<Item = DType>,
+    I::IntoIter: 'a,
+  {
+    Documents {
+      documents: Box::new(documents.into_iter()),
+      transform: None,
+      mapping: None,
+      count: 0,
+    }
+  }
+
+  /// Reshapes every document with `transform` before it's mapped.
+  pub fn transform(mut self, transform: Transform) -> Self {
+    self.transform = Some(transform);
+    self
+  }
+
+  /// Maps every document to triples with `mapping`, instead of reading it
+  /// as JSON-LD.
+  pub fn mapping(mut self, mapping: Mapping) -> Self {
+    self.mapping = Some(mapping);
+    self
+  }
+}
+
+impl<'a> Source for Documents<'a> {
+  fn read(&mut self) -> Result<Option<Vec<Triple>>> {
+    let document = match self.documents.next() {
+      Some(document) => document,
+      None => return Ok(None),
+    };
+    let scope = format!("d{}", self.count);
+    self.count += 1;
+    let transform = self.transform.as_ref();
+    triples(document, transform, self.mapping.as_ref(), &scope).map(Some)
+  }
+}
+
+/// Maps `document` to triples like `Documents` does, prefixing blank node
+/// labels with `scope`: `_:b0` becomes `_:d1_b0` in the scope `d1`.
+pub(crate) fn triples(
+  document: DType,
+  transform: Option<&Transform>,
+  mapping: Option<&Mapping>,
+  scope: &str,
+) -> Result<Vec<Triple>> {
+  let document = match transform {
+    Some(transform) => transform.apply(&document)?,
+    None => document,
+  };
+  let triples = match mapping {
+    Some(mapping) => mapping.apply(&document)?,
+    None => JsonLd::from_dtype(&document)?.into_triples(),
+  };
+  Ok(
+    triples
+      .into_iter()
+      .map(|triple| scoped(triple, scope))
+      .collect(),
+  )
+}
+
+fn scoped(triple: Triple, scope: &str) -> Triple {
+  let [source, destination] =
+    [triple.source(), triple.destination()].map(|node| match node {
+      Node::BlankId(label) => Node::BlankId(format!("{}_{}", scope, label)),
+      node => node.clone(),
+    });
+  let scoped =
+    Triple::from_nodes(source, triple.predicate().clone(), destination);
+  match triple.provenance() {
+    Some(provenance) => scoped.with_provenance(provenance.clone()),
+    None => scoped,
+  }
+}
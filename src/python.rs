@@ -0,0 +1,255 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::python` exposes `DType` & `KnowledgeGraph` to Python through
+//! [PyO3].
+//!
+//! Enable with the `pyo3` feature and build the extension module with
+//! [maturin]:
+//!
+//! ```sh
+//! maturin develop --features pyo3
+//! ```
+//!
+//! ```python
+//! import sage
+//!
+//! doc = sage.loads('{"name": "John", "born": "1990-01-01T00:00:00Z"}')
+//! graph = sage.KnowledgeGraph()
+//! graph.insert_literal("https://example.com/John", "name", doc["name"])
+//! graph.insert("https://example.com/John", "knows", "https://example.com/Jane")
+//! graph.query(predicate="knows")
+//! ```
+//!
+//! `DType`s map onto native Python values:
+//!
+//! | `DType`    | Python                        |
+//! |------------|-------------------------------|
+//! | `Null`     | `None`                        |
+//! | `Boolean`  | `bool`                        |
+//! | `Number`   | `int` or `float`              |
+//! | `String`   | `str`                         |
+//! | `Bytes`    | `bytes`                       |
+//! | `DateTime` | timezone aware `datetime`     |
+//! | `Array`    | `list` (also from `tuple`)    |
+//! | `Object`   | `dict` with `str` keys        |
+//!
+//! [PyO3]: https://pyo3.rs
+//! [maturin]: https://www.maturin.rs
+
+// False positive in the `#[pyfunction]` & `#[pymethods]` expansions.
+#![allow(clippy::useless_conversion)]
+
+use chrono::{DateTime as ChronoDateTime, Utc};
+use pyo3::{
+  exceptions::{PyTypeError, PyValueError},
+  prelude::*,
+  types::{
+    PyBool, PyBytes, PyDateTime, PyDict, PyFloat, PyList, PyLong, PyString,
+    PyTuple,
+  },
+};
+
+use crate::{
+  datastore::json,
+  dtype::{DType, Map},
+  error::Error,
+  graph::{KnowledgeGraph, Node, Predicate},
+};
+
+/// Converts a `DType` into the equivalent Python value.
+pub fn to_python(py: Python<'_>, value: &DType) -> PyResult<PyObject> {
+  Ok(match value {
+    DType::Null => py.None(),
+    DType::Boolean(b) => b.into_py(py),
+    DType::Number(n) => {
+      if let Some(i) = n.as_i64() {
+        i.into_py(py)
+      } else if let Some(u) = n.as_u64() {
+        u.into_py(py)
+      } else {
+        n.as_f64().unwrap_or(f64::NAN).into_py(py)
+      }
+    }
+    DType::String(s) => s.into_py(py),
+    DType::Bytes(b) => PyBytes::new_bound(py, b).into_py(py),
+    DType::DateTime(d) => d.as_chrono().into_py(py),
+    DType::Array(values) => {
+      let values = values
+        .iter()
+        .map(|v| to_python(py, v))
+        .collect::<PyResult<Vec<_>>>()?;
+      PyList::new_bound(py, values).into_py(py)
+    }
+    DType::Object(map) => {
+      let dict = PyDict::new_bound(py);
+      for (k, v) in map {
+        dict.set_item(k, to_python(py, v)?)?;
+      }
+      dict.into_py(py)
+    }
+  })
+}
+
+/// Converts a Python value into a `DType`.
+pub fn from_python(value: &Bound<'_, PyAny>) -> PyResult<DType> {
+  if value.is_none() {
+    Ok(DType::Null)
+  } else if let Ok(b) = value.downcast::<PyBool>() {
+    // `bool` is a subclass of `int`, so it must be checked first.
+    Ok(DType::Boolean(b.is_true()))
+  } else if value.is_instance_of::<PyLong>() {
+    match value.extract::<i64>() {
+      Ok(i) => Ok(i.into()),
+      Err(_) => Ok(value.extract::<u64>()?.into()),
+    }
+  } else if let Ok(f) = value.downcast::<PyFloat>() {
+    Ok(f.value().into())
+  } else if let Ok(s) = value.downcast::<PyString>() {
+    Ok(DType::String(s.to_str()?.to_string()))
+  } else if let Ok(b) = value.downcast::<PyBytes>() {
+    Ok(DType::Bytes(Box::new(b.as_bytes().to_vec())))
+  } else if value.is_instance_of::<PyDateTime>() {
+    let d: ChronoDateTime<Utc> = value.extract()?;
+    Ok(DType::DateTime(d.into()))
+  } else if let Ok(list) = value.downcast::<PyList>() {
+    list.iter().map(|v| from_python(&v)).collect()
+  } else if let Ok(tuple) = value.downcast::<PyTuple>() {
+    tuple.iter().map(|v| from_python(&v)).collect()
+  } else if let Ok(dict) = value.downcast::<PyDict>() {
+    let mut map = Map::new();
+    for (k, v) in dict.iter() {
+      let key = k
+        .downcast::<PyString>()
+        .map_err(|_| PyTypeError::new_err("dictionary keys must be strings"))?;
+      map.insert(key.to_str()?.to_string(), from_python(&v)?);
+    }
+    Ok(DType::Object(map))
+  } else {
+    Err(PyTypeError::new_err(format!(
+      "cannot convert `{}` into a sage value",
+      value.get_type().name()?
+    )))
+  }
+}
+
+/// Parses JSON text into native Python values.
+#[pyfunction]
+fn loads(py: Python<'_>, text: &str) -> PyResult<PyObject> {
+  let value: DType = json::from_str(text).map_err(to_py_err)?;
+  to_python(py, &value)
+}
+
+/// Serializes native Python values into JSON text.
+#[pyfunction]
+#[pyo3(signature = (value, pretty = false))]
+fn dumps(value: &Bound<'_, PyAny>, pretty: bool) -> PyResult<String> {
+  let value = from_python(value)?;
+  let text = if pretty {
+    json::to_string_pretty(&value)
+  } else {
+    json::to_string(&value)
+  };
+  text.map_err(to_py_err)
+}
+
+/// `PyKnowledgeGraph` is the Python `sage.KnowledgeGraph` class.
+#[pyclass(name = "KnowledgeGraph")]
+#[derive(Default)]
+pub struct PyKnowledgeGraph {
+  graph: KnowledgeGraph,
+}
+
+#[pymethods]
+impl PyKnowledgeGraph {
+  #[new]
+  fn new() -> Self {
+    PyKnowledgeGraph::default()
+  }
+
+  /// Links two resources identified by IRI.
+  fn insert(&mut self, subject: &str, predicate: &str, object: &str) {
+    self.graph.insert(
+      Node::Http(subject.to_string()),
+      Predicate::Literal(predicate.to_string()),
+      Node::Http(object.to_string()),
+    );
+  }
+
+  /// Attaches a literal `value` to the resource `subject`.
+  fn insert_literal(
+    &mut self,
+    subject: &str,
+    predicate: &str,
+    value: &Bound<'_, PyAny>,
+  ) -> PyResult<()> {
+    self.graph.insert(
+      Node::Http(subject.to_string()),
+      Predicate::Literal(predicate.to_string()),
+      Node::Literal(from_python(value)?),
+    );
+    Ok(())
+  }
+
+  /// Returns `(subject, predicate, object)` tuples matching the pattern.
+  /// Omitted terms match anything; `subject` & `object` are IRIs.
+  #[pyo3(signature = (subject = None, predicate = None, object = None))]
+  fn query(
+    &self,
+    py: Python<'_>,
+    subject: Option<String>,
+    predicate: Option<String>,
+    object: Option<String>,
+  ) -> PyResult<Vec<(String, String, PyObject)>> {
+    let subject = subject.map(Node::Http);
+    let object = object.map(Node::Http);
+
+    self
+      .graph
+      .matches(subject.as_ref(), predicate.as_deref(), object.as_ref())
+      .map(|triple| {
+        let object = match triple.destination() {
+          Node::Literal(value) => to_python(py, value)?,
+          node => node.to_string().into_py(py),
+        };
+        Ok((
+          triple.source().to_string(),
+          triple.predicate().to_string(),
+          object,
+        ))
+      })
+      .collect()
+  }
+
+  fn __len__(&self) -> usize {
+    self.graph.len()
+  }
+
+  fn __repr__(&self) -> String {
+    format!("<sage.KnowledgeGraph with {} triples>", self.graph.len())
+  }
+}
+
+/// The `sage` Python module.
+#[pymodule]
+fn sage(m: &Bound<'_, PyModule>) -> PyResult<()> {
+  m.add_function(wrap_pyfunction!(loads, m)?)?;
+  m.add_function(wrap_pyfunction!(dumps, m)?)?;
+  m.add_class::<PyKnowledgeGraph>()?;
+  Ok(())
+}
+
+fn to_py_err(err: Error) -> PyErr {
+  PyValueError::new_err(err.to_string())
+}
@@ -12,4 +12,36 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod aggregate;
+mod construct;
+#[cfg(feature = "http")]
+mod endpoint;
+mod federation;
+mod filter;
 mod iterator;
+mod parser;
+mod path;
+mod pattern;
+mod planner;
+mod prepared;
+mod results;
+mod rule;
+mod select;
+mod update;
+
+pub use construct::Construct;
+#[cfg(feature = "http")]
+pub use endpoint::Endpoint;
+pub use federation::{Federation, Service};
+pub use filter::Filter;
+#[cfg(feature = "server")]
+pub(crate) use parser::parse_term;
+pub(crate) use parser::Parser;
+pub use path::Path;
+pub(crate) use pattern::to_term;
+pub use pattern::{Aggregation, Query, Term};
+pub use prepared::PreparedQuery;
+pub use results::ResultFormat;
+pub use rule::{Reasoner, Rule};
+pub use select::{Rows, Select};
+pub use update::{Update, UpdateReport};
@@ -0,0 +1,228 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `GROUP BY` & aggregates of `SELECT` queries.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+  dtype::DType,
+  graph::Node,
+  query::{parser::Parser, pattern::Bindings, select::key, Term},
+  Result,
+};
+
+/// An aggregate function.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Function {
+  Count,
+  Sum,
+  Avg,
+  Min,
+  Max,
+  /// Joins the lexical forms with the separator.
+  GroupConcat(String),
+}
+
+/// An aggregate of the projection, e.g. `(COUNT(DISTINCT ?x) AS ?n)`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Aggregate {
+  pub(crate) function: Function,
+  pub(crate) distinct: bool,
+  /// Aggregated variable, `None` for `*`.
+  pub(crate) var: Option<String>,
+  /// Variable the result is bound to.
+  pub(crate) alias: String,
+}
+
+impl Aggregate {
+  /// Parses an aggregate of the projection, after its opening `(`.
+  pub(crate) fn parse(parser: &mut Parser) -> Result<Aggregate> {
+    let function = if parser.keyword("COUNT") {
+      Function::Count
+    } else if parser.keyword("SUM") {
+      Function::Sum
+    } else if parser.keyword("AVG") {
+      Function::Avg
+    } else if parser.keyword("MIN") {
+      Function::Min
+    } else if parser.keyword("MAX") {
+      Function::Max
+    } else if parser.keyword("GROUP_CONCAT") {
+      Function::GroupConcat(" ".to_string())
+    } else {
+      return Err(parser.error());
+    };
+
+    parser.expect('(')?;
+    let distinct = parser.keyword("DISTINCT");
+    let var = match function {
+      Function::Count if parser.eat('*') => None,
+      _ => Some(parser.var().ok_or_else(|| parser.error())?),
+    };
+    let function = match function {
+      Function::GroupConcat(_) if parser.eat(';') => {
+        if !parser.keyword("SEPARATOR") {
+          return Err(parser.error());
+        }
+        parser.expect('=')?;
+        match parser.term()? {
+          Term::Node(Node::Literal(DType::String(ref separator))) => {
+            Function::GroupConcat(separator.clone())
+          }
+          _ => return Err(parser.error()),
+        }
+      }
+      function => function,
+    };
+    parser.expect(')')?;
+
+    if !parser.keyword("AS") {
+      return Err(parser.error());
+    }
+    let alias = parser.var().ok_or_else(|| parser.error())?;
+    parser.expect(')')?;
+    Ok(Aggregate {
+      function,
+      distinct,
+      var,
+      alias,
+    })
+  }
+
+  /// Computes the aggregate over the solutions of a group, `None` if it's
+  /// unbound: a `SUM` or `AVG` of non-numeric values, or the `MIN` &
+  /// `MAX` of nothing.
+  fn evaluate(&self, solutions: &[Bindings]) -> Option<Node> {
+    let var = match &self.var {
+      Some(var) => var,
+      None if self.distinct => {
+        let distinct: HashSet<Vec<(&String, &Node)>> = solutions
+          .iter()
+          .map(|bindings| {
+            let mut row: Vec<_> = bindings.iter().collect();
+            row.sort_by(|a, b| a.0.cmp(b.0));
+            row
+          })
+          .collect();
+        return Some(Node::Literal(distinct.len().into()));
+      }
+      None => return Some(Node::Literal(solutions.len().into())),
+    };
+
+    let mut values: Vec<&Node> =
+      solutions.iter().filter_map(|b| b.get(var)).collect();
+    if self.distinct {
+      let mut seen = HashSet::new();
+      values.retain(|value| seen.insert(*value));
+    }
+
+    let value = match &self.function {
+      Function::Count => values.len().into(),
+      Function::Sum => sum(&values)?,
+      Function::Avg if values.is_empty() => 0.into(),
+      Function::Avg => {
+        let sum = sum(&values)?.as_f64()?;
+        (sum / values.len() as f64).into()
+      }
+      Function::Min => {
+        return values.into_iter().min_by_key(|v| key(Some(*v))).cloned()
+      }
+      Function::Max => {
+        return values.into_iter().max_by_key(|v| key(Some(*v))).cloned()
+      }
+      Function::GroupConcat(separator) => values
+        .iter()
+        .map(|value| lexical(value))
+        .collect::<Vec<_>>()
+        .join(separator)
+        .into(),
+    };
+    Some(Node::Literal(value))
+  }
+}
+
+/// Groups `solutions` by the values of the `group_by` variables (a single
+/// group without any) and returns one solution per group, in order of first
+/// appearance, binding the grouped variables & the `aggregates`.
+pub(crate) fn group(
+  solutions: Vec<Bindings>,
+  group_by: &[String],
+  aggregates: &[Aggregate],
+) -> Vec<Bindings> {
+  let mut index: HashMap<Vec<Option<Node>>, usize> = HashMap::new();
+  let mut groups: Vec<(Vec<Option<Node>>, Vec<Bindings>)> = Vec::new();
+  if group_by.is_empty() {
+    index.insert(Vec::new(), 0);
+    groups.push((Vec::new(), Vec::new()));
+  }
+  for bindings in solutions {
+    let values: Vec<Option<Node>> = group_by
+      .iter()
+      .map(|var| bindings.get(var).cloned())
+      .collect();
+    let i = *index.entry(values.clone()).or_insert_with(|| {
+      groups.push((values, Vec::new()));
+      groups.len() - 1
+    });
+    groups[i].1.push(bindings);
+  }
+
+  groups
+    .into_iter()
+    .map(|(values, solutions)| {
+      let mut row: Bindings = group_by
+        .iter()
+        .zip(values)
+        .filter_map(|(var, value)| Some((var.clone(), value?)))
+        .collect();
+      for aggregate in aggregates {
+        if let Some(value) = aggregate.evaluate(&solutions) {
+          row.insert(aggregate.alias.clone(), value);
+        }
+      }
+      row
+    })
+    .collect()
+}
+
+/// Adds up numeric literals, keeping integers as long as they fit.
+fn sum(values: &[&Node]) -> Option<DType> {
+  let mut integer: Option<i64> = Some(0);
+  let mut float = 0.0;
+  for value in values {
+    let number = match value {
+      Node::Literal(number @ DType::Number(_)) => number,
+      _ => return None,
+    };
+    integer = integer
+      .zip(number.as_i64())
+      .and_then(|(sum, n)| sum.checked_add(n));
+    float += number.as_f64()?;
+  }
+  Some(match integer {
+    Some(sum) => sum.into(),
+    None => float.into(),
+  })
+}
+
+/// Returns the lexical form of a value: the text of literals, the IRI or
+/// label of other nodes.
+fn lexical(node: &Node) -> String {
+  match node {
+    Node::Literal(DType::String(s)) => s.clone(),
+    Node::Literal(DType::Number(n)) => n.to_string(),
+    node => node.to_string(),
+  }
+}
@@ -0,0 +1,199 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+  collections::{HashMap, HashSet},
+  fmt,
+};
+
+use crate::{
+  graph::{KnowledgeGraph, Node, Predicate},
+  query::{parser::Parser, pattern::Bindings, rule::instantiate, Select, Term},
+  Result,
+};
+
+/// A statement with its predicate IRI.
+type Statement = (Node, String, Node);
+
+/// `Construct` is a parsed SPARQL `CONSTRUCT` query: a template of triples
+/// instantiated with every solution of its `WHERE` group.
+///
+/// The `WHERE` group & the modifiers following it (`ORDER BY`, `LIMIT` &
+/// `OFFSET`) are those of `Select`. `CONSTRUCT WHERE { ... }` uses the
+/// triple patterns of the group as the template. Template triples with an
+/// unbound variable, a literal subject or a predicate other than an IRI are
+/// left out, and blank node labels stand for fresh blank nodes in every
+/// solution.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node};
+/// use sage::query::{Construct, Update};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.update(
+///   &Update::parse(
+///     r#"PREFIX ex: <https://example.com/>
+///        PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+///        INSERT DATA { ex:Ada foaf:name "Ada" ; foaf:knows ex:Bob . }"#,
+///   )
+///   .unwrap(),
+/// );
+///
+/// let construct = Construct::parse(
+///   "PREFIX foaf: <http://xmlns.com/foaf/0.1/>
+///    CONSTRUCT { ?person schema:name ?name ; a schema:Person }
+///    WHERE { ?person foaf:name ?name }",
+/// )
+/// .unwrap();
+///
+/// let people = construct.graph(&graph);
+/// assert_eq!(people.len(), 2);
+/// let ada = Node::Http("https://example.com/Ada".to_string());
+/// assert_eq!(
+///   people
+///     .matches(Some(&ada), Some("https://schema.org/name"), None)
+///     .count(),
+///   1
+/// );
+///
+/// assert_eq!(construct.materialize(&mut graph), 2);
+/// assert_eq!(graph.len(), 4);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Construct {
+  source: String,
+  template: Vec<(Term, Term, Term)>,
+  /// `SELECT *` query of the `WHERE` group & its modifiers.
+  select: Select,
+}
+
+impl Construct {
+  /// Parses a SPARQL `CONSTRUCT` query.
+  pub fn parse(s: &str) -> Result<Construct> {
+    let mut parser = Parser::query(s);
+    parser.prologue()?;
+    let prologue = parser.parsed();
+    if !parser.keyword("CONSTRUCT") {
+      return Err(parser.error());
+    }
+
+    parser.skip_whitespace();
+    let start = parser.error();
+    let short = parser.keyword("WHERE");
+    let group = parser.parsed().len();
+    let template = parser.block(false)?.patterns;
+    if template.iter().any(|(_, p, _)| matches!(p, Term::Path(_))) {
+      return Err(start);
+    }
+
+    let rest = if short {
+      &s[group..]
+    } else {
+      &s[parser.parsed().len()..]
+    };
+    let select = Select::parse(&format!("{} SELECT * {}", prologue, rest))?;
+    Ok(Construct {
+      source: s.to_string(),
+      template,
+      select,
+    })
+  }
+
+  /// Returns the text the query was parsed from.
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+
+  /// Evaluates the query against `graph`, returning the graph made of the
+  /// instantiated templates.
+  pub fn graph(&self, graph: &KnowledgeGraph) -> KnowledgeGraph {
+    let mut constructed = KnowledgeGraph::new();
+    for (s, p, o) in self.statements(graph, &HashSet::new()) {
+      constructed.insert(s, Predicate::Literal(p), o);
+    }
+    constructed
+  }
+
+  /// Evaluates the query against `graph` & inserts the instantiated
+  /// templates into it, returning the number of statements added. Those
+  /// already in the graph are skipped.
+  pub fn materialize(&self, graph: &mut KnowledgeGraph) -> usize {
+    let statements = self.statements(graph, &graph.blank_labels());
+    let mut inserted = 0;
+    for (s, p, o) in statements {
+      if graph.matches(Some(&s), Some(&p), Some(&o)).next().is_none() {
+        graph.insert(s, Predicate::Literal(p), o);
+        inserted += 1;
+      }
+    }
+    inserted
+  }
+
+  /// Instantiates the template with every solution, without duplicates &
+  /// in order. Fresh blank nodes avoid the labels in `used`.
+  fn statements(
+    &self,
+    graph: &KnowledgeGraph,
+    used: &HashSet<String>,
+  ) -> Vec<Statement> {
+    let mut next = 0;
+    let mut seen = HashSet::new();
+    let mut statements = Vec::new();
+    for row in self.select.solutions(graph) {
+      let bindings: Bindings = self
+        .select
+        .variables()
+        .iter()
+        .zip(row)
+        .filter_map(|(var, node)| Some((var.clone(), node?)))
+        .collect();
+
+      let mut fresh = HashMap::new();
+      for pattern in &self.template {
+        let (s, p, o) = pattern;
+        let [s, o] = [s, o].map(|term| match term {
+          Term::Node(Node::BlankId(label)) => {
+            let node = fresh.entry(label).or_insert_with(|| loop {
+              let label = format!("b{}", next);
+              next += 1;
+              if !used.contains(&label) {
+                break Node::BlankId(label);
+              }
+            });
+            Term::Node(node.clone())
+          }
+          term => term.clone(),
+        });
+        let statement = match instantiate(&(s, p.clone(), o), &bindings) {
+          Some(statement) if !matches!(statement.0, Node::Literal(_)) => {
+            statement
+          }
+          _ => continue,
+        };
+        if seen.insert(statement.clone()) {
+          statements.push(statement);
+        }
+      }
+    }
+    statements
+  }
+}
+
+impl fmt::Display for Construct {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&self.source)
+  }
+}
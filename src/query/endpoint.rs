@@ -0,0 +1,188 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Remote SPARQL endpoints, queried as `Service`s over HTTP(S).
+//!
+//! Enable with the `http` feature.
+
+use std::time::Duration;
+
+use serde::de::Error as _;
+
+use crate::{
+  datastore::json,
+  dtype::{DType, Map},
+  error::Error,
+  formats::typed_value,
+  graph::Node,
+  http,
+  query::{Rows, Select, Service},
+  Result,
+};
+
+/// `Endpoint` is a remote SPARQL endpoint, queried over the [SPARQL 1.1
+/// Protocol] with `GET` requests accepting SPARQL JSON results.
+///
+/// Requests time out after 30 seconds unless set otherwise, and responses
+/// are capped at 64 MiB.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// use sage::query::{Endpoint, Federation, Select};
+///
+/// let endpoint = Endpoint::new("https://query.wikidata.org/sparql")
+///   .unwrap()
+///   .header("User-Agent", "sage")
+///   .unwrap()
+///   .timeout(Duration::from_secs(10));
+/// let select = Select::parse(
+///   "SELECT ?item WHERE {
+///      SERVICE <https://query.wikidata.org/sparql> {
+///        ?item <http://www.wikidata.org/prop/direct/P31>
+///          <http://www.wikidata.org/entity/Q146>
+///      }
+///    } LIMIT 3",
+/// )
+/// .unwrap();
+/// let federation = Federation::new()
+///   .service("https://query.wikidata.org/sparql", endpoint);
+/// println!("{:?}", federation.solutions(&select).unwrap());
+/// ```
+///
+/// [SPARQL 1.1 Protocol]: https://www.w3.org/TR/sparql11-protocol/
+#[derive(Clone, Debug)]
+pub struct Endpoint {
+  client: http::Client,
+}
+
+impl Endpoint {
+  /// Creates a client for the endpoint at `url`, e.g.
+  /// `"http://localhost:3030/dataset/sparql"`. Both `http://` & `https://`
+  /// URLs are supported.
+  pub fn new(url: &str) -> Result<Endpoint> {
+    let client = http::Client::new(url, "SPARQL endpoint")?;
+    Ok(Endpoint { client })
+  }
+
+  /// Sends `name: value` with every request, e.g. for authorization.
+  ///
+  /// # Errors
+  ///
+  /// Fails if `name` isn't a valid header name or `value` holds control
+  /// characters, such as CR or LF.
+  pub fn header(mut self, name: &str, value: &str) -> Result<Self> {
+    self.client = self.client.header(name, value)?;
+    Ok(self)
+  }
+
+  /// Fails requests which take longer than `timeout`, from connecting to
+  /// reading the whole response. Defaults to 30 seconds.
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.client = self.client.timeout(timeout);
+    self
+  }
+}
+
+impl Service for Endpoint {
+  fn select(&self, select: &Select) -> Result<Rows> {
+    let body = self.client.get(
+      &[("query", select.source())],
+      "application/sparql-results+json",
+    )?;
+    let (variables, solutions) = parse_json(&body)?;
+    // Endpoints may list the variables in another order.
+    let columns: Vec<Option<usize>> = select
+      .variables()
+      .iter()
+      .map(|var| variables.iter().position(|v| v == var))
+      .collect();
+    Ok(
+      solutions
+        .into_iter()
+        .map(|row| {
+          columns
+            .iter()
+            .map(|column| column.and_then(|i| row[i].clone()))
+            .collect()
+        })
+        .collect(),
+    )
+  }
+}
+
+/// Parses [SPARQL JSON results] into their variables & solutions, with
+/// literals converted back into `DType`s.
+///
+/// [SPARQL JSON results]: https://www.w3.org/TR/sparql11-results-json/
+fn parse_json(bytes: &[u8]) -> Result<(Vec<String>, Rows)> {
+  let malformed = || Error::custom("malformed SPARQL JSON results");
+  let results: DType = json::from_slice(bytes)?;
+  let variables: Vec<String> = results
+    .get("head")
+    .and_then(|head| head.get("vars"))
+    .and_then(DType::as_array)
+    .ok_or_else(malformed)?
+    .iter()
+    .map(|var| var.as_str().map(str::to_string).ok_or_else(malformed))
+    .collect::<Result<_>>()?;
+  let bindings = results
+    .get("results")
+    .and_then(|results| results.get("bindings"))
+    .and_then(DType::as_array)
+    .ok_or_else(malformed)?;
+
+  let solutions = bindings
+    .iter()
+    .map(|binding| {
+      variables
+        .iter()
+        .map(|var| binding.get(var.as_str()).map(term).transpose())
+        .collect::<Result<Vec<_>>>()
+    })
+    .collect::<Result<_>>()?;
+  Ok((variables, solutions))
+}
+
+/// Converts an RDF term of SPARQL JSON results into a node.
+fn term(term: &DType) -> Result<Node> {
+  let field = |name: &str| term.get(name).and_then(DType::as_str);
+  let value = field("value")
+    .ok_or_else(|| Error::custom("malformed SPARQL JSON results"))?
+    .to_string();
+  Ok(match field("type") {
+    Some("uri") => Node::Http(value),
+    Some("bnode") => Node::BlankId(value),
+    Some("literal" | "typed-literal") => match field("xml:lang") {
+      Some(language) => {
+        let mut literal = Map::new();
+        literal.insert("@value".to_string(), value.into());
+        literal.insert("@language".to_string(), language.into());
+        Node::Literal(DType::Object(literal))
+      }
+      None => match field("datatype") {
+        Some(datatype) => Node::Literal(typed_value(value, datatype)),
+        None => Node::Literal(value.into()),
+      },
+    },
+    kind => {
+      return Err(Error::custom(format!(
+        "unknown SPARQL JSON term type `{}`",
+        kind.unwrap_or_default()
+      )))
+    }
+  })
+}
@@ -0,0 +1,212 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Federated evaluation of `SELECT` queries over several graphs &
+//! `SERVICE` endpoints.
+
+use std::collections::HashMap;
+
+use serde::de::Error as _;
+
+#[cfg(feature = "http")]
+use crate::query::Endpoint;
+use crate::{
+  error::Error,
+  graph::KnowledgeGraph,
+  query::{pattern::Bindings, results, ResultFormat, Rows, Select},
+  Result,
+};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Services
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Service` evaluates the group of a `SERVICE` clause, received as a
+/// `SELECT *` query with the prologue of the enclosing query.
+pub trait Service {
+  /// Evaluates `select`, returning one row per solution with the value of
+  /// every variable of `select.variables()`, like `Select::solutions`.
+  fn select(&self, select: &Select) -> Result<Rows>;
+}
+
+impl Service for KnowledgeGraph {
+  fn select(&self, select: &Select) -> Result<Rows> {
+    Ok(select.solutions(self))
+  }
+}
+
+impl<S: Service + ?Sized> Service for &S {
+  fn select(&self, select: &Select) -> Result<Rows> {
+    (**self).select(select)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Federation
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// A `SERVICE` clause of a `Select`, with its group parsed as a query.
+#[derive(Clone, Debug)]
+pub(crate) struct ServiceCall {
+  pub(crate) iri: String,
+  pub(crate) silent: bool,
+  pub(crate) select: Select,
+}
+
+/// `Federation` evaluates `SELECT` queries against several graphs at once,
+/// delegating their `SERVICE` clauses to other graphs or remote endpoints.
+///
+/// Triple patterns match the union of the graphs, as if they had been
+/// merged, without copying them; statements found in several graphs match
+/// once. Property paths are evaluated within each graph.
+///
+/// A `SERVICE <iri> { ... }` group is sent as a `SELECT *` query to the
+/// service registered under its IRI or, once `remote` is enabled (feature
+/// `http`), to the SPARQL endpoint at that IRI. Its solutions are joined with the other
+/// patterns of the query on their shared variables, before `FILTER`s,
+/// grouping & ordering apply. A failing `SERVICE SILENT` group matches
+/// every solution instead of failing the query.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::query::{Federation, Select};
+///
+/// let node = |name: &str| Node::Http(format!("https://example.com/{}", name));
+/// let schema =
+///   |name: &str| Predicate::Literal(format!("https://schema.org/{}", name));
+/// let text = |s: &str| Node::Literal(s.into());
+///
+/// let mut people = KnowledgeGraph::new();
+/// people.insert(node("Ada"), schema("name"), text("Ada"));
+/// let mut jobs = KnowledgeGraph::new();
+/// jobs.insert(node("Ada"), schema("worksFor"), node("Acme"));
+/// let mut places = KnowledgeGraph::new();
+/// places.insert(node("Acme"), schema("location"), text("London"));
+///
+/// let select = Select::parse(
+///   "SELECT ?name ?city WHERE {
+///      ?person schema:name ?name ; schema:worksFor ?org .
+///      SERVICE <https://example.com/places> { ?org schema:location ?city }
+///    }",
+/// )
+/// .unwrap();
+///
+/// let federation = Federation::new()
+///   .graph(&people)
+///   .graph(&jobs)
+///   .service("https://example.com/places", &places);
+/// assert_eq!(
+///   federation.solutions(&select).unwrap(),
+///   [[Some(text("Ada")), Some(text("London"))]]
+/// );
+/// ```
+#[derive(Default)]
+pub struct Federation<'a> {
+  graphs: Vec<&'a KnowledgeGraph>,
+  services: HashMap<String, Box<dyn Service + 'a>>,
+  #[cfg(feature = "http")]
+  remote: bool,
+}
+
+impl<'a> Federation<'a> {
+  /// Creates a federation without graphs or services.
+  pub fn new() -> Federation<'a> {
+    Federation::default()
+  }
+
+  /// Adds `graph` to the graphs triple patterns match.
+  pub fn graph(mut self, graph: &'a KnowledgeGraph) -> Self {
+    self.graphs.push(graph);
+    self
+  }
+
+  /// Evaluates the `SERVICE` groups naming `iri` with `service`.
+  pub fn service<S: Service + 'a>(mut self, iri: &str, service: S) -> Self {
+    self.services.insert(iri.to_string(), Box::new(service));
+    self
+  }
+
+  /// Sends the `SERVICE` groups naming an unregistered HTTP(S) IRI to the
+  /// SPARQL endpoint at that IRI (see `Endpoint`). Disabled by default, so
+  /// queries can't reach the network unless allowed to.
+  #[cfg(feature = "http")]
+  pub fn remote(mut self, remote: bool) -> Self {
+    self.remote = remote;
+    self
+  }
+
+  /// Evaluates `select` against the graphs, returning one row per solution
+  /// like `Select::solutions`.
+  ///
+  /// # Errors
+  ///
+  /// Fails if a `SERVICE` group, other than a `SILENT` one, names an
+  /// unknown service or its service fails.
+  pub fn solutions(&self, select: &Select) -> Result<Rows> {
+    select.evaluate(&self.graphs, Bindings::new(), &|call| self.call(call))
+  }
+
+  /// Evaluates `select` like `Federation::solutions`, serializing the
+  /// solutions as `format`.
+  ///
+  /// # Errors
+  ///
+  /// See `Federation::solutions`.
+  pub fn results(
+    &self,
+    select: &Select,
+    format: ResultFormat,
+  ) -> Result<Vec<u8>> {
+    let solutions = self.solutions(select)?;
+    Ok(results::serialize(format, select.variables(), &solutions))
+  }
+
+  fn call(&self, call: &ServiceCall) -> Result<Rows> {
+    match self.services.get(&call.iri) {
+      Some(service) => service.select(&call.select),
+      #[cfg(feature = "http")]
+      None if self.remote => Endpoint::new(&call.iri)?.select(&call.select),
+      None => Err(Error::custom(format!("unknown service <{}>", call.iri))),
+    }
+  }
+}
+
+/// Joins every solution of `left` with the compatible solutions of
+/// `right`: those binding their shared variables to the same nodes.
+pub(crate) fn join(left: Vec<Bindings>, right: &[Bindings]) -> Vec<Bindings> {
+  let mut solutions = Vec::new();
+  for bindings in left {
+    for other in right {
+      let compatible = other
+        .iter()
+        .all(|(var, node)| bindings.get(var).is_none_or(|n| n == node));
+      if compatible {
+        let mut row = bindings.clone();
+        row.extend(other.iter().map(|(k, v)| (k.clone(), v.clone())));
+        solutions.push(row);
+      }
+    }
+  }
+  solutions
+}
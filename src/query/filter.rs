@@ -0,0 +1,153 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::{
+  dtype::{lang::language_matches, DType, LangString},
+  error::{Error, ErrorCode},
+  graph::Node,
+  Result,
+};
+
+/// `Filter` restricts the solutions of a `Query` by the language of a
+/// bound literal.
+///
+/// The language of a language tagged string (see `LangString`) is its
+/// tag, the language of a plain string is `""` and any other value has no
+/// language, so it never passes a filter.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::dtype::LangString;
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::query::{Filter, Query};
+///
+/// let john = Node::Http("https://example.com/John".to_string());
+/// let name = Predicate::Literal("https://schema.org/name".to_string());
+///
+/// let mut graph = KnowledgeGraph::new();
+/// for (value, lang) in [("John", "en"), ("Johann", "de"), ("Jean", "fr-CA")] {
+///   let literal = LangString::new(value, lang).unwrap();
+///   graph.insert(john.clone(), name.clone(), literal.into());
+/// }
+///
+/// let query = Query::new()
+///   .pattern(john.clone(), "https://schema.org/name", "?name")
+///   .filter(Filter::parse(r#"FILTER(lang(?name) = "de")"#).unwrap());
+/// assert_eq!(graph.query(&query)[0]["name"]["@value"], "Johann");
+///
+/// let query = Query::new()
+///   .pattern(john, "https://schema.org/name", "?name")
+///   .filter(Filter::lang_matches("name", "fr"));
+/// assert_eq!(graph.query(&query)[0]["name"]["@value"], "Jean");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Filter {
+  /// `lang(?var) = "tag"`, compared case-insensitively.
+  Lang {
+    /// Variable name, without the `?`.
+    var: String,
+    /// Expected language tag, `""` for plain strings.
+    tag: String,
+  },
+  /// `langMatches(lang(?var), "range")`, see `LangString::matches`.
+  LangMatches {
+    /// Variable name, without the `?`.
+    var: String,
+    /// Basic language range, e.g. `en` or `*`.
+    range: String,
+  },
+}
+
+impl Filter {
+  /// Creates a `lang(?var) = "tag"` filter.
+  pub fn lang(var: &str, tag: &str) -> Filter {
+    Filter::Lang {
+      var: var.trim_start_matches('?').to_string(),
+      tag: tag.to_string(),
+    }
+  }
+
+  /// Creates a `langMatches(lang(?var), "range")` filter.
+  pub fn lang_matches(var: &str, range: &str) -> Filter {
+    Filter::LangMatches {
+      var: var.trim_start_matches('?').to_string(),
+      range: range.to_string(),
+    }
+  }
+
+  /// Parses a SPARQL `lang(?var) = "tag"` or
+  /// `langMatches(lang(?var), "range")` expression, optionally wrapped in
+  /// `FILTER(...)`.
+  pub fn parse(s: &str) -> Result<Filter> {
+    let err = || Error::syntax(ErrorCode::InvalidFilter, 0, 0);
+    let mut s = s.trim();
+    if s.len() >= 6 && s[..6].eq_ignore_ascii_case("filter") {
+      s = s[6..].trim();
+      s = s
+        .strip_prefix('(')
+        .and_then(|s| s.strip_suffix(')'))
+        .ok_or_else(err)?;
+    }
+
+    let lang = Regex::new(r#"^lang\s*\(\s*\?(\w+)\s*\)\s*=\s*"([^"]*)"$"#)
+      .expect("valid regex");
+    let lang_matches = Regex::new(
+      r#"^langMatches\s*\(\s*lang\s*\(\s*\?(\w+)\s*\)\s*,\s*"([^"]*)"\s*\)$"#,
+    )
+    .expect("valid regex");
+
+    let s = s.trim();
+    if let Some(caps) = lang.captures(s) {
+      Ok(Filter::lang(&caps[1], &caps[2]))
+    } else if let Some(caps) = lang_matches.captures(s) {
+      Ok(Filter::lang_matches(&caps[1], &caps[2]))
+    } else {
+      Err(err())
+    }
+  }
+
+  /// Returns `true` if the solution passes the filter.
+  pub(crate) fn accepts(&self, bindings: &HashMap<String, Node>) -> bool {
+    let var = match self {
+      Filter::Lang { var, .. } | Filter::LangMatches { var, .. } => var,
+    };
+    let lang = match bindings.get(var) {
+      Some(Node::Literal(value)) => match language(value) {
+        Some(lang) => lang,
+        None => return false,
+      },
+      _ => return false,
+    };
+    match self {
+      Filter::Lang { tag, .. } => lang.eq_ignore_ascii_case(tag),
+      Filter::LangMatches { range, .. } => language_matches(lang, range),
+    }
+  }
+}
+
+/// Returns the language of a literal, `""` for plain strings.
+fn language(value: &DType) -> Option<&str> {
+  match value {
+    DType::String(_) => Some(""),
+    value if LangString::from_dtype(value).is_some() => {
+      value.get("@language").and_then(DType::as_str)
+    }
+    _ => None,
+  }
+}
@@ -0,0 +1,661 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+  dtype::{DType, Map},
+  error::{Error, ErrorCode},
+  formats::typed_value,
+  graph::Node,
+  query::{Filter, Path, Term},
+  vocab::Namespaces,
+  Result,
+};
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Full IRI of `rdf:type`, written as `a`.
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/// Parses `s` as a single subject or object, e.g. `"Ada"@en` or `<iri>`.
+#[cfg(feature = "server")]
+pub(crate) fn parse_term(s: &str) -> Result<Term> {
+  let mut parser = Parser::query(s);
+  let term = parser.term()?;
+  if parser.is_done() {
+    Ok(term)
+  } else {
+    Err(parser.error())
+  }
+}
+
+/// A `{ ... }` group of triple patterns & filters.
+#[derive(Debug, Default)]
+pub(crate) struct Block {
+  pub(crate) patterns: Vec<(Term, Term, Term)>,
+  pub(crate) filters: Vec<Filter>,
+  pub(crate) services: Vec<ServiceBlock>,
+}
+
+/// A `SERVICE <iri> { ... }` group, evaluated by another endpoint.
+#[derive(Debug)]
+pub(crate) struct ServiceBlock {
+  pub(crate) iri: String,
+  pub(crate) silent: bool,
+  /// Text of the `{ ... }` group.
+  pub(crate) group: String,
+}
+
+/// The language read by a `Parser`, picking the code of its errors.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Grammar {
+  Query,
+  Update,
+  Turtle,
+  Rules,
+}
+
+/// `Parser` reads the syntax shared by SPARQL queries, updates, Turtle &
+/// Datalog rules: prologues, groups of triples, terms & property paths.
+///
+/// Prefixed names expand against the default `Namespaces` & `xsd`, along
+/// with `PREFIX` (and in Turtle `@prefix`) declarations. Errors carry the
+/// line and column they occurred at.
+pub(crate) struct Parser<'a> {
+  s: &'a str,
+  pos: usize,
+  grammar: Grammar,
+  namespaces: Namespaces,
+}
+
+impl<'a> Parser<'a> {
+  /// Creates a parser of a query.
+  pub(crate) fn query(s: &'a str) -> Parser<'a> {
+    Parser::new(s, Grammar::Query)
+  }
+
+  /// Creates a parser of an update request.
+  pub(crate) fn update(s: &'a str) -> Parser<'a> {
+    Parser::new(s, Grammar::Update)
+  }
+
+  /// Creates a parser of a Turtle document.
+  pub(crate) fn turtle(s: &'a str) -> Parser<'a> {
+    Parser::new(s, Grammar::Turtle)
+  }
+
+  /// Creates a parser of Datalog rules.
+  pub(crate) fn rules(s: &'a str) -> Parser<'a> {
+    Parser::new(s, Grammar::Rules)
+  }
+
+  fn new(s: &'a str, grammar: Grammar) -> Parser<'a> {
+    let mut namespaces = Namespaces::default();
+    namespaces.bind("xsd", XSD);
+    Parser {
+      s,
+      pos: 0,
+      grammar,
+      namespaces,
+    }
+  }
+
+  /// Returns the text read so far, e.g. the prologue of a query.
+  pub(crate) fn parsed(&self) -> &'a str {
+    &self.s[..self.pos]
+  }
+
+  /// Moves back to `pos`, a length of `parsed` text.
+  pub(crate) fn rewind(&mut self, pos: usize) {
+    self.pos = pos;
+  }
+
+  fn rest(&self) -> &'a str {
+    &self.s[self.pos..]
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.rest().chars().next()
+  }
+
+  fn bump(&mut self) -> Option<char> {
+    let c = self.peek()?;
+    self.pos += c.len_utf8();
+    Some(c)
+  }
+
+  /// Skips whitespace & `#` comments.
+  pub(crate) fn skip_whitespace(&mut self) {
+    loop {
+      match self.peek() {
+        Some(c) if c.is_whitespace() => self.pos += c.len_utf8(),
+        Some('#') => {
+          let end = self.rest().find('\n').unwrap_or(self.rest().len());
+          self.pos += end;
+        }
+        _ => return,
+      }
+    }
+  }
+
+  /// Returns `true` if only whitespace & comments are left.
+  pub(crate) fn is_done(&mut self) -> bool {
+    self.skip_whitespace();
+    self.rest().is_empty()
+  }
+
+  pub(crate) fn eat(&mut self, c: char) -> bool {
+    self.skip_whitespace();
+    if self.peek() == Some(c) {
+      self.pos += c.len_utf8();
+      true
+    } else {
+      false
+    }
+  }
+
+  pub(crate) fn expect(&mut self, c: char) -> Result<()> {
+    if self.eat(c) {
+      Ok(())
+    } else {
+      Err(self.error())
+    }
+  }
+
+  /// Consumes the case-insensitive `keyword`, if it comes next as a word.
+  pub(crate) fn keyword(&mut self, keyword: &str) -> bool {
+    self.skip_whitespace();
+    let rest = self.rest();
+    let matches = rest
+      .get(..keyword.len())
+      .is_some_and(|word| word.eq_ignore_ascii_case(keyword))
+      && !rest[keyword.len()..]
+        .starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == ':');
+    if matches {
+      self.pos += keyword.len();
+    }
+    matches
+  }
+
+  pub(crate) fn error(&self) -> Error {
+    self.error_at(self.pos)
+  }
+
+  fn error_at(&self, pos: usize) -> Error {
+    let before = &self.s[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1);
+    let code = match self.grammar {
+      Grammar::Query => ErrorCode::InvalidQuery,
+      Grammar::Update => ErrorCode::InvalidUpdate,
+      Grammar::Turtle => ErrorCode::InvalidTurtle,
+      Grammar::Rules => ErrorCode::InvalidRules,
+    };
+    Error::syntax(code, line, column + 1)
+  }
+
+  /// Parses `PREFIX` declarations.
+  pub(crate) fn prologue(&mut self) -> Result<()> {
+    while self.prefix()? {}
+    Ok(())
+  }
+
+  /// Parses a `PREFIX` or, in Turtle, `@prefix` declaration, returning
+  /// `false` if none comes next.
+  fn prefix(&mut self) -> Result<bool> {
+    let turtle = self.grammar == Grammar::Turtle && self.keyword("@prefix");
+    if !turtle && !self.keyword("PREFIX") {
+      return Ok(false);
+    }
+    self.skip_whitespace();
+    let end = self.rest().find(':').ok_or_else(|| self.error())?;
+    let prefix = &self.rest()[..end];
+    if !prefix
+      .chars()
+      .all(|c| c.is_alphanumeric() || "_-.".contains(c))
+    {
+      return Err(self.error());
+    }
+    self.pos += end + 1;
+    let namespace = self.iri_ref()?;
+    self.namespaces.bind(prefix, &namespace);
+    if turtle {
+      self.expect('.')?;
+    }
+    Ok(true)
+  }
+
+  /// Parses a Turtle document into its statements.
+  ///
+  /// Blank node property lists (`[ ... ]`), collections, `@base` &
+  /// relative IRIs aren't supported.
+  pub(crate) fn document(&mut self) -> Result<Vec<(Node, String, Node)>> {
+    let mut statements = Vec::new();
+    let mut patterns = Vec::new();
+    while !self.is_done() {
+      if self.prefix()? {
+        continue;
+      }
+      let start = self.pos;
+      self.triples(&mut patterns)?;
+      self.expect('.')?;
+      for pattern in patterns.drain(..) {
+        match pattern {
+          (
+            Term::Node(subject),
+            Term::Node(Node::Http(predicate)),
+            Term::Node(object),
+          ) if !matches!(subject, Node::Literal(_)) => {
+            statements.push((subject, predicate, object))
+          }
+          // Variables & property paths are only valid in queries.
+          _ => return Err(self.error_at(start)),
+        }
+      }
+    }
+    Ok(statements)
+  }
+
+  /// Parses a `{ ... }` group of triples, with `FILTER`s & `SERVICE`
+  /// groups if `filters` is set.
+  pub(crate) fn block(&mut self, filters: bool) -> Result<Block> {
+    self.expect('{')?;
+    let mut block = Block::default();
+    while !self.eat('}') {
+      if filters && self.keyword("FILTER") {
+        block.filters.push(self.filter()?);
+        self.eat('.');
+        continue;
+      }
+      if filters && self.keyword("SERVICE") {
+        block.services.push(self.service()?);
+        self.eat('.');
+        continue;
+      }
+      self.triples(&mut block.patterns)?;
+      if !self.eat('.') {
+        self.expect('}')?;
+        break;
+      }
+    }
+    Ok(block)
+  }
+
+  /// Parses a subject along with its predicates & objects, separated by
+  /// `;` & `,`.
+  fn triples(&mut self, patterns: &mut Vec<(Term, Term, Term)>) -> Result<()> {
+    let subject = self.term()?;
+    loop {
+      let predicate = self.verb()?;
+      loop {
+        let object = self.term()?;
+        patterns.push((subject.clone(), predicate.clone(), object));
+        if !self.eat(',') {
+          break;
+        }
+      }
+      // `;` may be repeated or trail the last predicate.
+      let mut more = false;
+      while self.eat(';') {
+        more = true;
+      }
+      self.skip_whitespace();
+      if !more || matches!(self.peek(), Some('.' | '}')) {
+        return Ok(());
+      }
+    }
+  }
+
+  /// Parses the endpoint & group following `SERVICE`.
+  fn service(&mut self) -> Result<ServiceBlock> {
+    let silent = self.keyword("SILENT");
+    self.skip_whitespace();
+    let start = self.pos;
+    let iri = match self.term()? {
+      Term::Node(Node::Http(iri)) => iri,
+      _ => return Err(self.error_at(start)),
+    };
+    self.skip_whitespace();
+    let start = self.pos;
+    self.block(true)?;
+    Ok(ServiceBlock {
+      iri,
+      silent,
+      group: self.s[start..self.pos].to_string(),
+    })
+  }
+
+  /// Parses the parenthesized expression following `FILTER`.
+  fn filter(&mut self) -> Result<Filter> {
+    self.skip_whitespace();
+    let start = self.pos;
+    let mut depth = 0;
+    let mut quote = None;
+    while let Some(c) = self.bump() {
+      match (quote, c) {
+        (Some(q), c) if c == q => quote = None,
+        (Some(_), '\\') => {
+          self.bump();
+        }
+        (Some(_), _) => {}
+        (None, '"' | '\'') => quote = Some(c),
+        (None, '(') => depth += 1,
+        (None, ')') if depth > 1 => depth -= 1,
+        (None, ')') => break,
+        (None, _) if depth == 0 => return Err(self.error_at(start)),
+        (None, _) => {}
+      }
+    }
+    let filter = format!("FILTER{}", &self.s[start..self.pos]);
+    Filter::parse(&filter).map_err(|_| self.error_at(start))
+  }
+
+  /// Parses a predicate: a variable, `a` or a property path. Paths made of
+  /// a single IRI are returned as nodes.
+  fn verb(&mut self) -> Result<Term> {
+    self.skip_whitespace();
+    if matches!(self.peek(), Some('?' | '$')) {
+      return self.term();
+    }
+    Ok(match self.alternative()? {
+      Path::Iri(iri) => Term::Node(Node::Http(iri)),
+      path => Term::Path(path),
+    })
+  }
+
+  fn alternative(&mut self) -> Result<Path> {
+    let mut path = self.sequence()?;
+    while self.eat('|') {
+      path = path.or(self.sequence()?);
+    }
+    Ok(path)
+  }
+
+  fn sequence(&mut self) -> Result<Path> {
+    let mut path = self.element()?;
+    while self.eat('/') {
+      path = path.then(self.element()?);
+    }
+    Ok(path)
+  }
+
+  fn element(&mut self) -> Result<Path> {
+    let inverse = self.eat('^');
+    let mut path = self.primary()?;
+    loop {
+      // `?` followed by a name starts a variable, not a modifier.
+      let modifier = self.peek();
+      let next = self.rest()[modifier.map_or(0, char::len_utf8)..].chars();
+      path = match (modifier, next.clone().next()) {
+        (Some('+'), _) => path.one_or_more(),
+        (Some('*'), _) => path.zero_or_more(),
+        (Some('?'), c) if !c.is_some_and(|c| c.is_alphanumeric()) => {
+          path.zero_or_one()
+        }
+        _ => break,
+      };
+      self.pos += 1;
+    }
+    Ok(if inverse { path.inverse() } else { path })
+  }
+
+  fn primary(&mut self) -> Result<Path> {
+    if self.eat('(') {
+      let path = self.alternative()?;
+      self.expect(')')?;
+      return Ok(path);
+    }
+    self.skip_whitespace();
+    if self.rest().starts_with('a')
+      && !self.rest()[1..]
+        .starts_with(|c: char| c.is_alphanumeric() || c == ':')
+    {
+      self.pos += 1;
+      return Ok(Path::iri(RDF_TYPE));
+    }
+    match self.peek() {
+      Some('<') => Ok(Path::Iri(self.iri_ref()?)),
+      _ => Ok(Path::Iri(self.prefixed_name()?)),
+    }
+  }
+
+  /// Parses a subject or object: a variable, IRI, blank node or literal.
+  pub(crate) fn term(&mut self) -> Result<Term> {
+    self.skip_whitespace();
+    let node = match self.peek().ok_or_else(|| self.error())? {
+      '?' | '$' => {
+        self.pos += 1;
+        let name = self.name();
+        if name.is_empty() {
+          return Err(self.error());
+        }
+        return Ok(Term::var(name));
+      }
+      '<' => Node::Http(self.iri_ref()?),
+      '"' | '\'' => Node::Literal(self.literal()?),
+      '_' if self.rest().starts_with("_:") => {
+        self.pos += 2;
+        let label = self.name();
+        if label.is_empty() {
+          return Err(self.error());
+        }
+        Node::BlankId(label.to_string())
+      }
+      c if c.is_ascii_digit() || "+-.".contains(c) => {
+        Node::Literal(self.number()?)
+      }
+      _ if self.keyword("true") => Node::Literal(true.into()),
+      _ if self.keyword("false") => Node::Literal(false.into()),
+      _ => Node::Http(self.prefixed_name()?),
+    };
+    Ok(Term::Node(node))
+  }
+
+  /// Parses a variable, e.g. in a projection, returning its name.
+  pub(crate) fn var(&mut self) -> Option<String> {
+    self.skip_whitespace();
+    if !matches!(self.peek(), Some('?' | '$')) {
+      return None;
+    }
+    let start = self.pos;
+    self.pos += 1;
+    let name = self.name();
+    if name.is_empty() {
+      self.pos = start;
+      return None;
+    }
+    Some(name.to_string())
+  }
+
+  /// Parses a non-negative integer, e.g. of `LIMIT`.
+  pub(crate) fn integer(&mut self) -> Result<usize> {
+    self.skip_whitespace();
+    let end = self
+      .rest()
+      .find(|c: char| !c.is_ascii_digit())
+      .unwrap_or(self.rest().len());
+    let n = self.rest()[..end].parse().map_err(|_| self.error())?;
+    self.pos += end;
+    Ok(n)
+  }
+
+  /// Returns the name of a variable or blank node label.
+  /// Consumes a bare identifier (e.g. a Datalog relation or variable),
+  /// unless it's the prefix of a prefixed name.
+  pub(crate) fn identifier(&mut self) -> Option<&'a str> {
+    self.skip_whitespace();
+    if !self.peek().is_some_and(|c| c.is_alphabetic() || c == '_') {
+      return None;
+    }
+    let start = self.pos;
+    let name = self.name();
+    if self.rest().starts_with(':') && !self.rest().starts_with(":-") {
+      self.pos = start;
+      return None;
+    }
+    Some(name)
+  }
+
+  fn name(&mut self) -> &'a str {
+    let start = self.pos;
+    while let Some(c) = self.peek().filter(|c| c.is_alphanumeric() || *c == '_')
+    {
+      self.pos += c.len_utf8();
+    }
+    &self.s[start..self.pos]
+  }
+
+  fn iri_ref(&mut self) -> Result<String> {
+    self.expect('<')?;
+    let end = self.rest().find('>').ok_or_else(|| self.error())?;
+    let iri = self.rest()[..end].to_string();
+    self.pos += end + 1;
+    Ok(iri)
+  }
+
+  fn prefixed_name(&mut self) -> Result<String> {
+    let start = self.pos;
+    let rest = self.rest();
+    let end = rest
+      .find(|c: char| c.is_whitespace() || "{}()[];,<>\"'#/|^*+?".contains(c))
+      .unwrap_or(rest.len());
+    // A local name can't end with `.`, so it belongs to the triples.
+    let name = rest[..end].trim_end_matches('.');
+    if !name.contains(':') {
+      return Err(self.error());
+    }
+    self.pos += name.len();
+    self
+      .namespaces
+      .expand(name)
+      .map_err(|_| self.error_at(start))
+  }
+
+  fn literal(&mut self) -> Result<DType> {
+    let quote = self.bump().ok_or_else(|| self.error())?;
+    // `"""` & `'''` strings may span several lines.
+    let rest_of_long = quote.to_string().repeat(2);
+    let long = self.rest().starts_with(&rest_of_long);
+    if long {
+      self.pos += 2;
+    }
+    let mut lexical = String::new();
+    loop {
+      match self.bump().ok_or_else(|| self.error())? {
+        c if c == quote && !long => break,
+        c if c == quote && self.rest().starts_with(&rest_of_long) => {
+          self.pos += 2;
+          break;
+        }
+        '\\' => lexical.push(self.escape()?),
+        '\n' | '\r' if !long => return Err(self.error()),
+        c => lexical.push(c),
+      }
+    }
+
+    if self.rest().starts_with('@') {
+      self.pos += 1;
+      let start = self.pos;
+      while matches!(self.peek(), Some(c) if c.is_alphanumeric() || c == '-') {
+        self.pos += 1;
+      }
+      if start == self.pos {
+        return Err(self.error());
+      }
+      let mut value = Map::new();
+      value.insert("@value".to_string(), lexical.into());
+      value.insert("@language".to_string(), self.s[start..self.pos].into());
+      return Ok(DType::Object(value));
+    }
+    if self.rest().starts_with("^^") {
+      self.pos += 2;
+      let datatype = match self.peek() {
+        Some('<') => self.iri_ref()?,
+        _ => self.prefixed_name()?,
+      };
+      return Ok(typed_value(lexical, &datatype));
+    }
+    Ok(lexical.into())
+  }
+
+  fn escape(&mut self) -> Result<char> {
+    let c = match self.bump().ok_or_else(|| self.error())? {
+      't' => '\t',
+      'b' => '\u{8}',
+      'n' => '\n',
+      'r' => '\r',
+      'f' => '\u{c}',
+      '"' => '"',
+      '\'' => '\'',
+      '\\' => '\\',
+      'u' => self.unicode(4)?,
+      'U' => self.unicode(8)?,
+      _ => return Err(self.error()),
+    };
+    Ok(c)
+  }
+
+  fn unicode(&mut self, digits: usize) -> Result<char> {
+    let hex = self.rest().get(..digits).ok_or_else(|| self.error())?;
+    let c = u32::from_str_radix(hex, 16)
+      .ok()
+      .and_then(char::from_u32)
+      .ok_or_else(|| self.error())?;
+    self.pos += digits;
+    Ok(c)
+  }
+
+  /// Parses an integer, decimal or double.
+  fn number(&mut self) -> Result<DType> {
+    let start = self.pos;
+    if matches!(self.peek(), Some('+' | '-')) {
+      self.pos += 1;
+    }
+    let digits = |p: &mut Self| {
+      let from = p.pos;
+      while matches!(p.peek(), Some(c) if c.is_ascii_digit()) {
+        p.pos += 1;
+      }
+      p.pos - from
+    };
+    let mut datatype = "integer";
+    let mut count = digits(self);
+    // A `.` not followed by a digit ends the triple instead.
+    if self.rest().starts_with('.')
+      && self.rest()[1..].starts_with(|c: char| c.is_ascii_digit())
+    {
+      self.pos += 1;
+      count += digits(self);
+      datatype = "decimal";
+    }
+    if count > 0 && matches!(self.peek(), Some('e' | 'E')) {
+      self.pos += 1;
+      if matches!(self.peek(), Some('+' | '-')) {
+        self.pos += 1;
+      }
+      if digits(self) == 0 {
+        return Err(self.error());
+      }
+      datatype = "double";
+    }
+    if count == 0 {
+      self.pos = start;
+      return Err(self.error());
+    }
+    let lexical = self.s[start..self.pos].trim_start_matches('+');
+    Ok(typed_value(
+      lexical.to_string(),
+      &format!("{}{}", XSD, datatype),
+    ))
+  }
+}
@@ -0,0 +1,421 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+use crate::{
+  error::{Error, ErrorCode},
+  graph::{KnowledgeGraph, Node},
+  Result,
+};
+
+/// `Path` is a [SPARQL property path] used in the predicate position of a
+/// `Query` pattern to match chains of predicates instead of a single one.
+///
+/// | Syntax      | Matches                                     |
+/// |-------------|---------------------------------------------|
+/// | `<iri>`     | the predicate `iri`                         |
+/// | `^p`        | `p` from object to subject                  |
+/// | `p/q`       | `p` followed by `q`                         |
+/// | `p\|q`      | either `p` or `q`                           |
+/// | `p+`        | one or more `p`                             |
+/// | `p*`        | zero or more `p`                            |
+/// | `p?`        | zero or one `p`                             |
+/// | `(p)`       | grouping                                    |
+///
+/// Full IRIs must be written in angle brackets since they contain `/`.
+/// Names without operators (e.g. `knows`) are used as-is.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::query::{Path, Query};
+///
+/// let person = |name: &str| Node::Http(format!("https://example.com/{}", name));
+/// let knows = Predicate::Literal("https://schema.org/knows".to_string());
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(person("Ada"), knows.clone(), person("Bob"));
+/// graph.insert(person("Bob"), knows.clone(), person("Cy"));
+/// graph.insert(person("Cy"), knows, person("Di"));
+///
+/// let path = Path::parse("<https://schema.org/knows>+").unwrap();
+/// let query = Query::new().pattern(person("Ada"), path, "?friend");
+/// assert_eq!(graph.query(&query).len(), 3);
+///
+/// let path = Path::parse("<https://schema.org/knows>/<https://schema.org/knows>")
+///   .unwrap();
+/// let query = Query::new().pattern(person("Ada"), path, "?friend");
+/// assert_eq!(graph.query(&query)[0]["friend"], "https://example.com/Cy");
+/// ```
+///
+/// [SPARQL property path]: https://www.w3.org/TR/sparql11-query/#propertypaths
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Path {
+  /// A single predicate, compared against its full IRI.
+  Iri(String),
+  /// The path traversed from object to subject (`^p`).
+  Inverse(Box<Path>),
+  /// Each path followed by the next (`p/q`).
+  Sequence(Vec<Path>),
+  /// Any of the paths (`p|q`).
+  Alternative(Vec<Path>),
+  /// The path repeated at least once (`p+`).
+  OneOrMore(Box<Path>),
+  /// The path repeated any number of times, including none (`p*`).
+  ZeroOrMore(Box<Path>),
+  /// The path taken at most once (`p?`).
+  ZeroOrOne(Box<Path>),
+}
+
+impl Path {
+  /// Creates a path matching the single predicate `iri`.
+  pub fn iri(iri: &str) -> Path {
+    Path::Iri(iri.to_string())
+  }
+
+  /// Parses the SPARQL property path syntax.
+  pub fn parse(s: &str) -> Result<Path> {
+    let mut parser = Parser { s, pos: 0 };
+    let path = parser.alternative()?;
+    parser.skip_whitespace();
+    if parser.pos < s.len() {
+      return Err(parser.error());
+    }
+    Ok(path)
+  }
+
+  /// Returns `self` traversed from object to subject.
+  pub fn inverse(self) -> Path {
+    Path::Inverse(Box::new(self))
+  }
+
+  /// Returns `self` followed by `next`.
+  pub fn then(self, next: Path) -> Path {
+    match self {
+      Path::Sequence(mut paths) => {
+        paths.push(next);
+        Path::Sequence(paths)
+      }
+      path => Path::Sequence(vec![path, next]),
+    }
+  }
+
+  /// Returns a path matching either `self` or `other`.
+  pub fn or(self, other: Path) -> Path {
+    match self {
+      Path::Alternative(mut paths) => {
+        paths.push(other);
+        Path::Alternative(paths)
+      }
+      path => Path::Alternative(vec![path, other]),
+    }
+  }
+
+  /// Returns `self` repeated at least once.
+  pub fn one_or_more(self) -> Path {
+    Path::OneOrMore(Box::new(self))
+  }
+
+  /// Returns `self` repeated any number of times.
+  pub fn zero_or_more(self) -> Path {
+    Path::ZeroOrMore(Box::new(self))
+  }
+
+  /// Returns `self` taken at most once.
+  pub fn zero_or_one(self) -> Path {
+    Path::ZeroOrOne(Box::new(self))
+  }
+
+  /// Rewrites every predicate IRI with `f`, e.g. to expand prefixed names.
+  pub fn map_iris<F>(self, f: &F) -> Result<Path>
+  where
+    F: Fn(&str) -> Result<String>,
+  {
+    let map_all = |paths: Vec<Path>| -> Result<Vec<Path>> {
+      paths.into_iter().map(|p| p.map_iris(f)).collect()
+    };
+    Ok(match self {
+      Path::Iri(iri) => Path::Iri(f(&iri)?),
+      Path::Inverse(p) => Path::Inverse(Box::new(p.map_iris(f)?)),
+      Path::Sequence(paths) => Path::Sequence(map_all(paths)?),
+      Path::Alternative(paths) => Path::Alternative(map_all(paths)?),
+      Path::OneOrMore(p) => Path::OneOrMore(Box::new(p.map_iris(f)?)),
+      Path::ZeroOrMore(p) => Path::ZeroOrMore(Box::new(p.map_iris(f)?)),
+      Path::ZeroOrOne(p) => Path::ZeroOrOne(Box::new(p.map_iris(f)?)),
+    })
+  }
+
+  /// Returns every distinct `(subject, object)` pair connected by the path,
+  /// starting from `start` if given.
+  pub(crate) fn evaluate(
+    &self,
+    graph: &KnowledgeGraph,
+    start: Option<&Node>,
+  ) -> Vec<(Node, Node)> {
+    let mut pairs = Vec::new();
+    match self {
+      Path::Iri(iri) => {
+        for triple in graph.matches(start, Some(iri), None) {
+          push(
+            &mut pairs,
+            triple.source().clone(),
+            triple.destination().clone(),
+          );
+        }
+      }
+      Path::Inverse(path) => {
+        for (s, o) in path.evaluate(graph, None) {
+          if start.is_none_or(|start| *start == o) {
+            push(&mut pairs, o, s);
+          }
+        }
+      }
+      Path::Sequence(paths) => {
+        let mut current = match start {
+          Some(start) => vec![(start.clone(), start.clone())],
+          None => identity(graph),
+        };
+        for path in paths {
+          let mut next = Vec::new();
+          for (s, mid) in current {
+            for (_, o) in path.evaluate(graph, Some(&mid)) {
+              push(&mut next, s.clone(), o);
+            }
+          }
+          current = next;
+        }
+        pairs = current;
+      }
+      Path::Alternative(paths) => {
+        for path in paths {
+          for (s, o) in path.evaluate(graph, start) {
+            push(&mut pairs, s, o);
+          }
+        }
+      }
+      Path::OneOrMore(path) => pairs = closure(graph, path, start),
+      Path::ZeroOrMore(path) => {
+        pairs = zero_length(graph, start);
+        for (s, o) in closure(graph, path, start) {
+          push(&mut pairs, s, o);
+        }
+      }
+      Path::ZeroOrOne(path) => {
+        pairs = zero_length(graph, start);
+        for (s, o) in path.evaluate(graph, start) {
+          push(&mut pairs, s, o);
+        }
+      }
+    }
+    pairs
+  }
+}
+
+impl fmt::Display for Path {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let join = |f: &mut fmt::Formatter, paths: &[Path], sep| {
+      f.write_str("(")?;
+      for (i, path) in paths.iter().enumerate() {
+        if i > 0 {
+          f.write_str(sep)?;
+        }
+        write!(f, "{}", path)?;
+      }
+      f.write_str(")")
+    };
+    match self {
+      Path::Iri(iri) => write!(f, "<{}>", iri),
+      Path::Inverse(path) => write!(f, "^{}", path),
+      Path::Sequence(paths) => join(f, paths, "/"),
+      Path::Alternative(paths) => join(f, paths, "|"),
+      Path::OneOrMore(path) => write!(f, "{}+", path),
+      Path::ZeroOrMore(path) => write!(f, "{}*", path),
+      Path::ZeroOrOne(path) => write!(f, "{}?", path),
+    }
+  }
+}
+
+fn push(pairs: &mut Vec<(Node, Node)>, s: Node, o: Node) {
+  if !pairs.iter().any(|(ps, po)| *ps == s && *po == o) {
+    pairs.push((s, o));
+  }
+}
+
+/// Every node of the graph paired with itself.
+fn identity(graph: &KnowledgeGraph) -> Vec<(Node, Node)> {
+  let mut pairs = Vec::new();
+  for triple in graph.triples() {
+    for node in [triple.source(), triple.destination()] {
+      push(&mut pairs, node.clone(), node.clone());
+    }
+  }
+  pairs
+}
+
+fn zero_length(
+  graph: &KnowledgeGraph,
+  start: Option<&Node>,
+) -> Vec<(Node, Node)> {
+  match start {
+    Some(start) => vec![(start.clone(), start.clone())],
+    None => identity(graph),
+  }
+}
+
+/// Transitive closure of `path` from every start node, breadth first.
+fn closure(
+  graph: &KnowledgeGraph,
+  path: &Path,
+  start: Option<&Node>,
+) -> Vec<(Node, Node)> {
+  let starts: Vec<Node> = match start {
+    Some(start) => vec![start.clone()],
+    None => {
+      let mut starts = Vec::new();
+      for (s, _) in path.evaluate(graph, None) {
+        if !starts.contains(&s) {
+          starts.push(s);
+        }
+      }
+      starts
+    }
+  };
+
+  let mut pairs = Vec::new();
+  for s in starts {
+    let mut reached: Vec<Node> = Vec::new();
+    let mut frontier = vec![s.clone()];
+    while let Some(node) = frontier.pop() {
+      for (_, o) in path.evaluate(graph, Some(&node)) {
+        if !reached.contains(&o) {
+          reached.push(o.clone());
+          frontier.push(o);
+        }
+      }
+    }
+    for o in reached {
+      push(&mut pairs, s.clone(), o);
+    }
+  }
+  pairs
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Parser
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+struct Parser<'a> {
+  s: &'a str,
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn peek(&self) -> Option<char> {
+    self.s[self.pos..].chars().next()
+  }
+
+  fn skip_whitespace(&mut self) {
+    while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+      self.pos += 1;
+    }
+  }
+
+  fn eat(&mut self, c: char) -> bool {
+    self.skip_whitespace();
+    if self.peek() == Some(c) {
+      self.pos += c.len_utf8();
+      true
+    } else {
+      false
+    }
+  }
+
+  fn error(&self) -> Error {
+    Error::syntax(ErrorCode::InvalidPath, 1, self.pos + 1)
+  }
+
+  fn alternative(&mut self) -> Result<Path> {
+    let mut paths = vec![self.sequence()?];
+    while self.eat('|') {
+      paths.push(self.sequence()?);
+    }
+    Ok(match paths.len() {
+      1 => paths.remove(0),
+      _ => Path::Alternative(paths),
+    })
+  }
+
+  fn sequence(&mut self) -> Result<Path> {
+    let mut paths = vec![self.element()?];
+    while self.eat('/') {
+      paths.push(self.element()?);
+    }
+    Ok(match paths.len() {
+      1 => paths.remove(0),
+      _ => Path::Sequence(paths),
+    })
+  }
+
+  fn element(&mut self) -> Result<Path> {
+    let inverse = self.eat('^');
+    let mut path = self.primary()?;
+    loop {
+      path = if self.eat('+') {
+        path.one_or_more()
+      } else if self.eat('*') {
+        path.zero_or_more()
+      } else if self.eat('?') {
+        path.zero_or_one()
+      } else {
+        break;
+      };
+    }
+    Ok(if inverse { path.inverse() } else { path })
+  }
+
+  fn primary(&mut self) -> Result<Path> {
+    if self.eat('(') {
+      let path = self.alternative()?;
+      return if self.eat(')') {
+        Ok(path)
+      } else {
+        Err(self.error())
+      };
+    }
+    if self.eat('<') {
+      let end = self.s[self.pos..].find('>').ok_or_else(|| self.error())?;
+      let iri = &self.s[self.pos..self.pos + end];
+      self.pos += end + 1;
+      return Ok(Path::iri(iri));
+    }
+
+    let start = self.pos;
+    while matches!(
+      self.peek(),
+      Some(c) if !c.is_whitespace() && !"/|^()<>+*?".contains(c)
+    ) {
+      self.pos += self.peek().map_or(1, char::len_utf8);
+    }
+    if start == self.pos {
+      return Err(self.error());
+    }
+    Ok(Path::iri(&self.s[start..self.pos]))
+  }
+}
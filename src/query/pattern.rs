@@ -0,0 +1,503 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+  dtype::{from_dtype, DType, Map},
+  graph::{KnowledgeGraph, Node, Triple},
+  query::{planner, Filter, Path},
+  Result,
+};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Term
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Term` is a single position of a triple pattern: either a variable to
+/// be bound, a fixed `Node` or, in the predicate position, a property
+/// `Path`.
+///
+/// Strings starting with `?` convert into variables, any other string into
+/// an IRI (`Node::Http`).
+#[derive(Clone, Debug, PartialEq)]
+pub enum Term {
+  /// A variable, named without the leading `?`.
+  Var(String),
+  /// A fixed node. Predicates are compared against the node's IRI.
+  Node(Node),
+  /// A property path. Only valid as predicate, it never matches as subject
+  /// or object.
+  Path(Path),
+}
+
+impl Term {
+  /// Creates a variable named `name`.
+  pub fn var(name: &str) -> Term {
+    Term::Var(name.trim_start_matches('?').to_string())
+  }
+}
+
+impl From<&str> for Term {
+  fn from(s: &str) -> Term {
+    match s.strip_prefix('?') {
+      Some(name) => Term::Var(name.to_string()),
+      None => Term::Node(Node::Http(s.to_string())),
+    }
+  }
+}
+
+impl From<String> for Term {
+  fn from(s: String) -> Term {
+    Term::from(s.as_str())
+  }
+}
+
+impl From<Node> for Term {
+  fn from(node: Node) -> Term {
+    Term::Node(node)
+  }
+}
+
+impl From<Path> for Term {
+  fn from(path: Path) -> Term {
+    Term::Path(path)
+  }
+}
+
+impl From<DType> for Term {
+  fn from(value: DType) -> Term {
+    Term::Node(Node::Literal(value))
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Query
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Query` is a conjunction of triple patterns. Evaluating it yields one
+/// binding row per solution, mapping every variable to its value.
+///
+/// Patterns aren't joined in the order they're added: a planner orders
+/// them by their cardinality in the graph, starting with the most
+/// selective one. Rows therefore come in no particular order.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::query::Query;
+///
+/// let john = Node::Http("https://example.com/John".to_string());
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   john.clone(),
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal("John".into()),
+/// );
+/// graph.insert(
+///   john,
+///   Predicate::Literal("https://schema.org/age".to_string()),
+///   Node::Literal(42.into()),
+/// );
+///
+/// let query = Query::new()
+///   .pattern("?person", "https://schema.org/name", "?name")
+///   .pattern("?person", "https://schema.org/age", "?age");
+///
+/// let rows = graph.query(&query);
+/// assert_eq!(rows.len(), 1);
+/// assert_eq!(rows[0]["name"], "John");
+/// assert_eq!(rows[0]["age"], 42);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+  patterns: Vec<(Term, Term, Term)>,
+  filters: Vec<Filter>,
+  source: Option<String>,
+  min_confidence: Option<f64>,
+  confidence: Option<(String, Aggregation)>,
+}
+
+pub(crate) type Bindings = HashMap<String, Node>;
+
+/// `Aggregation` combines the confidences of the statements a query
+/// solution (or an inferred statement, see `Reasoner`) rests on.
+/// Statements without a confidence are taken for certain (`1.0`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Aggregation {
+  /// The smallest confidence: a chain is as strong as its weakest link.
+  #[default]
+  Min,
+  /// The product of the confidences, as if they were independent.
+  Product,
+}
+
+impl Aggregation {
+  pub(crate) fn combine(self, a: f64, b: f64) -> f64 {
+    match self {
+      Aggregation::Min => a.min(b),
+      Aggregation::Product => a * b,
+    }
+  }
+}
+
+impl Query {
+  /// Creates an empty query, matching a single empty row.
+  pub fn new() -> Query {
+    Query {
+      patterns: Vec::new(),
+      filters: Vec::new(),
+      source: None,
+      min_confidence: None,
+      confidence: None,
+    }
+  }
+
+  /// Adds a triple pattern every solution must satisfy.
+  pub fn pattern<S, P, O>(mut self, subject: S, predicate: P, object: O) -> Self
+  where
+    S: Into<Term>,
+    P: Into<Term>,
+    O: Into<Term>,
+  {
+    self
+      .patterns
+      .push((subject.into(), predicate.into(), object.into()));
+    self
+  }
+
+  /// Keeps only the solutions passing `filter`.
+  pub fn filter(mut self, filter: Filter) -> Self {
+    self.filters.push(filter);
+    self
+  }
+
+  /// Only matches triple patterns against statements from `source` (see
+  /// `Provenance`). Property paths aren't restricted.
+  pub fn from_source(mut self, source: &str) -> Self {
+    self.source = Some(source.to_string());
+    self
+  }
+
+  /// Only matches triple patterns against statements with a confidence of
+  /// at least `threshold`; statements without a confidence are taken for
+  /// certain. Property paths aren't restricted.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate, Provenance};
+  /// use sage::query::Query;
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// for (city, confidence) in [("London", 0.9), ("Paris", 0.2)] {
+  ///   graph.insert_with_provenance(
+  ///     Node::Http("https://example.com/Ada".to_string()),
+  ///     Predicate::Literal("https://schema.org/birthPlace".to_string()),
+  ///     Node::Literal(city.into()),
+  ///     Provenance::new("extractor").confidence(confidence),
+  ///   );
+  /// }
+  ///
+  /// let query = Query::new()
+  ///   .pattern("?person", "https://schema.org/birthPlace", "?city")
+  ///   .min_confidence(0.5);
+  /// let rows = graph.query(&query);
+  /// assert_eq!(rows.len(), 1);
+  /// assert_eq!(rows[0]["city"], "London");
+  /// ```
+  pub fn min_confidence(mut self, threshold: f64) -> Self {
+    self.min_confidence = Some(threshold);
+    self
+  }
+
+  /// Binds the confidence of every solution to `?var`: the confidences of
+  /// the statements matched by its triple patterns, combined by
+  /// `aggregation`. Property paths count as certain.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate, Provenance};
+  /// use sage::query::{Aggregation, Query};
+  ///
+  /// let node = |name: &str| Node::Http(format!("https://example.com/{}", name));
+  /// let knows = Predicate::Literal("https://schema.org/knows".to_string());
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// for (a, b, confidence) in [("Ada", "Bob", 0.8), ("Bob", "Cy", 0.5)] {
+  ///   graph.insert_with_provenance(
+  ///     node(a),
+  ///     knows.clone(),
+  ///     node(b),
+  ///     Provenance::new("nlp").confidence(confidence),
+  ///   );
+  /// }
+  ///
+  /// let query = Query::new()
+  ///   .pattern("?a", "https://schema.org/knows", "?b")
+  ///   .pattern("?b", "https://schema.org/knows", "?c")
+  ///   .confidence("p", Aggregation::Product);
+  /// assert_eq!(graph.query(&query)[0]["p"], 0.4);
+  /// ```
+  pub fn confidence(mut self, var: &str, aggregation: Aggregation) -> Self {
+    let var = var.trim_start_matches('?').to_string();
+    self.confidence = Some((var, aggregation));
+    self
+  }
+
+  /// Returns the variables of the triple patterns, in order of appearance.
+  pub(crate) fn variables(&self) -> Vec<String> {
+    let mut variables: Vec<String> = Vec::new();
+    for (s, p, o) in &self.patterns {
+      for term in [s, p, o] {
+        match term {
+          Term::Var(var) if !variables.contains(var) => {
+            variables.push(var.clone())
+          }
+          _ => {}
+        }
+      }
+    }
+    variables
+  }
+
+  /// Returns `true` if `triple` passes the provenance restrictions.
+  fn admits(&self, triple: &Triple) -> bool {
+    let source = self.source.as_deref().is_none_or(|source| {
+      triple
+        .provenance()
+        .is_some_and(|p| p.source.as_deref() == Some(source))
+    });
+    let confident = self
+      .min_confidence
+      .is_none_or(|min| triple.confidence().unwrap_or(1.0) >= min);
+    source && confident
+  }
+
+  /// Evaluates the query against `graph`, returning one `DType` object per
+  /// solution. Literals are bound to their value, other nodes to their IRI.
+  pub(crate) fn rows(&self, graph: &KnowledgeGraph) -> Vec<DType> {
+    let aggregation = self.confidence.as_ref().map(|(_, a)| *a);
+    self
+      .solve(graph, aggregation.unwrap_or_default())
+      .into_iter()
+      .map(|(bindings, confidence)| {
+        let mut row: Map<String, DType> = bindings
+          .into_iter()
+          .map(|(name, node)| (name, to_term(&node)))
+          .collect();
+        if let Some((var, _)) = &self.confidence {
+          row.insert(var.clone(), confidence.into());
+        }
+        DType::Object(row)
+      })
+      .collect()
+  }
+
+  /// Evaluates the query against `graph` and deserializes every row into
+  /// `T`.
+  pub(crate) fn select<T>(&self, graph: &KnowledgeGraph) -> Result<Vec<T>>
+  where
+    T: DeserializeOwned,
+  {
+    self.rows(graph).into_iter().map(from_dtype).collect()
+  }
+
+  /// Returns every solution along with its confidence, combined by
+  /// `aggregation`.
+  pub(crate) fn solve(
+    &self,
+    graph: &KnowledgeGraph,
+    aggregation: Aggregation,
+  ) -> Vec<(Bindings, f64)> {
+    self.solve_from(graph, aggregation, Bindings::new())
+  }
+
+  /// Returns every solution extending `bindings`, along with its
+  /// confidence.
+  pub(crate) fn solve_from(
+    &self,
+    graph: &KnowledgeGraph,
+    aggregation: Aggregation,
+    bindings: Bindings,
+  ) -> Vec<(Bindings, f64)> {
+    self.solve_over(&[graph], aggregation, bindings)
+  }
+
+  /// Returns every solution extending `bindings` against the union of
+  /// `graphs`, along with its confidence. Statements found in several
+  /// graphs are matched once; property paths don't cross graphs.
+  pub(crate) fn solve_over(
+    &self,
+    graphs: &[&KnowledgeGraph],
+    aggregation: Aggregation,
+    bindings: Bindings,
+  ) -> Vec<(Bindings, f64)> {
+    let bound: HashSet<&str> = bindings.keys().map(String::as_str).collect();
+    let order = self.plan(graphs, &bound);
+    self.solve_ordered(graphs, aggregation, bindings, &order)
+  }
+
+  /// Returns the order in which `solve_over` joins the patterns against
+  /// `graphs`, given the variables already `bound`.
+  pub(crate) fn plan(
+    &self,
+    graphs: &[&KnowledgeGraph],
+    bound: &HashSet<&str>,
+  ) -> Vec<usize> {
+    planner::plan(graphs, &self.patterns, bound)
+  }
+
+  /// Returns every solution like `solve_over`, joining the patterns in
+  /// `order` (see `plan`).
+  pub(crate) fn solve_ordered(
+    &self,
+    graphs: &[&KnowledgeGraph],
+    aggregation: Aggregation,
+    bindings: Bindings,
+    order: &[usize],
+  ) -> Vec<(Bindings, f64)> {
+    let mut solutions = vec![(bindings, 1.0)];
+    for &i in order {
+      let (subject, predicate, object) = &self.patterns[i];
+      if matches!(subject, Term::Path(_)) || matches!(object, Term::Path(_)) {
+        return Vec::new();
+      }
+      if let Term::Path(path) = predicate {
+        solutions = graphs
+          .iter()
+          .flat_map(|graph| {
+            solve_path(graph, &solutions, subject, path, object)
+          })
+          .collect();
+        continue;
+      }
+
+      let mut next = Vec::new();
+      for (bindings, confidence) in &solutions {
+        let s = resolve(subject, bindings);
+        let p = resolve(predicate, bindings).map(|p| p.to_string());
+        let o = resolve(object, bindings);
+
+        let mut seen = HashSet::new();
+        let triples = graphs
+          .iter()
+          .flat_map(|graph| graph.matches(s, p.as_deref(), o));
+        for triple in triples {
+          if !self.admits(triple) {
+            continue;
+          }
+          if graphs.len() > 1
+            && !seen.insert((
+              triple.source(),
+              triple.predicate().to_string(),
+              triple.destination(),
+            ))
+          {
+            continue;
+          }
+          let mut row = bindings.clone();
+          let predicate_node = Node::Http(triple.predicate().to_string());
+          if bind(&mut row, subject, triple.source())
+            && bind(&mut row, predicate, &predicate_node)
+            && bind(&mut row, object, triple.destination())
+          {
+            let certainty = triple.confidence().unwrap_or(1.0);
+            next.push((row, aggregation.combine(*confidence, certainty)));
+          }
+        }
+      }
+      solutions = next;
+    }
+    solutions
+      .retain(|(bindings, _)| self.filters.iter().all(|f| f.accepts(bindings)));
+    solutions
+  }
+}
+
+/// Extends every solution with the pairs connected by `path`.
+fn solve_path(
+  graph: &KnowledgeGraph,
+  solutions: &[(Bindings, f64)],
+  subject: &Term,
+  path: &Path,
+  object: &Term,
+) -> Vec<(Bindings, f64)> {
+  let mut next = Vec::new();
+  for (bindings, confidence) in solutions {
+    let o = resolve(object, bindings);
+    for (source, destination) in
+      path.evaluate(graph, resolve(subject, bindings))
+    {
+      if o.is_some_and(|o| *o != destination) {
+        continue;
+      }
+      let mut row = bindings.clone();
+      if bind(&mut row, subject, &source)
+        && bind(&mut row, object, &destination)
+      {
+        next.push((row, *confidence));
+      }
+    }
+  }
+  next
+}
+
+/// Returns the node `term` stands for, if it is fixed or already bound.
+pub(crate) fn resolve<'a>(
+  term: &'a Term,
+  bindings: &'a Bindings,
+) -> Option<&'a Node> {
+  match term {
+    Term::Node(node) => Some(node),
+    Term::Var(name) => bindings.get(name),
+    Term::Path(_) => None,
+  }
+}
+
+/// Binds `term` to `node`, returning `false` if it's bound to another node
+/// (e.g. `?x` repeated within one pattern).
+fn bind(bindings: &mut Bindings, term: &Term, node: &Node) -> bool {
+  match term {
+    Term::Node(_) | Term::Path(_) => true,
+    Term::Var(name) => match bindings.get(name) {
+      Some(bound) => bound == node,
+      None => {
+        bindings.insert(name.clone(), node.clone());
+        true
+      }
+    },
+  }
+}
+
+/// Literals are returned as their `DType`; everything else as its IRI.
+pub(crate) fn to_term(node: &Node) -> DType {
+  match node {
+    Node::Literal(value) => value.clone(),
+    Node::Multiple(nodes) => nodes.iter().map(to_term).collect(),
+    node => node.to_string().into(),
+  }
+}
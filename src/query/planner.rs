@@ -0,0 +1,150 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Join ordering of basic graph patterns.
+//!
+//! Patterns are joined one at a time, each extending the solutions so far,
+//! so the order decides how many partial solutions are carried around. The
+//! planner reads the cardinality of every pattern off the indexes the
+//! graphs keep up to date, then greedily picks the cheapest pattern sharing
+//! a variable with the ones already joined, estimating how much the bound
+//! variables narrow it down.
+
+use std::collections::HashSet;
+
+use crate::{
+  graph::{Cardinality, KnowledgeGraph, Node},
+  query::Term,
+};
+
+/// Returns the order in which to join `patterns`, given the variables
+/// already `bound` before the first one.
+///
+/// Statistics count every statement, including those a query's source or
+/// confidence filters leave out: they only steer the order.
+pub(crate) fn plan(
+  graphs: &[&KnowledgeGraph],
+  patterns: &[(Term, Term, Term)],
+  bound: &HashSet<&str>,
+) -> Vec<usize> {
+  if patterns.len() < 2 {
+    return (0..patterns.len()).collect();
+  }
+
+  let statistics: Vec<Option<Cardinality>> = patterns
+    .iter()
+    .map(|pattern| statistics(graphs, pattern))
+    .collect();
+  let len: usize = graphs.iter().map(|graph| graph.len()).sum();
+  let mut bound = bound.clone();
+  let mut remaining: Vec<usize> = (0..patterns.len()).collect();
+  let mut order = Vec::with_capacity(patterns.len());
+  while !remaining.is_empty() {
+    // Avoid cartesian products: prefer patterns joining on a bound variable.
+    let connected: Vec<usize> = remaining
+      .iter()
+      .copied()
+      .filter(|&i| variables(&patterns[i]).any(|var| bound.contains(var)))
+      .collect();
+    let candidates = if connected.is_empty() {
+      &remaining
+    } else {
+      &connected
+    };
+
+    let estimates = candidates.iter().map(|&i| {
+      let cost = match &statistics[i] {
+        Some(statistics) => estimate(&patterns[i], statistics, &bound),
+        // Property paths are evaluated from scratch, whatever is bound.
+        None => len as f64,
+      };
+      (i, cost)
+    });
+    let (best, _) = estimates
+      .min_by(|(i, a), (j, b)| a.total_cmp(b).then(i.cmp(j)))
+      .unwrap_or((remaining[0], 0.0));
+
+    remaining.retain(|&i| i != best);
+    bound.extend(variables(&patterns[best]));
+    order.push(best);
+  }
+  order
+}
+
+/// Returns the cardinality of `pattern` over the union of `graphs`, `None`
+/// for property paths. Distinct terms are summed, so statements found in
+/// several graphs count more than once.
+fn statistics(
+  graphs: &[&KnowledgeGraph],
+  pattern: &(Term, Term, Term),
+) -> Option<Cardinality> {
+  let (s, p, o) = pattern;
+  if let Term::Path(_) = p {
+    return None;
+  }
+  let p = fixed(p).map(Node::to_string);
+  let mut total = Cardinality::default();
+  for graph in graphs {
+    let cardinality = graph.cardinality(fixed(s), p.as_deref(), fixed(o));
+    total.statements += cardinality.statements;
+    total.subjects += cardinality.subjects;
+    total.predicates += cardinality.predicates;
+    total.objects += cardinality.objects;
+  }
+  Some(total)
+}
+
+/// Estimates the statements `pattern` matches per partial solution once
+/// the `bound` variables have a value, assuming values are uniformly
+/// distributed.
+fn estimate(
+  pattern: &(Term, Term, Term),
+  statistics: &Cardinality,
+  bound: &HashSet<&str>,
+) -> f64 {
+  let (s, p, o) = pattern;
+  let is_bound = |term: &Term| match term {
+    Term::Var(var) => bound.contains(var.as_str()),
+    _ => false,
+  };
+
+  let mut estimate = statistics.statements as f64;
+  for (term, distinct) in [
+    (s, statistics.subjects),
+    (p, statistics.predicates),
+    (o, statistics.objects),
+  ] {
+    if is_bound(term) {
+      estimate /= distinct.max(1) as f64;
+    }
+  }
+  estimate
+}
+
+/// Returns the node a fixed term stands for.
+fn fixed(term: &Term) -> Option<&Node> {
+  match term {
+    Term::Node(node) => Some(node),
+    Term::Var(_) | Term::Path(_) => None,
+  }
+}
+
+/// Returns the variables of `pattern`.
+fn variables(pattern: &(Term, Term, Term)) -> impl Iterator<Item = &str> {
+  let (s, p, o) = pattern;
+  [s, p, o].into_iter().filter_map(|term| match term {
+    Term::Var(var) => Some(var.as_str()),
+    _ => None,
+  })
+}
@@ -0,0 +1,229 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+  collections::{HashMap, HashSet},
+  fmt,
+  sync::{Mutex, PoisonError},
+};
+
+use serde::de::Error as _;
+
+use crate::{
+  error::Error,
+  graph::{KnowledgeGraph, Node},
+  query::{pattern::Bindings, results, ResultFormat, Rows, Select},
+  Result,
+};
+
+/// `PreparedQuery` is a SPARQL `SELECT` query parsed once and executed many
+/// times, with some of its variables bound to different values each time.
+///
+/// Every variable of the `WHERE` group is a parameter: binding it fixes
+/// its value before the patterns are matched, exactly as if the value had
+/// been written in its place, so no text is ever spliced into the query.
+/// Unbound parameters stay variables.
+///
+/// The join order of the patterns is planned the first time a set of
+/// parameters is bound, from the statistics of the graph at that time, and
+/// reused by later executions binding the same parameters.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node};
+/// use sage::query::{PreparedQuery, Update};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.update(
+///   &Update::parse(
+///     r#"PREFIX ex: <https://example.com/>
+///        INSERT DATA {
+///          ex:Ada schema:name "Ada" ; schema:knows ex:Bob .
+///          ex:Bob schema:name "Bob" .
+///        }"#,
+///   )
+///   .unwrap(),
+/// );
+///
+/// let names = PreparedQuery::parse(
+///   "SELECT ?name WHERE { ?person schema:knows ?friend .
+///                         ?friend schema:name ?name }",
+/// )
+/// .unwrap();
+/// assert_eq!(names.parameters(), ["person", "friend", "name"]);
+///
+/// let ada = Node::Http("https://example.com/Ada".to_string());
+/// let bob = Node::Http("https://example.com/Bob".to_string());
+/// assert_eq!(
+///   names.execute(&graph, &[("person", ada.clone())]).unwrap(),
+///   [[Some(Node::Literal("Bob".into()))]]
+/// );
+/// let rows = names.execute(&graph, &[("?person", bob.clone())]).unwrap();
+/// assert!(rows.is_empty());
+/// assert!(names.execute(&graph, &[("age", Node::Blank)]).is_err());
+///
+/// let friends = names
+///   .execute_many(&graph, &[[("person", ada)], [("person", bob)]])
+///   .unwrap();
+/// assert_eq!(friends[0], [[Some(Node::Literal("Bob".into()))]]);
+/// assert!(friends[1].is_empty());
+/// ```
+#[derive(Debug)]
+pub struct PreparedQuery {
+  select: Select,
+  parameters: Vec<String>,
+  /// Join orders, keyed by the sorted names of the bound parameters.
+  plans: Mutex<HashMap<Vec<String>, Vec<usize>>>,
+}
+
+impl PreparedQuery {
+  /// Parses a SPARQL `SELECT` query, see `Select::parse`.
+  pub fn parse(s: &str) -> Result<PreparedQuery> {
+    Select::parse(s).map(PreparedQuery::new)
+  }
+
+  /// Prepares an already parsed query.
+  pub fn new(select: Select) -> PreparedQuery {
+    let parameters = select.query().variables();
+    PreparedQuery {
+      select,
+      parameters,
+      plans: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Returns the parsed query.
+  pub fn select(&self) -> &Select {
+    &self.select
+  }
+
+  /// Returns the variables which can be bound, without the `?`, in order of
+  /// appearance.
+  pub fn parameters(&self) -> &[String] {
+    &self.parameters
+  }
+
+  /// Evaluates the query against `graph` with the parameters named in
+  /// `bindings` (with or without the `?`) bound to their node, like
+  /// `Select::solutions`.
+  ///
+  /// # Errors
+  ///
+  /// Fails if a name isn't a parameter of the query.
+  pub fn execute(
+    &self,
+    graph: &KnowledgeGraph,
+    bindings: &[(&str, Node)],
+  ) -> Result<Rows> {
+    let bindings = self.bindings(bindings)?;
+    let order = self.plan(graph, &bindings);
+    Ok(self.select.solutions_ordered(graph, bindings, &order))
+  }
+
+  /// Evaluates the query like `PreparedQuery::execute` once per set of
+  /// `bindings`, returning the solutions of each in order.
+  ///
+  /// # Errors
+  ///
+  /// Fails if a name isn't a parameter of the query, before any set is
+  /// evaluated.
+  pub fn execute_many<'a, B>(
+    &self,
+    graph: &KnowledgeGraph,
+    bindings: &[B],
+  ) -> Result<Vec<Rows>>
+  where
+    B: AsRef<[(&'a str, Node)]>,
+  {
+    let bindings = bindings
+      .iter()
+      .map(|bindings| self.bindings(bindings.as_ref()))
+      .collect::<Result<Vec<_>>>()?;
+    Ok(
+      bindings
+        .into_iter()
+        .map(|bindings| {
+          let order = self.plan(graph, &bindings);
+          self.select.solutions_ordered(graph, bindings, &order)
+        })
+        .collect(),
+    )
+  }
+
+  /// Evaluates the query like `PreparedQuery::execute`, serializing the
+  /// solutions as `format`.
+  ///
+  /// # Errors
+  ///
+  /// Fails if a name isn't a parameter of the query.
+  pub fn results(
+    &self,
+    graph: &KnowledgeGraph,
+    bindings: &[(&str, Node)],
+    format: ResultFormat,
+  ) -> Result<Vec<u8>> {
+    let solutions = self.execute(graph, bindings)?;
+    Ok(results::serialize(
+      format,
+      self.select.variables(),
+      &solutions,
+    ))
+  }
+
+  /// Returns the join order for `bindings`, planning it on first use.
+  fn plan(&self, graph: &KnowledgeGraph, bindings: &Bindings) -> Vec<usize> {
+    let mut bound: Vec<String> = bindings.keys().cloned().collect();
+    bound.sort();
+    let mut plans = self.plans.lock().unwrap_or_else(PoisonError::into_inner);
+    plans
+      .entry(bound)
+      .or_insert_with_key(|bound| {
+        let bound: HashSet<&str> = bound.iter().map(String::as_str).collect();
+        self.select.plan(graph, &bound)
+      })
+      .clone()
+  }
+
+  fn bindings(&self, bindings: &[(&str, Node)]) -> Result<Bindings> {
+    bindings
+      .iter()
+      .map(|(name, node)| {
+        let name = name.trim_start_matches('?');
+        if self.parameters.iter().any(|p| p == name) {
+          Ok((name.to_string(), node.clone()))
+        } else {
+          Err(Error::custom(format!("unknown query parameter ?{}", name)))
+        }
+      })
+      .collect()
+  }
+}
+
+impl Clone for PreparedQuery {
+  fn clone(&self) -> Self {
+    let plans = self.plans.lock().unwrap_or_else(PoisonError::into_inner);
+    PreparedQuery {
+      select: self.select.clone(),
+      parameters: self.parameters.clone(),
+      plans: Mutex::new(plans.clone()),
+    }
+  }
+}
+
+impl fmt::Display for PreparedQuery {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&self.select, f)
+  }
+}
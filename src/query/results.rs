@@ -0,0 +1,329 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::Write as _;
+
+use chrono::SecondsFormat;
+
+use crate::{
+  datastore::json,
+  dtype::{literal::base64_encode, DType, Map},
+  formats::viz::XmlEscape,
+  graph::Node,
+};
+
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+const RDF_JSON: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON";
+
+/// `ResultFormat` is a serialization of `Select` solutions (see
+/// `Select::results`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResultFormat {
+  /// [SPARQL JSON results](https://www.w3.org/TR/sparql11-results-json/).
+  Json,
+  /// [SPARQL CSV results](https://www.w3.org/TR/sparql11-results-csv-tsv/):
+  /// lexical forms only.
+  Csv,
+  /// [SPARQL TSV results](https://www.w3.org/TR/sparql11-results-csv-tsv/):
+  /// terms written like in N-Triples.
+  Tsv,
+  /// [SPARQL XML results](https://www.w3.org/TR/rdf-sparql-XMLres/).
+  Xml,
+}
+
+impl ResultFormat {
+  /// Returns the media type of the format.
+  pub fn media_type(self) -> &'static str {
+    match self {
+      ResultFormat::Json => "application/sparql-results+json",
+      ResultFormat::Csv => "text/csv; charset=utf-8",
+      ResultFormat::Tsv => "text/tab-separated-values; charset=utf-8",
+      ResultFormat::Xml => "application/sparql-results+xml",
+    }
+  }
+}
+
+/// Serializes the solutions of `variables` as `format`.
+pub(crate) fn serialize(
+  format: ResultFormat,
+  variables: &[String],
+  solutions: &[Vec<Option<Node>>],
+) -> Vec<u8> {
+  match format {
+    ResultFormat::Json => results_json(variables, solutions),
+    ResultFormat::Csv => results_csv(variables, solutions),
+    ResultFormat::Tsv => results_tsv(variables, solutions),
+    ResultFormat::Xml => results_xml(variables, solutions),
+  }
+}
+
+/// An RDF term of a solution.
+enum Value {
+  Iri(String),
+  Blank(String),
+  Literal {
+    lexical: String,
+    datatype: Option<String>,
+    language: Option<String>,
+  },
+}
+
+impl Value {
+  fn of(node: &Node) -> Value {
+    match node {
+      Node::Blank => Value::Blank("blank".to_string()),
+      Node::BlankId(label) => Value::Blank(label.clone()),
+      Node::Literal(value) => literal(value),
+      Node::Multiple(_) => Value::Literal {
+        lexical: node.to_string(),
+        datatype: None,
+        language: None,
+      },
+      node => Value::Iri(node.to_string()),
+    }
+  }
+}
+
+/// Splits a literal into its lexical form, datatype & language, like the
+/// N-Triples export.
+fn literal(value: &DType) -> Value {
+  let typed = |lexical: String, datatype: &str| Value::Literal {
+    lexical,
+    datatype: Some(datatype.to_string()),
+    language: None,
+  };
+  let xsd =
+    |lexical: String, name: &str| typed(lexical, &format!("{}{}", XSD, name));
+  match value {
+    DType::String(s) => Value::Literal {
+      lexical: s.clone(),
+      datatype: None,
+      language: None,
+    },
+    DType::Boolean(b) => xsd(b.to_string(), "boolean"),
+    DType::Bytes(b) => xsd(base64_encode(b), "base64Binary"),
+    DType::Number(n) if n.is_f64() => xsd(n.to_string(), "double"),
+    DType::Number(n) => xsd(n.to_string(), "integer"),
+    DType::DateTime(d) => xsd(
+      d.as_chrono().to_rfc3339_opts(SecondsFormat::AutoSi, true),
+      "dateTime",
+    ),
+    DType::Object(map) if map.len() == 2 => {
+      match (map.get("@value"), map.get("@language")) {
+        (Some(DType::String(s)), Some(DType::String(language))) => {
+          Value::Literal {
+            lexical: s.clone(),
+            datatype: None,
+            language: Some(language.clone()),
+          }
+        }
+        _ => typed(json::to_string(value).unwrap_or_default(), RDF_JSON),
+      }
+    }
+    value => typed(json::to_string(value).unwrap_or_default(), RDF_JSON),
+  }
+}
+
+/// Serializes solutions as [SPARQL JSON results].
+///
+/// [SPARQL JSON results]: https://www.w3.org/TR/sparql11-results-json/
+fn results_json(
+  variables: &[String],
+  solutions: &[Vec<Option<Node>>],
+) -> Vec<u8> {
+  let bindings: Vec<DType> = solutions
+    .iter()
+    .map(|row| {
+      let mut binding = Map::new();
+      for (var, node) in variables.iter().zip(row) {
+        let node = match node {
+          Some(node) => node,
+          None => continue,
+        };
+        let term = match Value::of(node) {
+          Value::Iri(iri) => crate::json!({ "type": "uri", "value": iri }),
+          Value::Blank(label) => {
+            crate::json!({ "type": "bnode", "value": label })
+          }
+          Value::Literal {
+            lexical,
+            datatype,
+            language,
+          } => {
+            let mut term =
+              crate::json!({ "type": "literal", "value": lexical });
+            if let Some(datatype) = datatype {
+              term["datatype"] = datatype.into();
+            }
+            if let Some(language) = language {
+              term["xml:lang"] = language.into();
+            }
+            term
+          }
+        };
+        binding.insert(var.clone(), term);
+      }
+      DType::Object(binding)
+    })
+    .collect();
+  let results = crate::json!({
+    "head": { "vars": variables },
+    "results": { "bindings": bindings },
+  });
+  json::to_vec(&results).unwrap_or_default()
+}
+
+/// Serializes solutions as [SPARQL CSV results]: lexical forms only.
+///
+/// [SPARQL CSV results]: https://www.w3.org/TR/sparql11-results-csv-tsv/
+fn results_csv(
+  variables: &[String],
+  solutions: &[Vec<Option<Node>>],
+) -> Vec<u8> {
+  let field = |s: &str| {
+    if s.contains(['"', ',', '\n', '\r']) {
+      format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+      s.to_string()
+    }
+  };
+  let mut out = String::new();
+  let header: Vec<String> = variables.iter().map(|v| field(v)).collect();
+  out.push_str(&header.join(","));
+  out.push_str("\r\n");
+  for row in solutions {
+    let fields: Vec<String> = row
+      .iter()
+      .map(|node| match node.as_ref().map(Value::of) {
+        None => String::new(),
+        Some(Value::Iri(iri)) => field(&iri),
+        Some(Value::Blank(label)) => field(&format!("_:{}", label)),
+        Some(Value::Literal { lexical, .. }) => field(&lexical),
+      })
+      .collect();
+    out.push_str(&fields.join(","));
+    out.push_str("\r\n");
+  }
+  out.into_bytes()
+}
+
+/// Serializes solutions as [SPARQL TSV results]: terms written like in
+/// N-Triples.
+///
+/// [SPARQL TSV results]: https://www.w3.org/TR/sparql11-results-csv-tsv/
+fn results_tsv(
+  variables: &[String],
+  solutions: &[Vec<Option<Node>>],
+) -> Vec<u8> {
+  let mut out = String::new();
+  let header: Vec<String> =
+    variables.iter().map(|v| format!("?{}", v)).collect();
+  out.push_str(&header.join("\t"));
+  out.push('\n');
+  for row in solutions {
+    let fields: Vec<String> = row
+      .iter()
+      .map(|node| match node.as_ref().map(Value::of) {
+        None => String::new(),
+        Some(Value::Iri(iri)) => format!("<{}>", iri),
+        Some(Value::Blank(label)) => format!("_:{}", label),
+        Some(Value::Literal {
+          lexical,
+          datatype,
+          language,
+        }) => {
+          let mut term = String::from("\"");
+          for c in lexical.chars() {
+            match c {
+              '"' => term.push_str("\\\""),
+              '\\' => term.push_str("\\\\"),
+              '\t' => term.push_str("\\t"),
+              '\n' => term.push_str("\\n"),
+              '\r' => term.push_str("\\r"),
+              c => term.push(c),
+            }
+          }
+          term.push('"');
+          match (datatype, language) {
+            (Some(datatype), _) => {
+              let _ = write!(term, "^^<{}>", datatype);
+            }
+            (_, Some(language)) => {
+              let _ = write!(term, "@{}", language);
+            }
+            _ => {}
+          }
+          term
+        }
+      })
+      .collect();
+    out.push_str(&fields.join("\t"));
+    out.push('\n');
+  }
+  out.into_bytes()
+}
+
+/// Serializes solutions as [SPARQL XML results].
+///
+/// [SPARQL XML results]: https://www.w3.org/TR/rdf-sparql-XMLres/
+fn results_xml(
+  variables: &[String],
+  solutions: &[Vec<Option<Node>>],
+) -> Vec<u8> {
+  let mut out = String::from(
+    "<?xml version=\"1.0\"?>\n\
+     <sparql xmlns=\"http://www.w3.org/2005/sparql-results#\">\n  <head>\n",
+  );
+  for var in variables {
+    let _ = writeln!(out, "    <variable name=\"{}\"/>", XmlEscape(var));
+  }
+  out.push_str("  </head>\n  <results>\n");
+  for row in solutions {
+    out.push_str("    <result>\n");
+    for (var, node) in variables.iter().zip(row) {
+      let term = match node.as_ref().map(Value::of) {
+        None => continue,
+        Some(Value::Iri(iri)) => format!("<uri>{}</uri>", XmlEscape(&iri)),
+        Some(Value::Blank(label)) => {
+          format!("<bnode>{}</bnode>", XmlEscape(&label))
+        }
+        Some(Value::Literal {
+          lexical,
+          datatype,
+          language,
+        }) => {
+          let attribute = match (datatype, language) {
+            (Some(datatype), _) => {
+              format!(" datatype=\"{}\"", XmlEscape(&datatype))
+            }
+            (_, Some(language)) => {
+              format!(" xml:lang=\"{}\"", XmlEscape(&language))
+            }
+            _ => String::new(),
+          };
+          format!("<literal{}>{}</literal>", attribute, XmlEscape(&lexical))
+        }
+      };
+      let _ = writeln!(
+        out,
+        "      <binding name=\"{}\">{}</binding>",
+        XmlEscape(var),
+        term
+      );
+    }
+    out.push_str("    </result>\n");
+  }
+  out.push_str("  </results>\n</sparql>\n");
+  out.into_bytes()
+}
@@ -0,0 +1,259 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+
+use crate::{
+  dtype::DateTime,
+  graph::{KnowledgeGraph, Node, Predicate, Provenance},
+  query::{
+    pattern::{resolve, Bindings},
+    Aggregation, Query, Term,
+  },
+};
+
+/// `Rule` derives the statements of its head from every solution of its
+/// body: `when` patterns are matched like a `Query`, `then` patterns are
+/// instantiated with the bindings.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::query::Rule;
+///
+/// // Grandparents are parents of parents.
+/// let rule = Rule::new()
+///   .when("?a", "https://schema.org/parent", "?b")
+///   .when("?b", "https://schema.org/parent", "?c")
+///   .then("?a", "https://example.com/grandparent", "?c");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Rule {
+  body: Query,
+  head: Vec<(Term, Term, Term)>,
+}
+
+impl Rule {
+  /// Creates a rule without premises or conclusions.
+  pub fn new() -> Rule {
+    Rule::default()
+  }
+
+  /// Adds a premise, see `Query::pattern`.
+  pub fn when<S, P, O>(mut self, subject: S, predicate: P, object: O) -> Self
+  where
+    S: Into<Term>,
+    P: Into<Term>,
+    O: Into<Term>,
+  {
+    self.body = self.body.pattern(subject, predicate, object);
+    self
+  }
+
+  /// Adds a conclusion. Variables must be bound by the premises & the
+  /// predicate must be an IRI (or a variable bound to one).
+  pub fn then<S, P, O>(mut self, subject: S, predicate: P, object: O) -> Self
+  where
+    S: Into<Term>,
+    P: Into<Term>,
+    O: Into<Term>,
+  {
+    self
+      .head
+      .push((subject.into(), predicate.into(), object.into()));
+    self
+  }
+}
+
+/// `Reasoner` applies `Rule`s to a graph until nothing new can be derived
+/// (forward chaining), tracking the confidence of what it derives.
+///
+/// The confidence of a derived statement combines the confidences of the
+/// premises it was derived from by the `Aggregation` rule: `Min` or
+/// `Product`. Derived statements below the threshold are dropped, which
+/// also stops long chains of uncertain inferences under `Product`.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate, Provenance};
+/// use sage::query::{Aggregation, Query, Reasoner, Rule};
+///
+/// let node = |name: &str| Node::Http(format!("https://example.com/{}", name));
+/// let located = "https://schema.org/containedInPlace";
+///
+/// let mut graph = KnowledgeGraph::new();
+/// for (place, within, confidence) in [
+///   ("Soho", "London", 0.9),
+///   ("London", "England", 0.8),
+///   ("England", "Europe", 0.5),
+/// ] {
+///   graph.insert_with_provenance(
+///     node(place),
+///     Predicate::Literal(located.to_string()),
+///     node(within),
+///     Provenance::new("gazetteer").confidence(confidence),
+///   );
+/// }
+///
+/// let transitive = Rule::new()
+///   .when("?a", located, "?b")
+///   .when("?b", located, "?c")
+///   .then("?a", located, "?c");
+/// let added = Reasoner::new()
+///   .rule(transitive)
+///   .aggregation(Aggregation::Product)
+///   .threshold(0.5)
+///   .infer(&mut graph);
+///
+/// // Soho-England (0.72) & London-Europe (0.4, dropped), then Soho-Europe
+/// // (0.36, dropped).
+/// assert_eq!(added, 1);
+/// let soho = node("Soho");
+/// let confidences: Vec<_> = graph
+///   .matches(Some(&soho), Some(located), None)
+///   .map(|t| t.confidence().unwrap())
+///   .collect();
+/// assert_eq!(confidences.len(), 2);
+/// assert!((confidences[1] - 0.72).abs() < 1e-9);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Reasoner {
+  rules: Vec<Rule>,
+  aggregation: Aggregation,
+  threshold: f64,
+}
+
+impl Reasoner {
+  /// Creates a reasoner without rules, combining confidences by `Min` &
+  /// keeping every derived statement.
+  pub fn new() -> Reasoner {
+    Reasoner::default()
+  }
+
+  /// Adds a rule.
+  pub fn rule(mut self, rule: Rule) -> Self {
+    self.rules.push(rule);
+    self
+  }
+
+  /// Sets how the confidences of premises are combined.
+  pub fn aggregation(mut self, aggregation: Aggregation) -> Self {
+    self.aggregation = aggregation;
+    self
+  }
+
+  /// Sets the confidence below which derived statements are dropped.
+  pub fn threshold(mut self, threshold: f64) -> Self {
+    self.threshold = threshold;
+    self
+  }
+
+  /// Adds every statement derivable from `graph` by the rules, returning
+  /// how many were added.
+  ///
+  /// Rounds of derivation repeat until one adds nothing. Rules only combine
+  /// the nodes of the graph & of their patterns, so this always ends.
+  ///
+  /// Statements already in the graph aren't derived again. A new statement
+  /// derived by several solutions of one round gets the highest of their
+  /// confidences. Derived statements carry a `Provenance` without source.
+  pub fn infer(&self, graph: &mut KnowledgeGraph) -> usize {
+    let mut existing: HashSet<(Node, String, Node)> = graph
+      .triples()
+      .map(|t| {
+        let predicate = t.predicate().to_string();
+        (t.source().clone(), predicate, t.destination().clone())
+      })
+      .collect();
+    let mut added = 0;
+    loop {
+      let derived = self.round(graph, &existing);
+      if derived.is_empty() {
+        break;
+      }
+      let ingested_at = DateTime::from(Utc::now());
+      added += derived.len();
+      for ((subject, predicate, object), confidence) in derived {
+        existing.insert((subject.clone(), predicate.clone(), object.clone()));
+        let provenance = Provenance {
+          source: None,
+          ingested_at: Some(ingested_at.clone()),
+          confidence: Some(confidence),
+        };
+        graph.insert_with_provenance(
+          subject,
+          Predicate::Literal(predicate),
+          object,
+          provenance,
+        );
+      }
+    }
+    added
+  }
+
+  /// Returns the statements derivable in one round which aren't `existing`
+  /// yet, in the order they were first derived.
+  fn round(
+    &self,
+    graph: &KnowledgeGraph,
+    existing: &HashSet<(Node, String, Node)>,
+  ) -> Vec<((Node, String, Node), f64)> {
+    let mut derived: Vec<((Node, String, Node), f64)> = Vec::new();
+    let mut index: HashMap<(Node, String, Node), usize> = HashMap::new();
+    for rule in &self.rules {
+      for (bindings, confidence) in rule.body.solve(graph, self.aggregation) {
+        if confidence < self.threshold {
+          continue;
+        }
+        for pattern in &rule.head {
+          let statement = match instantiate(pattern, &bindings) {
+            Some(statement) => statement,
+            None => continue,
+          };
+          if existing.contains(&statement) {
+            continue;
+          }
+          match index.get(&statement) {
+            Some(&i) => derived[i].1 = derived[i].1.max(confidence),
+            None => {
+              index.insert(statement.clone(), derived.len());
+              derived.push((statement, confidence));
+            }
+          }
+        }
+      }
+    }
+    derived
+  }
+}
+
+/// Returns the statement `pattern` stands for under `bindings`, if every
+/// term is bound & the predicate is an IRI.
+pub(crate) fn instantiate(
+  (subject, predicate, object): &(Term, Term, Term),
+  bindings: &Bindings,
+) -> Option<(Node, String, Node)> {
+  let predicate = match resolve(predicate, bindings)? {
+    Node::Http(iri) => iri.clone(),
+    _ => return None,
+  };
+  Some((
+    resolve(subject, bindings)?.clone(),
+    predicate,
+    resolve(object, bindings)?.clone(),
+  ))
+}
@@ -0,0 +1,465 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{cmp::Ordering, collections::HashSet, fmt};
+
+use serde::de::Error as _;
+
+use crate::{
+  dtype::DType,
+  error::Error,
+  graph::{KnowledgeGraph, Node},
+  query::{
+    aggregate::{self, Aggregate},
+    federation::{self, ServiceCall},
+    parser::Parser,
+    pattern::Bindings,
+    results, Aggregation, Filter, Query, ResultFormat,
+  },
+  Result,
+};
+
+/// `Rows` are the solutions of a `Select`: one row per solution holding
+/// the value of every variable, `None` where it's unbound.
+pub type Rows = Vec<Vec<Option<Node>>>;
+
+/// `Select` is a parsed SPARQL `SELECT` query, evaluated with `Query`.
+///
+/// The `WHERE` group holds triple patterns (written like in Turtle, with
+/// property paths as predicates) & language `FILTER`s (see `Filter`).
+/// `DISTINCT`, `ORDER BY` (`ASC`/`DESC`), `LIMIT` & `OFFSET` are supported;
+/// prefixed names expand like in `Update`.
+///
+/// Solutions can be grouped with `GROUP BY` & summarized by the aggregates
+/// `COUNT`, `SUM`, `AVG`, `MIN`, `MAX` & `GROUP_CONCAT` (with an optional
+/// `SEPARATOR`), projected as `(COUNT(?x) AS ?n)`; each takes `DISTINCT`
+/// & `COUNT` also takes `*`. Without `GROUP BY`, aggregates summarize every
+/// solution as a single row. Other projected variables must be grouped by.
+///
+/// `SERVICE` groups are evaluated by a `Federation`; elsewhere they can't
+/// be reached, so `SERVICE SILENT` groups match every solution & others
+/// none.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node};
+/// use sage::query::{Select, Update};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.update(
+///   &Update::parse(
+///     r#"PREFIX ex: <https://example.com/>
+///        INSERT DATA {
+///          ex:Ada schema:name "Ada" ; schema:knows ex:Bob .
+///          ex:Bob schema:name "Bob" .
+///        }"#,
+///   )
+///   .unwrap(),
+/// );
+///
+/// let select = Select::parse(
+///   "SELECT ?name WHERE { ?person schema:name ?name } ORDER BY DESC(?name)",
+/// )
+/// .unwrap();
+/// assert_eq!(select.variables(), ["name"]);
+///
+/// let solutions = select.solutions(&graph);
+/// assert_eq!(solutions.len(), 2);
+/// assert_eq!(solutions[0][0], Some(Node::Literal("Bob".into())));
+///
+/// let friends = Select::parse(
+///   "SELECT ?person (COUNT(?friend) AS ?friends)
+///    WHERE { ?person schema:knows ?friend } GROUP BY ?person",
+/// )
+/// .unwrap();
+/// assert_eq!(
+///   friends.solutions(&graph),
+///   [[
+///     Some(Node::Http("https://example.com/Ada".to_string())),
+///     Some(Node::Literal(1.into())),
+///   ]]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Select {
+  source: String,
+  variables: Vec<String>,
+  distinct: bool,
+  query: Query,
+  /// Filters applied once services are joined.
+  filters: Vec<Filter>,
+  services: Vec<ServiceCall>,
+  group_by: Vec<String>,
+  aggregates: Vec<Aggregate>,
+  /// Variables to sort by, `true` if descending.
+  order: Vec<(String, bool)>,
+  offset: usize,
+  limit: Option<usize>,
+}
+
+impl Select {
+  /// Parses a SPARQL `SELECT` query.
+  pub fn parse(s: &str) -> Result<Select> {
+    let mut parser = Parser::query(s);
+    parser.prologue()?;
+    let prologue = parser.parsed();
+    if !parser.keyword("SELECT") {
+      return Err(parser.error());
+    }
+    let distinct = parser.keyword("DISTINCT");
+    if !distinct {
+      parser.keyword("REDUCED");
+    }
+    let mut variables = Vec::new();
+    let mut aggregates = Vec::new();
+    if !parser.eat('*') {
+      loop {
+        if let Some(var) = parser.var() {
+          variables.push(var);
+        } else if parser.eat('(') {
+          let aggregate = Aggregate::parse(&mut parser)?;
+          variables.push(aggregate.alias.clone());
+          aggregates.push(aggregate);
+        } else {
+          break;
+        }
+      }
+      if variables.is_empty() {
+        return Err(parser.error());
+      }
+    }
+
+    parser.keyword("WHERE");
+    let block = parser.block(true)?;
+    let mut query = Query::new();
+    for (s, p, o) in block.patterns {
+      query = query.pattern(s, p, o);
+    }
+    // Filters may test variables bound by services.
+    let mut filters = Vec::new();
+    if block.services.is_empty() {
+      for filter in block.filters {
+        query = query.filter(filter);
+      }
+    } else {
+      filters = block.filters;
+    }
+    let services = block
+      .services
+      .into_iter()
+      .map(|service| {
+        let group = format!("{} SELECT * WHERE {}", prologue, service.group);
+        Ok(ServiceCall {
+          iri: service.iri,
+          silent: service.silent,
+          select: Select::parse(&group)?,
+        })
+      })
+      .collect::<Result<Vec<_>>>()?;
+    if variables.is_empty() {
+      variables = query.variables();
+      for service in &services {
+        for var in service.select.variables() {
+          if !variables.contains(var) {
+            variables.push(var.clone());
+          }
+        }
+      }
+    }
+
+    let mut select = Select {
+      source: s.to_string(),
+      variables,
+      distinct,
+      query,
+      filters,
+      services,
+      group_by: Vec::new(),
+      aggregates,
+      order: Vec::new(),
+      offset: 0,
+      limit: None,
+    };
+    loop {
+      if parser.keyword("GROUP") {
+        if !parser.keyword("BY") {
+          return Err(parser.error());
+        }
+        while let Some(var) = parser.var() {
+          select.group_by.push(var);
+        }
+        if select.group_by.is_empty() {
+          return Err(parser.error());
+        }
+      } else if parser.keyword("ORDER") {
+        if !parser.keyword("BY") {
+          return Err(parser.error());
+        }
+        select.order = order(&mut parser)?;
+      } else if parser.keyword("LIMIT") {
+        select.limit = Some(parser.integer()?);
+      } else if parser.keyword("OFFSET") {
+        select.offset = parser.integer()?;
+      } else if parser.is_done() {
+        break;
+      } else {
+        return Err(parser.error());
+      }
+    }
+
+    // Once solutions are grouped, only grouped variables keep a value.
+    if select.is_grouped() {
+      let projected = select.variables.iter().all(|var| {
+        select.group_by.contains(var)
+          || select.aggregates.iter().any(|a| a.alias == *var)
+      });
+      if !projected {
+        return Err(parser.error());
+      }
+    }
+    Ok(select)
+  }
+
+  /// Returns the text the query was parsed from.
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+
+  /// Returns the graph pattern of the `WHERE` group.
+  pub(crate) fn query(&self) -> &Query {
+    &self.query
+  }
+
+  /// Returns the projected variables, without the `?`. For `SELECT *`,
+  /// every variable of the patterns in order of appearance.
+  pub fn variables(&self) -> &[String] {
+    &self.variables
+  }
+
+  /// Evaluates the query against `graph`, returning one row per solution
+  /// with the value of every projected variable, `None` if unbound.
+  pub fn solutions(&self, graph: &KnowledgeGraph) -> Rows {
+    self.solutions_from(graph, Bindings::new())
+  }
+
+  /// Evaluates the query against `graph` with some variables already bound
+  /// by `bindings`.
+  pub(crate) fn solutions_from(
+    &self,
+    graph: &KnowledgeGraph,
+    bindings: Bindings,
+  ) -> Rows {
+    let unreachable = |call: &ServiceCall| {
+      Err(Error::custom(format!("unknown service <{}>", call.iri)))
+    };
+    self
+      .evaluate(&[graph], bindings, &unreachable)
+      .unwrap_or_default()
+  }
+
+  /// Evaluates the query against `graph` like `solutions_from`, joining the
+  /// patterns of the `WHERE` group in `order` (see `plan`).
+  pub(crate) fn solutions_ordered(
+    &self,
+    graph: &KnowledgeGraph,
+    bindings: Bindings,
+    order: &[usize],
+  ) -> Rows {
+    let unreachable = |call: &ServiceCall| {
+      Err(Error::custom(format!("unknown service <{}>", call.iri)))
+    };
+    self
+      .evaluate_with(&[graph], bindings, Some(order), &unreachable)
+      .unwrap_or_default()
+  }
+
+  /// Returns the order in which the patterns of the `WHERE` group are
+  /// joined against `graph`, given the variables already `bound`.
+  pub(crate) fn plan(
+    &self,
+    graph: &KnowledgeGraph,
+    bound: &HashSet<&str>,
+  ) -> Vec<usize> {
+    self.query.plan(&[graph], bound)
+  }
+
+  /// Evaluates the query against the union of `graphs` with some variables
+  /// already bound by `bindings`, evaluating `SERVICE` groups with `call`.
+  pub(crate) fn evaluate(
+    &self,
+    graphs: &[&KnowledgeGraph],
+    bindings: Bindings,
+    call: &dyn Fn(&ServiceCall) -> Result<Rows>,
+  ) -> Result<Rows> {
+    self.evaluate_with(graphs, bindings, None, call)
+  }
+
+  /// Evaluates the query like `evaluate`, joining the patterns in `order`
+  /// if given, else in the order planned for `bindings`.
+  fn evaluate_with(
+    &self,
+    graphs: &[&KnowledgeGraph],
+    bindings: Bindings,
+    order: Option<&[usize]>,
+    call: &dyn Fn(&ServiceCall) -> Result<Rows>,
+  ) -> Result<Rows> {
+    let aggregation = Aggregation::default();
+    let solutions = match order {
+      Some(order) => {
+        self
+          .query
+          .solve_ordered(graphs, aggregation, bindings, order)
+      }
+      None => self.query.solve_over(graphs, aggregation, bindings),
+    };
+    let mut solutions: Vec<Bindings> = solutions
+      .into_iter()
+      .map(|(bindings, _)| bindings)
+      .collect();
+    for service in &self.services {
+      let rows: Vec<Bindings> = match call(service) {
+        Ok(rows) => rows
+          .into_iter()
+          .map(|row| {
+            let variables = service.select.variables().iter().cloned();
+            variables
+              .zip(row)
+              .filter_map(|(var, node)| Some((var, node?)))
+              .collect()
+          })
+          .collect(),
+        Err(_) if service.silent => vec![Bindings::new()],
+        Err(error) => return Err(error),
+      };
+      solutions = federation::join(solutions, &rows);
+    }
+    solutions
+      .retain(|bindings| self.filters.iter().all(|f| f.accepts(bindings)));
+    if self.is_grouped() {
+      solutions = aggregate::group(solutions, &self.group_by, &self.aggregates);
+    }
+    if !self.order.is_empty() {
+      solutions.sort_by(|a, b| {
+        self
+          .order
+          .iter()
+          .map(|(var, descending)| {
+            let ordering = key(a.get(var)).cmp(&key(b.get(var)));
+            if *descending {
+              ordering.reverse()
+            } else {
+              ordering
+            }
+          })
+          .find(|ordering| ordering.is_ne())
+          .unwrap_or(Ordering::Equal)
+      });
+    }
+
+    let mut seen = HashSet::new();
+    Ok(
+      solutions
+        .into_iter()
+        .map(|bindings| {
+          self
+            .variables
+            .iter()
+            .map(|var| bindings.get(var).cloned())
+            .collect::<Vec<_>>()
+        })
+        .filter(|row| !self.distinct || seen.insert(row.clone()))
+        .skip(self.offset)
+        .take(self.limit.unwrap_or(usize::MAX))
+        .collect(),
+    )
+  }
+
+  fn is_grouped(&self) -> bool {
+    !self.group_by.is_empty() || !self.aggregates.is_empty()
+  }
+
+  /// Evaluates the query against `graph`, serializing the solutions as
+  /// `format`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  /// use sage::query::{ResultFormat, Select};
+  ///
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(
+  ///   Node::Http("https://example.com/Ada".to_string()),
+  ///   Predicate::Literal("https://schema.org/name".to_string()),
+  ///   Node::Literal("Ada".into()),
+  /// );
+  ///
+  /// let select = Select::parse("SELECT * { ?s schema:name ?name }").unwrap();
+  /// assert_eq!(
+  ///   String::from_utf8(select.results(&graph, ResultFormat::Tsv)).unwrap(),
+  ///   "?s\t?name\n<https://example.com/Ada>\t\"Ada\"\n"
+  /// );
+  /// ```
+  pub fn results(
+    &self,
+    graph: &KnowledgeGraph,
+    format: ResultFormat,
+  ) -> Vec<u8> {
+    results::serialize(format, &self.variables, &self.solutions(graph))
+  }
+}
+
+impl fmt::Display for Select {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&self.source)
+  }
+}
+
+/// Parses the conditions of `ORDER BY`.
+fn order(parser: &mut Parser) -> Result<Vec<(String, bool)>> {
+  let mut order = Vec::new();
+  loop {
+    let descending = if parser.keyword("ASC") {
+      false
+    } else if parser.keyword("DESC") {
+      true
+    } else {
+      match parser.var() {
+        Some(var) => {
+          order.push((var, false));
+          continue;
+        }
+        None => break,
+      }
+    };
+    parser.expect('(')?;
+    let var = parser.var().ok_or_else(|| parser.error())?;
+    parser.expect(')')?;
+    order.push((var, descending));
+  }
+  if order.is_empty() {
+    return Err(parser.error());
+  }
+  Ok(order)
+}
+
+/// Sort key of a value: unbound first, then blank nodes, IRIs & literals.
+pub(crate) fn key(node: Option<&Node>) -> Option<(u8, DType)> {
+  Some(match node? {
+    Node::Blank | Node::BlankId(_) => (0, node?.to_string().into()),
+    Node::Literal(value) => (2, value.clone()),
+    node => (1, node.to_string().into()),
+  })
+}
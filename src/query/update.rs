@@ -0,0 +1,211 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::HashSet, fmt};
+
+use crate::{
+  graph::{KnowledgeGraph, Node, Predicate},
+  query::{parser::Parser, rule::instantiate, Aggregation, Query, Term},
+  Result,
+};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Update
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Update` is a parsed [SPARQL Update] request: a sequence of operations,
+/// separated by `;`, applied in order by `KnowledgeGraph::update`.
+///
+/// Supported operations are `INSERT DATA`, `DELETE DATA` & `DELETE WHERE`,
+/// along with `PREFIX` declarations (on top of the default `Namespaces` &
+/// `xsd`).
+/// Triples are written like in Turtle: `;` & `,` lists, `a`, prefixed
+/// names, blank node labels & numeric, boolean, typed or language tagged
+/// literals. An `Update` displays as the text it was parsed from, so it
+/// can be logged as is.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::KnowledgeGraph;
+/// use sage::query::{Query, Update};
+///
+/// let mut graph = KnowledgeGraph::new();
+/// let update = Update::parse(
+///   r#"PREFIX ex: <https://example.com/>
+///      INSERT DATA {
+///        ex:Ada a schema:Person ;
+///          schema:name "Ada" ;
+///          schema:knows ex:Bob, ex:Cy .
+///      } ;
+///      DELETE WHERE { ex:Ada schema:knows ?friend }"#,
+/// )
+/// .unwrap();
+///
+/// let report = graph.update(&update);
+/// assert_eq!((report.inserted, report.deleted), (4, 2));
+/// assert_eq!(graph.len(), 2);
+/// ```
+///
+/// [SPARQL Update]: https://www.w3.org/TR/sparql11-update/
+#[derive(Clone, Debug)]
+pub struct Update {
+  source: String,
+  operations: Vec<Operation>,
+}
+
+/// A statement with its predicate IRI.
+type Statement = (Node, String, Node);
+
+#[derive(Clone, Debug)]
+enum Operation {
+  InsertData(Vec<Statement>),
+  DeleteData(Vec<Statement>),
+  DeleteWhere(Vec<(Term, Term, Term)>),
+}
+
+/// `UpdateReport` is what `KnowledgeGraph::update` did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UpdateReport {
+  /// Statements added to the graph.
+  pub inserted: usize,
+  /// Statements removed from the graph.
+  pub deleted: usize,
+}
+
+impl Update {
+  /// Parses a SPARQL Update request.
+  pub fn parse(s: &str) -> Result<Update> {
+    let mut parser = Parser::update(s);
+    let operations = request(&mut parser)?;
+    Ok(Update {
+      source: s.to_string(),
+      operations,
+    })
+  }
+
+  /// Returns the text the update was parsed from.
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+
+  /// Applies every operation to `graph`, in order.
+  ///
+  /// Inserting a statement already in the graph & deleting one which isn't
+  /// are no-ops, so they aren't counted.
+  pub(crate) fn apply(&self, graph: &mut KnowledgeGraph) -> UpdateReport {
+    let mut report = UpdateReport::default();
+    for operation in &self.operations {
+      match operation {
+        Operation::InsertData(statements) => {
+          for (s, p, o) in statements {
+            if graph.matches(Some(s), Some(p), Some(o)).next().is_none() {
+              let predicate = Predicate::Literal(p.clone());
+              graph.insert(s.clone(), predicate, o.clone());
+              report.inserted += 1;
+            }
+          }
+        }
+        Operation::DeleteData(statements) => {
+          let statements = statements.iter().cloned().collect();
+          report.deleted += graph.remove_statements(&statements);
+        }
+        Operation::DeleteWhere(patterns) => {
+          let query = patterns
+            .iter()
+            .cloned()
+            .fold(Query::new(), |query, (s, p, o)| query.pattern(s, p, o));
+          let statements: HashSet<Statement> = query
+            .solve(graph, Aggregation::default())
+            .iter()
+            .flat_map(|(bindings, _)| {
+              patterns.iter().filter_map(|p| instantiate(p, bindings))
+            })
+            .collect();
+          report.deleted += graph.remove_statements(&statements);
+        }
+      }
+    }
+    report
+  }
+}
+
+impl fmt::Display for Update {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&self.source)
+  }
+}
+
+/// Parses the operations of an update request.
+fn request(parser: &mut Parser) -> Result<Vec<Operation>> {
+  let mut operations = Vec::new();
+  loop {
+    parser.prologue()?;
+    if parser.is_done() {
+      return Ok(operations);
+    }
+    operations.push(operation(parser)?);
+    if !parser.eat(';') {
+      break;
+    }
+  }
+  if parser.is_done() {
+    Ok(operations)
+  } else {
+    Err(parser.error())
+  }
+}
+
+fn operation(parser: &mut Parser) -> Result<Operation> {
+  if parser.keyword("INSERT") && parser.keyword("DATA") {
+    return Ok(Operation::InsertData(data(parser)?));
+  }
+  if parser.keyword("DELETE") {
+    if parser.keyword("DATA") {
+      return Ok(Operation::DeleteData(data(parser)?));
+    }
+    if parser.keyword("WHERE") {
+      parser.skip_whitespace();
+      let start = parser.error();
+      let patterns = parser.block(false)?.patterns;
+      if patterns.iter().any(|(_, p, _)| matches!(p, Term::Path(_))) {
+        return Err(start);
+      }
+      return Ok(Operation::DeleteWhere(patterns));
+    }
+  }
+  Err(parser.error())
+}
+
+/// Parses a block of triples without variables or paths.
+fn data(parser: &mut Parser) -> Result<Vec<Statement>> {
+  parser.skip_whitespace();
+  let start = parser.error();
+  let mut statements = Vec::new();
+  for pattern in parser.block(false)?.patterns {
+    match pattern {
+      (Term::Node(s), Term::Node(Node::Http(p)), Term::Node(o))
+        if !matches!(s, Node::Literal(_)) =>
+      {
+        statements.push((s, p, o));
+      }
+      _ => return Err(start),
+    }
+  }
+  Ok(statements)
+}
@@ -0,0 +1,96 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::random` is the source of randomness of every sage component,
+//! from temporary blob names to graph sampling.
+//!
+//! By default it draws from the operating system. Seeding it (or plugging
+//! in any other `RngCore`) makes whole pipeline runs reproducible, e.g. to
+//! debug a flaky import or to audit a sampled report.
+//!
+//! # Example
+//!
+//! ```rust
+//! use sage::random;
+//!
+//! random::seed(42);
+//! let first = (random::u64(), random::u64());
+//! random::seed(42);
+//! assert_eq!(first, (random::u64(), random::u64()));
+//!
+//! random::entropy();
+//! ```
+
+use std::sync::{Mutex, MutexGuard};
+
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+#[cfg(feature = "uuid")]
+use uuid::Uuid;
+
+/// A plugged in source, `None` when drawing from the operating system.
+type Source = Option<Box<dyn RngCore + Send>>;
+
+static SOURCE: Mutex<Source> = Mutex::new(None);
+
+/// Makes every component draw from a `StdRng` seeded with `seed`.
+pub fn seed(seed: u64) {
+  set_source(StdRng::seed_from_u64(seed));
+}
+
+/// Makes every component draw from `rng`.
+pub fn set_source<R: RngCore + Send + 'static>(rng: R) {
+  *source() = Some(Box::new(rng));
+}
+
+/// Makes every component draw from the operating system again.
+pub fn entropy() {
+  *source() = None;
+}
+
+/// Returns `true` if a source was plugged in through `seed` or
+/// `set_source`.
+pub fn is_deterministic() -> bool {
+  source().is_some()
+}
+
+/// Calls `f` with the current source of randomness.
+///
+/// Components draw through this so the source is locked once per use, not
+/// once per number.
+pub fn with_rng<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+  match source().as_mut() {
+    Some(rng) => f(rng.as_mut()),
+    None => f(&mut rand::thread_rng()),
+  }
+}
+
+/// Returns a random `u64`.
+pub fn u64() -> u64 {
+  with_rng(|rng| rng.next_u64())
+}
+
+/// Returns a random (version 4) UUID.
+#[cfg(feature = "uuid")]
+pub fn uuid() -> Uuid {
+  let mut bytes = [0; 16];
+  with_rng(|rng| rng.fill_bytes(&mut bytes));
+  uuid::Builder::from_bytes(bytes)
+    .set_variant(uuid::Variant::RFC4122)
+    .set_version(uuid::Version::Random)
+    .build()
+}
+
+fn source() -> MutexGuard<'static, Source> {
+  SOURCE.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,514 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::repl` is an interactive shell for exploring a `KnowledgeGraph`
+//! without writing a program.
+//!
+//! Lines starting with `:` are commands, e.g.
+//!
+//! ```text
+//! sage> :load people.nt
+//! sage> :prefix ex https://example.com/
+//! sage> :query ?p schema:name ?name . ?p schema:knows ex:Jane
+//! sage> :query ex:John schema:knows+/schema:name ?name
+//! sage> :query ex:John schema:name ?name . FILTER(lang(?name) = "de")
+//! sage> :node ex:John
+//! sage> :export john.nt ex:John
+//! ```
+//!
+//! Terms in queries are `?variables`, `<full IRIs>`, `prefix:names`,
+//! `"strings"`, numbers or booleans; `a` is short for `rdf:type`.
+//! Predicates may be property paths (see `sage::query::Path`). Tab
+//! completes commands, prefixes & the predicates in the graph.
+//!
+
+use std::{
+  fs::File,
+  io::{self, BufReader, BufWriter, Write},
+  path::PathBuf,
+};
+
+use rustyline::{
+  completion::Completer, error::ReadlineError, highlight::Highlighter,
+  hint::Hinter, history::DefaultHistory, validate::Validator, Context, Editor,
+  Helper,
+};
+
+use crate::{
+  datastore::json,
+  dtype::DType,
+  error::Error,
+  formats::NTriples,
+  graph::{KnowledgeGraph, Node, Profile, Triple},
+  query::{Filter, Path, Query, Term},
+  vocab::Namespaces,
+  Result,
+};
+
+const COMMANDS: &[(&str, &str)] = &[
+  (":load", ":load <file.nt>          load an N-Triples file"),
+  (":query", ":query <s p o> [. ...]    run a pattern query"),
+  (
+    ":node",
+    ":node <iri>               show a node & its properties",
+  ),
+  (
+    ":export",
+    ":export <file> [iri ...]  write (a subgraph) as N-Triples",
+  ),
+  (":prefix", ":prefix [name iri]        list or add a prefix"),
+  (":size", ":size                     number of triples"),
+  (":help", ":help                     show this help"),
+  (":quit", ":quit                     leave the shell"),
+];
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Repl
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Repl` holds the graph & prefixes of an interactive session.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::repl::Repl;
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.insert(
+///   Node::Http("https://example.com/John".to_string()),
+///   Predicate::Literal("https://schema.org/name".to_string()),
+///   Node::Literal("John".into()),
+/// );
+///
+/// let mut repl = Repl::with_graph(graph);
+/// let output = repl.execute(":query ?p schema:name ?name").unwrap();
+/// assert!(output.contains("\"name\":\"John\""));
+/// assert_eq!(repl.execute(":size").unwrap(), "1 triples");
+/// ```
+pub struct Repl {
+  graph: KnowledgeGraph,
+  namespaces: Namespaces,
+  history: Option<PathBuf>,
+}
+
+impl Default for Repl {
+  fn default() -> Self {
+    Repl::new()
+  }
+}
+
+impl Repl {
+  /// Creates a session over an empty graph with the `rdf`, `rdfs`, `xsd` &
+  /// `schema` prefixes registered.
+  pub fn new() -> Repl {
+    Repl::with_graph(KnowledgeGraph::new())
+  }
+
+  /// Creates a session over `graph`.
+  pub fn with_graph(graph: KnowledgeGraph) -> Repl {
+    let mut namespaces = Namespaces::default();
+    namespaces.bind("xsd", "http://www.w3.org/2001/XMLSchema#");
+    Repl::with_namespaces(graph, namespaces)
+  }
+
+  /// Creates a session over `graph` resolving `prefix:names` with
+  /// `namespaces`.
+  pub fn with_namespaces(
+    graph: KnowledgeGraph,
+    namespaces: Namespaces,
+  ) -> Repl {
+    Repl {
+      graph,
+      namespaces,
+      history: None,
+    }
+  }
+
+  /// Persists the line history in `path` across sessions.
+  pub fn history<P: Into<PathBuf>>(mut self, path: P) -> Self {
+    self.history = Some(path.into());
+    self
+  }
+
+  /// Returns the session's graph.
+  pub fn graph(&self) -> &KnowledgeGraph {
+    &self.graph
+  }
+
+  /// Consumes the session, returning its graph.
+  pub fn into_graph(self) -> KnowledgeGraph {
+    self.graph
+  }
+
+  /// Runs the interactive loop on the terminal until `:quit` or EOF.
+  pub fn run(mut self) -> Result<()> {
+    let mut editor: Editor<ReplHelper, DefaultHistory> =
+      Editor::new().map_err(readline_error)?;
+    if let Some(path) = &self.history {
+      // A missing history file is expected on the first run.
+      let _ = editor.load_history(path);
+    }
+
+    loop {
+      editor.set_helper(Some(ReplHelper {
+        candidates: self.candidates(),
+      }));
+      let line = match editor.readline("sage> ") {
+        Ok(line) => line,
+        Err(ReadlineError::Interrupted) => continue,
+        Err(ReadlineError::Eof) => break,
+        Err(err) => return Err(readline_error(err)),
+      };
+      let line = line.trim();
+      if line.is_empty() {
+        continue;
+      }
+      let _ = editor.add_history_entry(line);
+      if matches!(line, ":quit" | ":q" | ":exit") {
+        break;
+      }
+
+      match self.execute(line) {
+        Ok(output) if output.is_empty() => {}
+        Ok(output) => println!("{}", output),
+        Err(err) => eprintln!("error: {}", err),
+      }
+    }
+
+    if let Some(path) = &self.history {
+      editor.save_history(path).map_err(readline_error)?;
+    }
+    Ok(())
+  }
+
+  /// Executes a single line, returning what it prints.
+  pub fn execute(&mut self, line: &str) -> Result<String> {
+    let line = line.trim();
+    let (command, args) = match line.split_once(char::is_whitespace) {
+      Some((command, args)) => (command, args.trim()),
+      None => (line, ""),
+    };
+
+    match command {
+      "" | ":quit" | ":q" | ":exit" => Ok(String::new()),
+      ":help" => Ok(
+        COMMANDS
+          .iter()
+          .map(|(_, usage)| *usage)
+          .collect::<Vec<_>>()
+          .join("\n"),
+      ),
+      ":size" => Ok(format!("{} triples", self.graph.len())),
+      ":load" => self.load(args),
+      ":query" => self.query(args),
+      ":node" => self.node(args),
+      ":export" => self.export(args),
+      ":prefix" => self.prefix(args),
+      // Bare patterns are queries.
+      _ if !command.starts_with(':') => self.query(line),
+      _ => Err(invalid(format!("unknown command {}, try :help", command))),
+    }
+  }
+
+  fn load(&mut self, path: &str) -> Result<String> {
+    if path.is_empty() {
+      return Err(invalid(":load expects a file".to_string()));
+    }
+    let file = File::open(path).map_err(Error::io)?;
+    let loaded = NTriples::from_reader(BufReader::new(file))?;
+    for triple in loaded.triples() {
+      self.graph.add(Triple::from_nodes(
+        triple.source().clone(),
+        triple.predicate().clone(),
+        triple.destination().clone(),
+      ));
+    }
+    Ok(format!("loaded {} triples from {}", loaded.len(), path))
+  }
+
+  fn query(&self, args: &str) -> Result<String> {
+    let mut query = Query::new();
+    for pattern in split_patterns(args) {
+      if pattern.len() >= 6 && pattern[..6].eq_ignore_ascii_case("filter") {
+        query = query.filter(Filter::parse(&pattern)?);
+        continue;
+      }
+      let terms = tokenize(&pattern);
+      if terms.len() != 3 {
+        return Err(invalid(format!("expected `s p o`, found `{}`", pattern)));
+      }
+      query = query.pattern(
+        self.term(&terms[0])?,
+        self.predicate(&terms[1])?,
+        self.term(&terms[2])?,
+      );
+    }
+
+    let rows = self.graph.query(&query);
+    let mut output = String::new();
+    for row in &rows {
+      output.push_str(&json::to_string(row)?);
+      output.push('\n');
+    }
+    output.push_str(&format!("{} rows", rows.len()));
+    Ok(output)
+  }
+
+  fn node(&self, args: &str) -> Result<String> {
+    let node = match self.term(args)? {
+      Term::Node(node) => node,
+      _ => return Err(invalid(":node expects an IRI".to_string())),
+    };
+    let projection = Profile::new().depth(0).project(&self.graph, &node);
+    json::to_string_pretty(&projection)
+  }
+
+  fn export(&self, args: &str) -> Result<String> {
+    let mut args = args.split_whitespace();
+    let path = args
+      .next()
+      .ok_or_else(|| invalid(":export expects a file".to_string()))?;
+    let subjects = args
+      .map(|iri| match self.term(iri)? {
+        Term::Node(node) => Ok(node),
+        _ => Err(invalid(format!("{} isn't an IRI", iri))),
+      })
+      .collect::<Result<Vec<Node>>>()?;
+
+    let subgraph: KnowledgeGraph = self
+      .graph
+      .triples()
+      .filter(|t| subjects.is_empty() || subjects.contains(t.source()))
+      .map(|t| {
+        Triple::from_nodes(
+          t.source().clone(),
+          t.predicate().clone(),
+          t.destination().clone(),
+        )
+      })
+      .collect();
+
+    let mut writer = BufWriter::new(File::create(path).map_err(Error::io)?);
+    NTriples::new(&subgraph).to_writer(&mut writer)?;
+    writer.flush().map_err(Error::io)?;
+    Ok(format!("wrote {} triples to {}", subgraph.len(), path))
+  }
+
+  fn prefix(&mut self, args: &str) -> Result<String> {
+    let mut args = args.split_whitespace();
+    match (args.next(), args.next()) {
+      (None, _) => Ok(
+        self
+          .namespaces
+          .prefixes()
+          .iter()
+          .map(|(p, iri)| format!("{}: <{}>", p, iri))
+          .collect::<Vec<_>>()
+          .join("\n"),
+      ),
+      (Some(prefix), Some(iri)) => {
+        let iri = iri.trim_start_matches('<').trim_end_matches('>');
+        self.namespaces.bind(prefix, iri);
+        Ok(String::new())
+      }
+      (Some(_), None) => {
+        Err(invalid(":prefix expects a name and an IRI".to_string()))
+      }
+    }
+  }
+
+  /// Parses a single query term.
+  fn term(&self, token: &str) -> Result<Term> {
+    let token = token.trim();
+    if token.is_empty() {
+      return Err(invalid("expected a term".to_string()));
+    }
+    if token.starts_with('?') {
+      return Ok(Term::var(token));
+    }
+    if token == "a" {
+      return Ok(Term::Node(Node::Http(self.expand("rdf:type")?)));
+    }
+    if let Some(iri) = token.strip_prefix('<').and_then(|t| t.strip_suffix('>'))
+    {
+      return Ok(Term::Node(Node::Http(iri.to_string())));
+    }
+    if token.starts_with('"') {
+      return Ok(Term::from(json::from_str::<DType>(token)?));
+    }
+    if let Ok(value) = json::from_str::<DType>(token) {
+      if value.is_number() || value.is_bool() {
+        return Ok(Term::from(value));
+      }
+    }
+    Ok(Term::Node(Node::Http(self.expand(token)?)))
+  }
+
+  /// Parses the predicate of a pattern, which may be a property path.
+  fn predicate(&self, token: &str) -> Result<Term> {
+    let is_iri = token.starts_with('<')
+      && token.find('>').is_some_and(|end| end == token.len() - 1);
+    if token.starts_with('?')
+      || is_iri
+      || !token.contains(['/', '|', '^', '(', '+', '*', '?'])
+    {
+      return self.term(token);
+    }
+
+    let path = Path::parse(token)?.map_iris(&|name| {
+      Ok(match self.term(name) {
+        Ok(Term::Node(Node::Http(iri))) => iri,
+        _ => name.to_string(),
+      })
+    })?;
+    Ok(Term::Path(path))
+  }
+
+  fn expand(&self, curie: &str) -> Result<String> {
+    match self.namespaces.expand(curie) {
+      Ok(iri) => Ok(iri),
+      Err(_) if !curie.contains(':') => Ok(curie.to_string()),
+      Err(_) => Err(invalid(format!("unknown prefix in {}", curie))),
+    }
+  }
+
+  /// Words offered by tab-completion.
+  fn candidates(&self) -> Vec<String> {
+    let mut candidates: Vec<String> =
+      COMMANDS.iter().map(|(c, _)| c.to_string()).collect();
+    let prefixes = self.namespaces.prefixes();
+    candidates.extend(prefixes.keys().map(|p| format!("{}:", p)));
+
+    for triple in self.graph.triples() {
+      let predicate = triple.predicate().to_string();
+      let compact = self.namespaces.compact(&predicate);
+      candidates.push(compact.unwrap_or_else(|| format!("<{}>", predicate)));
+    }
+    candidates.sort();
+    candidates.dedup();
+    candidates
+  }
+}
+
+/// Splits `s p o . s p o` into patterns, ignoring dots inside IRIs &
+/// strings.
+fn split_patterns(s: &str) -> Vec<String> {
+  let mut patterns = Vec::new();
+  let mut current = Vec::new();
+  for token in tokenize(s) {
+    if token == "." {
+      patterns.push(current.join(" "));
+      current.clear();
+    } else {
+      current.push(token);
+    }
+  }
+  if !current.is_empty() {
+    patterns.push(current.join(" "));
+  }
+  patterns
+}
+
+/// Splits on whitespace, keeping `"quoted strings"` together.
+fn tokenize(s: &str) -> Vec<String> {
+  let mut tokens = Vec::new();
+  let mut current = String::new();
+  let mut quoted = false;
+  let mut escaped = false;
+  for c in s.chars() {
+    match c {
+      _ if escaped => {
+        escaped = false;
+        current.push(c);
+      }
+      '\\' if quoted => {
+        escaped = true;
+        current.push(c);
+      }
+      '"' => {
+        quoted = !quoted;
+        current.push(c);
+      }
+      c if c.is_whitespace() && !quoted => {
+        if !current.is_empty() {
+          tokens.push(std::mem::take(&mut current));
+        }
+      }
+      c => current.push(c),
+    }
+  }
+  if !current.is_empty() {
+    tokens.push(current);
+  }
+  tokens
+}
+
+fn invalid(message: String) -> Error {
+  use serde::de::Error as _;
+  Error::custom(message)
+}
+
+fn readline_error(err: ReadlineError) -> Error {
+  Error::io(io::Error::other(err))
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Completion
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+struct ReplHelper {
+  candidates: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+  type Candidate = String;
+
+  fn complete(
+    &self,
+    line: &str,
+    pos: usize,
+    _ctx: &Context<'_>,
+  ) -> rustyline::Result<(usize, Vec<String>)> {
+    let start = line[..pos]
+      .rfind(char::is_whitespace)
+      .map_or(0, |idx| idx + 1);
+    let word = &line[start..pos];
+    let matches = self
+      .candidates
+      .iter()
+      .filter(|c| c.starts_with(word))
+      .cloned()
+      .collect();
+    Ok((start, matches))
+  }
+}
+
+impl Hinter for ReplHelper {
+  type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
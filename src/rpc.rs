@@ -0,0 +1,605 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::rpc` exposes a `KnowledgeGraph` over [JSON-RPC 2.0] so editors &
+//! tools written in other languages can drive an embedded sage instance,
+//! the way they drive language servers.
+//!
+//! Messages are framed like the [Language Server Protocol]: a
+//! `Content-Length: <bytes>` header, a blank line and the JSON body. The
+//! transport is either stdio (`Server::serve_stdio`) or TCP
+//! (`Server::serve_tcp`).
+//!
+//! | Method          | Params                                     | Result                |
+//! |-----------------|--------------------------------------------|-----------------------|
+//! | `graph/insert`  | `subject`, `predicate`, `object`/`literal` | new size              |
+//! | `graph/match`   | optional `subject`, `predicate`, `object`  | `[{s, p, o}]`         |
+//! | `graph/query`   | `patterns`: `[[s, p, o], ...]`, `filters`  | binding rows          |
+//! | `graph/node`    | `id`, optional `depth`                     | projected node        |
+//! | `graph/search`  | `query` (feature `fts`)                    | `[{s, p, text}]`      |
+//! | `graph/load`    | `ntriples` document                        | number of triples     |
+//! | `graph/export`  | none                                       | N-Triples document    |
+//! | `graph/size`    | none                                       | number of triples     |
+//! | `graph/checksum`| none                                       | content checksum      |
+//! | `shutdown`      | none                                       | `null`, then closes   |
+//!
+//! In `graph/query` patterns, strings starting with `?` are variables,
+//! other strings IRIs and any other value a literal. `filters` are SPARQL
+//! language filters, see `sage::query::Filter`.
+//!
+//! `shutdown` closes the session of the client sending it: stdio stops
+//! serving, while TCP keeps accepting other clients. Messages larger than
+//! 16 MiB are refused & close the session, as do TCP sessions idle for
+//! longer than `Server::timeout`.
+//!
+//! [JSON-RPC 2.0]: https://www.jsonrpc.org/specification
+//! [Language Server Protocol]: https://microsoft.github.io/language-server-protocol/
+//!
+
+use std::{
+  io::{self, BufRead, BufReader, BufWriter, Read, Write},
+  mem,
+  net::{TcpListener, TcpStream, ToSocketAddrs},
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    mpsc::{self, Sender},
+    Arc,
+  },
+  thread,
+  time::Duration,
+};
+
+use crate::{
+  datastore::json,
+  dtype::{DType, Map},
+  error::Error,
+  formats::NTriples,
+  graph::{KnowledgeGraph, Node, Predicate, Profile, Triple},
+  query::{to_term, Filter, Query, Term},
+  Result,
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+
+/// Largest message body read, in bytes.
+const MAX_MESSAGE: usize = 16 << 20;
+
+/// Longest header line read, in bytes.
+const MAX_LINE: usize = 8 << 10;
+
+/// Most header lines read per message.
+const MAX_HEADERS: usize = 100;
+
+/// Most TCP sessions served at once; others are refused with an error.
+const MAX_CONNECTIONS: usize = 64;
+
+/// Default time a TCP session may stay idle, or take to write a response,
+/// before it's closed.
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What a TCP session passes to the server: a message along with the
+/// channel for its reply, or the error that ended the session.
+enum Event {
+  Message(Vec<u8>, Sender<Result<Reply>>),
+  Dropped(Error),
+}
+
+/// The framed response to a message, if any, & whether the session ends.
+type Reply = (Option<Vec<u8>>, bool);
+
+/// An error returned to the client.
+struct RpcError {
+  code: i64,
+  message: String,
+}
+
+impl RpcError {
+  fn new(code: i64, message: impl Into<String>) -> RpcError {
+    RpcError {
+      code,
+      message: message.into(),
+    }
+  }
+
+  fn params(message: impl Into<String>) -> RpcError {
+    RpcError::new(INVALID_PARAMS, message)
+  }
+}
+
+impl From<Error> for RpcError {
+  fn from(err: Error) -> RpcError {
+    RpcError::new(SERVER_ERROR, err.to_string())
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Server
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Server` answers JSON-RPC requests against its graph.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::json;
+/// use sage::rpc::Server;
+///
+/// let mut server = Server::new();
+/// server.handle(json!({
+///   "jsonrpc": "2.0",
+///   "id": 1,
+///   "method": "graph/insert",
+///   "params": {
+///     "subject": "https://example.com/John",
+///     "predicate": "https://schema.org/name",
+///     "literal": "John"
+///   }
+/// }));
+///
+/// let response = server
+///   .handle(json!({
+///     "jsonrpc": "2.0",
+///     "id": 2,
+///     "method": "graph/query",
+///     "params": { "patterns": [["?p", "https://schema.org/name", "?name"]] }
+///   }))
+///   .unwrap();
+///
+/// assert_eq!(response["id"], 2);
+/// assert_eq!(response["result"][0]["name"], "John");
+/// ```
+pub struct Server {
+  graph: KnowledgeGraph,
+  running: bool,
+  timeout: Duration,
+  on_error: Option<Box<dyn FnMut(Error)>>,
+}
+
+impl Default for Server {
+  fn default() -> Self {
+    Server::new()
+  }
+}
+
+impl Server {
+  /// Creates a server over an empty graph.
+  pub fn new() -> Server {
+    Server::with_graph(KnowledgeGraph::new())
+  }
+
+  /// Creates a server over `graph`.
+  pub fn with_graph(graph: KnowledgeGraph) -> Server {
+    Server {
+      graph,
+      running: true,
+      timeout: TIMEOUT,
+      on_error: None,
+    }
+  }
+
+  /// Closes TCP sessions idle for longer than `timeout`, or whose client
+  /// doesn't read a response within it. Defaults to 60 seconds.
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.timeout = timeout;
+    self
+  }
+
+  /// Passes the errors ending TCP sessions, e.g. timeouts or oversized
+  /// messages, to `on_error`. They are otherwise ignored.
+  pub fn on_error<F: FnMut(Error) + 'static>(mut self, on_error: F) -> Self {
+    self.on_error = Some(Box::new(on_error));
+    self
+  }
+
+  /// Returns the served graph.
+  pub fn graph(&self) -> &KnowledgeGraph {
+    &self.graph
+  }
+
+  /// Consumes the server, returning its graph.
+  pub fn into_graph(self) -> KnowledgeGraph {
+    self.graph
+  }
+
+  /// Serves requests on stdin/stdout until `shutdown` or EOF.
+  pub fn serve_stdio(&mut self) -> Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    self.serve(stdin.lock(), stdout.lock())
+  }
+
+  /// Accepts TCP connections on `addr` until the process exits. A
+  /// client's session ends with `shutdown`, EOF or an error, which is
+  /// passed to `on_error` without stopping the server.
+  ///
+  /// Every session is read & written on its own thread, with a timeout
+  /// and at most 64 at once, while its requests are handled one at a time
+  /// on this thread.
+  pub fn serve_tcp<A: ToSocketAddrs>(&mut self, addr: A) -> Result<()> {
+    let listener = TcpListener::bind(addr).map_err(Error::io)?;
+    let (sender, events) = mpsc::channel();
+    let timeout = self.timeout;
+    thread::spawn(move || accept(listener, sender, timeout));
+    for event in events {
+      match event {
+        Event::Message(body, reply) => {
+          self.running = true;
+          let response = self.respond(&body);
+          let _ = reply.send(response.map(|r| (r, !self.running)));
+        }
+        Event::Dropped(err) => {
+          if let Some(on_error) = &mut self.on_error {
+            on_error(err);
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+
+  /// Serves `Content-Length` framed requests from `reader`, writing the
+  /// responses into `writer`, until `shutdown` or EOF.
+  pub fn serve<R: BufRead, W: Write>(
+    &mut self,
+    mut reader: R,
+    mut writer: W,
+  ) -> Result<()> {
+    self.running = true;
+    while self.running {
+      let body = match read_message(&mut reader)? {
+        Some(body) => body,
+        None => break,
+      };
+      if let Some(response) = self.respond(&body)? {
+        writer.write_all(&response).map_err(Error::io)?;
+        writer.flush().map_err(Error::io)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Handles the body of a message, returning the framed response if any.
+  fn respond(&mut self, body: &[u8]) -> Result<Option<Vec<u8>>> {
+    let response = match json::from_slice::<DType>(body) {
+      Ok(request) => self.handle(request),
+      Err(err) => Some(response(
+        DType::Null,
+        Err(RpcError::new(PARSE_ERROR, err.to_string())),
+      )),
+    };
+    match response {
+      Some(response) => Ok(Some(frame(&json::to_vec(&response)?))),
+      None => Ok(None),
+    }
+  }
+
+  /// Handles a single request (or batch), returning the response or `None`
+  /// for notifications.
+  pub fn handle(&mut self, mut request: DType) -> Option<DType> {
+    if let DType::Array(ref mut batch) = request {
+      let responses: Vec<DType> = mem::take(&mut **batch)
+        .into_iter()
+        .filter_map(|r| self.handle(r))
+        .collect();
+      return if responses.is_empty() {
+        None
+      } else {
+        Some(DType::from(responses))
+      };
+    }
+
+    let id = request.get("id").cloned();
+    let method = match request.get("method").and_then(DType::as_str) {
+      Some(method) if request["jsonrpc"] == "2.0" => method,
+      _ => {
+        return Some(response(
+          id.unwrap_or(DType::Null),
+          Err(RpcError::new(INVALID_REQUEST, "invalid request")),
+        ));
+      }
+    };
+    let params = request.get("params").cloned().unwrap_or(DType::Null);
+    let result = self.call(method, &params);
+    id.map(|id| response(id, result))
+  }
+
+  fn call(
+    &mut self,
+    method: &str,
+    params: &DType,
+  ) -> std::result::Result<DType, RpcError> {
+    match method {
+      "graph/insert" => self.insert(params),
+      "graph/match" => Ok(self.matches(params)),
+      "graph/query" => self.query(params),
+      "graph/node" => {
+        let id = string(params, "id")?;
+        let depth = params.get("depth").and_then(DType::as_u64).unwrap_or(1);
+        let profile = Profile::new().depth(depth as usize);
+        Ok(profile.project(&self.graph, &Node::Http(id.to_string())))
+      }
+      #[cfg(feature = "fts")]
+      "graph/search" => Ok(
+        self
+          .graph
+          .search(string(params, "query")?)
+          .into_iter()
+          .map(|hit| {
+            crate::json!({
+              "subject": hit.subject.to_string(),
+              "predicate": hit.predicate,
+              "text": hit.text,
+              "score": hit.score,
+            })
+          })
+          .collect(),
+      ),
+      "graph/load" => {
+        let loaded = NTriples::parse(string(params, "ntriples")?)?;
+        for triple in loaded.triples() {
+          self.graph.add(Triple::from_nodes(
+            triple.source().clone(),
+            triple.predicate().clone(),
+            triple.destination().clone(),
+          ));
+        }
+        Ok(loaded.len().into())
+      }
+      "graph/export" => Ok(NTriples::new(&self.graph).to_string().into()),
+      "graph/size" => Ok(self.graph.len().into()),
+      "graph/checksum" => Ok(self.graph.checksum().into()),
+      "shutdown" => {
+        self.running = false;
+        Ok(DType::Null)
+      }
+      method => Err(RpcError::new(
+        METHOD_NOT_FOUND,
+        format!("unknown method {}", method),
+      )),
+    }
+  }
+
+  fn insert(&mut self, params: &DType) -> std::result::Result<DType, RpcError> {
+    let subject = Node::Http(string(params, "subject")?.to_string());
+    let predicate =
+      Predicate::Literal(string(params, "predicate")?.to_string());
+    let object = match (params.get("object"), params.get("literal")) {
+      (Some(DType::String(iri)), None) => Node::Http(iri.clone()),
+      (None, Some(literal)) => Node::Literal(literal.clone()),
+      _ => {
+        return Err(RpcError::params(
+          "expected either an `object` IRI or a `literal`",
+        ))
+      }
+    };
+    self.graph.insert(subject, predicate, object);
+    Ok(self.graph.len().into())
+  }
+
+  fn matches(&self, params: &DType) -> DType {
+    let iri = |key| {
+      params
+        .get(key)
+        .and_then(DType::as_str)
+        .map(|s| Node::Http(s.to_string()))
+    };
+    let (subject, object) = (iri("subject"), iri("object"));
+    let predicate = params.get("predicate").and_then(DType::as_str);
+
+    self
+      .graph
+      .matches(subject.as_ref(), predicate, object.as_ref())
+      .map(|triple| {
+        let mut row = Map::new();
+        row.insert("subject".to_string(), to_term(triple.source()));
+        row.insert(
+          "predicate".to_string(),
+          triple.predicate().to_string().into(),
+        );
+        row.insert("object".to_string(), to_term(triple.destination()));
+        DType::Object(row)
+      })
+      .collect()
+  }
+
+  fn query(&self, params: &DType) -> std::result::Result<DType, RpcError> {
+    let patterns = params
+      .get("patterns")
+      .and_then(DType::as_array)
+      .ok_or_else(|| RpcError::params("expected `patterns`"))?;
+
+    let mut query = Query::new();
+    for pattern in patterns {
+      match pattern.as_array().map(Vec::as_slice) {
+        Some([s, p, o]) => query = query.pattern(term(s), term(p), term(o)),
+        _ => return Err(RpcError::params("expected `[s, p, o]` patterns")),
+      }
+    }
+    let filters = params.get("filters").and_then(DType::as_array);
+    for filter in filters.into_iter().flatten() {
+      let filter = filter
+        .as_str()
+        .ok_or_else(|| RpcError::params("expected filter strings"))?;
+      query = query.filter(Filter::parse(filter)?);
+    }
+    Ok(DType::from(self.graph.query(&query)))
+  }
+}
+
+fn term(value: &DType) -> Term {
+  match value {
+    DType::String(s) => Term::from(s.as_str()),
+    value => Term::from(value.clone()),
+  }
+}
+
+fn string<'a>(
+  params: &'a DType,
+  key: &str,
+) -> std::result::Result<&'a str, RpcError> {
+  params
+    .get(key)
+    .and_then(DType::as_str)
+    .ok_or_else(|| RpcError::params(format!("expected a `{}` string", key)))
+}
+
+fn response(id: DType, result: std::result::Result<DType, RpcError>) -> DType {
+  match result {
+    Ok(result) => {
+      crate::json!({ "jsonrpc": "2.0", "id": id, "result": result })
+    }
+    Err(err) => crate::json!({
+      "jsonrpc": "2.0",
+      "id": id,
+      "error": { "code": err.code, "message": err.message },
+    }),
+  }
+}
+
+/// Prefixes `body` with its `Content-Length` header.
+fn frame(body: &[u8]) -> Vec<u8> {
+  let header = format!("Content-Length: {}\r\n\r\n", body.len());
+  let mut message = header.into_bytes();
+  message.extend_from_slice(body);
+  message
+}
+
+/// Accepts connections, passing their messages to the server through
+/// `sender` along with a channel for the replies.
+fn accept(listener: TcpListener, sender: Sender<Event>, timeout: Duration) {
+  let active = Arc::new(AtomicUsize::new(0));
+  for stream in listener.incoming() {
+    let stream = match stream {
+      Ok(stream) => stream,
+      Err(err) => {
+        let _ = sender.send(Event::Dropped(Error::io(err)));
+        continue;
+      }
+    };
+    if active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+      active.fetch_sub(1, Ordering::SeqCst);
+      let busy =
+        response(DType::Null, Err(RpcError::new(SERVER_ERROR, "server busy")));
+      let _ = stream.set_write_timeout(Some(timeout));
+      if let Ok(busy) = json::to_vec(&busy) {
+        let _ = (&stream).write_all(&frame(&busy));
+      }
+      continue;
+    }
+    let (sender, active) = (sender.clone(), Arc::clone(&active));
+    thread::spawn(move || {
+      if let Err(err) = session(stream, &sender, timeout) {
+        let _ = sender.send(Event::Dropped(err));
+      }
+      active.fetch_sub(1, Ordering::SeqCst);
+    });
+  }
+}
+
+/// Reads the messages of a TCP session until `shutdown` or EOF, writing
+/// the replies of the server.
+fn session(
+  stream: TcpStream,
+  sender: &Sender<Event>,
+  timeout: Duration,
+) -> Result<()> {
+  stream.set_read_timeout(Some(timeout)).map_err(Error::io)?;
+  stream.set_write_timeout(Some(timeout)).map_err(Error::io)?;
+  let mut reader = BufReader::new(stream.try_clone().map_err(Error::io)?);
+  let mut writer = BufWriter::new(stream);
+  while let Some(body) = read_message(&mut reader)? {
+    let (reply, replies) = mpsc::channel();
+    if sender.send(Event::Message(body, reply)).is_err() {
+      return Ok(());
+    }
+    let (response, done) = match replies.recv() {
+      Ok(reply) => reply?,
+      Err(_) => return Ok(()),
+    };
+    if let Some(response) = response {
+      writer.write_all(&response).map_err(Error::io)?;
+      writer.flush().map_err(Error::io)?;
+    }
+    if done {
+      break;
+    }
+  }
+  Ok(())
+}
+
+/// Reads the next `Content-Length` framed message, `None` on EOF.
+///
+/// Fails on header lines longer than `MAX_LINE`, more than `MAX_HEADERS`
+/// of them or bodies larger than `MAX_MESSAGE`, before reading them.
+fn read_message<R: BufRead>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+  let mut length = None;
+  let mut headers = 0;
+  loop {
+    let mut line = String::new();
+    let limit = MAX_LINE as u64 + 1;
+    let read = reader
+      .by_ref()
+      .take(limit)
+      .read_line(&mut line)
+      .map_err(Error::io)?;
+    if read == 0 {
+      return Ok(None);
+    }
+    if line.len() > MAX_LINE {
+      return Err(Error::io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "header line too long",
+      )));
+    }
+    let line = line.trim_end();
+    if line.is_empty() {
+      // Blank lines before the headers are tolerated.
+      if length.is_some() {
+        break;
+      }
+      continue;
+    }
+    headers += 1;
+    if headers > MAX_HEADERS {
+      return Err(Error::io(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "too many header lines",
+      )));
+    }
+    if let Some((name, value)) = line.split_once(':') {
+      if name.eq_ignore_ascii_case("content-length") {
+        length = value.trim().parse::<usize>().ok();
+      }
+    }
+  }
+
+  let length = length.unwrap_or_default();
+  if length > MAX_MESSAGE {
+    return Err(Error::io(io::Error::new(
+      io::ErrorKind::InvalidData,
+      format!("message of {} bytes exceeds {}", length, MAX_MESSAGE),
+    )));
+  }
+  let mut body = vec![0; length];
+  reader.read_exact(&mut body).map_err(Error::io)?;
+  Ok(Some(body))
+}
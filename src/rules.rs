@@ -0,0 +1,308 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::rules` evaluates Datalog programs over a `KnowledgeGraph`, to
+//! encode domain inference beyond what RDFS & OWL entail.
+//!
+//! A program is made of facts & horn-clause rules, each ending with `.`:
+//!
+//! ```text
+//! PREFIX ex: <https://example.com/>
+//! parent(ex:Ada, ex:Bob).
+//! grandparent(X, Z) :- parent(X, Y), parent(Y, Z).
+//! orphan(X) :- person(X), not parent(_, X).
+//! ```
+//!
+//! - Relations named by an identifier (`parent`) are defined by the
+//!   program, with any arity. Relations named by an IRI or a prefixed name
+//!   (`schema:knows`) are binary: they hold the statements of the graph
+//!   with that predicate, along with those the program derives.
+//! - Variables start with an uppercase letter or `_`; every `_` is a
+//!   distinct variable. Other identifiers are string constants, along with
+//!   the literals, IRIs & prefixed names of Turtle.
+//! - Premises are atoms, negated atoms (`not p(X)` or `!p(X)`) & (in)equal
+//!   terms (`X = Y`, `X != Y`). Variables of the head, of negated atoms
+//!   (other than `_`) & of comparisons must occur in a positive atom.
+//!
+//! Rules are evaluated bottom-up by semi-naive iteration: after a first
+//! round, a rule is only re-evaluated against the facts derived in the
+//! previous round. Negation is stratified: a relation is computed in full
+//! before any rule negates it, so programs with recursion through negation
+//! are rejected.
+//!
+//! # Example
+//!
+//! ```rust
+//! use sage::graph::{KnowledgeGraph, Node, Predicate};
+//! use sage::rules::Program;
+//!
+//! let node = |name: &str| Node::Http(format!("https://example.com/{}", name));
+//! let parent = Predicate::Literal("https://schema.org/parent".to_string());
+//!
+//! // Each person's parent.
+//! let mut graph = KnowledgeGraph::new();
+//! graph.insert(node("Bob"), parent.clone(), node("Ada"));
+//! graph.insert(node("Cy"), parent, node("Bob"));
+//!
+//! let program = Program::parse(
+//!   "PREFIX ex: <https://example.com/>
+//!    parent(X, Y) :- schema:parent(Y, X).
+//!    ancestor(X, Y) :- parent(X, Y).
+//!    ancestor(X, Z) :- ancestor(X, Y), parent(Y, Z).
+//!    person(X) :- parent(X, _).
+//!    person(X) :- parent(_, X).
+//!    childless(X) :- person(X), not parent(X, _).
+//!    ex:grandparent(X, Z) :- parent(X, Y), parent(Y, Z).",
+//! )
+//! .unwrap();
+//!
+//! let facts = program.evaluate(&graph);
+//! assert_eq!(facts.count("ancestor"), 3);
+//! assert!(facts.contains("ancestor", &[node("Ada"), node("Cy")]));
+//! assert_eq!(facts.get("childless").collect::<Vec<_>>(), [[node("Cy")]]);
+//!
+//! // Write the derived statements of IRI relations back to the graph.
+//! assert_eq!(program.materialize(&mut graph), 1);
+//! let grandparent = "https://example.com/grandparent";
+//! assert_eq!(graph.matches(None, Some(grandparent), None).count(), 1);
+//!
+//! // Recursion through negation can't be stratified.
+//! assert!(Program::parse("p(X) :- q(X), not p(X).").is_err());
+//! ```
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+  graph::{KnowledgeGraph, Node, Predicate},
+  Result,
+};
+
+mod eval;
+mod parser;
+
+use eval::Relation;
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Clauses
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// An argument of an atom.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Arg {
+  Var(String),
+  Const(Node),
+}
+
+/// A relation applied to arguments, e.g. `parent(X, ex:Bob)`.
+#[derive(Clone, Debug)]
+pub(crate) struct Atom {
+  /// Identifier of a program relation, or IRI of a graph relation.
+  pub(crate) relation: String,
+  pub(crate) args: Vec<Arg>,
+}
+
+impl Atom {
+  /// Returns `true` for relations holding graph statements.
+  pub(crate) fn is_graph(&self) -> bool {
+    is_graph(&self.relation)
+  }
+}
+
+/// A premise of a rule.
+#[derive(Clone, Debug)]
+pub(crate) enum Literal {
+  Positive(Atom),
+  Negative(Atom),
+  /// Compared terms, `true` if they must differ.
+  Compare(Arg, Arg, bool),
+}
+
+/// A fact (without premises) or a rule.
+#[derive(Clone, Debug)]
+pub(crate) struct Clause {
+  pub(crate) head: Atom,
+  pub(crate) body: Vec<Literal>,
+}
+
+impl Clause {
+  /// Returns the head & the (negated) atoms of the body.
+  pub(crate) fn atoms(&self) -> impl Iterator<Item = &Atom> {
+    let body = self.body.iter().filter_map(|literal| match literal {
+      Literal::Positive(atom) | Literal::Negative(atom) => Some(atom),
+      Literal::Compare(..) => None,
+    });
+    std::iter::once(&self.head).chain(body)
+  }
+}
+
+/// Returns `true` if `relation` is an IRI, naming a graph relation.
+fn is_graph(relation: &str) -> bool {
+  relation.contains(':')
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Program
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Program` is a set of Datalog facts & rules, see the module
+/// documentation for their syntax.
+///
+/// Clauses are checked as they're added: a program is always safe &
+/// stratified.
+#[derive(Clone, Debug, Default)]
+pub struct Program {
+  clauses: Vec<Clause>,
+  /// Indices of the rules of every stratum, in evaluation order.
+  strata: Vec<Vec<usize>>,
+}
+
+impl Program {
+  /// Creates an empty program.
+  pub fn new() -> Program {
+    Program::default()
+  }
+
+  /// Parses a program.
+  pub fn parse(s: &str) -> Result<Program> {
+    let mut program = Program::new();
+    program.add(s)?;
+    Ok(program)
+  }
+
+  /// Registers the facts & rules of `s`, with its own `PREFIX`
+  /// declarations.
+  ///
+  /// # Errors
+  ///
+  /// Fails if a clause is malformed or unsafe, if a relation is used with
+  /// different arities, or if the rules can't be stratified. The program
+  /// is left unchanged then.
+  pub fn add(&mut self, s: &str) -> Result<()> {
+    let mut clauses = self.clauses.clone();
+    clauses.extend(parser::clauses(s, &self.clauses)?);
+    let strata = eval::stratify(&clauses)?;
+    self.clauses = clauses;
+    self.strata = strata;
+    Ok(())
+  }
+
+  /// Returns the number of facts & rules.
+  pub fn len(&self) -> usize {
+    self.clauses.len()
+  }
+
+  /// Returns `true` if the program has no facts or rules.
+  pub fn is_empty(&self) -> bool {
+    self.clauses.is_empty()
+  }
+
+  /// Computes every fact derivable from the program & the statements of
+  /// `graph`.
+  pub fn evaluate(&self, graph: &KnowledgeGraph) -> Facts {
+    Facts {
+      relations: eval::evaluate(&self.clauses, &self.strata, graph),
+    }
+  }
+
+  /// Evaluates the program against `graph` & inserts the facts of graph
+  /// relations into it, returning the number of statements added. Facts
+  /// with a literal subject can't be statements, so they're skipped.
+  pub fn materialize(&self, graph: &mut KnowledgeGraph) -> usize {
+    let facts = self.evaluate(graph);
+    let mut inserted = 0;
+    for (relation, tuples) in &facts.relations {
+      if !is_graph(relation) {
+        continue;
+      }
+      for tuple in tuples.iter() {
+        let (s, o) = (&tuple[0], &tuple[1]);
+        if matches!(s, Node::Literal(_))
+          || graph
+            .matches(Some(s), Some(relation), Some(o))
+            .next()
+            .is_some()
+        {
+          continue;
+        }
+        let predicate = Predicate::Literal(relation.clone());
+        graph.insert(s.clone(), predicate, o.clone());
+        inserted += 1;
+      }
+    }
+    inserted
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Facts
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Facts` are the tuples of every relation of an evaluated `Program`,
+/// graph relations included.
+pub struct Facts {
+  relations: HashMap<String, Relation>,
+}
+
+impl Facts {
+  /// Returns the tuples of `relation` (an identifier or a full IRI), in the
+  /// order they were derived.
+  pub fn get<'a>(
+    &'a self,
+    relation: &str,
+  ) -> impl Iterator<Item = &'a [Node]> + 'a {
+    self
+      .relations
+      .get(relation)
+      .into_iter()
+      .flat_map(|relation| relation.iter())
+  }
+
+  /// Returns `true` if `relation` holds `tuple`.
+  pub fn contains(&self, relation: &str, tuple: &[Node]) -> bool {
+    self
+      .relations
+      .get(relation)
+      .is_some_and(|relation| relation.contains(tuple))
+  }
+
+  /// Returns the number of tuples of `relation`.
+  pub fn count(&self, relation: &str) -> usize {
+    self.relations.get(relation).map_or(0, Relation::len)
+  }
+}
+
+impl fmt::Debug for Facts {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_map()
+      .entries(
+        self
+          .relations
+          .iter()
+          .map(|(name, relation)| (name, relation.iter().collect::<Vec<_>>())),
+      )
+      .finish()
+  }
+}
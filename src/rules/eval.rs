@@ -0,0 +1,358 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Stratification & semi-naive evaluation of Datalog programs.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+  error::{Error, ErrorCode},
+  graph::{KnowledgeGraph, Node},
+  rules::{Arg, Atom, Clause, Literal},
+  Result,
+};
+
+/// Values of the variables of a rule.
+type Bindings = HashMap<String, Node>;
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Relation
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// The tuples of a relation, in insertion order, indexed by the value of
+/// every column.
+#[derive(Default)]
+pub(crate) struct Relation {
+  tuples: Vec<Vec<Node>>,
+  set: HashSet<Vec<Node>>,
+  columns: Vec<HashMap<Node, Vec<usize>>>,
+}
+
+impl Relation {
+  /// Adds `tuple`, returning `false` if it's already there.
+  fn insert(&mut self, tuple: Vec<Node>) -> bool {
+    if self.set.contains(&tuple) {
+      return false;
+    }
+    if self.columns.len() < tuple.len() {
+      self.columns.resize_with(tuple.len(), HashMap::new);
+    }
+    let i = self.tuples.len();
+    for (column, value) in self.columns.iter_mut().zip(&tuple) {
+      column.entry(value.clone()).or_default().push(i);
+    }
+    self.set.insert(tuple.clone());
+    self.tuples.push(tuple);
+    true
+  }
+
+  pub(crate) fn contains(&self, tuple: &[Node]) -> bool {
+    self.set.contains(tuple)
+  }
+
+  pub(crate) fn len(&self) -> usize {
+    self.tuples.len()
+  }
+
+  pub(crate) fn iter(&self) -> impl Iterator<Item = &[Node]> {
+    self.tuples.iter().map(Vec::as_slice)
+  }
+
+  /// Returns the tuples which may match `args` under `bindings`: those
+  /// sharing the value of the first bound argument, or every tuple.
+  fn candidates<'a>(
+    &'a self,
+    args: &[Arg],
+    bindings: &Bindings,
+  ) -> Box<dyn Iterator<Item = &'a [Node]> + 'a> {
+    let bound = args.iter().enumerate().find_map(|(i, arg)| {
+      let value = match arg {
+        Arg::Const(node) => node,
+        Arg::Var(var) => bindings.get(var)?,
+      };
+      Some((i, value))
+    });
+    match bound {
+      Some((i, value)) => {
+        let rows = self.columns.get(i).and_then(|column| column.get(value));
+        Box::new(
+          rows
+            .into_iter()
+            .flatten()
+            .map(move |&row| self.tuples[row].as_slice()),
+        )
+      }
+      None => Box::new(self.iter()),
+    }
+  }
+}
+
+/// Binds `args` to the values of `tuple`, returning `None` if they
+/// conflict with `bindings`.
+fn unify(
+  args: &[Arg],
+  tuple: &[Node],
+  bindings: &Bindings,
+) -> Option<Bindings> {
+  let mut bindings = bindings.clone();
+  for (arg, value) in args.iter().zip(tuple) {
+    match arg {
+      Arg::Const(node) if node != value => return None,
+      Arg::Const(_) => {}
+      Arg::Var(var) => match bindings.get(var) {
+        Some(bound) if bound != value => return None,
+        Some(_) => {}
+        None => {
+          bindings.insert(var.clone(), value.clone());
+        }
+      },
+    }
+  }
+  Some(bindings)
+}
+
+/// Returns the value of `arg` under `bindings`.
+fn value<'a>(arg: &'a Arg, bindings: &'a Bindings) -> Option<&'a Node> {
+  match arg {
+    Arg::Const(node) => Some(node),
+    Arg::Var(var) => bindings.get(var),
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Stratification
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Assigns every rule to a stratum, so that a relation is computed in full
+/// before it's negated, returning the indices of the rules of every
+/// stratum. Facts aren't part of any stratum.
+///
+/// # Errors
+///
+/// Fails if a relation depends negatively on itself.
+pub(crate) fn stratify(clauses: &[Clause]) -> Result<Vec<Vec<usize>>> {
+  let relations: HashSet<&str> = clauses
+    .iter()
+    .map(|clause| clause.head.relation.as_str())
+    .collect();
+  let mut strata: HashMap<&str, usize> = HashMap::new();
+  let mut changed = true;
+  while changed {
+    changed = false;
+    for clause in clauses {
+      let head = clause.head.relation.as_str();
+      let mut stratum = strata.get(head).copied().unwrap_or(0);
+      for literal in &clause.body {
+        let required = match literal {
+          Literal::Positive(atom) => {
+            strata.get(atom.relation.as_str()).copied().unwrap_or(0)
+          }
+          Literal::Negative(atom) => {
+            strata.get(atom.relation.as_str()).copied().unwrap_or(0) + 1
+          }
+          Literal::Compare(..) => 0,
+        };
+        stratum = stratum.max(required);
+      }
+      // Strata only grow past the number of relations through a cycle.
+      if stratum > relations.len() {
+        return Err(Error::syntax(ErrorCode::InvalidRules, 0, 0));
+      }
+      if strata.get(head) != Some(&stratum) {
+        strata.insert(head, stratum);
+        changed = true;
+      }
+    }
+  }
+
+  let mut rules: Vec<Vec<usize>> = Vec::new();
+  for (i, clause) in clauses.iter().enumerate() {
+    if clause.body.is_empty() {
+      continue;
+    }
+    let stratum = strata[clause.head.relation.as_str()];
+    if rules.len() <= stratum {
+      rules.resize_with(stratum + 1, Vec::new);
+    }
+    rules[stratum].push(i);
+  }
+  Ok(rules)
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Evaluation
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Computes the relations of a stratified program: graph relations are
+/// loaded from `graph`, facts added, then the rules of every stratum
+/// evaluated by semi-naive iteration.
+pub(crate) fn evaluate(
+  clauses: &[Clause],
+  strata: &[Vec<usize>],
+  graph: &KnowledgeGraph,
+) -> HashMap<String, Relation> {
+  let mut relations: HashMap<String, Relation> = HashMap::new();
+  for atom in clauses.iter().flat_map(Clause::atoms) {
+    if relations.contains_key(&atom.relation) {
+      continue;
+    }
+    let mut relation = Relation::default();
+    if atom.is_graph() {
+      for triple in graph.matches(None, Some(&atom.relation), None) {
+        let tuple = vec![triple.source().clone(), triple.destination().clone()];
+        relation.insert(tuple);
+      }
+    }
+    relations.insert(atom.relation.clone(), relation);
+  }
+
+  for clause in clauses.iter().filter(|clause| clause.body.is_empty()) {
+    let tuple = instantiate(&clause.head, &Bindings::new());
+    if let (Some(relation), Some(tuple)) =
+      (relations.get_mut(&clause.head.relation), tuple)
+    {
+      relation.insert(tuple);
+    }
+  }
+
+  for stratum in strata {
+    let rules: Vec<&Clause> = stratum.iter().map(|&i| &clauses[i]).collect();
+    let recursive: HashSet<&str> = rules
+      .iter()
+      .map(|rule| rule.head.relation.as_str())
+      .collect();
+
+    // The first round evaluates every rule against every fact.
+    let mut delta = HashMap::new();
+    for rule in &rules {
+      for tuple in solve(rule, &relations, None) {
+        derive(&mut relations, &mut delta, &rule.head.relation, tuple);
+      }
+    }
+
+    // Later rounds only join the facts derived by the previous one.
+    while !delta.is_empty() {
+      let mut next = HashMap::new();
+      for rule in &rules {
+        for (i, literal) in rule.body.iter().enumerate() {
+          let atom = match literal {
+            Literal::Positive(atom) => atom,
+            _ => continue,
+          };
+          if !recursive.contains(atom.relation.as_str()) {
+            continue;
+          }
+          let delta = match delta.get(&atom.relation) {
+            Some(delta) => delta,
+            None => continue,
+          };
+          for tuple in solve(rule, &relations, Some((i, delta))) {
+            derive(&mut relations, &mut next, &rule.head.relation, tuple);
+          }
+        }
+      }
+      delta = next;
+    }
+  }
+  relations
+}
+
+/// Adds `tuple` to `relation` & to the facts derived in this round, if
+/// it's new.
+fn derive(
+  relations: &mut HashMap<String, Relation>,
+  delta: &mut HashMap<String, Relation>,
+  relation: &str,
+  tuple: Vec<Node>,
+) {
+  let all = relations.entry(relation.to_string()).or_default();
+  if all.insert(tuple.clone()) {
+    delta.entry(relation.to_string()).or_default().insert(tuple);
+  }
+}
+
+/// Returns the head tuples of every solution of `rule`, joining its
+/// premises in order. With `delta`, the premise at its index only matches
+/// the tuples of the given relation.
+fn solve(
+  rule: &Clause,
+  relations: &HashMap<String, Relation>,
+  delta: Option<(usize, &Relation)>,
+) -> Vec<Vec<Node>> {
+  let empty = Relation::default();
+  let mut solutions = vec![Bindings::new()];
+  for (i, literal) in rule.body.iter().enumerate() {
+    let atom = match literal {
+      Literal::Positive(atom) => atom,
+      _ => continue,
+    };
+    let relation = match delta {
+      Some((j, delta)) if i == j => delta,
+      _ => relations.get(&atom.relation).unwrap_or(&empty),
+    };
+    solutions = solutions
+      .iter()
+      .flat_map(|bindings| {
+        relation
+          .candidates(&atom.args, bindings)
+          .filter_map(move |tuple| unify(&atom.args, tuple, bindings))
+      })
+      .collect();
+    if solutions.is_empty() {
+      return Vec::new();
+    }
+  }
+
+  // Negations & comparisons only test variables bound by the atoms.
+  solutions.retain(|bindings| {
+    rule.body.iter().all(|literal| match literal {
+      Literal::Positive(_) => true,
+      Literal::Negative(atom) => {
+        let relation = relations.get(&atom.relation).unwrap_or(&empty);
+        !relation
+          .candidates(&atom.args, bindings)
+          .any(|tuple| unify(&atom.args, tuple, bindings).is_some())
+      }
+      Literal::Compare(a, b, differ) => {
+        (value(a, bindings) != value(b, bindings)) == *differ
+      }
+    })
+  });
+  solutions
+    .iter()
+    .filter_map(|bindings| instantiate(&rule.head, bindings))
+    .collect()
+}
+
+/// Returns the tuple `atom` stands for under `bindings`, if every argument
+/// is bound.
+fn instantiate(atom: &Atom, bindings: &Bindings) -> Option<Vec<Node>> {
+  atom
+    .args
+    .iter()
+    .map(|arg| value(arg, bindings).cloned())
+    .collect()
+}
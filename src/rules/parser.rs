@@ -0,0 +1,183 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Parsing of Datalog facts & rules.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+  graph::Node,
+  query::{Parser, Term},
+  rules::{Arg, Atom, Clause, Literal},
+  Result,
+};
+
+/// Parses the clauses of `s`, checking they're safe & use every relation
+/// with a single arity, including in the `existing` clauses.
+pub(crate) fn clauses(s: &str, existing: &[Clause]) -> Result<Vec<Clause>> {
+  let mut parser = Parser::rules(s);
+  parser.prologue()?;
+  let mut clauses = Vec::new();
+  let mut arities: HashMap<String, usize> = HashMap::new();
+  for clause in existing {
+    for atom in clause.atoms() {
+      arities.insert(atom.relation.clone(), atom.args.len());
+    }
+  }
+  let mut anonymous = 0;
+  while !parser.is_done() {
+    parser.skip_whitespace();
+    let start = parser.error();
+    let clause = clause(&mut parser, &mut anonymous)?;
+
+    for atom in clause.atoms() {
+      let arity = *arities
+        .entry(atom.relation.clone())
+        .or_insert(atom.args.len());
+      if arity != atom.args.len() || (atom.is_graph() && arity != 2) {
+        return Err(start);
+      }
+    }
+    if !is_safe(&clause) {
+      return Err(start);
+    }
+    clauses.push(clause);
+  }
+  Ok(clauses)
+}
+
+/// Parses `head.` or `head :- literal, ... .`.
+fn clause(parser: &mut Parser, anonymous: &mut usize) -> Result<Clause> {
+  let head = atom(parser, anonymous)?.ok_or_else(|| parser.error())?;
+  let mut body = Vec::new();
+  if parser.eat(':') {
+    parser.expect('-')?;
+    loop {
+      body.push(literal(parser, anonymous)?);
+      if !parser.eat(',') {
+        break;
+      }
+    }
+  }
+  parser.expect('.')?;
+  Ok(Clause { head, body })
+}
+
+/// Parses an atom, a negated atom or a comparison.
+fn literal(parser: &mut Parser, anonymous: &mut usize) -> Result<Literal> {
+  if parser.keyword("not") || parser.eat('!') {
+    let atom = atom(parser, anonymous)?.ok_or_else(|| parser.error())?;
+    return Ok(Literal::Negative(atom));
+  }
+  if let Some(atom) = atom(parser, anonymous)? {
+    return Ok(Literal::Positive(atom));
+  }
+
+  let left = arg(parser, anonymous)?;
+  let differ = parser.eat('!');
+  parser.expect('=')?;
+  let right = arg(parser, anonymous)?;
+  Ok(Literal::Compare(left, right, differ))
+}
+
+/// Parses `relation(arg, ...)`, returning `None` (without consuming
+/// anything) if no relation comes next.
+fn atom(parser: &mut Parser, anonymous: &mut usize) -> Result<Option<Atom>> {
+  parser.skip_whitespace();
+  let start = parser.parsed().len();
+  let relation = match parser.identifier() {
+    Some(name) => name.to_string(),
+    None => match parser.term() {
+      Ok(Term::Node(Node::Http(iri))) => iri,
+      _ => return rewind(parser, start),
+    },
+  };
+  if !parser.eat('(') {
+    return rewind(parser, start);
+  }
+
+  let mut args = Vec::new();
+  loop {
+    args.push(arg(parser, anonymous)?);
+    if !parser.eat(',') {
+      break;
+    }
+  }
+  parser.expect(')')?;
+  Ok(Some(Atom { relation, args }))
+}
+
+/// Moves `parser` back to `pos`, for lack of an atom.
+fn rewind(parser: &mut Parser, pos: usize) -> Result<Option<Atom>> {
+  parser.rewind(pos);
+  Ok(None)
+}
+
+/// Parses a variable or a constant.
+fn arg(parser: &mut Parser, anonymous: &mut usize) -> Result<Arg> {
+  if let Some(name) = parser.identifier() {
+    return Ok(match name {
+      "_" => {
+        *anonymous += 1;
+        Arg::Var(format!("_#{}", anonymous))
+      }
+      "true" | "false" => Arg::Const(Node::Literal((name == "true").into())),
+      name if name.starts_with(|c: char| c.is_uppercase() || c == '_') => {
+        Arg::Var(name.to_string())
+      }
+      name => Arg::Const(Node::Literal(name.into())),
+    });
+  }
+  match parser.term()? {
+    Term::Var(var) => Ok(Arg::Var(var)),
+    Term::Node(node) => Ok(Arg::Const(node)),
+    Term::Path(_) => Err(parser.error()),
+  }
+}
+
+/// Returns `true` if the variables of the head, of negated atoms (but
+/// `_`) & of comparisons occur in a positive atom of the body.
+fn is_safe(clause: &Clause) -> bool {
+  let mut bound = HashSet::new();
+  for literal in &clause.body {
+    if let Literal::Positive(atom) = literal {
+      bound.extend(variables(&atom.args));
+    }
+  }
+
+  let mut required: Vec<&str> = variables(&clause.head.args).collect();
+  for literal in &clause.body {
+    match literal {
+      Literal::Positive(_) => {}
+      Literal::Negative(atom) => required
+        .extend(variables(&atom.args).filter(|var| !var.starts_with("_#"))),
+      Literal::Compare(a, b, _) => {
+        for arg in [a, b] {
+          if let Arg::Var(var) = arg {
+            required.push(var);
+          }
+        }
+      }
+    }
+  }
+  required.iter().all(|var| bound.contains(var))
+}
+
+/// Returns the variables among `args`.
+fn variables(args: &[Arg]) -> impl Iterator<Item = &str> {
+  args.iter().filter_map(|arg| match arg {
+    Arg::Var(var) => Some(var.as_str()),
+    Arg::Const(_) => None,
+  })
+}
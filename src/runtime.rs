@@ -0,0 +1,228 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::runtime` runs background components (sweepers, index builders,
+//! replication, change feed consumers, ...) on threads it owns, so an
+//! embedding service can stop them all on exit instead of leaking them.
+//!
+//! Every task gets a `CancellationToken` it's expected to poll (or wait
+//! on) and return once cancelled. `Runtime::shutdown` cancels tasks in the
+//! reverse order they were spawned in, so a task can rely on those spawned
+//! before it, and joins each within what's left of a deadline.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use sage::graph::{KnowledgeGraph, Node, Predicate};
+//! use sage::runtime::Runtime;
+//!
+//! let mut graph = KnowledgeGraph::new();
+//! let (_, changes) = graph.watch(16);
+//!
+//! let mut runtime = Runtime::new();
+//! runtime
+//!   .spawn("replication", move |token| {
+//!     while !token.is_cancelled() {
+//!       if let Ok(batch) = changes.recv_timeout(Duration::from_millis(10)) {
+//!         // Ship `batch` to a replica...
+//!         drop(batch);
+//!       }
+//!     }
+//!   })
+//!   .unwrap();
+//!
+//! graph.insert(
+//!   Node::Http("https://example.com/John".to_string()),
+//!   Predicate::Literal("name".to_string()),
+//!   Node::Literal("John".into()),
+//! );
+//!
+//! let report = runtime.shutdown(Duration::from_secs(1));
+//! assert_eq!(report.stopped, vec!["replication"]);
+//! assert!(report.is_clean());
+//! ```
+
+use std::{
+  sync::{
+    mpsc::{self, Receiver, RecvTimeoutError},
+    Arc, Condvar, Mutex,
+  },
+  thread::{self, JoinHandle},
+  time::{Duration, Instant},
+};
+
+use crate::{error::Error, Result};
+
+/// How long dropping a `Runtime` waits for its tasks.
+const DROP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | CancellationToken
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `CancellationToken` tells a background task to stop. Clones share the
+/// same state.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+  state: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl CancellationToken {
+  /// Creates a token which isn't cancelled.
+  pub fn new() -> CancellationToken {
+    CancellationToken::default()
+  }
+
+  /// Cancels the token, waking up every waiting task.
+  pub fn cancel(&self) {
+    let (cancelled, condvar) = &*self.state;
+    *cancelled.lock().unwrap_or_else(|e| e.into_inner()) = true;
+    condvar.notify_all();
+  }
+
+  /// Returns `true` once the token is cancelled.
+  pub fn is_cancelled(&self) -> bool {
+    *self.state.0.lock().unwrap_or_else(|e| e.into_inner())
+  }
+
+  /// Sleeps for up to `timeout`, returning early (with `true`) once the
+  /// token is cancelled. Meant for periodic tasks, e.g. a sweeper running
+  /// every minute.
+  pub fn wait_timeout(&self, timeout: Duration) -> bool {
+    let (cancelled, condvar) = &*self.state;
+    let guard = cancelled.lock().unwrap_or_else(|e| e.into_inner());
+    let (guard, _) = condvar
+      .wait_timeout_while(guard, timeout, |cancelled| !*cancelled)
+      .unwrap_or_else(|e| e.into_inner());
+    *guard
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Runtime
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// A background task owned by a `Runtime`.
+struct Task {
+  name: String,
+  token: CancellationToken,
+  handle: JoinHandle<()>,
+  /// Disconnected once the task's thread is done, even if it panicked.
+  done: Receiver<()>,
+}
+
+/// `Shutdown` reports how each task of a `Runtime` stopped, in shutdown
+/// order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Shutdown {
+  /// Tasks which returned.
+  pub stopped: Vec<String>,
+  /// Tasks which panicked.
+  pub panicked: Vec<String>,
+  /// Tasks still running at the deadline, left detached.
+  pub timed_out: Vec<String>,
+}
+
+impl Shutdown {
+  /// Returns `true` if every task returned in time.
+  pub fn is_clean(&self) -> bool {
+    self.panicked.is_empty() && self.timed_out.is_empty()
+  }
+}
+
+/// `Runtime` owns the threads of background tasks. See the module
+/// documentation.
+///
+/// Dropping a runtime shuts it down, waiting up to 5 seconds.
+#[derive(Default)]
+pub struct Runtime {
+  tasks: Vec<Task>,
+}
+
+impl Runtime {
+  /// Creates a runtime without tasks.
+  pub fn new() -> Runtime {
+    Runtime::default()
+  }
+
+  /// Runs `f` on a new thread named `name`, passing it the token which
+  /// `shutdown` cancels.
+  pub fn spawn<F>(&mut self, name: &str, f: F) -> Result<CancellationToken>
+  where
+    F: FnOnce(CancellationToken) + Send + 'static,
+  {
+    let token = CancellationToken::new();
+    let (sender, done) = mpsc::channel::<()>();
+    let task_token = token.clone();
+    let handle = thread::Builder::new()
+      .name(name.to_string())
+      .spawn(move || {
+        // Dropped when the thread ends, panicking or not.
+        let _sender = sender;
+        f(task_token);
+      })
+      .map_err(Error::io)?;
+
+    self.tasks.push(Task {
+      name: name.to_string(),
+      token: token.clone(),
+      handle,
+      done,
+    });
+    Ok(token)
+  }
+
+  /// Returns the names of the tasks, in the order they were spawned in.
+  pub fn tasks(&self) -> Vec<&str> {
+    self.tasks.iter().map(|task| task.name.as_str()).collect()
+  }
+
+  /// Cancels & joins every task, last spawned first, giving up on those
+  /// still running once `timeout` elapsed.
+  pub fn shutdown(&mut self, timeout: Duration) -> Shutdown {
+    let deadline = Instant::now() + timeout;
+    let mut report = Shutdown::default();
+
+    while let Some(task) = self.tasks.pop() {
+      task.token.cancel();
+      let left = deadline.saturating_duration_since(Instant::now());
+      match task.done.recv_timeout(left) {
+        Err(RecvTimeoutError::Timeout) => report.timed_out.push(task.name),
+        Ok(()) | Err(RecvTimeoutError::Disconnected) => {
+          match task.handle.join() {
+            Ok(()) => report.stopped.push(task.name),
+            Err(_) => report.panicked.push(task.name),
+          }
+        }
+      }
+    }
+    report
+  }
+}
+
+impl Drop for Runtime {
+  fn drop(&mut self) {
+    self.shutdown(DROP_TIMEOUT);
+  }
+}
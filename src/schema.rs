@@ -15,7 +15,16 @@
 //! `sage::schema`
 //!
 
+mod constraints;
 mod custom;
 mod jsonld;
+mod ontology;
 mod rdf;
+mod typer;
 mod wikidata;
+
+pub use constraints::{
+  Constraint, Constraints, Datatype, Enforcement, Violation,
+};
+pub use ontology::{Ontology, ValidationMode, REPORT_NS};
+pub use typer::{TypeGuess, Typer};
@@ -0,0 +1,325 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+  dtype::DType,
+  error::{Error, ErrorCode},
+  graph::{KnowledgeGraph, Node, Predicate, Triple},
+  Result,
+};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Datatype
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Datatype` is the kind of value a predicate accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Datatype {
+  /// A boolean literal.
+  Boolean,
+  /// An integer literal.
+  Integer,
+  /// A numeric literal, integer or floating point.
+  Number,
+  /// A string literal.
+  String,
+  /// A date, time or datetime literal.
+  DateTime,
+  /// A binary literal.
+  Bytes,
+  /// An IRI.
+  Iri,
+  /// An IRI or a blank node, i.e. anything but a literal.
+  Resource,
+}
+
+impl Datatype {
+  /// Returns `true` if `value` is of this datatype.
+  pub fn accepts(&self, value: &Node) -> bool {
+    match (self, value) {
+      (Datatype::Iri, Node::Http(_)) => true,
+      (Datatype::Resource, node) => !matches!(node, Node::Literal(_)),
+      (Datatype::Boolean, Node::Literal(DType::Boolean(_))) => true,
+      (Datatype::Integer, Node::Literal(DType::Number(n))) => {
+        n.is_i64() || n.is_u64()
+      }
+      (Datatype::Number, Node::Literal(DType::Number(_))) => true,
+      (Datatype::String, Node::Literal(DType::String(_))) => true,
+      (Datatype::DateTime, Node::Literal(DType::DateTime(_))) => true,
+      (Datatype::Bytes, Node::Literal(DType::Bytes(_))) => true,
+      _ => false,
+    }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Enforcement
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Enforcement` decides what happens to statements violating the
+/// `Constraints` of a graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Enforcement {
+  /// Reject the statement with a `ConstraintViolation` error.
+  #[default]
+  Reject,
+  /// Keep the statement out of the graph, but record the violation so it
+  /// can be reviewed & repaired later.
+  Quarantine,
+  /// Admit the statement & record the violation.
+  Collect,
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Violation
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Constraint` is a single rule on the values of a predicate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Constraint {
+  /// Values must be of the datatype.
+  Datatype(Datatype),
+  /// A subject can't have more distinct values.
+  MaxCount(usize),
+}
+
+/// `Violation` is a statement breaking a `Constraint`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+  /// Subject of the statement.
+  pub subject: Node,
+  /// Predicate of the statement.
+  pub predicate: Predicate,
+  /// Value of the statement.
+  pub value: Node,
+  /// The broken constraint.
+  pub constraint: Constraint,
+  /// `true` if the statement was admitted into the graph anyway.
+  pub admitted: bool,
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Constraints
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Constraints` are datatype & cardinality rules on the values of
+/// predicates, checked when statements are written to a `KnowledgeGraph`
+/// through `KnowledgeGraph::try_insert`.
+///
+/// Violating statements are rejected, quarantined or collected depending on
+/// the `Enforcement`. Predicates without constraints accept any value; see
+/// `Ontology` to restrict the predicates themselves.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::schema::{Constraint, Constraints, Datatype, Enforcement};
+///
+/// let birth_date = "https://schema.org/birthDate";
+/// let constraints = Constraints::new()
+///   .datatype(birth_date, Datatype::Integer)
+///   .max_count(birth_date, 1);
+///
+/// let mut graph = KnowledgeGraph::new();
+/// graph.set_constraints(constraints.clone());
+///
+/// let ada = Node::Http("https://example.com/Ada".to_string());
+/// let born = Predicate::Literal(birth_date.to_string());
+/// graph
+///   .try_insert(ada.clone(), born.clone(), Node::Literal(1815.into()))
+///   .unwrap();
+///
+/// // A second birth date is one too many.
+/// let twice = Node::Literal(1816.into());
+/// assert!(graph.try_insert(ada.clone(), born.clone(), twice).is_err());
+/// assert_eq!(graph.len(), 1);
+///
+/// // Collecting violations admits the statements & reports them.
+/// let mut graph = KnowledgeGraph::new();
+/// graph.set_constraints(constraints.enforcement(Enforcement::Collect));
+/// let text = Node::Literal("December 10th, 1815".into());
+/// graph.try_insert(ada, born, text).unwrap();
+///
+/// let violations = graph.constraints().unwrap().violations();
+/// assert_eq!(graph.len(), 1);
+/// assert_eq!(
+///   violations[0].constraint,
+///   Constraint::Datatype(Datatype::Integer)
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Constraints {
+  properties: HashMap<String, Property>,
+  enforcement: Enforcement,
+  violations: Vec<Violation>,
+}
+
+/// Constraints on the values of a predicate.
+#[derive(Clone, Debug, Default)]
+struct Property {
+  datatype: Option<Datatype>,
+  max_count: Option<usize>,
+}
+
+impl Constraints {
+  /// Creates constraints accepting every statement, rejecting violations.
+  pub fn new() -> Constraints {
+    Constraints::default()
+  }
+
+  /// Requires the values of `predicate` (a full IRI) to be of `datatype`.
+  pub fn datatype(mut self, predicate: &str, datatype: Datatype) -> Self {
+    self.property(predicate).datatype = Some(datatype);
+    self
+  }
+
+  /// Allows every subject at most `max` distinct values of `predicate`.
+  pub fn max_count(mut self, predicate: &str, max: usize) -> Self {
+    self.property(predicate).max_count = Some(max);
+    self
+  }
+
+  /// Sets how violating statements are handled.
+  pub fn enforcement(mut self, enforcement: Enforcement) -> Self {
+    self.enforcement = enforcement;
+    self
+  }
+
+  fn property(&mut self, predicate: &str) -> &mut Property {
+    self.properties.entry(predicate.to_string()).or_default()
+  }
+
+  /// Returns the violations recorded so far, in order.
+  pub fn violations(&self) -> &[Violation] {
+    &self.violations
+  }
+
+  /// Returns the quarantined violations, whose statements were kept out of
+  /// the graph.
+  pub fn quarantined(&self) -> impl Iterator<Item = &Violation> {
+    self
+      .violations
+      .iter()
+      .filter(|violation| !violation.admitted)
+  }
+
+  /// Forgets the recorded violations, returning them.
+  pub fn clear(&mut self) -> Vec<Violation> {
+    std::mem::take(&mut self.violations)
+  }
+
+  /// Checks every statement of `graph`, e.g. before constraining a graph
+  /// which already has statements. Nothing is recorded.
+  pub fn validate(&self, graph: &KnowledgeGraph) -> Vec<Violation> {
+    let mut values: HashMap<(&Node, String), HashSet<&Node>> = HashMap::new();
+    let mut violations = Vec::new();
+    for triple in graph.triples() {
+      let predicate = triple.predicate().to_string();
+      let property = match self.properties.get(&predicate) {
+        Some(property) => property,
+        None => continue,
+      };
+      let value = triple.destination();
+      let values = values.entry((triple.source(), predicate)).or_default();
+      values.insert(value);
+      let constraint = match (property.datatype, property.max_count) {
+        (Some(datatype), _) if !datatype.accepts(value) => {
+          Constraint::Datatype(datatype)
+        }
+        (_, Some(max)) if values.len() > max => Constraint::MaxCount(max),
+        _ => continue,
+      };
+      violations.push(violation(triple, constraint, true));
+    }
+    violations
+  }
+
+  /// Returns the constraint `triple` would violate once added to `graph`.
+  pub(crate) fn check(
+    &self,
+    graph: &KnowledgeGraph,
+    triple: &Triple,
+  ) -> Option<Constraint> {
+    let predicate = triple.predicate().to_string();
+    let property = self.properties.get(&predicate)?;
+    let value = triple.destination();
+    if let Some(datatype) = property.datatype {
+      if !datatype.accepts(value) {
+        return Some(Constraint::Datatype(datatype));
+      }
+    }
+
+    let max = property.max_count?;
+    let mut values = HashSet::new();
+    values.insert(value);
+    for existing in graph.matches(Some(triple.source()), Some(&predicate), None)
+    {
+      values.insert(existing.destination());
+    }
+    (values.len() > max).then_some(Constraint::MaxCount(max))
+  }
+
+  /// Handles a statement violating `constraint`, returning `true` if it
+  /// should be admitted anyway.
+  pub(crate) fn enforce(
+    &mut self,
+    triple: &Triple,
+    constraint: Constraint,
+  ) -> Result<bool> {
+    let admitted = match self.enforcement {
+      Enforcement::Reject => {
+        return Err(Error::syntax(ErrorCode::ConstraintViolation, 0, 0))
+      }
+      Enforcement::Quarantine => false,
+      Enforcement::Collect => true,
+    };
+    self
+      .violations
+      .push(violation(triple, constraint, admitted));
+    Ok(admitted)
+  }
+}
+
+fn violation(
+  triple: &Triple,
+  constraint: Constraint,
+  admitted: bool,
+) -> Violation {
+  Violation {
+    subject: triple.source().clone(),
+    predicate: triple.predicate().clone(),
+    value: triple.destination().clone(),
+    constraint,
+    admitted,
+  }
+}
@@ -0,0 +1,205 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+  error::{Error, ErrorCode},
+  graph::{KnowledgeGraph, Node, Predicate, Triple},
+  Result,
+};
+
+/// Namespace of the predicates used in an `Ontology::report` graph.
+pub const REPORT_NS: &str = "urn:sage:report:";
+
+/// Number of example values kept per unknown predicate.
+const MAX_EXAMPLES: usize = 3;
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | ValidationMode
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `ValidationMode` decides what happens to statements using a predicate
+/// the `Ontology` doesn't declare.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ValidationMode {
+  /// Reject the statement with an `UnknownPredicate` error.
+  #[default]
+  Strict,
+  /// Admit the statement, but record how often the predicate is used along
+  /// with a few example values.
+  Observe,
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Ontology
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Ontology` is the set of predicates a `KnowledgeGraph` accepts through
+/// `KnowledgeGraph::try_insert`.
+///
+/// In `ValidationMode::Observe` unknown predicates are admitted and counted
+/// instead, so a schema can be tightened over time: `report` summarizes
+/// what was seen as a graph.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::schema::{Ontology, ValidationMode};
+///
+/// let ontology = Ontology::new()
+///   .namespace("https://schema.org/")
+///   .mode(ValidationMode::Observe);
+/// let mut graph = KnowledgeGraph::with_ontology(ontology);
+///
+/// let john = Node::Http("https://example.com/John".to_string());
+/// graph
+///   .try_insert(
+///     john.clone(),
+///     Predicate::Literal("https://schema.org/name".to_string()),
+///     Node::Literal("John".into()),
+///   )
+///   .unwrap();
+/// graph
+///   .try_insert(
+///     john,
+///     Predicate::Literal("https://example.com/nickname".to_string()),
+///     Node::Literal("Johnny".into()),
+///   )
+///   .unwrap();
+///
+/// let ontology = graph.ontology().unwrap();
+/// assert_eq!(ontology.count("https://example.com/nickname"), 1);
+/// assert_eq!(ontology.report().len(), 2);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Ontology {
+  predicates: HashSet<String>,
+  namespaces: Vec<String>,
+  mode: ValidationMode,
+  observed: HashMap<String, Observation>,
+}
+
+/// Usage statistics of an unknown predicate.
+#[derive(Clone, Debug, Default)]
+struct Observation {
+  count: u64,
+  examples: Vec<Node>,
+}
+
+impl Ontology {
+  /// Creates an empty, strict ontology.
+  pub fn new() -> Ontology {
+    Ontology::default()
+  }
+
+  /// Declares `predicate` (a full IRI).
+  pub fn predicate(mut self, predicate: &str) -> Self {
+    self.predicates.insert(predicate.to_string());
+    self
+  }
+
+  /// Declares every predicate starting with `namespace`, e.g.
+  /// `https://schema.org/`.
+  pub fn namespace(mut self, namespace: &str) -> Self {
+    self.namespaces.push(namespace.to_string());
+    self
+  }
+
+  /// Sets how unknown predicates are handled.
+  pub fn mode(mut self, mode: ValidationMode) -> Self {
+    self.mode = mode;
+    self
+  }
+
+  /// Returns `true` if `predicate` is declared.
+  pub fn declares(&self, predicate: &str) -> bool {
+    self.predicates.contains(predicate)
+      || self.namespaces.iter().any(|ns| predicate.starts_with(ns))
+  }
+
+  /// Checks `triple`, recording it if its predicate is unknown and the
+  /// ontology is observing.
+  pub(crate) fn check(&mut self, triple: &Triple) -> Result<()> {
+    let predicate = triple.predicate().to_string();
+    if self.declares(&predicate) {
+      return Ok(());
+    }
+
+    match self.mode {
+      ValidationMode::Strict => {
+        Err(Error::syntax(ErrorCode::UnknownPredicate, 0, 0))
+      }
+      ValidationMode::Observe => {
+        let observation = self.observed.entry(predicate).or_default();
+        observation.count += 1;
+        let value = triple.destination();
+        if observation.examples.len() < MAX_EXAMPLES
+          && !observation.examples.contains(value)
+        {
+          observation.examples.push(value.clone());
+        }
+        Ok(())
+      }
+    }
+  }
+
+  /// Returns how often the unknown `predicate` has been admitted.
+  pub fn count(&self, predicate: &str) -> u64 {
+    self.observed.get(predicate).map_or(0, |o| o.count)
+  }
+
+  /// Returns every unknown predicate admitted so far, most frequent first.
+  pub fn unknown_predicates(&self) -> Vec<(&str, u64)> {
+    let mut predicates: Vec<(&str, u64)> = self
+      .observed
+      .iter()
+      .map(|(p, o)| (p.as_str(), o.count))
+      .collect();
+    predicates.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    predicates
+  }
+
+  /// Summarizes the unknown predicates as a graph. Every predicate is a
+  /// subject with a `urn:sage:report:count` and up to three
+  /// `urn:sage:report:example` values.
+  pub fn report(&self) -> KnowledgeGraph {
+    let count = Predicate::Literal(format!("{}count", REPORT_NS));
+    let example = Predicate::Literal(format!("{}example", REPORT_NS));
+
+    let mut graph = KnowledgeGraph::new();
+    for (predicate, _) in self.unknown_predicates() {
+      let observation = &self.observed[predicate];
+      let subject = Node::Http(predicate.to_string());
+      graph.insert(
+        subject.clone(),
+        count.clone(),
+        Node::Literal(observation.count.into()),
+      );
+      for value in &observation.examples {
+        graph.insert(subject.clone(), example.clone(), value.clone());
+      }
+    }
+    graph
+  }
+}
@@ -0,0 +1,248 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashSet;
+
+use crate::graph::{KnowledgeGraph, Node, Predicate};
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | TypeGuess
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `TypeGuess` is the most probable `rdf:type` of an untyped node.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeGuess {
+  /// The untyped node.
+  pub subject: Node,
+  /// Its most probable class.
+  pub class: Node,
+  /// Posterior probability of `class`, between 0 and 1.
+  pub confidence: f64,
+  /// `true` if `confidence` is below the `Typer`'s threshold, so the guess
+  /// should be reviewed before it's trusted.
+  pub review: bool,
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Typer
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Typer` guesses the `rdf:type` of untyped nodes from the predicates they
+/// use, compared to those of typed nodes in the same graph.
+///
+/// It's a naive Bayes classifier over predicate sets: every class is scored
+/// by how often its typed nodes use (or don't use) each predicate the
+/// untyped node uses (or doesn't), with Laplace smoothing.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::graph::{KnowledgeGraph, Node, Predicate};
+/// use sage::schema::Typer;
+///
+/// let rdf_type = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+/// let schema = |name: &str| format!("https://schema.org/{}", name);
+/// let node = |name: &str| Node::Http(format!("https://example.com/{}", name));
+///
+/// let mut graph = KnowledgeGraph::new();
+/// for (name, class, predicates) in [
+///   ("Ada", "Person", ["birthDate", "givenName"]),
+///   ("Alan", "Person", ["birthDate", "givenName"]),
+///   ("Acme", "Organization", ["foundingDate", "legalName"]),
+///   ("Grace", "", ["birthDate", "givenName"]),
+/// ] {
+///   for predicate in predicates {
+///     graph.insert(
+///       node(name),
+///       Predicate::Literal(schema(predicate)),
+///       Node::Literal("...".into()),
+///     );
+///   }
+///   if !class.is_empty() {
+///     graph.insert(
+///       node(name),
+///       Predicate::Literal(rdf_type.to_string()),
+///       Node::Http(schema(class)),
+///     );
+///   }
+/// }
+///
+/// let guesses = Typer::new().infer(&graph);
+/// assert_eq!(guesses.len(), 1);
+/// assert_eq!(guesses[0].subject, node("Grace"));
+/// assert_eq!(guesses[0].class, Node::Http(schema("Person")));
+/// assert!(!guesses[0].review);
+///
+/// Typer::new().assign(&mut graph);
+/// assert!(Typer::new().infer(&graph).is_empty());
+/// ```
+#[derive(Clone, Debug)]
+pub struct Typer {
+  threshold: f64,
+}
+
+impl Default for Typer {
+  fn default() -> Self {
+    Typer { threshold: 0.8 }
+  }
+}
+
+/// Predicate usage of the typed nodes of a class.
+struct Class<'a> {
+  class: &'a Node,
+  /// Number of typed nodes.
+  nodes: usize,
+  /// Number of typed nodes using each predicate of the vocabulary.
+  uses: Vec<usize>,
+}
+
+impl Typer {
+  /// Creates a `Typer` flagging guesses below 80% confidence for review.
+  pub fn new() -> Typer {
+    Typer::default()
+  }
+
+  /// Sets the confidence below which guesses are flagged for review.
+  pub fn threshold(mut self, threshold: f64) -> Self {
+    self.threshold = threshold;
+    self
+  }
+
+  /// Guesses a class for every subject of `graph` without an `rdf:type`, in
+  /// order of first appearance. Nothing is guessed if the graph has no
+  /// typed subjects.
+  pub fn infer(&self, graph: &KnowledgeGraph) -> Vec<TypeGuess> {
+    // Every predicate but `rdf:type`, and each subject's set of them.
+    let mut vocabulary: Vec<String> = Vec::new();
+    let mut subjects: Vec<(&Node, HashSet<usize>, Vec<&Node>)> = Vec::new();
+    for triple in graph.triples() {
+      let subject = triple.source();
+      let idx = match subjects.iter().position(|(s, ..)| *s == subject) {
+        Some(idx) => idx,
+        None => {
+          subjects.push((subject, HashSet::new(), Vec::new()));
+          subjects.len() - 1
+        }
+      };
+
+      let predicate = triple.predicate().to_string();
+      if predicate == RDF_TYPE {
+        subjects[idx].2.push(triple.destination());
+        continue;
+      }
+      let p = match vocabulary.iter().position(|v| *v == predicate) {
+        Some(p) => p,
+        None => {
+          vocabulary.push(predicate);
+          vocabulary.len() - 1
+        }
+      };
+      subjects[idx].1.insert(p);
+    }
+
+    let mut classes: Vec<Class> = Vec::new();
+    for (_, predicates, types) in &subjects {
+      for class in types {
+        let idx = match classes.iter().position(|c| c.class == *class) {
+          Some(idx) => idx,
+          None => {
+            classes.push(Class {
+              class,
+              nodes: 0,
+              uses: vec![0; vocabulary.len()],
+            });
+            classes.len() - 1
+          }
+        };
+        classes[idx].nodes += 1;
+        predicates.iter().for_each(|&p| classes[idx].uses[p] += 1);
+      }
+    }
+    if classes.is_empty() {
+      return Vec::new();
+    }
+
+    let total: usize = classes.iter().map(|c| c.nodes).sum();
+    subjects
+      .iter()
+      .filter(|(_, _, types)| types.is_empty())
+      .map(|(subject, predicates, _)| {
+        let scores: Vec<f64> = classes
+          .iter()
+          .map(|class| score(class, total, vocabulary.len(), predicates))
+          .collect();
+        let best = scores.iter().enumerate().fold(0, |best, (i, s)| {
+          if *s > scores[best] {
+            i
+          } else {
+            best
+          }
+        });
+        // Posterior of the best class, normalized over every class.
+        let sum: f64 = scores.iter().map(|s| (s - scores[best]).exp()).sum();
+        let confidence = 1.0 / sum;
+
+        TypeGuess {
+          subject: (*subject).clone(),
+          class: classes[best].class.clone(),
+          confidence,
+          review: confidence < self.threshold,
+        }
+      })
+      .collect()
+  }
+
+  /// Adds an `rdf:type` statement for every guess of `infer` that doesn't
+  /// need review, returning all guesses.
+  pub fn assign(&self, graph: &mut KnowledgeGraph) -> Vec<TypeGuess> {
+    let guesses = self.infer(graph);
+    for guess in guesses.iter().filter(|guess| !guess.review) {
+      graph.insert(
+        guess.subject.clone(),
+        Predicate::Literal(RDF_TYPE.to_string()),
+        guess.class.clone(),
+      );
+    }
+    guesses
+  }
+}
+
+/// Log-likelihood of a node using `predicates` (indices into a vocabulary
+/// of `size` predicates) being of `class`, out of `total` typed nodes.
+fn score(
+  class: &Class,
+  total: usize,
+  size: usize,
+  predicates: &HashSet<usize>,
+) -> f64 {
+  let prior = (class.nodes as f64 / total as f64).ln();
+  (0..size).fold(prior, |score, p| {
+    let used = (class.uses[p] as f64 + 1.0) / (class.nodes as f64 + 2.0);
+    if predicates.contains(&p) {
+      score + used.ln()
+    } else {
+      score + (1.0 - used).ln()
+    }
+  })
+}
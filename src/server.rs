@@ -0,0 +1,785 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::server` serves a `KnowledgeGraph` over HTTP: a SPARQL endpoint
+//! following the [SPARQL 1.1 Protocol], so standard tools like YASGUI can
+//! query it, along with REST routes for lightweight web consumption.
+//!
+//! | Route           | Method | Request                                    |
+//! |-----------------|--------|--------------------------------------------|
+//! | `/sparql`       | `GET`  | `?query=` (see `sage::query::Select`)      |
+//! | `/sparql`       | `POST` | `application/sparql-query` body or         |
+//! |                 |        | `query=` form, `application/sparql-update` |
+//! |                 |        | body or `update=` form (see                |
+//! |                 |        | `sage::query::Update`)                     |
+//! | `/entity/{iri}` | `GET`  | percent-encoded IRI or `_:label`           |
+//! | `/triples`      | `GET`  | optional `s`, `p`, `o` & `page`            |
+//!
+//! Query results are negotiated with the `Accept` header: SPARQL JSON
+//! results (`application/sparql-results+json`, the default), CSV
+//! (`text/csv`), TSV (`text/tab-separated-values`) or SPARQL XML results
+//! (`application/sparql-results+xml`), see `sage::query::ResultFormat`.
+//! Updates answer with the `{"inserted", "deleted"}` counts.
+//!
+//! Updates are refused unless enabled with `Server::updates`, and
+//! cross-origin requests unless an origin is allowed with
+//! `Server::allow_origin`, so that web pages can't modify the graph.
+//!
+//! `/entity/{iri}` answers with the JSON-LD node object of the entity (see
+//! `Entity::to_dtype`), tagged with its content hash as `ETag`. IRIs merged
+//! by `KnowledgeGraph::resolve_same_as` redirect to their canonical IRI.
+//!
+//! `/triples` answers with a [Triple Pattern Fragment]: the statements
+//! matching the `s`, `p` & `o` selectors, 100 per page, along with Hydra
+//! paging controls & the total count. Literal selectors are written like
+//! in SPARQL (`"Ada"@en`, `"42"^^xsd:integer`), blank nodes as `_:label`
+//! and any other value is an IRI.
+//!
+//! The server speaks plain HTTP/1.1 over `std::net`, closing every
+//! connection after its response. Connections are read & written on their
+//! own threads, with timeouts and limits on the size of requests, while
+//! requests are handled one at a time against the graph; put a reverse
+//! proxy in front of it for TLS.
+//!
+//! [SPARQL 1.1 Protocol]: https://www.w3.org/TR/sparql11-protocol/
+//! [Triple Pattern Fragment]: https://linkeddatafragments.org/specification/triple-pattern-fragments/
+
+use std::{
+  fmt::Write as _,
+  io::{BufRead, BufReader, Read, Write},
+  net::{TcpListener, TcpStream, ToSocketAddrs},
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    mpsc::{self, Sender},
+    Arc,
+  },
+  thread,
+  time::Duration,
+};
+
+use crate::{
+  datastore::json,
+  dtype::DType,
+  error::Error,
+  graph::{KnowledgeGraph, Node},
+  query::{parse_term, ResultFormat, Select, Term, Update},
+  Result,
+};
+
+/// Statements per page of a triple pattern fragment.
+const PAGE_SIZE: usize = 100;
+
+/// Largest request body read, in bytes.
+const MAX_BODY: usize = 16 << 20;
+
+/// Longest request or header line read, in bytes.
+const MAX_LINE: usize = 8 << 10;
+
+/// Most headers read per request.
+const MAX_HEADERS: usize = 100;
+
+/// Most connections served at once; others are answered with `503`.
+const MAX_CONNECTIONS: usize = 64;
+
+/// Time a connection may stay idle while its request is read or its
+/// response written.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Request & Response
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Request` is an HTTP request to a `Server`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Request {
+  method: String,
+  path: String,
+  params: Vec<(String, String)>,
+  headers: Vec<(String, String)>,
+  body: Vec<u8>,
+}
+
+impl Request {
+  /// Creates a request for `target`, a path with an optional query string
+  /// (e.g. `/sparql?query=...`, percent-encoded).
+  pub fn new(method: &str, target: &str) -> Request {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    Request {
+      method: method.to_ascii_uppercase(),
+      path: decode(path, false),
+      params: form(query),
+      headers: Vec::new(),
+      body: Vec::new(),
+    }
+  }
+
+  /// Adds a header.
+  pub fn header(mut self, name: &str, value: &str) -> Self {
+    self.headers.push((name.to_string(), value.to_string()));
+    self
+  }
+
+  /// Sets the body.
+  pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+    self.body = body.into();
+    self
+  }
+
+  /// Returns the value of the first header called `name`.
+  fn get(&self, name: &str) -> Option<&str> {
+    self
+      .headers
+      .iter()
+      .find(|(n, _)| n.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_str())
+  }
+
+  /// Returns the value of the first query string parameter called `name`.
+  fn param(&self, name: &str) -> Option<&str> {
+    lookup(&self.params, name)
+  }
+
+  /// Returns the media type of the body, without parameters.
+  fn content_type(&self) -> String {
+    let value = self.get("content-type").unwrap_or_default();
+    let media = value.split(';').next().unwrap_or_default();
+    media.trim().to_ascii_lowercase()
+  }
+}
+
+/// `Response` is the HTTP response of a `Server` to a `Request`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+  /// Status code, e.g. `200`.
+  pub status: u16,
+  /// Headers, besides `Content-Length`.
+  pub headers: Vec<(String, String)>,
+  /// Body.
+  pub body: Vec<u8>,
+}
+
+impl Response {
+  fn new(status: u16, content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+    Response {
+      status,
+      headers: vec![("Content-Type".to_string(), content_type.to_string())],
+      body: body.into(),
+    }
+  }
+
+  fn error(status: u16, message: impl Into<String>) -> Response {
+    let mut body = message.into();
+    body.push('\n');
+    Response::new(status, "text/plain; charset=utf-8", body)
+  }
+
+  fn with_header(mut self, name: &str, value: &str) -> Self {
+    self.headers.push((name.to_string(), value.to_string()));
+    self
+  }
+
+  /// Returns the value of the first header called `name`.
+  pub fn header(&self, name: &str) -> Option<&str> {
+    self
+      .headers
+      .iter()
+      .find(|(n, _)| n.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_str())
+  }
+
+  /// Returns the body as text, replacing invalid UTF-8.
+  pub fn text(&self) -> String {
+    String::from_utf8_lossy(&self.body).into_owned()
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Server
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Server` answers SPARQL Protocol requests against its graph.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::server::{Request, Server};
+///
+/// let mut server = Server::new().updates(true);
+/// let response = server.handle(
+///   &Request::new("POST", "/sparql")
+///     .header("Content-Type", "application/sparql-update")
+///     .body(r#"INSERT DATA { <https://example.com/Ada> schema:name "Ada" }"#),
+/// );
+/// assert_eq!(response.text(), r#"{"deleted":0,"inserted":1}"#);
+///
+/// let response = server.handle(
+///   &Request::new(
+///     "GET",
+///     "/sparql?query=SELECT%20*%20WHERE%20%7B%20?s%20?p%20?o%20%7D",
+///   )
+///   .header("Accept", "text/csv"),
+/// );
+/// let content_type = response.header("Content-Type");
+/// assert_eq!(content_type, Some("text/csv; charset=utf-8"));
+/// assert_eq!(
+///   response.text(),
+///   "s,p,o\r\nhttps://example.com/Ada,https://schema.org/name,Ada\r\n"
+/// );
+///
+/// let ada = "/entity/https%3A%2F%2Fexample.com%2FAda";
+/// let response = server.handle(&Request::new("GET", ada));
+/// assert_eq!(response.status, 200);
+/// assert!(response.text().contains(r#""https://schema.org/name":"Ada""#));
+///
+/// let response =
+///   server.handle(&Request::new("GET", "/triples?o=%22Ada%22&page=1"));
+/// assert!(response.text().contains(r#""hydra:totalItems":1"#));
+/// ```
+pub struct Server {
+  graph: KnowledgeGraph,
+  updates: bool,
+  origin: Option<String>,
+}
+
+impl Default for Server {
+  fn default() -> Self {
+    Server::new()
+  }
+}
+
+impl Server {
+  /// Creates a server over an empty graph.
+  pub fn new() -> Server {
+    Server::with_graph(KnowledgeGraph::new())
+  }
+
+  /// Creates a server over `graph`.
+  pub fn with_graph(graph: KnowledgeGraph) -> Server {
+    Server {
+      graph,
+      updates: false,
+      origin: None,
+    }
+  }
+
+  /// Sets whether SPARQL updates are run, instead of answered with `403`.
+  /// Disabled by default: anyone who can reach the server could otherwise
+  /// modify the graph.
+  pub fn updates(mut self, enabled: bool) -> Self {
+    self.updates = enabled;
+    self
+  }
+
+  /// Allows cross-origin requests from `origin` (e.g.
+  /// `https://yasgui.triply.cc`, or `*` for any web page), so browser-based
+  /// tools can reach the server. Disallowed by default.
+  pub fn allow_origin(mut self, origin: &str) -> Self {
+    self.origin = Some(origin.to_string());
+    self
+  }
+
+  /// Returns the served graph.
+  pub fn graph(&self) -> &KnowledgeGraph {
+    &self.graph
+  }
+
+  /// Consumes the server, returning its graph.
+  pub fn into_graph(self) -> KnowledgeGraph {
+    self.graph
+  }
+
+  /// Accepts HTTP connections on `addr` until the process exits.
+  ///
+  /// Every connection is read & written on its own thread, so slow clients
+  /// don't hold up others, then its request is handled on this thread.
+  /// Failing connections are dropped without stopping the server.
+  pub fn serve<A: ToSocketAddrs>(&mut self, addr: A) -> Result<()> {
+    let listener = TcpListener::bind(addr).map_err(Error::io)?;
+    let (sender, requests) = mpsc::channel();
+    thread::spawn(move || accept(listener, sender));
+    for (request, reply) in requests {
+      let _ = reply.send(self.handle(&request));
+    }
+    Ok(())
+  }
+
+  /// Handles a single request.
+  pub fn handle(&mut self, request: &Request) -> Response {
+    let response = self.route(request);
+    match &self.origin {
+      Some(origin) => response
+        .with_header("Access-Control-Allow-Origin", origin)
+        .with_header("Vary", "Origin"),
+      None => response,
+    }
+  }
+
+  fn route(&mut self, request: &Request) -> Response {
+    if request.method == "OPTIONS" {
+      return Response::new(204, "text/plain", Vec::new())
+        .with_header("Allow", "GET, POST, OPTIONS")
+        .with_header("Access-Control-Allow-Methods", "GET, POST, OPTIONS")
+        .with_header("Access-Control-Allow-Headers", "Accept, Content-Type");
+    }
+    match request.path.as_str() {
+      "/sparql" => self.sparql(request),
+      "/triples" if request.method == "GET" => self.triples(request),
+      path => match path.strip_prefix("/entity/") {
+        Some(iri) if request.method == "GET" => self.entity(request, iri),
+        _ => Response::error(
+          404,
+          format!("no route for {} {}", request.method, path),
+        ),
+      },
+    }
+  }
+
+  fn entity(&self, request: &Request, iri: &str) -> Response {
+    let entity = self.graph.entity(iri);
+    if !entity.exists() {
+      let canonical = self.graph.redirect(iri);
+      if canonical != iri {
+        let location = format!("/entity/{}", encode(canonical));
+        return Response::error(301, format!("moved to {}", location))
+          .with_header("Location", &location);
+      }
+      return Response::error(404, format!("no entity {}", iri));
+    }
+
+    let etag = format!("\"{}\"", entity.content_hash());
+    if request.get("if-none-match") == Some(etag.as_str()) {
+      return Response::new(304, "application/ld+json", Vec::new())
+        .with_header("ETag", &etag);
+    }
+    let body = json::to_vec(&entity.to_dtype()).unwrap_or_default();
+    Response::new(200, "application/ld+json", body).with_header("ETag", &etag)
+  }
+
+  fn triples(&self, request: &Request) -> Response {
+    let mut selectors = Vec::new();
+    for name in ["s", "p", "o"] {
+      let selector = match request.param(name).filter(|v| !v.is_empty()) {
+        Some(value) => value,
+        None => {
+          selectors.push(None);
+          continue;
+        }
+      };
+      match selector_node(selector) {
+        Ok(node) => selectors.push(Some((name, selector, node))),
+        Err(err) => {
+          return Response::error(400, format!("invalid {}: {}", name, err))
+        }
+      }
+    }
+    let node = |i: usize| selectors[i].as_ref().map(|(_, _, node)| node);
+    let predicate = node(1).map(Node::to_string);
+    let page = match request.param("page").map(str::parse::<usize>) {
+      None => 1,
+      Some(Ok(page)) if page > 0 => page,
+      Some(_) => {
+        return Response::error(400, "page must be a positive integer")
+      }
+    };
+
+    let matches: Vec<_> = self
+      .graph
+      .matches(node(0), predicate.as_deref(), node(2))
+      .collect();
+    let total = matches.len();
+    let triples: Vec<DType> = matches
+      .into_iter()
+      .skip((page - 1) * PAGE_SIZE)
+      .take(PAGE_SIZE)
+      .map(|triple| {
+        crate::json!({
+          "subject": triple.source().to_string(),
+          "predicate": triple.predicate().to_string(),
+          "object": value(triple.destination()),
+        })
+      })
+      .collect();
+
+    let query: String = selectors
+      .iter()
+      .flatten()
+      .map(|(name, selector, _)| format!("{}={}&", name, encode(selector)))
+      .collect();
+    let url = |page: usize| format!("/triples?{}page={}", query, page);
+    let mut fragment = crate::json!({
+      "@context": { "hydra": "http://www.w3.org/ns/hydra/core#" },
+      "@id": url(page),
+      "hydra:totalItems": total,
+      "hydra:itemsPerPage": PAGE_SIZE,
+      "hydra:first": url(1),
+      "triples": triples,
+    });
+    if page > 1 {
+      fragment["hydra:previous"] = url(page - 1).into();
+    }
+    if page * PAGE_SIZE < total {
+      fragment["hydra:next"] = url(page + 1).into();
+    }
+    let body = json::to_vec(&fragment).unwrap_or_default();
+    Response::new(200, "application/ld+json", body)
+  }
+
+  fn sparql(&mut self, request: &Request) -> Response {
+    let (query, update) = match request.method.as_str() {
+      "GET" => (request.param("query").map(str::to_string), None),
+      "POST" => {
+        let body = String::from_utf8_lossy(&request.body).into_owned();
+        match request.content_type().as_str() {
+          "application/sparql-query" => (Some(body), None),
+          "application/sparql-update" => (None, Some(body)),
+          "application/x-www-form-urlencoded" => {
+            let params = form(&body);
+            let get = |name| lookup(&params, name).map(str::to_string);
+            (get("query"), get("update"))
+          }
+          media => {
+            return Response::error(
+              415,
+              format!("unsupported content type {:?}", media),
+            )
+          }
+        }
+      }
+      _ => {
+        return Response::error(405, "use GET or POST")
+          .with_header("Allow", "GET, POST, OPTIONS")
+      }
+    };
+
+    match (query, update) {
+      (Some(query), None) => self.select(request, &query),
+      (None, Some(_)) if !self.updates => {
+        Response::error(403, "updates are disabled")
+      }
+      (None, Some(update)) => match Update::parse(&update) {
+        Ok(update) => {
+          let report = self.graph.update(&update);
+          let body = crate::json!({
+            "inserted": report.inserted,
+            "deleted": report.deleted,
+          });
+          let body = json::to_vec(&body).unwrap_or_default();
+          Response::new(200, "application/json", body)
+        }
+        Err(err) => Response::error(400, err.to_string()),
+      },
+      _ => Response::error(400, "expected exactly one of query or update"),
+    }
+  }
+
+  fn select(&self, request: &Request, query: &str) -> Response {
+    let format = match negotiate(request.get("accept")) {
+      Some(format) => format,
+      None => {
+        return Response::error(
+          406,
+          "supported formats are application/sparql-results+json, text/csv, \
+           text/tab-separated-values & application/sparql-results+xml",
+        )
+      }
+    };
+    let select = match Select::parse(query) {
+      Ok(select) => select,
+      Err(err) => return Response::error(400, err.to_string()),
+    };
+    let body = select.results(&self.graph, format);
+    Response::new(200, format.media_type(), body)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Results
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Picks the format most preferred by an `Accept` header, JSON if there is
+/// none. Returns `None` if no supported format is acceptable.
+fn negotiate(accept: Option<&str>) -> Option<ResultFormat> {
+  let accept = match accept {
+    Some(accept) if !accept.trim().is_empty() => accept,
+    _ => return Some(ResultFormat::Json),
+  };
+  let mut best: Option<(f64, ResultFormat)> = None;
+  for range in accept.split(',') {
+    let mut parts = range.split(';');
+    let media = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+    let quality = parts
+      .filter_map(|p| p.trim().strip_prefix("q="))
+      .find_map(|q| q.trim().parse::<f64>().ok())
+      .unwrap_or(1.0);
+    let format = match media.as_str() {
+      "application/sparql-results+json" | "application/json" => {
+        ResultFormat::Json
+      }
+      "text/csv" => ResultFormat::Csv,
+      "text/tab-separated-values" => ResultFormat::Tsv,
+      "application/sparql-results+xml" | "application/xml" | "text/xml" => {
+        ResultFormat::Xml
+      }
+      "*/*" | "application/*" => ResultFormat::Json,
+      "text/*" => ResultFormat::Csv,
+      _ => continue,
+    };
+    if quality > 0.0 && best.is_none_or(|(q, _)| quality > q) {
+      best = Some((quality, format));
+    }
+  }
+  best.map(|(_, format)| format)
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | HTTP
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// Accepts connections, passing their requests to the server through
+/// `sender` along with a channel for the response.
+fn accept(listener: TcpListener, sender: Sender<(Request, Sender<Response>)>) {
+  let active = Arc::new(AtomicUsize::new(0));
+  for stream in listener.incoming().flatten() {
+    if active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+      active.fetch_sub(1, Ordering::SeqCst);
+      let _ = stream.set_write_timeout(Some(TIMEOUT));
+      let _ = write_response(stream, &Response::error(503, "server busy"));
+      continue;
+    }
+    let (sender, active) = (sender.clone(), Arc::clone(&active));
+    thread::spawn(move || {
+      let _ = serve_connection(stream, &sender);
+      active.fetch_sub(1, Ordering::SeqCst);
+    });
+  }
+}
+
+fn serve_connection(
+  stream: TcpStream,
+  sender: &Sender<(Request, Sender<Response>)>,
+) -> Result<()> {
+  stream.set_read_timeout(Some(TIMEOUT)).map_err(Error::io)?;
+  stream.set_write_timeout(Some(TIMEOUT)).map_err(Error::io)?;
+  let mut reader = BufReader::new(stream.try_clone().map_err(Error::io)?);
+  let response = match read_request(&mut reader)? {
+    Some(request) => {
+      let (reply, response) = mpsc::channel();
+      if sender.send((request, reply)).is_err() {
+        return Ok(());
+      }
+      match response.recv() {
+        Ok(response) => response,
+        Err(_) => return Ok(()),
+      }
+    }
+    None => Response::error(400, "malformed or oversized request"),
+  };
+  write_response(stream, &response)
+}
+
+/// Reads a line of at most `MAX_LINE` bytes, `None` if it's longer or the
+/// connection is closed.
+fn read_line<R: BufRead>(reader: &mut R) -> Result<Option<String>> {
+  let mut line = String::new();
+  let limit = MAX_LINE as u64 + 1;
+  let read = reader.take(limit).read_line(&mut line).map_err(Error::io)?;
+  if read == 0 || line.len() > MAX_LINE {
+    return Ok(None);
+  }
+  Ok(Some(line))
+}
+
+/// Reads a request, `None` if it's malformed or exceeds the limits on
+/// line length, header count or body size.
+fn read_request<R: BufRead>(reader: &mut R) -> Result<Option<Request>> {
+  let line = match read_line(reader)? {
+    Some(line) => line,
+    None => return Ok(None),
+  };
+  let mut parts = line.split_whitespace();
+  let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+    (Some(method), Some(target), Some(version))
+      if version.starts_with("HTTP/") =>
+    {
+      (method.to_string(), target.to_string())
+    }
+    _ => return Ok(None),
+  };
+
+  let mut request = Request::new(&method, &target);
+  loop {
+    let line = match read_line(reader)? {
+      Some(line) => line,
+      None => return Ok(None),
+    };
+    let line = line.trim_end();
+    if line.is_empty() {
+      break;
+    }
+    if request.headers.len() == MAX_HEADERS {
+      return Ok(None);
+    }
+    match line.split_once(':') {
+      Some((name, value)) => {
+        request = request.header(name.trim(), value.trim())
+      }
+      None => return Ok(None),
+    }
+  }
+
+  let length = match request.get("content-length") {
+    Some(length) => match length.parse::<usize>() {
+      Ok(length) if length <= MAX_BODY => length,
+      _ => return Ok(None),
+    },
+    None => 0,
+  };
+  let mut body = vec![0; length];
+  reader.read_exact(&mut body).map_err(Error::io)?;
+  Ok(Some(request.body(body)))
+}
+
+fn write_response<W: Write>(mut writer: W, response: &Response) -> Result<()> {
+  let reason = match response.status {
+    200 => "OK",
+    204 => "No Content",
+    301 => "Moved Permanently",
+    304 => "Not Modified",
+    400 => "Bad Request",
+    403 => "Forbidden",
+    404 => "Not Found",
+    405 => "Method Not Allowed",
+    406 => "Not Acceptable",
+    415 => "Unsupported Media Type",
+    503 => "Service Unavailable",
+    _ => "",
+  };
+  let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason);
+  for (name, value) in &response.headers {
+    let _ = write!(head, "{}: {}\r\n", name, value);
+  }
+  let _ = write!(
+    head,
+    "Content-Length: {}\r\nConnection: close\r\n\r\n",
+    response.body.len()
+  );
+  writer.write_all(head.as_bytes()).map_err(Error::io)?;
+  writer.write_all(&response.body).map_err(Error::io)?;
+  writer.flush().map_err(Error::io)
+}
+
+/// Parses a fragment selector: a literal written like in SPARQL, a blank
+/// node label or an IRI, optionally in `<>`.
+fn selector_node(selector: &str) -> Result<Node> {
+  if selector.starts_with(['"', '\'']) {
+    if let Term::Node(node) = parse_term(selector)? {
+      return Ok(node);
+    }
+  }
+  Ok(match selector.strip_prefix("_:") {
+    Some(label) => Node::BlankId(label.to_string()),
+    None => {
+      let iri = selector.strip_prefix('<').and_then(|s| s.strip_suffix('>'));
+      Node::Http(iri.unwrap_or(selector).to_string())
+    }
+  })
+}
+
+/// Writes an object like a JSON-LD value: literals as is, other nodes as
+/// `{"@id": ...}`.
+fn value(node: &Node) -> DType {
+  match node {
+    Node::Literal(value) => value.clone(),
+    Node::Multiple(nodes) => nodes.iter().map(value).collect(),
+    node => crate::json!({ "@id": node.to_string() }),
+  }
+}
+
+/// Percent-encodes every byte outside of the unreserved URI characters.
+fn encode(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  for byte in s.bytes() {
+    match byte {
+      b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+        out.push(byte as char)
+      }
+      byte => {
+        let _ = write!(out, "%{:02X}", byte);
+      }
+    }
+  }
+  out
+}
+
+/// Parses `application/x-www-form-urlencoded` pairs.
+fn form(s: &str) -> Vec<(String, String)> {
+  s.split('&')
+    .filter(|pair| !pair.is_empty())
+    .map(|pair| {
+      let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+      (decode(name, true), decode(value, true))
+    })
+    .collect()
+}
+
+fn lookup<'a>(pairs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+  pairs
+    .iter()
+    .find(|(n, _)| n == name)
+    .map(|(_, value)| value.as_str())
+}
+
+/// Percent-decodes `s`, also decoding `+` as a space in forms. Invalid
+/// escapes are kept as is.
+fn decode(s: &str, form: bool) -> String {
+  let bytes = s.as_bytes();
+  let mut out = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    match bytes[i] {
+      b'%' => {
+        let hex = s
+          .get(i + 1..i + 3)
+          .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+          Some(byte) => {
+            out.push(byte);
+            i += 2;
+          }
+          None => out.push(b'%'),
+        }
+      }
+      b'+' if form => out.push(b' '),
+      byte => out.push(byte),
+    }
+    i += 1;
+  }
+  String::from_utf8_lossy(&out).into_owned()
+}
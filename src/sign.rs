@@ -0,0 +1,562 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::sign` signs & verifies graphs and entities with Ed25519, as
+//! [Data Integrity] proofs, so published knowledge can be authenticated.
+//!
+//! Proofs are `DataIntegrityProof`s of the [`eddsa-rdfc-2022`]
+//! cryptosuite: the signature covers the SHA-256 of the canonical proof
+//! options followed by the SHA-256 of the canonical statements, and the
+//! `proofValue` is its base58btc multibase encoding.
+//!
+//! Statements are canonicalized as [RDFC-1.0] does for graphs without
+//! blank nodes: sorted, deduplicated N-Triples. Graphs & entities with
+//! blank nodes are refused, since their canonical labels would not cover
+//! how blank nodes are connected; `KnowledgeGraph::skolemize` them before
+//! signing.
+//!
+//! Keys are [ed25519-dalek] keys; `SigningKey` & `VerifyingKey` also plug
+//! into `sage::vc` as `Signer` & `Verifier`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use sage::graph::{KnowledgeGraph, Node, Predicate};
+//! use sage::sign::{Proof, SigningKey};
+//!
+//! let ada = Node::Http("https://example.com/Ada".to_string());
+//! let name = Predicate::Literal("https://schema.org/name".to_string());
+//! let mut graph = KnowledgeGraph::new();
+//! graph.insert(ada.clone(), name.clone(), Node::Literal("Ada".into()));
+//!
+//! let key = SigningKey::from_bytes(&[7; 32]);
+//! let proof = key.sign_graph(&graph, "did:example:sage#key-1").unwrap();
+//! assert_eq!(proof.to_dtype()["type"], "DataIntegrityProof");
+//! assert_eq!(proof.cryptosuite, "eddsa-rdfc-2022");
+//!
+//! // Proofs travel as JSON-LD objects.
+//! let proof = Proof::from_dtype(&proof.to_dtype()).unwrap();
+//! let public = key.verifying_key();
+//! assert!(public.verify_graph(&graph, &proof).unwrap());
+//!
+//! // Any change to the statements invalidates the proof.
+//! graph.insert(ada, name, Node::Literal("Augusta".into()));
+//! assert!(!public.verify_graph(&graph, &proof).unwrap());
+//! ```
+//!
+//! [Data Integrity]: https://www.w3.org/TR/vc-data-integrity/
+//! [`eddsa-rdfc-2022`]: https://www.w3.org/TR/vc-di-eddsa/#eddsa-rdfc-2022
+//! [RDFC-1.0]: https://www.w3.org/TR/rdf-canon/
+//! [ed25519-dalek]: https://docs.rs/ed25519-dalek
+
+use chrono::{SecondsFormat, Utc};
+use ed25519_dalek::{Signature, Signer as _};
+use rand::{rngs::OsRng, RngCore};
+use serde::de::Error as _;
+use sha2::{Digest, Sha256};
+
+use crate::{
+  dtype::{DType, Map},
+  error::Error,
+  formats::{self, escape_iri, escape_literal},
+  graph::{Entity, KnowledgeGraph, Triple},
+  vc, Result,
+};
+
+/// Type of the proofs made by this module.
+pub const PROOF_TYPE: &str = "DataIntegrityProof";
+
+/// Cryptosuite of the proofs made by this module: Ed25519 over RDFC-1.0
+/// canonical statements.
+pub const CRYPTOSUITE: &str = "eddsa-rdfc-2022";
+
+/// Namespace of the Data Integrity vocabulary.
+const SECURITY: &str = "https://w3id.org/security#";
+
+/// Multicodec prefix of Ed25519 public keys in multibase form.
+const ED25519_PUB: [u8; 2] = [0xed, 0x01];
+
+/// Proof type of the `sage::vc` signatures made with a `SigningKey`.
+const VC_PROOF_TYPE: &str = "Ed25519Signature2020";
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Keys.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `SigningKey` is an Ed25519 secret key.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::sign::SigningKey;
+///
+/// let key = SigningKey::generate();
+/// let signature = key.sign(b"Ada Lovelace");
+/// assert!(key.verifying_key().verify(b"Ada Lovelace", &signature));
+/// assert!(!key.verifying_key().verify(b"Charles Babbage", &signature));
+/// ```
+#[derive(Clone)]
+pub struct SigningKey {
+  key: ed25519_dalek::SigningKey,
+  /// Verification method of `sage::vc` proofs.
+  method: String,
+}
+
+impl SigningKey {
+  /// Generates a new key from the randomness of the operating system.
+  /// Unlike `sage::random`, it can't be seeded, so keys are never
+  /// predictable.
+  pub fn generate() -> SigningKey {
+    let mut seed = [0; 32];
+    OsRng.fill_bytes(&mut seed);
+    SigningKey::from_bytes(&seed)
+  }
+
+  /// Creates the key of the 32-byte secret `seed`.
+  pub fn from_bytes(seed: &[u8; 32]) -> SigningKey {
+    SigningKey {
+      key: ed25519_dalek::SigningKey::from_bytes(seed),
+      method: String::new(),
+    }
+  }
+
+  /// Returns the 32-byte secret seed.
+  pub fn to_bytes(&self) -> [u8; 32] {
+    self.key.to_bytes()
+  }
+
+  /// Returns the public key.
+  pub fn verifying_key(&self) -> VerifyingKey {
+    VerifyingKey {
+      key: self.key.verifying_key(),
+    }
+  }
+
+  /// Sets the verification method `sage::vc` proofs refer to, e.g.
+  /// `"did:example:issuer#key-1"`.
+  pub fn verification_method(mut self, method: &str) -> Self {
+    self.method = method.to_string();
+    self
+  }
+
+  /// Signs `message`, returning the 64-byte signature.
+  pub fn sign(&self, message: &[u8]) -> [u8; 64] {
+    self.key.sign(message).to_bytes()
+  }
+
+  /// Signs the statements of `graph`. `verification_method` is the IRI
+  /// verifiers resolve the public key from, e.g. a DID URL.
+  ///
+  /// # Errors
+  ///
+  /// Fails if `graph` has blank nodes, see the module documentation.
+  pub fn sign_graph(
+    &self,
+    graph: &KnowledgeGraph,
+    verification_method: &str,
+  ) -> Result<Proof> {
+    self.sign_triples(graph.triples(), verification_method)
+  }
+
+  /// Signs the statements about `entity`, see `SigningKey::sign_graph`.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::graph::{KnowledgeGraph, Node, Predicate};
+  /// use sage::sign::SigningKey;
+  ///
+  /// let name = Predicate::Literal("https://schema.org/name".to_string());
+  /// let mut graph = KnowledgeGraph::new();
+  /// graph.insert(
+  ///   Node::Http("https://example.com/Ada".to_string()),
+  ///   name.clone(),
+  ///   Node::Literal("Ada".into()),
+  /// );
+  ///
+  /// let key = SigningKey::generate();
+  /// let ada = graph.entity("https://example.com/Ada");
+  /// let proof = key.sign_entity(&ada, "did:example:sage#key-1").unwrap();
+  ///
+  /// // Statements about other entities don't affect the proof.
+  /// graph.insert(
+  ///   Node::Http("https://example.com/Charles".to_string()),
+  ///   name,
+  ///   Node::Literal("Charles".into()),
+  /// );
+  /// let ada = graph.entity("https://example.com/Ada");
+  /// assert!(key.verifying_key().verify_entity(&ada, &proof).unwrap());
+  /// ```
+  pub fn sign_entity(
+    &self,
+    entity: &Entity,
+    verification_method: &str,
+  ) -> Result<Proof> {
+    self.sign_triples(entity.triples(), verification_method)
+  }
+
+  fn sign_triples<'t>(
+    &self,
+    triples: impl IntoIterator<Item = &'t Triple>,
+    verification_method: &str,
+  ) -> Result<Proof> {
+    let mut proof = Proof {
+      cryptosuite: CRYPTOSUITE.to_string(),
+      created: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+      verification_method: verification_method.to_string(),
+      proof_purpose: "assertionMethod".to_string(),
+      proof_value: String::new(),
+    };
+    let signature = self.sign(&hash_data(&proof, triples)?);
+    proof.proof_value = multibase(&signature);
+    Ok(proof)
+  }
+}
+
+impl vc::Signer for SigningKey {
+  fn proof_type(&self) -> &str {
+    VC_PROOF_TYPE
+  }
+
+  fn verification_method(&self) -> &str {
+    &self.method
+  }
+
+  fn sign(&self, data: &[u8]) -> Result<String> {
+    Ok(multibase(&SigningKey::sign(self, data)))
+  }
+}
+
+/// `VerifyingKey` is an Ed25519 public key.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::sign::{SigningKey, VerifyingKey};
+///
+/// let public = SigningKey::from_bytes(&[7; 32]).verifying_key();
+/// let multibase = public.to_multibase();
+/// assert!(multibase.starts_with("z6Mk"));
+/// assert_eq!(VerifyingKey::from_multibase(&multibase).unwrap(), public);
+///
+/// assert!(VerifyingKey::from_multibase("z6MkInvalid").is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct VerifyingKey {
+  key: ed25519_dalek::VerifyingKey,
+}
+
+impl VerifyingKey {
+  /// Decodes a 32-byte public key, failing if it isn't a point of the
+  /// curve.
+  pub fn from_bytes(bytes: &[u8; 32]) -> Result<VerifyingKey> {
+    match ed25519_dalek::VerifyingKey::from_bytes(bytes) {
+      Ok(key) => Ok(VerifyingKey { key }),
+      Err(_) => Err(Error::custom("invalid Ed25519 public key")),
+    }
+  }
+
+  /// Decodes a `publicKeyMultibase` value: the base58btc encoded,
+  /// multicodec prefixed public key of a `Multikey` or
+  /// `Ed25519VerificationKey2020`.
+  pub fn from_multibase(value: &str) -> Result<VerifyingKey> {
+    let bytes = from_multibase(value)?;
+    match bytes.strip_prefix(&ED25519_PUB[..]) {
+      Some(key) if key.len() == 32 => {
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(key);
+        VerifyingKey::from_bytes(&bytes)
+      }
+      _ => Err(Error::custom("not a multicodec Ed25519 public key")),
+    }
+  }
+
+  /// Returns the 32-byte public key.
+  pub fn as_bytes(&self) -> &[u8; 32] {
+    self.key.as_bytes()
+  }
+
+  /// Encodes the key as a `publicKeyMultibase` value, e.g. `z6Mk...`.
+  pub fn to_multibase(&self) -> String {
+    let mut bytes = ED25519_PUB.to_vec();
+    bytes.extend_from_slice(self.key.as_bytes());
+    multibase(&bytes)
+  }
+
+  /// Returns `true` if `signature` is a valid signature of `message`.
+  /// Signatures which aren't in their canonical encoding are rejected.
+  pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+    match Signature::from_slice(signature) {
+      Ok(signature) => self.key.verify_strict(message, &signature).is_ok(),
+      Err(_) => false,
+    }
+  }
+
+  /// Verifies a proof made by `SigningKey::sign_graph`. Fails if the proof
+  /// isn't of this module's cryptosuite, its `proofValue` is malformed or
+  /// `graph` has blank nodes.
+  pub fn verify_graph(
+    &self,
+    graph: &KnowledgeGraph,
+    proof: &Proof,
+  ) -> Result<bool> {
+    self.verify_triples(graph.triples(), proof)
+  }
+
+  /// Verifies a proof made by `SigningKey::sign_entity`.
+  pub fn verify_entity(&self, entity: &Entity, proof: &Proof) -> Result<bool> {
+    self.verify_triples(entity.triples(), proof)
+  }
+
+  fn verify_triples<'t>(
+    &self,
+    triples: impl IntoIterator<Item = &'t Triple>,
+    proof: &Proof,
+  ) -> Result<bool> {
+    if proof.cryptosuite != CRYPTOSUITE {
+      return Err(Error::custom(format_args!(
+        "unsupported cryptosuite `{}`",
+        proof.cryptosuite
+      )));
+    }
+    let signature = from_multibase(&proof.proof_value)?;
+    Ok(self.verify(&hash_data(proof, triples)?, &signature))
+  }
+}
+
+impl vc::Verifier for VerifyingKey {
+  /// Verifies a `proofValue` made by a `SigningKey`, whatever the
+  /// verification method.
+  fn verify(&self, _: &str, data: &[u8], proof_value: &str) -> Result<bool> {
+    Ok(VerifyingKey::verify(
+      self,
+      data,
+      &from_multibase(proof_value)?,
+    ))
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Proof.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Proof` is a Data Integrity proof of a graph or an entity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+  /// Cryptosuite, `CRYPTOSUITE` for proofs of this module.
+  pub cryptosuite: String,
+  /// When the proof was made, as an XML Schema dateTime.
+  pub created: String,
+  /// IRI of the public key, e.g. `"did:example:issuer#key-1"`.
+  pub verification_method: String,
+  /// Why the proof was made, `"assertionMethod"` by default.
+  pub proof_purpose: String,
+  /// Multibase encoded signature.
+  pub proof_value: String,
+}
+
+impl Proof {
+  /// Returns the proof as a JSON-LD object.
+  pub fn to_dtype(&self) -> DType {
+    let mut proof = self.options();
+    proof.insert("proofValue".to_string(), self.proof_value.clone().into());
+    DType::Object(proof)
+  }
+
+  /// Reads a proof from a JSON-LD object, failing if it isn't a
+  /// `DataIntegrityProof` or lacks a property.
+  pub fn from_dtype(value: &DType) -> Result<Proof> {
+    if value["type"] != PROOF_TYPE {
+      return Err(Error::custom("proof must be a `DataIntegrityProof`"));
+    }
+    let get = |key: &str| match value[key].as_str() {
+      Some(value) => Ok(value.to_string()),
+      None => Err(Error::custom(format_args!("proof must have a `{}`", key))),
+    };
+    Ok(Proof {
+      cryptosuite: get("cryptosuite")?,
+      created: get("created")?,
+      verification_method: get("verificationMethod")?,
+      proof_purpose: get("proofPurpose")?,
+      proof_value: get("proofValue")?,
+    })
+  }
+
+  /// Returns the proof without its `proofValue`.
+  fn options(&self) -> Map<String, DType> {
+    let mut proof = Map::new();
+    proof.insert("type".to_string(), PROOF_TYPE.into());
+    proof.insert("cryptosuite".to_string(), self.cryptosuite.clone().into());
+    proof.insert("created".to_string(), self.created.clone().into());
+    proof.insert(
+      "verificationMethod".to_string(),
+      self.verification_method.clone().into(),
+    );
+    proof.insert(
+      "proofPurpose".to_string(),
+      self.proof_purpose.clone().into(),
+    );
+    proof
+  }
+
+  /// Returns the RDFC-1.0 canonical statements of the proof options, as
+  /// read with the Data Integrity context: a single blank node, labelled
+  /// `_:c14n0`.
+  fn canonical_options(&self) -> Vec<String> {
+    let security = |term: &str| format!("<{}{}>", SECURITY, escape_iri(term));
+    let option = |predicate: String, object: String| {
+      format!("_:c14n0 {} {} .", predicate, object)
+    };
+    vec![
+      option(
+        "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>".to_string(),
+        security(PROOF_TYPE),
+      ),
+      option(
+        security("cryptosuite"),
+        format!(
+          "\"{}\"^^{}",
+          escape_literal(&self.cryptosuite),
+          security("cryptosuiteString")
+        ),
+      ),
+      option(
+        "<http://purl.org/dc/terms/created>".to_string(),
+        format!(
+          "\"{}\"^^<http://www.w3.org/2001/XMLSchema#dateTime>",
+          escape_literal(&self.created)
+        ),
+      ),
+      option(
+        security("verificationMethod"),
+        format!("<{}>", escape_iri(&self.verification_method)),
+      ),
+      option(security("proofPurpose"), security(&self.proof_purpose)),
+    ]
+  }
+}
+
+/// Returns the bytes signed for `triples` under `proof`: the SHA-256 of the
+/// canonical proof options followed by the SHA-256 of the canonical
+/// statements. Fails if a statement has a blank node.
+fn hash_data<'t>(
+  proof: &Proof,
+  triples: impl IntoIterator<Item = &'t Triple>,
+) -> Result<Vec<u8>> {
+  let statements = formats::canonical_statements_of(triples);
+  let blank = |term: &str| term.starts_with("_:");
+  if statements
+    .iter()
+    .any(|s| blank(&s.subject) || blank(&s.object))
+  {
+    return Err(Error::custom(
+      "can't sign blank nodes, skolemize them before signing",
+    ));
+  }
+
+  let mut data = canonical_hash(proof.canonical_options());
+  data.extend(canonical_hash(
+    statements
+      .into_iter()
+      .map(|statement| statement.line)
+      .collect(),
+  ));
+  Ok(data)
+}
+
+/// Returns the SHA-256 of the canonical N-Quads document of `lines`.
+fn canonical_hash(mut lines: Vec<String>) -> Vec<u8> {
+  lines.sort_unstable();
+  lines.dedup();
+  let mut hasher = Sha256::new();
+  for line in lines {
+    hasher.update(line.as_bytes());
+    hasher.update(b"\n");
+  }
+  hasher.finalize().to_vec()
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Multibase.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+const BASE58: &[u8; 58] =
+  b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encodes `bytes` as base58btc multibase (`z` followed by base58).
+fn multibase(bytes: &[u8]) -> String {
+  let zeros = bytes.iter().take_while(|&&b| b == 0).count();
+  // Base 58 digits, least significant first.
+  let mut digits: Vec<u8> = Vec::new();
+  for &byte in &bytes[zeros..] {
+    let mut carry = byte as u32;
+    for digit in digits.iter_mut() {
+      carry += (*digit as u32) << 8;
+      *digit = (carry % 58) as u8;
+      carry /= 58;
+    }
+    while carry > 0 {
+      digits.push((carry % 58) as u8);
+      carry /= 58;
+    }
+  }
+
+  let mut out = String::with_capacity(1 + zeros + digits.len());
+  out.push('z');
+  out.push_str(&"1".repeat(zeros));
+  out.extend(digits.iter().rev().map(|&d| BASE58[d as usize] as char));
+  out
+}
+
+/// Decodes a base58btc multibase value.
+fn from_multibase(value: &str) -> Result<Vec<u8>> {
+  let encoded = value
+    .strip_prefix('z')
+    .ok_or_else(|| Error::custom("only base58btc multibase is supported"))?;
+  let zeros = encoded.bytes().take_while(|&c| c == b'1').count();
+  // Bytes, least significant first.
+  let mut bytes: Vec<u8> = Vec::new();
+  for c in encoded[zeros..].bytes() {
+    let mut carry = BASE58
+      .iter()
+      .position(|&d| d == c)
+      .ok_or_else(|| Error::custom("invalid base58 character"))?
+      as u32;
+    for byte in bytes.iter_mut() {
+      carry += (*byte as u32) * 58;
+      *byte = carry as u8;
+      carry >>= 8;
+    }
+    while carry > 0 {
+      bytes.push(carry as u8);
+      carry >>= 8;
+    }
+  }
+
+  let mut out = vec![0; zeros];
+  out.extend(bytes.iter().rev());
+  Ok(out)
+}
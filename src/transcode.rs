@@ -0,0 +1,303 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Direct transcoding between serde formats.
+//!
+//! Every value read by a `Deserializer` is written straight to a
+//! `Serializer`, so converting a huge file from one format to another needs
+//! no more memory than the nesting of its values, rather than a `DType` tree
+//! of the whole input.
+
+use std::{cell::RefCell, fmt};
+
+use serde::{
+  de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor},
+  ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer},
+};
+
+/// Transcodes the value of `deserializer` into `serializer`.
+///
+/// The input format must be self-describing (e.g. JSON or CBOR), since the
+/// value is read with `deserialize_any`. With the `arbitrary_precision`
+/// feature, `json::Deserializer` hands numbers over as single entry maps
+/// to keep their digits, so they're transcoded as
+/// `{"$sage::dtype::Number": "<digits>"}` objects.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::json;
+///
+/// let input = r#"{ "name": "Ada", "tags": ["math", true, null] }"#;
+/// let mut deserializer = json::Deserializer::from_str(input);
+///
+/// let mut output = Vec::new();
+/// let mut serializer = json::Serializer::new(&mut output);
+/// sage::transcode(&mut deserializer, &mut serializer).unwrap();
+/// deserializer.end().unwrap();
+///
+/// assert_eq!(output, br#"{"name":"Ada","tags":["math",true,null]}"#);
+/// ```
+///
+/// # Errors
+///
+/// Fails with the serializer's error type if either side fails; errors of
+/// the deserializer are converted with `serde::ser::Error::custom`.
+pub fn transcode<'de, D, S>(
+  deserializer: D,
+  serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+  D: Deserializer<'de>,
+  S: Serializer,
+{
+  Transcoder::new(deserializer).serialize(serializer)
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `Transcoder`.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+/// A `Serialize` value which transcodes a `Deserializer`, for embedding a
+/// transcoded value in a larger serialized structure.
+///
+/// # Panics
+///
+/// A `Transcoder` can only be serialized once, serializing it again panics.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::{json, Transcoder};
+/// use serde::ser::{SerializeMap, Serializer};
+///
+/// let mut deserializer = json::Deserializer::from_str(r#"["a", "b"]"#);
+/// let mut output = Vec::new();
+/// let mut serializer = json::Serializer::new(&mut output);
+///
+/// let mut map = serializer.serialize_map(Some(1)).unwrap();
+/// map
+///   .serialize_entry("data", &Transcoder::new(&mut deserializer))
+///   .unwrap();
+/// map.end().unwrap();
+///
+/// assert_eq!(output, br#"{"data":["a","b"]}"#);
+/// ```
+pub struct Transcoder<D>(RefCell<Option<D>>);
+
+impl<'de, D> Transcoder<D>
+where
+  D: Deserializer<'de>,
+{
+  /// Wraps `deserializer` for serialization.
+  pub fn new(deserializer: D) -> Transcoder<D> {
+    Transcoder(RefCell::new(Some(deserializer)))
+  }
+}
+
+impl<'de, D> Serialize for Transcoder<D>
+where
+  D: Deserializer<'de>,
+{
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    let deserializer = self
+      .0
+      .borrow_mut()
+      .take()
+      .expect("Transcoder can only be serialized once");
+    deserializer
+      .deserialize_any(Transcode(serializer))
+      .map_err(ser::Error::custom)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | `Transcode` - visitor writing each value to the serializer.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+*/
+
+struct Transcode<S>(S);
+
+macro_rules! transcode_primitive {
+  ($($visit:ident => $serialize:ident($ty:ty),)*) => {
+    $(
+      fn $visit<E>(self, v: $ty) -> Result<S::Ok, E>
+      where
+        E: de::Error,
+      {
+        self.0.$serialize(v).map_err(E::custom)
+      }
+    )*
+  };
+}
+
+impl<'de, S> Visitor<'de> for Transcode<S>
+where
+  S: Serializer,
+{
+  type Value = S::Ok;
+
+  fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+    formatter.write_str("any value")
+  }
+
+  transcode_primitive! {
+    visit_bool => serialize_bool(bool),
+    visit_i8 => serialize_i8(i8),
+    visit_i16 => serialize_i16(i16),
+    visit_i32 => serialize_i32(i32),
+    visit_i64 => serialize_i64(i64),
+    visit_i128 => serialize_i128(i128),
+    visit_u8 => serialize_u8(u8),
+    visit_u16 => serialize_u16(u16),
+    visit_u32 => serialize_u32(u32),
+    visit_u64 => serialize_u64(u64),
+    visit_u128 => serialize_u128(u128),
+    visit_f32 => serialize_f32(f32),
+    visit_f64 => serialize_f64(f64),
+    visit_char => serialize_char(char),
+    visit_str => serialize_str(&str),
+    visit_bytes => serialize_bytes(&[u8]),
+  }
+
+  fn visit_none<E>(self) -> Result<S::Ok, E>
+  where
+    E: de::Error,
+  {
+    self.0.serialize_none().map_err(E::custom)
+  }
+
+  fn visit_some<D>(self, deserializer: D) -> Result<S::Ok, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    self
+      .0
+      .serialize_some(&Transcoder::new(deserializer))
+      .map_err(de::Error::custom)
+  }
+
+  fn visit_unit<E>(self) -> Result<S::Ok, E>
+  where
+    E: de::Error,
+  {
+    self.0.serialize_unit().map_err(E::custom)
+  }
+
+  fn visit_newtype_struct<D>(self, deserializer: D) -> Result<S::Ok, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    self
+      .0
+      .serialize_newtype_struct("<transcoded>", &Transcoder::new(deserializer))
+      .map_err(de::Error::custom)
+  }
+
+  fn visit_seq<A>(self, mut seq: A) -> Result<S::Ok, A::Error>
+  where
+    A: SeqAccess<'de>,
+  {
+    let mut out = tri!(self
+      .0
+      .serialize_seq(seq.size_hint())
+      .map_err(de::Error::custom));
+    while tri!(seq.next_element_seed(Element(&mut out))).is_some() {}
+    out.end().map_err(de::Error::custom)
+  }
+
+  fn visit_map<A>(self, mut map: A) -> Result<S::Ok, A::Error>
+  where
+    A: MapAccess<'de>,
+  {
+    let mut out = tri!(self
+      .0
+      .serialize_map(map.size_hint())
+      .map_err(de::Error::custom));
+    while tri!(map.next_key_seed(Key(&mut out))).is_some() {
+      tri!(map.next_value_seed(Value(&mut out)));
+    }
+    out.end().map_err(de::Error::custom)
+  }
+}
+
+/// Transcodes an element of a sequence.
+struct Element<'a, S>(&'a mut S);
+
+impl<'de, 'a, S> DeserializeSeed<'de> for Element<'a, S>
+where
+  S: SerializeSeq,
+{
+  type Value = ();
+
+  fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    self
+      .0
+      .serialize_element(&Transcoder::new(deserializer))
+      .map_err(de::Error::custom)
+  }
+}
+
+/// Transcodes a key of a map.
+struct Key<'a, S>(&'a mut S);
+
+impl<'de, 'a, S> DeserializeSeed<'de> for Key<'a, S>
+where
+  S: SerializeMap,
+{
+  type Value = ();
+
+  fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    self
+      .0
+      .serialize_key(&Transcoder::new(deserializer))
+      .map_err(de::Error::custom)
+  }
+}
+
+/// Transcodes a value of a map.
+struct Value<'a, S>(&'a mut S);
+
+impl<'de, 'a, S> DeserializeSeed<'de> for Value<'a, S>
+where
+  S: SerializeMap,
+{
+  type Value = ();
+
+  fn deserialize<D>(self, deserializer: D) -> Result<(), D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    self
+      .0
+      .serialize_value(&Transcoder::new(deserializer))
+      .map_err(de::Error::custom)
+  }
+}
@@ -0,0 +1,830 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::transform` reshapes `DType` values with small [jq]-like
+//! pipelines, e.g. to normalize payloads of different sources into one
+//! shape before they're loaded into a graph.
+//!
+//! A `Transform` is compiled once and can be applied to any number of
+//! values:
+//!
+//! ```rust
+//! use sage::json;
+//! use sage::transform::Transform;
+//!
+//! let normalize = Transform::compile(
+//!   ".results | filter(.active) | map({ id: .uid, name: .name // .title })",
+//! )
+//! .unwrap();
+//!
+//! let payload = json!({
+//!   "results": [
+//!     { "uid": 1, "name": "Ada", "active": true },
+//!     { "uid": 2, "title": "Dr. Who", "active": true },
+//!     { "uid": 3, "name": "Charles", "active": false }
+//!   ]
+//! });
+//! assert_eq!(
+//!   normalize.apply(&payload).unwrap(),
+//!   json!([{ "id": 1, "name": "Ada" }, { "id": 2, "name": "Dr. Who" }])
+//! );
+//! ```
+//!
+//! # Syntax
+//!
+//! | Expression              | Result                                       |
+//! |-------------------------|----------------------------------------------|
+//! | `.`                     | the input                                    |
+//! | `.name`, `."a b"`       | a member, `null` if missing                  |
+//! | `.[0]`, `.[-1]`         | an element, negative from the end            |
+//! | `a \| b`                | `b` applied to the result of `a`             |
+//! | `{ id: .uid, name }`    | an object (`name` is short for `name: .name`) |
+//! | `[.a, .b]`              | an array                                     |
+//! | `a // b`                | `a` unless it's `null` or `false`, else `b`  |
+//! | `==` `!=` `<` `<=` `>` `>=` | comparisons                              |
+//! | `and`, `or`, `not`      | boolean logic                                |
+//! | `+`, `-`                | arithmetic, `+` also joins strings, arrays & objects |
+//!
+//! Members of missing values or of non-objects are `null` rather than
+//! errors, so heterogeneous payloads don't abort a pipeline.
+//!
+//! Functions: `map(f)`, `filter(f)` (keep elements where `f` holds),
+//! `select(f)` (the input if `f` holds, else `null`), `pluck(f)` (`map(f)`
+//! without `null`s), `flatten`, `flatten(depth)`, `keys`, `values`,
+//! `length`, `first`, `last`, `reverse`, `compact` (drop `null`s),
+//! `unique`, `has(key)`, `tostring`, `tonumber`, `ascii_downcase` &
+//! `ascii_upcase`.
+//!
+//! [jq]: https://jqlang.github.io/jq/manual/
+
+use std::{fmt, mem};
+
+use serde::de::Error as _;
+
+use crate::{
+  datastore::json,
+  dtype::{DType, Map},
+  error::{Error, ErrorCode},
+  Result,
+};
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Transform
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Transform` is a compiled transformation pipeline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transform {
+  source: String,
+  expr: Expr,
+}
+
+impl Transform {
+  /// Compiles `source`. Fails with an `InvalidTransform` error pointing at
+  /// the offending column for malformed expressions or unknown functions.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::transform::Transform;
+  ///
+  /// assert!(Transform::compile(".a | map(.b)").is_ok());
+  /// assert_eq!(Transform::compile(".a | nope").unwrap_err().column(), 6);
+  /// ```
+  pub fn compile(source: &str) -> Result<Transform> {
+    let mut parser = Parser { s: source, pos: 0 };
+    let expr = parser.pipe()?;
+    parser.skip_whitespace();
+    if parser.pos < source.len() {
+      return Err(parser.error());
+    }
+    Ok(Transform {
+      source: source.to_string(),
+      expr,
+    })
+  }
+
+  /// Applies the transformation to `input`. Fails if a function is given a
+  /// value of the wrong type, e.g. `map` a string.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::json;
+  /// use sage::transform::Transform;
+  ///
+  /// let tags = Transform::compile(
+  ///   ".posts | pluck(.tags) | flatten | map(ascii_downcase) | unique",
+  /// )
+  /// .unwrap();
+  ///
+  /// let input = json!({
+  ///   "posts": [{ "tags": ["Rust", "Graphs"] }, { "tags": ["rust"] }, {}]
+  /// });
+  /// assert_eq!(tags.apply(&input).unwrap(), json!(["rust", "graphs"]));
+  /// assert!(tags.apply(&json!({ "posts": "oops" })).is_err());
+  /// ```
+  pub fn apply(&self, input: &DType) -> Result<DType> {
+    self.expr.eval(input)
+  }
+
+  /// Returns the source the transformation was compiled from.
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+}
+
+impl fmt::Display for Transform {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&self.source)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Evaluation
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+  Identity,
+  Literal(DType),
+  Field(Box<Expr>, String),
+  Index(Box<Expr>, Box<Expr>),
+  Pipe(Box<Expr>, Box<Expr>),
+  Alternative(Box<Expr>, Box<Expr>),
+  And(Box<Expr>, Box<Expr>),
+  Or(Box<Expr>, Box<Expr>),
+  Binary(Box<Expr>, Op, Box<Expr>),
+  Object(Vec<(String, Expr)>),
+  Array(Vec<Expr>),
+  Call(Function, Vec<Expr>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+  Eq,
+  Ne,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+  Add,
+  Sub,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+  Map,
+  Filter,
+  Select,
+  Pluck,
+  Flatten,
+  Keys,
+  Values,
+  Length,
+  First,
+  Last,
+  Reverse,
+  Compact,
+  Unique,
+  Has,
+  Not,
+  ToString,
+  ToNumber,
+  Downcase,
+  Upcase,
+}
+
+impl Function {
+  /// Returns the function called `name` & the number of arguments it
+  /// accepts.
+  fn lookup(name: &str) -> Option<(Function, &'static [usize])> {
+    let function = match name {
+      "map" => (Function::Map, &[1][..]),
+      "filter" => (Function::Filter, &[1][..]),
+      "select" => (Function::Select, &[1][..]),
+      "pluck" => (Function::Pluck, &[1][..]),
+      "flatten" => (Function::Flatten, &[0, 1][..]),
+      "keys" => (Function::Keys, &[0][..]),
+      "values" => (Function::Values, &[0][..]),
+      "length" => (Function::Length, &[0][..]),
+      "first" => (Function::First, &[0][..]),
+      "last" => (Function::Last, &[0][..]),
+      "reverse" => (Function::Reverse, &[0][..]),
+      "compact" => (Function::Compact, &[0][..]),
+      "unique" => (Function::Unique, &[0][..]),
+      "has" => (Function::Has, &[1][..]),
+      "not" => (Function::Not, &[0][..]),
+      "tostring" => (Function::ToString, &[0][..]),
+      "tonumber" => (Function::ToNumber, &[0][..]),
+      "ascii_downcase" => (Function::Downcase, &[0][..]),
+      "ascii_upcase" => (Function::Upcase, &[0][..]),
+      _ => return None,
+    };
+    Some(function)
+  }
+}
+
+impl Expr {
+  fn eval(&self, input: &DType) -> Result<DType> {
+    Ok(match self {
+      Expr::Identity => input.clone(),
+      Expr::Literal(value) => value.clone(),
+      Expr::Field(base, name) => match base.eval(input)? {
+        DType::Object(ref mut map) => map.remove(name).unwrap_or(DType::Null),
+        _ => DType::Null,
+      },
+      Expr::Index(base, index) => {
+        let base = base.eval(input)?;
+        match (base, index.eval(input)?) {
+          (DType::Object(ref mut map), DType::String(ref key)) => {
+            map.remove(key).unwrap_or(DType::Null)
+          }
+          (DType::Array(ref mut list), DType::Number(ref n)) => {
+            let len = list.len() as i64;
+            let i = n.as_i64().unwrap_or(len);
+            let i = if i < 0 { len + i } else { i };
+            match usize::try_from(i) {
+              Ok(i) => list.get_mut(i).map(mem::take).unwrap_or(DType::Null),
+              Err(_) => DType::Null,
+            }
+          }
+          _ => DType::Null,
+        }
+      }
+      Expr::Pipe(left, right) => right.eval(&left.eval(input)?)?,
+      Expr::Alternative(left, right) => match left.eval(input)? {
+        value if truthy(&value) => value,
+        _ => right.eval(input)?,
+      },
+      Expr::And(left, right) => DType::Boolean(
+        truthy(&left.eval(input)?) && truthy(&right.eval(input)?),
+      ),
+      Expr::Or(left, right) => DType::Boolean(
+        truthy(&left.eval(input)?) || truthy(&right.eval(input)?),
+      ),
+      Expr::Binary(left, op, right) => {
+        binary(left.eval(input)?, *op, right.eval(input)?)?
+      }
+      Expr::Object(entries) => {
+        let mut map = Map::new();
+        for (key, value) in entries {
+          map.insert(key.clone(), value.eval(input)?);
+        }
+        DType::Object(map)
+      }
+      Expr::Array(items) => DType::from(
+        items
+          .iter()
+          .map(|item| item.eval(input))
+          .collect::<Result<Vec<_>>>()?,
+      ),
+      Expr::Call(function, args) => call(*function, args, input)?,
+    })
+  }
+}
+
+/// `null` & `false` are false, every other value is true.
+fn truthy(value: &DType) -> bool {
+  !matches!(value, DType::Null | DType::Boolean(false))
+}
+
+fn kind(value: &DType) -> &'static str {
+  match value {
+    DType::Null => "null",
+    DType::Boolean(_) => "boolean",
+    DType::Number(_) => "number",
+    DType::String(_) => "string",
+    DType::Bytes(_) => "bytes",
+    DType::DateTime(_) => "datetime",
+    DType::Array(_) => "array",
+    DType::Object(_) => "object",
+  }
+}
+
+fn type_error(function: &str, value: &DType) -> Error {
+  Error::custom(format!("{} can't be applied to {}", function, kind(value)))
+}
+
+fn binary(mut left: DType, op: Op, mut right: DType) -> Result<DType> {
+  let number = |n: &crate::dtype::Number| n.as_f64().unwrap_or(f64::NAN);
+  Ok(match op {
+    Op::Eq => DType::Boolean(equal(&left, &right)),
+    Op::Ne => DType::Boolean(!equal(&left, &right)),
+    Op::Lt => DType::Boolean(less(&left, &right)),
+    Op::Le => DType::Boolean(less(&left, &right) || equal(&left, &right)),
+    Op::Gt => DType::Boolean(less(&right, &left)),
+    Op::Ge => DType::Boolean(less(&right, &left) || equal(&left, &right)),
+    Op::Add => match (&mut left, &mut right) {
+      (DType::Null, value) | (value, DType::Null) => mem::take(value),
+      (DType::Number(a), DType::Number(b)) => match (a.as_i64(), b.as_i64()) {
+        (Some(a), Some(b)) if a.checked_add(b).is_some() => (a + b).into(),
+        _ => (number(a) + number(b)).into(),
+      },
+      (DType::String(a), DType::String(b)) => {
+        DType::String(mem::take(a) + b.as_str())
+      }
+      (DType::Array(a), DType::Array(b)) => {
+        a.extend(mem::take(&mut **b));
+        DType::Array(mem::take(a))
+      }
+      (DType::Object(a), DType::Object(b)) => {
+        a.extend(mem::take(b));
+        DType::Object(mem::take(a))
+      }
+      (left, right) => {
+        return Err(Error::custom(format!(
+          "{} and {} can't be added",
+          kind(left),
+          kind(right)
+        )))
+      }
+    },
+    Op::Sub => match (&left, &right) {
+      (DType::Number(a), DType::Number(b)) => match (a.as_i64(), b.as_i64()) {
+        (Some(a), Some(b)) if a.checked_sub(b).is_some() => (a - b).into(),
+        _ => (number(a) - number(b)).into(),
+      },
+      (left, right) => {
+        return Err(Error::custom(format!(
+          "{} can't be subtracted from {}",
+          kind(right),
+          kind(left)
+        )))
+      }
+    },
+  })
+}
+
+fn equal(a: &DType, b: &DType) -> bool {
+  match (a, b) {
+    (DType::Number(x), DType::Number(y)) => x.as_f64() == y.as_f64(),
+    (a, b) => a == b,
+  }
+}
+
+fn less(a: &DType, b: &DType) -> bool {
+  match (a, b) {
+    (DType::Number(x), DType::Number(y)) => x.as_f64() < y.as_f64(),
+    (DType::String(x), DType::String(y)) => x < y,
+    _ => false,
+  }
+}
+
+fn call(function: Function, args: &[Expr], input: &DType) -> Result<DType> {
+  let name = format!("{:?}", function).to_lowercase();
+  let list = || match input {
+    DType::Array(list) => Ok(list.as_slice()),
+    value => Err(type_error(&name, value)),
+  };
+
+  Ok(match function {
+    Function::Map => DType::from(
+      list()?
+        .iter()
+        .map(|item| args[0].eval(item))
+        .collect::<Result<Vec<_>>>()?,
+    ),
+    Function::Pluck => {
+      let mut values = Vec::new();
+      for item in list()? {
+        let value = args[0].eval(item)?;
+        if !value.is_null() {
+          values.push(value);
+        }
+      }
+      DType::from(values)
+    }
+    Function::Filter => {
+      let mut values = Vec::new();
+      for item in list()? {
+        if truthy(&args[0].eval(item)?) {
+          values.push(item.clone());
+        }
+      }
+      DType::from(values)
+    }
+    Function::Select => match truthy(&args[0].eval(input)?) {
+      true => input.clone(),
+      false => DType::Null,
+    },
+    Function::Flatten => {
+      let depth = match args.first() {
+        Some(depth) => depth
+          .eval(input)?
+          .as_u64()
+          .ok_or_else(|| Error::custom("flatten depth must be a number"))?,
+        None => u64::MAX,
+      };
+      let mut values = Vec::new();
+      flatten(list()?, depth, &mut values);
+      DType::from(values)
+    }
+    Function::Keys => match input {
+      DType::Object(map) => map.keys().cloned().map(DType::String).collect(),
+      DType::Array(list) => (0..list.len()).map(DType::from).collect(),
+      value => return Err(type_error(&name, value)),
+    },
+    Function::Values => match input {
+      DType::Object(map) => map.values().cloned().collect(),
+      DType::Array(list) => DType::Array(list.clone()),
+      value => return Err(type_error(&name, value)),
+    },
+    Function::Length => match input {
+      DType::Null => 0.into(),
+      DType::String(s) => s.chars().count().into(),
+      DType::Array(list) => list.len().into(),
+      DType::Object(map) => map.len().into(),
+      DType::Number(n) => n.as_f64().map_or(f64::NAN, f64::abs).into(),
+      value => return Err(type_error(&name, value)),
+    },
+    Function::First => list()?.first().cloned().unwrap_or(DType::Null),
+    Function::Last => list()?.last().cloned().unwrap_or(DType::Null),
+    Function::Reverse => match input {
+      DType::String(s) => DType::String(s.chars().rev().collect()),
+      DType::Null => DType::Null,
+      _ => list()?.iter().rev().cloned().collect(),
+    },
+    Function::Compact => {
+      list()?.iter().filter(|v| !v.is_null()).cloned().collect()
+    }
+    Function::Unique => {
+      let mut values: Vec<DType> = Vec::new();
+      for item in list()? {
+        if !values.iter().any(|v| equal(v, item)) {
+          values.push(item.clone());
+        }
+      }
+      DType::from(values)
+    }
+    Function::Has => {
+      let key = args[0].eval(input)?;
+      DType::Boolean(match (input, &key) {
+        (DType::Object(map), DType::String(key)) => map.contains_key(key),
+        (DType::Array(list), DType::Number(i)) => {
+          i.as_u64().is_some_and(|i| (i as usize) < list.len())
+        }
+        (value, _) => return Err(type_error(&name, value)),
+      })
+    }
+    Function::Not => DType::Boolean(!truthy(input)),
+    Function::ToString => match input {
+      DType::String(s) => DType::String(s.clone()),
+      value => DType::String(json::to_string(value)?),
+    },
+    Function::ToNumber => match input {
+      DType::Number(n) => DType::Number(n.clone()),
+      DType::String(s) => match json::from_str::<DType>(s.trim()) {
+        Ok(number @ DType::Number(_)) => number,
+        _ => {
+          return Err(Error::custom(format!("{:?} isn't a number", s)));
+        }
+      },
+      value => return Err(type_error(&name, value)),
+    },
+    Function::Downcase | Function::Upcase => match input {
+      DType::String(s) if function == Function::Downcase => {
+        DType::String(s.to_ascii_lowercase())
+      }
+      DType::String(s) => DType::String(s.to_ascii_uppercase()),
+      value => return Err(type_error(&name, value)),
+    },
+  })
+}
+
+fn flatten(values: &[DType], depth: u64, out: &mut Vec<DType>) {
+  for value in values {
+    match value {
+      DType::Array(list) if depth > 0 => flatten(list, depth - 1, out),
+      value => out.push(value.clone()),
+    }
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Parser
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+struct Parser<'a> {
+  s: &'a str,
+  pos: usize,
+}
+
+impl<'a> Parser<'a> {
+  fn peek(&self) -> Option<char> {
+    self.s[self.pos..].chars().next()
+  }
+
+  fn skip_whitespace(&mut self) {
+    while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+      self.pos += 1;
+    }
+  }
+
+  fn eat(&mut self, token: &str) -> bool {
+    self.skip_whitespace();
+    if self.s[self.pos..].starts_with(token) {
+      self.pos += token.len();
+      true
+    } else {
+      false
+    }
+  }
+
+  /// Eats the keyword `word` unless it's the start of a longer name.
+  fn eat_word(&mut self, word: &str) -> bool {
+    self.skip_whitespace();
+    let rest = &self.s[self.pos..];
+    let boundary = rest[word.len().min(rest.len())..]
+      .chars()
+      .next()
+      .is_none_or(|c| !is_name(c));
+    if rest.starts_with(word) && boundary {
+      self.pos += word.len();
+      true
+    } else {
+      false
+    }
+  }
+
+  fn expect(&mut self, token: &str) -> Result<()> {
+    if self.eat(token) {
+      Ok(())
+    } else {
+      Err(self.error())
+    }
+  }
+
+  fn error(&self) -> Error {
+    Error::syntax(ErrorCode::InvalidTransform, 1, self.pos + 1)
+  }
+
+  fn pipe(&mut self) -> Result<Expr> {
+    let mut expr = self.alternative()?;
+    while self.eat("|") {
+      expr = Expr::Pipe(Box::new(expr), Box::new(self.alternative()?));
+    }
+    Ok(expr)
+  }
+
+  fn alternative(&mut self) -> Result<Expr> {
+    let mut expr = self.or()?;
+    while self.eat("//") {
+      expr = Expr::Alternative(Box::new(expr), Box::new(self.or()?));
+    }
+    Ok(expr)
+  }
+
+  fn or(&mut self) -> Result<Expr> {
+    let mut expr = self.and()?;
+    while self.eat_word("or") {
+      expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+    }
+    Ok(expr)
+  }
+
+  fn and(&mut self) -> Result<Expr> {
+    let mut expr = self.comparison()?;
+    while self.eat_word("and") {
+      expr = Expr::And(Box::new(expr), Box::new(self.comparison()?));
+    }
+    Ok(expr)
+  }
+
+  fn comparison(&mut self) -> Result<Expr> {
+    let left = self.additive()?;
+    let op = [
+      ("==", Op::Eq),
+      ("!=", Op::Ne),
+      ("<=", Op::Le),
+      (">=", Op::Ge),
+      ("<", Op::Lt),
+      (">", Op::Gt),
+    ]
+    .into_iter()
+    .find(|(token, _)| self.eat(token));
+    match op {
+      Some((_, op)) => {
+        Ok(Expr::Binary(Box::new(left), op, Box::new(self.additive()?)))
+      }
+      None => Ok(left),
+    }
+  }
+
+  fn additive(&mut self) -> Result<Expr> {
+    let mut expr = self.postfix()?;
+    loop {
+      let op = if self.eat("+") {
+        Op::Add
+      } else if self.eat("-") {
+        Op::Sub
+      } else {
+        return Ok(expr);
+      };
+      expr = Expr::Binary(Box::new(expr), op, Box::new(self.postfix()?));
+    }
+  }
+
+  fn postfix(&mut self) -> Result<Expr> {
+    let mut expr = self.primary()?;
+    loop {
+      // `//` is the alternative operator, not a member.
+      if self.s[self.pos..].starts_with('.') {
+        self.pos += 1;
+        expr = self.member(expr)?;
+      } else if self.eat("[") {
+        let index = self.pipe()?;
+        self.expect("]")?;
+        expr = Expr::Index(Box::new(expr), Box::new(index));
+      } else {
+        return Ok(expr);
+      }
+    }
+  }
+
+  /// Parses the `name` or `"name"` following a `.`.
+  fn member(&mut self, base: Expr) -> Result<Expr> {
+    match self.peek() {
+      Some('"') => Ok(Expr::Field(Box::new(base), self.string()?)),
+      Some(c) if is_name(c) => Ok(Expr::Field(Box::new(base), self.name()?)),
+      _ => Err(self.error()),
+    }
+  }
+
+  fn primary(&mut self) -> Result<Expr> {
+    self.skip_whitespace();
+    match self.peek() {
+      Some('.') => {
+        self.pos += 1;
+        match self.peek() {
+          Some('"') => self.member(Expr::Identity),
+          Some(c) if is_name(c) => self.member(Expr::Identity),
+          _ => Ok(Expr::Identity),
+        }
+      }
+      Some('(') => {
+        self.pos += 1;
+        let expr = self.pipe()?;
+        self.expect(")")?;
+        Ok(expr)
+      }
+      Some('[') => {
+        self.pos += 1;
+        let mut items = Vec::new();
+        if !self.eat("]") {
+          items.push(self.pipe()?);
+          while self.eat(",") {
+            items.push(self.pipe()?);
+          }
+          self.expect("]")?;
+        }
+        Ok(Expr::Array(items))
+      }
+      Some('{') => {
+        self.pos += 1;
+        self.object()
+      }
+      Some('"') => Ok(Expr::Literal(DType::String(self.string()?))),
+      Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+      Some(c) if is_name(c) => self.call(),
+      _ => Err(self.error()),
+    }
+  }
+
+  fn object(&mut self) -> Result<Expr> {
+    let mut entries = Vec::new();
+    if self.eat("}") {
+      return Ok(Expr::Object(entries));
+    }
+    loop {
+      self.skip_whitespace();
+      let key = match self.peek() {
+        Some('"') => self.string()?,
+        Some(c) if is_name(c) => self.name()?,
+        _ => return Err(self.error()),
+      };
+      let value = if self.eat(":") {
+        self.alternative()?
+      } else {
+        Expr::Field(Box::new(Expr::Identity), key.clone())
+      };
+      entries.push((key, value));
+      if self.eat("}") {
+        return Ok(Expr::Object(entries));
+      }
+      self.expect(",")?;
+    }
+  }
+
+  fn call(&mut self) -> Result<Expr> {
+    let start = self.pos;
+    let name = self.name()?;
+    match name.as_str() {
+      "true" => return Ok(Expr::Literal(DType::Boolean(true))),
+      "false" => return Ok(Expr::Literal(DType::Boolean(false))),
+      "null" => return Ok(Expr::Literal(DType::Null)),
+      _ => {}
+    }
+    let (function, arities) = match Function::lookup(&name) {
+      Some(function) => function,
+      None => {
+        self.pos = start;
+        return Err(self.error());
+      }
+    };
+
+    let mut args = Vec::new();
+    if self.eat("(") {
+      args.push(self.pipe()?);
+      while self.eat(";") {
+        args.push(self.pipe()?);
+      }
+      self.expect(")")?;
+    }
+    if !arities.contains(&args.len()) {
+      self.pos = start;
+      return Err(self.error());
+    }
+    Ok(Expr::Call(function, args))
+  }
+
+  fn name(&mut self) -> Result<String> {
+    let start = self.pos;
+    while matches!(self.peek(), Some(c) if is_name(c)) {
+      self.pos += self.peek().map_or(1, char::len_utf8);
+    }
+    if start == self.pos {
+      return Err(self.error());
+    }
+    Ok(self.s[start..self.pos].to_string())
+  }
+
+  /// Parses a JSON string literal.
+  fn string(&mut self) -> Result<String> {
+    let start = self.pos;
+    let mut escaped = false;
+    for (i, c) in self.s[start + 1..].char_indices() {
+      match c {
+        '\\' if !escaped => escaped = true,
+        '"' if !escaped => {
+          let end = start + 1 + i + 1;
+          let s =
+            json::from_str(&self.s[start..end]).map_err(|_| self.error())?;
+          self.pos = end;
+          return Ok(s);
+        }
+        _ => escaped = false,
+      }
+    }
+    Err(self.error())
+  }
+
+  fn number(&mut self) -> Result<Expr> {
+    let start = self.pos;
+    if self.peek() == Some('-') {
+      self.pos += 1;
+    }
+    while matches!(
+      self.peek(),
+      Some(c) if c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+')
+    ) {
+      // A `-` only belongs to an exponent.
+      self.pos += 1;
+    }
+    let value = json::from_str::<DType>(&self.s[start..self.pos]);
+    match value {
+      Ok(value @ DType::Number(_)) => Ok(Expr::Literal(value)),
+      _ => {
+        self.pos = start;
+        Err(self.error())
+      }
+    }
+  }
+}
+
+fn is_name(c: char) -> bool {
+  c.is_alphanumeric() || c == '_'
+}
@@ -0,0 +1,514 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::vc` implements builders & validators for the [W3C Verifiable
+//! Credentials Data Model] on top of `DType`.
+//!
+//! Credentials are plain JSON-LD documents, so a `VerifiableCredential` is a
+//! validated `DType::Object` with typed accessors for the commonly used
+//! properties. Cryptography is left to the caller through the [`Signer`] &
+//! [`Verifier`] hooks which produce & check the `proof` block; the Ed25519
+//! keys of `sage::sign` implement both.
+//!
+//! [W3C Verifiable Credentials Data Model]: https://www.w3.org/TR/vc-data-model/
+
+use std::mem;
+
+use chrono::{DateTime as ChronoDateTime, SecondsFormat, Utc};
+use serde::de::Error as _;
+
+use crate::{
+  datastore::json,
+  dtype::{DType, Map},
+  error::Error,
+  Result,
+};
+
+/// Base context of the Verifiable Credentials Data Model v1.1.
+pub const CREDENTIALS_V1: &str = "https://www.w3.org/2018/credentials/v1";
+
+/// Base context of the Verifiable Credentials Data Model v2.0.
+pub const CREDENTIALS_V2: &str = "https://www.w3.org/ns/credentials/v2";
+
+/// Type every credential must declare.
+pub const VERIFIABLE_CREDENTIAL: &str = "VerifiableCredential";
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | Signing hooks.
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `Signer` produces the `proofValue` of a credential proof.
+///
+/// The bytes handed to `Signer::sign` are the compact JSON serialization of
+/// the credential without its `proof` block, with object keys in sorted
+/// order.
+pub trait Signer {
+  /// Proof type, e.g. `"Ed25519Signature2020"`.
+  fn proof_type(&self) -> &str;
+
+  /// IRI of the key used for signing, e.g. `"did:example:123#key-1"`.
+  fn verification_method(&self) -> &str;
+
+  /// Signs `data` and returns the encoded signature.
+  fn sign(&self, data: &[u8]) -> Result<String>;
+}
+
+/// `Verifier` checks the `proofValue` of a credential proof.
+pub trait Verifier {
+  /// Returns `true` if `proof_value` is a valid signature of `data` made
+  /// by `verification_method`.
+  fn verify(
+    &self,
+    verification_method: &str,
+    data: &[u8],
+    proof_value: &str,
+  ) -> Result<bool>;
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | VerifiableCredential
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `VerifiableCredential` is a validated W3C Verifiable Credential.
+///
+/// # Example
+///
+/// ```rust
+/// use sage::json;
+/// use sage::vc::VerifiableCredential;
+///
+/// let vc = VerifiableCredential::from_dtype(json!({
+///   "@context": ["https://www.w3.org/2018/credentials/v1"],
+///   "type": ["VerifiableCredential", "AlumniCredential"],
+///   "issuer": { "id": "did:example:university" },
+///   "issuanceDate": "2021-01-01T00:00:00Z",
+///   "credentialSubject": { "id": "did:example:ada", "alumniOf": "Oxford" }
+/// }))
+/// .unwrap();
+///
+/// assert_eq!(vc.issuer(), Some("did:example:university"));
+/// assert!(vc.has_type("AlumniCredential"));
+/// assert_eq!(vc.subjects()[0]["alumniOf"], "Oxford");
+///
+/// // Credentials without an issuer are rejected.
+/// assert!(VerifiableCredential::from_dtype(json!({
+///   "@context": ["https://www.w3.org/2018/credentials/v1"],
+///   "type": ["VerifiableCredential"],
+///   "credentialSubject": {}
+/// }))
+/// .is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerifiableCredential {
+  doc: Map<String, DType>,
+}
+
+impl VerifiableCredential {
+  /// Validates `value` against the data model and wraps it.
+  pub fn from_dtype(mut value: DType) -> Result<VerifiableCredential> {
+    let doc = match value {
+      DType::Object(ref mut doc) => mem::take(doc),
+      _ => return Err(Error::custom("credential must be a JSON object")),
+    };
+    let vc = VerifiableCredential { doc };
+    vc.validate()?;
+    Ok(vc)
+  }
+
+  /// Parses & validates a credential from JSON text.
+  pub fn parse(s: &str) -> Result<VerifiableCredential> {
+    VerifiableCredential::from_dtype(json::from_str(s)?)
+  }
+
+  fn validate(&self) -> Result<()> {
+    let first_context = match self.doc.get("@context") {
+      Some(DType::Array(contexts)) => contexts.first().and_then(DType::as_str),
+      Some(DType::String(context)) => Some(context.as_str()),
+      _ => None,
+    };
+    if !matches!(first_context, Some(CREDENTIALS_V1 | CREDENTIALS_V2)) {
+      return Err(Error::custom(
+        "first `@context` must be the credentials base context",
+      ));
+    }
+
+    if !self.has_type(VERIFIABLE_CREDENTIAL) {
+      return Err(Error::custom("`type` must include `VerifiableCredential`"));
+    }
+
+    if self.issuer().is_none() {
+      return Err(Error::custom(
+        "`issuer` must be an IRI or an object with an `id`",
+      ));
+    }
+
+    let subjects = self.subjects();
+    if subjects.is_empty() || !subjects.iter().all(|s| s.is_object()) {
+      return Err(Error::custom(
+        "`credentialSubject` must be an object or a list of objects",
+      ));
+    }
+
+    for key in ["issuanceDate", "validFrom", "expirationDate", "validUntil"] {
+      if let Some(date) = self.doc.get(key) {
+        if date.as_str().and_then(parse_datetime).is_none() {
+          return Err(Error::custom(format_args!(
+            "`{}` must be an XML Schema dateTime",
+            key
+          )));
+        }
+      }
+    }
+    if self.issuance_date().is_none() {
+      return Err(Error::custom("`issuanceDate` or `validFrom` is required"));
+    }
+
+    for proof in self.proofs() {
+      if proof.get("type").and_then(DType::as_str).is_none() {
+        return Err(Error::custom("every `proof` must declare its `type`"));
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Returns the credential `id`, if any.
+  pub fn id(&self) -> Option<&str> {
+    self.doc.get("id").and_then(DType::as_str)
+  }
+
+  /// Returns the declared credential types.
+  pub fn types(&self) -> Vec<&str> {
+    match self.doc.get("type") {
+      Some(DType::String(t)) => vec![t.as_str()],
+      Some(DType::Array(types)) => {
+        types.iter().filter_map(DType::as_str).collect()
+      }
+      _ => Vec::new(),
+    }
+  }
+
+  /// Returns `true` if the credential declares `ty` as one of its types.
+  pub fn has_type(&self, ty: &str) -> bool {
+    self.types().contains(&ty)
+  }
+
+  /// Returns the issuer IRI, whether given as a string or an object `id`.
+  pub fn issuer(&self) -> Option<&str> {
+    match self.doc.get("issuer")? {
+      DType::String(issuer) => Some(issuer),
+      DType::Object(issuer) => issuer.get("id").and_then(DType::as_str),
+      _ => None,
+    }
+  }
+
+  /// Returns every credential subject.
+  pub fn subjects(&self) -> Vec<&DType> {
+    match self.doc.get("credentialSubject") {
+      Some(DType::Array(subjects)) => subjects.iter().collect(),
+      Some(subject) => vec![subject],
+      None => Vec::new(),
+    }
+  }
+
+  /// Returns the issuance date (`issuanceDate` in v1, `validFrom` in v2).
+  pub fn issuance_date(&self) -> Option<ChronoDateTime<Utc>> {
+    self.date("issuanceDate").or_else(|| self.date("validFrom"))
+  }
+
+  /// Returns the expiration date (`expirationDate` in v1, `validUntil` in
+  /// v2).
+  pub fn expiration_date(&self) -> Option<ChronoDateTime<Utc>> {
+    self
+      .date("expirationDate")
+      .or_else(|| self.date("validUntil"))
+  }
+
+  /// Returns `true` if the credential has expired at `now`.
+  pub fn is_expired(&self, now: ChronoDateTime<Utc>) -> bool {
+    matches!(self.expiration_date(), Some(exp) if exp < now)
+  }
+
+  /// Returns every proof block attached to the credential.
+  pub fn proofs(&self) -> Vec<&Map<String, DType>> {
+    match self.doc.get("proof") {
+      Some(DType::Array(proofs)) => {
+        proofs.iter().filter_map(DType::as_object).collect()
+      }
+      Some(DType::Object(proof)) => vec![proof],
+      _ => Vec::new(),
+    }
+  }
+
+  /// Signs the credential with `signer`, appending a new proof block.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::vc::{CredentialBuilder, Signer, Verifier};
+  /// use sage::{json, Result};
+  ///
+  /// // Toy "signature" for illustration purposes only.
+  /// struct Reverse;
+  ///
+  /// impl Signer for Reverse {
+  ///   fn proof_type(&self) -> &str { "ReverseSignature" }
+  ///   fn verification_method(&self) -> &str { "did:example:issuer#key-1" }
+  ///   fn sign(&self, data: &[u8]) -> Result<String> {
+  ///     Ok(data.iter().rev().map(|b| format!("{:02x}", b)).collect())
+  ///   }
+  /// }
+  ///
+  /// impl Verifier for Reverse {
+  ///   fn verify(&self, _: &str, data: &[u8], proof: &str) -> Result<bool> {
+  ///     Ok(self.sign(data)? == proof)
+  ///   }
+  /// }
+  ///
+  /// let mut vc = CredentialBuilder::new()
+  ///   .issuer("did:example:issuer")
+  ///   .subject(json!({ "id": "did:example:ada" }))
+  ///   .build()
+  ///   .unwrap();
+  ///
+  /// vc.sign(&Reverse).unwrap();
+  /// assert_eq!(vc.proofs()[0]["type"], "ReverseSignature");
+  /// assert!(vc.verify(&Reverse).unwrap());
+  /// ```
+  pub fn sign<S: Signer + ?Sized>(&mut self, signer: &S) -> Result<()> {
+    let data = self.signing_input()?;
+    let proof_value = signer.sign(&data)?;
+
+    let mut proof = Map::new();
+    proof.insert("type".to_string(), signer.proof_type().into());
+    proof.insert("created".to_string(), format_datetime(Utc::now()).into());
+    proof.insert(
+      "verificationMethod".to_string(),
+      signer.verification_method().into(),
+    );
+    proof.insert("proofPurpose".to_string(), "assertionMethod".into());
+    proof.insert("proofValue".to_string(), proof_value.into());
+
+    let proof = DType::Object(proof);
+    match self.doc.remove("proof") {
+      None => self.doc.insert("proof".to_string(), proof),
+      Some(DType::Array(ref mut proofs)) => {
+        proofs.push(proof);
+        self
+          .doc
+          .insert("proof".to_string(), DType::Array(mem::take(proofs)))
+      }
+      Some(existing) => self
+        .doc
+        .insert("proof".to_string(), DType::from(vec![existing, proof])),
+    };
+    Ok(())
+  }
+
+  /// Verifies every proof block with `verifier`. Returns `false` if the
+  /// credential has no proof or any proof fails to verify.
+  pub fn verify<V: Verifier + ?Sized>(&self, verifier: &V) -> Result<bool> {
+    let proofs = self.proofs();
+    if proofs.is_empty() {
+      return Ok(false);
+    }
+
+    let data = self.signing_input()?;
+    for proof in proofs {
+      let method = proof.get("verificationMethod").and_then(DType::as_str);
+      let value = proof.get("proofValue").and_then(DType::as_str);
+      match (method, value) {
+        (Some(method), Some(value)) => {
+          if !verifier.verify(method, &data, value)? {
+            return Ok(false);
+          }
+        }
+        _ => return Ok(false),
+      }
+    }
+    Ok(true)
+  }
+
+  /// Returns the bytes covered by a proof: the credential without `proof`.
+  fn signing_input(&self) -> Result<Vec<u8>> {
+    let mut unsigned = self.doc.clone();
+    unsigned.remove("proof");
+    json::to_vec(&unsigned)
+  }
+
+  /// Returns the credential as a `DType`.
+  pub fn as_dtype(&self) -> DType {
+    DType::Object(self.doc.clone())
+  }
+
+  /// Consumes the credential, returning the underlying `DType`.
+  pub fn into_dtype(self) -> DType {
+    DType::Object(self.doc)
+  }
+
+  fn date(&self, key: &str) -> Option<ChronoDateTime<Utc>> {
+    self
+      .doc
+      .get(key)
+      .and_then(DType::as_str)
+      .and_then(parse_datetime)
+  }
+}
+
+/*
+ * +----------------------------------------------------------------------+
+ * | +------------------------------------------------------------------+ |
+ * | | CredentialBuilder
+ * | +------------------------------------------------------------------+ |
+ * +----------------------------------------------------------------------+
+ */
+
+/// `CredentialBuilder` assembles a `VerifiableCredential` (data model v1.1).
+///
+/// # Example
+///
+/// ```rust
+/// use sage::json;
+/// use sage::vc::CredentialBuilder;
+///
+/// let vc = CredentialBuilder::new()
+///   .id("https://example.edu/credentials/1872")
+///   .add_type("AlumniCredential")
+///   .issuer("did:example:university")
+///   .subject(json!({ "id": "did:example:ada", "alumniOf": "Oxford" }))
+///   .build()
+///   .unwrap();
+///
+/// assert_eq!(vc.types(), vec!["VerifiableCredential", "AlumniCredential"]);
+/// assert!(vc.issuance_date().is_some());
+/// ```
+#[derive(Debug, Default)]
+pub struct CredentialBuilder {
+  contexts: Vec<DType>,
+  id: Option<String>,
+  types: Vec<String>,
+  issuer: Option<DType>,
+  issuance_date: Option<ChronoDateTime<Utc>>,
+  expiration_date: Option<ChronoDateTime<Utc>>,
+  subjects: Vec<DType>,
+}
+
+impl CredentialBuilder {
+  /// Creates a builder with the v1.1 base context & `VerifiableCredential`
+  /// type already set.
+  pub fn new() -> CredentialBuilder {
+    CredentialBuilder {
+      contexts: vec![CREDENTIALS_V1.into()],
+      types: vec![VERIFIABLE_CREDENTIAL.to_string()],
+      ..Default::default()
+    }
+  }
+
+  /// Adds an extra JSON-LD context (IRI or inline context object).
+  pub fn context<C: Into<DType>>(mut self, context: C) -> Self {
+    self.contexts.push(context.into());
+    self
+  }
+
+  /// Sets the credential `id`.
+  pub fn id(mut self, id: &str) -> Self {
+    self.id = Some(id.to_string());
+    self
+  }
+
+  /// Adds a credential type.
+  pub fn add_type(mut self, ty: &str) -> Self {
+    self.types.push(ty.to_string());
+    self
+  }
+
+  /// Sets the issuer IRI.
+  pub fn issuer(mut self, issuer: &str) -> Self {
+    self.issuer = Some(issuer.into());
+    self
+  }
+
+  /// Sets the issuer as an object (which must contain an `id`).
+  pub fn issuer_object(mut self, issuer: DType) -> Self {
+    self.issuer = Some(issuer);
+    self
+  }
+
+  /// Sets the issuance date. Defaults to the time `build` is called.
+  pub fn issuance_date(mut self, date: ChronoDateTime<Utc>) -> Self {
+    self.issuance_date = Some(date);
+    self
+  }
+
+  /// Sets the expiration date.
+  pub fn expiration_date(mut self, date: ChronoDateTime<Utc>) -> Self {
+    self.expiration_date = Some(date);
+    self
+  }
+
+  /// Adds a credential subject.
+  pub fn subject(mut self, subject: DType) -> Self {
+    self.subjects.push(subject);
+    self
+  }
+
+  /// Builds & validates the credential.
+  pub fn build(self) -> Result<VerifiableCredential> {
+    let mut doc = Map::new();
+    doc.insert("@context".to_string(), DType::from(self.contexts));
+    if let Some(id) = self.id {
+      doc.insert("id".to_string(), id.into());
+    }
+    doc.insert("type".to_string(), self.types.into());
+    if let Some(issuer) = self.issuer {
+      doc.insert("issuer".to_string(), issuer);
+    }
+
+    let issued = self.issuance_date.unwrap_or_else(Utc::now);
+    doc.insert("issuanceDate".to_string(), format_datetime(issued).into());
+    if let Some(expires) = self.expiration_date {
+      doc.insert(
+        "expirationDate".to_string(),
+        format_datetime(expires).into(),
+      );
+    }
+
+    let mut subjects = self.subjects;
+    let subjects = match subjects.len() {
+      1 => subjects.remove(0),
+      _ => DType::from(subjects),
+    };
+    doc.insert("credentialSubject".to_string(), subjects);
+
+    VerifiableCredential::from_dtype(DType::Object(doc))
+  }
+}
+
+fn parse_datetime(s: &str) -> Option<ChronoDateTime<Utc>> {
+  ChronoDateTime::parse_from_rfc3339(s)
+    .ok()
+    .map(|d| d.with_timezone(&Utc))
+}
+
+fn format_datetime(d: ChronoDateTime<Utc>) -> String {
+  d.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -12,9 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::dtype::IRI;
+use crate::{
+  dtype::{DeepSizeOf, IRI},
+  error::{Error, ErrorCode},
+  iri::Iri,
+  Result,
+};
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 /// `URI` expands and contracts a URL given it's context and the property.
 pub struct URI {
@@ -146,6 +151,12 @@ impl Namespace {
   }
 }
 
+impl DeepSizeOf for Namespace {
+  fn heap_size_of(&self) -> usize {
+    self.prefix.heap_size_of() + self.full.heap_size_of()
+  }
+}
+
 impl Default for Namespace {
   /// `Namespace::default` creates a default namespace.
   ///
@@ -456,6 +467,109 @@ impl NamespaceStore {
     }
     ns
   }
+
+  /// `NamespaceStore::bind` binds `prefix` to the `namespace` IRI, so
+  /// `prefix:name` expands to `namespace` followed by `name`. Rebinding a
+  /// prefix replaces its namespace.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::vocab::Namespaces;
+  ///
+  /// let mut ns = Namespaces::new();
+  /// ns.bind("schema", "https://schema.org/")
+  ///   .bind("ex", "https://example.com/");
+  ///
+  /// assert_eq!(ns.expand("schema:Person").unwrap(), "https://schema.org/Person");
+  /// assert_eq!(
+  ///   ns.compact("https://example.com/Ada").as_deref(),
+  ///   Some("ex:Ada")
+  /// );
+  /// assert_eq!(ns.prefixes()["schema"], "https://schema.org/");
+  /// ```
+  pub fn bind(&mut self, prefix: &str, namespace: &str) -> &mut Self {
+    let prefix = format!("{}:", prefix.trim_end_matches(':'));
+    self.prefixes.insert(prefix, namespace.to_string());
+    self
+  }
+
+  /// `NamespaceStore::expand` expands a CURIE such as `schema:Person` into a
+  /// full IRI. Registered terms (e.g. `rdf:type`) expand to their IRI.
+  ///
+  /// Absolute IRIs with an authority (`scheme://...`) are returned as is,
+  /// unbound prefixes fail with an `IllegalNamespace` error.
+  ///
+  /// # Example
+  ///
+  /// ```rust
+  /// use sage::vocab::Namespaces;
+  ///
+  /// let ns = Namespaces::default();
+  /// assert_eq!(
+  ///   ns.expand("rdf:type").unwrap(),
+  ///   "http://www.w3.org/1999/02/22-rdf-syntax-ns#type"
+  /// );
+  /// assert_eq!(
+  ///   ns.expand("https://example.com/Ada").unwrap(),
+  ///   "https://example.com/Ada"
+  /// );
+  /// assert!(ns.expand("foaf:name").is_err());
+  /// ```
+  pub fn expand(&self, curie: &str) -> Result<IRI> {
+    if let Some(full) = self.prefixes.get(curie) {
+      return Ok(full.to_string());
+    }
+    let (prefix, local) = curie
+      .split_once(':')
+      .ok_or_else(|| Error::syntax(ErrorCode::IllegalNamespace, 0, 0))?;
+    match self.prefixes.get(&format!("{}:", prefix)) {
+      Some(namespace) if !local.starts_with("//") => {
+        Ok(format!("{}{}", namespace, local))
+      }
+      _ if local.starts_with("//") && Iri::parse(curie).is_ok() => {
+        Ok(curie.to_string())
+      }
+      _ => Err(Error::syntax(ErrorCode::IllegalNamespace, 0, 0)),
+    }
+  }
+
+  /// `NamespaceStore::compact` writes `iri` as a CURIE using the longest
+  /// bound namespace it starts with. Returns `None` if no namespace matches
+  /// or the remaining local name can't be written in a CURIE.
+  pub fn compact(&self, iri: &str) -> Option<IRI> {
+    self
+      .prefixes()
+      .into_iter()
+      .filter_map(|(prefix, namespace)| {
+        let local = iri.strip_prefix(namespace.as_str())?;
+        is_local_name(local).then_some((namespace.len(), prefix, local))
+      })
+      .max_by_key(|(len, _, _)| *len)
+      .map(|(_, prefix, local)| format!("{}:{}", prefix, local))
+  }
+
+  /// `NamespaceStore::prefixes` returns every bound prefix (without the
+  /// trailing `:`) and its namespace IRI, sorted by prefix.
+  pub fn prefixes(&self) -> BTreeMap<String, IRI> {
+    self
+      .prefixes
+      .iter()
+      .filter_map(|(prefix, namespace)| {
+        let prefix = prefix.strip_suffix(':')?;
+        Some((prefix.to_string(), namespace.to_string()))
+      })
+      .collect()
+  }
+}
+
+/// Returns `true` if `local` can follow the `prefix:` of a CURIE without
+/// escaping.
+fn is_local_name(local: &str) -> bool {
+  local
+    .chars()
+    .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+    && !local.ends_with('.')
 }
 
 impl Default for NamespaceStore {
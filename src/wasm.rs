@@ -0,0 +1,176 @@
+// Copyright 2021 Victor I. Afolabi
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `sage::wasm` is a [`wasm-bindgen`] facade for using Sage from
+//! JavaScript, e.g. in browser based knowledge graph explorers.
+//!
+//! Enable with the `wasm` feature, build a `cdylib` & generate the
+//! JavaScript bindings with:
+//!
+//! ```sh
+//! cargo rustc --lib --release --target wasm32-unknown-unknown \
+//!   --features wasm --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg \
+//!   target/wasm32-unknown-unknown/release/sage.wasm
+//! ```
+//!
+//! Values cross the JavaScript boundary either as opaque `Value` handles or
+//! as JSON text, so no JavaScript object graph has to be walked on the Rust
+//! side.
+//!
+//! [`wasm-bindgen`]: https://rustwasm.github.io/wasm-bindgen/
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+  datastore::json,
+  dtype::{DType, Map},
+  graph::{KnowledgeGraph, Node, Predicate},
+  query::to_term,
+};
+
+/// Parses JSON text into a `Value`.
+#[wasm_bindgen]
+pub fn parse(text: &str) -> Result<Value, JsError> {
+  Ok(Value(json::from_str(text)?))
+}
+
+/// Serializes `value` back to JSON text.
+#[wasm_bindgen]
+pub fn stringify(
+  value: &Value,
+  pretty: Option<bool>,
+) -> Result<String, JsError> {
+  value.stringify(pretty)
+}
+
+/// `Value` is a JavaScript handle to a `DType`.
+#[wasm_bindgen]
+pub struct Value(DType);
+
+#[wasm_bindgen]
+impl Value {
+  /// Parses JSON text into a `Value`.
+  #[wasm_bindgen(constructor)]
+  pub fn new(text: &str) -> Result<Value, JsError> {
+    parse(text)
+  }
+
+  /// Looks up a value by [RFC 6901] JSON pointer, e.g. `"/a/b/0"`.
+  ///
+  /// [RFC 6901]: https://tools.ietf.org/html/rfc6901
+  pub fn pointer(&self, pointer: &str) -> Option<Value> {
+    self.0.pointer(pointer).cloned().map(Value)
+  }
+
+  /// Serializes the value to JSON text.
+  pub fn stringify(&self, pretty: Option<bool>) -> Result<String, JsError> {
+    let text = if pretty.unwrap_or(false) {
+      json::to_string_pretty(&self.0)?
+    } else {
+      json::to_string(&self.0)?
+    };
+    Ok(text)
+  }
+
+  /// Returns the `DType` variant name, e.g. `"Object"`.
+  #[wasm_bindgen(getter, js_name = kind)]
+  pub fn kind(&self) -> String {
+    let kind = match self.0 {
+      DType::Array(_) => "Array",
+      DType::Boolean(_) => "Boolean",
+      DType::Bytes(_) => "Bytes",
+      DType::DateTime(_) => "DateTime",
+      DType::Null => "Null",
+      DType::Number(_) => "Number",
+      DType::Object(_) => "Object",
+      DType::String(_) => "String",
+    };
+    kind.to_string()
+  }
+}
+
+/// `Graph` is a JavaScript handle to a `KnowledgeGraph`.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Graph(KnowledgeGraph);
+
+#[wasm_bindgen]
+impl Graph {
+  /// Creates an empty graph.
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> Graph {
+    Graph(KnowledgeGraph::new())
+  }
+
+  /// Number of triples in the graph.
+  #[wasm_bindgen(getter)]
+  pub fn size(&self) -> usize {
+    self.0.len()
+  }
+
+  /// Links two resources identified by IRI.
+  pub fn insert(&mut self, subject: &str, predicate: &str, object: &str) {
+    self.0.insert(
+      Node::Http(subject.to_string()),
+      Predicate::Literal(predicate.to_string()),
+      Node::Http(object.to_string()),
+    );
+  }
+
+  /// Attaches a literal `value` to the resource `subject`.
+  #[wasm_bindgen(js_name = insertLiteral)]
+  pub fn insert_literal(
+    &mut self,
+    subject: &str,
+    predicate: &str,
+    value: &Value,
+  ) {
+    self.0.insert(
+      Node::Http(subject.to_string()),
+      Predicate::Literal(predicate.to_string()),
+      Node::Literal(value.0.clone()),
+    );
+  }
+
+  /// Returns every triple matching the pattern as a JSON array of
+  /// `{ subject, predicate, object }` objects. Omitted (`undefined`) terms
+  /// match anything; `subject` & `object` are matched as IRIs.
+  pub fn query(
+    &self,
+    subject: Option<String>,
+    predicate: Option<String>,
+    object: Option<String>,
+  ) -> Result<String, JsError> {
+    let subject = subject.map(Node::Http);
+    let object = object.map(Node::Http);
+
+    let rows: Vec<DType> = self
+      .0
+      .matches(subject.as_ref(), predicate.as_deref(), object.as_ref())
+      .map(|triple| {
+        let mut row = Map::new();
+        row.insert("subject".to_string(), to_term(triple.source()));
+        row.insert(
+          "predicate".to_string(),
+          triple.predicate().to_string().into(),
+        );
+        row.insert("object".to_string(), to_term(triple.destination()));
+        DType::Object(row)
+      })
+      .collect();
+
+    Ok(json::to_string(&rows)?)
+  }
+}